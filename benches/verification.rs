@@ -0,0 +1,117 @@
+//! Verification Benchmarks
+//!
+//! Measures the three costs a verification of the ceremony is made of: hashing the files, reading
+//! the points of the accumulators and verifying a transform. The accumulators are generated for a
+//! [`MiniCeremony`] with a few powers of tau, so that the benchmarks run without the files of the
+//! ceremony. Run them with `cargo bench`.
+
+use ark_bn254::Bn254;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ppot_verifier::{
+    accumulator::Accumulator, calculate_hash, curve::Curve, format::Section, hash::Hash64,
+    synthetic::MiniCeremony, HashAlgorithm,
+};
+
+/// Sizes of the chunks hashed by a single call to `calculate_hash`
+const HASH_CHUNK_SIZES: [usize; 4] = [1 << 16, 1 << 20, 1 << 24, 1 << 26];
+
+/// Base-two logarithms of the numbers of powers of the accumulators
+const LOG_POWERS: [u32; 3] = [6, 8, 10];
+
+/// Generates a challenge file over `C` with `powers` powers of tau and a valid response file
+/// contributing to it.
+fn round<C>(powers: usize) -> (Vec<u8>, Vec<u8>)
+where
+    C: Curve,
+{
+    let mut ceremony = MiniCeremony::<C>::generate(powers, 1, &mut rand::thread_rng());
+    (
+        ceremony.challenges.swap_remove(0),
+        ceremony.responses.swap_remove(0),
+    )
+}
+
+/// Benchmarks `calculate_hash` on chunks of increasing sizes with every hash algorithm.
+fn hash_chunks(c: &mut Criterion) {
+    let data = vec![0x5a; *HASH_CHUNK_SIZES.iter().max().unwrap()];
+    for algorithm in [
+        HashAlgorithm::Blake2b,
+        HashAlgorithm::Blake3,
+        HashAlgorithm::Sha256,
+    ] {
+        let mut group = c.benchmark_group(format!("calculate_hash/{}", algorithm.name()));
+        for size in HASH_CHUNK_SIZES {
+            group.throughput(Throughput::Bytes(size as u64));
+            group.bench_with_input(
+                BenchmarkId::from_parameter(size),
+                &data[..size],
+                |b, chunk| b.iter(|| calculate_hash(chunk, algorithm)),
+            );
+        }
+        group.finish();
+    }
+}
+
+/// Benchmarks reading every point of the compressed and uncompressed accumulators of increasing
+/// numbers of powers, with their subgroup checks.
+fn read_points(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_points");
+    for log_powers in LOG_POWERS {
+        let powers = 1 << log_powers;
+        let (challenge, response) = round::<Bn254>(powers);
+        for (name, data, accumulator) in [
+            (
+                "uncompressed",
+                &challenge,
+                Accumulator::<Bn254>::challenge as fn(_, _) -> _,
+            ),
+            ("compressed", &response, Accumulator::<Bn254>::response),
+        ] {
+            let accumulator = accumulator(data, powers).unwrap();
+            group.throughput(Throughput::Bytes(data.len() as u64));
+            group.bench_with_input(
+                BenchmarkId::new(name, log_powers),
+                &accumulator,
+                |b, accumulator| {
+                    b.iter(|| {
+                        for section in Section::ALL {
+                            for index in 0..section.len(powers) {
+                                if section.is_g2() {
+                                    let _ = black_box(accumulator.g2(section, index).unwrap());
+                                } else {
+                                    let _ = black_box(accumulator.g1(section, index).unwrap());
+                                }
+                            }
+                        }
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Benchmarks the verification of a single transform on accumulators of increasing numbers of
+/// powers.
+fn verify_transform(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_transform");
+    group.sample_size(10);
+    for log_powers in LOG_POWERS {
+        let powers = 1 << log_powers;
+        let (challenge, response) = round::<Bn254>(powers);
+        let challenge_hash = Hash64::from_header(&response).unwrap();
+        let challenge = Accumulator::<Bn254>::challenge(&challenge, powers).unwrap();
+        let response = Accumulator::<Bn254>::response(&response, powers).unwrap();
+        group.bench_function(BenchmarkId::from_parameter(log_powers), |b| {
+            b.iter(|| {
+                challenge
+                    .verify_transform(&response, &challenge_hash, powers)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, hash_chunks, read_points, verify_transform);
+criterion_main!(benches);
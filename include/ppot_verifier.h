@@ -0,0 +1,68 @@
+/*
+ * C bindings of ppot-verifier, built into the `cdylib` of the crate with the `ffi` feature:
+ *
+ *     cargo build --release --lib --features ffi
+ *
+ * Every function returns PPOT_OK on success, PPOT_INVALID when the files are read but fail the
+ * check and PPOT_ERROR when they cannot be read or the arguments are invalid, in which case
+ * ppot_last_error() describes the error.
+ */
+
+#ifndef PPOT_VERIFIER_H
+#define PPOT_VERIFIER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The call succeeded and the files passed the check */
+#define PPOT_OK 0
+
+/* The files were read but failed the check */
+#define PPOT_INVALID 1
+
+/* The files could not be read or the arguments are invalid */
+#define PPOT_ERROR (-1)
+
+/* Curve of the BN254 ceremonies, such as the perpetual powers of tau */
+#define PPOT_CURVE_BN254 0
+
+/* Curve of the BLS12-381 ceremonies, such as the Zcash Sapling powers of tau */
+#define PPOT_CURVE_BLS12_381 1
+
+/*
+ * Returns the message of the last error of the calling thread, or NULL if there is none. The
+ * message stays valid until the next call failing on the same thread.
+ */
+const char *ppot_last_error(void);
+
+/* Computes the Blake2b hash of the file at `path` and writes its 64 bytes to `out`. */
+int32_t ppot_calculate_hash(const char *path, uint8_t *out);
+
+/*
+ * Checks the hash chain between the `len` consecutive files at `paths`, where each file asserts
+ * the hash of the one before it in its header. When a link does not match, the index of the file
+ * whose hash differs is written to `mismatch`, if it is not NULL, and PPOT_INVALID is returned.
+ */
+int32_t ppot_verify_hash_chain(const char *const *paths, size_t len, size_t *mismatch);
+
+/*
+ * Verifies the round turning the challenge file at `challenge` into the response file at
+ * `response`, both over `curve` with 2^file_log_powers powers of tau, on their first
+ * 2^log_powers powers. Files which cannot be parsed with that many powers are reported as
+ * errors rather than as invalid.
+ */
+int32_t ppot_verify_round(const char *challenge,
+                          const char *response,
+                          uint32_t curve,
+                          uint32_t file_log_powers,
+                          uint32_t log_powers);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PPOT_VERIFIER_H */
@@ -0,0 +1,595 @@
+//! Transform Verification
+//!
+//! Each round multiplies the accumulator of its challenge file by the secrets of its contribution
+//! and publishes the result, compressed, in its response file, followed by the public key of the
+//! contribution. [`Accumulator::verify_transform`] checks the response against the challenge as the
+//! original ceremony code does: the proofs of knowledge of the key must hold against the hash of
+//! the challenge, the first points of the response must be the first points of the challenge
+//! multiplied by the secrets of the key, and each section of the response must hold successive
+//! powers of the same tau starting from the generators, which a random linear combination of its
+//! points checks with two pairings.
+//!
+//! Only the pairing and the encoding of the points depend on the curve, so the verification is
+//! generic over the [`Curve`] of the ceremony and covers the BLS12-381 ceremonies as well as PPoT.
+//! Points are read on demand and only the first powers of the files need to be checked, as with
+//! the subaccumulators of `verify_ppot`. An accumulator given a [`Profile`] records the time spent
+//! in each phase of the verification.
+
+use crate::{
+    curve::{Curve, CurveKind, G1, G2},
+    format::{read_g1_on, read_g2_on, Encoding, Layout, Section},
+    hash::Hash64,
+    pok::{derive_g2, PublicKey, Secret},
+    profile::{self, Phase, Profile},
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{PrimeField, UniformRand, Zero};
+use core::marker::PhantomData;
+use rayon::prelude::*;
+
+/// Number of points checked by a single multi-scalar multiplication
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Returns `true` if the ratio between the G1 points `a` of `C` is the ratio between the G2 points
+/// `b`.
+#[inline]
+pub fn same_ratio<C>(a: (G1<C>, G1<C>), b: (G2<C>, G2<C>)) -> bool
+where
+    C: Curve,
+{
+    C::Engine::pairing(a.0, b.1) == C::Engine::pairing(a.1, b.0)
+}
+
+/// Combines the first `len - 1` points read by `point` and the `len - 1` points following them with
+/// the same random scalars, so that the two sums have the ratio of two successive points.
+#[inline]
+pub(crate) fn combine_powers<G, F>(len: usize, point: F) -> Result<(G, G)>
+where
+    G: AffineCurve,
+    F: Fn(usize) -> Result<G> + Sync,
+{
+    combine_powers_with(len, point, None)
+}
+
+/// Combines the powers read by `point` like [`combine_powers`], recording the time spent in the
+/// multi-scalar multiplications to `profile`.
+#[inline]
+pub(crate) fn combine_powers_with<G, F>(
+    len: usize,
+    point: F,
+    profile: Option<&Profile>,
+) -> Result<(G, G)>
+where
+    G: AffineCurve,
+    F: Fn(usize) -> Result<G> + Sync,
+{
+    let sums = (0..(len - 1).div_ceil(CHUNK_SIZE))
+        .into_par_iter()
+        .map(|chunk| {
+            let start = chunk * CHUNK_SIZE;
+            let end = (start + CHUNK_SIZE).min(len - 1);
+            let points = (start..=end).map(&point).collect::<Result<Vec<_>>>()?;
+            let mut rng = rand::thread_rng();
+            let scalars = (start..end)
+                .map(|_| G::ScalarField::rand(&mut rng).into_repr())
+                .collect::<Vec<_>>();
+            Ok(profile::time(profile, Phase::MultiExp, || {
+                (
+                    VariableBaseMSM::multi_scalar_mul(&points[..points.len() - 1], &scalars),
+                    VariableBaseMSM::multi_scalar_mul(&points[1..], &scalars),
+                )
+            }))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let (a, b) = sums.into_iter().fold(
+        (G::Projective::zero(), G::Projective::zero()),
+        |(a, b), (c, d)| (a + c, b + d),
+    );
+    Ok((a.into_affine(), b.into_affine()))
+}
+
+/// Accumulator of a Challenge or Response File
+#[derive(Clone, Copy, Debug)]
+pub struct Accumulator<'d, C>
+where
+    C: Curve,
+{
+    /// Bytes of the file
+    data: &'d [u8],
+
+    /// Layout of the file
+    layout: Layout,
+
+    /// Profile recording the time spent reading and checking the points
+    profile: Option<&'d Profile>,
+
+    /// Curve of the ceremony
+    curve: PhantomData<C>,
+}
+
+impl<'d, C> Accumulator<'d, C>
+where
+    C: Curve,
+{
+    /// Wraps the file in `data` with `layout`, checking its size.
+    #[inline]
+    pub fn new(data: &'d [u8], layout: Layout) -> Result<Self> {
+        ensure!(
+            layout.field_size == C::FIELD_SIZE,
+            "The layout is not over {}",
+            C::KIND
+        );
+        ensure!(
+            layout.powers >= 2,
+            "Unable to verify less than two powers of tau"
+        );
+        ensure!(
+            data.len() == layout.file_size(),
+            "The file holds {} bytes instead of {} for 2^{} powers.",
+            data.len(),
+            layout.file_size(),
+            layout.powers.trailing_zeros()
+        );
+        Ok(Self {
+            data,
+            layout,
+            profile: None,
+            curve: PhantomData,
+        })
+    }
+
+    /// Records the time spent reading and checking the points of `self` to `profile`.
+    #[inline]
+    pub fn with_profile(mut self, profile: &'d Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Runs `f`, recording the time it takes in `phase` of the profile of `self`, if any.
+    #[inline]
+    fn time<T, F>(&self, phase: Phase, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        profile::time(self.profile, phase, f)
+    }
+
+    /// Wraps the challenge file in `data` with `powers` powers of tau.
+    #[inline]
+    pub fn challenge(data: &'d [u8], powers: usize) -> Result<Self> {
+        Self::new(data, Layout::challenge_on::<C>(powers))
+    }
+
+    /// Wraps the response file in `data` with `powers` powers of tau.
+    #[inline]
+    pub fn response(data: &'d [u8], powers: usize) -> Result<Self> {
+        Self::new(data, Layout::response_on::<C>(powers))
+    }
+
+    /// Returns the layout of the file.
+    #[inline]
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Returns the public key ending the response file.
+    #[inline]
+    pub fn public_key(&self) -> Result<PublicKey<C>> {
+        ensure!(
+            self.layout.encoding == Encoding::Compressed,
+            "Only response files end with a public key"
+        );
+        PublicKey::read(self.data)
+    }
+
+    /// Returns the bytes of the point at `index` in `section`.
+    #[inline]
+    fn point_bytes(&self, section: Section, index: usize) -> Result<&'d [u8]> {
+        ensure!(
+            index < section.len(self.layout.powers),
+            "No point {} in {:?}",
+            index,
+            section
+        );
+        let bytes = &self.data[self.layout.point_range(section, index)];
+        if let Some(profile) = self.profile {
+            // Touch the ends of the point so that the page faults of the mapped file are counted
+            // as reading it rather than as decoding it.
+            profile.time(Phase::Io, || {
+                core::hint::black_box((bytes.first(), bytes.last()));
+            });
+            profile.read(bytes.len());
+        }
+        Ok(bytes)
+    }
+
+    /// Reads the G1 point at `index` in `section`, checking that it is in the prime-order subgroup
+    /// and is not the point at infinity.
+    #[inline]
+    pub fn g1(&self, section: Section, index: usize) -> Result<G1<C>> {
+        ensure!(!section.is_g2(), "{:?} holds G2 points", section);
+        let bytes = self.point_bytes(section, index)?;
+        let point = self.time(Phase::Deserialization, || {
+            read_g1_on::<C>(bytes, self.layout.encoding)
+        })?;
+        ensure!(
+            !point.is_zero()
+                && self.time(Phase::SubgroupCheck, || {
+                    point.is_in_correct_subgroup_assuming_on_curve()
+                }),
+            "Invalid point {} in {:?}",
+            index,
+            section
+        );
+        Ok(point)
+    }
+
+    /// Reads the G2 point at `index` in `section`, checking that it is in the prime-order subgroup
+    /// and is not the point at infinity.
+    #[inline]
+    pub fn g2(&self, section: Section, index: usize) -> Result<G2<C>> {
+        ensure!(section.is_g2(), "{:?} holds G1 points", section);
+        let bytes = self.point_bytes(section, index)?;
+        let point = self.time(Phase::Deserialization, || {
+            read_g2_on::<C>(bytes, self.layout.encoding)
+        })?;
+        ensure!(
+            !point.is_zero()
+                && self.time(Phase::SubgroupCheck, || {
+                    point.is_in_correct_subgroup_assuming_on_curve()
+                }),
+            "Invalid point {} in {:?}",
+            index,
+            section
+        );
+        Ok(point)
+    }
+
+    /// Returns `true` if the ratio between the G1 points `a` is the ratio between the G2 points `b`,
+    /// see [`same_ratio`].
+    #[inline]
+    fn same_ratio(&self, a: (G1<C>, G1<C>), b: (G2<C>, G2<C>)) -> bool {
+        self.time(Phase::Pairing, || same_ratio::<C>(a, b))
+    }
+
+    /// Checks that the first `powers` powers of each section are successive powers of the same tau,
+    /// starting from the generators, with a random linear combination of the points of each
+    /// section. Returns the sections whose points are not.
+    #[inline]
+    pub fn check_powers(&self, powers: usize) -> Result<Vec<Section>> {
+        let powers = powers.clamp(2, self.layout.powers);
+        let g1 = G1::<C>::prime_subgroup_generator();
+        let g2 = G2::<C>::prime_subgroup_generator();
+        let (tau_g1, tau_g2) = (self.g1(Section::TauG1, 1)?, self.g2(Section::TauG2, 1)?);
+        let mut invalid = Vec::new();
+        if self.g1(Section::TauG1, 0)? != g1 || self.g2(Section::TauG2, 0)? != g2 {
+            invalid.push(Section::TauG1);
+        }
+        for section in [Section::TauG1, Section::AlphaG1, Section::BetaG1] {
+            let (a, b) =
+                combine_powers_with(section.len(powers), |i| self.g1(section, i), self.profile)?;
+            if !invalid.contains(&section) && !self.same_ratio((a, b), (g2, tau_g2)) {
+                invalid.push(section);
+            }
+        }
+        let (a, b) = combine_powers_with(powers, |i| self.g2(Section::TauG2, i), self.profile)?;
+        if !self.same_ratio((g1, tau_g1), (a, b)) {
+            invalid.push(Section::TauG2);
+        }
+        Ok(invalid)
+    }
+
+    /// Verifies on their first `powers` powers that the `response` file is the transform of the
+    /// challenge file `self`, whose hash is `challenge_hash`, by the contribution whose public key
+    /// ends `response`.
+    #[inline]
+    pub fn verify_transform(
+        &self,
+        response: &Self,
+        challenge_hash: &Hash64,
+        powers: usize,
+    ) -> Result {
+        ensure!(
+            self.layout.encoding == Encoding::Uncompressed
+                && response.layout.encoding == Encoding::Compressed,
+            "A transform turns a challenge file into a response file"
+        );
+        let key = response.public_key()?;
+        let invalid = key.verify(challenge_hash);
+        if !invalid.is_empty() {
+            bail!("Invalid proofs of knowledge of {:?}", invalid);
+        }
+        for secret in Secret::ALL {
+            let index = secret.personalization() as usize;
+            let (s, s_x) = key.g1[index];
+            let r = derive_g2::<C>(challenge_hash, &s, &s_x, secret);
+            let (section, power) = match secret {
+                Secret::Tau => (Section::TauG1, 1),
+                Secret::Alpha => (Section::AlphaG1, 0),
+                Secret::Beta => (Section::BetaG1, 0),
+            };
+            let points = (self.g1(section, power)?, response.g1(section, power)?);
+            if !self.same_ratio(points, (r, key.g2[index])) {
+                bail!(
+                    "The response does not multiply {:?} by the {} of its public key",
+                    section,
+                    secret
+                );
+            }
+        }
+        let beta_g1 = (
+            self.g1(Section::BetaG1, 0)?,
+            response.g1(Section::BetaG1, 0)?,
+        );
+        let beta_g2 = (
+            self.g2(Section::BetaG2, 0)?,
+            response.g2(Section::BetaG2, 0)?,
+        );
+        if !self.same_ratio(beta_g1, beta_g2) {
+            bail!("The response does not multiply BetaG2 by the beta of its public key");
+        }
+        let invalid = response.check_powers(powers)?;
+        if !invalid.is_empty() {
+            bail!(
+                "The response does not hold successive powers of tau in {:?}",
+                invalid
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Verifies on their first `powers` powers that the `response` file over `C` is the transform of
+/// the `challenge` file, both with `file_powers` powers of tau, against the hash of the challenge
+/// file asserted by the header of the response.
+#[inline]
+pub fn verify_round<C>(
+    challenge: &[u8],
+    response: &[u8],
+    file_powers: usize,
+    powers: usize,
+) -> Result
+where
+    C: Curve,
+{
+    let challenge_hash =
+        Hash64::from_header(response).ok_or_else(|| anyhow!("The response file is too short"))?;
+    Accumulator::<C>::challenge(challenge, file_powers)?.verify_transform(
+        &Accumulator::<C>::response(response, file_powers)?,
+        &challenge_hash,
+        powers,
+    )
+}
+
+/// Verifies the round turning the `challenge` file into the `response` file over `curve`, see
+/// [`verify_round`].
+#[inline]
+pub fn verify_round_on(
+    curve: CurveKind,
+    challenge: &[u8],
+    response: &[u8],
+    file_powers: usize,
+    powers: usize,
+) -> Result {
+    match curve {
+        CurveKind::Bn254 => verify_round::<Bn254>(challenge, response, file_powers, powers),
+        CurveKind::Bls12_381 => verify_round::<Bls12_381>(challenge, response, file_powers, powers),
+    }
+}
+
+/// Returns the outcome of a round at each power level of `levels`, from the largest to the
+/// smallest, after its outcome on all its `2^log_powers` powers, failed with `error` if any. The
+/// powers of a level are a prefix of the powers of the levels above it, so the levels below the
+/// largest one verified successfully are verified as well, and only the levels above it are
+/// verified on their own with `verify`, on their first `2^level` powers.
+#[inline]
+pub fn verify_levels<F>(
+    log_powers: u32,
+    error: Option<&str>,
+    levels: &[u32],
+    mut verify: F,
+) -> Vec<(u32, Option<String>)>
+where
+    F: FnMut(u32) -> Result,
+{
+    let mut outcomes = vec![(log_powers, error.map(String::from))];
+    let mut verified = error.is_none();
+    for &level in levels {
+        let error = if verified {
+            None
+        } else {
+            match verify(level) {
+                Ok(()) => {
+                    verified = true;
+                    None
+                }
+                Err(err) => Some(err.to_string()),
+            }
+        };
+        outcomes.push((level, error));
+    }
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::Scalar,
+        format::{write_g1_on, write_g2_on},
+        synthetic::{MiniCeremony, SYNTHETIC_LOG_POWERS},
+    };
+    use ark_ff::{Field, One};
+
+    /// Writes an accumulator over `C` with `layout` for the secrets `tau`, `alpha` and `beta`,
+    /// ending with `key` for response files.
+    fn accumulator<C>(
+        layout: &Layout,
+        [tau, alpha, beta]: [Scalar<C>; 3],
+        key: Option<&PublicKey<C>>,
+    ) -> Vec<u8>
+    where
+        C: Curve,
+    {
+        let mut data = vec![0; layout.file_size()];
+        let g1 = G1::<C>::prime_subgroup_generator();
+        let g2 = G2::<C>::prime_subgroup_generator();
+        for section in Section::ALL {
+            for index in 0..section.len(layout.powers) {
+                let power = tau.pow([index as u64]);
+                let out = &mut data[layout.point_range(section, index)];
+                match section {
+                    Section::TauG1 => {
+                        write_g1_on::<C>(&g1.mul(power).into_affine(), layout.encoding, out)
+                    }
+                    Section::AlphaG1 => {
+                        write_g1_on::<C>(&g1.mul(alpha * power).into_affine(), layout.encoding, out)
+                    }
+                    Section::BetaG1 => {
+                        write_g1_on::<C>(&g1.mul(beta * power).into_affine(), layout.encoding, out)
+                    }
+                    Section::TauG2 => {
+                        write_g2_on::<C>(&g2.mul(power).into_affine(), layout.encoding, out)
+                    }
+                    Section::BetaG2 => {
+                        write_g2_on::<C>(&g2.mul(beta).into_affine(), layout.encoding, out)
+                    }
+                }
+            }
+        }
+        if let Some(key) = key {
+            let size = PublicKey::<C>::size();
+            let out = &mut data[layout.file_size() - size..];
+            let (g1_size, g2_size) = (
+                Encoding::Uncompressed.g1_size_on::<C>(),
+                Encoding::Uncompressed.g2_size_on::<C>(),
+            );
+            for (i, point) in key.g1.iter().flat_map(|(s, s_x)| [s, s_x]).enumerate() {
+                write_g1_on::<C>(point, Encoding::Uncompressed, &mut out[i * g1_size..]);
+            }
+            for (i, point) in key.g2.iter().enumerate() {
+                write_g2_on::<C>(
+                    point,
+                    Encoding::Uncompressed,
+                    &mut out[6 * g1_size + i * g2_size..],
+                );
+            }
+        }
+        data
+    }
+
+    /// Checks that a contribution over `C` is verified and that a response multiplying the
+    /// accumulator by other secrets than the secrets of its key is rejected.
+    fn verify_on<C>()
+    where
+        C: Curve,
+    {
+        let mut rng = rand::thread_rng();
+        let powers = 4;
+        let challenge_hash = Hash64([5; 64]);
+        let before = [0; 3].map(|_| Scalar::<C>::rand(&mut rng));
+        let secrets = [0; 3].map(|_| Scalar::<C>::rand(&mut rng));
+        let mut key = PublicKey::<C> {
+            g1: [(G1::<C>::zero(), G1::<C>::zero()); 3],
+            g2: [G2::<C>::zero(); 3],
+        };
+        for secret in Secret::ALL {
+            let index = secret.personalization() as usize;
+            let s = G1::<C>::prime_subgroup_generator()
+                .mul(Scalar::<C>::rand(&mut rng))
+                .into_affine();
+            let s_x = s.mul(secrets[index]).into_affine();
+            key.g1[index] = (s, s_x);
+            key.g2[index] = derive_g2::<C>(&challenge_hash, &s, &s_x, secret)
+                .mul(secrets[index])
+                .into_affine();
+        }
+        let after = [0, 1, 2].map(|i| before[i] * secrets[i]);
+        let challenge = accumulator::<C>(&Layout::challenge_on::<C>(powers), before, None);
+        let challenge = Accumulator::<C>::challenge(&challenge, powers).unwrap();
+        let response = accumulator::<C>(&Layout::response_on::<C>(powers), after, Some(&key));
+        let response = Accumulator::<C>::response(&response, powers).unwrap();
+        assert!(response.check_powers(powers).unwrap().is_empty());
+        challenge
+            .verify_transform(&response, &challenge_hash, powers)
+            .unwrap();
+        assert!(challenge
+            .verify_transform(&response, &Hash64([6; 64]), powers)
+            .is_err());
+        let other = [after[0], after[1] + Scalar::<C>::one(), after[2]];
+        let tampered = accumulator::<C>(&Layout::response_on::<C>(powers), other, Some(&key));
+        assert!(challenge
+            .verify_transform(
+                &Accumulator::<C>::response(&tampered, powers).unwrap(),
+                &challenge_hash,
+                powers
+            )
+            .is_err());
+        assert!(Accumulator::<C>::response(&tampered[1..], powers).is_err());
+    }
+
+    /// Checks the verification of a transform over both curves.
+    #[test]
+    fn verify_transform() {
+        verify_on::<Bn254>();
+        verify_on::<Bls12_381>();
+    }
+
+    /// Checks that the power levels of a valid round are verified without verifying them again,
+    /// and that a round broken between two levels fails on the levels above the break only.
+    #[test]
+    fn verify_power_levels() {
+        let mut rng = rand::thread_rng();
+        let powers = 1 << SYNTHETIC_LOG_POWERS;
+        let ceremony = MiniCeremony::<Bn254>::generate(powers, 1, &mut rng);
+        let levels = [6, 4, 2];
+        let verify = |response: &[u8], level: u32| {
+            verify_round::<Bn254>(&ceremony.challenges[0], response, powers, 1 << level)
+        };
+        let full = verify(&ceremony.responses[0], SYNTHETIC_LOG_POWERS);
+        assert!(full.is_ok());
+        let outcomes = verify_levels(SYNTHETIC_LOG_POWERS, None, &levels, |_| {
+            panic!("The levels of a verified round are verified as well.")
+        });
+        assert_eq!(outcomes, [(8, None), (6, None), (4, None), (2, None)]);
+        for level in levels {
+            assert!(verify(&ceremony.responses[0], level).is_ok());
+        }
+        // The powers of tau in G1 of level `L` are the first `2^(L + 1) - 1` ones, so a point
+        // broken at index 40 breaks the levels 6 and 8 only.
+        let layout = Layout::response_on::<Bn254>(powers);
+        let mut response = ceremony.responses[0].clone();
+        let point = G1::<Bn254>::prime_subgroup_generator()
+            .mul(Scalar::<Bn254>::rand(&mut rng))
+            .into_affine();
+        write_g1_on::<Bn254>(
+            &point,
+            layout.encoding,
+            &mut response[layout.point_range(Section::TauG1, 40)],
+        );
+        let error = verify(&response, SYNTHETIC_LOG_POWERS)
+            .unwrap_err()
+            .to_string();
+        let mut verified = Vec::new();
+        let outcomes = verify_levels(SYNTHETIC_LOG_POWERS, Some(&error), &levels, |level| {
+            verified.push(level);
+            verify(&response, level)
+        });
+        assert_eq!(verified, [6, 4]);
+        assert_eq!(
+            outcomes
+                .iter()
+                .map(|(level, error)| (*level, error.is_none()))
+                .collect::<Vec<_>>(),
+            [(8, false), (6, false), (4, true), (2, true)]
+        );
+        assert!(outcomes[1]
+            .1
+            .as_deref()
+            .is_some_and(|error| error.contains("TauG1")));
+    }
+}
@@ -0,0 +1,305 @@
+//! Aggregation of Signed Reports
+//!
+//! Every verifier who publishes a signed canonical report vouches for the rounds it verified. An
+//! [`Aggregate`] collects the reports of many verifiers into one view of the ceremony: for every
+//! round, the signers who verified it, those whose verification failed and the files they claim
+//! different hashes for, so that a coordinator can tell at a glance which rounds were confirmed by
+//! enough independent parties. A round is confirmed once at least `threshold` signers verified it
+//! and none of them disagrees.
+
+use crate::{canonical::CanonicalReport, hash::Hash64, status::Verification};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Canonical Report whose Signature was Checked
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SignedReport {
+    /// Name of the signer, the same for every report signed with the same key or identity
+    pub signer: String,
+
+    /// Report
+    pub report: CanonicalReport,
+}
+
+/// Hash Claimed for a File by Some Signers
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct HashClaim {
+    /// Claimed Blake2b hash
+    pub blake2b: Hash64,
+
+    /// Signers claiming the hash
+    pub signers: BTreeSet<String>,
+}
+
+/// File Claimed with Different Hashes
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct HashConflict {
+    /// Local file name
+    pub path: String,
+
+    /// Claimed hashes, with the signers claiming them
+    pub claims: Vec<HashClaim>,
+}
+
+/// Aggregated Round
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundAggregate {
+    /// Index of the round
+    pub round: usize,
+
+    /// Signers who verified the round
+    pub verified: BTreeSet<String>,
+
+    /// Signers whose verification of the round failed
+    pub failed: BTreeSet<String>,
+
+    /// Files of the round claimed with different hashes
+    pub conflicts: Vec<HashConflict>,
+}
+
+impl RoundAggregate {
+    /// Returns `true` if the signers disagree on the round.
+    #[inline]
+    pub fn is_disputed(&self) -> bool {
+        !self.failed.is_empty() || !self.conflicts.is_empty()
+    }
+
+    /// Returns `true` if at least `threshold` signers verified the round and none disagrees.
+    #[inline]
+    pub fn is_confirmed(&self, threshold: usize) -> bool {
+        self.verified.len() >= threshold && !self.is_disputed()
+    }
+
+    /// Returns the name of the status of the round with respect to `threshold`.
+    #[inline]
+    pub fn status(&self, threshold: usize) -> &'static str {
+        if self.is_disputed() {
+            "disputed"
+        } else if self.is_confirmed(threshold) {
+            "confirmed"
+        } else {
+            "below threshold"
+        }
+    }
+}
+
+/// Aggregate of Signed Reports
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Aggregate {
+    /// Signers of the aggregated reports
+    pub signers: BTreeSet<String>,
+
+    /// Number of signers who must verify a round for it to be confirmed
+    pub threshold: usize,
+
+    /// Every round found in the reports, sorted by index
+    pub rounds: Vec<RoundAggregate>,
+}
+
+impl Aggregate {
+    /// Aggregates `reports`, confirming the rounds verified by at least `threshold` signers.
+    #[inline]
+    pub fn new(reports: &[SignedReport], threshold: usize) -> Self {
+        let mut signers = BTreeSet::new();
+        let mut rounds = BTreeMap::<usize, (RoundAggregate, BTreeMap<&str, HashClaims>)>::new();
+        for SignedReport { signer, report } in reports {
+            signers.insert(signer.clone());
+            for round in &report.rounds {
+                let (aggregate, files) = rounds.entry(round.round).or_insert_with(|| {
+                    (
+                        RoundAggregate {
+                            round: round.round,
+                            verified: BTreeSet::new(),
+                            failed: BTreeSet::new(),
+                            conflicts: Vec::new(),
+                        },
+                        BTreeMap::new(),
+                    )
+                });
+                match round.verification {
+                    Verification::Verified => {
+                        aggregate.verified.insert(signer.clone());
+                    }
+                    Verification::Failed => {
+                        aggregate.failed.insert(signer.clone());
+                    }
+                    Verification::Pending => {}
+                }
+                for file in &round.files {
+                    if let Some(blake2b) = file.blake2b {
+                        files
+                            .entry(file.path.as_str())
+                            .or_default()
+                            .entry(blake2b)
+                            .or_default()
+                            .insert(signer.clone());
+                    }
+                }
+            }
+        }
+        Self {
+            signers,
+            threshold,
+            rounds: rounds
+                .into_values()
+                .map(|(mut aggregate, files)| {
+                    aggregate.conflicts = files
+                        .into_iter()
+                        .filter(|(_, claims)| claims.len() > 1)
+                        .map(|(path, claims)| HashConflict {
+                            path: path.into(),
+                            claims: claims
+                                .into_iter()
+                                .map(|(blake2b, signers)| HashClaim { blake2b, signers })
+                                .collect(),
+                        })
+                        .collect();
+                    aggregate
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the number of confirmed rounds.
+    #[inline]
+    pub fn confirmed(&self) -> usize {
+        self.rounds
+            .iter()
+            .filter(|round| round.is_confirmed(self.threshold))
+            .count()
+    }
+
+    /// Returns the disputed rounds.
+    #[inline]
+    pub fn disputed(&self) -> impl Iterator<Item = &RoundAggregate> {
+        self.rounds.iter().filter(|round| round.is_disputed())
+    }
+
+    /// Renders the aggregate as a Markdown summary.
+    #[inline]
+    pub fn markdown(&self) -> String {
+        let mut markdown = String::from("# Perpetual Powers of Tau Verifications\n\n");
+        markdown.push_str(&format!(
+            "{} of {} rounds verified by at least {} of {} independent signers.\n\n",
+            self.confirmed(),
+            self.rounds.len(),
+            self.threshold,
+            self.signers.len()
+        ));
+        markdown.push_str("## Signers\n\n");
+        for signer in &self.signers {
+            markdown.push_str(&format!("- {}\n", signer));
+        }
+        markdown.push_str("\n## Rounds\n\n");
+        markdown.push_str("| Round | Verified by | Failed by | Status |\n");
+        markdown.push_str("|------:|-------------|-----------|--------|\n");
+        let join =
+            |signers: &BTreeSet<String>| signers.iter().cloned().collect::<Vec<_>>().join(", ");
+        for round in &self.rounds {
+            markdown.push_str(&format!(
+                "| {} | {} ({}) | {} | {} |\n",
+                round.round,
+                round.verified.len(),
+                join(&round.verified),
+                join(&round.failed),
+                round.status(self.threshold)
+            ));
+        }
+        let conflicts = self
+            .rounds
+            .iter()
+            .flat_map(|round| {
+                round
+                    .conflicts
+                    .iter()
+                    .map(move |conflict| (round, conflict))
+            })
+            .collect::<Vec<_>>();
+        if !conflicts.is_empty() {
+            markdown.push_str("\n## Hash Conflicts\n\n");
+            for (round, conflict) in conflicts {
+                markdown.push_str(&format!("- Round {}, {}:\n", round.round, conflict.path));
+                for claim in &conflict.claims {
+                    markdown.push_str(&format!(
+                        "  - `{}` by {}\n",
+                        claim.blake2b,
+                        join(&claim.signers)
+                    ));
+                }
+            }
+        }
+        markdown
+    }
+}
+
+/// Signers of Every Hash Claimed for a File
+type HashClaims = BTreeMap<Hash64, BTreeSet<String>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canonical::{CanonicalFile, CanonicalRound, CANONICAL_VERSION};
+
+    /// Checks that rounds are confirmed by enough agreeing signers and disputed on failed
+    /// verifications and conflicting hashes.
+    #[test]
+    fn reports_aggregate() {
+        let report = |signer: &str, rounds: Vec<(usize, Verification, u8)>| SignedReport {
+            signer: signer.into(),
+            report: CanonicalReport {
+                log_powers: Some(19),
+                rounds: rounds
+                    .into_iter()
+                    .map(|(round, verification, byte)| CanonicalRound {
+                        chain: None,
+                        files: vec![CanonicalFile {
+                            blake2b: Some(Hash64([byte; 64])),
+                            path: format!("response_{:04}", round),
+                        }],
+                        round,
+                        verification,
+                    })
+                    .collect(),
+                version: CANONICAL_VERSION,
+            },
+        };
+        let aggregate = Aggregate::new(
+            &[
+                report(
+                    "alice",
+                    vec![
+                        (1, Verification::Verified, 1),
+                        (2, Verification::Verified, 2),
+                        (3, Verification::Verified, 3),
+                    ],
+                ),
+                report(
+                    "bob",
+                    vec![
+                        (1, Verification::Verified, 1),
+                        (2, Verification::Failed, 2),
+                        (3, Verification::Verified, 4),
+                    ],
+                ),
+                report("carol", vec![(1, Verification::Verified, 1)]),
+            ],
+            2,
+        );
+        assert_eq!(aggregate.signers.len(), 3);
+        let statuses = aggregate
+            .rounds
+            .iter()
+            .map(|round| (round.round, round.verified.len(), round.status(2)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [(1, 3, "confirmed"), (2, 1, "disputed"), (3, 2, "disputed")]
+        );
+        assert_eq!(aggregate.confirmed(), 1);
+        assert_eq!(aggregate.rounds[2].conflicts[0].claims.len(), 2);
+        assert_eq!(aggregate.disputed().count(), 2);
+        assert!(aggregate
+            .markdown()
+            .contains("1 of 3 rounds verified by at least 2 of 3 independent signers."));
+    }
+}
@@ -0,0 +1,97 @@
+//! Atomic Writes
+//!
+//! Hash files, manifests, caches and download checkpoints are read back by later runs, so they are
+//! first written to a temporary file next to their destination, synced, and then renamed over it.
+//! A crash while writing leaves either the previous file or the new one, never a truncated file.
+
+use crate::Result;
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+#[cfg(feature = "download")]
+use tokio::io::AsyncWriteExt;
+
+/// Returns the path of the temporary file written before being renamed to `path`.
+#[inline]
+pub fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+/// Atomically replaces the contents of the file at `path` with `contents`.
+#[inline]
+pub fn write<P, C>(path: P, contents: C) -> Result
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    write_with(path, |file| Ok(file.write_all(contents.as_ref())?))
+}
+
+/// Atomically replaces the contents of the file at `path` with the bytes written by `write`, for
+/// contents too large to be held in memory.
+#[inline]
+pub fn write_with<P, F>(path: P, write: F) -> Result
+where
+    P: AsRef<Path>,
+    F: FnOnce(&mut BufWriter<File>) -> Result,
+{
+    let path = path.as_ref();
+    let temporary = temporary_path(path);
+    let result = (|| {
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        write(&mut file)?;
+        file.into_inner()?.sync_all()?;
+        Ok(fs::rename(&temporary, path)?)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+/// Atomically replaces the contents of the file at `path` with `contents`, without blocking the
+/// runtime.
+#[cfg(feature = "download")]
+#[inline]
+pub async fn write_async<P, C>(path: P, contents: C) -> Result
+where
+    P: AsRef<Path>,
+    C: AsRef<[u8]>,
+{
+    let path = path.as_ref();
+    let temporary = temporary_path(path);
+    let result = async {
+        let mut file = tokio::fs::File::create(&temporary).await?;
+        file.write_all(contents.as_ref()).await?;
+        file.sync_all().await?;
+        tokio::fs::rename(&temporary, path).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temporary).await;
+    }
+    Ok(result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that writes replace the previous contents without leaving the temporary file behind.
+    #[test]
+    fn writes_replace_contents() {
+        let path = std::env::temp_dir().join("ppot-verifier-atomic-test");
+        write(&path, b"old contents").unwrap();
+        write(&path, b"new").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert!(!temporary_path(&path).exists());
+        fs::remove_file(&path).unwrap();
+        let missing = std::env::temp_dir().join("ppot-verifier-missing-dir/file");
+        assert!(write(&missing, b"contents").is_err());
+        assert!(!temporary_path(&missing).exists());
+    }
+}
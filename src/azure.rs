@@ -0,0 +1,260 @@
+//! Azure Blob Storage Container Listing
+
+use crate::Result;
+use anyhow::anyhow;
+use quick_xml::{events::Event, Reader};
+use reqwest::{Client, Method};
+
+/// Public Azure Blob Storage container holding the PPoT `challenge` and `response` files
+pub const CONTAINER_URL: &str = "https://ppot.blob.core.windows.net/public";
+
+/// Blob Kind
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum BlobKind {
+    /// Challenge File
+    Challenge,
+
+    /// Response File
+    Response,
+}
+
+/// Blob Metadata
+///
+/// This is the subset of the properties returned by the [List Blobs] operation that we need to
+/// locate and validate the ceremony files.
+///
+/// [List Blobs]: https://learn.microsoft.com/en-us/rest/api/storageservices/list-blobs
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Blob {
+    /// Name of the blob inside of the container
+    pub name: String,
+
+    /// Size of the blob in bytes
+    pub size: u64,
+
+    /// Base64-encoded MD5 digest of the blob, if the server has one on record
+    pub content_md5: Option<String>,
+}
+
+impl Blob {
+    /// Returns the URL of `self` inside of the container at `container_url`.
+    #[inline]
+    pub fn url(&self, container_url: &str) -> String {
+        format!("{}/{}", container_url.trim_end_matches('/'), self.name)
+    }
+
+    /// Parses the kind and ceremony number out of the blob name, returning `None` if the blob is
+    /// not a `challenge` or `response` file. The initial challenge is named `challenge_initial` and
+    /// is assigned the number `1`.
+    #[inline]
+    pub fn parse_name(&self) -> Option<(BlobKind, usize)> {
+        parse_blob_name(&self.name)
+    }
+
+    /// Returns the local file name of `self` following the numbering of [`challenge_paths`] and
+    /// [`response_paths`], which for challenges is offset by one from the ceremony numbering.
+    ///
+    /// [`challenge_paths`]: crate::challenge_paths
+    /// [`response_paths`]: crate::response_paths
+    #[inline]
+    pub fn local_path(&self) -> Option<String> {
+        match self.parse_name()? {
+            (BlobKind::Challenge, number) => Some(format!("challenge_{:04}", number - 1)),
+            (BlobKind::Response, number) => Some(format!("response_{:04}", number)),
+        }
+    }
+}
+
+/// Parses a blob name of the form `challenge_initial`, `challenge_NNNN[_name]` or
+/// `response_NNNN_name` into its kind and ceremony number.
+#[inline]
+pub fn parse_blob_name(name: &str) -> Option<(BlobKind, usize)> {
+    if name == "challenge_initial" {
+        return Some((BlobKind::Challenge, 1));
+    }
+    let (kind, rest) = if let Some(rest) = name.strip_prefix("challenge_") {
+        (BlobKind::Challenge, rest)
+    } else if let Some(rest) = name.strip_prefix("response_") {
+        (BlobKind::Response, rest)
+    } else {
+        return None;
+    };
+    let number = rest.split('_').next()?;
+    if number.len() != 4 {
+        return None;
+    }
+    match number.parse() {
+        Ok(0) | Err(_) => None,
+        Ok(number) => Some((kind, number)),
+    }
+}
+
+/// Lists all the blobs in the container at `container_url`, following the continuation markers
+/// until the listing is exhausted.
+#[inline]
+pub async fn list_blobs(client: &Client, container_url: &str) -> Result<Vec<Blob>> {
+    let mut blobs = Vec::new();
+    let mut marker = None;
+    loop {
+        let mut request = client
+            .request(Method::GET, container_url)
+            .query(&[("restype", "container"), ("comp", "list")]);
+        if let Some(marker) = &marker {
+            request = request.query(&[("marker", marker)]);
+        }
+        let body = request.send().await?.error_for_status()?.text().await?;
+        marker = parse_listing(&body, &mut blobs)?;
+        if marker.is_none() {
+            return Ok(blobs);
+        }
+    }
+}
+
+/// Parses one page of a [List Blobs] XML response, pushing the blobs it contains onto `blobs` and
+/// returning the continuation marker if there are more pages to fetch.
+///
+/// [List Blobs]: https://learn.microsoft.com/en-us/rest/api/storageservices/list-blobs
+fn parse_listing(xml: &str, blobs: &mut Vec<Blob>) -> Result<Option<String>> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut buffer = Vec::new();
+    let mut path = Vec::<Vec<u8>>::new();
+    let mut current = None::<Blob>;
+    let mut marker = None;
+    loop {
+        match reader.read_event(&mut buffer)? {
+            Event::Start(tag) => {
+                if tag.name() == b"Blob" {
+                    current = Some(Blob::default());
+                }
+                path.push(tag.name().to_vec());
+            }
+            Event::End(tag) => {
+                if tag.name() == b"Blob" {
+                    blobs.push(
+                        current
+                            .take()
+                            .ok_or_else(|| anyhow!("Unbalanced <Blob> tag."))?,
+                    );
+                }
+                path.pop();
+            }
+            Event::Text(text) => {
+                let text = text.unescape_and_decode(&reader)?;
+                match (path.last().map(Vec::as_slice), current.as_mut()) {
+                    (Some(b"Name"), Some(blob)) => blob.name = text,
+                    (Some(b"Content-Length"), Some(blob)) => blob.size = text.parse()?,
+                    (Some(b"Content-MD5"), Some(blob)) => blob.content_md5 = Some(text),
+                    (Some(b"NextMarker"), None) => marker = Some(text),
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+    Ok(marker)
+}
+
+/// Sorts the `challenge` and `response` blobs in `blobs` by their ceremony number, dropping any
+/// other blobs. If the container holds more than one blob for the same file, the first one in
+/// listing order is kept.
+#[inline]
+pub fn sort_blobs(blobs: Vec<Blob>) -> (Vec<Blob>, Vec<Blob>) {
+    let mut challenges = Vec::<(usize, Blob)>::new();
+    let mut responses = Vec::<(usize, Blob)>::new();
+    for blob in blobs {
+        let (kind, number) = match blob.parse_name() {
+            Some(parsed) => parsed,
+            _ => continue,
+        };
+        let list = match kind {
+            BlobKind::Challenge => &mut challenges,
+            BlobKind::Response => &mut responses,
+        };
+        if !list.iter().any(|(n, _)| *n == number) {
+            list.push((number, blob));
+        }
+    }
+    challenges.sort_by_key(|(n, _)| *n);
+    responses.sort_by_key(|(n, _)| *n);
+    (
+        challenges.into_iter().map(|(_, b)| b).collect(),
+        responses.into_iter().map(|(_, b)| b).collect(),
+    )
+}
+
+/// Queries the container at `container_url` and returns the `challenge` and `response` URLs sorted
+/// by ceremony number, instead of the embedded [`challenge_urls`] and [`response_urls`].
+///
+/// [`challenge_urls`]: crate::challenge_urls
+/// [`response_urls`]: crate::response_urls
+#[inline]
+pub async fn blob_urls(client: &Client, container_url: &str) -> Result<(Vec<String>, Vec<String>)> {
+    let (challenges, responses) = sort_blobs(list_blobs(client, container_url).await?);
+    Ok((
+        challenges.iter().map(|b| b.url(container_url)).collect(),
+        responses.iter().map(|b| b.url(container_url)).collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that a single page of the listing is parsed along with its continuation marker.
+    #[test]
+    fn parse_listing_page() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <EnumerationResults ContainerName="https://ppot.blob.core.windows.net/public">
+                <Blobs>
+                    <Blob>
+                        <Name>challenge_0002_kobi</Name>
+                        <Properties>
+                            <Content-Length>103079215232</Content-Length>
+                            <Content-MD5>1B2M2Y8AsgTpgAmY7PhCfg==</Content-MD5>
+                        </Properties>
+                    </Blob>
+                    <Blob>
+                        <Name>response_0001_weijie</Name>
+                        <Properties>
+                            <Content-Length>51539608416</Content-Length>
+                        </Properties>
+                    </Blob>
+                </Blobs>
+                <NextMarker>2!72!MDAwMDE</NextMarker>
+            </EnumerationResults>"#;
+        let mut blobs = Vec::new();
+        let marker = parse_listing(xml, &mut blobs).unwrap();
+        assert_eq!(marker.as_deref(), Some("2!72!MDAwMDE"));
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].name, "challenge_0002_kobi");
+        assert_eq!(blobs[0].size, 103079215232);
+        assert_eq!(
+            blobs[0].content_md5.as_deref(),
+            Some("1B2M2Y8AsgTpgAmY7PhCfg==")
+        );
+        assert_eq!(blobs[1].content_md5, None);
+        assert_eq!(blobs[1].local_path().as_deref(), Some("response_0001"));
+    }
+
+    /// Checks the irregular blob names of the ceremony.
+    #[test]
+    fn parse_blob_names() {
+        assert_eq!(
+            parse_blob_name("challenge_initial"),
+            Some((BlobKind::Challenge, 1))
+        );
+        assert_eq!(
+            parse_blob_name("challenge_0003"),
+            Some((BlobKind::Challenge, 3))
+        );
+        assert_eq!(
+            parse_blob_name("response_0016_aurel"),
+            Some((BlobKind::Response, 16))
+        );
+        assert_eq!(parse_blob_name("response_0016.torrent"), None);
+        assert_eq!(parse_blob_name("README"), None);
+    }
+}
@@ -1,227 +1,32 @@
 //! Download all PPoT challenge and response files
 
-use anyhow::anyhow;
-use core::{cmp::min, num::ParseIntError, str::FromStr};
-use futures::future::try_join_all;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use reqwest::{
-    header::{CONTENT_RANGE, RANGE},
-    Client, Method, Response, StatusCode,
-};
-use std::path::Path;
-use tokio::{
-    fs::{File, OpenOptions},
-    io::{AsyncWriteExt, BufWriter},
-    task,
-};
-
-/// Result Type
-pub type Result<T = (), E = anyhow::Error> = core::result::Result<T, E>;
-
-/// Checks if the file exists by sending a [`GET`](Method::GET) request to the server at `url` and
-/// checking if an [`OK`](StatusCode::OK) is returned.
-#[inline]
-pub async fn file_exists(client: &Client, url: &str) -> Result<bool> {
-    Ok(client.request(Method::GET, url).send().await?.status() == StatusCode::OK)
-}
-
-/// Content Range
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
-pub enum ContentRange {
-    /// Full Range Data
-    ///
-    /// This is the response from the server when the [`RANGE`] header `start` value is set less
-    /// than the entire data stream.
-    Full {
-        /// Starting index
-        start: u64,
-
-        /// Ending index
-        end: u64,
-
-        /// Total size of the data stored on the server. This is not a measure of how much data is
-        /// sent over in the response, that would be `end - start`.
-        size: u64,
+use anyhow::{anyhow, bail};
+use clap::Parser;
+use core::time::Duration;
+use futures::future::join_all;
+use ppot_verifier::{
+    checkpoint::{Checkpoint, CHECKPOINT_PATH},
+    checksum::{fetch_content_md5, verify_content_md5},
+    config::{Config, CONFIG_PATH},
+    db::StateDb,
+    disk::estimate,
+    download::{
+        download_file, file_exists, DownloadOptions, CONNECT_TIMEOUT, RETRIES, STALL_TIMEOUT,
     },
-
-    /// Size Data
-    ///
-    /// When the [`RANGE`] header `start` value sent to the server is exactly equal to the size of
-    /// the data payload, then only that same size is returned back.
-    Size(u64),
-}
-
-impl ContentRange {
-    /// Parses a [`ContentRange`] from `response` returning `None` if the header did not exist or if
-    /// it did exist but could not be parsed.
-    #[inline]
-    pub fn from_response(response: &Response) -> Option<Self> {
-        response
-            .headers()
-            .get(CONTENT_RANGE)?
-            .to_str()
-            .ok()?
-            .parse()
-            .ok()
-    }
-}
-
-impl FromStr for ContentRange {
-    type Err = ContentRangeParseError;
-
-    #[inline]
-    fn from_str(range_string: &str) -> Result<Self, Self::Err> {
-        let (bytes_tag, range) = range_string
-            .split_once(' ')
-            .ok_or(Self::Err::MissingSpace)?;
-        if bytes_tag == "bytes" {
-            match range.split_once('-') {
-                Some((start, end_and_size)) => {
-                    let (end, size) = end_and_size
-                        .split_once('/')
-                        .ok_or(Self::Err::MissingSlash)?;
-                    Ok(Self::Full {
-                        start: start.parse().map_err(Self::Err::InvalidStart)?,
-                        end: end.parse().map_err(Self::Err::InvalidEnd)?,
-                        size: size.parse().map_err(Self::Err::InvalidSize)?,
-                    })
-                }
-                _ => {
-                    let (star, size) = range.split_once('/').ok_or(Self::Err::MissingSlash)?;
-                    if star == "*" {
-                        Ok(Self::Size(size.parse().map_err(Self::Err::InvalidSize)?))
-                    } else {
-                        Err(Self::Err::MissingStar)
-                    }
-                }
-            }
-        } else {
-            Err(Self::Err::MissingBytesTag)
-        }
-    }
-}
-
-/// Content Range Parse Error
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum ContentRangeParseError {
-    /// Missing Space
-    MissingSpace,
-
-    /// Missing Bytes Tag
-    MissingBytesTag,
-
-    /// Missing Slash
-    MissingSlash,
-
-    /// Missing Star
-    MissingStar,
-
-    /// Invalid Start Index
-    InvalidStart(ParseIntError),
-
-    /// Invalid End Index
-    InvalidEnd(ParseIntError),
-
-    /// Invalid Size Index
-    InvalidSize(ParseIntError),
-}
-
-/// Opens the file at `path` into a [`BufWriter`] and returns its current length.
-#[inline]
-pub async fn open_file<P>(path: P) -> Result<(u64, BufWriter<File>)>
-where
-    P: AsRef<Path>,
-{
-    let file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(path)
-        .await?;
-    Ok((file.metadata().await?.len(), BufWriter::new(file)))
-}
-
-/// Sends the download request to the server at `url` with the [`RANGE`] header set to start its
-/// range at `start`, returning the [`Response`] from the server and the total size of the file to
-/// be downloaded. This function returns `None` if the [`RANGE`] `start` bound is equal to the size
-/// of the file, meaning nothing needs to be downloaded.
-#[inline]
-pub async fn send_download_request(
-    client: &Client,
-    url: &str,
-    start: u64,
-) -> Result<Option<(u64, Response)>> {
-    let response = client
-        .request(Method::GET, url)
-        .header(RANGE, format!("bytes={}-", start))
-        .send()
-        .await?;
-    match ContentRange::from_response(&response) {
-        Some(ContentRange::Full { size, .. }) => Ok(Some((size, response))),
-        Some(ContentRange::Size(size)) => {
-            if size == start {
-                Ok(None)
-            } else {
-                Err(anyhow!("Size mismatch."))
-            }
-        }
-        _ => Err(anyhow!("Failed to parse content range from '{}'", url)),
-    }
-}
-
-/// Progress Bar Template
-const PROGRESS_BAR_TEMPLATE: &str =
-    "{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes}";
-
-/// Instatiates a [`ProgressBar`] of `len` elements and style given by [`PROGRESS_BAR_TEMPLATE`]
-/// and pushes it to `multibar`.
-#[inline]
-fn progress_bar(multibar: &MultiProgress, len: u64) -> Result<ProgressBar> {
-    let progress_bar = multibar.add(ProgressBar::new(len));
-    progress_bar.set_style(
-        ProgressStyle::default_bar()
-            .template(PROGRESS_BAR_TEMPLATE)?
-            .progress_chars("#>-"),
-    );
-    Ok(progress_bar)
-}
-
-/// Downloads the file at `url` to `path`. If the file is not empty, we use the size of the file to
-/// determine how many bytes to read from the server. This allows for restarting the download
-/// process after a network or disk failure.
-///
-/// # Note
-///
-/// This function assumes that a single `path` will always be associated to a single `url` so that
-/// restarting downloading makes sense.
-#[inline]
-pub async fn download_file<P>(
-    multibar: &MultiProgress,
-    client: &Client,
-    url: &str,
-    path: P,
-) -> Result<()>
-where
-    P: AsRef<Path>,
-{
-    let path = path.as_ref();
-    let (mut amount_downloaded, file) = open_file(path).await?;
-    let (total_size, mut response) =
-        match send_download_request(client, url, amount_downloaded).await? {
-            Some((total_size, response)) => (total_size, response),
-            _ => return Ok(()),
-        };
-    let mut file = BufWriter::new(file);
-    let progress_bar = progress_bar(multibar, total_size)?;
-    progress_bar.set_message(format!("Downloading {}", url));
-    while let Some(chunk) = response.chunk().await? {
-        file.write_all(&chunk).await?;
-        amount_downloaded = min(amount_downloaded + (chunk.len() as u64), total_size);
-        progress_bar.set_position(amount_downloaded);
-    }
-    file.flush().await?;
-    progress_bar.finish_with_message(format!("Downloaded {} to {}", url, path.display()));
-    Ok(())
-}
+    log::LogOptions,
+    memory::{available_memory, download_buffer_size, ByteSize},
+    notify::Event,
+    progress::{ProgressBars, ProgressSink},
+    registry::{Registry, RemoteFile, REGISTRY_PATH},
+    signal::{cancel_on_interrupt, INTERRUPTED_EXIT_CODE},
+    storage::StorageOptions,
+    throttle::ByteRate,
+    torrent::download_torrent,
+    Result,
+};
+use std::process;
+use tokio::task;
+use tracing::{error, info, warn};
 
 #[test]
 fn print_challenge_urls_paths() {
@@ -231,7 +36,7 @@ fn print_challenge_urls_paths() {
     let paths = challenge_paths(urls.len());
     let output: Vec<(&str, &str)> = urls
         .iter()
-        .copied()
+        .map(String::as_str)
         .zip(paths.iter().map(|s| s.as_str()))
         .collect();
     println!("{:#?}", output);
@@ -245,610 +50,263 @@ fn print_response_urls_paths() {
     let paths = response_paths(urls.len());
     let output: Vec<(&str, &str)> = urls
         .iter()
-        .copied()
+        .map(String::as_str)
         .zip(paths.iter().map(|s| s.as_str()))
         .collect();
     println!("{:#?}", output);
 }
 
-/// Spawns a multi-threaded [`tokio`] runtime and downloads a set of files in parallel.
+/// Command Line Arguments
+#[derive(Parser)]
+#[clap(about)]
+struct Arguments {
+    /// Cap on the total bandwidth of all downloads, like `50MB/s`
+    #[clap(long)]
+    limit_rate: Option<ByteRate>,
+
+    /// Cap on the bandwidth of each download, like `5MB/s`
+    #[clap(long)]
+    limit_rate_per_file: Option<ByteRate>,
+
+    /// Number of times a download is retried after every mirror has failed
+    #[clap(long, default_value_t = RETRIES)]
+    retries: usize,
+
+    /// Seconds allowed to establish a connection
+    #[clap(long, default_value_t = CONNECT_TIMEOUT.as_secs())]
+    connect_timeout: u64,
+
+    /// Seconds without receiving any data after which a transfer is restarted
+    #[clap(long, default_value_t = STALL_TIMEOUT.as_secs())]
+    read_timeout: u64,
+
+    /// Number of connections each file is downloaded over
+    #[clap(long, default_value_t = 1)]
+    segments: usize,
+
+    /// Size of the buffer each download is written to disk through, like `4MiB`. Defaults to a
+    /// share of the available memory
+    #[clap(long)]
+    buffer_size: Option<ByteSize>,
+
+    /// Proxy to download through, like `http://proxy:3128` or `socks5h://proxy:1080`. Defaults
+    /// to the `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables
+    #[clap(long, env = "PPOT_PROXY")]
+    proxy: Option<String>,
+
+    /// Connects directly, ignoring the proxy environment variables
+    #[clap(long, conflicts_with = "proxy")]
+    no_proxy: bool,
+
+    /// Skips checking downloaded files against the MD5 digest published by the server
+    #[clap(long)]
+    skip_md5: bool,
+
+    /// Skips hashing files while they download, leaving them to the `hasher` binary
+    #[clap(long)]
+    skip_hash: bool,
+
+    /// Starts downloading even if the files do not fit in the free disk space
+    #[clap(long)]
+    ignore_disk_space: bool,
+
+    /// Only downloads the files of the rounds which the verification checkpoint has not verified
+    /// yet, see `verify_ppot --incremental`
+    #[clap(long)]
+    incremental: bool,
+
+    /// Storage Options
+    #[clap(flatten)]
+    storage: StorageOptions,
+
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
+
+/// Spawns a multi-threaded [`tokio`] runtime and downloads every `challenge` and `response` file
+/// recorded in the registry in parallel. Run `ppot sync` first to pick up new rounds.
 fn main() -> Result<()> {
+    let arguments = Arguments::parse();
+    arguments.log.init()?;
+    let skip_md5 = arguments.skip_md5;
+    let ignore_disk_space = arguments.ignore_disk_space;
+    let incremental = arguments.incremental;
+    let storage = arguments.storage;
+    std::fs::create_dir_all(&storage.dir)?;
+    storage.create_dirs()?;
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+    let options = DownloadOptions {
+        retries: arguments.retries,
+        connect_timeout: Duration::from_secs(arguments.connect_timeout),
+        read_timeout: Duration::from_secs(arguments.read_timeout),
+        segments: arguments.segments,
+        proxy: arguments.proxy,
+        no_proxy: arguments.no_proxy,
+        hash: !arguments.skip_hash,
+        hash_dir: Some(storage.state_dir()),
+        cancel: cancel_on_interrupt()?,
+        http: config.http.clone(),
+        buffer_size: arguments.buffer_size.map_or_else(
+            || download_buffer_size(available_memory()),
+            |size| size.0 as usize,
+        ),
+        ..DownloadOptions::with_rate_limits(arguments.limit_rate, arguments.limit_rate_per_file)
+    };
     tokio::runtime::Builder::new_multi_thread()
         .worker_threads(10)
         .enable_io()
         .enable_time()
         .build()?
         .block_on(async {
-            let multibar = MultiProgress::new();
-            let client = Client::new();
+            let progress = ProgressBars::default();
+            let client = options.client()?;
+            let notifier = config.notifier();
+            let db = StateDb::open_in(&storage)?;
+            let mut registry = Registry::load_or_builtin(storage.state_path(REGISTRY_PATH))?;
+            info!(
+                "Found {} challenge files and {} response files",
+                registry.challenges.len(),
+                registry.responses.len()
+            );
+            if incremental {
+                // The verifier starts from the challenge file of the next round and only needs the
+                // files which come after it.
+                if let Some(checkpoint) = Checkpoint::load(storage.state_path(CHECKPOINT_PATH))? {
+                    let verified = checkpoint.next_round;
+                    registry
+                        .challenges
+                        .drain(..verified.min(registry.challenges.len()));
+                    registry
+                        .responses
+                        .drain(..verified.min(registry.responses.len()));
+                    info!(
+                        "Skipping the {} verified rounds, {} challenge files and {} response files left",
+                        verified - 1,
+                        registry.challenges.len(),
+                        registry.responses.len()
+                    );
+                } else {
+                    warn!("No verification checkpoint, downloading every file");
+                }
+            }
+            let mut files = vec![];
+            for file in registry.challenges.into_iter().chain(registry.responses) {
+                let urls = file
+                    .urls()
+                    .map(|url| config.resolve_url(url))
+                    .collect::<Result<Vec<_>>>()?;
+                let mut exists = false;
+                let mut size = file.size;
+                for url in &urls {
+                    if let Ok(info) = file_exists(&client, url).await {
+                        if info.exists {
+                            exists = true;
+                            size = size.or(info.size);
+                            break;
+                        }
+                    }
+                }
+                files.push((file, urls, exists, size));
+            }
+            let estimate = estimate(
+                &storage.dir,
+                files
+                    .iter()
+                    .map(|(file, _, _, size)| (storage.path(&file.path), *size)),
+            )?;
+            info!("Disk space: {}", estimate);
+            if !estimate.fits() {
+                if ignore_disk_space {
+                    warn!("The files do not fit in the free disk space");
+                } else {
+                    bail!(
+                        "Not enough disk space: {}. Free some space or pass --ignore-disk-space.",
+                        estimate
+                    );
+                }
+            }
             let mut handles = vec![];
-            for (url, path) in [
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_initial",
-                    "challenge_0000",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0002_kobi",
-                    "challenge_0001",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0003",
-                    "challenge_0002",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0004",
-                    "challenge_0003",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0005",
-                    "challenge_0004",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0006",
-                    "challenge_0005",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0007",
-                    "challenge_0006",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0008",
-                    "challenge_0007",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0009",
-                    "challenge_0008",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0010",
-                    "challenge_0009",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0011",
-                    "challenge_0010",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0012",
-                    "challenge_0011",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0013",
-                    "challenge_0012",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0014",
-                    "challenge_0013",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0015",
-                    "challenge_0014",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0016",
-                    "challenge_0015",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0017",
-                    "challenge_0016",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0018",
-                    "challenge_0017",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0019",
-                    "challenge_0018",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0020",
-                    "challenge_0019",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0021",
-                    "challenge_0020",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0022",
-                    "challenge_0021",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0023",
-                    "challenge_0022",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0024",
-                    "challenge_0023",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0025",
-                    "challenge_0024",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0026",
-                    "challenge_0025",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0027",
-                    "challenge_0026",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0028",
-                    "challenge_0027",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0029",
-                    "challenge_0028",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0030",
-                    "challenge_0029",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0031",
-                    "challenge_0030",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0032",
-                    "challenge_0031",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0033",
-                    "challenge_0032",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0034",
-                    "challenge_0033",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0035",
-                    "challenge_0034",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0036",
-                    "challenge_0035",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0037",
-                    "challenge_0036",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0038",
-                    "challenge_0037",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0039",
-                    "challenge_0038",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0040",
-                    "challenge_0039",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0041",
-                    "challenge_0040",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0042",
-                    "challenge_0041",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0043",
-                    "challenge_0042",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0044",
-                    "challenge_0043",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0045",
-                    "challenge_0044",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0046",
-                    "challenge_0045",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0047",
-                    "challenge_0046",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0048",
-                    "challenge_0047",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0049",
-                    "challenge_0048",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0050",
-                    "challenge_0049",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0051",
-                    "challenge_0050",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0052",
-                    "challenge_0051",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0053",
-                    "challenge_0052",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0054",
-                    "challenge_0053",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0055",
-                    "challenge_0054",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0056",
-                    "challenge_0055",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0057",
-                    "challenge_0056",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0058",
-                    "challenge_0057",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0059",
-                    "challenge_0058",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0060",
-                    "challenge_0059",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0061",
-                    "challenge_0060",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0062",
-                    "challenge_0061",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0063",
-                    "challenge_0062",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0064",
-                    "challenge_0063",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0065",
-                    "challenge_0064",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0066",
-                    "challenge_0065",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0067",
-                    "challenge_0066",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0068",
-                    "challenge_0067",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0069",
-                    "challenge_0068",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0070",
-                    "challenge_0069",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0071",
-                    "challenge_0070",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/challenge_0072",
-                    "challenge_0071",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0001_weijie",
-                    "response_0001",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0002_kobi",
-                    "response_0002",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0003_poma",
-                    "response_0003",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0004_pepesha",
-                    "response_0004",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0005_amrullah",
-                    "response_0005",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0006_zac",
-                    "response_0006",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0007_youssef",
-                    "response_0007",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0008_mike",
-                    "response_0008",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0009_brecht",
-                    "response_0009",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0010_vano",
-                    "response_0010",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0011_zhiniang",
-                    "response_0011",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0012_daniel",
-                    "response_0012",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0013_kevin",
-                    "response_0013",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0014_weijie",
-                    "response_0014",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0015_anon0",
-                    "response_0015",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0016_aurel",
-                    "response_0016",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0017_philip",
-                    "response_0017",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0018_cody",
-                    "response_0018",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0019_petr",
-                    "response_0019",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0020_edu",
-                    "response_0020",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0021_rf",
-                    "response_0021",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0022_roman",
-                    "response_0022",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0023_shomari",
-                    "response_0023",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0024_vb",
-                    "response_0024",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0025_stefan",
-                    "response_0025",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0026_geoff",
-                    "response_0026",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0027_alex",
-                    "response_0027",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0028_dimitris",
-                    "response_0028",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0029_gustavo",
-                    "response_0029",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0030_anant",
-                    "response_0030",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0031_golem",
-                    "response_0031",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0032_josephc",
-                    "response_0032",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0033_oskar",
-                    "response_0033",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0034_igor",
-                    "response_0034",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0035_leonard",
-                    "response_0035",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0036_stefaan",
-                    "response_0036",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0037_chihcheng",
-                    "response_0037",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0038_james",
-                    "response_0038",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0039_wanseob",
-                    "response_0039",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0040_weitang",
-                    "response_0040",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0041_evan",
-                    "response_0041",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0042_vaibhav",
-                    "response_0042",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0043_albert",
-                    "response_0043",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0044_yingtong",
-                    "response_0044",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0045_ben",
-                    "response_0045",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0046_tkorwin",
-                    "response_0046",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0047_saravanan",
-                    "response_0047",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0048_tyler",
-                    "response_0048",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0049_jordi",
-                    "response_0049",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0050_weijie",
-                    "response_0050",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0051_joe",
-                    "response_0051",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0052_zaki",
-                    "response_0052",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0053_juan",
-                    "response_0053",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0054_jarrad",
-                    "response_0054",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0055_tyler",
-                    "response_0055",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0056_auryn",
-                    "response_0056",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0057_gisli",
-                    "response_0057",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0058_rasikh",
-                    "response_0058",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0059_pau",
-                    "response_0059",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0060_weijie",
-                    "response_0060",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0061_adria",
-                    "response_0061",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0062_lev",
-                    "response_0062",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0063_david",
-                    "response_0063",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0064_ian",
-                    "response_0064",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0065_adrian",
-                    "response_0065",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0066_kieran",
-                    "response_0066",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0067_nick",
-                    "response_0067",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0068_elena",
-                    "response_0068",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0069_justice",
-                    "response_0069",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0070_bertrand",
-                    "response_0070",
-                ),
-                (
-                    "https://ppot.blob.core.windows.net/public/response_0071_edward",
-                    "response_0071",
-                ),
-            ] {
-                if file_exists(&client, url).await? {
-                    let multibar = multibar.clone();
+            let mut failures = vec![];
+            for (file, urls, exists, _) in files {
+                if exists || file.torrent.is_some() {
+                    let progress = progress.clone();
                     let client = client.clone();
-                    handles.push(task::spawn(async move {
-                        download_file(&multibar, &client, url, path).await
-                    }));
+                    let options = options.clone();
+                    let RemoteFile {
+                        path,
+                        torrent,
+                        content_md5,
+                        ..
+                    } = file;
+                    let local_path = storage.path(&path);
+                    handles.push((
+                        path.clone(),
+                        task::spawn(async move {
+                            let result = if exists {
+                                download_file(&progress, &client, &urls, &local_path, &options)
+                                    .await
+                            } else {
+                                Err(anyhow!("No HTTP mirror serves {}", path))
+                            };
+                            match (result, torrent) {
+                                (Err(err), Some(torrent)) => {
+                                    progress.on_warning(&format!(
+                                        "{}. Falling back to BitTorrent.",
+                                        err
+                                    ));
+                                    download_torrent(progress.multibar(), &torrent, &local_path)
+                                        .await?
+                                }
+                                (result, _) => result?,
+                            }
+                            if skip_md5 {
+                                return Ok(());
+                            }
+                            let content_md5 = match content_md5 {
+                                Some(content_md5) => Some(content_md5),
+                                _ if exists => fetch_content_md5(&client, &urls[0]).await?,
+                                _ => None,
+                            };
+                            match content_md5 {
+                                Some(content_md5) => {
+                                    progress
+                                        .multibar()
+                                        .suspend(|| info!("Checking MD5 of {}", path));
+                                    verify_content_md5(&local_path, &content_md5).await
+                                }
+                                _ => Ok(()),
+                            }
+                        }),
+                    ));
                 } else {
-                    multibar.println(format!("ERROR: The file at '{}' does not exist", url))?;
+                    error!("The file at '{}' does not exist on any mirror", file.url);
+                    failures.push((file.path, "not found on any mirror".to_string()));
+                }
+            }
+            let (paths, handles): (Vec<_>, Vec<_>) = handles.into_iter().unzip();
+            for (path, result) in paths.into_iter().zip(join_all(handles).await) {
+                match result {
+                    Ok(Ok(())) => {
+                        let size = std::fs::metadata(storage.path(&path))?.len();
+                        db.record_download(&path, size)?;
+                        notifier.notify(&Event::DownloadComplete { path }).await
+                    }
+                    Ok(Err(err)) => failures.push((path, err.to_string())),
+                    Err(err) => failures.push((path, err.to_string())),
                 }
             }
-            for result in try_join_all(handles).await? {
-                result?;
+            if failures.is_empty() {
+                return Ok(());
+            }
+            if options.cancel.is_cancelled() {
+                warn!("Interrupted, run the downloader again to resume.");
+                process::exit(INTERRUPTED_EXIT_CODE);
+            }
+            error!("Failed to download {} files:", failures.len());
+            for (path, reason) in &failures {
+                error!("\t{}: {}", path, reason);
             }
-            Ok(())
+            bail!("{} downloads failed", failures.len())
         })
 }
@@ -1,122 +1,218 @@
-// use memmap::MmapOptions;
-use ppot_verifier::{challenge_paths, response_paths};
-use std::fs::OpenOptions;
-use std::io::Read;
-
-const NUM_ROUNDS: usize = 70; // TODO: Change to 71
-
-fn main() {
-    let challenge_files = challenge_paths(NUM_ROUNDS);
-    let response_files = response_paths(NUM_ROUNDS);
-
-    for (challenge, response) in challenge_files.iter().zip(response_files.iter()) {
-        // Read computed hash of challenge file:
-        let mut hash_path = challenge.clone().to_owned();
-        hash_path.push_str("_hash");
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(hash_path)
-            .expect("unable to open file in this directory");
-        let mut computed_hash = [0u8; 64];
-        let _ = file.read(&mut computed_hash[..]).unwrap();
-        // Read asserted hash from reponse file
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(response)
-            .expect("unable to open file in this directory");
-        let mut asserted_hash = [0u8; 64];
-        let _ = file.read(&mut asserted_hash[..]).unwrap();
-
-        if computed_hash != asserted_hash {
-            println!("Hashes don't match for {:?} and {:?}", challenge, response);
-            println!("Computed hash");
-            for line in computed_hash.chunks(16) {
-                print!("\t");
-                for section in line.chunks(4) {
-                    for b in section {
-                        print!("{:02x}", b);
-                    }
-                    print!(" ");
-                }
-            }
-            println!(" ");
-            println!("Asserted hash:");
-            for line in asserted_hash.chunks(16) {
-                print!("\t");
-                for section in line.chunks(4) {
-                    for b in section {
-                        print!("{:02x}", b);
-                    }
-                    print!(" ");
-                }
-            }
-        } else {
-            // println!("The hash of {:?} is", challenge);
-            // for line in computed_hash.chunks(16) {
-            //     print!("\t");
-            //     for section in line.chunks(4) {
-            //         for b in section {
-            //             print!("{:02x}", b);
-            //         }
-            //         print!(" ");
-            //     }
-            // }
-            // println!(" ");
+use clap::Parser;
+use ppot_verifier::{
+    config::{Config, CONFIG_PATH},
+    db::{ChainStatus as Status, StateDb},
+    download::DownloadOptions,
+    hash::Hash64,
+    log::LogOptions,
+    notify::Event,
+    quarantine::redownload,
+    registry::{Registry, REGISTRY_PATH},
+    storage::StorageOptions,
+    HashAlgorithm,
+};
+use serde::Serialize;
+use std::{
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+use tracing::{error, warn};
+
+/// Command Line Arguments
+#[derive(Parser)]
+struct Arguments {
+    /// Prints a JSON summary of every checked pair instead of only the mismatches
+    #[clap(long)]
+    json: bool,
+
+    /// Moves the files whose hash does not match to the quarantine directory and downloads them
+    /// again, hashing them on the way
+    #[clap(long)]
+    redownload: bool,
+
+    /// Storage Options
+    #[clap(flatten)]
+    storage: StorageOptions,
+
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
+
+/// Checked Pair
+#[derive(Clone, Debug, Serialize)]
+struct Pair {
+    /// File whose hash was computed
+    hashed: PathBuf,
+
+    /// File whose header asserts the hash of `hashed`
+    asserted_by: PathBuf,
+
+    /// Result of the comparison
+    status: Status,
+
+    /// Hash computed by the `hasher` binary
+    computed: Option<Hash64>,
+
+    /// Hash found in the header of `asserted_by`
+    asserted: Option<Hash64>,
+}
+
+impl Pair {
+    /// Compares the computed hash of `hashed`, recorded in `db` or saved to the state directory of
+    /// `storage`, with the hash in the header of `asserted_by`.
+    #[inline]
+    fn check(storage: &StorageOptions, db: &StateDb, hashed: &Path, asserted_by: &Path) -> Self {
+        let name = hashed.file_name().unwrap_or_default().to_string_lossy();
+        let computed = match db.hash(&name, HashAlgorithm::Blake2b) {
+            Ok(Some(hash)) => Hash64::try_from(hash.as_slice()).ok(),
+            _ => Hash64::load(storage.hash_path(hashed, HashAlgorithm::Blake2b)).ok(),
+        };
+        let asserted = Hash64::read_header(asserted_by).ok();
+        let status = match (computed, asserted) {
+            (Some(computed), Some(asserted)) if computed == asserted => Status::Match,
+            (Some(_), Some(_)) => Status::Mismatch,
+            _ => Status::Missing,
+        };
+        Self {
+            hashed: hashed.into(),
+            asserted_by: asserted_by.into(),
+            status,
+            computed,
+            asserted,
+        }
+    }
+}
+
+/// Summary
+#[derive(Clone, Debug, Serialize)]
+struct Summary {
+    /// Number of pairs whose hashes match
+    matched: usize,
+
+    /// Number of pairs whose hashes differ
+    mismatched: usize,
+
+    /// Number of pairs which could not be checked
+    missing: usize,
+
+    /// Every checked pair, in ceremony order
+    pairs: Vec<Pair>,
+}
+
+impl Summary {
+    /// Builds the summary of `pairs`.
+    #[inline]
+    fn new(pairs: Vec<Pair>) -> Self {
+        let count = |status| pairs.iter().filter(|pair| pair.status == status).count();
+        Self {
+            matched: count(Status::Match),
+            mismatched: count(Status::Mismatch),
+            missing: count(Status::Missing),
+            pairs,
+        }
+    }
+}
+
+/// Checks the hash chain of the ceremony, exiting with status `1` if any hash does not match and
+/// with status `2` if some hashes could not be checked.
+fn main() -> ExitCode {
+    let arguments = Arguments::parse();
+    arguments.log.init().expect("unable to install the logger");
+    let storage = &arguments.storage;
+    let registry = Registry::load_or_builtin(storage.state_path(REGISTRY_PATH))
+        .expect("unable to load the registry");
+    let num_rounds = registry.rounds();
+    let challenge_files = storage.challenge_paths(num_rounds);
+    let response_files = storage.response_paths(num_rounds);
+
+    let db = StateDb::open_in(storage).expect("unable to open the state database");
+
+    let mut pairs = Vec::new();
+    for (i, response) in response_files.iter().enumerate() {
+        // Check the hash of the challenge file asserted by the response file
+        pairs.push(Pair::check(storage, &db, &challenge_files[i], response));
+        // Check the hash of the response file asserted by the next challenge file
+        pairs.push(Pair::check(storage, &db, response, &challenge_files[i + 1]));
+    }
+    for pair in &pairs {
+        let name = |path: &Path| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned()
+        };
+        db.record_chain(&name(&pair.hashed), &name(&pair.asserted_by), pair.status)
+            .expect("unable to record the hash chain");
+    }
+    let summary = Summary::new(pairs);
+
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))
+        .expect("unable to load the configuration");
+    let notifier = config.notifier();
+    for pair in &summary.pairs {
+        if pair.status == Status::Mismatch {
+            notifier.notify_blocking(&Event::HashMismatch {
+                hashed: pair.hashed.display().to_string(),
+                asserted_by: pair.asserted_by.display().to_string(),
+            });
         }
     }
-    // Check hashes of response files
-    for (challenge, response) in challenge_files.iter().skip(1).zip(response_files.iter()) {
-        // Read computed hash of response file:
-        let mut hash_path = response.clone().to_owned();
-        hash_path.push_str("_hash");
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(hash_path)
-            .expect("unable to open file in this directory");
-        let mut computed_hash = [0u8; 64];
-        let _ = file.read(&mut computed_hash[..]).unwrap();
-        // Read asserted hash from challenge file
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(challenge)
-            .expect("unable to open file in this directory");
-        let mut asserted_hash = [0u8; 64];
-        let _ = file.read(&mut asserted_hash[..]).unwrap();
-        if computed_hash != asserted_hash {
-            println!("Hashes don't match for {:?} and {:?}", challenge, response);
-            println!("Computed hash");
-            for line in computed_hash.chunks(16) {
-                print!("\t");
-                for section in line.chunks(4) {
-                    for b in section {
-                        print!("{:02x}", b);
-                    }
-                    print!(" ");
+
+    if arguments.redownload {
+        let options = DownloadOptions {
+            hash: true,
+            hash_dir: Some(storage.state_dir()),
+            http: config.http.clone(),
+            ..Default::default()
+        };
+        for pair in &summary.pairs {
+            if pair.status == Status::Mismatch {
+                if let Err(err) =
+                    redownload(storage, &config, &db, &registry, &pair.hashed, &options)
+                {
+                    error!("Unable to download {:?} again: {}", pair.hashed, err);
                 }
             }
-            println!(" ");
-            println!("Asserted hash:");
-            for line in asserted_hash.chunks(16) {
-                print!("\t");
-                for section in line.chunks(4) {
-                    for b in section {
-                        print!("{:02x}", b);
-                    }
-                    print!(" ");
+        }
+    }
+
+    if arguments.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&summary).expect("the summary is always serializable")
+        );
+    } else {
+        for pair in &summary.pairs {
+            match pair.status {
+                Status::Match => {}
+                Status::Mismatch => {
+                    println!(
+                        "Hashes don't match for {:?} and {:?}",
+                        pair.hashed, pair.asserted_by
+                    );
+                    println!("Computed hash");
+                    println!("{}", pair.computed.unwrap_or_default().pretty());
+                    println!("Asserted hash:");
+                    println!("{}", pair.asserted.unwrap_or_default().pretty());
                 }
+                Status::Missing => warn!(
+                    "Unable to check {:?} against {:?}",
+                    pair.hashed, pair.asserted_by
+                ),
             }
-        } else {
-            // println!("The hash of {:?} is", response);
-            // for line in computed_hash.chunks(16) {
-            //     print!("\t");
-            //     for section in line.chunks(4) {
-            //         for b in section {
-            //             print!("{:02x}", b);
-            //         }
-            //         print!(" ");
-            //     }
-            // }
-            // println!(" ");
         }
+        println!(
+            "{} matched, {} mismatched, {} missing",
+            summary.matched, summary.mismatched, summary.missing
+        );
+    }
+
+    if summary.mismatched > 0 {
+        ExitCode::from(1)
+    } else if summary.missing > 0 {
+        ExitCode::from(2)
+    } else {
+        ExitCode::SUCCESS
     }
 }
@@ -1,10 +1,16 @@
 use memmap::MmapOptions;
-use ppot_verifier::calculate_hash;
+use ppot_verifier::{
+    calculate_hashes,
+    hash::Hash64,
+    hash_path, hash_progress_bar, into_array_unchecked,
+    memory::{available_memory, hash_chunk_size},
+    HashAlgorithm,
+};
 use std::fs::OpenOptions; // TODO: Is standard okay?
-use std::io::{Read, Write};
+use std::path::Path;
 
 fn main() {
-    let path = "challenge_0011";
+    let path = Path::new("challenge_0011");
     let reader = OpenOptions::new()
         .read(true)
         .open(path)
@@ -15,48 +21,29 @@ fn main() {
             .map(&reader)
             .expect("unable to create a memory map for input")
     };
-    let hash = calculate_hash(&challenge);
+    let progress_bar = hash_progress_bar(path, challenge.len() as u64)
+        .expect("the progress bar template is valid");
+    let hash = Hash64(into_array_unchecked(
+        calculate_hashes(
+            &challenge,
+            &[HashAlgorithm::Blake2b],
+            Some(&progress_bar),
+            None,
+            hash_chunk_size(available_memory()),
+        )
+        .expect("hashing without a cancellation token always completes")
+        .remove(0),
+    ));
+    progress_bar.finish_and_clear();
     println!("The hash of {:?} is ", path);
-    for line in hash.chunks(16) {
-        print!("\t");
-        for section in line.chunks(4) {
-            for b in section {
-                print!("{:02x}", b);
-            }
-            print!(" ");
-        }
-    }
-    // make writer (open file in write mode)
-    // writer.write_all()
-    // see `std::io` traits
-    let mut hash_path = path.to_owned();
-    hash_path.push_str("_hash");
-    let mut file = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(hash_path.clone())
+    println!("{}", hash.pretty());
+    hash.save(hash_path(path))
         .expect("unable to open file in this directory");
-    file.write_all(&hash).unwrap();
-    drop(file);
 
     // Check that it worked
     println!("Opening hash file");
-    let mut file = OpenOptions::new()
-        .read(true)
-        .open(hash_path)
-        .expect("unable to open file in this directory");
-    let mut contents = [0u8; 64];
-    let bytes_read = file.read(&mut contents[..]).unwrap();
+    let contents = Hash64::load(hash_path(path)).expect("unable to open file in this directory");
     println!("The contents of the file are");
-    for line in contents.chunks(16) {
-        print!("\t");
-        for section in line.chunks(4) {
-            for b in section {
-                print!("{:02x}", b);
-            }
-            print!(" ");
-        }
-    }
-    assert_eq!(bytes_read, 64);
+    println!("{}", contents.pretty());
+    assert_eq!(contents, hash);
 }
@@ -1,89 +1,315 @@
-use memmap::MmapOptions;
-use ppot_verifier::{calculate_hash, challenge_paths, response_paths};
-use std::fs::{File, OpenOptions};
-use std::io::Write;
-use std::time::Instant;
+use clap::Parser;
+use indicatif::{HumanDuration, MultiProgress};
+use ppot_verifier::{
+    atomic,
+    cache::{HashCache, HASH_CACHE_PATH},
+    ceremony::ceremony_of,
+    db::StateDb,
+    digests::{PrefixDigests, DIGEST_LOG_POWERS},
+    format::Layout,
+    hash::Hash64,
+    hash_progress_bar,
+    input::InputOptions,
+    lock::FileLock,
+    log::LogOptions,
+    registry::{Registry, REGISTRY_PATH},
+    signal::{cancel_on_interrupt, INTERRUPTED_EXIT_CODE},
+    storage::StorageOptions,
+    HashAlgorithm, Result,
+};
+use rayon::{prelude::*, ThreadPoolBuilder};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
 
-const NUM_ROUNDS: usize = 72;
+/// Command Line Arguments
+#[derive(Parser)]
+#[clap(about)]
+struct Arguments {
+    /// Hash algorithms computed in the same pass over each file, only Blake2b hashes can be
+    /// checked against the ceremony
+    #[clap(long = "algorithm", value_enum, default_value = "blake2b")]
+    algorithms: Vec<HashAlgorithm>,
 
+    /// Computes the digests of the first 2^10, 2^11, ... powers of every challenge file in the same
+    /// pass, the hashes of the files holding only these powers, reading the challenge files hashed
+    /// before once more if their digests are missing
+    #[clap(long)]
+    digests: bool,
+
+    /// Number of files hashed at the same time
+    #[clap(long, default_value_t = 1)]
+    jobs: usize,
+
+    /// Input Options
+    #[clap(flatten)]
+    input: InputOptions,
+
+    /// Storage Options
+    #[clap(flatten)]
+    storage: StorageOptions,
+
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
+
+/// Hashes every `challenge` and `response` file recorded in the registry and saves the hashes to
+/// the state directory. Hashes are cached in [`HASH_CACHE_PATH`] together with the size and
+/// modification time of the file, so only the files which changed since they were last hashed are
+/// hashed again. On Ctrl-C, the files being hashed are abandoned after their current chunk and the
+/// hashes of the files already done are kept.
 fn main() {
-    let challenge_files = challenge_paths(NUM_ROUNDS);
-    let response_files = response_paths(NUM_ROUNDS);
-
-    for path in response_files.iter() {
-        // Saves hash to `response_xxxx_hash`
-        let mut hash_path = path.to_owned();
-        hash_path.push_str("_hash");
-        match OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&hash_path)
-        {
-            Ok(mut file) => {
-                let now = Instant::now();
-                hash_to(&mut file, path).unwrap();
-                println!("File {:?} has been hashed in \n {:?}", path, now.elapsed());
+    let arguments = Arguments::parse();
+    arguments.log.init().expect("unable to install the logger");
+    let storage = &arguments.storage;
+    storage
+        .create_dirs()
+        .expect("unable to create the state directories");
+    let registry = Registry::load_or_builtin(storage.state_path(REGISTRY_PATH))
+        .expect("unable to load the registry");
+    let num_rounds = registry.rounds();
+    let layout = ceremony_of(&registry, false, None).challenge_layout();
+    let challenge_files = storage.challenge_paths(num_rounds);
+    let response_files = storage.response_paths(num_rounds);
+    let cache_path = storage.cache_path(HASH_CACHE_PATH);
+    let _cache_lock = FileLock::exclusive(&cache_path).expect("unable to lock the hash cache");
+    let mut cache = HashCache::load_or_default(&cache_path).expect("unable to load the hash cache");
+
+    let db = StateDb::open_in(storage).expect("unable to open the state database");
+
+    let mut pending = Vec::new();
+    for path in response_files.iter().chain(challenge_files.iter()) {
+        if !path.exists() {
+            warn!("File {:?} is missing", path);
+            continue;
+        }
+        // Saves hashes to `response_xxxx_hash`, `challenge_xxxx_sha256`, ...
+        let mut stale = Vec::new();
+        for algorithm in arguments.algorithms.iter().copied() {
+            let hash_path = storage.hash_path(path, algorithm);
+            let cached = match cache.get(path, algorithm).unwrap() {
+                Some(hash) => Some(hash),
+                _ => cache.adopt(path, &hash_path, algorithm).unwrap(),
+            };
+            match cached {
+                Some(hash) => {
+                    if fs::read(&hash_path).ok().as_ref() != Some(&hash) {
+                        atomic::write(hash_path, &hash).unwrap();
+                    }
+                    // Fill the database with the hashes computed before it existed
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    if db.hash(&name, algorithm).unwrap().as_ref() != Some(&hash) {
+                        db.record_hash(&name, algorithm, &hash).unwrap();
+                    }
+                }
+                _ => stale.push(algorithm),
             }
-            // std::io::ErrorKind(AlreadyExists) => { todo!() },
-            _ => println!("File {:?} has been hashed", path),
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let digests = (arguments.digests
+            && challenge_files.contains(path)
+            && db.digests(&name).unwrap().is_empty())
+        .then_some(layout);
+        if stale.is_empty() && digests.is_none() {
+            info!("File {:?} has already been hashed", path);
+        } else {
+            pending.push(Pending {
+                path: path.clone(),
+                algorithms: stale,
+                digests,
+            });
         }
     }
+    let cancel = cancel_on_interrupt().expect("unable to install the interrupt handler");
+    hash_all(
+        &pending,
+        arguments.jobs,
+        &Mutex::new(cache),
+        &Mutex::new(db),
+        storage,
+        &arguments.input,
+        &cancel,
+    );
+    if cancel.is_cancelled() {
+        process::exit(INTERRUPTED_EXIT_CODE);
+    }
+}
 
-    for path in challenge_files.iter() {
-        // Saves hash to `challenge_xxxx_hash`
-        let mut hash_path = path.to_owned();
-        hash_path.push_str("_hash");
-        match OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .open(&hash_path)
-        {
-            Ok(mut file) => {
-                let now = Instant::now();
-                hash_to(&mut file, path).unwrap();
-                println!("File {:?} has been hashed in \n {:?}", path, now.elapsed());
+/// File to Hash
+struct Pending {
+    /// Path of the file
+    path: PathBuf,
+
+    /// Hash algorithms whose hash is missing
+    algorithms: Vec<HashAlgorithm>,
+
+    /// Layout of the challenge file, if its digests are missing
+    digests: Option<Layout>,
+}
+
+/// Hashes and Digests of a File
+type Hashes = (Vec<Vec<u8>>, BTreeMap<u32, Hash64>);
+
+/// Hashes the `pending` file, opened with `input`, with each of its algorithms and computes its
+/// digests in a single pass, showing the progress in a new bar of `multibar`. Returns `None` if
+/// `cancel` is cancelled before the end of the file.
+fn hash(
+    multibar: &MultiProgress,
+    pending: &Pending,
+    input: &InputOptions,
+    cancel: &CancellationToken,
+) -> Result<Option<Hashes>> {
+    let path = pending.path.as_path();
+    // Keep the file from being written while it is hashed
+    let _lock = FileLock::shared(path)?;
+    // Memory map `path`, or stream it if it cannot be mapped
+    let reader = input.open(path)?;
+    let mut digests = match pending.digests {
+        Some(layout) if reader.len() == layout.file_size() as u64 => {
+            Some(PrefixDigests::new(&layout, DIGEST_LOG_POWERS))
+        }
+        Some(layout) => {
+            warn!(
+                "File {:?} does not hold 2^{} powers, not computing its digests",
+                path,
+                layout.powers.trailing_zeros()
+            );
+            None
+        }
+        _ => None,
+    };
+    let progress_bar = multibar.add(hash_progress_bar(path, reader.len())?);
+    let hashes = reader.hashes_with(
+        &pending.algorithms,
+        Some(&progress_bar),
+        Some(cancel),
+        |offset, chunk| {
+            if let Some(digests) = &mut digests {
+                digests.update(offset, chunk);
             }
-            // std::io::ErrorKind(AlreadyExists) => { todo!() },
-            _ => println!("File {:?} has already been hashed", path),
+        },
+    )?;
+    let hashes = match hashes {
+        Some(hashes) => hashes,
+        _ => {
+            progress_bar.abandon_with_message(format!("Interrupted hashing {}", path.display()));
+            return Ok(None);
         }
-    }
+    };
+    progress_bar.finish_with_message(format!(
+        "Hashed {} in {}",
+        path.display(),
+        HumanDuration(progress_bar.elapsed())
+    ));
+    Ok(Some((
+        hashes,
+        digests.map(PrefixDigests::finalize).unwrap_or_default(),
+    )))
 }
 
-/// Hashes the file at `path` and saves the hash to `file`.
-fn hash_to(file: &mut File, path: &str) -> Result<(), std::io::Error> {
-    // Make memory map from `path`
-    let reader = OpenOptions::new()
-        .read(true)
-        .open(path)
-        .expect("unable open file in this directory");
-    // Make a memory map
-    let reader = unsafe {
-        MmapOptions::new()
-            .map(&reader)
-            .expect("unable to create a memory map for input")
-    };
-    let hash = calculate_hash(&reader);
-    file.write_all(&hash)?;
+/// Records the `digests` of the challenge file at `path` laid out as `layout` in `db`, if any were
+/// computed, with the digest of all its powers, which is its Blake2b hash.
+fn save_digests(
+    db: &StateDb,
+    path: &Path,
+    layout: &Layout,
+    digests: BTreeMap<u32, Hash64>,
+) -> Result<()> {
+    if digests.is_empty() {
+        return Ok(());
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    for (log_powers, digest) in &digests {
+        db.record_digest(&name, *log_powers, digest)?;
+    }
+    if let Some(hash) = db.hash(&name, HashAlgorithm::Blake2b)? {
+        db.record_digest(
+            &name,
+            layout.powers.trailing_zeros(),
+            &Hash64::try_from(hash.as_slice())?,
+        )?;
+    }
     Ok(())
 }
 
-/// Computes Blake2 hash of all files specified by a list
-/// of paths, returning all hashes.
-fn _hash_all(files: Vec<String>) -> Vec<[u8; 64]> {
-    let mut hashes = vec![[0u8; 64]; files.len()];
-    // TODO: This can be parallelized
-    for (i, file) in files.iter().enumerate() {
-        let reader = OpenOptions::new()
-            .read(true)
-            .open(file)
-            .expect("unable open file in this directory");
-        // Make a memory map
-        let challenge = unsafe {
-            MmapOptions::new()
-                .map(&reader)
-                .expect("unable to create a memory map for input")
-        };
-        hashes[i] = calculate_hash(&challenge);
+/// Saves the `hashes` of the file at `path` to the state directory of `storage` and to `db`,
+/// records them in `cache` and prints them above the progress bars of `multibar`.
+fn save(
+    multibar: &MultiProgress,
+    storage: &StorageOptions,
+    path: &Path,
+    algorithms: &[HashAlgorithm],
+    hashes: Vec<Vec<u8>>,
+    cache: &mut HashCache,
+    db: &StateDb,
+) -> Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    for (algorithm, hash) in algorithms.iter().zip(hashes) {
+        let hash_path = storage.hash_path(path, *algorithm);
+        match algorithm {
+            HashAlgorithm::Blake2b => Hash64::try_from(hash.as_slice())?.save(hash_path)?,
+            _ => atomic::write(hash_path, &hash)?,
+        }
+        cache.insert(path, *algorithm, &hash)?;
+        db.record_hash(&name, *algorithm, &hash)?;
+        multibar.println(format!(
+            "{:?}: {}  {}",
+            algorithm,
+            hex::encode(&hash),
+            path.display()
+        ))?;
     }
-    hashes
+    cache.save(storage.cache_path(HASH_CACHE_PATH))
+}
+
+/// Hashes all the `files` with their algorithms on a pool of `jobs` threads, saving the hashes and
+/// the digests of each file as soon as it is done. Once `cancel` is cancelled, no more files are
+/// started.
+fn hash_all(
+    files: &[Pending],
+    jobs: usize,
+    cache: &Mutex<HashCache>,
+    db: &Mutex<StateDb>,
+    storage: &StorageOptions,
+    input: &InputOptions,
+    cancel: &CancellationToken,
+) {
+    let multibar = MultiProgress::new();
+    ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .expect("unable to build the thread pool")
+        .install(|| {
+            files.par_iter().for_each(|pending| {
+                if cancel.is_cancelled() {
+                    return;
+                }
+                let path = &pending.path;
+                let (hashes, digests) = match hash(&multibar, pending, input, cancel) {
+                    Ok(Some(hashes)) => hashes,
+                    Ok(None) => return,
+                    Err(err) => panic!("unable to hash {}: {}", path.display(), err),
+                };
+                let mut cache = cache.lock().expect("hash cache lock is never poisoned");
+                let db = db.lock().expect("state database lock is never poisoned");
+                save(
+                    &multibar,
+                    storage,
+                    path,
+                    &pending.algorithms,
+                    hashes,
+                    &mut cache,
+                    &db,
+                )
+                .expect("unable to save hashes");
+                if let Some(layout) = &pending.digests {
+                    save_digests(&db, path, layout, digests).expect("unable to save digests");
+                }
+            })
+        });
 }
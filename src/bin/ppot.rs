@@ -0,0 +1,3933 @@
+//! PPoT Verifier Command Line Interface
+
+use anyhow::{anyhow, bail, ensure};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use clap::{Args, Parser, Subcommand};
+use indicatif::{HumanBytes, HumanDuration, MultiProgress};
+use memmap::Mmap;
+use ppot_verifier::{
+    aggregate::{Aggregate, SignedReport},
+    atomic,
+    canonical::CanonicalReport,
+    ceremony::{ceremony_of, Ceremony},
+    claims::{check_claims, known_hash},
+    compress::{compress_file, compressed_path, decompress_file, COMPRESSION_LEVEL, FRAME_SIZE},
+    config::{Config, CONFIG_PATH},
+    contributions::{ContributionList, CONTRIBUTIONS_PATH},
+    coordinator::{
+        coordinate, work_for, worker_key, ChainReport, Coordinator, WorkItem, COORDINATOR_ADDRESS,
+        COORDINATOR_PATH, LEASE_DURATION,
+    },
+    curve::CurveKind,
+    db::{ChainStatus, StateDb},
+    disk,
+    doctor::{
+        check_disk, check_limits, check_memory, check_network, check_write_speed, Severity,
+        WRITE_PROBE_SIZE,
+    },
+    download::{download_file, DownloadOptions},
+    eip4844::{Transcript, TRANSCRIPT_PATH, TRANSCRIPT_URL},
+    export::{
+        arkworks, halo2, lagrange, phase1radix, phase1radix_file, points, PointFormat, Srs,
+        SrsHeader,
+    },
+    fft::Domain,
+    format::{self, Encoding, Layout, Section, CEREMONY_LOG_POWERS, CEREMONY_POWERS},
+    github::{
+        api_token, create_gist, list_attestations, upload_release_assets, AttestationStatus,
+        PublishedFile,
+    },
+    hash::Hash64,
+    hash_file, hash_progress_bar,
+    input::InputOptions,
+    ipfs::{IpfsManifest, IpfsNode, IPFS_API_URL, IPFS_MANIFEST_PATH},
+    kzg::Kzg,
+    locate::locate_corruption,
+    log::LogOptions,
+    manifest::{FileStatus, Manifest, MANIFEST_PATH},
+    memory::{available_memory, verification_size, ByteSize},
+    merkle::{chunks_path, ChunkManifest, CHUNK_SIZE},
+    notify::Event,
+    parity::{
+        parity_path, protect_file, repair_file, RepairReport, PARITY_BLOCK_SIZE, PARITY_MIN_SIZE,
+        PARITY_REDUNDANCY,
+    },
+    pgp::{load_keyring, SignatureStatus},
+    phase2::{MpcParams, Phase1Radix},
+    plan::{probe_bandwidth, round_files, Plan, RoundRange, PROBE_SIZE},
+    pok::{fetch_public_key_bytes, Secret},
+    prefix::{Prefix, RawEncoding},
+    profile::Profile,
+    progress::ProgressBars,
+    provenance::{trace, ProvenanceReport, DEFAULT_SEARCH_POINTS},
+    ptau::{Ptau, PublishedHash, PublishedHashes, PUBLISHED_HASHES_PATH},
+    quarantine::blake2b,
+    queue::{open_queue, work_from},
+    r1cs::R1cs,
+    registry::{Registry, RemoteFile, REGISTRY_PATH},
+    scrub::{due_files, scrub_file, Pacer, ScrubOutcome, SCRUB_INTERVAL, SCRUB_RATE},
+    server::{serve, LISTEN_ADDRESS},
+    sign::{PublicKey, SecretKey, Signature},
+    signal::cancel_on_interrupt,
+    sigstore::{
+        identity_token, Identity, KeylessSignature, DEFAULT_FULCIO_URL, DEFAULT_REKOR_URL,
+        SIGSTORE_EXTENSION,
+    },
+    status::{FileState, Report, Verification},
+    storage::StorageOptions,
+    synthetic::{Fault, MiniCeremony, SYNTHETIC_LOG_POWERS, SYNTHETIC_PARTICIPANTS},
+    throttle::ByteRate,
+    timestamp::{Timestamp, DEFAULT_TSA_URL, TIMESTAMP_EXTENSION},
+    transform::{extract, fetch_header},
+    watch::{Stage, POLL_INTERVAL},
+    zkey::Zkey,
+    HashAlgorithm, Result,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha20Rng;
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, Write},
+    net::SocketAddr,
+    ops::Range,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task;
+use tracing::{error, info, warn};
+
+/// Command Line Arguments
+#[derive(Parser)]
+#[clap(version, about)]
+struct Arguments {
+    /// Command to run
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Storage Options
+    #[clap(flatten)]
+    storage: StorageOptions,
+
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
+
+/// Commands
+#[derive(Subcommand)]
+enum Command {
+    /// Discovers the latest round of the ceremony and updates the local registry.
+    Sync {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Take the list of contributions from the GitHub repository instead of the blob listing
+        #[clap(long)]
+        github: bool,
+
+        /// GitHub API token, defaults to the `GITHUB_TOKEN` environment variable
+        #[clap(long)]
+        token: Option<String>,
+    },
+
+    /// Maintains the contribution list embedded in the crate.
+    Registry {
+        /// Registry command to run
+        #[clap(subcommand)]
+        command: RegistryCommand,
+    },
+
+    /// Prints the files left to download to verify a range of rounds, the disk space and memory
+    /// they need and an estimate of the download time, without downloading them.
+    Plan {
+        /// Rounds to verify, such as `1..71` or `1..=70`, defaults to every round
+        #[clap(long)]
+        rounds: Option<RoundRange>,
+
+        /// Base-two logarithm of the number of powers of tau verified in each round
+        #[clap(long, default_value_t = 19, value_parser = clap::value_parser!(u32).range(1..=28))]
+        powers: u32,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Skips measuring the bandwidth to estimate the download time
+        #[clap(long)]
+        no_probe: bool,
+    },
+
+    /// Checks the disk space, memory, process limits, network and disk speed a verification
+    /// needs, warning about whatever would make a long run fail.
+    Doctor {
+        /// Rounds to verify, such as `1..71` or `1..=70`, defaults to every round
+        #[clap(long)]
+        rounds: Option<RoundRange>,
+
+        /// Base-two logarithm of the number of powers of tau verified in each round
+        #[clap(long, default_value_t = 19, value_parser = clap::value_parser!(u32).range(1..=28))]
+        powers: u32,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Skips the checks which use the network
+        #[clap(long)]
+        offline: bool,
+    },
+
+    /// Prints the download, hash, hash chain and verification state of every round.
+    Status {
+        /// Prints the whole report as JSON
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Compares the hash of every response file with the hashes published by its participant in
+    /// the attestations of the `perpetualpowersoftau` repository, and checks the PGP signatures of
+    /// the attestations.
+    Attestations {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// GitHub API token, defaults to the `GITHUB_TOKEN` environment variable
+        #[clap(long)]
+        token: Option<String>,
+
+        /// File or directory of PGP public keys to check the signatures against, on top of the keys
+        /// published along with the attestations
+        #[clap(long)]
+        keyring: Option<PathBuf>,
+    },
+
+    /// Publishes the verification report or compares it with the report of another verifier.
+    Report {
+        /// Report command to run
+        #[clap(subcommand)]
+        command: ReportCommand,
+    },
+
+    /// Polls for new rounds of the ceremony, then downloads, hashes and verifies them, until
+    /// interrupted.
+    Watch {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Take the list of contributions from the GitHub repository instead of the blob listing
+        #[clap(long)]
+        github: bool,
+
+        /// GitHub API token, defaults to the `GITHUB_TOKEN` environment variable
+        #[clap(long)]
+        token: Option<String>,
+
+        /// Number of seconds between two polls
+        #[clap(long, default_value_t = POLL_INTERVAL.as_secs())]
+        interval: u64,
+    },
+
+    /// Re-hashes the stored files whose hash is recorded, at a limited rate, and alerts on any
+    /// change, until interrupted. An interrupted scrub resumes with the files it did not reach.
+    Scrub {
+        /// Cap on the rate the files are read at, like `50MB/s`
+        #[clap(long, default_value = SCRUB_RATE)]
+        rate: ByteRate,
+
+        /// Number of days between two scrubs of the same file
+        #[clap(long, default_value_t = SCRUB_INTERVAL.as_secs() / (24 * 60 * 60))]
+        days: u64,
+
+        /// Number of seconds between two polls for files due for a scrub
+        #[clap(long, default_value_t = POLL_INTERVAL.as_secs())]
+        interval: u64,
+
+        /// Scrub the files due once and exit, failing if any of them changed
+        #[clap(long)]
+        once: bool,
+
+        /// Repairs the changed files which have parity data instead of only alerting on them
+        #[clap(long)]
+        repair: bool,
+    },
+
+    /// Serves the download, hash and verification status of the ceremony files over HTTP, until
+    /// interrupted.
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = LISTEN_ADDRESS)]
+        listen: SocketAddr,
+    },
+
+    /// Hands out the rounds of a range to `ppot work` processes on other machines, until every
+    /// round has a result, then stitches the hash chain from their results.
+    Coordinate {
+        /// Rounds to verify, such as `1..71` or `1..=70`, defaults to every round
+        #[clap(long)]
+        rounds: Option<RoundRange>,
+
+        /// Base-two logarithm of the number of powers verified in each round, defaults to all of
+        /// them
+        #[clap(long)]
+        log_powers: Option<u32>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Address to listen on
+        #[clap(long, default_value = COORDINATOR_ADDRESS)]
+        listen: SocketAddr,
+
+        /// Key of the coordinator, which the keys the workers sign their results with derive from
+        #[clap(long, env = "PPOT_COORDINATOR_KEY", hide_env_values = true)]
+        key: String,
+
+        /// Number of seconds after which a round without a result is handed out again
+        #[clap(long, default_value_t = LEASE_DURATION.as_secs())]
+        lease: u64,
+    },
+
+    /// Verifies the rounds handed out by a `ppot coordinate` process until it has no work left.
+    Work {
+        /// URL of the coordinator, such as `http://10.0.0.1:8081`
+        coordinator: String,
+
+        /// Name of this worker, recorded with its results
+        #[clap(long)]
+        name: String,
+
+        /// Key of this worker to sign the results, as printed by `ppot worker-key`
+        #[clap(long, env = "PPOT_WORKER_KEY", hide_env_values = true)]
+        key: String,
+    },
+
+    /// Prints the key a worker of a `ppot coordinate` process signs its results with, derived
+    /// from the key of the coordinator and the name of the worker.
+    WorkerKey {
+        /// Name of the worker
+        name: String,
+
+        /// Key of the coordinator
+        #[clap(long, env = "PPOT_COORDINATOR_KEY", hide_env_values = true)]
+        key: String,
+    },
+
+    /// Pushes rounds into a shared work queue, verifies them or reports on their results.
+    Queue {
+        /// Queue command to run
+        #[clap(subcommand)]
+        command: QueueCommand,
+    },
+
+    /// Generates or checks the manifest of expected sizes and hashes.
+    Manifest {
+        /// Manifest command to run
+        #[clap(subcommand)]
+        command: ManifestCommand,
+    },
+
+    /// Generates or checks the per-file manifests of chunk hashes.
+    Chunks {
+        /// Chunk manifest command to run
+        #[clap(subcommand)]
+        command: ChunksCommand,
+    },
+
+    /// Generates Reed-Solomon parity data for the large files or repairs files with it.
+    Parity {
+        /// Parity command to run
+        #[clap(subcommand)]
+        command: ParityCommand,
+    },
+
+    /// Stores the ceremony files compressed with zstd in the seekable format, or restores them.
+    Zstd {
+        /// Compression command to run
+        #[clap(subcommand)]
+        command: ZstdCommand,
+    },
+
+    /// Locates the byte ranges where a local file differs from its remote copy.
+    Locate {
+        /// Local file to check
+        path: String,
+
+        /// URL of the remote copy, defaults to the URL recorded in the registry for `path`
+        #[clap(long)]
+        url: Option<String>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Offset of the first byte to check
+        #[clap(long)]
+        start: Option<u64>,
+
+        /// Offset past the last byte to check, defaults to the end of the file
+        #[clap(long)]
+        end: Option<u64>,
+
+        /// Overwrites the divergent ranges with the remote bytes
+        #[clap(long)]
+        repair: bool,
+    },
+
+    /// Reconstructs the challenge file produced by a round from its response file and checks it
+    /// against the hash asserted by the next response file, instead of downloading it.
+    Challenge {
+        /// Round whose response file is decompressed
+        round: usize,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Checks the proofs of knowledge of tau, alpha and beta in the response file of a round,
+    /// without the rest of the verification. The key is fetched from the registry URL if the
+    /// response file is not on disk.
+    Contribution {
+        /// Round whose contribution is checked
+        round: usize,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Verifies the transform of a round, over the curve of the registry, by checking its
+    /// response file against its challenge file on their first 2^N powers, without the
+    /// subaccumulators of `verify_ppot`.
+    Verify {
+        /// Round whose transform is verified
+        round: usize,
+
+        /// Base-two logarithm of the number of powers of tau to verify, defaults to every power
+        #[clap(long)]
+        log_powers: Option<u32>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Writes the first 2^N powers of a challenge file to a new, smaller challenge file with the
+    /// same header.
+    Extract {
+        /// Path to the challenge file
+        path: PathBuf,
+
+        /// Base-two logarithm of the number of powers to extract
+        #[clap(long)]
+        log_powers: u32,
+
+        /// Base-two logarithm of the number of powers of the challenge file
+        #[clap(long, default_value_t = CEREMONY_LOG_POWERS)]
+        input_log_powers: u32,
+
+        /// Curve of the challenge file
+        #[clap(long, value_enum, default_value = "bn254")]
+        curve: CurveKind,
+
+        /// Path to write the extracted challenge file to
+        #[clap(long)]
+        output: PathBuf,
+    },
+
+    /// Prints the digests of the first 2^10, 2^11, ... powers of the challenge file produced by a
+    /// round, as computed by `hasher --digests`: the Blake2b hashes of the files `extract` writes
+    /// for these numbers of powers.
+    Digests {
+        /// Round whose challenge file the digests are printed for
+        round: usize,
+
+        /// Prints the digests as JSON, by base-two logarithm of the number of powers
+        #[clap(long)]
+        json: bool,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Exports the accumulator of a verified round for other tooling.
+    Export {
+        /// Export Command
+        #[clap(subcommand)]
+        command: ExportCommand,
+    },
+
+    /// Commits to a random polynomial with the powers of a verified round, or of an arkworks SRS
+    /// file exported from it, opens it at a random point and checks the opening.
+    Kzg {
+        /// Round whose powers are used
+        round: usize,
+
+        /// Base-two logarithm of the number of coefficients of the polynomial
+        #[clap(long, default_value_t = 10)]
+        log_size: u32,
+
+        /// Path to an arkworks SRS file exported from the round, used instead of its challenge file
+        #[clap(long)]
+        srs: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Checks that the points of a small SRS file are the first points of the challenge file
+    /// produced by a round, fetching them with range requests if the challenge file is not on
+    /// disk. Challenge files with fewer powers, `.ptau` files and arkworks SRS files are supported.
+    Prefix {
+        /// Path to the SRS file
+        path: PathBuf,
+
+        /// Round whose challenge file the SRS file claims to come from
+        round: usize,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Traces every point of an SRS file to the challenge file produced by the round it claims to
+    /// come from, even if it is not a prefix, and prints a provenance report. Files in an unknown
+    /// format are read as raw points with `--encoding`.
+    Provenance {
+        /// Path to the SRS file
+        path: PathBuf,
+
+        /// Round whose challenge file the SRS file claims to come from
+        round: usize,
+
+        /// Raw SRS Options
+        #[clap(flatten)]
+        raw: RawOptions,
+
+        /// Number of leading points of each section searched for the points which are not at
+        /// their own index
+        #[clap(long, default_value_t = DEFAULT_SEARCH_POINTS)]
+        search: usize,
+
+        /// Prints the report as JSON
+        #[clap(long)]
+        json: bool,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Checks that a snarkjs `.ptau` file, such as `powersOfTau28_hez_final_XX.ptau`, builds on a
+    /// verified round of the ceremony: one of its contributions must produce a challenge file
+    /// hashed locally, every later contribution must build on it and the points of the file must
+    /// be consistent powers. If a hash of the file is listed in the published hashes, which are
+    /// not shipped with the verifier, its Blake2b hash must match it.
+    Ptau {
+        /// Path to the `.ptau` file
+        path: PathBuf,
+
+        /// Checks only the first 2^N powers of each section, defaults to all of them
+        #[clap(long)]
+        log_powers: Option<u32>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Path to the JSON file mapping the names of `.ptau` files to their published Blake2b
+        /// hashes
+        #[clap(long, default_value = PUBLISHED_HASHES_PATH)]
+        hashes: PathBuf,
+
+        /// Fails if no hash of the file is listed in the published hashes
+        #[clap(long)]
+        require_published: bool,
+    },
+
+    /// Checks a chain of Groth16 phase 2 parameter files of a circom circuit against a verified
+    /// round: the first file must build on the initial parameters of the circuit computed from the
+    /// round, and every later file must add a single valid contribution to the previous one.
+    Phase2 {
+        /// Path to the `.r1cs` file of the circuit
+        r1cs: PathBuf,
+
+        /// Paths to the parameter files, in the order of the contributions
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Round the initial parameters are computed from
+        #[clap(long)]
+        round: usize,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Checks that a snarkjs `.zkey` file derives from the initial parameters of its circuit on a
+    /// verified round or on a `.ptau` file, through its chain of contributions.
+    Zkey {
+        /// Path to the `.zkey` file
+        path: PathBuf,
+
+        /// Path to the `.r1cs` file of the circuit
+        #[clap(long)]
+        r1cs: PathBuf,
+
+        /// Round the initial parameters are computed from
+        #[clap(long, required_unless_present = "ptau", conflicts_with = "ptau")]
+        round: Option<usize>,
+
+        /// `.ptau` file the initial parameters are computed from, to be checked with `ppot ptau`
+        #[clap(long)]
+        ptau: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Downloads and verifies the transcript of the Ethereum KZG ceremony of EIP-4844: the powers
+    /// of tau of each sub-ceremony and the witness of every contribution.
+    Eip4844 {
+        /// Path to the transcript, downloaded to the data directory if missing
+        #[clap(long)]
+        path: Option<PathBuf>,
+
+        /// URL to download the transcript from
+        #[clap(long, default_value = TRANSCRIPT_URL)]
+        url: String,
+    },
+
+    /// Generates a mini ceremony with the file format of the ceremony on a few powers of tau, with
+    /// its registry, so that the other commands can be tried on files of a few hundred kilobytes.
+    Synthetic {
+        /// Base-two logarithm of the number of powers of tau of the files
+        #[clap(long, default_value_t = SYNTHETIC_LOG_POWERS, value_parser = clap::value_parser!(u32).range(1..=20))]
+        log_powers: u32,
+
+        /// Number of contributions
+        #[clap(long, default_value_t = SYNTHETIC_PARTICIPANTS)]
+        participants: usize,
+
+        /// Curve of the ceremony
+        #[clap(long, value_enum, default_value = "bn254")]
+        curve: CurveKind,
+
+        /// Seed of the secrets, drawn at random if missing
+        #[clap(long)]
+        seed: Option<u64>,
+
+        /// Breaks the last contribution with this fault, to try how the verifier rejects it
+        #[clap(long, value_enum)]
+        fault: Option<Fault>,
+
+        /// Path to write the registry of the mini ceremony to, which must not exist yet
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+}
+
+/// Chunk Manifest Commands
+#[derive(Subcommand)]
+enum ChunksCommand {
+    /// Hashes the chunks of every file of the registry present on disk which has no chunk manifest
+    /// yet.
+    Generate {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Size of the chunks in bytes
+        #[clap(long, default_value_t = CHUNK_SIZE)]
+        chunk_size: u64,
+    },
+
+    /// Checks every file of the registry with a chunk manifest and reports the corrupt chunks.
+    Check {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+}
+
+/// Parity Commands
+#[derive(Subcommand)]
+enum ParityCommand {
+    /// Generates the parity data of every file of the registry present on disk, with a recorded
+    /// Blake2b hash and at least `--min-size` bytes, which has no parity data yet.
+    Generate {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Size of the parity data, as a percentage of the size of the files
+        #[clap(long, default_value_t = PARITY_REDUNDANCY)]
+        redundancy: u32,
+
+        /// Size of the blocks in bytes
+        #[clap(long, default_value_t = PARITY_BLOCK_SIZE)]
+        block_size: u32,
+
+        /// Size in bytes from which files are protected
+        #[clap(long, default_value_t = PARITY_MIN_SIZE)]
+        min_size: u64,
+    },
+
+    /// Repairs a stored file in place with its parity data.
+    Repair {
+        /// Path of the file relative to the storage directory
+        path: String,
+    },
+}
+
+/// Compression Commands
+#[derive(Subcommand)]
+enum ZstdCommand {
+    /// Compresses every file of the registry present on disk with a recorded Blake2b hash,
+    /// checking that the compressed copy decompresses to that hash before removing the file.
+    Compress {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Compression level, from 1 to 22
+        #[clap(long, default_value_t = COMPRESSION_LEVEL)]
+        level: i32,
+
+        /// Number of decompressed bytes of a frame, the smallest unit read from a compressed file
+        #[clap(long, default_value_t = FRAME_SIZE)]
+        frame_size: usize,
+
+        /// Keeps the files next to their compressed copies
+        #[clap(long)]
+        keep: bool,
+    },
+
+    /// Decompresses a stored file compressed by `ppot zstd compress`.
+    Decompress {
+        /// Path of the file relative to the storage directory
+        path: String,
+
+        /// Keeps the compressed copy next to the file
+        #[clap(long)]
+        keep: bool,
+    },
+}
+
+/// Export Commands
+#[derive(Subcommand)]
+enum ExportCommand {
+    /// Writes the `phase1radix2m{N}` files read by the phase 2 tooling of bellman, for every `N`
+    /// up to `log_size`.
+    Phase1radix {
+        /// Round whose challenge file is exported
+        round: usize,
+
+        /// Base-two logarithm of the largest domain
+        #[clap(long)]
+        log_size: u32,
+
+        /// Directory to write the files to
+        #[clap(long, default_value = ".")]
+        output: PathBuf,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Writes the first 2^N powers in the canonical compressed serialization of arkworks, after a
+    /// header with the number of powers, the round and the hash of its challenge file.
+    Arkworks {
+        /// Round whose challenge file is exported
+        round: usize,
+
+        /// Base-two logarithm of the number of powers
+        #[clap(long)]
+        log_powers: u32,
+
+        /// Path to write the file to, defaults to `ppot_round{ROUND}_2^{N}.srs`
+        #[clap(long)]
+        output: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Writes the Lagrange basis of the radix-2 domain of size 2^N at tau in G1, after a header
+    /// as in `export arkworks` and the generator the roots of unity of the domain derive from.
+    Lagrange {
+        /// Round whose challenge file is exported
+        round: usize,
+
+        /// Base-two logarithm of the size of the domain
+        #[clap(long)]
+        log_size: u32,
+
+        /// Library convention for the roots of unity of the domain
+        #[clap(long, value_enum, default_value = "arkworks")]
+        domain: Domain,
+
+        /// Path to write the file to, defaults to `ppot_round{ROUND}_lagrange_2^{N}.srs`
+        #[clap(long)]
+        output: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Writes the KZG parameters of halo2 for circuits of 2^K rows.
+    Halo2 {
+        /// Round whose challenge file is exported
+        round: usize,
+
+        /// Base-two logarithm of the number of rows
+        #[clap(short)]
+        k: u32,
+
+        /// Path to write the file to, defaults to `kzg_bn254_{K}.params`
+        #[clap(long)]
+        output: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Writes the points at a range of indices of a section, for verifier keys and test vectors.
+    Points {
+        /// Round whose challenge file is exported
+        round: usize,
+
+        /// Section of the accumulator the points are taken from
+        #[clap(long, value_enum, default_value = "tau-g1")]
+        section: Section,
+
+        /// Index of the first point
+        #[clap(long, default_value_t = 0)]
+        start: usize,
+
+        /// Index after the last point
+        #[clap(long)]
+        end: usize,
+
+        /// Format of the points
+        #[clap(long, value_enum, default_value = "json")]
+        format: PointFormat,
+
+        /// Path to write the points to, defaults to the standard output
+        #[clap(long)]
+        output: Option<PathBuf>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+}
+
+/// Report Commands
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Uploads the report as JSON and Markdown, with the transcript of the file hashes, to a
+    /// release of a GitHub repository, or to a new gist if no repository is given.
+    Publish {
+        /// Repository to publish to, as `owner/name`
+        #[clap(long)]
+        repo: Option<String>,
+
+        /// Tag of the release to publish to, created if needed
+        #[clap(long, default_value = "ppot-verification")]
+        tag: String,
+
+        /// Lists the gist on the profile of its owner
+        #[clap(long)]
+        public: bool,
+
+        /// GitHub API token, defaults to the `GITHUB_TOKEN` environment variable
+        #[clap(long)]
+        token: Option<String>,
+
+        /// Timestamps the canonical report with the Time-Stamping Authority at this URL and
+        /// publishes the timestamp along with the report
+        #[clap(long)]
+        timestamp: Option<String>,
+    },
+
+    /// Computes the IPFS CIDs of the hash transcript of every round and pins the transcripts to
+    /// an IPFS node, writing the manifest of their CIDs.
+    Ipfs {
+        /// URL of the RPC API of the IPFS node
+        #[clap(long, default_value = IPFS_API_URL)]
+        api: String,
+
+        /// Pins the response files as well as their hashes
+        #[clap(long)]
+        files: bool,
+
+        /// Only computes the CIDs, without pinning anything
+        #[clap(long, conflicts_with = "files")]
+        offline: bool,
+
+        /// Path to the IPFS manifest
+        #[clap(long, default_value = IPFS_MANIFEST_PATH)]
+        manifest: PathBuf,
+    },
+
+    /// Writes the canonical form of the report, which is the same for every verifier who saw the
+    /// same files, and prints its Blake2b hash.
+    Canonical {
+        /// File to write the canonical report to, instead of the standard output
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Generates a key pair to sign reports with, in the format of minisign.
+    Keygen {
+        /// File to write the secret key to
+        #[clap(long, default_value = "ppot.key")]
+        secret_key: PathBuf,
+
+        /// File to write the public key to
+        #[clap(long, default_value = "ppot.pub")]
+        public_key: PathBuf,
+    },
+
+    /// Signs a canonical report, written by `ppot report canonical`, with a minisign signature,
+    /// or keylessly with Sigstore.
+    Sign {
+        /// Canonical report to sign
+        report: PathBuf,
+
+        /// Secret key, in the format of minisign or as a hexadecimal Ed25519 seed
+        #[clap(long, required_unless_present = "keyless")]
+        key: Option<PathBuf>,
+
+        /// Signs with an ephemeral key certified by Fulcio for the identity of an OpenID Connect
+        /// token, and records the signature in the Rekor transparency log
+        #[clap(long, conflicts_with = "key")]
+        keyless: bool,
+
+        /// OpenID Connect identity token for keyless signing, read from `SIGSTORE_ID_TOKEN` or
+        /// requested from GitHub Actions by default
+        #[clap(long, requires = "keyless")]
+        identity_token: Option<String>,
+
+        /// URL of the Fulcio certificate authority
+        #[clap(long, default_value = DEFAULT_FULCIO_URL)]
+        fulcio_url: String,
+
+        /// URL of the Rekor transparency log
+        #[clap(long, default_value = DEFAULT_REKOR_URL)]
+        rekor_url: String,
+
+        /// File to write the signature to, next to the report with the `.minisig` extension, or
+        /// `.sigstore.json` for keyless signatures, by default
+        #[clap(long)]
+        signature: Option<PathBuf>,
+    },
+
+    /// Asks a Time-Stamping Authority for a trusted timestamp of a canonical report, proving when
+    /// its verification was performed.
+    Timestamp {
+        /// Canonical report to timestamp
+        report: PathBuf,
+
+        /// URL of the Time-Stamping Authority
+        #[clap(long, default_value = DEFAULT_TSA_URL)]
+        tsa: String,
+
+        /// File to write the timestamp to, next to the report with the `.timestamp.json`
+        /// extension by default
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Verifies the signature of a canonical report, and that its timestamp, if there is one next
+    /// to it, is for the report, leaving the signature of the authority to `openssl ts`.
+    Verify {
+        /// Canonical report to verify
+        report: PathBuf,
+
+        /// Signer Options
+        #[clap(flatten)]
+        signer: SignerOptions,
+
+        /// Skips looking up keyless signatures in the Rekor transparency log
+        #[clap(long, conflicts_with = "key")]
+        offline: bool,
+    },
+
+    /// Verifies the signature of a canonical report published by another verifier, then checks
+    /// the hashes it claims against the local hashes and the headers of the ceremony files.
+    Check {
+        /// Canonical report to check
+        report: PathBuf,
+
+        /// Signer Options
+        #[clap(flatten)]
+        signer: SignerOptions,
+
+        /// Identifier of the entry of the Rekor transparency log holding the keyless signature of
+        /// the report, instead of a signature file
+        #[clap(long, conflicts_with_all = &["key", "signature"])]
+        rekor: Option<String>,
+
+        /// URL of the Rekor transparency log
+        #[clap(long, default_value = DEFAULT_REKOR_URL)]
+        rekor_url: String,
+
+        /// Fetches the headers of the files which are not on disk to check the claimed hashes
+        #[clap(long)]
+        remote: bool,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Aggregates canonical reports signed by many verifiers into a view of which rounds were
+    /// confirmed by enough independent signers, failing if any of them disagree.
+    Aggregate {
+        /// Canonical reports, each with its `.minisig` or `.sigstore.json` signature next to it
+        #[clap(required = true)]
+        reports: Vec<PathBuf>,
+
+        /// Public keys of the signers of the minisign signatures, each signer being named after
+        /// the file of their key
+        #[clap(long, multiple_values = true)]
+        keys: Vec<PathBuf>,
+
+        /// Identities of the signers of the keyless signatures with their OpenID Connect issuer,
+        /// such as `'name@example.org (https://github.com/login/oauth)'`, each signer being named
+        /// after their identity
+        #[clap(long, multiple_values = true)]
+        identities: Vec<Identity>,
+
+        /// Number of signers who must verify a round for it to be confirmed, a majority of the
+        /// signers by default
+        #[clap(long)]
+        threshold: Option<usize>,
+
+        /// Prints the aggregate as JSON instead of Markdown
+        #[clap(long)]
+        json: bool,
+
+        /// Rejects keyless signatures instead of looking them up in the Rekor transparency log
+        #[clap(long)]
+        offline: bool,
+    },
+
+    /// Compares two reports saved by `ppot status --json` round by round, failing if they
+    /// disagree.
+    Diff {
+        /// Local report
+        mine: PathBuf,
+
+        /// Report of the other verifier
+        theirs: PathBuf,
+
+        /// Prints the discrepancies as JSON
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+/// Raw SRS Options
+#[derive(Args)]
+struct RawOptions {
+    /// Reads the SRS file as a list of G1 points followed by G2 points in this encoding
+    #[clap(long, value_enum)]
+    encoding: Option<RawEncoding>,
+
+    /// Number of bytes before the first point of the raw SRS file
+    #[clap(long, default_value_t = 0, requires = "encoding")]
+    offset: usize,
+
+    /// Number of G2 points ending the raw SRS file
+    #[clap(long, default_value_t = 0, requires = "encoding")]
+    g2: usize,
+}
+
+/// Signer Options
+#[derive(Args)]
+struct SignerOptions {
+    /// Public key of the signer, in the format of minisign or as a hexadecimal Ed25519 key, the
+    /// report being signed keylessly if none is given
+    #[clap(long)]
+    key: Option<PathBuf>,
+
+    /// Signature of the report, next to the report with the `.minisig` extension, or
+    /// `.sigstore.json` for keyless signatures, by default
+    #[clap(long)]
+    signature: Option<PathBuf>,
+
+    /// Expected email or URI of the keyless signer, required to check keyless signatures
+    #[clap(long, conflicts_with = "key")]
+    identity: Option<String>,
+
+    /// Expected OpenID Connect issuer of the keyless signer, required to check keyless
+    /// signatures
+    #[clap(long, conflicts_with = "key")]
+    issuer: Option<String>,
+}
+
+/// Queue Commands
+#[derive(Subcommand)]
+enum QueueCommand {
+    /// Pushes the rounds of a range into the queue, skipping the rounds already queued.
+    Push {
+        /// Directory of the queue, or URL of its Redis server such as `redis://10.0.0.1:6379/0`
+        queue: String,
+
+        /// Rounds to verify, such as `1..71` or `1..=70`, defaults to every round
+        #[clap(long)]
+        rounds: Option<RoundRange>,
+
+        /// Base-two logarithm of the number of powers verified in each round, defaults to all of
+        /// them
+        #[clap(long)]
+        log_powers: Option<u32>,
+
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+    },
+
+    /// Claims and verifies the rounds of the queue until every round is claimed or verified.
+    Pull {
+        /// Directory of the queue, or URL of its Redis server such as `redis://10.0.0.1:6379/0`
+        queue: String,
+
+        /// Name of this worker, recorded with its claims and results
+        #[clap(long)]
+        name: String,
+
+        /// Number of seconds after which a claimed round without a result is claimed again
+        #[clap(long, default_value_t = LEASE_DURATION.as_secs())]
+        lease: u64,
+    },
+
+    /// Stitches the hash chain from the results recorded in the queue so far.
+    Report {
+        /// Directory of the queue, or URL of its Redis server such as `redis://10.0.0.1:6379/0`
+        queue: String,
+    },
+}
+
+/// Registry Commands
+#[derive(Subcommand)]
+enum RegistryCommand {
+    /// Regenerates the embedded contribution list from the GitHub repository of the ceremony and
+    /// the listing of the blob container.
+    Update {
+        /// File to write the contribution list to, `src/contributions.json` in the source tree
+        /// the binary was built from by default
+        #[clap(long, default_value = CONTRIBUTIONS_PATH)]
+        output: PathBuf,
+
+        /// GitHub API token, defaults to the `GITHUB_TOKEN` environment variable
+        #[clap(long)]
+        token: Option<String>,
+    },
+}
+
+/// Manifest Commands
+#[derive(Subcommand)]
+enum ManifestCommand {
+    /// Records the size and hash of every file of the registry present on disk.
+    Generate {
+        /// Path to the registry file
+        #[clap(long, default_value = REGISTRY_PATH)]
+        registry: PathBuf,
+
+        /// Path to the manifest file
+        #[clap(long, default_value = MANIFEST_PATH)]
+        manifest: PathBuf,
+    },
+
+    /// Checks the files on disk against the manifest.
+    Check {
+        /// Path to the manifest file
+        #[clap(long, default_value = MANIFEST_PATH)]
+        manifest: PathBuf,
+    },
+}
+
+/// Returns the default [`DownloadOptions`] with the connection settings of the configuration.
+fn download_options(storage: &StorageOptions) -> Result<DownloadOptions> {
+    Ok(Config::load_or_default(storage.state_path(CONFIG_PATH))?.download_options())
+}
+
+/// Runs the `sync` command, returning the number of new rounds.
+async fn sync(
+    storage: &StorageOptions,
+    registry_path: PathBuf,
+    github: bool,
+    token: Option<String>,
+) -> Result<usize> {
+    let registry_path = storage.state_path(registry_path);
+    let mut registry = Registry::load_or_builtin(&registry_path)?;
+    let latest = ceremony_of(&registry, github, api_token(token))
+        .discover(&download_options(storage)?.client()?)
+        .await?;
+    let new_rounds = registry.update(latest);
+    registry.save(&registry_path)?;
+    info!(
+        "Registry {:?} covers {} rounds ({} new)",
+        registry_path,
+        registry.rounds(),
+        new_rounds
+    );
+    Ok(new_rounds)
+}
+
+/// Runs the `registry update` command.
+async fn update_contributions(
+    storage: &StorageOptions,
+    output: PathBuf,
+    token: Option<String>,
+) -> Result {
+    let client = download_options(storage)?.client()?;
+    let list = ContributionList::discover(&client, api_token(token).as_deref()).await?;
+    let embedded = ContributionList::embedded();
+    list.save(&output)?;
+    println!(
+        "Wrote {} contributions ({} new) with {} exceptions to the naming convention to {}",
+        list.participants.len(),
+        list.participants
+            .len()
+            .saturating_sub(embedded.participants.len()),
+        list.exceptions.len(),
+        output.display()
+    );
+    for (conventional, name) in &list.exceptions {
+        if embedded.exceptions.get(conventional) != Some(name) {
+            println!("New exception: {} is named {}", conventional, name);
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `plan` command.
+async fn plan(
+    storage: &StorageOptions,
+    rounds: Option<RoundRange>,
+    log_powers: u32,
+    registry_path: PathBuf,
+    probe: bool,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let rounds = match rounds {
+        Some(RoundRange(rounds)) if rounds.end > registry.rounds() => bail!(
+            "The registry only covers rounds up to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+        Some(RoundRange(rounds)) => rounds,
+        _ => 1..registry.rounds(),
+    };
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+    let client = config.download_options().client()?;
+    let plan = Plan::new(&client, &config, storage, &registry, rounds.clone()).await?;
+    let needed = round_files(&registry, rounds.clone()).len();
+    println!(
+        "Rounds {:?} read {} files, {} of them already on disk",
+        rounds,
+        needed,
+        needed - plan.files.len()
+    );
+    for file in &plan.files {
+        match (file.size, file.remaining) {
+            (Some(size), Some(remaining)) if remaining < size => println!(
+                "\t{} {} ({} left)",
+                file.path,
+                HumanBytes(size),
+                HumanBytes(remaining)
+            ),
+            (Some(size), _) => println!("\t{} {}", file.path, HumanBytes(size)),
+            _ => println!("\t{} unknown size", file.path),
+        }
+    }
+    let unknown = plan.files.iter().filter(|file| file.size.is_none()).count();
+    if unknown > 0 {
+        println!(
+            "Download: {}, not counting {} files of unknown size",
+            HumanBytes(plan.bytes()),
+            unknown
+        );
+    } else {
+        println!("Download: {}", HumanBytes(plan.bytes()));
+    }
+    println!("Disk space: {}", plan.disk);
+    if !plan.disk.fits() {
+        warn!("The files do not fit in the free disk space");
+    }
+    println!(
+        "Memory: {} to verify 2^{} powers",
+        ByteSize(verification_size(1 << log_powers)),
+        log_powers
+    );
+    match plan.files.first() {
+        Some(file) if probe => match probe_bandwidth(&client, &file.url, PROBE_SIZE).await {
+            Ok(bandwidth) => println!(
+                "Bandwidth: {}/s, downloading should take about {}",
+                HumanBytes(bandwidth),
+                HumanDuration(plan.download_time(bandwidth))
+            ),
+            Err(err) => warn!("Unable to measure the bandwidth: {}", err),
+        },
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Runs the `doctor` command.
+async fn doctor(
+    storage: &StorageOptions,
+    rounds: Option<RoundRange>,
+    log_powers: u32,
+    registry_path: PathBuf,
+    offline: bool,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let rounds = match rounds {
+        Some(RoundRange(rounds)) if rounds.end > registry.rounds() => bail!(
+            "The registry only covers rounds up to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+        Some(RoundRange(rounds)) => rounds,
+        _ => 1..registry.rounds(),
+    };
+    storage.create_dirs()?;
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+    let client = config.download_options().client()?;
+    let mut findings = Vec::new();
+    if offline {
+        findings.push(check_disk(&disk::estimate(
+            &storage.dir,
+            round_files(&registry, rounds)
+                .into_iter()
+                .map(|file| (storage.path(&file.path), file.size)),
+        )?));
+    } else {
+        let plan = Plan::new(&client, &config, storage, &registry, rounds).await?;
+        findings.push(check_disk(&plan.disk));
+        let url = match plan.files.first() {
+            Some(file) => file.url.clone(),
+            _ => config.resolve_url(&registry.challenges[0].url)?,
+        };
+        findings.push(check_network(&client, &url, PROBE_SIZE).await);
+    }
+    findings.push(check_memory(available_memory(), log_powers));
+    findings.extend(check_limits());
+    findings.push(check_write_speed(&storage.dir, WRITE_PROBE_SIZE)?);
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    let count = |severity| {
+        findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    };
+    let (warnings, errors) = (count(Severity::Warning), count(Severity::Error));
+    ensure!(
+        errors == 0,
+        "{} checks failed and {} gave warnings, the verification would not complete",
+        errors,
+        warnings
+    );
+    if warnings > 0 {
+        println!(
+            "{} warnings, the verification may be slow or fail",
+            warnings
+        );
+    } else {
+        println!("Ready to verify");
+    }
+    Ok(())
+}
+
+/// Runs the `status` command.
+fn status(storage: &StorageOptions, json: bool) -> Result {
+    let report = Report::collect(storage)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    println!(
+        "{:<6} {:<12} {:<12} {:<12} {:<7} {:<9} Verified",
+        "Round", "Challenge", "Response", "Next", "Hashed", "Chain"
+    );
+    for round in &report.rounds {
+        let file = |i: usize| {
+            round
+                .files
+                .get(i)
+                .map_or("missing", |file| file.download_label())
+        };
+        let hashed = round
+            .files
+            .iter()
+            .filter(|file| file.blake2b.is_some())
+            .count();
+        let chain = match round.chain {
+            Some(status) => status.name(),
+            _ => "unchecked",
+        };
+        let verification = match round.verification {
+            Verification::Pending => "pending",
+            Verification::Verified => "verified",
+            Verification::Failed => "FAILED",
+        };
+        println!(
+            "{:<6} {:<12} {:<12} {:<12} {:<7} {:<9} {}",
+            round.round,
+            file(0),
+            file(1),
+            file(2),
+            format!("{}/{}", hashed, round.files.len()),
+            chain,
+            verification
+        );
+    }
+    let status = &report.status;
+    println!(
+        "{}/{} files downloaded ({} in progress), {} hashed{}, {}/{} rounds verified, {} failed",
+        status.downloaded,
+        status.files,
+        status.downloading,
+        status.hashed,
+        if status.hashing { " (hashing)" } else { "" },
+        status.verified,
+        status.rounds,
+        status.failed_rounds.len()
+    );
+    let profiled = report
+        .rounds
+        .iter()
+        .filter_map(|round| Some((round.round, round.profile?)))
+        .collect::<Vec<_>>();
+    if !profiled.is_empty() {
+        println!(
+            "\n{:<6} {:>9} {:>9} {:>12} {:>9} {:>9} {:>9} {:>11} {:>11}",
+            "Round",
+            "Total",
+            "IO",
+            "Deserialize",
+            "Subgroup",
+            "MultiExp",
+            "Pairing",
+            "Read",
+            "Rate"
+        );
+        let seconds = |millis: u64| format!("{:.1}s", millis as f64 / 1000.0);
+        for (round, profile) in profiled {
+            println!(
+                "{:<6} {:>9} {:>9} {:>12} {:>9} {:>9} {:>9} {:>11} {:>11}",
+                round,
+                seconds(profile.total_ms),
+                seconds(profile.io_ms),
+                seconds(profile.deserialization_ms),
+                seconds(profile.subgroup_check_ms),
+                seconds(profile.multiexp_ms),
+                seconds(profile.pairing_ms),
+                HumanBytes(profile.bytes_read).to_string(),
+                format!("{}/s", HumanBytes(profile.read_rate())),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `attestations` command.
+async fn check_attestations(
+    storage: &StorageOptions,
+    registry_path: PathBuf,
+    token: Option<String>,
+    keyring: Option<PathBuf>,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let db = StateDb::open_in(storage)?;
+    let client = download_options(storage)?.client()?;
+    let keyring = match keyring {
+        Some(keyring) => load_keyring(keyring)?,
+        _ => Vec::new(),
+    };
+    let attestations = list_attestations(&client, api_token(token).as_deref(), &keyring).await?;
+    let mut mismatches = Vec::new();
+    let mut invalid = Vec::new();
+    for attestation in &attestations {
+        let contribution = &attestation.contribution;
+        let local = match registry.responses.get(contribution.number - 1) {
+            Some(response) => FileState::collect(storage, &db, response)?.blake2b,
+            _ => None,
+        };
+        let status = attestation.check(local.as_ref());
+        if status == AttestationStatus::Mismatch {
+            mismatches.push(contribution.number);
+        }
+        let signature = &attestation.signature;
+        if signature.status == SignatureStatus::Invalid {
+            invalid.push(contribution.number);
+        }
+        db.record_attestation(contribution.number, signature)?;
+        println!(
+            "{:<6} {:<24} {:<18} {}{}",
+            contribution.number,
+            contribution.participant,
+            match status {
+                AttestationStatus::Match => "match",
+                AttestationStatus::Mismatch => "MISMATCH",
+                AttestationStatus::Unpublished => "no published hash",
+                AttestationStatus::Unhashed => "not hashed yet",
+            },
+            match signature.status {
+                SignatureStatus::Valid => "signed",
+                SignatureStatus::Invalid => "INVALID SIGNATURE",
+                SignatureStatus::UnknownKey => "signed by an unknown key",
+                SignatureStatus::Unverified => "signed by an unverified key",
+                SignatureStatus::Unsupported => "unsupported signature",
+                SignatureStatus::Unsigned => "unsigned",
+            },
+            match &signature.signer {
+                Some(signer) => format!(" {}", signer),
+                _ => String::new(),
+            }
+        );
+    }
+    ensure!(
+        mismatches.is_empty(),
+        "The response files of contributions {:?} do not match their published hashes",
+        mismatches
+    );
+    ensure!(
+        invalid.is_empty(),
+        "The attestations of contributions {:?} have invalid PGP signatures",
+        invalid
+    );
+    Ok(())
+}
+
+/// Runs the `report publish` command.
+async fn publish_report(
+    storage: &StorageOptions,
+    repo: Option<String>,
+    tag: String,
+    public: bool,
+    token: Option<String>,
+    timestamp: Option<String>,
+) -> Result {
+    let token = match api_token(token) {
+        Some(token) => token,
+        _ => bail!("Publishing requires a GitHub token, see `--token`"),
+    };
+    let report = Report::collect(storage)?;
+    let canonical = CanonicalReport::new(&report).to_bytes();
+    let client = download_options(storage)?.client()?;
+    let mut files = vec![
+        PublishedFile::new("ppot-report.json", serde_json::to_string_pretty(&report)?),
+        PublishedFile::new("ppot-report.md", report.markdown()),
+        PublishedFile::new(
+            "ppot-report.canonical.json",
+            String::from_utf8(canonical.clone())?,
+        ),
+        PublishedFile::new("ppot-hashes.b2", report.transcript()),
+    ];
+    if let Some(tsa) = timestamp {
+        let timestamp = Timestamp::request(&client, &tsa, &canonical).await?;
+        files.push(PublishedFile::new(
+            format!("ppot-report.canonical.json{}", TIMESTAMP_EXTENSION),
+            serde_json::to_string_pretty(&timestamp)?,
+        ));
+    }
+    let url = match repo {
+        Some(repo) => upload_release_assets(&client, &token, &repo, &tag, &files).await?,
+        _ => {
+            let description = format!(
+                "Perpetual powers of tau verification: {}/{} rounds verified",
+                report.status.verified, report.status.rounds
+            );
+            create_gist(&client, &token, &description, &files, public).await?
+        }
+    };
+    println!("Published the report to {}", url);
+    Ok(())
+}
+
+/// Runs the `report ipfs` command.
+async fn pin_report(
+    storage: &StorageOptions,
+    api: String,
+    files: bool,
+    offline: bool,
+    manifest_path: PathBuf,
+) -> Result {
+    let report = Report::collect(storage)?;
+    let manifest = if offline {
+        IpfsManifest::from_report(&report)
+    } else {
+        let node = IpfsNode::new(download_options(storage)?.client()?, &api);
+        IpfsManifest::pin(&node, &report, storage, files).await?
+    };
+    manifest.save(storage.state_path(&manifest_path))?;
+    for (round, cids) in &manifest.rounds {
+        match &cids.response {
+            Some(response) => println!("{:<6} {} {}", round, cids.transcript, response),
+            _ => println!("{:<6} {}", round, cids.transcript),
+        }
+    }
+    println!("Hash transcript: {}", manifest.transcript);
+    Ok(())
+}
+
+/// Runs the `report canonical` command.
+fn canonical_report(storage: &StorageOptions, output: Option<PathBuf>) -> Result {
+    let report = CanonicalReport::new(&Report::collect(storage)?);
+    match output {
+        Some(output) => {
+            atomic::write(&output, report.to_bytes())?;
+            println!("{}  {}", report.digest(), output.display());
+        }
+        _ => {
+            io::stdout().write_all(&report.to_bytes())?;
+            eprintln!("{}", report.digest());
+        }
+    }
+    Ok(())
+}
+
+/// Returns `path` if given, otherwise the path next to the report at `report` with `extension`
+/// appended to its name.
+fn next_to_report(report: &Path, path: Option<PathBuf>, extension: &str) -> PathBuf {
+    path.unwrap_or_else(|| {
+        let mut path = report.as_os_str().to_owned();
+        path.push(extension);
+        path.into()
+    })
+}
+
+/// Runs the `report keygen` command.
+fn generate_keys(secret_key: PathBuf, public_key: PathBuf) -> Result {
+    for path in [&secret_key, &public_key] {
+        ensure!(!path.exists(), "{} already exists", path.display());
+    }
+    let key = SecretKey::generate(&mut rand::rngs::OsRng);
+    atomic::write(&secret_key, key.to_minisign())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&secret_key, fs::Permissions::from_mode(0o600))?;
+    }
+    atomic::write(&public_key, key.public_key().to_minisign())?;
+    println!(
+        "Generated key {} in {} and {}",
+        key.key_id(),
+        secret_key.display(),
+        public_key.display()
+    );
+    Ok(())
+}
+
+/// Runs the `report sign` command.
+async fn sign_report(
+    storage: &StorageOptions,
+    report: PathBuf,
+    key: Option<PathBuf>,
+    token: Option<String>,
+    fulcio_url: String,
+    rekor_url: String,
+    signature: Option<PathBuf>,
+) -> Result {
+    let bytes = fs::read(&report)?;
+    CanonicalReport::from_bytes(&bytes)?;
+    let key = match key {
+        Some(key) => SecretKey::load(&key)?,
+        _ => {
+            let client = download_options(storage)?.client()?;
+            let token = identity_token(&client, token).await?;
+            let signed =
+                KeylessSignature::sign(&client, &bytes, &token, &fulcio_url, &rekor_url).await?;
+            let identity = signed.verify(&bytes)?;
+            let signature_path = next_to_report(&report, signature, SIGSTORE_EXTENSION);
+            atomic::write(&signature_path, serde_json::to_string_pretty(&signed)?)?;
+            println!(
+                "Signed {} as {} in {}, logged at index {} of {}",
+                report.display(),
+                identity,
+                signature_path.display(),
+                signed.rekor.log_index,
+                signed.rekor.url
+            );
+            return Ok(());
+        }
+    };
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let file_name = report
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+    let trusted_comment = format!("timestamp:{}\tfile:{}\tprehashed", timestamp, file_name);
+    let signature_path = next_to_report(&report, signature, ".minisig");
+    atomic::write(
+        &signature_path,
+        key.sign(&bytes, &trusted_comment)?.to_string(),
+    )?;
+    println!(
+        "Signed {} with key {} in {}",
+        report.display(),
+        key.key_id(),
+        signature_path.display()
+    );
+    Ok(())
+}
+
+/// Runs the `report verify` command.
+async fn verify_report_signature(
+    storage: &StorageOptions,
+    report: PathBuf,
+    signer: SignerOptions,
+    offline: bool,
+) -> Result {
+    let bytes = fs::read(&report)?;
+    let canonical = CanonicalReport::from_bytes(&bytes)?;
+    let signer = check_report_signature(storage, &report, &bytes, signer, None, offline).await?;
+    println!("{} is signed by {}", report.display(), signer);
+    println!(
+        "{} rounds, {} verified, digest {}",
+        canonical.rounds.len(),
+        canonical
+            .rounds
+            .iter()
+            .filter(|round| round.verification == Verification::Verified)
+            .count(),
+        canonical.digest()
+    );
+    let timestamp_path = next_to_report(&report, None, TIMESTAMP_EXTENSION);
+    if timestamp_path.exists() {
+        let timestamp = Timestamp::load(&timestamp_path)?;
+        timestamp.check(&bytes)?;
+        // The time and the authority are only claimed by the timestamp until `openssl ts` checks
+        // the signature of the authority.
+        println!(
+            "Claims to be timestamped at {} by {}, whose signature is unverified, see `openssl ts -verify`",
+            timestamp.time, timestamp.tsa
+        );
+    }
+    Ok(())
+}
+
+/// Checks the signature of the report at `report` holding `bytes` with `signer`, reading keyless
+/// signatures from the entry of the Rekor log given by its URL and identifier in `rekor`, if any,
+/// or looking them up in the log unless `offline`, and returns a description of the signer.
+///
+/// Keyless signatures are only accepted from the identity and issuer expected by `signer`, since
+/// anyone can get a certificate for their own identity.
+async fn check_report_signature(
+    storage: &StorageOptions,
+    report: &Path,
+    bytes: &[u8],
+    signer: SignerOptions,
+    rekor: Option<(&str, &str)>,
+    offline: bool,
+) -> Result<String> {
+    if let Some(key) = signer.key {
+        let key = PublicKey::load(&key)?;
+        let signature = Signature::load(next_to_report(report, signer.signature, ".minisig"))?;
+        key.verify(bytes, &signature)?;
+        return Ok(format!(
+            "key {}: {}",
+            key.key_id(),
+            signature.trusted_comment()
+        ));
+    }
+    let (expected_identity, expected_issuer) = match (signer.identity, signer.issuer) {
+        (Some(identity), Some(issuer)) => (identity, issuer),
+        _ => bail!(
+            "Keyless signatures are only accepted from an expected signer, pass --identity and \
+             --issuer, or --key for a minisign signature"
+        ),
+    };
+    let signed = match rekor {
+        Some((rekor_url, uuid)) => {
+            let client = download_options(storage)?.client()?;
+            KeylessSignature::fetch(&client, rekor_url, uuid).await?
+        }
+        _ => KeylessSignature::load(next_to_report(report, signer.signature, SIGSTORE_EXTENSION))?,
+    };
+    let identity = signed.verify(bytes)?;
+    ensure!(
+        identity.subject == expected_identity,
+        "{} is signed by {}, not {}",
+        report.display(),
+        identity.subject,
+        expected_identity
+    );
+    ensure!(
+        identity.issuer.as_deref() == Some(expected_issuer.as_str()),
+        "{} is signed by {}, not authenticated by {}",
+        report.display(),
+        identity,
+        expected_issuer
+    );
+    if !offline && rekor.is_none() {
+        let client = download_options(storage)?.client()?;
+        signed.check_log(&client, bytes).await?;
+    }
+    Ok(format!(
+        "{}, logged at index {} of {}",
+        identity, signed.rekor.log_index, signed.rekor.url
+    ))
+}
+
+/// Runs the `report check` command.
+async fn check_report(
+    storage: &StorageOptions,
+    report: PathBuf,
+    signer: SignerOptions,
+    rekor: Option<String>,
+    rekor_url: String,
+    remote: bool,
+    registry_path: PathBuf,
+) -> Result {
+    let bytes = fs::read(&report)?;
+    let canonical = CanonicalReport::from_bytes(&bytes)?;
+    let rekor = rekor.as_deref().map(|uuid| (rekor_url.as_str(), uuid));
+    let signer = check_report_signature(storage, &report, &bytes, signer, rekor, false).await?;
+    println!("{} is signed by {}", report.display(), signer);
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let db = StateDb::open_in(storage)?;
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+    let client = config.download_options().client()?;
+    let checks = check_claims(
+        storage,
+        &db,
+        &registry,
+        &canonical,
+        if remote {
+            Some((&client, &config))
+        } else {
+            None
+        },
+    )
+    .await?;
+    let mut mismatches = Vec::new();
+    let mut unchecked = 0;
+    for check in &checks {
+        match (&check.found, check.status()) {
+            (Some((_, source)), ChainStatus::Match) => {
+                println!("{}  match  {}", check.claim.path, source)
+            }
+            (Some((_, source)), _) => {
+                println!("{}  mismatch  {}", check.claim.path, source);
+                mismatches.push(check.claim.path.clone());
+            }
+            _ => {
+                println!("{}  unchecked", check.claim.path);
+                unchecked += 1;
+            }
+        }
+    }
+    ensure!(
+        mismatches.is_empty(),
+        "The hashes claimed by {} for {:?} do not match the ceremony files",
+        report.display(),
+        mismatches
+    );
+    println!(
+        "{} of the {} hashes claimed by {} match, {} unchecked{}",
+        checks.len() - unchecked,
+        checks.len(),
+        report.display(),
+        unchecked,
+        if unchecked > 0 && !remote {
+            ", see `--remote`"
+        } else {
+            ""
+        }
+    );
+    Ok(())
+}
+
+/// Runs the `report timestamp` command.
+async fn timestamp_report(
+    storage: &StorageOptions,
+    report: PathBuf,
+    tsa: String,
+    output: Option<PathBuf>,
+) -> Result {
+    let bytes = fs::read(&report)?;
+    CanonicalReport::from_bytes(&bytes)?;
+    let client = download_options(storage)?.client()?;
+    let timestamp = Timestamp::request(&client, &tsa, &bytes).await?;
+    let output = next_to_report(&report, output, TIMESTAMP_EXTENSION);
+    atomic::write(&output, serde_json::to_string_pretty(&timestamp)?)?;
+    println!(
+        "{} was timestamped at {} by {}, see {}",
+        report.display(),
+        timestamp.time,
+        tsa,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Runs the `report aggregate` command.
+async fn aggregate_reports(
+    storage: &StorageOptions,
+    reports: Vec<PathBuf>,
+    keys: Vec<PathBuf>,
+    identities: Vec<Identity>,
+    threshold: Option<usize>,
+    json: bool,
+    offline: bool,
+) -> Result {
+    let keys = keys
+        .iter()
+        .map(|path| {
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            Ok((name, PublicKey::load(path)?))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let client = download_options(storage)?.client()?;
+    let mut signed = Vec::with_capacity(reports.len());
+    for path in &reports {
+        let bytes = fs::read(path)?;
+        let report = CanonicalReport::from_bytes(&bytes)?;
+        let minisig = next_to_report(path, None, ".minisig");
+        let signer = if minisig.exists() {
+            let signature = Signature::load(&minisig)?;
+            let (name, key) = keys
+                .iter()
+                .find(|(_, key)| key.key_id() == signature.key_id())
+                .ok_or_else(|| {
+                    anyhow!(
+                        "{} is signed by key {}, which is not among `--keys`",
+                        path.display(),
+                        signature.key_id()
+                    )
+                })?;
+            key.verify(&bytes, &signature)?;
+            name.clone()
+        } else {
+            ensure!(
+                !offline,
+                "{} is signed keylessly, which cannot be looked up in the Rekor log with `--offline`",
+                path.display()
+            );
+            let keyless = KeylessSignature::load(next_to_report(path, None, SIGSTORE_EXTENSION))?;
+            let identity = keyless.verify(&bytes)?;
+            ensure!(
+                identities.contains(&identity),
+                "{} is signed by {}, who is not among `--identities`",
+                path.display(),
+                identity
+            );
+            keyless.check_log(&client, &bytes).await?;
+            identity.to_string()
+        };
+        info!("{} is signed by {}", path.display(), signer);
+        signed.push(SignedReport { signer, report });
+    }
+    let signers = signed
+        .iter()
+        .map(|report| &report.signer)
+        .collect::<BTreeSet<_>>()
+        .len();
+    let aggregate = Aggregate::new(&signed, threshold.unwrap_or(signers / 2 + 1));
+    if json {
+        println!("{}", serde_json::to_string_pretty(&aggregate)?);
+    } else {
+        print!("{}", aggregate.markdown());
+    }
+    let disputed = aggregate
+        .disputed()
+        .map(|round| round.round)
+        .collect::<Vec<_>>();
+    ensure!(
+        disputed.is_empty(),
+        "The signers disagree on rounds {:?}",
+        disputed
+    );
+    Ok(())
+}
+
+/// Runs the `report diff` command.
+fn diff_reports(mine: PathBuf, theirs: PathBuf, json: bool) -> Result {
+    let discrepancies = Report::load(&mine)?.diff(&Report::load(&theirs)?);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&discrepancies)?);
+    } else {
+        for discrepancy in &discrepancies {
+            println!("{}", discrepancy);
+        }
+    }
+    ensure!(
+        discrepancies.is_empty(),
+        "{} discrepancies between {} and {}",
+        discrepancies.len(),
+        mine.display(),
+        theirs.display()
+    );
+    if !json {
+        println!("{} and {} agree", mine.display(), theirs.display());
+    }
+    Ok(())
+}
+
+/// Runs the `watch` command.
+async fn watch(
+    storage: &StorageOptions,
+    log: &LogOptions,
+    registry_path: PathBuf,
+    github: bool,
+    token: Option<String>,
+    interval: Duration,
+) -> Result {
+    let cancel = cancel_on_interrupt()?;
+    // Catch up on the rounds left over by the previous runs before waiting for new ones, and retry
+    // the rounds whose pipeline failed on the next poll.
+    let mut pending = true;
+    while !cancel.is_cancelled() {
+        match sync(storage, registry_path.clone(), github, token.clone()).await {
+            Ok(new_rounds) if pending || new_rounds > 0 => {
+                pending = false;
+                for stage in Stage::ALL {
+                    // The stages receive the interrupts of the terminal too and save their progress.
+                    if let Err(err) = stage.run(storage, log).await {
+                        if cancel.is_cancelled() {
+                            return Ok(());
+                        }
+                        error!("{}", err);
+                        pending = true;
+                        break;
+                    }
+                }
+            }
+            Ok(_) => info!("No new rounds"),
+            Err(err) => warn!("Unable to poll for new rounds: {}", err),
+        }
+        info!("Polling again in {:?}", interval);
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = cancel.cancelled() => {}
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `scrub` command.
+async fn scrub(
+    storage: &StorageOptions,
+    rate: ByteRate,
+    every: Duration,
+    interval: Duration,
+    once: bool,
+    repair: bool,
+) -> Result {
+    let cancel = cancel_on_interrupt()?;
+    let notifier = Config::load_or_default(storage.state_path(CONFIG_PATH))?.notifier();
+    let db = StateDb::open_in(storage)?;
+    let mut corrupted = 0;
+    while !cancel.is_cancelled() {
+        let due = due_files(&db, every)?;
+        if !due.is_empty() {
+            info!("Scrubbing {} files at {}", due.len(), rate);
+        }
+        let mut pacer = Pacer::new(rate);
+        for (name, expected) in due {
+            let outcome =
+                match scrub_file(&storage.path(&name), &expected, &mut pacer, Some(&cancel))? {
+                    Some(outcome) => outcome,
+                    _ => return Ok(()),
+                };
+            db.record_scrub(&name, outcome.is_intact())?;
+            let actual = match outcome {
+                ScrubOutcome::Intact => {
+                    info!("{} is intact", name);
+                    continue;
+                }
+                ScrubOutcome::Corrupted(actual) => Some(actual),
+                ScrubOutcome::Missing => None,
+            };
+            if repair && actual.is_some() {
+                match repair_with_parity(storage, &db, &name) {
+                    Ok(report) if report.is_repaired() => continue,
+                    Ok(_) => {}
+                    Err(error) => warn!("{} was not repaired: {}", name, error),
+                }
+            }
+            let event = Event::FileCorrupted {
+                path: name,
+                expected,
+                actual,
+            };
+            error!("{}", event);
+            notifier.notify(&event).await;
+            corrupted += 1;
+        }
+        if once {
+            break;
+        }
+        info!("Polling again in {:?}", interval);
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = cancel.cancelled() => {}
+        }
+    }
+    ensure!(
+        corrupted == 0,
+        "{} files changed or disappeared since they were hashed",
+        corrupted
+    );
+    Ok(())
+}
+
+/// Runs the `coordinate` command.
+async fn coordinate_rounds(
+    storage: &StorageOptions,
+    rounds: Option<RoundRange>,
+    log_powers: Option<u32>,
+    registry_path: PathBuf,
+    listen: SocketAddr,
+    key: String,
+    lease: Duration,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let rounds = rounds.map_or(1..registry.rounds(), |rounds| rounds.0);
+    let log_powers = log_powers.unwrap_or(registry.powers().trailing_zeros());
+    let coordinator = Coordinator::new(registry, rounds, log_powers, key.into_bytes(), lease)?
+        .resume_from(storage.state_path(COORDINATOR_PATH))?;
+    let cancel = cancel_on_interrupt()?;
+    let report = coordinate(listen, coordinator, async move { cancel.cancelled().await }).await?;
+    print_chain_report(&report)
+}
+
+/// Runs the `work` command.
+async fn work(storage: &StorageOptions, coordinator: String, name: String, key: String) -> Result {
+    let options = DownloadOptions {
+        cancel: cancel_on_interrupt()?,
+        ..download_options(storage)?
+    };
+    let verified = work_for(
+        &options.client()?,
+        &coordinator,
+        &name,
+        key.as_bytes(),
+        storage,
+        &options,
+        &ProgressBars::default(),
+    )
+    .await?;
+    println!("Verified {} rounds for {}", verified, coordinator);
+    Ok(())
+}
+
+/// Prints the chain `report` and checks that it is valid.
+fn print_chain_report(report: &ChainReport) -> Result {
+    println!(
+        "{} of the {} rounds have a result",
+        report.results,
+        report.rounds.len()
+    );
+    for issue in &report.issues {
+        println!("{:?}", issue);
+    }
+    ensure!(report.is_valid(), "The chain of rounds is not verified");
+    println!("Rounds {:?} form a valid chain", report.rounds);
+    Ok(())
+}
+
+/// Runs the `queue push` command.
+fn push_queue(
+    storage: &StorageOptions,
+    queue: String,
+    rounds: Option<RoundRange>,
+    log_powers: Option<u32>,
+    registry_path: PathBuf,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let rounds = rounds.map_or(1..registry.rounds(), |rounds| rounds.0);
+    ensure!(
+        rounds.start > 0 && rounds.end <= registry.rounds(),
+        "The registry covers rounds 1 to {}, not {:?}",
+        registry.rounds() - 1,
+        rounds
+    );
+    let log_powers = log_powers.unwrap_or(registry.powers().trailing_zeros());
+    ensure!(
+        1 << log_powers <= registry.powers(),
+        "Unable to verify 2^{} powers of files with {}",
+        log_powers,
+        registry.powers()
+    );
+    let work_queue = open_queue(&queue)?;
+    let mut pushed = 0;
+    for round in rounds {
+        if work_queue.push(&WorkItem::new(&registry, round, log_powers))? {
+            pushed += 1;
+        }
+    }
+    println!("Pushed {} rounds into {}", pushed, queue);
+    Ok(())
+}
+
+/// Runs the `queue pull` command.
+async fn pull_queue(
+    storage: &StorageOptions,
+    queue: String,
+    name: String,
+    lease: Duration,
+) -> Result {
+    let options = DownloadOptions {
+        cancel: cancel_on_interrupt()?,
+        ..download_options(storage)?
+    };
+    let verified = work_from(
+        &*open_queue(&queue)?,
+        &name,
+        lease,
+        &options.client()?,
+        storage,
+        &options,
+        &ProgressBars::default(),
+    )
+    .await?;
+    println!("Verified {} rounds from {}", verified, queue);
+    Ok(())
+}
+
+/// Runs the `queue report` command.
+fn report_queue(queue: String) -> Result {
+    print_chain_report(&open_queue(&queue)?.report()?)
+}
+
+/// Runs the `manifest generate` command.
+fn generate_manifest(
+    storage: &StorageOptions,
+    registry_path: PathBuf,
+    manifest_path: PathBuf,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let manifest_path = storage.state_path(manifest_path);
+    let manifest = Manifest::generate(&registry, storage)?;
+    manifest.save(&manifest_path)?;
+    info!(
+        "Manifest {:?} records {} of {} files",
+        manifest_path,
+        manifest.files.len(),
+        registry.challenges.len() + registry.responses.len()
+    );
+    Ok(())
+}
+
+/// Runs the `manifest check` command.
+fn check_manifest(storage: &StorageOptions, manifest_path: PathBuf) -> Result {
+    let manifest = Manifest::load(storage.state_path(manifest_path))?;
+    let mut failures = 0;
+    for entry in &manifest.files {
+        match entry.check(storage)? {
+            FileStatus::Valid => info!("{}: OK", entry.path),
+            FileStatus::Missing => {
+                failures += 1;
+                error!("{}: MISSING", entry.path);
+            }
+            FileStatus::SizeMismatch { actual } => {
+                failures += 1;
+                error!(
+                    "{}: SIZE MISMATCH, expected {} bytes but found {}",
+                    entry.path, entry.size, actual
+                );
+            }
+            FileStatus::HashMismatch { actual } => {
+                failures += 1;
+                error!(
+                    "{}: HASH MISMATCH, expected {} but found {}",
+                    entry.path, entry.blake2b, actual
+                );
+            }
+        }
+    }
+    if failures > 0 {
+        bail!(
+            "{} of {} files failed the check",
+            failures,
+            manifest.files.len()
+        );
+    }
+    Ok(())
+}
+
+/// Memory-maps the file at `path`, decompressing it into memory if only its compressed copy is
+/// stored.
+fn map_file(path: &Path) -> Result<Mmap> {
+    if !path.exists() && compressed_path(path).exists() {
+        return InputOptions::default().open(path)?.into_mmap();
+    }
+    Ok(unsafe { Mmap::map(&File::open(path)?)? })
+}
+
+/// Runs the `chunks generate` command.
+fn generate_chunks(storage: &StorageOptions, registry_path: PathBuf, chunk_size: u64) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    for file in registry.challenges.iter().chain(&registry.responses) {
+        let path = storage.path(&file.path);
+        let chunks_path = chunks_path(storage.state_path_of(&path));
+        if !path.exists() || chunks_path.exists() {
+            continue;
+        }
+        let map = map_file(&path)?;
+        let progress_bar = hash_progress_bar(&path, map.len() as u64)?;
+        let manifest = ChunkManifest::compute(&map, chunk_size, Some(&progress_bar));
+        progress_bar.finish_and_clear();
+        manifest.save(chunks_path)?;
+        let hash_path = storage.hash_path(&path, HashAlgorithm::Blake2b);
+        if !hash_path.exists() {
+            manifest.blake2b.save(hash_path)?;
+        }
+        info!(
+            "{}: {} chunks, root {}",
+            file.path,
+            manifest.chunks.len(),
+            manifest.root
+        );
+    }
+    Ok(())
+}
+
+/// Runs the `chunks check` command.
+fn check_chunks(storage: &StorageOptions, registry_path: PathBuf) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let mut failures = 0;
+    for file in registry.challenges.iter().chain(&registry.responses) {
+        let path = storage.path(&file.path);
+        let manifest = match ChunkManifest::load(chunks_path(storage.state_path_of(&path))) {
+            Ok(manifest) => manifest,
+            _ => continue,
+        };
+        if !manifest.is_consistent() {
+            failures += 1;
+            error!("{}: the chunk manifest does not match its root", file.path);
+            continue;
+        }
+        let mismatched = manifest.mismatched_chunks(&map_file(&path)?);
+        if mismatched.is_empty() {
+            info!("{}: OK", file.path);
+            continue;
+        }
+        failures += 1;
+        error!("{}: {} corrupt chunks", file.path, mismatched.len());
+        for index in mismatched {
+            let range = manifest.chunk_range(index);
+            error!("\tchunk {} at bytes {}..{}", index, range.start, range.end);
+        }
+    }
+    if failures > 0 {
+        bail!("{} files have corrupt chunks", failures);
+    }
+    Ok(())
+}
+
+/// Runs the `parity generate` command.
+fn generate_parity(
+    storage: &StorageOptions,
+    registry_path: PathBuf,
+    redundancy: u32,
+    block_size: u32,
+    min_size: u64,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let db = StateDb::open_in(storage)?;
+    for file in registry.challenges.iter().chain(&registry.responses) {
+        let path = storage.path(&file.path);
+        let parity_path = parity_path(storage.state_path_of(&path));
+        if !path.exists() || parity_path.exists() || fs::metadata(&path)?.len() < min_size {
+            continue;
+        }
+        let blake2b = match db.hash(&file.path, HashAlgorithm::Blake2b)? {
+            Some(hash) => Hash64::try_from(hash.as_slice())?,
+            _ => {
+                warn!("{}: no recorded Blake2b hash, verify it first", file.path);
+                continue;
+            }
+        };
+        let header = protect_file(&path, &parity_path, blake2b, block_size, redundancy)?;
+        info!(
+            "{}: {} parity blocks for {} stripes of {} blocks",
+            file.path,
+            header.parity_blocks as usize * header.stripes(),
+            header.stripes(),
+            header.data_blocks
+        );
+    }
+    Ok(())
+}
+
+/// Repairs the stored file `name` with its parity data, recording a successful repair as an
+/// intact scrub.
+fn repair_with_parity(storage: &StorageOptions, db: &StateDb, name: &str) -> Result<RepairReport> {
+    let path = storage.path(name);
+    let parity_path = parity_path(storage.state_path_of(&path));
+    ensure!(parity_path.exists(), "{} has no parity data", name);
+    let report = repair_file(&path, &parity_path)?;
+    if report.damaged_parity > 0 {
+        warn!(
+            "{}: {} parity blocks are damaged",
+            name, report.damaged_parity
+        );
+    }
+    if !report.is_repaired() {
+        error!(
+            "{}: {} of {} damaged blocks could not be rebuilt",
+            name,
+            report.unrepairable.len(),
+            report.damaged.len()
+        );
+        return Ok(report);
+    }
+    info!("{}: {} damaged blocks rebuilt", name, report.damaged.len());
+    db.record_scrub(name, true)?;
+    Ok(report)
+}
+
+/// Runs the `zstd compress` command.
+fn compress_files(
+    storage: &StorageOptions,
+    registry_path: PathBuf,
+    level: i32,
+    frame_size: usize,
+    keep: bool,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let db = StateDb::open_in(storage)?;
+    for file in registry.challenges.iter().chain(&registry.responses) {
+        let path = storage.path(&file.path);
+        let output = compressed_path(&path);
+        if !path.exists() || output.exists() {
+            continue;
+        }
+        let blake2b = match db.hash(&file.path, HashAlgorithm::Blake2b)? {
+            Some(hash) => Hash64::try_from(hash.as_slice())?,
+            _ => {
+                warn!("{}: no recorded Blake2b hash, verify it first", file.path);
+                continue;
+            }
+        };
+        let table = compress_file(&path, &output, &blake2b, level, frame_size)?;
+        let compressed = fs::metadata(&output)?.len();
+        info!(
+            "{}: {} compressed to {} ({:.1}%) in {} frames",
+            file.path,
+            HumanBytes(table.len()),
+            HumanBytes(compressed),
+            100.0 * compressed as f64 / table.len().max(1) as f64,
+            table.frames()
+        );
+        if !keep {
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Runs the `zstd decompress` command.
+fn decompress(storage: &StorageOptions, name: &str, keep: bool) -> Result {
+    let path = storage.path(name);
+    let compressed = compressed_path(&path);
+    ensure!(!path.exists(), "{} is already decompressed", name);
+    decompress_file(&compressed, &path)?;
+    if let Some(hash) = StateDb::open_in(storage)?.hash(name, HashAlgorithm::Blake2b)? {
+        if hash_file(&path)?.0[..] != hash[..] {
+            fs::remove_file(&path)?;
+            bail!("{} does not decompress to its recorded hash", name);
+        }
+    }
+    if !keep {
+        fs::remove_file(&compressed)?;
+    }
+    info!("{}: decompressed", name);
+    Ok(())
+}
+
+/// Runs the `parity repair` command.
+fn repair_parity(storage: &StorageOptions, name: &str) -> Result {
+    let report = repair_with_parity(storage, &StateDb::open_in(storage)?, name)?;
+    ensure!(report.is_repaired(), "{} could not be repaired", name);
+    Ok(())
+}
+
+/// Runs the `locate` command.
+async fn locate(
+    storage: &StorageOptions,
+    path: String,
+    url: Option<String>,
+    registry_path: PathBuf,
+    range: (Option<u64>, Option<u64>),
+    repair: bool,
+) -> Result {
+    let url = match url {
+        Some(url) => url,
+        _ => {
+            let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+            let file = registry
+                .challenges
+                .iter()
+                .chain(&registry.responses)
+                .find(|file| file.path == path)
+                .ok_or_else(|| anyhow!("{} is not in the registry", path))?;
+            Config::load_or_default(storage.state_path(CONFIG_PATH))?.resolve_url(&file.url)?
+        }
+    };
+    let local_path = storage.path(&path);
+    let range = match range {
+        (None, None) => None,
+        (start, Some(end)) => Some(start.unwrap_or(0)..end),
+        (Some(start), None) => Some(start..u64::MAX),
+    };
+    let client = download_options(storage)?.client()?;
+    let report = locate_corruption(
+        &MultiProgress::new(),
+        &client,
+        &url,
+        &local_path,
+        range,
+        repair,
+    )
+    .await?;
+    info!(
+        "{}: {} bytes locally, {} bytes remotely",
+        path, report.local_size, report.remote_size
+    );
+    for range in &report.ranges {
+        warn!("\tdivergent bytes {}..{}", range.start, range.end);
+    }
+    if report.ranges.is_empty() {
+        info!("No divergence found");
+    } else if report.repaired {
+        let _ = fs::remove_file(storage.hash_path(&local_path, HashAlgorithm::Blake2b));
+        info!(
+            "Repaired {} bytes, rehash the file",
+            report.divergent_bytes()
+        );
+    } else {
+        bail!("{} bytes diverge from '{}'", report.divergent_bytes(), url);
+    }
+    Ok(())
+}
+
+/// Runs the `challenge` command.
+async fn challenge(storage: &StorageOptions, round: usize, registry_path: PathBuf) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let (response, next) = match (
+        registry.responses.get(round),
+        registry.challenges.get(round + 1),
+    ) {
+        (Some(response), Some(next)) if round > 0 => (response, next),
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let (response_path, next_path) = (storage.path(&response.path), storage.path(&next.path));
+    let hash = {
+        let (response_path, next_path) = (response_path.clone(), next_path.clone());
+        let ceremony = ceremony_of(&registry, false, None);
+        task::spawn_blocking(move || {
+            ceremony.reconstruct(&response_path, &next_path, &InputOptions::default())
+        })
+        .await??
+    };
+    info!("Reconstructed {:?}", next_path);
+    let db = StateDb::open_in(storage)?;
+    db.record_hash(&next.path, HashAlgorithm::Blake2b, &hash.0)?;
+    hash.save(storage.hash_path(&next_path, HashAlgorithm::Blake2b))?;
+    let asserted_by = match registry.responses.get(round + 1) {
+        Some(asserted_by) => asserted_by,
+        _ => {
+            warn!(
+                "No response file asserts the hash of {} yet, it is unchecked",
+                next.path
+            );
+            return Ok(());
+        }
+    };
+    let asserted_path = storage.path(&asserted_by.path);
+    let asserted = if asserted_path.exists() {
+        Hash64::read_header(&asserted_path)?
+    } else {
+        let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+        let client = config.download_options().client()?;
+        fetch_header(&client, &config.resolve_url(&asserted_by.url)?).await?
+    };
+    if hash == asserted {
+        db.record_chain(&next.path, &asserted_by.path, ChainStatus::Match)?;
+        info!(
+            "The hash of {} matches the hash asserted by {}",
+            next.path, asserted_by.path
+        );
+        Ok(())
+    } else {
+        db.record_chain(&next.path, &asserted_by.path, ChainStatus::Mismatch)?;
+        bail!(
+            "The hash of the reconstructed {} does not match the hash asserted by {}",
+            next.path,
+            asserted_by.path
+        )
+    }
+}
+
+/// Checks the hash of the `challenge` file, if it is known, against the `challenge_hash` asserted
+/// by the `response` file, only logging the outcome.
+fn check_builds_on(
+    storage: &StorageOptions,
+    challenge: &RemoteFile,
+    response: &RemoteFile,
+    challenge_hash: &Hash64,
+) -> Result {
+    let db = StateDb::open_in(storage)?;
+    match known_hash(storage, &db, &challenge.path)? {
+        Some(computed) if computed == *challenge_hash => {
+            println!("{} builds on {}", response.path, challenge.path)
+        }
+        Some(_) => error!(
+            "{} asserts a different hash than the hash of {}",
+            response.path, challenge.path
+        ),
+        _ => warn!(
+            "{} is not hashed, the proofs are only checked against the hash asserted by {}",
+            challenge.path, response.path
+        ),
+    }
+    Ok(())
+}
+
+/// Reads the hash of the challenge file asserted by the `response` file of `ceremony` and checks
+/// the proofs of knowledge of its public key, returning the hash with the secrets whose proof is
+/// invalid. The hash and the key are fetched from the registry URL if the file is not on disk.
+async fn check_proofs(
+    storage: &StorageOptions,
+    ceremony: &dyn Ceremony,
+    response: &RemoteFile,
+) -> Result<(Hash64, Vec<Secret>)> {
+    let response_path = storage.path(&response.path);
+    if response_path.exists() {
+        let map = map_file(&response_path)?;
+        let challenge_hash =
+            Hash64::from_header(&map).ok_or_else(|| anyhow!("{:?} is too short", response_path))?;
+        let invalid = ceremony.verify_proofs(&map, &challenge_hash)?;
+        Ok((challenge_hash, invalid))
+    } else {
+        let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+        let client = config.download_options().client()?;
+        let (challenge_hash, key) = fetch_public_key_bytes(
+            &client,
+            &config.resolve_url(&response.url)?,
+            ceremony.public_key_size(),
+        )
+        .await?;
+        let invalid = ceremony.verify_proofs(&key, &challenge_hash)?;
+        Ok((challenge_hash, invalid))
+    }
+}
+
+/// Runs the `contribution` command.
+async fn contribution(storage: &StorageOptions, round: usize, registry_path: PathBuf) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let (challenge, response) = match (
+        registry.challenges.get(round),
+        registry.responses.get(round),
+    ) {
+        (Some(challenge), Some(response)) if round > 0 => (challenge, response),
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let ceremony = ceremony_of(&registry, false, None);
+    let (challenge_hash, invalid) = check_proofs(storage, ceremony.as_ref(), response).await?;
+    check_builds_on(storage, challenge, response, &challenge_hash)?;
+    for secret in Secret::ALL {
+        println!(
+            "Proof of knowledge of {}: {}",
+            secret,
+            if invalid.contains(&secret) {
+                "INVALID"
+            } else {
+                "valid"
+            }
+        );
+    }
+    if !invalid.is_empty() {
+        bail!("The contribution of round {} is invalid", round);
+    }
+    Ok(())
+}
+
+/// Runs the `verify` command.
+fn verify_round(
+    storage: &StorageOptions,
+    round: usize,
+    log_powers: Option<u32>,
+    registry_path: PathBuf,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let (challenge, response) = match (
+        registry.challenges.get(round),
+        registry.responses.get(round),
+    ) {
+        (Some(challenge), Some(response)) if round > 0 => (challenge, response),
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let ceremony = ceremony_of(&registry, false, None);
+    let powers = log_powers.map_or(ceremony.powers(), |log_powers| 1 << log_powers);
+    ensure!(
+        (2..=ceremony.powers()).contains(&powers),
+        "Unable to verify 2^{} powers of files with 2^{}",
+        powers.trailing_zeros(),
+        ceremony.log_powers()
+    );
+    let (challenge_map, response_map) = (
+        map_file(&storage.path(&challenge.path))?,
+        map_file(&storage.path(&response.path))?,
+    );
+    let challenge_hash = Hash64::from_header(&response_map)
+        .ok_or_else(|| anyhow!("{} is too short", response.path))?;
+    check_builds_on(storage, challenge, response, &challenge_hash)?;
+    info!(
+        "Verifying the first 2^{} powers of round {} over {}",
+        powers.trailing_zeros(),
+        round,
+        ceremony.curve()
+    );
+    let profile = Profile::new();
+    let result = ceremony.verify_round(
+        round,
+        &challenge_map,
+        &response_map,
+        &challenge_hash,
+        powers,
+        Some(&profile),
+        &ProgressBars::default(),
+    );
+    let error = result.as_ref().err().map(ToString::to_string);
+    let db = StateDb::open_in(storage)?;
+    db.record_round(round, powers.trailing_zeros(), error.as_deref())?;
+    db.record_profile(round, &profile.summary())?;
+    result?;
+    println!(
+        "Round {} is valid on its first 2^{} powers",
+        round,
+        powers.trailing_zeros()
+    );
+    Ok(())
+}
+
+/// Runs the `extract` command.
+fn extract_powers(
+    path: PathBuf,
+    log_powers: u32,
+    input_log_powers: u32,
+    curve: CurveKind,
+    output: PathBuf,
+) -> Result {
+    let (map, powers, reduced) = (map_file(&path)?, 1 << input_log_powers, 1 << log_powers);
+    let hash = match curve {
+        CurveKind::Bn254 => extract::<Bn254>(&map, powers, reduced, &output)?,
+        CurveKind::Bls12_381 => extract::<Bls12_381>(&map, powers, reduced, &output)?,
+    };
+    println!(
+        "Extracted 2^{} powers of {:?} to {:?}, with Blake2b hash:\n{}",
+        log_powers,
+        path,
+        output,
+        hash.pretty()
+    );
+    Ok(())
+}
+
+/// Runs the `digests` command.
+fn print_digests(storage: &StorageOptions, round: usize, json: bool, registry: PathBuf) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry))?;
+    let challenge = match registry.challenges.get(round + 1) {
+        Some(challenge) if round > 0 => challenge,
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let digests = StateDb::open_in(storage)?.digests(&challenge.path)?;
+    if digests.is_empty() {
+        bail!(
+            "No digests of {} are recorded, compute them with `hasher --digests`",
+            challenge.path
+        );
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&digests)?);
+    } else {
+        for (log_powers, digest) in digests {
+            println!("2^{:<2}  {}  {}", log_powers, digest, challenge.path);
+        }
+    }
+    Ok(())
+}
+
+/// Returns the path of the challenge file produced by `round`, failing unless the round is recorded
+/// as verified and the file is on disk.
+fn verified_challenge(
+    storage: &StorageOptions,
+    round: usize,
+    registry_path: PathBuf,
+) -> Result<PathBuf> {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    ensure!(
+        registry.curve.is_bn254(),
+        "Only BN254 rounds are supported, the registry describes a ceremony over {}",
+        registry.curve
+    );
+    let challenge = match registry.challenges.get(round + 1) {
+        Some(challenge) if round > 0 => challenge,
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let verified = StateDb::open_in(storage)?
+        .rounds()?
+        .into_iter()
+        .any(|record| record.round == round && record.verified);
+    if !verified {
+        bail!(
+            "Round {} is not recorded as verified, run `verify_ppot` on it first",
+            round
+        );
+    }
+    let path = storage.path(&challenge.path);
+    if !path.exists() {
+        bail!(
+            "{:?} is not on disk, download it or reconstruct it with `ppot challenge {}`",
+            path,
+            round
+        );
+    }
+    Ok(path)
+}
+
+/// Runs the `export phase1radix` command.
+fn export_phase1radix(
+    storage: &StorageOptions,
+    round: usize,
+    log_size: u32,
+    output: PathBuf,
+    registry_path: PathBuf,
+) -> Result {
+    let path = verified_challenge(storage, round, registry_path)?;
+    fs::create_dir_all(&output)?;
+    let map = map_file(&path)?;
+    for path in phase1radix(&map, CEREMONY_POWERS, log_size, &output)? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/// Runs the `export arkworks` command.
+fn export_arkworks(
+    storage: &StorageOptions,
+    round: usize,
+    log_powers: u32,
+    output: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let path = verified_challenge(storage, round, registry_path)?;
+    let db = StateDb::open_in(storage)?;
+    let header = SrsHeader {
+        log_powers,
+        round: round as u64,
+        provenance: blake2b(storage, &InputOptions::default(), &db, &path)?,
+    };
+    let output = output
+        .unwrap_or_else(|| PathBuf::from(format!("ppot_round{}_2^{}.srs", round, log_powers)));
+    arkworks(&map_file(&path)?, CEREMONY_POWERS, &header, &output)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// Runs the `export lagrange` command.
+fn export_lagrange(
+    storage: &StorageOptions,
+    round: usize,
+    log_size: u32,
+    domain: Domain,
+    output: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let path = verified_challenge(storage, round, registry_path)?;
+    let db = StateDb::open_in(storage)?;
+    let header = SrsHeader {
+        log_powers: log_size,
+        round: round as u64,
+        provenance: blake2b(storage, &InputOptions::default(), &db, &path)?,
+    };
+    let output = output.unwrap_or_else(|| {
+        PathBuf::from(format!("ppot_round{}_lagrange_2^{}.srs", round, log_size))
+    });
+    lagrange(&map_file(&path)?, CEREMONY_POWERS, domain, &header, &output)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// Runs the `export halo2` command.
+fn export_halo2(
+    storage: &StorageOptions,
+    round: usize,
+    k: u32,
+    output: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let path = verified_challenge(storage, round, registry_path)?;
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("kzg_bn254_{}.params", k)));
+    halo2(&map_file(&path)?, CEREMONY_POWERS, k, &output)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+/// Runs the `export points` command.
+fn export_points(
+    storage: &StorageOptions,
+    round: usize,
+    section: Section,
+    range: Range<usize>,
+    format: PointFormat,
+    output: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let path = verified_challenge(storage, round, registry_path)?;
+    let bytes = points(&map_file(&path)?, CEREMONY_POWERS, section, range, format)?;
+    match output {
+        Some(output) => {
+            atomic::write(&output, bytes)?;
+            println!("{}", output.display());
+        }
+        _ => io::stdout().write_all(&bytes)?,
+    }
+    Ok(())
+}
+
+/// Runs the `kzg` command.
+fn kzg(
+    storage: &StorageOptions,
+    round: usize,
+    log_size: u32,
+    srs: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let len = 1 << log_size;
+    let kzg = match srs {
+        Some(path) => {
+            let (header, srs) = Srs::read(&fs::read(&path)?)?;
+            ensure!(
+                header.round == round as u64,
+                "{} was exported from round {} instead of {}",
+                path.display(),
+                header.round,
+                round
+            );
+            Kzg::from_srs(&srs, len)?
+        }
+        _ => {
+            let path = verified_challenge(storage, round, registry_path)?;
+            Kzg::from_accumulator(&map_file(&path)?, CEREMONY_POWERS, len)?
+        }
+    };
+    kzg.smoke_test(&mut rand::thread_rng())?;
+    println!(
+        "Committed to a polynomial of degree {} with the powers of round {}, opened it and \
+         checked the opening",
+        kzg.max_degree(),
+        round
+    );
+    Ok(())
+}
+
+/// Runs the `prefix` command.
+async fn prefix(
+    storage: &StorageOptions,
+    path: PathBuf,
+    round: usize,
+    registry_path: PathBuf,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    ensure!(
+        registry.curve.is_bn254(),
+        "Only BN254 rounds are supported, the registry describes a ceremony over {}",
+        registry.curve
+    );
+    let challenge = match registry.challenges.get(round + 1) {
+        Some(challenge) if round > 0 => challenge,
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let prefix = {
+        let path = path.clone();
+        task::spawn_blocking(move || Prefix::read(&map_file(&path)?)).await??
+    };
+    println!(
+        "{:?} holds 2^{} powers as a {:?} file",
+        path,
+        prefix.powers.trailing_zeros(),
+        prefix.format
+    );
+    let challenge_path = storage.path(&challenge.path);
+    let mismatches = if challenge_path.exists() {
+        let map = map_file(&challenge_path)?;
+        prefix.mismatches(&map, CEREMONY_POWERS)?
+    } else {
+        let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+        let client = config.download_options().client()?;
+        info!(
+            "Fetching the first powers of {} from its mirrors",
+            challenge.path
+        );
+        prefix
+            .fetch_mismatches(
+                &client,
+                &config.resolve_url(&challenge.url)?,
+                CEREMONY_POWERS,
+            )
+            .await?
+    };
+    let verified = StateDb::open_in(storage)?
+        .rounds()?
+        .into_iter()
+        .any(|record| record.round == round && record.verified);
+    if !verified {
+        warn!(
+            "Round {} is not recorded as verified, the prefix is only checked against {}",
+            round, challenge.path
+        );
+    }
+    if mismatches.is_empty() {
+        println!("{:?} is a prefix of {}", path, challenge.path);
+        return Ok(());
+    }
+    for mismatch in &mismatches {
+        error!(
+            "Point {} of {:?} differs from {}",
+            mismatch.index, mismatch.section, challenge.path
+        );
+    }
+    bail!("{:?} is not a prefix of {}", path, challenge.path)
+}
+
+/// Runs the `provenance` command.
+fn provenance(
+    storage: &StorageOptions,
+    path: PathBuf,
+    round: usize,
+    raw: RawOptions,
+    search: usize,
+    json: bool,
+    registry_path: PathBuf,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    ensure!(
+        registry.curve.is_bn254(),
+        "Only BN254 rounds are supported, the registry describes a ceremony over {}",
+        registry.curve
+    );
+    let challenge = match registry.challenges.get(round + 1) {
+        Some(challenge) if round > 0 => challenge,
+        _ => bail!(
+            "The registry covers rounds 1 to {}, run `ppot sync` to pick up new rounds.",
+            registry.rounds() - 1
+        ),
+    };
+    let challenge_path = storage.path(&challenge.path);
+    if !challenge_path.exists() {
+        bail!(
+            "{:?} is not on disk, download it to trace the points of {:?}",
+            challenge_path,
+            path
+        );
+    }
+    let map = map_file(&path)?;
+    let prefix = match raw.encoding {
+        Some(encoding) => Prefix::raw(&map, encoding, raw.offset, raw.g2)?,
+        _ => Prefix::read(&map)?,
+    };
+    info!("Tracing the points of {:?} to {}", path, challenge.path);
+    let sections = trace(
+        &prefix,
+        &map_file(&challenge_path)?,
+        CEREMONY_POWERS,
+        search,
+    )?;
+    let db = StateDb::open_in(storage)?;
+    let report = ProvenanceReport {
+        file: path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned(),
+        format: format!("{:?}", prefix.format),
+        round,
+        challenge: challenge.path.clone(),
+        challenge_hash: known_hash(storage, &db, &challenge.path)?,
+        verified: db
+            .rounds()?
+            .into_iter()
+            .any(|record| record.round == round && record.verified),
+        sections,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report.markdown());
+    }
+    ensure!(
+        report.is_traced(),
+        "{:?} is not traced to verified round {}",
+        path,
+        round
+    );
+    Ok(())
+}
+
+/// Runs the `ptau` command.
+fn ptau(
+    storage: &StorageOptions,
+    path: PathBuf,
+    log_powers: Option<u32>,
+    registry_path: PathBuf,
+    hashes_path: PathBuf,
+    require_published: bool,
+) -> Result {
+    let registry = Registry::load_or_builtin(storage.state_path(registry_path))?;
+    let published = PublishedHashes::load_or_default(storage.state_path(&hashes_path))?;
+    if published.0.is_empty() {
+        warn!(
+            "No published hashes of `.ptau` files are listed in {:?}, the file can only be tied \
+             to a round and not to a published artifact",
+            storage.state_path(&hashes_path)
+        );
+    }
+    let db = StateDb::open_in(storage)?;
+    let map = map_file(&path)?;
+    let ptau = Ptau::parse(&map)?;
+    println!(
+        "{:?} holds 2^{} powers of tau out of 2^{} and {} contributions",
+        path,
+        ptau.header.power,
+        ptau.header.ceremony_power,
+        ptau.contributions.len()
+    );
+    let mut hashes = Vec::new();
+    for (index, challenge) in registry.challenges.iter().enumerate() {
+        if let Some(hash) = known_hash(storage, &db, &challenge.path)? {
+            hashes.push((index, hash));
+        }
+    }
+    let (position, index) = ptau
+        .contributions
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(position, contribution)| {
+            hashes
+                .iter()
+                .find(|(index, hash)| *index > 0 && *hash == contribution.next_challenge)
+                .map(|(index, _)| (position, *index))
+        })
+        .ok_or_else(|| {
+            anyhow!(
+                "No contribution of {:?} produces a challenge file hashed locally, hash the \
+                 challenge files of the ceremony first",
+                path
+            )
+        })?;
+    let (round, imported) = (index - 1, &ptau.contributions[position]);
+    let challenge = &registry.challenges[index];
+    println!(
+        "Contribution {} imports round {}, producing {}",
+        position + 1,
+        round,
+        challenge.path
+    );
+    let mut valid = true;
+    match known_hash(storage, &db, &registry.challenges[round].path)? {
+        Some(hash) => {
+            let invalid = imported.key.verify(&hash);
+            if !invalid.is_empty() {
+                error!(
+                    "The proofs of knowledge of {:?} of contribution {} are invalid",
+                    invalid,
+                    position + 1
+                );
+                valid = false;
+            }
+        }
+        _ => warn!(
+            "{} is not hashed, the proofs of knowledge of contribution {} are unchecked",
+            registry.challenges[round].path,
+            position + 1
+        ),
+    }
+    let challenge_path = storage.path(&challenge.path);
+    if challenge_path.exists() {
+        let challenge_map = map_file(&challenge_path)?;
+        let layout = Layout::challenge(CEREMONY_POWERS);
+        let g1 = |section, index| {
+            format::read_g1(
+                &challenge_map[layout.point_range(section, index)],
+                Encoding::Uncompressed,
+            )
+        };
+        let g2 = |section, index| {
+            format::read_g2(
+                &challenge_map[layout.point_range(section, index)],
+                Encoding::Uncompressed,
+            )
+        };
+        if imported.tau_g1 != g1(Section::TauG1, 1)?
+            || imported.tau_g2 != g2(Section::TauG2, 1)?
+            || imported.alpha_g1 != g1(Section::AlphaG1, 0)?
+            || imported.beta_g1 != g1(Section::BetaG1, 0)?
+            || imported.beta_g2 != g2(Section::BetaG2, 0)?
+        {
+            error!(
+                "The points of contribution {} differ from the points of {}",
+                position + 1,
+                challenge.path
+            );
+            valid = false;
+        }
+    } else {
+        warn!(
+            "{} is not on disk, the points of contribution {} are unchecked",
+            challenge.path,
+            position + 1
+        );
+    }
+    for (offset, pair) in ptau.contributions[position..].windows(2).enumerate() {
+        let invalid = pair[1].follows(&pair[0]);
+        let number = position + offset + 2;
+        if invalid.is_empty() {
+            println!(
+                "Contribution {}{} builds on contribution {}",
+                number,
+                if pair[1].beacon { " (beacon)" } else { "" },
+                number - 1
+            );
+        } else {
+            error!(
+                "Contribution {} does not build on contribution {} for {:?}",
+                number,
+                number - 1,
+                invalid
+            );
+            valid = false;
+        }
+    }
+    if !ptau.check_last_contribution()? {
+        error!("The points of the file differ from the points of its last contribution");
+        valid = false;
+    }
+    let powers = log_powers.map_or(ptau.header.powers(), |log_powers| 1 << log_powers);
+    info!("Checking the first {} powers of each section", powers);
+    let invalid = ptau.check_powers(powers)?;
+    if invalid.is_empty() {
+        println!("The powers of every section are consistent");
+    } else {
+        error!("The powers of {:?} are inconsistent", invalid);
+        valid = false;
+    }
+    let verified = db
+        .rounds()?
+        .into_iter()
+        .any(|record| record.round == round && record.verified);
+    if !verified {
+        error!(
+            "Round {} is not recorded as verified, run `verify_ppot` on it first",
+            round
+        );
+        valid = false;
+    }
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    let published_hash = match published.get(&name) {
+        Some(_) => {
+            info!("Hashing {:?} to compare it with its published hash", path);
+            published.check(&name, &hash_file(&path)?)
+        }
+        _ => PublishedHash::Unknown,
+    };
+    match published_hash {
+        PublishedHash::Match => println!("{:?} has the Blake2b hash it was published with", path),
+        PublishedHash::Mismatch(hash) => {
+            error!(
+                "The Blake2b hash of {:?} differs from the hash it was published with:\n{}",
+                path,
+                hash.pretty()
+            );
+            valid = false;
+        }
+        PublishedHash::Unknown if require_published => {
+            error!(
+                "No hash of {} is listed in {:?}",
+                name,
+                storage.state_path(&hashes_path)
+            );
+            valid = false;
+        }
+        PublishedHash::Unknown => warn!(
+            "No hash of {} is listed in {:?}, the file is not tied to a published artifact",
+            name,
+            storage.state_path(&hashes_path)
+        ),
+    }
+    if !valid {
+        bail!("{:?} is not consistent with round {}", path, round);
+    }
+    println!("{:?} is consistent with verified round {}", path, round);
+    Ok(())
+}
+
+/// Runs the `phase2` command.
+fn phase2(
+    storage: &StorageOptions,
+    r1cs: PathBuf,
+    files: Vec<PathBuf>,
+    round: usize,
+    registry_path: PathBuf,
+) -> Result {
+    let r1cs = R1cs::read(&map_file(&r1cs)?)?;
+    let log_size = r1cs.domain_log_size();
+    println!(
+        "The circuit has {} constraints, {} public wires out of {}, on a domain of size 2^{}",
+        r1cs.constraints.len(),
+        r1cs.public,
+        r1cs.wires,
+        log_size
+    );
+    let path = verified_challenge(storage, round, registry_path)?;
+    info!(
+        "Computing the initial parameters of the circuit from round {}",
+        round
+    );
+    let phase1 = Phase1Radix::read(
+        &phase1radix_file(
+            &map_file(&path)?,
+            &Layout::challenge(CEREMONY_POWERS),
+            log_size,
+        )?,
+        log_size,
+    )?;
+    let initial = MpcParams::initial(&phase1, &r1cs)?;
+    drop(phase1);
+    println!("Initial parameters hash: {}", initial.cs_hash.pretty());
+    let mut previous: Option<MpcParams> = None;
+    for file in &files {
+        let params = MpcParams::read(&map_file(file)?)?;
+        match &previous {
+            None => {
+                let hashes = params.verify(&initial).map_err(|error| {
+                    anyhow!("{:?} does not build on round {}: {}", file, round, error)
+                })?;
+                for (index, hash) in hashes.iter().enumerate() {
+                    println!("Contribution {}: {}", index + 1, hash.pretty());
+                }
+            }
+            Some(previous) => {
+                let hash = params.verify_contribution(previous).map_err(|error| {
+                    anyhow!("{:?} does not build on the previous file: {}", file, error)
+                })?;
+                println!(
+                    "Contribution {}: {}",
+                    params.contributions.len(),
+                    hash.pretty()
+                );
+            }
+        }
+        previous = Some(params);
+    }
+    println!(
+        "{:?} holds {} valid contributions on the initial parameters of round {}",
+        files.last().expect("at least one file is required"),
+        previous.map_or(0, |params| params.contributions.len()),
+        round
+    );
+    Ok(())
+}
+
+/// Runs the `zkey` command.
+fn zkey(
+    storage: &StorageOptions,
+    path: PathBuf,
+    r1cs: PathBuf,
+    round: Option<usize>,
+    ptau_path: Option<PathBuf>,
+    registry_path: PathBuf,
+) -> Result {
+    let r1cs = R1cs::read(&map_file(&r1cs)?)?;
+    let zkey = Zkey::read(&map_file(&path)?)?;
+    println!(
+        "{:?} holds a circuit of {} wires, {} of them public, on a domain of size {}, with {} \
+         contributions",
+        path,
+        zkey.wires,
+        zkey.public,
+        zkey.domain_size,
+        zkey.contributions.len()
+    );
+    info!("Computing the initial parameters of the circuit");
+    let (initial, source) = match (round, ptau_path) {
+        (Some(round), _) => {
+            let map = map_file(&verified_challenge(storage, round, registry_path)?)?;
+            let layout = Layout::challenge(CEREMONY_POWERS);
+            let point = |section: Section, index: usize| {
+                if index >= section.len(CEREMONY_POWERS) {
+                    bail!("No point {} in {:?}", index, section);
+                }
+                Ok(&map[layout.point_range(section, index)])
+            };
+            let initial = Zkey::initial(
+                |section, index| format::read_g1(point(section, index)?, Encoding::Uncompressed),
+                |section, index| format::read_g2(point(section, index)?, Encoding::Uncompressed),
+                &r1cs,
+            )?;
+            (initial, format!("verified round {}", round))
+        }
+        (_, Some(ptau_path)) => {
+            let map = map_file(&ptau_path)?;
+            let ptau = Ptau::parse(&map)?;
+            let initial = Zkey::initial(
+                |section, index| ptau.g1(section, index),
+                |section, index| ptau.g2(section, index),
+                &r1cs,
+            )?;
+            warn!(
+                "Check {:?} against the ceremony with `ppot ptau` to tie the zkey to it",
+                ptau_path
+            );
+            (initial, format!("{:?}", ptau_path))
+        }
+        _ => bail!("Either a round or a .ptau file is required"),
+    };
+    let hashes = zkey
+        .verify(&initial)
+        .map_err(|error| anyhow!("{:?} does not derive from {}: {}", path, source, error))?;
+    for (index, (contribution, hash)) in zkey.contributions.iter().zip(hashes).enumerate() {
+        println!(
+            "Contribution {}{}{}: {}",
+            index + 1,
+            contribution
+                .name
+                .as_ref()
+                .map_or(String::new(), |name| format!(" ({})", name)),
+            if contribution.beacon { " (beacon)" } else { "" },
+            hash.pretty()
+        );
+    }
+    println!("{:?} derives from {}", path, source);
+    Ok(())
+}
+
+/// Runs the `eip4844` command.
+async fn eip4844(storage: &StorageOptions, path: Option<PathBuf>, url: String) -> Result {
+    let path = path.unwrap_or_else(|| storage.path(TRANSCRIPT_PATH));
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let options = DownloadOptions {
+            hash: false,
+            ..download_options(storage)?
+        };
+        download_file(
+            &ProgressBars::default(),
+            &options.client()?,
+            &[url],
+            &path,
+            &options,
+        )
+        .await?;
+    }
+    let transcript = Transcript::read(&map_file(&path)?)?;
+    println!(
+        "{:?} holds {} sub-ceremonies with {} contributions, {} of them signed",
+        path,
+        transcript.transcripts.len(),
+        transcript
+            .transcripts
+            .first()
+            .map_or(0, |transcript| transcript.contributions()),
+        transcript
+            .transcripts
+            .first()
+            .map_or(0, |transcript| transcript.signed_contributions()),
+    );
+    info!("Verifying the powers of tau and the witness of every contribution");
+    task::spawn_blocking(move || transcript.verify()).await??;
+    println!("{:?} is a valid transcript", path);
+    warn!("The BLS signatures of the participants are not verified");
+    Ok(())
+}
+
+/// Runs the `synthetic` command.
+fn synthetic(
+    storage: &StorageOptions,
+    log_powers: u32,
+    participants: usize,
+    curve: CurveKind,
+    seed: Option<u64>,
+    fault: Option<Fault>,
+    registry_path: PathBuf,
+) -> Result {
+    ensure!(
+        participants > 0 || fault.is_none(),
+        "A fault breaks the last contribution, generate at least one"
+    );
+    let registry_path = storage.state_path(registry_path);
+    ensure!(
+        !registry_path.exists(),
+        "{:?} already exists, pass another `--registry` to keep it",
+        registry_path
+    );
+    let mut rng = match seed {
+        Some(seed) => ChaCha20Rng::seed_from_u64(seed),
+        _ => ChaCha20Rng::from_entropy(),
+    };
+    let powers = 1 << log_powers;
+    info!(
+        "Generating {} contributions to 2^{} powers over {}",
+        participants, log_powers, curve
+    );
+    let (challenges, responses) = match curve {
+        CurveKind::Bn254 => {
+            MiniCeremony::<Bn254>::generate_faulty(powers, participants, fault, &mut rng)
+                .write(&storage.dir)?
+        }
+        CurveKind::Bls12_381 => {
+            MiniCeremony::<Bls12_381>::generate_faulty(powers, participants, fault, &mut rng)
+                .write(&storage.dir)?
+        }
+    };
+    let remote = |paths: Vec<PathBuf>| {
+        paths
+            .into_iter()
+            .map(|path| {
+                let name = path
+                    .file_name()
+                    .expect("Ceremony files have a name.")
+                    .to_string_lossy()
+                    .into_owned();
+                let url = format!("file://{}", fs::canonicalize(&path)?.display());
+                Ok::<_, anyhow::Error>(RemoteFile::new(name, url))
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+    Registry {
+        challenges: remote(challenges)?,
+        responses: remote(responses)?,
+        curve,
+        log_powers: Some(log_powers),
+    }
+    .save(&registry_path)?;
+    println!(
+        "Wrote {} rounds of 2^{} powers over {} to {:?}, with the registry {:?}",
+        participants, log_powers, curve, storage.dir, registry_path
+    );
+    if let Some(fault) = fault {
+        println!("Round {} is broken by {:?}", participants, fault);
+    }
+    Ok(())
+}
+
+fn main() -> Result {
+    let arguments = Arguments::parse();
+    arguments.log.init()?;
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_io()
+        .enable_time()
+        .build()?
+        .block_on(async {
+            let storage = &arguments.storage;
+            storage.create_dirs()?;
+            match arguments.command {
+                Command::Sync {
+                    registry,
+                    github,
+                    token,
+                } => sync(storage, registry, github, token).await.map(drop),
+                Command::Registry { command } => match command {
+                    RegistryCommand::Update { output, token } => {
+                        update_contributions(storage, output, token).await
+                    }
+                },
+                Command::Plan {
+                    rounds,
+                    powers,
+                    registry,
+                    no_probe,
+                } => plan(storage, rounds, powers, registry, !no_probe).await,
+                Command::Doctor {
+                    rounds,
+                    powers,
+                    registry,
+                    offline,
+                } => doctor(storage, rounds, powers, registry, offline).await,
+                Command::Status { json } => status(storage, json),
+                Command::Attestations {
+                    registry,
+                    token,
+                    keyring,
+                } => check_attestations(storage, registry, token, keyring).await,
+                Command::Report { command } => match command {
+                    ReportCommand::Publish {
+                        repo,
+                        tag,
+                        public,
+                        token,
+                        timestamp,
+                    } => publish_report(storage, repo, tag, public, token, timestamp).await,
+                    ReportCommand::Ipfs {
+                        api,
+                        files,
+                        offline,
+                        manifest,
+                    } => pin_report(storage, api, files, offline, manifest).await,
+                    ReportCommand::Canonical { output } => canonical_report(storage, output),
+                    ReportCommand::Timestamp {
+                        report,
+                        tsa,
+                        output,
+                    } => timestamp_report(storage, report, tsa, output).await,
+                    ReportCommand::Keygen {
+                        secret_key,
+                        public_key,
+                    } => generate_keys(secret_key, public_key),
+                    ReportCommand::Sign {
+                        report,
+                        key,
+                        keyless: _,
+                        identity_token,
+                        fulcio_url,
+                        rekor_url,
+                        signature,
+                    } => {
+                        sign_report(
+                            storage,
+                            report,
+                            key,
+                            identity_token,
+                            fulcio_url,
+                            rekor_url,
+                            signature,
+                        )
+                        .await
+                    }
+                    ReportCommand::Verify {
+                        report,
+                        signer,
+                        offline,
+                    } => verify_report_signature(storage, report, signer, offline).await,
+                    ReportCommand::Check {
+                        report,
+                        signer,
+                        rekor,
+                        rekor_url,
+                        remote,
+                        registry,
+                    } => {
+                        check_report(storage, report, signer, rekor, rekor_url, remote, registry)
+                            .await
+                    }
+                    ReportCommand::Aggregate {
+                        reports,
+                        keys,
+                        identities,
+                        threshold,
+                        json,
+                        offline,
+                    } => {
+                        aggregate_reports(
+                            storage, reports, keys, identities, threshold, json, offline,
+                        )
+                        .await
+                    }
+                    ReportCommand::Diff { mine, theirs, json } => diff_reports(mine, theirs, json),
+                },
+                Command::Watch {
+                    registry,
+                    github,
+                    token,
+                    interval,
+                } => {
+                    watch(
+                        storage,
+                        &arguments.log,
+                        registry,
+                        github,
+                        token,
+                        Duration::from_secs(interval),
+                    )
+                    .await
+                }
+                Command::Scrub {
+                    rate,
+                    days,
+                    interval,
+                    once,
+                    repair,
+                } => {
+                    scrub(
+                        storage,
+                        rate,
+                        Duration::from_secs(days * 24 * 60 * 60),
+                        Duration::from_secs(interval),
+                        once,
+                        repair,
+                    )
+                    .await
+                }
+                Command::Serve { listen } => {
+                    let cancel = cancel_on_interrupt()?;
+                    serve(
+                        listen,
+                        storage.clone(),
+                        async move { cancel.cancelled().await },
+                    )
+                    .await
+                }
+                Command::Coordinate {
+                    rounds,
+                    log_powers,
+                    registry,
+                    listen,
+                    key,
+                    lease,
+                } => {
+                    coordinate_rounds(
+                        storage,
+                        rounds,
+                        log_powers,
+                        registry,
+                        listen,
+                        key,
+                        Duration::from_secs(lease),
+                    )
+                    .await
+                }
+                Command::Work {
+                    coordinator,
+                    name,
+                    key,
+                } => work(storage, coordinator, name, key).await,
+                Command::WorkerKey { name, key } => {
+                    println!("{}", worker_key(key.as_bytes(), &name));
+                    Ok(())
+                }
+                Command::Queue { command } => match command {
+                    QueueCommand::Push {
+                        queue,
+                        rounds,
+                        log_powers,
+                        registry,
+                    } => push_queue(storage, queue, rounds, log_powers, registry),
+                    QueueCommand::Pull { queue, name, lease } => {
+                        pull_queue(storage, queue, name, Duration::from_secs(lease)).await
+                    }
+                    QueueCommand::Report { queue } => report_queue(queue),
+                },
+                Command::Manifest { command } => match command {
+                    ManifestCommand::Generate { registry, manifest } => {
+                        generate_manifest(storage, registry, manifest)
+                    }
+                    ManifestCommand::Check { manifest } => check_manifest(storage, manifest),
+                },
+                Command::Chunks { command } => match command {
+                    ChunksCommand::Generate {
+                        registry,
+                        chunk_size,
+                    } => generate_chunks(storage, registry, chunk_size),
+                    ChunksCommand::Check { registry } => check_chunks(storage, registry),
+                },
+                Command::Parity { command } => match command {
+                    ParityCommand::Generate {
+                        registry,
+                        redundancy,
+                        block_size,
+                        min_size,
+                    } => generate_parity(storage, registry, redundancy, block_size, min_size),
+                    ParityCommand::Repair { path } => repair_parity(storage, &path),
+                },
+                Command::Zstd { command } => match command {
+                    ZstdCommand::Compress {
+                        registry,
+                        level,
+                        frame_size,
+                        keep,
+                    } => compress_files(storage, registry, level, frame_size, keep),
+                    ZstdCommand::Decompress { path, keep } => decompress(storage, &path, keep),
+                },
+                Command::Locate {
+                    path,
+                    url,
+                    registry,
+                    start,
+                    end,
+                    repair,
+                } => locate(storage, path, url, registry, (start, end), repair).await,
+                Command::Challenge { round, registry } => challenge(storage, round, registry).await,
+                Command::Contribution { round, registry } => {
+                    contribution(storage, round, registry).await
+                }
+                Command::Verify {
+                    round,
+                    log_powers,
+                    registry,
+                } => verify_round(storage, round, log_powers, registry),
+                Command::Extract {
+                    path,
+                    log_powers,
+                    input_log_powers,
+                    curve,
+                    output,
+                } => extract_powers(path, log_powers, input_log_powers, curve, output),
+                Command::Digests {
+                    round,
+                    json,
+                    registry,
+                } => print_digests(storage, round, json, registry),
+                Command::Export { command } => match command {
+                    ExportCommand::Phase1radix {
+                        round,
+                        log_size,
+                        output,
+                        registry,
+                    } => export_phase1radix(storage, round, log_size, output, registry),
+                    ExportCommand::Arkworks {
+                        round,
+                        log_powers,
+                        output,
+                        registry,
+                    } => export_arkworks(storage, round, log_powers, output, registry),
+                    ExportCommand::Lagrange {
+                        round,
+                        log_size,
+                        domain,
+                        output,
+                        registry,
+                    } => export_lagrange(storage, round, log_size, domain, output, registry),
+                    ExportCommand::Halo2 {
+                        round,
+                        k,
+                        output,
+                        registry,
+                    } => export_halo2(storage, round, k, output, registry),
+                    ExportCommand::Points {
+                        round,
+                        section,
+                        start,
+                        end,
+                        format,
+                        output,
+                        registry,
+                    } => export_points(
+                        storage,
+                        round,
+                        section,
+                        start..end,
+                        format,
+                        output,
+                        registry,
+                    ),
+                },
+                Command::Kzg {
+                    round,
+                    log_size,
+                    srs,
+                    registry,
+                } => kzg(storage, round, log_size, srs, registry),
+                Command::Prefix {
+                    path,
+                    round,
+                    registry,
+                } => prefix(storage, path, round, registry).await,
+                Command::Provenance {
+                    path,
+                    round,
+                    raw,
+                    search,
+                    json,
+                    registry,
+                } => provenance(storage, path, round, raw, search, json, registry),
+                Command::Ptau {
+                    path,
+                    log_powers,
+                    registry,
+                    hashes,
+                    require_published,
+                } => ptau(
+                    storage,
+                    path,
+                    log_powers,
+                    registry,
+                    hashes,
+                    require_published,
+                ),
+                Command::Phase2 {
+                    r1cs,
+                    files,
+                    round,
+                    registry,
+                } => phase2(storage, r1cs, files, round, registry),
+                Command::Zkey {
+                    path,
+                    r1cs,
+                    round,
+                    ptau,
+                    registry,
+                } => zkey(storage, path, r1cs, round, ptau, registry),
+                Command::Eip4844 { path, url } => eip4844(storage, path, url).await,
+                Command::Synthetic {
+                    log_powers,
+                    participants,
+                    curve,
+                    seed,
+                    fault,
+                    registry,
+                } => synthetic(
+                    storage,
+                    log_powers,
+                    participants,
+                    curve,
+                    seed,
+                    fault,
+                    registry,
+                ),
+            }
+        })
+}
@@ -1,14 +1,35 @@
+use clap::Parser;
 use memmap::MmapOptions;
-use ppot_verifier::calculate_hash;
+use ppot_verifier::{
+    calculate_hashes,
+    hash::Hash64,
+    hash_path, hash_progress_bar, into_array_unchecked,
+    log::LogOptions,
+    memory::{available_memory, hash_chunk_size},
+    HashAlgorithm,
+};
 use std::fs::{File, OpenOptions};
-use std::io::{Read, Write};
+use std::io::Write;
+use std::path::Path;
 use std::time::Instant;
+use tracing::info;
+
+/// Command Line Arguments
+#[derive(Parser)]
+struct Arguments {
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
 
 fn main() {
-    for path in ["challenge_0002_clean", "challenge_0003_clean"] {
+    Arguments::parse()
+        .log
+        .init()
+        .expect("unable to install the logger");
+    for path in ["challenge_0002_clean", "challenge_0003_clean"].map(Path::new) {
         // Saves hash to `challenge_xxxx_hash`
-        let mut hash_path = path.to_owned();
-        hash_path.push_str("_hash");
+        let hash_path = hash_path(path);
         match OpenOptions::new()
             .write(true)
             .create_new(true)
@@ -17,35 +38,22 @@ fn main() {
             Ok(mut file) => {
                 let now = Instant::now();
                 hash_to(&mut file, path).unwrap();
-                println!("File {:?} has been hashed in \n {:?}", path, now.elapsed());
+                info!("File {:?} has been hashed in {:?}", path, now.elapsed());
             }
             // std::io::ErrorKind(AlreadyExists) => { todo!() },
-            _ => println!("File {:?} has already been hashed", path),
+            _ => info!("File {:?} has already been hashed", path),
         }
 
         // Now print the hashes
-        let mut file = OpenOptions::new()
-            .read(true)
-            .open(&hash_path)
-            .expect("unable to open file in this directory");
-        let mut computed_hash = [0u8; 64];
-        let _ = file.read(&mut computed_hash[..]).unwrap();
+        let computed_hash =
+            Hash64::load(&hash_path).expect("unable to open file in this directory");
         println!("The hash of {:?} is", hash_path);
-        for line in computed_hash.chunks(16) {
-            print!("\t");
-            for section in line.chunks(4) {
-                for b in section {
-                    print!("{:02x}", b);
-                }
-                print!(" ");
-            }
-        }
-        println!(" ");
+        println!("{}", computed_hash.pretty());
     }
 }
 
 /// Hashes the file at `path` and saves the hash to `file`.
-fn hash_to(file: &mut File, path: &str) -> Result<(), std::io::Error> {
+fn hash_to(file: &mut File, path: &Path) -> Result<(), std::io::Error> {
     // Make memory map from `path`
     let reader = OpenOptions::new()
         .read(true)
@@ -57,7 +65,20 @@ fn hash_to(file: &mut File, path: &str) -> Result<(), std::io::Error> {
             .map(&reader)
             .expect("unable to create a memory map for input")
     };
-    let hash = calculate_hash(&reader);
-    file.write_all(&hash)?;
+    let progress_bar =
+        hash_progress_bar(path, reader.len() as u64).expect("the progress bar template is valid");
+    let hash = Hash64(into_array_unchecked(
+        calculate_hashes(
+            &reader,
+            &[HashAlgorithm::Blake2b],
+            Some(&progress_bar),
+            None,
+            hash_chunk_size(available_memory()),
+        )
+        .expect("hashing without a cancellation token always completes")
+        .remove(0),
+    ));
+    progress_bar.finish_and_clear();
+    file.write_all(hash.as_ref())?;
     Ok(())
 }
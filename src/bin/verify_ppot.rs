@@ -1,87 +1,637 @@
+use anyhow::anyhow;
+use clap::Parser;
 use manta_trusted_setup::groth16::kzg::Accumulator;
 use manta_trusted_setup::groth16::ppot::kzg::PerpetualPowersOfTauCeremony;
 use manta_trusted_setup::groth16::ppot::serialization::{
     read_kzg_proof, read_subaccumulator, Compressed, PpotSerializer,
 };
-use manta_util::into_array_unchecked;
-use memmap::{Mmap, MmapOptions};
-use ppot_verifier::{challenge_paths, response_paths};
-use std::fs::OpenOptions;
+use ppot_verifier::{
+    accumulator::verify_levels,
+    checkpoint::{Checkpoint, CHECKPOINT_PATH},
+    config::{Config, CONFIG_PATH},
+    db::StateDb,
+    download::DownloadOptions,
+    hash::Hash64,
+    input::{Input, InputOptions},
+    lock::FileLock,
+    log::LogOptions,
+    memory::{auto_budget, available_memory, tune, verification_size, ByteSize},
+    notify::{Event, Notifier},
+    plan::round_files,
+    quarantine::{check_chain, redownload},
+    registry::{Registry, REGISTRY_PATH},
+    signal::{cancel_on_interrupt, INTERRUPTED_EXIT_CODE},
+    snapshot::SnapshotCache,
+    storage::StorageOptions,
+    window::{spawn_prefetch, PrefetchFile, Window},
+    Result,
+};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
 use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
 
-/// Size of subaccumulator we are verifying
-const NUM_POWERS: usize = 1 << 19;
+/// Base-two logarithm of the default size of subaccumulator we are verifying
+const LOG_POWERS: u32 = 19;
+/// Base-two logarithm of the smallest size of subaccumulator we can verify
+const MIN_LOG_POWERS: u32 = 16;
+/// Base-two logarithm of the largest size of subaccumulator we can verify
+const MAX_LOG_POWERS: u32 = 22;
+/// Base-two logarithm of the smallest power level we can report on
+const MIN_LEVEL_LOG_POWERS: u32 = 10;
 /// Subaccumulator type
-type SmallCeremony = PerpetualPowersOfTauCeremony<PpotSerializer, NUM_POWERS>;
-
-/// Number of rounds of ceremony to verify
-const NUM_ROUNDS: usize = 71;
-
-/// Given a path, produces a read-only MemMap to that path
-unsafe fn try_into_mmap(path: &str) -> Option<Mmap> {
-    match OpenOptions::new().read(true).open(path) {
-        Ok(file) => Some(
-            MmapOptions::new()
-                .map(&file)
-                .expect("unable to create a memory map for input"),
-        ),
+type SmallCeremony<const N: usize> = PerpetualPowersOfTauCeremony<PpotSerializer, N>;
+
+/// Command Line Arguments
+#[derive(Parser)]
+struct Arguments {
+    /// Base-two logarithm of the number of powers of tau verified in each round, defaulting to
+    /// the largest number which fits in the memory budget, or to 19 if the available memory is
+    /// unknown
+    #[clap(
+        long,
+        value_parser = clap::value_parser!(u32).range(MIN_LOG_POWERS as i64..=MAX_LOG_POWERS as i64)
+    )]
+    log_powers: Option<u32>,
+
+    /// Base-two logarithms of the smaller numbers of powers to report on as well, such as
+    /// `10,15`. A round verified on all its powers is verified on every prefix of them, the
+    /// smaller levels are only verified on their own when it fails, from the files already mapped
+    #[clap(
+        long,
+        value_delimiter = ',',
+        value_parser = clap::value_parser!(u32).range(MIN_LEVEL_LOG_POWERS as i64..=MAX_LOG_POWERS as i64)
+    )]
+    levels: Vec<u32>,
+
+    /// Memory budget for the subaccumulators, such as `16G`, lowering the number of powers
+    /// verified in each round until they fit, defaulting to three quarters of the available memory
+    #[clap(long, env = "PPOT_MAX_MEM")]
+    max_mem: Option<ByteSize>,
+
+    /// Number of threads of the verification, defaulting to as many as the memory budget holds
+    /// the scratch space of, up to the number of cores
+    #[clap(long, env = "PPOT_THREADS")]
+    threads: Option<usize>,
+
+    /// Disk budget for the ceremony files, such as `500G`, downloading the files ahead of the
+    /// round being verified and deleting the files of each round once it is hashed and verified
+    #[clap(long, env = "PPOT_MAX_DISK")]
+    max_disk: Option<ByteSize>,
+
+    /// Reads every subaccumulator from its challenge file, ignoring the snapshots saved by
+    /// previous runs
+    #[clap(long)]
+    no_snapshots: bool,
+
+    /// Resumes from the verification checkpoint, verifying only the rounds which were not
+    /// verified yet
+    #[clap(long)]
+    incremental: bool,
+
+    /// Moves the files of a failed round which break the hash chain to the quarantine directory,
+    /// downloads them again and verifies the round once more
+    #[clap(long)]
+    redownload: bool,
+
+    /// Input Options
+    #[clap(flatten)]
+    input: InputOptions,
+
+    /// Storage Options
+    #[clap(flatten)]
+    storage: StorageOptions,
+
+    /// Logging Options
+    #[clap(flatten)]
+    log: LogOptions,
+}
+
+/// Verification Context
+struct Context<'a> {
+    /// Storage Options
+    storage: &'a StorageOptions,
+
+    /// Input Options
+    input: &'a InputOptions,
+
+    /// Snapshots of the subaccumulators, unless they are disabled
+    snapshots: Option<SnapshotCache>,
+
+    /// Cancelled on Ctrl-C
+    cancel: CancellationToken,
+
+    /// Resumes from the verification checkpoint
+    incremental: bool,
+
+    /// Notifies the failed rounds and the end of the run
+    notifier: Notifier,
+
+    /// Records the verification of every round
+    db: StateDb,
+
+    /// Ceremony files to verify
+    registry: Registry,
+
+    /// Configuration
+    config: Config,
+
+    /// Disk budget for the ceremony files, keeping every file if unset
+    max_disk: Option<ByteSize>,
+
+    /// Downloads the corrupt files of the failed rounds again
+    redownload: bool,
+
+    /// Smaller power levels to report on, from the largest to the smallest
+    levels: Vec<u32>,
+}
+
+/// Given a path, opens it for reading with `input`, through a memory map if possible
+fn try_open(path: &Path, input: &InputOptions) -> Option<Input> {
+    // Wait for the file to be completely written before mapping it
+    let _lock = FileLock::shared(path).expect("unable to lock the input");
+    match input.open(path) {
+        Ok(input) => Some(input),
         _ => {
-            println!("Unable to open file at {:?}", path);
+            error!("Unable to open file at {:?}", path);
             None
         }
     }
 }
 
 fn main() {
-    unsafe {
-        let challenges = challenge_paths(NUM_ROUNDS);
-        let responses = response_paths(NUM_ROUNDS);
-
-        let mut prev = read_subaccumulator::<SmallCeremony>(
-            &try_into_mmap(&challenges[1]).unwrap(),
-            Compressed::No,
-        )
-        .unwrap();
-        for i in 1..NUM_ROUNDS {
-            let now = Instant::now();
-
-            // read next accumulator from challenge file
-            let next = read_subaccumulator::<SmallCeremony>(
-                &try_into_mmap(&challenges[i + 1]).unwrap(),
-                Compressed::No,
-            )
-            .unwrap();
-            // read next challenge hash from response file
-            let response = try_into_mmap(&responses[i]).unwrap();
-            let challenge_hash: [u8; 64] = into_array_unchecked(
-                response
-                    .get(0..64)
-                    .expect("Response file header is 64 bit hash of challenge file"),
+    let arguments = Arguments::parse();
+    arguments.log.init().expect("unable to install the logger");
+    let storage = &arguments.storage;
+    let cancel = cancel_on_interrupt().expect("unable to install the interrupt handler");
+    let registry = Registry::load_or_builtin(storage.state_path(REGISTRY_PATH))
+        .expect("unable to load the registry");
+    if !registry.curve.is_bn254() {
+        error!(
+            "The registry describes a ceremony over {}, verify its rounds with `ppot verify`",
+            registry.curve
+        );
+        process::exit(1);
+    }
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))
+        .expect("unable to load the configuration");
+    // Number of rounds of ceremony to verify
+    let num_rounds = registry.rounds();
+    let max_mem = arguments
+        .max_mem
+        .or_else(|| auto_budget(available_memory()));
+    let requested = arguments.log_powers.unwrap_or(match max_mem {
+        Some(_) => MAX_LOG_POWERS,
+        _ => LOG_POWERS,
+    });
+    let cores = std::thread::available_parallelism()
+        .map(|cores| cores.get())
+        .unwrap_or(1);
+    let tuning = match tune(max_mem, requested, MIN_LOG_POWERS, arguments.threads, cores) {
+        Ok(tuning) => tuning,
+        Err(err) => {
+            error!("{}", err);
+            process::exit(1);
+        }
+    };
+    let log_powers = tuning.log_powers;
+    if arguments.log_powers.is_some() && log_powers < requested {
+        warn!(
+            "Verifying the first 2^{} powers instead of 2^{} to fit in {}",
+            log_powers,
+            requested,
+            max_mem.unwrap_or_default()
+        );
+    }
+    if let Err(err) = rayon::ThreadPoolBuilder::new()
+        .num_threads(tuning.threads)
+        .build_global()
+    {
+        warn!("Unable to set the number of threads: {}", err);
+    }
+    info!(
+        "Verifying 2^{} powers with {} of subaccumulators on {} threads, within a budget of {}",
+        log_powers,
+        ByteSize(verification_size(1 << log_powers)),
+        tuning.threads,
+        match max_mem {
+            Some(budget) => budget.to_string(),
+            _ => String::from("unknown memory"),
+        }
+    );
+    let mut levels = arguments.levels.clone();
+    levels.sort_unstable_by(|a, b| b.cmp(a));
+    levels.dedup();
+    if levels.first().map_or(false, |level| *level > log_powers) {
+        warn!(
+            "Not reporting on the power levels above the 2^{} powers verified",
+            log_powers
+        );
+    }
+    levels.retain(|level| *level < log_powers);
+    let context = Context {
+        storage,
+        input: &arguments.input,
+        snapshots: (!arguments.no_snapshots)
+            .then(|| SnapshotCache::open(storage).expect("unable to open the snapshot cache")),
+        cancel,
+        incremental: arguments.incremental,
+        notifier: config.notifier(),
+        db: StateDb::open_in(storage).expect("unable to open the state database"),
+        registry,
+        config,
+        max_disk: arguments.max_disk,
+        redownload: arguments.redownload,
+        levels,
+    };
+    match log_powers {
+        16 => verify::<{ 1 << 16 }>(&context, num_rounds),
+        17 => verify::<{ 1 << 17 }>(&context, num_rounds),
+        18 => verify::<{ 1 << 18 }>(&context, num_rounds),
+        19 => verify::<{ 1 << 19 }>(&context, num_rounds),
+        20 => verify::<{ 1 << 20 }>(&context, num_rounds),
+        21 => verify::<{ 1 << 21 }>(&context, num_rounds),
+        22 => verify::<{ 1 << 22 }>(&context, num_rounds),
+        _ => unreachable!("The number of powers is checked by the argument parser."),
+    }
+}
+
+/// Reads the subaccumulator of `N` powers from the challenge file at `path`, loading it from its
+/// snapshot in `snapshots` if there is one
+fn read_challenge<const N: usize>(
+    path: &Path,
+    input: &InputOptions,
+    snapshots: Option<&SnapshotCache>,
+) -> Result<Accumulator<SmallCeremony<N>>> {
+    let read = || {
+        let challenge = try_open(path, input)
+            .ok_or_else(|| anyhow!("Unable to open {:?}", path))?
+            .into_mmap()?;
+        read_subaccumulator::<SmallCeremony<N>>(&challenge, Compressed::No)
+            .map_err(|err| anyhow!("Unable to read the subaccumulator: {:?}", err))
+    };
+    match snapshots {
+        Some(snapshots) => snapshots.load_or_else(path, N, read),
+        _ => read(),
+    }
+}
+
+/// Verifies that the response file at `response` transforms `prev` into the subaccumulator of the
+/// challenge file at `next`
+fn verify_round<const N: usize>(
+    context: &Context,
+    prev: Accumulator<SmallCeremony<N>>,
+    response: &Path,
+    next: &Path,
+) -> Result<Accumulator<SmallCeremony<N>>> {
+    let Context { input, .. } = context;
+    // read next accumulator from challenge file
+    let next = read_challenge::<N>(next, input, context.snapshots.as_ref())?;
+    // read next challenge hash from response file
+    let response = try_open(response, input)
+        .ok_or_else(|| anyhow!("Unable to open {:?}", response))?
+        .into_mmap()?;
+    let challenge_hash = Hash64::from_header(&response)
+        .ok_or_else(|| anyhow!("The response file is too short to hold a hash"))?;
+    // read proof from response file
+    let proof =
+        read_kzg_proof(&response).map_err(|err| anyhow!("Unable to read the proof: {:?}", err))?;
+    Accumulator::<SmallCeremony<N>>::verify_transform(
+        prev,
+        next,
+        challenge_hash.0,
+        proof.cast_to_subceremony(),
+    )
+    .map_err(|err| anyhow!("{:?}", err))
+}
+
+/// Verifies the round of the files at `challenge`, `response` and `next` on the first
+/// `2^log_powers` powers only
+fn verify_level(
+    context: &Context,
+    log_powers: u32,
+    challenge: &Path,
+    response: &Path,
+    next: &Path,
+) -> Result {
+    /// Verifies the round on the first `N` powers
+    fn verify_prefix<const N: usize>(
+        context: &Context,
+        challenge: &Path,
+        response: &Path,
+        next: &Path,
+    ) -> Result {
+        let prev = read_challenge::<N>(challenge, context.input, context.snapshots.as_ref())?;
+        verify_round::<N>(context, prev, response, next).map(|_| ())
+    }
+    match log_powers {
+        10 => verify_prefix::<{ 1 << 10 }>(context, challenge, response, next),
+        11 => verify_prefix::<{ 1 << 11 }>(context, challenge, response, next),
+        12 => verify_prefix::<{ 1 << 12 }>(context, challenge, response, next),
+        13 => verify_prefix::<{ 1 << 13 }>(context, challenge, response, next),
+        14 => verify_prefix::<{ 1 << 14 }>(context, challenge, response, next),
+        15 => verify_prefix::<{ 1 << 15 }>(context, challenge, response, next),
+        16 => verify_prefix::<{ 1 << 16 }>(context, challenge, response, next),
+        17 => verify_prefix::<{ 1 << 17 }>(context, challenge, response, next),
+        18 => verify_prefix::<{ 1 << 18 }>(context, challenge, response, next),
+        19 => verify_prefix::<{ 1 << 19 }>(context, challenge, response, next),
+        20 => verify_prefix::<{ 1 << 20 }>(context, challenge, response, next),
+        21 => verify_prefix::<{ 1 << 21 }>(context, challenge, response, next),
+        _ => unreachable!("The power levels are below the number of powers verified."),
+    }
+}
+
+/// Records the outcome of `round` at every power level of `context`, given the `error` of its
+/// verification on all `2^log_powers` powers, verifying the levels on their own where needed, see
+/// [`verify_levels`]
+fn record_levels(
+    context: &Context,
+    round: usize,
+    log_powers: u32,
+    error: Option<&str>,
+    files: [&Path; 3],
+) {
+    let [challenge, response, next] = files;
+    let outcomes = verify_levels(log_powers, error, &context.levels, |level| {
+        let verification = verify_level(context, level, challenge, response, next);
+        match &verification {
+            Ok(()) => info!("Verified round {:?} on its first 2^{} powers", round, level),
+            Err(err) => warn!(
+                "Round {:?} fails on its first 2^{} powers too: {}",
+                round, level, err
+            ),
+        }
+        verification
+    });
+    for (level, error) in outcomes {
+        if let Err(err) = context.db.record_level(round, level, error.as_deref()) {
+            warn!(
+                "Unable to record the verification of round {:?} on 2^{} powers: {}",
+                round, level, err
             );
-            // read proof from response file
-            let proof = read_kzg_proof(&response).unwrap();
-            // verify
-            prev = match Accumulator::<SmallCeremony>::verify_transform(
-                prev,
-                next,
-                challenge_hash,
-                proof.cast_to_subceremony(),
-            ) {
+        }
+    }
+}
+
+/// Quarantines the files around a failed round which break the hash chain and downloads them
+/// again, returning `false` if every file matches the hash chain
+fn repair(context: &Context, files: &[&Path]) -> Result<bool> {
+    let Context {
+        storage,
+        input,
+        cancel,
+        db,
+        registry,
+        config,
+        ..
+    } = context;
+    let links = check_chain(storage, input, db, files)?;
+    let options = DownloadOptions {
+        hash: true,
+        hash_dir: Some(storage.state_dir()),
+        cancel: cancel.clone(),
+        http: config.http.clone(),
+        ..Default::default()
+    };
+    for (hashed, asserted_by) in &links {
+        warn!(
+            "The hash of {:?} does not match the hash asserted by {:?}",
+            hashed, asserted_by
+        );
+        redownload(storage, config, db, registry, hashed, &options)?;
+    }
+    Ok(!links.is_empty())
+}
+
+/// Starts downloading the files of the rounds from `first_round` to `num_rounds` within the disk
+/// budget of `context`, returning the window tracking them
+fn prefetch(
+    context: &Context,
+    first_round: usize,
+    num_rounds: usize,
+    budget: ByteSize,
+) -> Result<Arc<Window>> {
+    let Context {
+        storage,
+        cancel,
+        registry,
+        config,
+        ..
+    } = context;
+    let files = round_files(registry, first_round..num_rounds)
+        .into_iter()
+        .map(|file| {
+            Ok(PrefetchFile {
+                path: storage.path(&file.path),
+                urls: file
+                    .urls()
+                    .map(|url| config.resolve_url(url))
+                    .collect::<Result<_>>()?,
+                size: file.size,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let window = Arc::new(Window::new(budget.0));
+    spawn_prefetch(
+        window.clone(),
+        files,
+        DownloadOptions {
+            hash: true,
+            hash_dir: Some(storage.state_dir()),
+            cancel: cancel.clone(),
+            http: config.http.clone(),
+            ..Default::default()
+        },
+    )?;
+    info!("Keeping the ceremony files within {} of disk", budget);
+    Ok(window)
+}
+
+/// Hashes the challenge file and the response file of a verified round, records the links of the
+/// hash chain they take part in and deletes them from `window`. The next challenge file is kept,
+/// the next round starts from it.
+fn evict(
+    context: &Context,
+    window: &Window,
+    challenge: &Path,
+    response: &Path,
+    next: &Path,
+) -> Result {
+    let Context {
+        storage,
+        input,
+        notifier,
+        db,
+        ..
+    } = context;
+    for (hashed, asserted_by) in check_chain(storage, input, db, &[challenge, response, next])? {
+        error!(
+            "The hash of {:?} does not match the hash asserted by {:?}",
+            hashed, asserted_by
+        );
+        notifier.notify_blocking(&Event::HashMismatch {
+            hashed: hashed.display().to_string(),
+            asserted_by: asserted_by.display().to_string(),
+        });
+    }
+    window.remove(challenge)?;
+    window.remove(response)
+}
+
+/// Verifies the first `num_rounds` rounds of the ceremony on subaccumulators of `N` powers,
+/// skipping the rounds recorded in the verification checkpoint if the run is incremental.
+fn verify<const N: usize>(context: &Context, num_rounds: usize) {
+    let Context {
+        storage,
+        input,
+        cancel,
+        incremental,
+        notifier,
+        db,
+        ..
+    } = context;
+    let snapshots = context.snapshots.as_ref();
+    let challenges = storage.challenge_paths(num_rounds);
+    let responses = storage.response_paths(num_rounds);
+    let log_powers = N.trailing_zeros();
+    let checkpoint_path = storage.state_path(CHECKPOINT_PATH);
+
+    let mut checkpoint = match Checkpoint::load(&checkpoint_path) {
+        Ok(Some(checkpoint))
+            if *incremental
+                && checkpoint.next_round <= num_rounds
+                && checkpoint.resumes(log_powers, &challenges[checkpoint.next_round]) =>
+        {
+            info!(
+                "Resuming from round {:?}, {:?} rounds were verified before",
+                checkpoint.next_round,
+                checkpoint.next_round - 1
+            );
+            checkpoint
+        }
+        Ok(_) if *incremental => {
+            warn!("No matching verification checkpoint, verifying every round");
+            Checkpoint::new(log_powers)
+        }
+        Err(err) if *incremental => {
+            warn!("Unable to load the verification checkpoint: {}", err);
+            Checkpoint::new(log_powers)
+        }
+        _ => Checkpoint::new(log_powers),
+    };
+    let first_round = checkpoint.next_round;
+    if first_round >= num_rounds {
+        info!("All {:?} rounds are already verified", num_rounds - 1);
+        return;
+    }
+    let window = context.max_disk.map(|budget| {
+        prefetch(context, first_round, num_rounds, budget).expect("unable to start the downloads")
+    });
+    let wait_ready = |path: &Path| {
+        if let Some(window) = &window {
+            if let Err(err) = window.wait_ready(path) {
+                error!("{}", err);
+                process::exit(1);
+            }
+        }
+    };
+
+    wait_ready(&challenges[first_round]);
+    let mut prev = read_challenge::<N>(&challenges[first_round], input, snapshots)
+        .expect("unable to read the first challenge file");
+    for i in first_round..num_rounds {
+        if cancel.is_cancelled() {
+            warn!("Interrupted before verifying round {:?}", i);
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        wait_ready(&responses[i]);
+        wait_ready(&challenges[i + 1]);
+        let now = Instant::now();
+
+        let mut retried = false;
+        let (accumulator, error) = loop {
+            match verify_round::<N>(context, prev, &responses[i], &challenges[i + 1]) {
                 Ok(accumulator) => {
-                    println!("Verified round {:?} in {:?}", i, now.elapsed());
-                    accumulator
+                    info!("Verified round {:?} in {:?}", i, now.elapsed());
+                    break (accumulator, None);
                 }
                 Err(e) => {
-                    println!("Verification error {:?} occurred checking round {:?}", e, i);
+                    error!("Verification error {} occurred checking round {:?}", e, i);
+                    if context.redownload && !retried {
+                        let mut files =
+                            vec![challenges[i].as_path(), &responses[i], &challenges[i + 1]];
+                        files.extend(responses.get(i + 1).map(PathBuf::as_path));
+                        match repair(context, &files) {
+                            Ok(true) => {
+                                info!("Verifying round {:?} again", i);
+                                retried = true;
+                                prev = read_challenge::<N>(&challenges[i], input, snapshots)
+                                    .expect("unable to read the challenge file");
+                                continue;
+                            }
+                            Ok(false) => warn!(
+                                "The files of round {:?} match the hash chain, not downloading them again",
+                                i
+                            ),
+                            Err(err) => error!("Unable to download the files of round {:?} again: {}", i, err),
+                        }
+                    }
+                    let error = e.to_string();
+                    notifier.notify_blocking(&Event::RoundFailed {
+                        round: i,
+                        error: error.clone(),
+                    });
                     // We continue with verification anyway, try just using the unverified next subaccumulator.
                     // This makes sense because it helps us to detect individual corrupted files.
-                    read_subaccumulator::<SmallCeremony>(
-                        &try_into_mmap(&challenges[i + 1]).unwrap(),
-                        Compressed::No,
-                    )
-                    .unwrap()
+                    break (
+                        read_challenge::<N>(&challenges[i + 1], input, snapshots)
+                            .expect("unable to read the next challenge file"),
+                        Some(error),
+                    );
                 }
-            };
+            }
+        };
+        prev = accumulator;
+        if let Err(err) = db.record_round(i, log_powers, error.as_deref()) {
+            warn!(
+                "Unable to record the verification of round {:?}: {}",
+                i, err
+            );
+        }
+        if !context.levels.is_empty() {
+            record_levels(
+                context,
+                i,
+                log_powers,
+                error.as_deref(),
+                [&challenges[i], &responses[i], &challenges[i + 1]],
+            );
+        }
+        checkpoint.record(
+            i,
+            error.is_none(),
+            Hash64::read_header(&challenges[i + 1]).ok(),
+        );
+        if let Err(err) = checkpoint.save(&checkpoint_path) {
+            warn!("Unable to save the verification checkpoint: {}", err);
+        }
+        if let Some(window) = &window {
+            if let Err(err) = evict(
+                context,
+                window,
+                &challenges[i],
+                &responses[i],
+                &challenges[i + 1],
+            ) {
+                error!("Unable to free the files of round {:?}: {}", i, err);
+                process::exit(1);
+            }
         }
     }
+    notifier.notify_blocking(&Event::RunComplete {
+        verified: num_rounds - 1 - checkpoint.failed_rounds.len(),
+        failed_rounds: checkpoint.failed_rounds,
+    });
 }
@@ -0,0 +1,166 @@
+//! Hash Cache
+//!
+//! Hashes are cached by path together with the size and modification time the file had when it
+//! was hashed. A file which was downloaded again or repaired since gets a different size or
+//! modification time, so its cached hashes are ignored and computed again instead of silently
+//! trusting a stale `*_hash` file.
+
+use crate::{atomic, HashAlgorithm, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path, time::SystemTime};
+
+/// Default location of the hash cache
+pub const HASH_CACHE_PATH: &str = "hash_cache.json";
+
+/// Cache Entry
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct CacheEntry {
+    /// Size of the file when it was hashed
+    pub size: u64,
+
+    /// Modification time of the file when it was hashed
+    pub modified: SystemTime,
+
+    /// Hex-encoded hashes of the file by algorithm name
+    pub hashes: BTreeMap<String, String>,
+}
+
+/// Hash Cache
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct HashCache {
+    /// Cache entries by file path
+    pub entries: BTreeMap<String, CacheEntry>,
+}
+
+impl HashCache {
+    /// Loads the hash cache from the JSON file at `path`, starting from an empty cache if the
+    /// file does not exist.
+    #[inline]
+    pub fn load_or_default<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the hash cache as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Returns the `algorithm` hash of the file at `path` if it was cached while the file had its
+    /// current size and modification time.
+    #[inline]
+    pub fn get<P>(&self, path: P, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let entry = match self.entries.get(path.to_string_lossy().as_ref()) {
+            Some(entry) => entry,
+            _ => return Ok(None),
+        };
+        let metadata = fs::metadata(path)?;
+        if entry.size != metadata.len() || entry.modified != metadata.modified()? {
+            return Ok(None);
+        }
+        match entry.hashes.get(algorithm.name()) {
+            Some(hash) => Ok(Some(hex::decode(hash)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Records `hash` as the `algorithm` hash of the file at `path` in its current state, dropping
+    /// the hashes cached for an earlier state of the file.
+    #[inline]
+    pub fn insert<P>(&mut self, path: P, algorithm: HashAlgorithm, hash: &[u8]) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let metadata = fs::metadata(path)?;
+        let (size, modified) = (metadata.len(), metadata.modified()?);
+        let entry = self
+            .entries
+            .entry(path.to_string_lossy().into())
+            .or_insert_with(|| CacheEntry {
+                size,
+                modified,
+                hashes: BTreeMap::new(),
+            });
+        if entry.size != size || entry.modified != modified {
+            *entry = CacheEntry {
+                size,
+                modified,
+                hashes: BTreeMap::new(),
+            };
+        }
+        entry
+            .hashes
+            .insert(algorithm.name().into(), hex::encode(hash));
+        Ok(())
+    }
+
+    /// Adopts the `algorithm` hash of the file at `path` saved to `hash_path` if it is not older
+    /// than the file itself, meaning it was computed after the last change to the file. Returns the
+    /// adopted hash.
+    #[inline]
+    pub fn adopt<P, H>(
+        &mut self,
+        path: P,
+        hash_path: H,
+        algorithm: HashAlgorithm,
+    ) -> Result<Option<Vec<u8>>>
+    where
+        P: AsRef<Path>,
+        H: AsRef<Path>,
+    {
+        let (path, hash_path) = (path.as_ref(), hash_path.as_ref());
+        let (file, hash_file) = match (fs::metadata(path), fs::metadata(hash_path)) {
+            (Ok(file), Ok(hash_file)) => (file, hash_file),
+            _ => return Ok(None),
+        };
+        if hash_file.modified()? < file.modified()? {
+            return Ok(None);
+        }
+        let hash = fs::read(hash_path)?;
+        if hash.len() != algorithm.output_len() {
+            return Ok(None);
+        }
+        self.insert(path, algorithm, &hash)?;
+        Ok(Some(hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that cached hashes are dropped once the file changes.
+    #[test]
+    fn stale_entries_are_ignored() {
+        let path = std::env::temp_dir().join("ppot-verifier-cache-test");
+        let path = path.to_str().unwrap();
+        fs::write(path, b"contents").unwrap();
+        let mut cache = HashCache::default();
+        cache
+            .insert(path, HashAlgorithm::Blake2b, &[1; 64])
+            .unwrap();
+        assert_eq!(
+            cache.get(path, HashAlgorithm::Blake2b).unwrap(),
+            Some(vec![1; 64])
+        );
+        assert_eq!(cache.get(path, HashAlgorithm::Sha256).unwrap(), None);
+        fs::write(path, b"new contents").unwrap();
+        assert_eq!(cache.get(path, HashAlgorithm::Blake2b).unwrap(), None);
+        fs::remove_file(path).unwrap();
+    }
+}
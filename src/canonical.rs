@@ -0,0 +1,226 @@
+//! Canonical Reports
+//!
+//! The [`Report`] of the pipeline holds the state of the machine it was collected on: files being
+//! downloaded, sizes of partial files and timings. A [`CanonicalReport`] only keeps what follows
+//! from the ceremony files themselves, the hash of every file, the hash chain and the verification
+//! of every round, and serializes it to a single form: compact JSON with the keys of every object
+//! sorted, the rounds and their files in order and a trailing newline. Two verifiers who saw the
+//! same files produce byte-identical canonical reports, which can be compared, hashed and signed.
+//!
+//! The format is versioned by [`CANONICAL_VERSION`], bumped on any change to the fields or their
+//! encoding, and readers reject reports of other versions.
+
+use crate::{
+    db::ChainStatus,
+    hash::Hash64,
+    into_array_unchecked,
+    status::{FileState, Report, RoundState, Verification},
+    Result,
+};
+use anyhow::ensure;
+use blake2::{Blake2b512, Digest};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+/// Version of the canonical report format
+pub const CANONICAL_VERSION: u32 = 1;
+
+/// Canonical File
+///
+/// The fields are declared in alphabetical order, which is the order they are serialized in.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanonicalFile {
+    /// Blake2b hash of the file, if it has been hashed
+    pub blake2b: Option<Hash64>,
+
+    /// Local file name
+    pub path: String,
+}
+
+impl From<&FileState> for CanonicalFile {
+    #[inline]
+    fn from(file: &FileState) -> Self {
+        Self {
+            blake2b: file.blake2b,
+            path: file.path.clone(),
+        }
+    }
+}
+
+/// Canonical Round
+///
+/// The fields are declared in alphabetical order, which is the order they are serialized in.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanonicalRound {
+    /// Status of the links of the hash chain between the files of the round, if they were checked
+    pub chain: Option<ChainStatus>,
+
+    /// Challenge file the round starts from, response file and challenge file it produces
+    pub files: Vec<CanonicalFile>,
+
+    /// Index of the round
+    pub round: usize,
+
+    /// Verification of the round
+    pub verification: Verification,
+}
+
+impl From<&RoundState> for CanonicalRound {
+    #[inline]
+    fn from(round: &RoundState) -> Self {
+        Self {
+            chain: round.chain,
+            files: round.files.iter().map(CanonicalFile::from).collect(),
+            round: round.round,
+            verification: round.verification,
+        }
+    }
+}
+
+/// Canonical Report
+///
+/// The fields are declared in alphabetical order, which is the order they are serialized in.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct CanonicalReport {
+    /// Base-two logarithm of the number of powers verified in each round, if any was verified
+    pub log_powers: Option<u32>,
+
+    /// Every round, sorted by index
+    pub rounds: Vec<CanonicalRound>,
+
+    /// Version of the format, see [`CANONICAL_VERSION`]
+    pub version: u32,
+}
+
+impl CanonicalReport {
+    /// Returns the canonical form of `report`.
+    #[inline]
+    pub fn new(report: &Report) -> Self {
+        let mut rounds = report
+            .rounds
+            .iter()
+            .map(CanonicalRound::from)
+            .collect::<Vec<_>>();
+        rounds.sort_by_key(|round| round.round);
+        Self {
+            log_powers: report.status.log_powers,
+            rounds,
+            version: CANONICAL_VERSION,
+        }
+    }
+
+    /// Serializes `self` to its canonical bytes.
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = serde_json::to_vec(self).expect("Canonical reports always serialize.");
+        bytes.push(b'\n');
+        bytes
+    }
+
+    /// Parses a canonical report from `bytes`, checking that they are in canonical form, so that
+    /// their hash is the [`digest`](Self::digest) of the report.
+    #[inline]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let report = serde_json::from_slice::<Self>(bytes)?;
+        ensure!(
+            report.version == CANONICAL_VERSION,
+            "Unsupported canonical report version {}, expected {}.",
+            report.version,
+            CANONICAL_VERSION
+        );
+        ensure!(
+            report.to_bytes() == bytes,
+            "The report is not in canonical form."
+        );
+        Ok(report)
+    }
+
+    /// Returns the Blake2b hash of the canonical bytes of `self`.
+    #[inline]
+    pub fn digest(&self) -> Hash64 {
+        Hash64(into_array_unchecked(Blake2b512::digest(self.to_bytes())))
+    }
+
+    /// Loads the canonical report saved at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status::Status;
+
+    /// Checks that reports differing only in the state of the machine have the same canonical
+    /// bytes, with sorted keys, and that only canonical bytes are parsed back.
+    #[test]
+    fn canonical_reports_agree() {
+        let file = |path: &str, size, blake2b| FileState {
+            path: path.into(),
+            size,
+            expected_size: Some(9),
+            downloading: false,
+            blake2b,
+        };
+        let round = |round, size| RoundState {
+            round,
+            files: vec![
+                file("challenge_0001", size, Some(Hash64([1; 64]))),
+                file("response_0002", None, None),
+            ],
+            chain: Some(ChainStatus::Match),
+            verification: Verification::Verified,
+            profile: None,
+            signature: None,
+            levels: Default::default(),
+        };
+        let status = Status {
+            files: 2,
+            downloaded: 1,
+            downloading: 0,
+            hashed: 1,
+            hashing: false,
+            rounds: 2,
+            verified: 2,
+            failed_rounds: Vec::new(),
+            log_powers: Some(19),
+        };
+        let mine = Report {
+            status: status.clone(),
+            rounds: vec![round(1, Some(9)), round(2, Some(9))],
+        };
+        let theirs = Report {
+            status: Status {
+                downloaded: 0,
+                hashing: true,
+                ..status
+            },
+            rounds: vec![round(2, None), round(1, Some(4))],
+        };
+        let canonical = CanonicalReport::new(&mine);
+        assert_eq!(
+            canonical.to_bytes(),
+            CanonicalReport::new(&theirs).to_bytes()
+        );
+        assert_eq!(canonical.digest(), CanonicalReport::new(&theirs).digest());
+        let bytes = canonical.to_bytes();
+        let text = String::from_utf8(bytes.clone()).unwrap();
+        assert!(text.starts_with(
+            "{\"log_powers\":19,\"rounds\":[{\"chain\":\"match\",\"files\":[{\"blake2b\":\""
+        ));
+        assert!(text.ends_with("\"verification\":\"verified\"}],\"version\":1}\n"));
+        assert_eq!(CanonicalReport::from_bytes(&bytes).unwrap(), canonical);
+        let pretty = serde_json::to_vec_pretty(&canonical).unwrap();
+        assert!(CanonicalReport::from_bytes(&pretty).is_err());
+        let future = text.replace("\"version\":1}", "\"version\":2}");
+        assert!(CanonicalReport::from_bytes(future.as_bytes()).is_err());
+    }
+}
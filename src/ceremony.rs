@@ -0,0 +1,322 @@
+//! Ceremonies
+//!
+//! A [`Ceremony`] describes everything the verifier needs to know about a powers of tau ceremony:
+//! how its files are named on disk, where the list of its files comes from, how its points are
+//! serialized, which curve it runs on and how a response is checked against its challenge. The
+//! commands only go through this trait, so verifying another ceremony is a matter of implementing
+//! it rather than forking the binaries.
+//!
+//! Every ceremony descending from the Zcash powers of tau shares the same file format and the same
+//! transform verification, which only depend on the curve, so the provided methods cover them and
+//! an implementation only gives its curve, its size, its naming and its source of files. The
+//! perpetual powers of tau is [`Ppot`], and any other ceremony whose files are listed in a
+//! registry file is a [`Listed`] ceremony.
+
+use crate::{
+    accumulator::Accumulator,
+    curve::{Curve, CurveKind},
+    format::{public_key_size, Layout, CEREMONY_LOG_POWERS},
+    hash::Hash64,
+    input::InputOptions,
+    pok::{PublicKey, Secret},
+    profile::Profile,
+    progress::ProgressSink,
+    registry::Registry,
+    transform, Result,
+};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use futures::future::{self, BoxFuture, FutureExt};
+use reqwest::Client;
+use std::path::Path;
+
+/// Powers of Tau Ceremony
+pub trait Ceremony: Send + Sync {
+    /// Returns the name of the ceremony.
+    fn name(&self) -> &str;
+
+    /// Returns the curve of the ceremony.
+    fn curve(&self) -> CurveKind;
+
+    /// Returns the base-two logarithm of the number of powers of tau in the files of the ceremony.
+    fn log_powers(&self) -> u32;
+
+    /// Lists the files of the ceremony published so far.
+    fn discover<'c>(&'c self, client: &'c Client) -> BoxFuture<'c, Result<Registry>>;
+
+    /// Returns the number of powers of tau in the files of the ceremony.
+    #[inline]
+    fn powers(&self) -> usize {
+        1 << self.log_powers()
+    }
+
+    /// Returns the local name of the challenge file with `index`, the first one being `0`.
+    #[inline]
+    fn challenge_path(&self, index: usize) -> String {
+        format!("challenge_{:04}", index)
+    }
+
+    /// Returns the local name of the response file of `round`, the first one being `1`.
+    #[inline]
+    fn response_path(&self, round: usize) -> String {
+        format!("response_{:04}", round)
+    }
+
+    /// Returns the layout of the challenge files.
+    #[inline]
+    fn challenge_layout(&self) -> Layout {
+        match self.curve() {
+            CurveKind::Bn254 => Layout::challenge_on::<Bn254>(self.powers()),
+            CurveKind::Bls12_381 => Layout::challenge_on::<Bls12_381>(self.powers()),
+        }
+    }
+
+    /// Returns the layout of the response files.
+    #[inline]
+    fn response_layout(&self) -> Layout {
+        match self.curve() {
+            CurveKind::Bn254 => Layout::response_on::<Bn254>(self.powers()),
+            CurveKind::Bls12_381 => Layout::response_on::<Bls12_381>(self.powers()),
+        }
+    }
+
+    /// Returns the size of the public key ending the response files.
+    #[inline]
+    fn public_key_size(&self) -> usize {
+        public_key_size(self.challenge_layout().field_size)
+    }
+
+    /// Checks the proofs of knowledge of the public key ending `response`, the bytes of a response
+    /// file or of its last bytes, against `challenge_hash`, returning the secrets whose proof is
+    /// invalid.
+    #[inline]
+    fn verify_proofs(&self, response: &[u8], challenge_hash: &Hash64) -> Result<Vec<Secret>> {
+        Ok(match self.curve() {
+            CurveKind::Bn254 => PublicKey::<Bn254>::read(response)?.verify(challenge_hash),
+            CurveKind::Bls12_381 => PublicKey::<Bls12_381>::read(response)?.verify(challenge_hash),
+        })
+    }
+
+    /// Verifies on their first `powers` powers that the `response` file is the transform of the
+    /// `challenge` file, whose hash is `challenge_hash`, recording the time spent in each phase to
+    /// `profile`, if any.
+    #[inline]
+    fn verify_transform(
+        &self,
+        challenge: &[u8],
+        response: &[u8],
+        challenge_hash: &Hash64,
+        powers: usize,
+        profile: Option<&Profile>,
+    ) -> Result {
+        match self.curve() {
+            CurveKind::Bn254 => verify_transform_with::<Bn254>(
+                Accumulator::new(challenge, self.challenge_layout())?,
+                Accumulator::new(response, self.response_layout())?,
+                challenge_hash,
+                powers,
+                profile,
+            ),
+            CurveKind::Bls12_381 => verify_transform_with::<Bls12_381>(
+                Accumulator::new(challenge, self.challenge_layout())?,
+                Accumulator::new(response, self.response_layout())?,
+                challenge_hash,
+                powers,
+                profile,
+            ),
+        }
+    }
+
+    /// Verifies the transform of `round` like [`verify_transform`](Self::verify_transform),
+    /// reporting its result to `progress`.
+    #[inline]
+    #[allow(clippy::too_many_arguments)]
+    fn verify_round(
+        &self,
+        round: usize,
+        challenge: &[u8],
+        response: &[u8],
+        challenge_hash: &Hash64,
+        powers: usize,
+        profile: Option<&Profile>,
+        progress: &dyn ProgressSink,
+    ) -> Result {
+        let result = self.verify_transform(challenge, response, challenge_hash, powers, profile);
+        progress.on_round_verified(round, &result);
+        result
+    }
+
+    /// Reconstructs the `challenge` file following the `response` file, returning its hash.
+    #[inline]
+    fn reconstruct(
+        &self,
+        response: &Path,
+        challenge: &Path,
+        input: &InputOptions,
+    ) -> Result<Hash64> {
+        match self.curve() {
+            CurveKind::Bn254 => {
+                transform::reconstruct::<Bn254>(response, challenge, self.powers(), input)
+            }
+            CurveKind::Bls12_381 => {
+                transform::reconstruct::<Bls12_381>(response, challenge, self.powers(), input)
+            }
+        }
+    }
+}
+
+/// Perpetual Powers of Tau
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Ppot {
+    /// Takes the list of contributions from the GitHub repository instead of the blob listing
+    pub github: bool,
+
+    /// GitHub API token
+    pub token: Option<String>,
+}
+
+impl Ceremony for Ppot {
+    #[inline]
+    fn name(&self) -> &str {
+        "Perpetual Powers of Tau"
+    }
+
+    #[inline]
+    fn curve(&self) -> CurveKind {
+        CurveKind::Bn254
+    }
+
+    #[inline]
+    fn log_powers(&self) -> u32 {
+        CEREMONY_LOG_POWERS
+    }
+
+    #[inline]
+    fn discover<'c>(&'c self, client: &'c Client) -> BoxFuture<'c, Result<Registry>> {
+        Registry::discover(client, self.github, self.token.as_deref()).boxed()
+    }
+}
+
+/// Ceremony Listed by a Registry File
+///
+/// The registry gives the curve, the number of powers and the files of the ceremony, which is not
+/// discovered any further.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Listed {
+    /// Registry of the ceremony
+    pub registry: Registry,
+}
+
+impl Ceremony for Listed {
+    #[inline]
+    fn name(&self) -> &str {
+        "Listed ceremony"
+    }
+
+    #[inline]
+    fn curve(&self) -> CurveKind {
+        self.registry.curve
+    }
+
+    #[inline]
+    fn log_powers(&self) -> u32 {
+        self.registry.powers().trailing_zeros()
+    }
+
+    #[inline]
+    fn discover<'c>(&'c self, _: &'c Client) -> BoxFuture<'c, Result<Registry>> {
+        future::ready(Ok(self.registry.clone())).boxed()
+    }
+}
+
+/// Returns the ceremony of `registry`: [`Ppot`], discovered with `github` and `token`, if the
+/// registry has the curve and the size of the perpetual powers of tau, or else the [`Listed`]
+/// ceremony of the registry.
+#[inline]
+pub fn ceremony_of(registry: &Registry, github: bool, token: Option<String>) -> Box<dyn Ceremony> {
+    if registry.curve.is_bn254() && registry.powers() == Ppot::default().powers() {
+        Box::new(Ppot { github, token })
+    } else {
+        Box::new(Listed {
+            registry: registry.clone(),
+        })
+    }
+}
+
+/// Verifies on their first `powers` powers that `response` is the transform of `challenge`, whose
+/// hash is `challenge_hash`, recording the time spent in each phase to `profile`, if any.
+#[inline]
+fn verify_transform_with<C>(
+    challenge: Accumulator<C>,
+    response: Accumulator<C>,
+    challenge_hash: &Hash64,
+    powers: usize,
+    profile: Option<&Profile>,
+) -> Result
+where
+    C: Curve,
+{
+    let (challenge, response) = match profile {
+        Some(profile) => (
+            challenge.with_profile(profile),
+            response.with_profile(profile),
+        ),
+        _ => (challenge, response),
+    };
+    challenge.verify_transform(&response, challenge_hash, powers)
+}
+
+/// Returns the local names of the first `challenges` challenge files and `responses` response files
+/// of `ceremony`.
+#[inline]
+pub fn file_paths<C>(
+    ceremony: &C,
+    challenges: usize,
+    responses: usize,
+) -> (Vec<String>, Vec<String>)
+where
+    C: Ceremony + ?Sized,
+{
+    (
+        (0..challenges)
+            .map(|i| ceremony.challenge_path(i))
+            .collect(),
+        (1..=responses).map(|i| ceremony.response_path(i)).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{challenge_paths, response_paths};
+
+    /// Checks that the PPoT ceremony names its files as the registry does and picks the listed
+    /// ceremony for registries over other curves or sizes.
+    #[test]
+    fn ceremony_of_registry() {
+        let registry = Registry::builtin();
+        let ceremony = ceremony_of(&registry, false, None);
+        assert_eq!(ceremony.name(), Ppot::default().name());
+        assert_eq!(
+            file_paths(
+                ceremony.as_ref(),
+                registry.challenges.len(),
+                registry.rounds()
+            ),
+            (
+                challenge_paths(registry.challenges.len() - 1),
+                response_paths(registry.rounds())
+            )
+        );
+        assert_eq!(ceremony.public_key_size(), PublicKey::<Bn254>::size());
+        let registry = Registry {
+            curve: CurveKind::Bls12_381,
+            log_powers: Some(21),
+            ..registry
+        };
+        let ceremony = ceremony_of(&registry, false, None);
+        assert_eq!(ceremony.curve(), CurveKind::Bls12_381);
+        assert_eq!(ceremony.powers(), 1 << 21);
+        assert_eq!(ceremony.public_key_size(), PublicKey::<Bls12_381>::size());
+    }
+}
@@ -0,0 +1,112 @@
+//! Verification Checkpoint
+//!
+//! The verifier records the rounds it has verified after each one of them, so that once `ppot
+//! sync` has found new contributions, only their files need to be downloaded and only their
+//! transforms verified. The next run starts from the accumulator of the last challenge file it
+//! verified, read again from that file.
+
+use crate::{atomic, hash::Hash64, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Default path of the verification checkpoint in the state directory
+pub const CHECKPOINT_PATH: &str = "verification.json";
+
+/// Verification Checkpoint
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Checkpoint {
+    /// Base-two logarithm of the number of powers verified in each round
+    pub log_powers: u32,
+
+    /// Next round to verify, from the challenge file with the same index
+    pub next_round: usize,
+
+    /// Rounds whose verification failed
+    pub failed_rounds: Vec<usize>,
+
+    /// Header of the challenge file the next round starts from
+    pub challenge_header: Option<Hash64>,
+}
+
+impl Checkpoint {
+    /// Builds a new [`Checkpoint`] before the first round verified with `2^log_powers` powers.
+    #[inline]
+    pub fn new(log_powers: u32) -> Self {
+        Self {
+            log_powers,
+            next_round: 1,
+            failed_rounds: Vec::new(),
+            challenge_header: None,
+        }
+    }
+
+    /// Loads the checkpoint from the JSON file at `path`, returning `None` if there is none.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Option<Self>>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the checkpoint as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Returns `true` if a run verifying `2^log_powers` powers can resume from `self`, starting
+    /// from the challenge file at `challenge`, which must not have changed since it was recorded.
+    #[inline]
+    pub fn resumes<P>(&self, log_powers: u32, challenge: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.log_powers == log_powers
+            && self.challenge_header.is_some()
+            && Hash64::read_header(challenge).ok() == self.challenge_header
+    }
+
+    /// Records the verification of `round`, after which the next round starts from the challenge
+    /// file whose header is `challenge_header`.
+    #[inline]
+    pub fn record(&mut self, round: usize, verified: bool, challenge_header: Option<Hash64>) {
+        self.failed_rounds.retain(|failed| *failed < round);
+        if !verified {
+            self.failed_rounds.push(round);
+        }
+        self.next_round = round + 1;
+        self.challenge_header = challenge_header;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that checkpoints only resume from the challenge file they were recorded for.
+    #[test]
+    fn resume_from_recorded_challenge() {
+        let path = std::env::temp_dir().join("ppot-verifier-checkpoint-test");
+        let header = Hash64([3; 64]);
+        fs::write(&path, header.0).unwrap();
+        let mut checkpoint = Checkpoint::new(19);
+        assert!(!checkpoint.resumes(19, &path));
+        checkpoint.record(1, false, Some(header));
+        checkpoint.record(2, true, Some(header));
+        assert_eq!(checkpoint.next_round, 3);
+        assert_eq!(checkpoint.failed_rounds, [1]);
+        assert!(checkpoint.resumes(19, &path));
+        assert!(!checkpoint.resumes(18, &path));
+        fs::write(&path, [4; 64]).unwrap();
+        assert!(!checkpoint.resumes(19, &path));
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+//! Checksums
+//!
+//! Azure records the MD5 digest of each blob in its `Content-MD5` property. Comparing it with the
+//! digest of the downloaded file catches a corrupt download right away, long before the Blake2b
+//! hashes of the ceremony are checked.
+
+use crate::Result;
+use anyhow::{anyhow, bail};
+use md5::{Digest, Md5};
+use reqwest::{Client, Method};
+use std::{fs::File, io::Read, path::Path};
+use tokio::task;
+
+/// Name of the header carrying the base64-encoded MD5 digest of a blob
+pub const CONTENT_MD5: &str = "Content-MD5";
+
+/// Size of the buffer used to read files while hashing them
+const BUFFER_SIZE: usize = 1 << 20;
+
+/// Computes the MD5 digest of the file at `path`.
+#[inline]
+pub fn md5_file<P>(path: P) -> Result<[u8; 16]>
+where
+    P: AsRef<Path>,
+{
+    let mut file = File::open(path)?;
+    let mut hasher = Md5::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Fetches the [`CONTENT_MD5`] header of the file at `url` with a [`HEAD`](Method::HEAD) request,
+/// returning `None` if the server does not send one.
+#[inline]
+pub async fn fetch_content_md5(client: &Client, url: &str) -> Result<Option<String>> {
+    let response = client
+        .request(Method::HEAD, url)
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response
+        .headers()
+        .get(CONTENT_MD5)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from))
+}
+
+/// Checks that the file at `path` matches the base64-encoded MD5 digest `content_md5`.
+#[inline]
+pub async fn verify_content_md5<P>(path: P, content_md5: &str) -> Result
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let expected = base64::decode(content_md5)
+        .map_err(|err| anyhow!("Invalid {} '{}': {}", CONTENT_MD5, content_md5, err))?;
+    let owned_path = path.to_owned();
+    let digest = task::spawn_blocking(move || md5_file(owned_path)).await??;
+    if digest[..] != expected[..] {
+        bail!(
+            "{} mismatch for {}: expected {} but the file has {}",
+            CONTENT_MD5,
+            path.display(),
+            content_md5,
+            base64::encode(digest)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks the digest of a small file against the test vector from RFC 1321.
+    #[test]
+    fn md5_of_file() {
+        let path = std::env::temp_dir().join("ppot-verifier-md5-test");
+        std::fs::write(&path, b"abc").unwrap();
+        assert_eq!(
+            hex::encode(md5_file(&path).unwrap()),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
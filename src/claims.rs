@@ -0,0 +1,246 @@
+//! Hash Claims of Reports
+//!
+//! A canonical report published by another verifier claims the Blake2b hash of every file it
+//! hashed. Once its signature is checked, the claims are only worth as much as they agree with the
+//! ceremony files themselves, so every claim is checked against the hash computed locally if the
+//! file was hashed here, and otherwise against the header of the next file of the ceremony, which
+//! holds the hash of the file it builds on. The header is read from disk if the file was
+//! downloaded, or fetched from its URL, only its first 64 bytes being requested.
+
+use crate::{
+    canonical::CanonicalReport,
+    config::Config,
+    db::{ChainStatus, StateDb},
+    hash::Hash64,
+    registry::{Registry, RemoteFile},
+    storage::StorageOptions,
+    transform::fetch_header,
+    HashAlgorithm, Result,
+};
+use core::fmt;
+use reqwest::Client;
+
+/// Hash of a File Claimed by a Report
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Claim {
+    /// Local file name
+    pub path: String,
+
+    /// Claimed Blake2b hash
+    pub blake2b: Hash64,
+}
+
+/// Returns the hashes claimed by `report`, every file once, in the order of the rounds.
+#[inline]
+pub fn claims(report: &CanonicalReport) -> Vec<Claim> {
+    let mut claims = Vec::<Claim>::new();
+    for file in report.rounds.iter().flat_map(|round| &round.files) {
+        if let Some(blake2b) = file.blake2b {
+            if !claims.iter().any(|claim| claim.path == file.path) {
+                claims.push(Claim {
+                    path: file.path.clone(),
+                    blake2b,
+                });
+            }
+        }
+    }
+    claims
+}
+
+/// Returns the file of `registry` whose header holds the hash of the file named `path`:
+/// `responses[i]` builds on `challenges[i]` and `challenges[i + 1]` on `responses[i]`.
+#[inline]
+pub fn asserted_by<'r>(registry: &'r Registry, path: &str) -> Option<&'r RemoteFile> {
+    if let Some(round) = registry
+        .challenges
+        .iter()
+        .position(|file| file.path == path)
+    {
+        return registry.responses.get(round);
+    }
+    let round = registry
+        .responses
+        .iter()
+        .position(|file| file.path == path)?;
+    registry.challenges.get(round + 1)
+}
+
+/// Returns the Blake2b hash of the file named `name`, recorded in `db` or saved to the state
+/// directory of `storage`, if it was computed.
+#[inline]
+pub fn known_hash(storage: &StorageOptions, db: &StateDb, name: &str) -> Result<Option<Hash64>> {
+    Ok(match db.hash(name, HashAlgorithm::Blake2b)? {
+        Some(hash) => Some(Hash64::try_from(hash.as_slice())?),
+        _ => Hash64::load(storage.hash_path(storage.path(name), HashAlgorithm::Blake2b)).ok(),
+    })
+}
+
+/// Source of the Hash a Claim is Checked Against
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum HashSource {
+    /// Hash computed locally
+    Computed,
+
+    /// Header of the file with this name, on disk
+    Header(String),
+
+    /// Header of the file served at this URL
+    RemoteHeader(String),
+}
+
+impl fmt::Display for HashSource {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Computed => write!(f, "local hash"),
+            Self::Header(path) => write!(f, "header of {}", path),
+            Self::RemoteHeader(url) => write!(f, "header of {}", url),
+        }
+    }
+}
+
+/// Outcome of the Check of a Claim
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ClaimCheck {
+    /// Checked claim
+    pub claim: Claim,
+
+    /// Hash the claim was checked against and where it comes from, if one was found
+    pub found: Option<(Hash64, HashSource)>,
+}
+
+impl ClaimCheck {
+    /// Returns whether the claimed hash matches the hash it was checked against.
+    #[inline]
+    pub fn status(&self) -> ChainStatus {
+        match &self.found {
+            Some((hash, _)) if *hash == self.claim.blake2b => ChainStatus::Match,
+            Some(_) => ChainStatus::Mismatch,
+            _ => ChainStatus::Missing,
+        }
+    }
+}
+
+/// Checks every claim of `report` against the hashes computed locally and the headers of the
+/// files of `registry` on disk, fetching the headers of the files which are not on disk with
+/// `remote` if given.
+#[inline]
+pub async fn check_claims(
+    storage: &StorageOptions,
+    db: &StateDb,
+    registry: &Registry,
+    report: &CanonicalReport,
+    remote: Option<(&Client, &Config)>,
+) -> Result<Vec<ClaimCheck>> {
+    let mut checks = Vec::new();
+    for claim in claims(report) {
+        let found = match known_hash(storage, db, &claim.path)? {
+            Some(hash) => Some((hash, HashSource::Computed)),
+            _ => match asserted_by(registry, &claim.path) {
+                Some(file) if storage.path(&file.path).exists() => Some((
+                    Hash64::read_header(storage.path(&file.path))?,
+                    HashSource::Header(file.path.clone()),
+                )),
+                Some(file) => match remote {
+                    Some((client, config)) => {
+                        let url = config.resolve_url(&file.url)?;
+                        Some((
+                            fetch_header(client, &url).await?,
+                            HashSource::RemoteHeader(file.url.clone()),
+                        ))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+        };
+        checks.push(ClaimCheck { claim, found });
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        canonical::{CanonicalFile, CanonicalRound, CANONICAL_VERSION},
+        status::Verification,
+    };
+
+    /// Checks that the claims are read once per file and checked against the local hashes and the
+    /// headers of the files building on them.
+    #[test]
+    fn claims_are_checked() {
+        let dir = std::env::temp_dir().join(format!("ppot-claims-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let storage = StorageOptions {
+            dir: dir.clone(),
+            state_dir: Some(dir.join("state")),
+            cache_dir: Some(dir.join("cache")),
+        };
+        storage.create_dirs().unwrap();
+        let db = StateDb::open_in(&storage).unwrap();
+        let file = |path: &str, byte: Option<u8>| CanonicalFile {
+            blake2b: byte.map(|byte| Hash64([byte; 64])),
+            path: path.into(),
+        };
+        let round = |round, files| CanonicalRound {
+            chain: None,
+            files,
+            round,
+            verification: Verification::Verified,
+        };
+        let report = CanonicalReport {
+            log_powers: Some(19),
+            rounds: vec![
+                round(
+                    1,
+                    vec![
+                        file("challenge_0001", Some(1)),
+                        file("response_0001", Some(2)),
+                        file("challenge_0002", Some(3)),
+                    ],
+                ),
+                round(
+                    2,
+                    vec![
+                        file("challenge_0002", Some(3)),
+                        file("response_0002", None),
+                        file("challenge_0003", None),
+                    ],
+                ),
+            ],
+            version: CANONICAL_VERSION,
+        };
+        let registry = Registry::builtin();
+        assert_eq!(
+            asserted_by(&registry, "challenge_0002").map(|file| file.path.as_str()),
+            Some("response_0003")
+        );
+        assert_eq!(
+            asserted_by(&registry, "response_0001").map(|file| file.path.as_str()),
+            Some("challenge_0001")
+        );
+        db.record_hash("challenge_0001", HashAlgorithm::Blake2b, &[1; 64])
+            .unwrap();
+        std::fs::write(dir.join("response_0003"), [4; 80]).unwrap();
+        let checks = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(check_claims(&storage, &db, &registry, &report, None))
+            .unwrap();
+        let statuses = checks
+            .iter()
+            .map(|check| (check.claim.path.as_str(), check.status()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            [
+                ("challenge_0001", ChainStatus::Match),
+                ("response_0001", ChainStatus::Missing),
+                ("challenge_0002", ChainStatus::Mismatch),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
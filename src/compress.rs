@@ -0,0 +1,566 @@
+//! Compressed Storage
+//!
+//! The points of the ceremony files only compress by 10 to 15%, but over the whole ceremony that
+//! still saves terabytes, so the stored files can be kept compressed with zstd, next to their name
+//! in [`compressed_path`]. They are written in the seekable format of zstd: independent frames of
+//! [`FRAME_SIZE`] decompressed bytes followed by a [`SeekTable`] in a skippable frame, so that any
+//! range of a file is read by decompressing only the frames holding it, and so that `zstd -d` still
+//! decompresses the whole file.
+//!
+//! An [`Input`](crate::input::Input) whose file is missing reads through its compressed copy, so
+//! the hashers, the scrubber and the verifier do not need to know how a file is stored.
+
+use crate::{atomic, calculate_hashes_streaming, hash::Hash64, HashAlgorithm, Result};
+use anyhow::{bail, ensure};
+use core::ops::Range;
+use memmap::Mmap;
+use rayon::prelude::*;
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// Default number of decompressed bytes of a frame
+pub const FRAME_SIZE: usize = 4 << 20;
+
+/// Largest number of decompressed bytes of a frame
+pub const MAX_FRAME_SIZE: usize = 1 << 30;
+
+/// Default compression level
+pub const COMPRESSION_LEVEL: i32 = 3;
+
+/// Magic number of the skippable frame holding the seek table
+const SKIPPABLE_MAGIC: u32 = 0x184d_2a5e;
+
+/// Magic number ending the seek table
+const SEEKABLE_MAGIC: u32 = 0x8f92_eab1;
+
+/// Size of the header of a skippable frame
+const SKIPPABLE_HEADER_SIZE: usize = 8;
+
+/// Size of the footer of the seek table
+const FOOTER_SIZE: usize = 9;
+
+/// Flag of the seek table descriptor telling that the entries hold checksums
+const CHECKSUM_FLAG: u8 = 0x80;
+
+/// Reserved bits of the seek table descriptor
+const RESERVED_BITS: u8 = 0x7c;
+
+/// Number of frames compressed at once by every thread
+const FRAMES_PER_THREAD: usize = 4;
+
+/// Returns the path of the compressed copy of the file at `path`.
+#[inline]
+pub fn compressed_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut compressed_path = path.as_ref().as_os_str().to_owned();
+    compressed_path.push(".zst");
+    compressed_path.into()
+}
+
+/// Returns `true` if the file at `path` is stored, as is or compressed.
+#[inline]
+pub fn is_stored<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    path.exists() || compressed_path(path).exists()
+}
+
+/// Seek Table of a Compressed File
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SeekTable {
+    /// Offsets of the frames in the compressed file, followed by the offset of the seek table
+    compressed: Vec<u64>,
+
+    /// Offsets of the frames in the decompressed file, followed by its length
+    decompressed: Vec<u64>,
+}
+
+impl SeekTable {
+    /// Builds an empty seek table.
+    #[inline]
+    fn new() -> Self {
+        Self {
+            compressed: vec![0],
+            decompressed: vec![0],
+        }
+    }
+
+    /// Appends a frame of `compressed` bytes decompressing to `decompressed` bytes.
+    #[inline]
+    fn push(&mut self, compressed: u32, decompressed: u32) {
+        let (end, decompressed_end) = (self.compressed_len(), self.len());
+        self.compressed.push(end + u64::from(compressed));
+        self.decompressed
+            .push(decompressed_end + u64::from(decompressed));
+    }
+
+    /// Returns the number of frames.
+    #[inline]
+    pub fn frames(&self) -> usize {
+        self.compressed.len() - 1
+    }
+
+    /// Returns the length of the decompressed file.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.decompressed[self.frames()]
+    }
+
+    /// Returns `true` if the decompressed file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the length of the frames of the compressed file, without the seek table.
+    #[inline]
+    pub fn compressed_len(&self) -> u64 {
+        self.compressed[self.frames()]
+    }
+
+    /// Returns the range of the compressed file holding `frame`.
+    #[inline]
+    pub fn compressed_range(&self, frame: usize) -> Range<usize> {
+        self.compressed[frame] as usize..self.compressed[frame + 1] as usize
+    }
+
+    /// Returns the range of the decompressed file held by `frame`.
+    #[inline]
+    pub fn decompressed_range(&self, frame: usize) -> Range<u64> {
+        self.decompressed[frame]..self.decompressed[frame + 1]
+    }
+
+    /// Returns the frame holding the byte at `offset` of the decompressed file, which must be
+    /// smaller than its length.
+    #[inline]
+    pub fn frame_at(&self, offset: u64) -> usize {
+        self.decompressed[1..].partition_point(|end| *end <= offset)
+    }
+
+    /// Returns the skippable frame holding the seek table, without checksums.
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let size = self.frames() * 8 + FOOTER_SIZE;
+        let mut bytes = Vec::with_capacity(SKIPPABLE_HEADER_SIZE + size);
+        bytes.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+        bytes.extend_from_slice(&(size as u32).to_le_bytes());
+        for frame in 0..self.frames() {
+            let decompressed = self.decompressed_range(frame);
+            bytes.extend_from_slice(&(self.compressed_range(frame).len() as u32).to_le_bytes());
+            bytes
+                .extend_from_slice(&((decompressed.end - decompressed.start) as u32).to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.frames() as u32).to_le_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        bytes
+    }
+
+    /// Reads the seek table ending the compressed file `bytes`, checking that the frames it lists
+    /// cover the file up to the table.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        let u32_at = |offset: usize| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[offset..offset + 4]);
+            u32::from_le_bytes(word)
+        };
+        ensure!(
+            bytes.len() >= SKIPPABLE_HEADER_SIZE + FOOTER_SIZE,
+            "The file is too short to end with a seek table"
+        );
+        let footer = bytes.len() - FOOTER_SIZE;
+        ensure!(
+            u32_at(footer + 5) == SEEKABLE_MAGIC,
+            "The file does not end with a seek table, it was not compressed in the seekable format"
+        );
+        let frames = u32_at(footer) as usize;
+        let descriptor = bytes[footer + 4];
+        ensure!(
+            descriptor & RESERVED_BITS == 0,
+            "The seek table descriptor {:#04x} sets reserved bits",
+            descriptor
+        );
+        let entry_size = match descriptor & CHECKSUM_FLAG {
+            0 => 8,
+            _ => 12,
+        };
+        let size = frames * entry_size + FOOTER_SIZE;
+        ensure!(
+            bytes.len() >= SKIPPABLE_HEADER_SIZE + size,
+            "The seek table of {} frames does not fit in the file",
+            frames
+        );
+        let start = bytes.len() - size - SKIPPABLE_HEADER_SIZE;
+        ensure!(
+            u32_at(start) == SKIPPABLE_MAGIC && u32_at(start + 4) as usize == size,
+            "The seek table is not held by a skippable frame of its size"
+        );
+        let mut table = Self::new();
+        for frame in 0..frames {
+            let entry = start + SKIPPABLE_HEADER_SIZE + frame * entry_size;
+            table.push(u32_at(entry), u32_at(entry + 4));
+        }
+        ensure!(
+            table.compressed_len() == start as u64,
+            "The frames of the seek table cover {} bytes instead of the {} before it",
+            table.compressed_len(),
+            start
+        );
+        Ok(table)
+    }
+}
+
+/// Compresses `data` with `level` into frames of `frame_size` decompressed bytes, in parallel,
+/// writing them and their seek table to `output`.
+#[inline]
+pub fn compress<W>(data: &[u8], level: i32, frame_size: usize, output: &mut W) -> Result<SeekTable>
+where
+    W: Write,
+{
+    ensure!(
+        (1..=MAX_FRAME_SIZE).contains(&frame_size),
+        "Frames hold between 1 and {} bytes",
+        MAX_FRAME_SIZE
+    );
+    let mut table = SeekTable::new();
+    let batch_size = frame_size * FRAMES_PER_THREAD * rayon::current_num_threads();
+    for batch in data.chunks(batch_size) {
+        let frames = batch
+            .par_chunks(frame_size)
+            .map(|frame| zstd::bulk::compress(frame, level))
+            .collect::<io::Result<Vec<_>>>()?;
+        for (frame, compressed) in batch.chunks(frame_size).zip(frames) {
+            output.write_all(&compressed)?;
+            table.push(compressed.len() as u32, frame.len() as u32);
+        }
+    }
+    output.write_all(&table.to_bytes())?;
+    Ok(table)
+}
+
+/// Seekable Compressed File
+pub struct SeekableFile<B = Mmap> {
+    /// Bytes of the compressed file
+    bytes: B,
+
+    /// Seek table of the file
+    table: SeekTable,
+}
+
+impl SeekableFile {
+    /// Maps the compressed file at `path`.
+    #[inline]
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        // SAFETY: The map is read-only and the ceremony files are not modified once written.
+        let map = unsafe { Mmap::map(&File::open(path)?)? };
+        Self::new(map)
+    }
+}
+
+impl<B> SeekableFile<B>
+where
+    B: AsRef<[u8]>,
+{
+    /// Builds a seekable file from the compressed `bytes`, reading their seek table.
+    #[inline]
+    pub fn new(bytes: B) -> Result<Self> {
+        let table = SeekTable::read(bytes.as_ref())?;
+        Ok(Self { bytes, table })
+    }
+
+    /// Returns the seek table of the file.
+    #[inline]
+    pub fn table(&self) -> &SeekTable {
+        &self.table
+    }
+
+    /// Returns the length of the decompressed file.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.table.len()
+    }
+
+    /// Returns `true` if the decompressed file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+
+    /// Decompresses `frame` into `output`, which must have its decompressed length.
+    #[inline]
+    fn decompress_frame_into(&self, frame: usize, output: &mut [u8]) -> Result {
+        let compressed = &self.bytes.as_ref()[self.table.compressed_range(frame)];
+        let len = zstd::bulk::Decompressor::new()?.decompress_to_buffer(compressed, output)?;
+        ensure!(
+            len == output.len(),
+            "Frame {} decompresses to {} bytes instead of {}",
+            frame,
+            len,
+            output.len()
+        );
+        Ok(())
+    }
+
+    /// Decompresses `frame`.
+    #[inline]
+    pub fn frame(&self, frame: usize) -> Result<Vec<u8>> {
+        let range = self.table.decompressed_range(frame);
+        let mut output = vec![0; (range.end - range.start) as usize];
+        self.decompress_frame_into(frame, &mut output)?;
+        Ok(output)
+    }
+
+    /// Decompresses the bytes at `range` of the decompressed file, decompressing only the frames
+    /// holding them, in parallel.
+    #[inline]
+    pub fn read_range(&self, range: Range<u64>) -> Result<Vec<u8>>
+    where
+        B: Sync,
+    {
+        ensure!(
+            range.start <= range.end && range.end <= self.len(),
+            "The range {:?} is out of the {} bytes of the file",
+            range,
+            self.len()
+        );
+        if range.is_empty() {
+            return Ok(Vec::new());
+        }
+        let frames = self.table.frame_at(range.start)..self.table.frame_at(range.end - 1) + 1;
+        let start = self.table.decompressed_range(frames.start).start;
+        let bytes = frames
+            .into_par_iter()
+            .map(|frame| self.frame(frame))
+            .collect::<Result<Vec<_>>>()?
+            .concat();
+        Ok(bytes[(range.start - start) as usize..(range.end - start) as usize].to_vec())
+    }
+
+    /// Decompresses the whole file into `output`, which must have its decompressed length, in
+    /// parallel.
+    #[inline]
+    pub fn decompress_into(&self, mut output: &mut [u8]) -> Result
+    where
+        B: Sync,
+    {
+        ensure!(
+            output.len() as u64 == self.len(),
+            "The file decompresses to {} bytes, not {}",
+            self.len(),
+            output.len()
+        );
+        let mut frames = Vec::with_capacity(self.table.frames());
+        for frame in 0..self.table.frames() {
+            let range = self.table.decompressed_range(frame);
+            let (head, tail) = output.split_at_mut((range.end - range.start) as usize);
+            frames.push((frame, head));
+            output = tail;
+        }
+        frames
+            .into_par_iter()
+            .try_for_each(|(frame, output)| self.decompress_frame_into(frame, output))
+    }
+
+    /// Returns a reader of the decompressed file, decompressing one frame at a time.
+    #[inline]
+    pub fn reader(&self) -> SeekableReader<'_, B> {
+        SeekableReader {
+            file: self,
+            position: 0,
+            frame: None,
+        }
+    }
+
+    /// Computes the Blake2b hash of the decompressed file.
+    #[inline]
+    pub fn hash(&self) -> Result<Hash64> {
+        let hashes =
+            calculate_hashes_streaming(self.reader(), &[HashAlgorithm::Blake2b], None, None)?
+                .expect("Hashing without a cancellation token always completes.");
+        Ok(Hash64::try_from(hashes[0].as_slice())?)
+    }
+}
+
+/// Reader of a [`SeekableFile`]
+pub struct SeekableReader<'f, B = Mmap> {
+    /// Compressed file
+    file: &'f SeekableFile<B>,
+
+    /// Position in the decompressed file
+    position: u64,
+
+    /// Last decompressed frame, with its index
+    frame: Option<(usize, Vec<u8>)>,
+}
+
+impl<B> Read for SeekableReader<'_, B>
+where
+    B: AsRef<[u8]>,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position >= self.file.len() {
+            return Ok(0);
+        }
+        let index = self.file.table.frame_at(self.position);
+        let bytes = match &self.frame {
+            Some((frame, bytes)) if *frame == index => bytes,
+            _ => {
+                let bytes = self
+                    .file
+                    .frame(index)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+                &self.frame.insert((index, bytes)).1
+            }
+        };
+        let offset = (self.position - self.file.table.decompressed_range(index).start) as usize;
+        let len = buf.len().min(bytes.len() - offset);
+        buf[..len].copy_from_slice(&bytes[offset..offset + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<B> Seek for SeekableReader<'_, B>
+where
+    B: AsRef<[u8]>,
+{
+    #[inline]
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.file.len().checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        match position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seeking before the start of the file",
+            )),
+        }
+    }
+}
+
+/// Compresses the file at `path` to `output` like [`compress`], checking that the compressed file
+/// decompresses to the Blake2b hash `blake2b` of the file.
+#[inline]
+pub fn compress_file(
+    path: &Path,
+    output: &Path,
+    blake2b: &Hash64,
+    level: i32,
+    frame_size: usize,
+) -> Result<SeekTable> {
+    // SAFETY: The map is read-only and the ceremony files are not modified once written.
+    let map = unsafe { Mmap::map(&File::open(path)?)? };
+    let mut table = None;
+    atomic::write_with(output, |file| {
+        table = Some(compress(&map, level, frame_size, file)?);
+        Ok(())
+    })?;
+    let hash = SeekableFile::open(output)?.hash()?;
+    if hash != *blake2b {
+        std::fs::remove_file(output)?;
+        bail!(
+            "{} does not decompress to the hash {} of {}",
+            output.display(),
+            blake2b,
+            path.display()
+        );
+    }
+    Ok(table.expect("The seek table is set once the file is written."))
+}
+
+/// Decompresses the compressed file at `path` to `output`, decompressing batches of frames in
+/// parallel.
+#[inline]
+pub fn decompress_file(path: &Path, output: &Path) -> Result {
+    let file = SeekableFile::open(path)?;
+    let batch = FRAMES_PER_THREAD * rayon::current_num_threads();
+    let frames = (0..file.table.frames()).collect::<Vec<_>>();
+    atomic::write_with(output, |output| {
+        for batch in frames.chunks(batch) {
+            let frames = batch
+                .par_iter()
+                .map(|frame| file.frame(*frame))
+                .collect::<Result<Vec<_>>>()?;
+            for frame in frames {
+                output.write_all(&frame)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_file;
+    use std::fs;
+
+    /// Checks that compressed files decompress to their bytes as a whole, by range and through
+    /// their reader, and that damaged seek tables are rejected.
+    #[test]
+    fn compressed_files_are_seekable() {
+        let dir = std::env::temp_dir().join("ppot-verifier-compress-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("challenge_0001");
+        let data = (0..1_000_003u32)
+            .map(|i| (i % 251 * (i / 4096 % 3)) as u8)
+            .collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+        let hash = hash_file(&path).unwrap();
+        let output = compressed_path(&path);
+        assert!(compress_file(&path, &output, &Hash64([0; 64]), 3, 1 << 16).is_err());
+        assert!(!output.exists());
+        let table = compress_file(&path, &output, &hash, 3, 1 << 16).unwrap();
+        assert_eq!((table.frames(), table.len()), (16, data.len() as u64));
+        assert_eq!(table.frame_at(0), 0);
+        assert_eq!(table.frame_at(1 << 16), 1);
+        assert_eq!(table.frame_at(data.len() as u64 - 1), 15);
+        let file = SeekableFile::open(&output).unwrap();
+        assert_eq!(file.table(), &table);
+        assert_eq!(file.hash().unwrap(), hash);
+        assert_eq!(
+            file.read_range(65_000..200_000).unwrap(),
+            &data[65_000..200_000]
+        );
+        assert!(file.read_range(0..data.len() as u64 + 1).is_err());
+        let mut reader = file.reader();
+        reader.seek(SeekFrom::End(-10)).unwrap();
+        let mut tail = Vec::new();
+        reader.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, &data[data.len() - 10..]);
+        let mut decompressed = vec![0; data.len()];
+        file.decompress_into(&mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+        assert_eq!(
+            zstd::decode_all(&fs::read(&output).unwrap()[..]).unwrap(),
+            data
+        );
+        let restored = dir.join("restored");
+        decompress_file(&output, &restored).unwrap();
+        assert_eq!(fs::read(&restored).unwrap(), data);
+        let mut bytes = fs::read(&output).unwrap();
+        let len = bytes.len();
+        bytes[len - 24] ^= 1;
+        assert!(SeekableFile::new(&bytes).is_err());
+        assert!(SeekableFile::new(&data).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! Configuration
+
+use crate::{
+    download::{DownloadOptions, HttpOptions},
+    notify::{Hook, Notifier},
+    s3, Result,
+};
+use anyhow::anyhow;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+/// Default location of the configuration file
+pub const CONFIG_PATH: &str = "ppot.toml";
+
+/// Configuration
+///
+/// The configuration is read from a TOML file, for example:
+///
+/// ```toml
+/// [buckets.internal]
+/// endpoint = "https://minio.example.com"
+/// bucket = "ppot"
+/// access_key_id = "..."
+/// secret_access_key = "..."
+///
+/// [[notifications]]
+/// url = "https://hooks.slack.com/services/..."
+/// format = "slack"
+///
+/// [http]
+/// pool_max_idle_per_host = 16
+/// ```
+///
+/// after which registry entries can list `s3://internal/challenge_0001` as a mirror, the key
+/// events of the runs are posted to Slack, see [`notify`](crate::notify), and the HTTP clients
+/// keep more connections open, see [`HttpOptions`].
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Config {
+    /// S3-compatible buckets by name
+    #[serde(default)]
+    pub buckets: BTreeMap<String, s3::Bucket>,
+
+    /// Hooks notified of the key events of the runs
+    #[serde(default)]
+    pub notifications: Vec<Hook>,
+
+    /// Connection settings of the HTTP clients
+    #[serde(default)]
+    pub http: HttpOptions,
+}
+
+impl Config {
+    /// Loads the configuration from the TOML file at `path`, falling back to the default
+    /// configuration if the file does not exist.
+    #[inline]
+    pub fn load_or_default<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(toml::from_str(&contents)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the default [`DownloadOptions`] with the connection settings of the configuration.
+    #[inline]
+    pub fn download_options(&self) -> DownloadOptions {
+        DownloadOptions {
+            http: self.http.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the [`Notifier`] posting to the hooks of the configuration.
+    #[inline]
+    pub fn notifier(&self) -> Notifier {
+        Notifier::new(self.notifications.clone())
+    }
+
+    /// Turns `url` into a URL that can be downloaded from over HTTP, presigning `s3://` URLs with
+    /// the credentials of the bucket they refer to. Other URLs are returned as is.
+    #[inline]
+    pub fn resolve_url(&self, url: &str) -> Result<String> {
+        match s3::parse_url(url) {
+            Some((name, key)) => self
+                .buckets
+                .get(name)
+                .ok_or_else(|| anyhow!("Bucket '{}' is not configured.", name))?
+                .object_url(key),
+            _ => Ok(url.into()),
+        }
+    }
+}
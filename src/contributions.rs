@@ -0,0 +1,175 @@
+//! Embedded Contribution List
+//!
+//! The list of contributions built into the crate, from which [`challenge_urls`] and
+//! [`response_urls`] derive the URLs of the ceremony files when no registry has been synced yet.
+//! It lives in `src/contributions.json` rather than in Rust tables: the name of every participant
+//! in order, and the exceptions to the naming convention of the blob container, such as
+//! `challenge_initial` for the first challenge. `ppot registry update` regenerates it from the
+//! GitHub repository of the ceremony and the listing of the container.
+//!
+//! [`challenge_urls`]: crate::challenge_urls
+//! [`response_urls`]: crate::response_urls
+
+use crate::{atomic, Result};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+#[cfg(feature = "native")]
+use crate::{azure, github};
+
+#[cfg(feature = "native")]
+use reqwest::Client;
+
+/// Path of the embedded contribution list in the source tree the crate was built from
+pub const CONTRIBUTIONS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/contributions.json");
+
+/// Embedded contribution list
+const EMBEDDED: &str = include_str!("contributions.json");
+
+/// Contribution List
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContributionList {
+    /// URL of the blob container holding the ceremony files
+    pub container_url: String,
+
+    /// Participant of every contribution, starting with contribution `1`
+    pub participants: Vec<String>,
+
+    /// Names of the files of the container which do not follow the convention, by their
+    /// conventional name: `challenge_NNNN` for the challenge numbered `NNNN`, starting at `1`, and
+    /// `response_NNNN_participant` for the response of contribution `NNNN`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exceptions: BTreeMap<String, String>,
+}
+
+impl ContributionList {
+    /// Returns the contribution list embedded in the crate.
+    #[inline]
+    pub fn embedded() -> Self {
+        serde_json::from_str(EMBEDDED).expect("The embedded contribution list is valid.")
+    }
+
+    /// Returns the name of the file whose conventional name is `conventional`.
+    #[inline]
+    fn name(&self, conventional: String) -> String {
+        self.exceptions
+            .get(&conventional)
+            .cloned()
+            .unwrap_or(conventional)
+    }
+
+    /// Returns the names of the challenge files, one more than there are contributions.
+    #[inline]
+    pub fn challenge_names(&self) -> Vec<String> {
+        (1..self.participants.len() + 2)
+            .map(|number| self.name(format!("challenge_{:04}", number)))
+            .collect()
+    }
+
+    /// Returns the names of the response files, one for every contribution.
+    #[inline]
+    pub fn response_names(&self) -> Vec<String> {
+        self.participants
+            .iter()
+            .enumerate()
+            .map(|(i, participant)| self.name(format!("response_{:04}_{}", i + 1, participant)))
+            .collect()
+    }
+
+    /// Returns the URLs of the challenge files.
+    #[inline]
+    pub fn challenge_urls(&self) -> Vec<String> {
+        self.urls(self.challenge_names())
+    }
+
+    /// Returns the URLs of the response files.
+    #[inline]
+    pub fn response_urls(&self) -> Vec<String> {
+        self.urls(self.response_names())
+    }
+
+    /// Returns the URLs of the files named `names` in the container.
+    #[inline]
+    fn urls(&self, names: Vec<String>) -> Vec<String> {
+        let container_url = self.container_url.trim_end_matches('/');
+        names
+            .into_iter()
+            .map(|name| format!("{}/{}", container_url, name))
+            .collect()
+    }
+
+    /// Builds the contribution list from the contributions recorded in the GitHub repository of
+    /// the ceremony, authenticating with `token`, and the names of the files in the blob container
+    /// at [`CONTAINER_URL`](azure::CONTAINER_URL).
+    #[cfg(feature = "native")]
+    #[inline]
+    pub async fn discover(client: &Client, token: Option<&str>) -> Result<Self> {
+        let contributions = github::list_contributions(client, token).await?;
+        let blobs = azure::list_blobs(client, azure::CONTAINER_URL).await?;
+        let (challenges, responses) =
+            github::contribution_urls(&contributions, blobs, azure::CONTAINER_URL);
+        let mut list = Self {
+            container_url: azure::CONTAINER_URL.into(),
+            participants: contributions
+                .into_iter()
+                .map(|contribution| contribution.participant)
+                .collect(),
+            exceptions: BTreeMap::new(),
+        };
+        let prefix = format!("{}/", azure::CONTAINER_URL.trim_end_matches('/'));
+        for (conventional, url) in list
+            .challenge_names()
+            .into_iter()
+            .zip(challenges)
+            .chain(list.response_names().into_iter().zip(responses))
+        {
+            let name = url.strip_prefix(&prefix).unwrap_or(&url);
+            if name != conventional {
+                list.exceptions.insert(conventional, name.into());
+            }
+        }
+        Ok(list)
+    }
+
+    /// Saves the contribution list as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        let mut json = serde_json::to_vec_pretty(self)?;
+        json.push(b'\n');
+        atomic::write(path, json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the embedded list keeps the names which do not follow the convention and that
+    /// it is saved back byte for byte.
+    #[test]
+    fn embedded_list_round_trips() {
+        let list = ContributionList::embedded();
+        let challenges = list.challenge_urls();
+        let responses = list.response_urls();
+        assert_eq!(challenges.len(), responses.len() + 1);
+        assert_eq!(
+            challenges[0],
+            "https://ppot.blob.core.windows.net/public/challenge_initial"
+        );
+        assert_eq!(
+            challenges[1],
+            "https://ppot.blob.core.windows.net/public/challenge_0002_kobi"
+        );
+        assert_eq!(
+            responses[15],
+            "https://ppot.blob.core.windows.net/public/response_0016_aurel"
+        );
+        let mut json = serde_json::to_string_pretty(&list).unwrap();
+        json.push('\n');
+        assert_eq!(json, EMBEDDED);
+    }
+}
@@ -0,0 +1,804 @@
+//! Distributed Verification
+//!
+//! Verifying every round on a single machine takes weeks, so `ppot coordinate` partitions the
+//! rounds across machines. The [`Coordinator`] hands out a [`WorkItem`] for each round to the
+//! workers asking for one, and each worker, running `ppot work`, downloads the challenge and
+//! response files of its round, hashes them, verifies the transform and returns a
+//! [`SignedResult`]. Results are authenticated with an HMAC-SHA256 under the key of their worker,
+//! which the coordinator derives from its own key and the name of the worker, see [`worker_key`],
+//! so that a worker cannot sign results in the name of another. A result is only accepted from the
+//! worker holding the lease of its round.
+//!
+//! Since each worker only sees the files of its round, the coordinator stitches the hash chain from
+//! the results, see [`Coordinator::chain`]: the response file of each round must assert the hash of
+//! its challenge file, and the challenge file of the next round must assert the hash of that
+//! response file. Items are leased rather than assigned for good, so an item whose result does not
+//! come back within its lease is handed out again and a worker dying halfway only costs its lease.
+//!
+//! The coordinator serves the workers over HTTP:
+//!
+//! - `POST /work` with the [`WorkRequest`] of a worker returns its next [`WorkItem`], or
+//!   `204 No Content` once every round is handed out
+//! - `POST /results` records a [`SignedResult`]
+//! - `GET /chain` returns the [`ChainReport`] of the results so far
+
+use crate::{
+    accumulator::verify_round_on,
+    atomic,
+    config::{Config, CONFIG_PATH},
+    curve::CurveKind,
+    download::{download_file, DownloadOptions},
+    hash::Hash64,
+    hash_file_with,
+    input::InputOptions,
+    progress::ProgressSink,
+    registry::{Registry, RemoteFile},
+    storage::StorageOptions,
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use axum::{http::StatusCode, routing::get, routing::post, Extension, Json, Router};
+use core::{fmt, future::Future, ops::Range, time::Duration};
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::ErrorKind,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tokio::{sync::Notify, task};
+use tracing::{info, warn};
+
+/// Default path of the results collected by the coordinator in the state directory
+pub const COORDINATOR_PATH: &str = "coordinator.json";
+
+/// Default address the coordinator listens on
+pub const COORDINATOR_ADDRESS: &str = "0.0.0.0:8081";
+
+/// Default duration of the lease of a work item
+pub const LEASE_DURATION: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Request for Work
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WorkRequest {
+    /// Name of the worker, recorded with its lease
+    pub worker: String,
+}
+
+/// Work Item
+///
+/// Everything a worker needs to verify a round without a registry of its own.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct WorkItem {
+    /// Round to verify
+    pub round: usize,
+
+    /// Curve of the ceremony
+    pub curve: CurveKind,
+
+    /// Base-two logarithm of the number of powers of tau in the files of the ceremony
+    pub file_log_powers: u32,
+
+    /// Base-two logarithm of the number of powers to verify
+    pub log_powers: u32,
+
+    /// Challenge file the round starts from
+    pub challenge: RemoteFile,
+
+    /// Response file of the round
+    pub response: RemoteFile,
+}
+
+impl WorkItem {
+    /// Builds the item verifying `round` of `registry` on its first `2^log_powers` powers.
+    #[inline]
+    pub fn new(registry: &Registry, round: usize, log_powers: u32) -> Self {
+        Self {
+            round,
+            curve: registry.curve,
+            file_log_powers: registry.powers().trailing_zeros(),
+            log_powers,
+            challenge: registry.challenges[round].clone(),
+            response: registry.responses[round].clone(),
+        }
+    }
+}
+
+/// Returns the key of `worker`, the hex-encoded HMAC-SHA256 of its name under the `key` of the
+/// coordinator, which the worker signs its results with.
+#[inline]
+pub fn worker_key(key: &[u8], worker: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length.");
+    mac.update(worker.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Result of a Round
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundResult {
+    /// Verified round
+    pub round: usize,
+
+    /// Name of the worker which verified the round
+    pub worker: String,
+
+    /// Hash asserted by the header of the challenge file, the hash of the previous response file
+    pub challenge_header: Hash64,
+
+    /// Hash of the challenge file
+    pub challenge_hash: Hash64,
+
+    /// Hash asserted by the header of the response file, the hash of its challenge file
+    pub response_header: Hash64,
+
+    /// Hash of the response file
+    pub response_hash: Hash64,
+
+    /// Error of the verification of the transform, if it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RoundResult {
+    /// Returns the HMAC-SHA256 of `self` under `key`.
+    #[inline]
+    fn mac(&self, key: &[u8]) -> Result<Hmac<Sha256>> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length.");
+        mac.update(&serde_json::to_vec(self)?);
+        Ok(mac)
+    }
+
+    /// Signs `self` with `key`.
+    #[inline]
+    pub fn sign(self, key: &[u8]) -> Result<SignedResult> {
+        let signature = hex::encode(self.mac(key)?.finalize().into_bytes());
+        Ok(SignedResult {
+            result: self,
+            signature,
+        })
+    }
+}
+
+/// Signed Result of a Round
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SignedResult {
+    /// Result of the round
+    pub result: RoundResult,
+
+    /// Hex-encoded HMAC-SHA256 of the result under the key of its worker
+    pub signature: String,
+}
+
+/// Invalid Signature Error
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct InvalidSignature;
+
+impl fmt::Display for InvalidSignature {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The result is not signed with the key of its worker")
+    }
+}
+
+impl std::error::Error for InvalidSignature {}
+
+impl SignedResult {
+    /// Returns the result if it is signed with `key`.
+    #[inline]
+    pub fn verify(&self, key: &[u8]) -> Result<&RoundResult> {
+        let signature = hex::decode(&self.signature).map_err(|_| InvalidSignature)?;
+        self.result
+            .mac(key)?
+            .verify_slice(&signature)
+            .map_err(|_| InvalidSignature)?;
+        Ok(&self.result)
+    }
+}
+
+/// Problem Found while Stitching the Chain
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ChainIssue {
+    /// No result came back for the round yet
+    Missing {
+        /// Round without a result
+        round: usize,
+    },
+
+    /// The transform of the round is invalid
+    Invalid {
+        /// Invalid round
+        round: usize,
+
+        /// Error of the verification
+        error: String,
+    },
+
+    /// The response file of the round does not assert the hash of its challenge file
+    Unlinked {
+        /// Round whose files do not link up
+        round: usize,
+    },
+
+    /// The challenge file of the next round does not assert the hash of the response file of the
+    /// round, the two results coming from different files
+    Broken {
+        /// Round after which the chain breaks
+        round: usize,
+    },
+}
+
+/// Chain Report
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ChainReport {
+    /// Rounds partitioned across the workers
+    pub rounds: Range<usize>,
+
+    /// Number of rounds with a result
+    pub results: usize,
+
+    /// Problems found in the results so far
+    pub issues: Vec<ChainIssue>,
+}
+
+impl ChainReport {
+    /// Returns `true` if every round has a result and the results form a valid chain.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Stitches the hash chain of `rounds` from the `results` of the rounds verified so far.
+    #[inline]
+    pub fn stitch(rounds: Range<usize>, results: &BTreeMap<usize, RoundResult>) -> Self {
+        let mut issues = Vec::new();
+        for round in rounds.clone() {
+            let result = match results.get(&round) {
+                Some(result) => result,
+                _ => {
+                    issues.push(ChainIssue::Missing { round });
+                    continue;
+                }
+            };
+            if let Some(error) = &result.error {
+                issues.push(ChainIssue::Invalid {
+                    round,
+                    error: error.clone(),
+                });
+            }
+            if result.response_header != result.challenge_hash {
+                issues.push(ChainIssue::Unlinked { round });
+            }
+            if let Some(next) = results.get(&(round + 1)) {
+                if next.challenge_header != result.response_hash {
+                    issues.push(ChainIssue::Broken { round });
+                }
+            }
+        }
+        Self {
+            results: results
+                .keys()
+                .filter(|round| rounds.contains(round))
+                .count(),
+            rounds,
+            issues,
+        }
+    }
+}
+
+/// Lease of a Work Item
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct Lease {
+    /// Name of the worker holding the lease
+    worker: String,
+
+    /// Expiration of the lease
+    expires: Instant,
+}
+
+/// Verification Coordinator
+#[derive(Clone, Debug)]
+pub struct Coordinator {
+    /// Registry of the ceremony
+    registry: Registry,
+
+    /// Rounds to verify
+    rounds: Range<usize>,
+
+    /// Base-two logarithm of the number of powers to verify
+    log_powers: u32,
+
+    /// Key the keys of the workers derive from
+    key: Vec<u8>,
+
+    /// Duration of the leases
+    lease: Duration,
+
+    /// Rounds neither handed out nor verified
+    pending: BTreeSet<usize>,
+
+    /// Rounds handed out and waiting for their result
+    leases: BTreeMap<usize, Lease>,
+
+    /// Results received so far
+    results: BTreeMap<usize, RoundResult>,
+
+    /// Path the results are saved to as they come
+    path: Option<PathBuf>,
+}
+
+impl Coordinator {
+    /// Builds a coordinator handing out `rounds` of `registry`, to verify on their first
+    /// `2^log_powers` powers, to workers whose keys derive from `key`, each for the `lease`
+    /// duration.
+    #[inline]
+    pub fn new(
+        registry: Registry,
+        rounds: Range<usize>,
+        log_powers: u32,
+        key: Vec<u8>,
+        lease: Duration,
+    ) -> Result<Self> {
+        ensure!(!key.is_empty(), "The key of the coordinator is empty");
+        ensure!(
+            rounds.start > 0 && rounds.end <= registry.rounds(),
+            "The registry covers rounds 1 to {}, not {:?}",
+            registry.rounds() - 1,
+            rounds
+        );
+        ensure!(
+            1 << log_powers <= registry.powers(),
+            "Unable to verify 2^{} powers of files with {}",
+            log_powers,
+            registry.powers()
+        );
+        Ok(Self {
+            pending: rounds.clone().collect(),
+            registry,
+            rounds,
+            log_powers,
+            key,
+            lease,
+            leases: BTreeMap::new(),
+            results: BTreeMap::new(),
+            path: None,
+        })
+    }
+
+    /// Loads the results saved at `path` by an earlier run, whose rounds are not handed out again,
+    /// and saves the results to `path` as they come.
+    #[inline]
+    pub fn resume_from(mut self, path: PathBuf) -> Result<Self> {
+        let results: BTreeMap<usize, RoundResult> = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        for (round, result) in results {
+            if self.rounds.contains(&round) {
+                self.pending.remove(&round);
+                self.results.insert(round, result);
+            }
+        }
+        self.path = Some(path);
+        Ok(self)
+    }
+
+    /// Returns `true` once every round has a result.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.results.len() == self.rounds.len()
+    }
+
+    /// Leases the next round to `worker` at `now`, handing out again the rounds whose lease
+    /// expired, or returns `None` if every round is handed out.
+    #[inline]
+    pub fn next_item(&mut self, worker: &str, now: Instant) -> Option<WorkItem> {
+        let expired = self
+            .leases
+            .iter()
+            .filter(|(_, lease)| lease.expires <= now)
+            .map(|(round, _)| *round)
+            .collect::<Vec<_>>();
+        for round in expired {
+            if let Some(lease) = self.leases.remove(&round) {
+                warn!("The lease of round {} by {} expired", round, lease.worker);
+                self.pending.insert(round);
+            }
+        }
+        let round = self.pending.pop_first()?;
+        self.leases.insert(
+            round,
+            Lease {
+                worker: worker.into(),
+                expires: now + self.lease,
+            },
+        );
+        info!("Leasing round {} to {}", round, worker);
+        Some(WorkItem::new(&self.registry, round, self.log_powers))
+    }
+
+    /// Records the `signed` result of a round, once its signature is checked against the key of
+    /// its worker, which must hold the lease of the round. The results are not saved, see
+    /// [`saved_results`](Self::saved_results).
+    #[inline]
+    pub fn submit(&mut self, signed: &SignedResult) -> Result {
+        let result = signed.verify(worker_key(&self.key, &signed.result.worker).as_bytes())?;
+        ensure!(
+            self.rounds.contains(&result.round),
+            "Round {} is not coordinated",
+            result.round
+        );
+        if self.results.contains_key(&result.round) {
+            bail!("Round {} already has a result", result.round);
+        }
+        match self.leases.get(&result.round) {
+            Some(lease) if lease.worker == result.worker => {}
+            Some(lease) => bail!(
+                "Round {} is leased to {}, not {}",
+                result.round,
+                lease.worker,
+                result.worker
+            ),
+            _ => bail!("Round {} is not leased to {}", result.round, result.worker),
+        }
+        self.leases.remove(&result.round);
+        self.pending.remove(&result.round);
+        self.results.insert(result.round, result.clone());
+        info!(
+            "Round {} is {} according to {}",
+            result.round,
+            if result.error.is_none() {
+                "valid"
+            } else {
+                "INVALID"
+            },
+            result.worker
+        );
+        Ok(())
+    }
+
+    /// Returns the path the results are saved to, if any, with the results received so far
+    /// encoded in JSON.
+    #[inline]
+    pub fn saved_results(&self) -> Result<Option<(PathBuf, Vec<u8>)>> {
+        self.path
+            .as_ref()
+            .map(|path| Ok((path.clone(), serde_json::to_vec_pretty(&self.results)?)))
+            .transpose()
+    }
+
+    /// Stitches the hash chain from the results received so far.
+    #[inline]
+    pub fn chain(&self) -> ChainReport {
+        ChainReport::stitch(self.rounds.clone(), &self.results)
+    }
+}
+
+/// State Shared by the Handlers
+struct Shared {
+    /// Coordinator
+    coordinator: Mutex<Coordinator>,
+
+    /// Held while saving the results, so that an older save never overwrites a newer one
+    saving: tokio::sync::Mutex<()>,
+
+    /// Notified once every round has a result
+    complete: Notify,
+}
+
+impl Shared {
+    /// Locks the coordinator.
+    #[inline]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Coordinator> {
+        self.coordinator
+            .lock()
+            .expect("The coordinator is never poisoned.")
+    }
+
+    /// Saves the latest results of the coordinator, writing them on a blocking thread once the
+    /// coordinator is unlocked.
+    #[inline]
+    async fn save(&self) -> Result {
+        let _saving = self.saving.lock().await;
+        let saved = self.lock().saved_results()?;
+        if let Some((path, results)) = saved {
+            task::spawn_blocking(move || atomic::write(path, results)).await??;
+        }
+        Ok(())
+    }
+}
+
+/// Handles `POST /work`.
+#[inline]
+async fn work(
+    Extension(shared): Extension<Arc<Shared>>,
+    Json(request): Json<WorkRequest>,
+) -> core::result::Result<Json<WorkItem>, StatusCode> {
+    shared
+        .lock()
+        .next_item(&request.worker, Instant::now())
+        .map(Json)
+        .ok_or(StatusCode::NO_CONTENT)
+}
+
+/// Handles `POST /results`.
+#[inline]
+async fn results(
+    Extension(shared): Extension<Arc<Shared>>,
+    Json(signed): Json<SignedResult>,
+) -> StatusCode {
+    let submitted = shared.lock().submit(&signed);
+    match submitted {
+        Ok(()) => {
+            if let Err(err) = shared.save().await {
+                warn!(
+                    "Unable to save the result of round {}: {}",
+                    signed.result.round, err
+                );
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            if shared.lock().is_complete() {
+                shared.complete.notify_one();
+            }
+            StatusCode::OK
+        }
+        Err(err) if err.is::<InvalidSignature>() => {
+            warn!(
+                "Rejecting a result of round {}: {}",
+                signed.result.round, err
+            );
+            StatusCode::UNAUTHORIZED
+        }
+        Err(err) => {
+            warn!(
+                "Rejecting a result of round {}: {}",
+                signed.result.round, err
+            );
+            StatusCode::CONFLICT
+        }
+    }
+}
+
+/// Handles `GET /chain`.
+#[inline]
+async fn chain(Extension(shared): Extension<Arc<Shared>>) -> Json<ChainReport> {
+    Json(shared.lock().chain())
+}
+
+/// Serves the work items of `coordinator` on `address` until every round has a result or
+/// `shutdown` completes, returning the chain report of the results.
+#[inline]
+pub async fn coordinate<F>(
+    address: SocketAddr,
+    coordinator: Coordinator,
+    shutdown: F,
+) -> Result<ChainReport>
+where
+    F: Future<Output = ()>,
+{
+    let complete = coordinator.is_complete();
+    let shared = Arc::new(Shared {
+        coordinator: Mutex::new(coordinator),
+        saving: tokio::sync::Mutex::new(()),
+        complete: Notify::new(),
+    });
+    if !complete {
+        let router = Router::new()
+            .route("/work", post(work))
+            .route("/results", post(results))
+            .route("/chain", get(chain))
+            .layer(Extension(shared.clone()));
+        let server = axum::Server::try_bind(&address)?;
+        info!("Coordinating the verification on http://{}", address);
+        server
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                tokio::select! {
+                    _ = shared.complete.notified() => {}
+                    _ = shutdown => {}
+                }
+            })
+            .await?;
+    }
+    let report = shared.lock().chain();
+    Ok(report)
+}
+
+/// Verifies `item` as `worker`: downloads its files into `storage`, hashes them and verifies the
+/// transform of its round. The hashing and the verification block the current thread, which must
+/// belong to a multi-threaded runtime.
+#[inline]
+pub async fn verify_item(
+    client: &Client,
+    item: &WorkItem,
+    worker: &str,
+    storage: &StorageOptions,
+    options: &DownloadOptions,
+    progress: &dyn ProgressSink,
+) -> Result<RoundResult> {
+    ensure!(
+        item.log_powers <= item.file_log_powers && item.file_log_powers < usize::BITS,
+        "Unable to verify 2^{} powers of files with 2^{}",
+        item.log_powers,
+        item.file_log_powers
+    );
+    let config = Config::load_or_default(storage.state_path(CONFIG_PATH))?;
+    for file in [&item.challenge, &item.response] {
+        let urls = file
+            .urls()
+            .map(|url| config.resolve_url(url))
+            .collect::<Result<Vec<_>>>()?;
+        download_file(progress, client, &urls, storage.path(&file.path), options).await?;
+    }
+    let (challenge, response) = (
+        storage.path(&item.challenge.path),
+        storage.path(&item.response.path),
+    );
+    task::block_in_place(|| {
+        let challenge_hash = hash_file_with(&challenge, progress)?;
+        let response_hash = hash_file_with(&response, progress)?;
+        let input = InputOptions::default();
+        let challenge = input.open(&challenge)?.into_mmap()?;
+        let response = input.open(&response)?.into_mmap()?;
+        let header = |map: &[u8], path: &str| {
+            Hash64::from_header(map).ok_or_else(|| anyhow!("{} is too short", path))
+        };
+        let verification = verify_round_on(
+            item.curve,
+            &challenge,
+            &response,
+            1 << item.file_log_powers,
+            1 << item.log_powers,
+        );
+        progress.on_round_verified(item.round, &verification);
+        Ok(RoundResult {
+            round: item.round,
+            worker: worker.into(),
+            challenge_header: header(&challenge, &item.challenge.path)?,
+            challenge_hash,
+            response_header: header(&response, &item.response.path)?,
+            response_hash,
+            error: verification.err().map(|err| err.to_string()),
+        })
+    })
+}
+
+/// Verifies the rounds handed out by the coordinator at `url` as `worker`, signing the results
+/// with `key`, the key of the worker given by [`worker_key`], until the coordinator has no work
+/// left. Returns the number of rounds verified.
+#[inline]
+pub async fn work_for(
+    client: &Client,
+    url: &str,
+    worker: &str,
+    key: &[u8],
+    storage: &StorageOptions,
+    options: &DownloadOptions,
+    progress: &dyn ProgressSink,
+) -> Result<usize> {
+    let url = url.trim_end_matches('/');
+    let mut verified = 0;
+    loop {
+        let response = client
+            .post(format!("{}/work", url))
+            .json(&WorkRequest {
+                worker: worker.into(),
+            })
+            .send()
+            .await?
+            .error_for_status()?;
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(verified);
+        }
+        let item = response.json::<WorkItem>().await?;
+        info!("Verifying round {}", item.round);
+        let result = verify_item(client, &item, worker, storage, options, progress).await?;
+        client
+            .post(format!("{}/results", url))
+            .json(&result.sign(key)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        verified += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the result of `round` by `worker` whose challenge file asserts `previous` and
+    /// hashes to `challenge`, and whose response file hashes to `response`.
+    fn result(
+        round: usize,
+        worker: &str,
+        previous: u8,
+        challenge: u8,
+        response: u8,
+    ) -> RoundResult {
+        RoundResult {
+            round,
+            worker: worker.into(),
+            challenge_header: Hash64([previous; 64]),
+            challenge_hash: Hash64([challenge; 64]),
+            response_header: Hash64([challenge; 64]),
+            response_hash: Hash64([response; 64]),
+            error: None,
+        }
+    }
+
+    /// Checks that rounds are leased once until their lease expires, that only the results signed
+    /// by the holder of the lease are recorded and that the chain is stitched across workers.
+    #[test]
+    fn stitch_results_across_workers() {
+        let key = b"key".to_vec();
+        let mut coordinator = Coordinator::new(
+            Registry::builtin(),
+            1..4,
+            10,
+            key.clone(),
+            Duration::from_secs(60),
+        )
+        .unwrap();
+        let now = Instant::now();
+        let rounds = [
+            coordinator.next_item("a", now).unwrap().round,
+            coordinator.next_item("b", now).unwrap().round,
+            coordinator.next_item("c", now).unwrap().round,
+        ];
+        assert_eq!(rounds, [1, 2, 3]);
+        assert!(coordinator.next_item("d", now).is_none());
+        let later = now + Duration::from_secs(61);
+        assert_eq!(coordinator.next_item("d", later).unwrap().round, 1);
+        assert_eq!(coordinator.next_item("b", later).unwrap().round, 2);
+        assert_eq!(coordinator.next_item("c", later).unwrap().round, 3);
+        let signed = |result: RoundResult| {
+            let key = worker_key(&key, &result.worker);
+            result.sign(key.as_bytes()).unwrap()
+        };
+        let forged = SignedResult {
+            result: result(1, "d", 0, 1, 2),
+            signature: hex::encode([0; 32]),
+        };
+        assert!(coordinator
+            .submit(&forged)
+            .unwrap_err()
+            .is::<InvalidSignature>());
+        let impersonated = result(1, "d", 0, 1, 2).sign(worker_key(&key, "a").as_bytes());
+        assert!(coordinator
+            .submit(&impersonated.unwrap())
+            .unwrap_err()
+            .is::<InvalidSignature>());
+        assert!(coordinator
+            .submit(&result(1, "d", 0, 1, 2).sign(&key).unwrap())
+            .unwrap_err()
+            .is::<InvalidSignature>());
+        let expired = coordinator
+            .submit(&signed(result(1, "a", 0, 1, 2)))
+            .unwrap_err();
+        assert!(expired.to_string().contains("leased to d"), "{}", expired);
+        for result in [
+            result(1, "d", 0, 1, 2),
+            result(2, "b", 2, 3, 4),
+            result(3, "c", 5, 6, 7),
+        ] {
+            coordinator.submit(&signed(result)).unwrap();
+        }
+        assert!(coordinator
+            .submit(&signed(result(3, "c", 5, 6, 7)))
+            .is_err());
+        assert!(coordinator.is_complete());
+        assert_eq!(
+            coordinator.chain().issues,
+            [ChainIssue::Broken { round: 2 }]
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Ceremony Curves
+//!
+//! The perpetual powers of tau descends from the Zcash Sapling powers of tau, whose file format is
+//! shared by every ceremony built on the same code, such as the Filecoin powers of tau: the files
+//! only differ by the curve of the accumulator, BN254 or BLS12-381. A [`Curve`] describes what the
+//! format depends on, the size of the base field and the flags packed into the first byte of the
+//! points, while the [`CurveKind`] of a ceremony selects its curve at runtime, from the registry.
+//!
+//! The flags differ between both curves. The BN254 base field leaves two free bits: the highest
+//! one flags a compressed point whose `y` coordinate is the greatest of `y` and `-y` and the next
+//! one the point at infinity. The BLS12-381 base field leaves three: the highest one flags every
+//! compressed point, the next one the point at infinity and the last one the greatest `y`
+//! coordinate, as in the `pairing` crate.
+
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::{
+    models::{ModelParameters, SWModelParameters},
+    short_weierstrass_jacobian::GroupAffine,
+    PairingEngine,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Point Encoding Flags
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Flags {
+    /// Flag of every compressed point, zero if compressed points are not flagged
+    pub compressed: u8,
+
+    /// Flag of a compressed point whose `y` coordinate is the greatest of `y` and `-y`
+    pub greatest: u8,
+
+    /// Flag of the point at infinity
+    pub infinity: u8,
+}
+
+impl Flags {
+    /// Returns the mask of every flag.
+    #[inline]
+    pub fn mask(&self) -> u8 {
+        self.compressed | self.greatest | self.infinity
+    }
+}
+
+/// G1 point over `C`
+pub type G1<C> = GroupAffine<<C as Curve>::G1Parameters>;
+
+/// G2 point over `C`
+pub type G2<C> = GroupAffine<<C as Curve>::G2Parameters>;
+
+/// Scalar field of `C`
+pub type Scalar<C> = <<C as Curve>::G1Parameters as ModelParameters>::ScalarField;
+
+/// Ceremony Curve
+pub trait Curve: 'static + Copy + fmt::Debug + Eq + Send + Sync {
+    /// Parameters of G1
+    type G1Parameters: SWModelParameters;
+
+    /// Parameters of G2, over the quadratic extension of the base field
+    type G2Parameters: SWModelParameters<ScalarField = Scalar<Self>>;
+
+    /// Pairing between G1 and G2
+    type Engine: PairingEngine<G1Affine = G1<Self>, G2Affine = G2<Self>, Fr = Scalar<Self>>;
+
+    /// Kind of the curve
+    const KIND: CurveKind;
+
+    /// Size of an element of the base field
+    const FIELD_SIZE: usize;
+
+    /// Flags of the point encoding
+    const FLAGS: Flags;
+}
+
+impl Curve for Bn254 {
+    type G1Parameters = ark_bn254::g1::Parameters;
+    type G2Parameters = ark_bn254::g2::Parameters;
+    type Engine = Self;
+    const KIND: CurveKind = CurveKind::Bn254;
+    const FIELD_SIZE: usize = 32;
+    const FLAGS: Flags = Flags {
+        compressed: 0,
+        greatest: 1 << 7,
+        infinity: 1 << 6,
+    };
+}
+
+impl Curve for Bls12_381 {
+    type G1Parameters = ark_bls12_381::g1::Parameters;
+    type G2Parameters = ark_bls12_381::g2::Parameters;
+    type Engine = Self;
+    const KIND: CurveKind = CurveKind::Bls12_381;
+    const FIELD_SIZE: usize = 48;
+    const FLAGS: Flags = Flags {
+        compressed: 1 << 7,
+        greatest: 1 << 5,
+        infinity: 1 << 6,
+    };
+}
+
+/// Curve of a Ceremony
+#[derive(
+    clap::ValueEnum, Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum CurveKind {
+    /// BN254, as in the perpetual powers of tau
+    #[default]
+    Bn254,
+
+    /// BLS12-381, as in the Zcash Sapling and Filecoin powers of tau
+    Bls12_381,
+}
+
+impl CurveKind {
+    /// Returns `true` if `self` is BN254.
+    #[inline]
+    pub fn is_bn254(&self) -> bool {
+        *self == Self::Bn254
+    }
+}
+
+impl fmt::Display for CurveKind {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bn254 => write!(f, "BN254"),
+            Self::Bls12_381 => write!(f, "BLS12-381"),
+        }
+    }
+}
@@ -0,0 +1,599 @@
+//! State Database
+//!
+//! The results of every stage of the pipeline are recorded in a single SQLite database in the
+//! state directory: the files the `downloader` completed, the hashes computed by the `hasher` with
+//! the digests of the first powers of the challenge files, the links of the hash chain checked by
+//! `hash_check`, the rounds verified by `verify_ppot` with their outcome at every power level it
+//! was asked for and the profiles of the rounds verified by `ppot verify`. The hash files are still
+//! written next to it for the tools which read them, but the database is what the status and
+//! reports are built from. The PGP signatures of the attestations checked by `ppot attestations`
+//! and the outcome of the last scrub of every file by `ppot scrub` are recorded as well.
+//!
+//! The binaries open the database concurrently, so it is kept in write-ahead logging mode and
+//! writers wait for each other for up to [`BUSY_TIMEOUT`].
+
+use crate::{
+    hash::Hash64,
+    pgp::{SignatureCheck, SignatureStatus},
+    profile::RoundProfile,
+    storage::StorageOptions,
+    HashAlgorithm, Result,
+};
+use core::time::Duration;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+/// Default path of the state database in the state directory
+pub const DB_PATH: &str = "state.db";
+
+/// Time a writer waits for the other writers to finish
+pub const BUSY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Schema of the database
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        downloaded_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS hashes (
+        path TEXT NOT NULL,
+        algorithm TEXT NOT NULL,
+        hash BLOB NOT NULL,
+        hashed_at TEXT NOT NULL,
+        PRIMARY KEY (path, algorithm)
+    );
+    CREATE TABLE IF NOT EXISTS digests (
+        path TEXT NOT NULL,
+        log_powers INTEGER NOT NULL,
+        blake2b BLOB NOT NULL,
+        computed_at TEXT NOT NULL,
+        PRIMARY KEY (path, log_powers)
+    );
+    CREATE TABLE IF NOT EXISTS chain (
+        hashed TEXT NOT NULL,
+        asserted_by TEXT NOT NULL,
+        status TEXT NOT NULL,
+        checked_at TEXT NOT NULL,
+        PRIMARY KEY (hashed, asserted_by)
+    );
+    CREATE TABLE IF NOT EXISTS rounds (
+        round INTEGER PRIMARY KEY,
+        log_powers INTEGER NOT NULL,
+        verified INTEGER NOT NULL,
+        error TEXT,
+        verified_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS levels (
+        round INTEGER NOT NULL,
+        log_powers INTEGER NOT NULL,
+        verified INTEGER NOT NULL,
+        error TEXT,
+        verified_at TEXT NOT NULL,
+        PRIMARY KEY (round, log_powers)
+    );
+    CREATE TABLE IF NOT EXISTS profiles (
+        round INTEGER PRIMARY KEY,
+        total_ms INTEGER NOT NULL,
+        io_ms INTEGER NOT NULL,
+        deserialization_ms INTEGER NOT NULL,
+        subgroup_check_ms INTEGER NOT NULL,
+        multiexp_ms INTEGER NOT NULL,
+        pairing_ms INTEGER NOT NULL,
+        bytes_read INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS attestations (
+        contribution INTEGER PRIMARY KEY,
+        status TEXT NOT NULL,
+        signer TEXT,
+        checked_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS scrubs (
+        path TEXT PRIMARY KEY,
+        intact INTEGER NOT NULL,
+        scrubbed_at TEXT NOT NULL
+    );
+";
+
+/// Hash Chain Link Status
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainStatus {
+    /// The computed hash matches the asserted hash
+    Match,
+
+    /// The computed hash differs from the asserted hash
+    Mismatch,
+
+    /// The hash file or the file holding the asserted hash could not be read
+    Missing,
+}
+
+impl ChainStatus {
+    /// Returns the name of `self` in the database.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Match => "match",
+            Self::Mismatch => "mismatch",
+            Self::Missing => "missing",
+        }
+    }
+
+    /// Parses the name of a status in the database.
+    #[inline]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "match" => Some(Self::Match),
+            "mismatch" => Some(Self::Mismatch),
+            "missing" => Some(Self::Missing),
+            _ => None,
+        }
+    }
+}
+
+/// Scrub Record
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ScrubRecord {
+    /// `true` if the hash of the file was the recorded one
+    pub intact: bool,
+
+    /// UTC time of the scrub
+    pub scrubbed_at: String,
+
+    /// Number of seconds since the scrub
+    pub age: u64,
+}
+
+/// Round Record
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundRecord {
+    /// Index of the round
+    pub round: usize,
+
+    /// Base-two logarithm of the number of powers verified
+    pub log_powers: u32,
+
+    /// `true` if the round was verified successfully
+    pub verified: bool,
+
+    /// Verification error, if the round failed
+    pub error: Option<String>,
+
+    /// UTC time of the verification
+    pub verified_at: String,
+}
+
+/// State Database
+#[derive(Debug)]
+pub struct StateDb {
+    /// Database Connection
+    connection: Connection,
+}
+
+impl StateDb {
+    /// Opens the database at `path`, creating it if needed.
+    #[inline]
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let connection = Connection::open(path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Self { connection })
+    }
+
+    /// Opens the database in the state directory of `storage`.
+    #[inline]
+    pub fn open_in(storage: &StorageOptions) -> Result<Self> {
+        Self::open(storage.state_path(DB_PATH))
+    }
+
+    /// Records that the file at `path`, a file name of the registry, was completely downloaded
+    /// with `size` bytes.
+    #[inline]
+    pub fn record_download(&self, path: &str, size: u64) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO files (path, size, downloaded_at)
+             VALUES (?1, ?2, datetime('now'))",
+            params![path, size],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the size of the file at `path` when it was completely downloaded, if it was.
+    #[inline]
+    pub fn downloaded_size(&self, path: &str) -> Result<Option<u64>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT size FROM files WHERE path = ?1",
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Records the `algorithm` hash of the file at `path`.
+    #[inline]
+    pub fn record_hash(&self, path: &str, algorithm: HashAlgorithm, hash: &[u8]) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO hashes (path, algorithm, hash, hashed_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            params![path, algorithm.name(), hash],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the `algorithm` hash of the file at `path`, if it was recorded.
+    #[inline]
+    pub fn hash(&self, path: &str, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>> {
+        Ok(self
+            .connection
+            .query_row(
+                "SELECT hash FROM hashes WHERE path = ?1 AND algorithm = ?2",
+                params![path, algorithm.name()],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Returns the `algorithm` hash of every file it was recorded for, by path.
+    #[inline]
+    pub fn hashes(&self, algorithm: HashAlgorithm) -> Result<BTreeMap<String, Vec<u8>>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT path, hash FROM hashes WHERE algorithm = ?1")?;
+        let hashes = statement
+            .query_map(params![algorithm.name()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(hashes)
+    }
+
+    /// Records the `digest` of the first `2^log_powers` powers of the challenge file at `path`.
+    #[inline]
+    pub fn record_digest(&self, path: &str, log_powers: u32, digest: &Hash64) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO digests (path, log_powers, blake2b, computed_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            params![path, log_powers, digest.0.as_slice()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the digests of the first powers of the challenge file at `path`, by base-two
+    /// logarithm of the number of powers.
+    #[inline]
+    pub fn digests(&self, path: &str) -> Result<BTreeMap<u32, Hash64>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT log_powers, blake2b FROM digests WHERE path = ?1")?;
+        let digests = statement
+            .query_map(params![path], |row| {
+                Ok((row.get::<_, u32>(0)?, row.get::<_, Vec<u8>>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        digests
+            .into_iter()
+            .map(|(log_powers, digest)| Ok((log_powers, Hash64::try_from(digest.as_slice())?)))
+            .collect()
+    }
+
+    /// Records the `status` of the link of the hash chain where `asserted_by` asserts the hash of
+    /// `hashed`.
+    #[inline]
+    pub fn record_chain(&self, hashed: &str, asserted_by: &str, status: ChainStatus) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO chain (hashed, asserted_by, status, checked_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            params![hashed, asserted_by, status.name()],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the status of the link of the hash chain where `asserted_by` asserts the hash of
+    /// `hashed`, if it was checked.
+    #[inline]
+    pub fn chain(&self, hashed: &str, asserted_by: &str) -> Result<Option<ChainStatus>> {
+        let name: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT status FROM chain WHERE hashed = ?1 AND asserted_by = ?2",
+                params![hashed, asserted_by],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(name.as_deref().and_then(ChainStatus::from_name))
+    }
+
+    /// Forgets the download, the hashes and the links of the hash chain of the file at `path`,
+    /// once it has been replaced.
+    #[inline]
+    pub fn forget(&self, path: &str) -> Result {
+        self.connection
+            .execute("DELETE FROM files WHERE path = ?1", params![path])?;
+        self.connection
+            .execute("DELETE FROM hashes WHERE path = ?1", params![path])?;
+        self.connection
+            .execute("DELETE FROM digests WHERE path = ?1", params![path])?;
+        self.connection
+            .execute("DELETE FROM scrubs WHERE path = ?1", params![path])?;
+        self.connection.execute(
+            "DELETE FROM chain WHERE hashed = ?1 OR asserted_by = ?1",
+            params![path],
+        )?;
+        Ok(())
+    }
+
+    /// Records the scrub of the file at `path`, which found it `intact` or not.
+    #[inline]
+    pub fn record_scrub(&self, path: &str, intact: bool) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO scrubs (path, intact, scrubbed_at)
+             VALUES (?1, ?2, datetime('now'))",
+            params![path, intact],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the last scrub of every file, by path.
+    #[inline]
+    pub fn scrubs(&self) -> Result<BTreeMap<String, ScrubRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT path, intact, scrubbed_at,
+             CAST(strftime('%s', 'now') - strftime('%s', scrubbed_at) AS INTEGER) FROM scrubs",
+        )?;
+        let scrubs = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    ScrubRecord {
+                        intact: row.get(1)?,
+                        scrubbed_at: row.get(2)?,
+                        age: row.get::<_, i64>(3)?.max(0) as u64,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(scrubs)
+    }
+
+    /// Records the verification of `round` with `2^log_powers` powers, failed with `error` if any.
+    #[inline]
+    pub fn record_round(&self, round: usize, log_powers: u32, error: Option<&str>) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO rounds (round, log_powers, verified, error, verified_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![round, log_powers, error.is_none(), error],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the verification records of every round, in order.
+    #[inline]
+    pub fn rounds(&self) -> Result<Vec<RoundRecord>> {
+        let mut statement = self.connection.prepare(
+            "SELECT round, log_powers, verified, error, verified_at FROM rounds ORDER BY round",
+        )?;
+        let rounds = statement
+            .query_map([], |row| {
+                Ok(RoundRecord {
+                    round: row.get(0)?,
+                    log_powers: row.get(1)?,
+                    verified: row.get(2)?,
+                    error: row.get(3)?,
+                    verified_at: row.get(4)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rounds)
+    }
+
+    /// Records the verification of the first `2^log_powers` powers of `round`, failed with `error`
+    /// if any.
+    #[inline]
+    pub fn record_level(&self, round: usize, log_powers: u32, error: Option<&str>) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO levels (round, log_powers, verified, error, verified_at)
+             VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+            params![round, log_powers, error.is_none(), error],
+        )?;
+        Ok(())
+    }
+
+    /// Returns whether every power level of every round was verified successfully, by round.
+    #[inline]
+    pub fn levels(&self) -> Result<BTreeMap<usize, BTreeMap<u32, bool>>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT round, log_powers, verified FROM levels")?;
+        let mut levels = BTreeMap::<_, BTreeMap<_, _>>::new();
+        for level in statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))? {
+            let (round, log_powers, verified) = level?;
+            levels
+                .entry(round)
+                .or_default()
+                .insert(log_powers, verified);
+        }
+        Ok(levels)
+    }
+
+    /// Records the `profile` of the last verification of `round`.
+    #[inline]
+    pub fn record_profile(&self, round: usize, profile: &RoundProfile) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO profiles (round, total_ms, io_ms, deserialization_ms,
+             subgroup_check_ms, multiexp_ms, pairing_ms, bytes_read)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                round,
+                profile.total_ms,
+                profile.io_ms,
+                profile.deserialization_ms,
+                profile.subgroup_check_ms,
+                profile.multiexp_ms,
+                profile.pairing_ms,
+                profile.bytes_read
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the profile of the last verification of every profiled round.
+    #[inline]
+    pub fn profiles(&self) -> Result<BTreeMap<usize, RoundProfile>> {
+        let mut statement = self.connection.prepare(
+            "SELECT round, total_ms, io_ms, deserialization_ms, subgroup_check_ms, multiexp_ms,
+             pairing_ms, bytes_read FROM profiles",
+        )?;
+        let profiles = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get(0)?,
+                    RoundProfile {
+                        total_ms: row.get(1)?,
+                        io_ms: row.get(2)?,
+                        deserialization_ms: row.get(3)?,
+                        subgroup_check_ms: row.get(4)?,
+                        multiexp_ms: row.get(5)?,
+                        pairing_ms: row.get(6)?,
+                        bytes_read: row.get(7)?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<BTreeMap<_, _>>>()?;
+        Ok(profiles)
+    }
+
+    /// Records the `check` of the PGP signatures of the attestation of `contribution`.
+    #[inline]
+    pub fn record_attestation(&self, contribution: usize, check: &SignatureCheck) -> Result {
+        self.connection.execute(
+            "INSERT OR REPLACE INTO attestations (contribution, status, signer, checked_at)
+             VALUES (?1, ?2, ?3, datetime('now'))",
+            params![contribution, check.status.name(), check.signer],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the last check of the PGP signatures of the attestation of every contribution.
+    #[inline]
+    pub fn attestations(&self) -> Result<BTreeMap<usize, SignatureCheck>> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT contribution, status, signer FROM attestations")?;
+        let attestations = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, usize>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(contribution, status, signer)| {
+                Some((
+                    contribution,
+                    SignatureCheck {
+                        status: SignatureStatus::from_name(&status)?,
+                        signer,
+                    },
+                ))
+            })
+            .collect();
+        Ok(attestations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the results of every stage are recorded and replaced by newer ones.
+    #[test]
+    fn record_stages() {
+        let db = StateDb {
+            connection: Connection::open_in_memory().unwrap(),
+        };
+        db.connection.execute_batch(SCHEMA).unwrap();
+        db.record_download("challenge_0001", 9).unwrap();
+        assert_eq!(db.downloaded_size("challenge_0001").unwrap(), Some(9));
+        assert_eq!(db.downloaded_size("challenge_0002").unwrap(), None);
+        db.record_hash("challenge_0001", HashAlgorithm::Blake2b, &[1; 64])
+            .unwrap();
+        db.record_hash("challenge_0001", HashAlgorithm::Blake2b, &[2; 64])
+            .unwrap();
+        assert_eq!(
+            db.hash("challenge_0001", HashAlgorithm::Blake2b).unwrap(),
+            Some(vec![2; 64])
+        );
+        assert_eq!(
+            db.hash("challenge_0001", HashAlgorithm::Sha256).unwrap(),
+            None
+        );
+        db.record_chain("challenge_0001", "response_0002", ChainStatus::Mismatch)
+            .unwrap();
+        assert_eq!(
+            db.chain("challenge_0001", "response_0002").unwrap(),
+            Some(ChainStatus::Mismatch)
+        );
+        db.record_digest("challenge_0001", 10, &Hash64([3; 64]))
+            .unwrap();
+        assert_eq!(
+            db.digests("challenge_0001").unwrap(),
+            BTreeMap::from([(10, Hash64([3; 64]))])
+        );
+        assert_eq!(
+            db.hashes(HashAlgorithm::Blake2b).unwrap(),
+            BTreeMap::from([("challenge_0001".into(), vec![2; 64])])
+        );
+        db.record_scrub("challenge_0001", false).unwrap();
+        db.record_scrub("challenge_0001", true).unwrap();
+        let scrubs = db.scrubs().unwrap();
+        assert!(scrubs["challenge_0001"].intact);
+        assert!(scrubs["challenge_0001"].age < 60);
+        db.forget("challenge_0001").unwrap();
+        assert!(db.scrubs().unwrap().is_empty());
+        assert!(db.digests("challenge_0001").unwrap().is_empty());
+        assert_eq!(db.downloaded_size("challenge_0001").unwrap(), None);
+        assert_eq!(
+            db.hash("challenge_0001", HashAlgorithm::Blake2b).unwrap(),
+            None
+        );
+        assert_eq!(db.chain("challenge_0001", "response_0002").unwrap(), None);
+        db.record_round(2, 19, Some("invalid proof")).unwrap();
+        db.record_round(1, 19, None).unwrap();
+        let rounds = db.rounds().unwrap();
+        assert_eq!(rounds.len(), 2);
+        assert!(rounds[0].verified);
+        assert_eq!(rounds[1].error.as_deref(), Some("invalid proof"));
+        db.record_level(2, 19, Some("invalid proof")).unwrap();
+        db.record_level(2, 10, None).unwrap();
+        assert_eq!(
+            db.levels().unwrap(),
+            BTreeMap::from([(2, BTreeMap::from([(10, true), (19, false)]))])
+        );
+        let profile = RoundProfile {
+            total_ms: 1500,
+            io_ms: 700,
+            bytes_read: 1 << 30,
+            ..Default::default()
+        };
+        db.record_profile(1, &RoundProfile::default()).unwrap();
+        db.record_profile(1, &profile).unwrap();
+        assert_eq!(db.profiles().unwrap(), BTreeMap::from([(1, profile)]));
+        let check = SignatureCheck {
+            status: SignatureStatus::Valid,
+            signer: Some("564F6C46F7E83CCAC3BF2481FC7E7C659A7E7262".into()),
+        };
+        db.record_attestation(2, &SignatureCheck::UNSIGNED).unwrap();
+        db.record_attestation(2, &check).unwrap();
+        assert_eq!(db.attestations().unwrap(), BTreeMap::from([(2, check)]));
+    }
+}
@@ -0,0 +1,159 @@
+//! DER Encoding
+//!
+//! The small part of the Distinguished Encoding Rules of ASN.1 needed to build timestamp requests
+//! and to read the timestamp tokens and X.509 certificates given back by the authorities the
+//! reports are timestamped and signed with. Only single-byte tags and definite lengths are
+//! supported, which is all DER allows for these structures.
+
+use crate::Result;
+use anyhow::{anyhow, bail, ensure};
+
+/// Tag of booleans
+pub const BOOLEAN: u8 = 0x01;
+
+/// Tag of integers
+pub const INTEGER: u8 = 0x02;
+
+/// Tag of bit strings
+pub const BIT_STRING: u8 = 0x03;
+
+/// Tag of octet strings
+pub const OCTET_STRING: u8 = 0x04;
+
+/// Tag of nulls
+pub const NULL: u8 = 0x05;
+
+/// Tag of object identifiers
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+
+/// Tag of UTF-8 strings
+pub const UTF8_STRING: u8 = 0x0c;
+
+/// Tag of UTC times
+pub const UTC_TIME: u8 = 0x17;
+
+/// Tag of generalized times
+pub const GENERALIZED_TIME: u8 = 0x18;
+
+/// Tag of sequences
+pub const SEQUENCE: u8 = 0x30;
+
+/// Tag of sets
+pub const SET: u8 = 0x31;
+
+/// Returns the tag of the explicit context-specific field `[n]`.
+#[inline]
+pub const fn explicit(n: u8) -> u8 {
+    0xa0 | n
+}
+
+/// Returns the tag of the implicit primitive context-specific field `[n]`.
+#[inline]
+pub const fn implicit(n: u8) -> u8 {
+    0x80 | n
+}
+
+/// Encodes the value of `tag` holding `content`.
+#[inline]
+pub fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut value = vec![tag];
+    match content.len() {
+        length @ 0..=0x7f => value.push(length as u8),
+        length => {
+            let bytes = length.to_be_bytes();
+            let skip = bytes.iter().take_while(|byte| **byte == 0).count();
+            value.push(0x80 | (bytes.len() - skip) as u8);
+            value.extend_from_slice(&bytes[skip..]);
+        }
+    }
+    value.extend_from_slice(content);
+    value
+}
+
+/// Returns the content of the encoding of the non-negative integer with big-endian `bytes`.
+#[inline]
+pub fn integer_content(bytes: &[u8]) -> Vec<u8> {
+    let bytes = &bytes[bytes.iter().take_while(|byte| **byte == 0).count()..];
+    let mut content = Vec::with_capacity(bytes.len() + 1);
+    if !matches!(bytes.first(), Some(byte) if byte & 0x80 == 0) {
+        content.push(0);
+    }
+    content.extend_from_slice(bytes);
+    content
+}
+
+/// Reader of DER Values
+#[derive(Clone, Copy, Debug)]
+pub struct Reader<'d>(pub &'d [u8]);
+
+impl<'d> Reader<'d> {
+    /// Returns `true` if every value has been read.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reads the next value, returning its tag and content.
+    #[inline]
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<(u8, &'d [u8])> {
+        let (tag, content, rest) = split(self.0)?;
+        self.0 = rest;
+        Ok((tag, content))
+    }
+
+    /// Reads the next value, returning its whole encoding, tag and length included.
+    #[inline]
+    pub fn next_encoded(&mut self) -> Result<&'d [u8]> {
+        let (_, _, rest) = split(self.0)?;
+        let encoded = &self.0[..self.0.len() - rest.len()];
+        self.0 = rest;
+        Ok(encoded)
+    }
+
+    /// Reads the next value, checking that it has `tag`, and returns its content.
+    #[inline]
+    pub fn expect(&mut self, tag: u8) -> Result<&'d [u8]> {
+        let (found, content) = self.next()?;
+        ensure!(
+            found == tag,
+            "Unexpected DER tag {:#04x}, expected {:#04x}.",
+            found,
+            tag
+        );
+        Ok(content)
+    }
+
+    /// Reads the next value if it has `tag`, returning its content.
+    #[inline]
+    pub fn optional(&mut self, tag: u8) -> Result<Option<&'d [u8]>> {
+        match self.0.first() {
+            Some(found) if *found == tag => self.expect(tag).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Splits the first value of `data` into its tag and content, returned with the rest of `data`.
+#[inline]
+fn split(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let truncated = || anyhow!("Truncated DER value.");
+    let (&tag, rest) = data.split_first().ok_or_else(truncated)?;
+    let (&first, mut rest) = rest.split_first().ok_or_else(truncated)?;
+    let length = match first {
+        0..=0x7f => first as usize,
+        0x81..=0x84 => {
+            let count = (first & 0x7f) as usize;
+            ensure!(count <= rest.len(), "Truncated DER value.");
+            let (bytes, tail) = rest.split_at(count);
+            rest = tail;
+            bytes
+                .iter()
+                .fold(0, |length, byte| (length << 8) | *byte as usize)
+        }
+        _ => bail!("Unsupported DER length."),
+    };
+    ensure!(length <= rest.len(), "Truncated DER value.");
+    let (content, rest) = rest.split_at(length);
+    Ok((tag, content, rest))
+}
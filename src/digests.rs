@@ -0,0 +1,144 @@
+//! Subaccumulator Digests
+//!
+//! Whoever extracts an SRS of `2^k` powers from a challenge file, with `ppot extract` or any tool
+//! keeping the first points of each section, ends up with the challenge file of `2^k` powers built
+//! from the byte ranges given by [`Layout::prefix_ranges`]. Its Blake2b hash is the digest of the
+//! first `2^k` powers of the challenge file, which the `hasher` computes for every level of
+//! [`DIGEST_LOG_POWERS`] in the same pass as the hash of the whole file, so that the extracted files
+//! can be checked against the published digests without running the pipeline again.
+
+use crate::{
+    format::{Layout, CEREMONY_LOG_POWERS},
+    hash::Hash64,
+};
+use blake2::{Blake2b512, Digest};
+use core::ops::{Range, RangeInclusive};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+/// Base-two logarithms of the numbers of powers digests are computed for
+pub const DIGEST_LOG_POWERS: RangeInclusive<u32> = 10..=CEREMONY_LOG_POWERS;
+
+/// Digest of the First Powers of a Challenge File
+struct PrefixDigest {
+    /// Base-two logarithm of the number of powers
+    log_powers: u32,
+
+    /// Byte ranges of the challenge file making up the prefix, in order
+    ranges: Vec<Range<usize>>,
+
+    /// Hasher fed with the bytes of the ranges read so far
+    hasher: Blake2b512,
+}
+
+impl PrefixDigest {
+    /// Feeds the bytes of `chunk`, starting at `offset` in the file, which fall in the ranges of
+    /// the prefix into the hasher.
+    #[inline]
+    fn update(&mut self, offset: usize, chunk: &[u8]) {
+        let end = offset + chunk.len();
+        for range in &self.ranges {
+            let (start, stop) = (range.start.max(offset), range.end.min(end));
+            if start < stop {
+                self.hasher.update(&chunk[start - offset..stop - offset]);
+            }
+        }
+    }
+}
+
+/// Digests of the First Powers of a Challenge File, Computed in One Pass
+pub struct PrefixDigests {
+    /// Digest of every level
+    digests: Vec<PrefixDigest>,
+}
+
+impl PrefixDigests {
+    /// Starts computing the digests of the challenge file laid out as `layout` for every level of
+    /// `log_powers` below its number of powers. The prefix of as many powers as the file holds is
+    /// the whole file, whose digest is its hash.
+    #[inline]
+    pub fn new<I>(layout: &Layout, log_powers: I) -> Self
+    where
+        I: IntoIterator<Item = u32>,
+    {
+        Self {
+            digests: log_powers
+                .into_iter()
+                .filter(|log_powers| (1 << log_powers) < layout.powers)
+                .map(|log_powers| PrefixDigest {
+                    log_powers,
+                    ranges: layout.prefix_ranges(1 << log_powers),
+                    hasher: Blake2b512::new(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Feeds the next `chunk` of the file, starting at `offset`, into every digest. The chunks
+    /// must be fed in order.
+    #[inline]
+    pub fn update(&mut self, offset: u64, chunk: &[u8]) {
+        self.digests
+            .par_iter_mut()
+            .for_each(|digest| digest.update(offset as usize, chunk));
+    }
+
+    /// Returns the digest of every level, by base-two logarithm of its number of powers.
+    #[inline]
+    pub fn finalize(self) -> BTreeMap<u32, Hash64> {
+        self.digests
+            .into_iter()
+            .map(|digest| {
+                let mut hash = [0; 64];
+                hash.copy_from_slice(&digest.hasher.finalize());
+                (digest.log_powers, Hash64(hash))
+            })
+            .collect()
+    }
+}
+
+/// Computes the digests of the first powers of the challenge file `bytes` laid out as `layout`
+/// for every level of `log_powers` below its number of powers, see [`PrefixDigests`].
+#[inline]
+pub fn prefix_digests<I>(bytes: &[u8], layout: &Layout, log_powers: I) -> BTreeMap<u32, Hash64>
+where
+    I: IntoIterator<Item = u32>,
+{
+    let mut digests = PrefixDigests::new(layout, log_powers);
+    digests.update(0, bytes);
+    digests.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{calculate_hash, HashAlgorithm};
+
+    /// Checks that the digests fed by chunks are the hashes of the files holding the first powers,
+    /// as written by `extract`.
+    #[test]
+    fn digests_match_extracted_files() {
+        let layout = Layout::challenge(1 << 4);
+        let bytes = (0..layout.file_size())
+            .map(|i| (i * 7 % 251) as u8)
+            .collect::<Vec<_>>();
+        let mut digests = PrefixDigests::new(&layout, 1..=4);
+        for (i, chunk) in bytes.chunks(1000).enumerate() {
+            digests.update(i as u64 * 1000, chunk);
+        }
+        let digests = digests.finalize();
+        assert_eq!(digests.keys().copied().collect::<Vec<_>>(), [1, 2, 3]);
+        for (log_powers, digest) in &digests {
+            let extracted = layout
+                .prefix_ranges(1 << log_powers)
+                .into_iter()
+                .flat_map(|range| bytes[range].to_vec())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                digest.0.to_vec(),
+                calculate_hash(&extracted, HashAlgorithm::Blake2b)
+            );
+        }
+        assert_eq!(prefix_digests(&bytes, &layout, 1..=4), digests);
+    }
+}
@@ -0,0 +1,100 @@
+//! Disk Space
+
+use crate::Result;
+use core::fmt;
+use indicatif::HumanBytes;
+use std::{fs, path::Path};
+
+/// Disk Space Estimate
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SpaceEstimate {
+    /// Number of bytes still to be written to disk
+    pub required: u64,
+
+    /// Number of bytes available on the target filesystem
+    pub available: u64,
+
+    /// Number of files whose size is unknown and which are not counted in `required`
+    pub unknown: usize,
+}
+
+impl SpaceEstimate {
+    /// Returns `true` if the files fit in the available space.
+    #[inline]
+    pub fn fits(&self) -> bool {
+        self.required <= self.available
+    }
+}
+
+impl fmt::Display for SpaceEstimate {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} required, {} available",
+            HumanBytes(self.required),
+            HumanBytes(self.available)
+        )?;
+        if self.unknown > 0 {
+            write!(f, ", not counting {} files of unknown size", self.unknown)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the number of bytes missing from the file at `path` for it to reach `size` bytes.
+#[inline]
+pub fn remaining_bytes<P>(path: P, size: u64) -> u64
+where
+    P: AsRef<Path>,
+{
+    let existing = fs::metadata(path).map_or(0, |metadata| metadata.len());
+    size.saturating_sub(existing)
+}
+
+/// Estimates the space needed to download `files`, given as paths and total sizes, and compares
+/// it with the space available on the filesystem holding `directory`.
+#[inline]
+pub fn estimate<D, I, P>(directory: D, files: I) -> Result<SpaceEstimate>
+where
+    D: AsRef<Path>,
+    I: IntoIterator<Item = (P, Option<u64>)>,
+    P: AsRef<Path>,
+{
+    let mut estimate = SpaceEstimate {
+        available: fs2::available_space(directory)?,
+        ..Default::default()
+    };
+    for (path, size) in files {
+        match size {
+            Some(size) => estimate.required += remaining_bytes(path, size),
+            _ => estimate.unknown += 1,
+        }
+    }
+    Ok(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that only the bytes missing from partial files are counted.
+    #[test]
+    fn estimate_counts_missing_bytes() {
+        let directory = std::env::temp_dir();
+        let partial = directory.join("ppot-verifier-disk-test");
+        fs::write(&partial, [0; 100]).unwrap();
+        let estimate = estimate(
+            &directory,
+            [
+                (partial.clone(), Some(1000)),
+                (directory.join("ppot-verifier-disk-test-missing"), Some(50)),
+                (directory.join("ppot-verifier-disk-test-unknown"), None),
+            ],
+        )
+        .unwrap();
+        fs::remove_file(partial).unwrap();
+        assert_eq!(estimate.required, 950);
+        assert_eq!(estimate.unknown, 1);
+    }
+}
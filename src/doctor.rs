@@ -0,0 +1,368 @@
+//! Environment Diagnostics
+//!
+//! A verification of the whole ceremony downloads hundreds of gigabytes and runs for days, so the
+//! environment is worth checking before it starts rather than when it fails: the space left for
+//! the files, the memory the verification of the requested powers needs, the process limits and
+//! kernel settings which make mapping large files fail, the reachability of the blob store and the
+//! speed at which the storage directory is written to. Every check gives a [`Finding`], whose
+//! message says what to change when the check does not pass.
+
+use crate::{
+    disk::SpaceEstimate,
+    memory::{fit_powers, verification_size, ByteSize},
+    plan::probe_bandwidth,
+    Result,
+};
+use core::fmt;
+use indicatif::HumanBytes;
+use reqwest::Client;
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    time::Instant,
+};
+
+/// Smallest write speed, in bytes per second, below which hashing is bound by the disk
+pub const MIN_WRITE_SPEED: u64 = 100 << 20;
+
+/// Smallest download speed, in bytes per second, below which a warning is given
+pub const MIN_BANDWIDTH: u64 = 10 << 20;
+
+/// Smallest number of open files the segmented downloads and the database need
+pub const MIN_OPEN_FILES: u64 = 1024;
+
+/// Smallest number of memory mappings of a process, the Linux default
+pub const MIN_MAP_COUNT: u64 = 65530;
+
+/// Size of the file written to measure the write speed of the storage directory
+pub const WRITE_PROBE_SIZE: usize = 256 << 20;
+
+/// Severity of a Finding
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The check passed
+    Ok,
+
+    /// The run may be slow or fail under some conditions
+    Warning,
+
+    /// The run is bound to fail
+    Error,
+}
+
+impl Severity {
+    /// Returns the name of `self`.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        }
+    }
+}
+
+/// Outcome of a Check
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Finding {
+    /// Name of the check
+    pub check: &'static str,
+
+    /// Severity of the outcome
+    pub severity: Severity,
+
+    /// Description of the outcome, with the action to take if the check did not pass
+    pub message: String,
+}
+
+impl Finding {
+    /// Builds a [`Finding`] of `check` with `severity` and `message`.
+    #[inline]
+    pub fn new<M>(check: &'static str, severity: Severity, message: M) -> Self
+    where
+        M: Into<String>,
+    {
+        Self {
+            check,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "[{:>7}] {}: {}",
+            self.severity.name(),
+            self.check,
+            self.message
+        )
+    }
+}
+
+/// Checks that the files to download fit in the space available, as given by `estimate`.
+#[inline]
+pub fn check_disk(estimate: &SpaceEstimate) -> Finding {
+    if !estimate.fits() {
+        Finding::new(
+            "disk",
+            Severity::Error,
+            format!(
+                "{}, free {} or pick fewer rounds with `--rounds`",
+                estimate,
+                HumanBytes(estimate.required - estimate.available)
+            ),
+        )
+    } else if estimate.unknown > 0 {
+        Finding::new(
+            "disk",
+            Severity::Warning,
+            format!("{}, run `ppot sync` to learn their sizes", estimate),
+        )
+    } else {
+        Finding::new("disk", Severity::Ok, estimate.to_string())
+    }
+}
+
+/// Checks that verifying `2^log_powers` powers fits in the `available` memory, suggesting the
+/// largest power count which does otherwise.
+#[inline]
+pub fn check_memory(available: Option<u64>, log_powers: u32) -> Finding {
+    let needed = verification_size(1 << log_powers);
+    let available = match available {
+        Some(available) => available,
+        _ => {
+            return Finding::new(
+                "memory",
+                Severity::Warning,
+                format!(
+                    "verifying 2^{} powers needs {}, the available memory is unknown",
+                    log_powers,
+                    HumanBytes(needed)
+                ),
+            )
+        }
+    };
+    if needed <= available {
+        return Finding::new(
+            "memory",
+            Severity::Ok,
+            format!(
+                "verifying 2^{} powers needs {} of the {} available",
+                log_powers,
+                HumanBytes(needed),
+                HumanBytes(available)
+            ),
+        );
+    }
+    let advice = match fit_powers(log_powers, 1, Some(ByteSize(available))) {
+        Ok(fitting) => format!("verify 2^{} powers with `--powers {}`", fitting, fitting),
+        _ => String::from("free some memory"),
+    };
+    Finding::new(
+        "memory",
+        Severity::Error,
+        format!(
+            "verifying 2^{} powers needs {} but only {} is available, {}",
+            log_powers,
+            HumanBytes(needed),
+            HumanBytes(available),
+            advice
+        ),
+    )
+}
+
+/// Checks the process limits and the kernel settings on which mapping and allocating the
+/// ceremony files depend.
+#[inline]
+pub fn check_limits() -> Vec<Finding> {
+    let mut findings = Vec::new();
+    #[cfg(unix)]
+    {
+        let limit = |resource| {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            // SAFETY: `limit` is a valid `rlimit` for `getrlimit` to write to.
+            let status = unsafe { libc::getrlimit(resource, &mut limit) };
+            #[allow(clippy::unnecessary_cast)] // `rlim_t` is only `u64` on some platforms
+            let current = limit.rlim_cur as u64;
+            match status {
+                0 if limit.rlim_cur == libc::RLIM_INFINITY => None,
+                0 => Some(current),
+                _ => None,
+            }
+        };
+        findings.push(match limit(libc::RLIMIT_AS) {
+            Some(size) => Finding::new(
+                "address space",
+                Severity::Warning,
+                format!(
+                    "limited to {}, mapping the ceremony files may fail, raise it with \
+                     `ulimit -v unlimited`",
+                    HumanBytes(size)
+                ),
+            ),
+            _ => Finding::new("address space", Severity::Ok, "unlimited"),
+        });
+        findings.push(match limit(libc::RLIMIT_NOFILE) {
+            Some(count) if count < MIN_OPEN_FILES => Finding::new(
+                "open files",
+                Severity::Warning,
+                format!(
+                    "limited to {}, raise it with `ulimit -n {}`",
+                    count, MIN_OPEN_FILES
+                ),
+            ),
+            Some(count) => {
+                Finding::new("open files", Severity::Ok, format!("limited to {}", count))
+            }
+            _ => Finding::new("open files", Severity::Ok, "unlimited"),
+        });
+    }
+    let read = |path| {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok())
+    };
+    if let Some(mode) = read("/proc/sys/vm/overcommit_memory") {
+        findings.push(check_overcommit(mode));
+    }
+    if let Some(count) = read("/proc/sys/vm/max_map_count") {
+        findings.push(if count < MIN_MAP_COUNT {
+            Finding::new(
+                "memory maps",
+                Severity::Warning,
+                format!(
+                    "limited to {}, raise it with `sysctl vm.max_map_count={}`",
+                    count, MIN_MAP_COUNT
+                ),
+            )
+        } else {
+            Finding::new("memory maps", Severity::Ok, format!("limited to {}", count))
+        });
+    }
+    findings
+}
+
+/// Checks the overcommit `mode` of the Linux kernel, read from `vm.overcommit_memory`.
+#[inline]
+pub fn check_overcommit(mode: u64) -> Finding {
+    match mode {
+        2 => Finding::new(
+            "overcommit",
+            Severity::Warning,
+            "strict, mapping the ceremony files counts against the commit limit and may fail, set \
+             `sysctl vm.overcommit_memory=0`",
+        ),
+        _ => Finding::new("overcommit", Severity::Ok, format!("mode {}", mode)),
+    }
+}
+
+/// Checks that the blob store at `url` answers, measuring the bandwidth to it by downloading its
+/// first `size` bytes.
+#[inline]
+pub async fn check_network(client: &Client, url: &str, size: u64) -> Finding {
+    match probe_bandwidth(client, url, size).await {
+        Ok(bandwidth) if bandwidth < MIN_BANDWIDTH => Finding::new(
+            "network",
+            Severity::Warning,
+            format!(
+                "{}/s from {}, downloading the ceremony will take days",
+                HumanBytes(bandwidth),
+                url
+            ),
+        ),
+        Ok(bandwidth) => Finding::new(
+            "network",
+            Severity::Ok,
+            format!("{}/s from {}", HumanBytes(bandwidth), url),
+        ),
+        Err(err) => Finding::new(
+            "network",
+            Severity::Error,
+            format!(
+                "{} is unreachable: {}, check the proxy settings of `ppot.toml`",
+                url, err
+            ),
+        ),
+    }
+}
+
+/// Measures the speed at which `size` bytes are written and synced to a file in `dir`.
+#[inline]
+pub fn check_write_speed<P>(dir: P, size: usize) -> Result<Finding>
+where
+    P: AsRef<Path>,
+{
+    let path = dir
+        .as_ref()
+        .join(format!(".ppot-doctor-{}", std::process::id()));
+    let chunk = vec![0x5a; 1 << 20];
+    let start = Instant::now();
+    let written = (|| {
+        let mut file = File::create(&path)?;
+        let mut written = 0;
+        while written < size {
+            let len = chunk.len().min(size - written);
+            file.write_all(&chunk[..len])?;
+            written += len;
+        }
+        file.sync_all()?;
+        Ok::<_, std::io::Error>(written)
+    })();
+    let elapsed = start.elapsed().as_secs_f64().max(1e-3);
+    let _ = fs::remove_file(&path);
+    let speed = (written? as f64 / elapsed) as u64;
+    Ok(if speed < MIN_WRITE_SPEED {
+        Finding::new(
+            "write speed",
+            Severity::Warning,
+            format!(
+                "{}/s to {}, downloads and hashing will be bound by the disk",
+                HumanBytes(speed),
+                dir.as_ref().display()
+            ),
+        )
+    } else {
+        Finding::new(
+            "write speed",
+            Severity::Ok,
+            format!("{}/s to {}", HumanBytes(speed), dir.as_ref().display()),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that shortages of memory and disk space are errors, with the power count to use
+    /// instead, and that the write speed of the temporary directory is measured.
+    #[test]
+    fn findings_flag_shortages() {
+        let needed = verification_size(1 << 19);
+        assert_eq!(check_memory(Some(needed), 19).severity, Severity::Ok);
+        let finding = check_memory(Some(verification_size(1 << 17)), 19);
+        assert_eq!(finding.severity, Severity::Error);
+        assert!(finding.message.contains("`--powers 17`"));
+        assert_eq!(check_memory(None, 19).severity, Severity::Warning);
+        let estimate = |required, unknown| SpaceEstimate {
+            required,
+            available: 100,
+            unknown,
+        };
+        assert_eq!(check_disk(&estimate(50, 0)).severity, Severity::Ok);
+        assert_eq!(check_disk(&estimate(50, 1)).severity, Severity::Warning);
+        assert_eq!(check_disk(&estimate(150, 0)).severity, Severity::Error);
+        assert_eq!(check_overcommit(2).severity, Severity::Warning);
+        assert_eq!(check_overcommit(0).severity, Severity::Ok);
+        let finding = check_write_speed(std::env::temp_dir(), 1 << 20).unwrap();
+        assert_eq!(finding.check, "write speed");
+    }
+}
@@ -0,0 +1,1509 @@
+//! Resumable Downloads
+
+use crate::{
+    atomic,
+    hash::Hash64,
+    hash_path, into_array_unchecked,
+    lock::FileLock,
+    memory::{available_memory, download_buffer_size},
+    progress::ProgressSink,
+    segment::{self, download_segmented},
+    throttle::{ByteRate, RateLimiter},
+    validator::{RemoteFileChanged, Validator},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use blake2::{Blake2b512, Digest};
+use core::{cmp::min, fmt, num::ParseIntError, ops::Range, str::FromStr, time::Duration};
+use futures::future::join_all;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use rand::Rng;
+use reqwest::{
+    header::{CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE},
+    Client, ClientBuilder, Method, Proxy, Response, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{self, Read, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    fs::{self, File, OpenOptions},
+    io::{AsyncSeekExt, AsyncWriteExt, BufWriter},
+    task,
+    time::{sleep, timeout},
+};
+use tokio_util::sync::CancellationToken;
+
+/// Amount of time without receiving any data after which a transfer is considered stalled
+pub const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of stalls tolerated on a single mirror before failing over to the next one
+pub const MAX_STALLS: usize = 3;
+
+/// Default amount of time allowed to establish a connection
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of times a download is retried after every mirror has failed
+pub const RETRIES: usize = 5;
+
+/// Default delay before the first retry, doubled on every subsequent retry
+pub const BACKOFF: Duration = Duration::from_secs(2);
+
+/// Default upper bound on the delay between two retries
+pub const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Default value of the `User-Agent` header of the requests
+pub const USER_AGENT: &str = concat!("ppot-verifier/", env!("CARGO_PKG_VERSION"));
+
+/// TLS library the HTTP clients are built against, preferring rustls when both are enabled
+pub const TLS_BACKEND: &str = if cfg!(feature = "rustls") {
+    "rustls"
+} else if cfg!(feature = "native-tls") {
+    "native-tls"
+} else {
+    "none"
+};
+
+/// HTTP Client Options
+///
+/// Read from the `[http]` table of the [`Config`](crate::config::Config), every field being
+/// optional:
+///
+/// ```toml
+/// [http]
+/// http2 = false
+/// pool_max_idle_per_host = 16
+/// tcp_keepalive_secs = 30
+/// user_agent = "archive-verifier/1.0"
+/// ```
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpOptions {
+    /// Negotiates HTTP/2 with the servers offering it, sticking to HTTP/1.1 otherwise
+    pub http2: bool,
+
+    /// Maximum number of idle connections kept open to each host
+    pub pool_max_idle_per_host: Option<usize>,
+
+    /// Number of seconds after which idle connections are closed
+    pub pool_idle_timeout_secs: Option<u64>,
+
+    /// Interval in seconds of the TCP keepalive probes, disabled if unset
+    pub tcp_keepalive_secs: Option<u64>,
+
+    /// Disables Nagle's algorithm on the connections
+    pub tcp_nodelay: bool,
+
+    /// Value of the `User-Agent` header, [`USER_AGENT`] by default
+    pub user_agent: Option<String>,
+}
+
+impl HttpOptions {
+    /// Applies `self` to `builder`.
+    #[inline]
+    pub fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        builder = builder
+            .user_agent(self.user_agent.as_deref().unwrap_or(USER_AGENT))
+            .tcp_nodelay(self.tcp_nodelay)
+            .tcp_keepalive(self.tcp_keepalive_secs.map(Duration::from_secs));
+        if !self.http2 {
+            builder = builder.http1_only();
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(timeout));
+        }
+        builder
+    }
+}
+
+impl Default for HttpOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            http2: true,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout_secs: None,
+            tcp_keepalive_secs: None,
+            tcp_nodelay: true,
+            user_agent: None,
+        }
+    }
+}
+
+/// Download Options
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    /// Bandwidth cap shared by every download using these options
+    pub rate_limit: Option<Arc<RateLimiter>>,
+
+    /// Bandwidth cap applied to each download separately
+    pub rate_limit_per_file: Option<ByteRate>,
+
+    /// Amount of time allowed to establish a connection
+    pub connect_timeout: Duration,
+
+    /// Amount of time without receiving any data after which a transfer is considered stalled
+    pub read_timeout: Duration,
+
+    /// Number of times a download is retried after every mirror has failed
+    pub retries: usize,
+
+    /// Delay before the first retry, doubled on every subsequent retry
+    pub backoff: Duration,
+
+    /// Upper bound on the delay between two retries
+    pub max_backoff: Duration,
+
+    /// Number of connections each file is downloaded over, see [`download_segmented`]
+    pub segments: usize,
+
+    /// Proxy every request goes through, like `http://proxy:3128` or `socks5h://proxy:1080`,
+    /// overriding the `HTTP_PROXY`, `HTTPS_PROXY` and `ALL_PROXY` environment variables
+    pub proxy: Option<String>,
+
+    /// Ignores the proxy environment variables and connects directly
+    pub no_proxy: bool,
+
+    /// Computes the Blake2b hash of each file as it is downloaded and saves it to its
+    /// [`hash_path`](Self::hash_path)
+    pub hash: bool,
+
+    /// Directory the hashes of the downloaded files are saved to, next to each file by default
+    pub hash_dir: Option<PathBuf>,
+
+    /// Token stopping every download using these options once cancelled, see [`Cancelled`]
+    pub cancel: CancellationToken,
+
+    /// Connection settings of the HTTP client
+    pub http: HttpOptions,
+
+    /// Size of the buffer the received bytes are written to disk through, by default a share of
+    /// the available memory, see [`download_buffer_size`]
+    pub buffer_size: usize,
+}
+
+impl DownloadOptions {
+    /// Builds [`DownloadOptions`] capping the total bandwidth at `rate_limit` and the bandwidth of
+    /// each download at `rate_limit_per_file`.
+    #[inline]
+    pub fn with_rate_limits(
+        rate_limit: Option<ByteRate>,
+        rate_limit_per_file: Option<ByteRate>,
+    ) -> Self {
+        Self {
+            rate_limit: rate_limit.map(|rate| Arc::new(RateLimiter::new(rate))),
+            rate_limit_per_file,
+            ..Default::default()
+        }
+    }
+
+    /// Builds a [`Client`] honoring the timeouts, proxy and connection settings of `self`. Unless a
+    /// proxy is set or [`no_proxy`](Self::no_proxy) is enabled, the proxy is taken from the
+    /// environment.
+    #[inline]
+    pub fn client(&self) -> Result<Client> {
+        let mut builder = self
+            .http
+            .apply(Client::builder().connect_timeout(self.connect_timeout));
+        #[cfg(feature = "rustls")]
+        {
+            builder = builder.use_rustls_tls();
+        }
+        if self.no_proxy {
+            builder = builder.no_proxy();
+        } else if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                Proxy::all(proxy.as_str())
+                    .map_err(|err| anyhow!("Invalid proxy '{}': {}", proxy, err))?,
+            );
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Returns the path the hash of the file downloaded to `path` is saved to.
+    #[inline]
+    pub fn hash_path(&self, path: &Path) -> PathBuf {
+        match &self.hash_dir {
+            Some(dir) => hash_path(dir.join(path.file_name().unwrap_or_default())),
+            _ => hash_path(path),
+        }
+    }
+
+    /// Returns the delay before retry number `attempt`, starting at zero. The delay grows
+    /// exponentially up to [`max_backoff`](Self::max_backoff) and is then scaled by a random
+    /// factor between one half and one, so that concurrent downloads failing together do not all
+    /// retry at the same time.
+    #[inline]
+    pub fn backoff_delay(&self, attempt: usize) -> Duration {
+        let delay = self
+            .backoff
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_backoff);
+        delay.mul_f64(rand::thread_rng().gen_range(0.5..=1.0))
+    }
+}
+
+impl Default for DownloadOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            rate_limit: None,
+            rate_limit_per_file: None,
+            connect_timeout: CONNECT_TIMEOUT,
+            read_timeout: STALL_TIMEOUT,
+            retries: RETRIES,
+            backoff: BACKOFF,
+            max_backoff: MAX_BACKOFF,
+            segments: 1,
+            proxy: None,
+            no_proxy: false,
+            hash: true,
+            hash_dir: None,
+            cancel: CancellationToken::new(),
+            http: HttpOptions::default(),
+            buffer_size: download_buffer_size(available_memory()),
+        }
+    }
+}
+
+/// Cancelled Download Error
+///
+/// Returned when the [`cancel`](DownloadOptions::cancel) token of the options of a download is
+/// cancelled. The bytes received so far are flushed to disk along with the state needed to resume
+/// the download later.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "The download was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Remote File Information
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RemoteFileInfo {
+    /// Whether the server has the file
+    pub exists: bool,
+
+    /// Size of the file, if the server reports it
+    pub size: Option<u64>,
+
+    /// Entity tag of the file, if the server reports it
+    pub etag: Option<String>,
+}
+
+impl RemoteFileInfo {
+    /// Reads the [`RemoteFileInfo`] from the headers of `response`.
+    #[inline]
+    fn from_response(response: &Response) -> Self {
+        let size = match ContentRange::from_response(response) {
+            Some(ContentRange::Full { size, .. } | ContentRange::Size(size)) => Some(size),
+            _ => response.content_length(),
+        };
+        Self {
+            exists: response.status().is_success(),
+            size,
+            etag: response
+                .headers()
+                .get(ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+        }
+    }
+}
+
+/// Checks if the file at `url` exists by sending a [`HEAD`](Method::HEAD) request to the server,
+/// capturing its size and entity tag along the way. Servers which refuse `HEAD` requests, like
+/// presigned URLs which are only signed for [`GET`](Method::GET), are asked for the first byte of
+/// the file instead so that the body is never streamed.
+#[inline]
+pub async fn file_exists(client: &Client, url: &str) -> Result<RemoteFileInfo> {
+    let response = client.request(Method::HEAD, url).send().await?;
+    if response.status().is_success() || response.status() == StatusCode::NOT_FOUND {
+        return Ok(RemoteFileInfo::from_response(&response));
+    }
+    let response = client
+        .request(Method::GET, url)
+        .header(RANGE, "bytes=0-0")
+        .send()
+        .await?;
+    Ok(RemoteFileInfo::from_response(&response))
+}
+
+/// Content Range
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ContentRange {
+    /// Full Range Data
+    ///
+    /// This is the response from the server when the [`RANGE`] header `start` value is set less
+    /// than the entire data stream.
+    Full {
+        /// Starting index
+        start: u64,
+
+        /// Ending index
+        end: u64,
+
+        /// Total size of the data stored on the server. This is not a measure of how much data is
+        /// sent over in the response, that would be `end - start`.
+        size: u64,
+    },
+
+    /// Size Data
+    ///
+    /// When the [`RANGE`] header `start` value sent to the server is exactly equal to the size of
+    /// the data payload, then only that same size is returned back.
+    Size(u64),
+}
+
+impl ContentRange {
+    /// Returns the byte range sent in the response, with an exclusive end, or `None` for an
+    /// unsatisfiable range.
+    #[inline]
+    pub fn range(&self) -> Option<Range<u64>> {
+        match self {
+            Self::Full { start, end, .. } => Some(*start..*end + 1),
+            Self::Size(_) => None,
+        }
+    }
+
+    /// Returns the total size of the file on the server.
+    #[inline]
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Full { size, .. } | Self::Size(size) => *size,
+        }
+    }
+
+    /// Parses a [`ContentRange`] from `response` returning `None` if the header did not exist or if
+    /// it did exist but could not be parsed.
+    #[inline]
+    pub fn from_response(response: &Response) -> Option<Self> {
+        response
+            .headers()
+            .get(CONTENT_RANGE)?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()
+    }
+}
+
+impl FromStr for ContentRange {
+    type Err = ContentRangeParseError;
+
+    #[inline]
+    fn from_str(range_string: &str) -> Result<Self, Self::Err> {
+        let (bytes_tag, range) = range_string
+            .split_once(' ')
+            .ok_or(Self::Err::MissingSpace)?;
+        if bytes_tag == "bytes" {
+            match range.split_once('-') {
+                Some((start, end_and_size)) => {
+                    let (end, size) = end_and_size
+                        .split_once('/')
+                        .ok_or(Self::Err::MissingSlash)?;
+                    let (start, end) = (
+                        start.parse().map_err(Self::Err::InvalidStart)?,
+                        end.parse().map_err(Self::Err::InvalidEnd)?,
+                    );
+                    if end < start {
+                        return Err(Self::Err::InvertedRange);
+                    }
+                    Ok(Self::Full {
+                        start,
+                        end,
+                        size: size.parse().map_err(Self::Err::InvalidSize)?,
+                    })
+                }
+                _ => {
+                    let (star, size) = range.split_once('/').ok_or(Self::Err::MissingSlash)?;
+                    if star == "*" {
+                        Ok(Self::Size(size.parse().map_err(Self::Err::InvalidSize)?))
+                    } else {
+                        Err(Self::Err::MissingStar)
+                    }
+                }
+            }
+        } else {
+            Err(Self::Err::MissingBytesTag)
+        }
+    }
+}
+
+/// Content Range Parse Error
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ContentRangeParseError {
+    /// Missing Space
+    MissingSpace,
+
+    /// Missing Bytes Tag
+    MissingBytesTag,
+
+    /// Missing Slash
+    MissingSlash,
+
+    /// Missing Star
+    MissingStar,
+
+    /// Invalid Start Index
+    InvalidStart(ParseIntError),
+
+    /// Invalid End Index
+    InvalidEnd(ParseIntError),
+
+    /// Invalid Size Index
+    InvalidSize(ParseIntError),
+
+    /// End Index Before the Start Index
+    InvertedRange,
+}
+
+impl fmt::Display for ContentRangeParseError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingSpace => write!(f, "Missing space after the unit of the content range"),
+            Self::MissingBytesTag => write!(f, "The content range is not in bytes"),
+            Self::MissingSlash => write!(f, "Missing size of the content range"),
+            Self::MissingStar => write!(f, "Missing range of the content range"),
+            Self::InvalidStart(err) => write!(f, "Invalid start of the content range: {}", err),
+            Self::InvalidEnd(err) => write!(f, "Invalid end of the content range: {}", err),
+            Self::InvalidSize(err) => write!(f, "Invalid size of the content range: {}", err),
+            Self::InvertedRange => write!(f, "The content range ends before it starts"),
+        }
+    }
+}
+
+impl std::error::Error for ContentRangeParseError {}
+
+/// Byte Range
+///
+/// One range of the [`RANGE`] header of a request, with an inclusive end as in the header.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ByteRange {
+    /// Every byte from `start` to the end of the file, `bytes=start-`
+    From(u64),
+
+    /// The bytes from `start` to `end` included, `bytes=start-end`
+    Bounded {
+        /// Starting index
+        start: u64,
+
+        /// Ending index, included
+        end: u64,
+    },
+
+    /// The last bytes of the file, `bytes=-length`
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Returns the range of bytes covering `range`, with an exclusive end.
+    #[inline]
+    pub fn of(range: Range<u64>) -> Self {
+        Self::Bounded {
+            start: range.start,
+            end: range.end.saturating_sub(1),
+        }
+    }
+
+    /// Resolves `self` against a file of `size` bytes into the range of bytes the server sends,
+    /// with an exclusive end, or `None` if none of them are in the file.
+    #[inline]
+    pub fn resolve(&self, size: u64) -> Option<Range<u64>> {
+        match *self {
+            Self::From(start) if start < size => Some(start..size),
+            Self::Bounded { start, end } if start < size && start <= end => {
+                Some(start..min(end + 1, size))
+            }
+            Self::Suffix(length) if length > 0 && size > 0 => {
+                Some(size.saturating_sub(length)..size)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value of the [`RANGE`] header requesting all the `ranges` at once.
+    #[inline]
+    pub fn header(ranges: &[Self]) -> String {
+        let ranges = ranges.iter().map(ToString::to_string).collect::<Vec<_>>();
+        format!("bytes={}", ranges.join(","))
+    }
+}
+
+impl fmt::Display for ByteRange {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::From(start) => write!(f, "{}-", start),
+            Self::Bounded { start, end } => write!(f, "{}-{}", start, end),
+            Self::Suffix(length) => write!(f, "-{}", length),
+        }
+    }
+}
+
+/// Range Request Error
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RangeError {
+    /// None of the requested bytes are in the file, whose size is given if the server sent it
+    NotSatisfiable(Option<u64>),
+
+    /// The server answered with something else than the requested bytes or the whole file
+    Unsupported(StatusCode),
+
+    /// A partial response does not say which bytes it holds
+    MissingContentRange,
+
+    /// The content range of a partial response cannot be parsed
+    InvalidContentRange(ContentRangeParseError),
+
+    /// The server did not send the requested range
+    MissingRange(ByteRange),
+
+    /// The body of a `multipart/byteranges` response is malformed
+    InvalidMultipart(&'static str),
+
+    /// The body ended before the end of the range it was sent for
+    Truncated {
+        /// Number of bytes of the range
+        expected: u64,
+
+        /// Number of bytes received
+        received: u64,
+    },
+}
+
+impl fmt::Display for RangeError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotSatisfiable(Some(size)) => {
+                write!(
+                    f,
+                    "The requested range is outside the {} bytes of the file",
+                    size
+                )
+            }
+            Self::NotSatisfiable(_) => write!(f, "The requested range is outside the file"),
+            Self::Unsupported(status) => {
+                write!(f, "The server answered a range request with {}", status)
+            }
+            Self::MissingContentRange => write!(f, "Missing content range of a partial response"),
+            Self::InvalidContentRange(err) => write!(f, "{}", err),
+            Self::MissingRange(range) => write!(f, "The server did not send the bytes {}", range),
+            Self::InvalidMultipart(reason) => write!(f, "Invalid multipart response: {}", reason),
+            Self::Truncated { expected, received } => write!(
+                f,
+                "The server sent {} bytes of a range of {} bytes",
+                received, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+impl From<ContentRangeParseError> for RangeError {
+    #[inline]
+    fn from(err: ContentRangeParseError) -> Self {
+        Self::InvalidContentRange(err)
+    }
+}
+
+/// Returns the boundary of a `multipart/byteranges` body from its `content_type`, or `None` if the
+/// body is not multipart.
+#[inline]
+fn multipart_boundary(content_type: &str) -> Option<&str> {
+    let (media_type, parameters) = content_type.split_once(';')?;
+    if !media_type
+        .trim()
+        .eq_ignore_ascii_case("multipart/byteranges")
+    {
+        return None;
+    }
+    parameters.split(';').find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("boundary")
+            .then(|| value.trim().trim_matches('"'))
+    })
+}
+
+/// Returns the index of the first occurrence of `needle` in `haystack`.
+#[inline]
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Splits the `body` of a `multipart/byteranges` response delimited by `boundary` into its parts,
+/// along with the content range of each of them. The bytes of each part are cut by the length of
+/// its content range, so that they can hold the delimiter.
+#[inline]
+pub fn parse_multipart<'b>(
+    boundary: &str,
+    body: &'b [u8],
+) -> Result<Vec<(ContentRange, &'b [u8])>, RangeError> {
+    let delimiter = format!("--{}", boundary);
+    let start = find(body, delimiter.as_bytes())
+        .ok_or(RangeError::InvalidMultipart("missing first boundary"))?;
+    let mut rest = &body[start + delimiter.len()..];
+    let mut parts = Vec::new();
+    while !rest.starts_with(b"--") {
+        rest = rest
+            .strip_prefix(b"\r\n")
+            .ok_or(RangeError::InvalidMultipart(
+                "missing line break after a boundary",
+            ))?;
+        let headers_end =
+            find(rest, b"\r\n\r\n").ok_or(RangeError::InvalidMultipart("unterminated headers"))?;
+        let headers = core::str::from_utf8(&rest[..headers_end])
+            .map_err(|_| RangeError::InvalidMultipart("headers are not UTF-8"))?;
+        let content_range = headers
+            .split("\r\n")
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim()
+                    .eq_ignore_ascii_case(CONTENT_RANGE.as_str())
+                    .then(|| value.trim())
+            })
+            .ok_or(RangeError::MissingContentRange)?
+            .parse::<ContentRange>()?;
+        let range = content_range
+            .range()
+            .ok_or(RangeError::InvalidMultipart("part without a range"))?;
+        rest = &rest[headers_end + 4..];
+        let expected = range.end - range.start;
+        if (rest.len() as u64) < expected {
+            return Err(RangeError::Truncated {
+                expected,
+                received: rest.len() as u64,
+            });
+        }
+        parts.push((content_range, &rest[..expected as usize]));
+        rest = &rest[expected as usize..];
+        rest = rest.strip_prefix(b"\r\n").unwrap_or(rest);
+        rest = rest
+            .strip_prefix(delimiter.as_bytes())
+            .ok_or(RangeError::InvalidMultipart(
+                "missing boundary after a part",
+            ))?;
+    }
+    Ok(parts)
+}
+
+/// Fetches the bytes of `range` of the file served at `url`, see [`fetch_ranges`].
+#[inline]
+pub async fn fetch_range(client: &Client, url: &str, range: ByteRange) -> Result<Vec<u8>> {
+    Ok(fetch_ranges(client, url, &[range]).await?.remove(0))
+}
+
+/// Fetches the bytes of each of the `ranges` of the file served at `url` with a single request,
+/// returning them in the same order. The server may answer with a `multipart/byteranges` body, a
+/// single range covering all of them, or the whole file if it does not support range requests, in
+/// which case the body is only read up to the end of the last range.
+#[inline]
+pub async fn fetch_ranges(
+    client: &Client,
+    url: &str,
+    ranges: &[ByteRange],
+) -> Result<Vec<Vec<u8>>> {
+    if ranges.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut response = client
+        .get(url)
+        .header(RANGE, ByteRange::header(ranges))
+        .send()
+        .await?;
+    let mut parts = Vec::new();
+    let size = match response.status() {
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            let size = ContentRange::from_response(&response).map(|range| range.size());
+            return Err(RangeError::NotSatisfiable(size).into());
+        }
+        StatusCode::OK => {
+            let size = response
+                .content_length()
+                .ok_or_else(|| anyhow!("Missing content length from '{}'", url))?;
+            let resolved = ranges
+                .iter()
+                .filter_map(|range| range.resolve(size))
+                .collect::<Vec<_>>();
+            let (start, end) = (
+                resolved.iter().map(|range| range.start).min().unwrap_or(0),
+                resolved.iter().map(|range| range.end).max().unwrap_or(0),
+            );
+            let (mut offset, mut kept) = (0, Vec::new());
+            while offset < end {
+                let chunk = match response.chunk().await? {
+                    Some(chunk) => chunk,
+                    _ => break,
+                };
+                let chunk_range = offset..offset + chunk.len() as u64;
+                let from = start.clamp(chunk_range.start, chunk_range.end) - offset;
+                let to = end.clamp(chunk_range.start, chunk_range.end) - offset;
+                kept.extend_from_slice(&chunk[from as usize..to as usize]);
+                offset = chunk_range.end;
+            }
+            parts.push((start..end, kept));
+            size
+        }
+        StatusCode::PARTIAL_CONTENT => {
+            let boundary = response
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(multipart_boundary)
+                .map(String::from);
+            match boundary {
+                Some(boundary) => {
+                    let body = response.bytes().await?;
+                    let mut size = 0;
+                    for (content_range, bytes) in parse_multipart(&boundary, &body)? {
+                        size = content_range.size();
+                        let range = content_range.range().expect("Parts always have a range.");
+                        parts.push((range, bytes.to_vec()));
+                    }
+                    size
+                }
+                _ => {
+                    let content_range = response
+                        .headers()
+                        .get(CONTENT_RANGE)
+                        .ok_or(RangeError::MissingContentRange)?
+                        .to_str()
+                        .map_err(|_| RangeError::MissingContentRange)?
+                        .parse::<ContentRange>()
+                        .map_err(RangeError::from)?;
+                    let range = content_range
+                        .range()
+                        .ok_or(RangeError::NotSatisfiable(Some(content_range.size())))?;
+                    parts.push((range, response.bytes().await?.to_vec()));
+                    content_range.size()
+                }
+            }
+        }
+        status => {
+            response.error_for_status_ref()?;
+            return Err(RangeError::Unsupported(status).into());
+        }
+    };
+    ranges
+        .iter()
+        .map(|range| -> Result<Vec<u8>> {
+            let wanted = range
+                .resolve(size)
+                .ok_or(RangeError::NotSatisfiable(Some(size)))?;
+            let (part, bytes) = parts
+                .iter()
+                .find(|(part, _)| part.start <= wanted.start && wanted.end <= part.end)
+                .ok_or(RangeError::MissingRange(*range))?;
+            let expected = part.end - part.start;
+            if (bytes.len() as u64) < expected {
+                return Err(RangeError::Truncated {
+                    expected,
+                    received: bytes.len() as u64,
+                }
+                .into());
+            }
+            let offset = wanted.start - part.start;
+            Ok(bytes[offset as usize..(offset + wanted.end - wanted.start) as usize].to_vec())
+        })
+        .collect()
+}
+
+/// Opens the file at `path` into a [`BufWriter`] of `buffer_size` bytes positioned at its end and
+/// returns its current length.
+///
+/// The file is not opened in append mode since, on Windows, append-only handles cannot be
+/// truncated by [`restart_file`].
+#[inline]
+pub async fn open_file<P>(path: P, buffer_size: usize) -> Result<(u64, BufWriter<File>)>
+where
+    P: AsRef<Path>,
+{
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .await?;
+    let len = file.seek(SeekFrom::End(0)).await?;
+    Ok((len, BufWriter::with_capacity(buffer_size, file)))
+}
+
+/// Sends the download request to the server at `url` with the [`RANGE`] header set to start its
+/// range at `start`, returning the [`Response`] from the server and the total size of the file to
+/// be downloaded. This function returns `None` if the [`RANGE`] `start` bound is equal to the size
+/// of the file, meaning nothing needs to be downloaded. The request is conditioned on `validator`,
+/// if any, and a server answering with an [`OK`](StatusCode::OK) sends the whole file from the
+/// start, either because the file changed or because it does not support range requests.
+#[inline]
+pub async fn send_download_request(
+    client: &Client,
+    url: &str,
+    start: u64,
+    validator: Option<&Validator>,
+) -> Result<Option<(u64, Response)>> {
+    let request = client
+        .request(Method::GET, url)
+        .header(RANGE, ByteRange::header(&[ByteRange::From(start)]));
+    let response = Validator::apply(validator, request).send().await?;
+    if response.status() != StatusCode::RANGE_NOT_SATISFIABLE {
+        response.error_for_status_ref()?;
+    }
+    if response.status() == StatusCode::OK {
+        return match response.content_length() {
+            Some(size) => Ok(Some((size, response))),
+            _ => Err(anyhow!("Missing content length from '{}'", url)),
+        };
+    }
+    match ContentRange::from_response(&response) {
+        Some(ContentRange::Full { size, .. }) => Ok(Some((size, response))),
+        Some(ContentRange::Size(size)) => {
+            if size == start {
+                Ok(None)
+            } else {
+                Err(anyhow!("Size mismatch."))
+            }
+        }
+        _ => Err(anyhow!("Failed to parse content range from '{}'", url)),
+    }
+}
+
+/// Empties the partial file behind `file` when the server sends the whole file again.
+#[inline]
+async fn restart_file(file: &mut BufWriter<File>) -> Result {
+    file.flush().await?;
+    file.get_ref().set_len(0).await?;
+    file.seek(SeekFrom::Start(0)).await?;
+    Ok(())
+}
+
+/// Feeds the first `len` bytes of the file at `path` into a new Blake2b hasher, so that a resumed
+/// download can keep hashing where it left off.
+#[inline]
+pub(crate) async fn resume_hasher(path: &Path, len: u64) -> Result<Blake2b512> {
+    let path = path.to_owned();
+    task::spawn_blocking(move || {
+        let mut hasher = Blake2b512::new();
+        if len > 0 {
+            io::copy(&mut std::fs::File::open(path)?.take(len), &mut hasher)?;
+        }
+        Ok(hasher)
+    })
+    .await?
+}
+
+/// Saves the hash computed by `hasher` to the [`hash_path`](DownloadOptions::hash_path) of `path`.
+#[inline]
+pub(crate) async fn save_hash(
+    path: &Path,
+    hasher: Blake2b512,
+    options: &DownloadOptions,
+) -> Result {
+    let hash = Hash64(into_array_unchecked(hasher.finalize()));
+    atomic::write_async(options.hash_path(path), hash).await
+}
+
+/// Hashes the complete file at `path` if its [`hash_path`](DownloadOptions::hash_path) does not
+/// exist yet.
+#[inline]
+pub(crate) async fn hash_if_missing(path: &Path, options: &DownloadOptions) -> Result {
+    if fs::metadata(options.hash_path(path)).await.is_ok() {
+        return Ok(());
+    }
+    let len = fs::metadata(path).await?.len();
+    save_hash(path, resume_hasher(path, len).await?, options).await
+}
+
+/// Progress Bar Template
+const PROGRESS_BAR_TEMPLATE: &str =
+    "{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes}";
+
+/// Instatiates a [`ProgressBar`] of `len` elements and style given by [`PROGRESS_BAR_TEMPLATE`]
+/// and pushes it to `multibar`.
+#[inline]
+pub(crate) fn progress_bar(multibar: &MultiProgress, len: u64) -> Result<ProgressBar> {
+    let progress_bar = multibar.add(ProgressBar::new(len));
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template(PROGRESS_BAR_TEMPLATE)?
+            .progress_chars("#>-"),
+    );
+    Ok(progress_bar)
+}
+
+/// Downloads the file at `url` to `path`. If the file is not empty, we use the size of the file to
+/// determine how many bytes to read from the server. This allows for restarting the download
+/// process after a network or disk failure. If no data arrives for the read timeout of `options`,
+/// the request is sent again from the current position, up to [`MAX_STALLS`] times. The transfer
+/// is paced to stay under the rate limits of `options`. When `options` asks for more than one
+/// segment, or an earlier segmented download of `path` was interrupted, the download is handed
+/// over to [`download_segmented`]. Resuming is validated against the version of the file the
+/// download started from, see [`Validator`], and starts over if the file changed. Unless disabled
+/// in `options`, the bytes are hashed as they arrive and the hash is saved to the
+/// [`hash_path`](DownloadOptions::hash_path) of `path` once the download completes, saving a second pass over the file.
+///
+/// # Note
+///
+/// This function assumes that a single `path` will always be associated to a single `url` so that
+/// restarting downloading makes sense.
+#[inline]
+pub async fn download_from<P>(
+    progress: &dyn ProgressSink,
+    client: &Client,
+    url: &str,
+    path: P,
+    options: &DownloadOptions,
+) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if options.segments > 1 || segment::state_path(path).exists() {
+        return download_segmented(progress, client, url, path, options).await;
+    }
+    let file_rate_limit = options.rate_limit_per_file.map(RateLimiter::new);
+    let rate_limits = options
+        .rate_limit
+        .as_deref()
+        .into_iter()
+        .chain(file_rate_limit.as_ref())
+        .collect::<Vec<_>>();
+    let (mut amount_downloaded, mut file) = open_file(path, options.buffer_size).await?;
+    let validator = Validator::load(path, url).await;
+    let (total_size, mut response) =
+        match send_download_request(client, url, amount_downloaded, validator.as_ref()).await? {
+            Some((total_size, response)) => (total_size, response),
+            _ => {
+                if options.hash {
+                    hash_if_missing(path, options).await?;
+                }
+                return Validator::remove(path).await;
+            }
+        };
+    if response.status() == StatusCode::OK && amount_downloaded > 0 {
+        progress.on_warning(&format!(
+            "{}. Restarting from zero.",
+            RemoteFileChanged { url: url.into() }
+        ));
+        restart_file(&mut file).await?;
+        amount_downloaded = 0;
+    }
+    let validator = Validator::from_response(url, &response);
+    validator.save(path).await?;
+    let mut hasher = if options.hash {
+        Some(resume_hasher(path, amount_downloaded).await?)
+    } else {
+        None
+    };
+    progress.on_download_started(url, path, amount_downloaded, total_size);
+    let result: Result = async {
+        let mut stalls = 0;
+        loop {
+            let chunk = tokio::select! {
+                chunk = timeout(options.read_timeout, response.chunk()) => chunk,
+                _ = options.cancel.cancelled() => {
+                    file.flush().await?;
+                    return Err(Cancelled.into());
+                }
+            };
+            match chunk.map(|c| c.transpose()) {
+                Ok(Some(Ok(chunk))) => {
+                    for rate_limit in &rate_limits {
+                        rate_limit.acquire(chunk.len() as u64).await;
+                    }
+                    file.write_all(&chunk).await?;
+                    if let Some(hasher) = &mut hasher {
+                        hasher.update(&chunk);
+                    }
+                    amount_downloaded = min(amount_downloaded + (chunk.len() as u64), total_size);
+                    progress.on_bytes_downloaded(path, amount_downloaded);
+                }
+                Ok(None) => break,
+                Ok(Some(Err(err))) => {
+                    file.flush().await?;
+                    return Err(err.into());
+                }
+                Err(_) => {
+                    file.flush().await?;
+                    stalls += 1;
+                    if stalls >= MAX_STALLS {
+                        bail!("Download from '{}' stalled {} times.", url, stalls);
+                    }
+                    response = match send_download_request(
+                        client,
+                        url,
+                        amount_downloaded,
+                        Some(&validator),
+                    )
+                    .await?
+                    {
+                        Some((_, response)) => response,
+                        _ => break,
+                    };
+                    if response.status() == StatusCode::OK {
+                        restart_file(&mut file).await?;
+                        amount_downloaded = 0;
+                        progress.on_bytes_downloaded(path, 0);
+                        if let Some(hasher) = &mut hasher {
+                            hasher.reset();
+                        }
+                    }
+                }
+            }
+        }
+        file.flush().await?;
+        if let Some(hasher) = hasher {
+            save_hash(path, hasher, options).await?;
+        }
+        Validator::remove(path).await?;
+        Ok(())
+    }
+    .await;
+    progress.on_download_finished(url, path, &result);
+    result
+}
+
+/// Downloads the file at `path` from the first of `urls` that succeeds, failing over to the next
+/// mirror whenever the server answers with an error status or the transfer stalls repeatedly. Since
+/// every mirror serves the same file, the bytes already written by a failed mirror are kept and
+/// the next one resumes from there. Once every mirror has failed, the whole round is retried after
+/// an exponentially growing delay, up to the number of retries of `options`. Cancelling the token
+/// of `options` stops the download right away with a [`Cancelled`] error.
+#[inline]
+pub async fn download_file<U, P>(
+    progress: &dyn ProgressSink,
+    client: &Client,
+    urls: &[U],
+    path: P,
+    options: &DownloadOptions,
+) -> Result<()>
+where
+    U: AsRef<str>,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let _lock = FileLock::try_exclusive(path)?;
+    let mut attempt = 0;
+    loop {
+        let mut errors = Vec::with_capacity(urls.len());
+        for url in urls {
+            let url = url.as_ref();
+            if options.cancel.is_cancelled() {
+                return Err(Cancelled.into());
+            }
+            match download_from(progress, client, url, path, options).await {
+                Ok(()) => return Ok(()),
+                Err(err) if err.is::<Cancelled>() => return Err(err),
+                Err(err) => {
+                    progress.on_warning(&format!(
+                        "Failed to download '{}': {}. Trying the next mirror.",
+                        url, err
+                    ));
+                    errors.push(format!("{}: {}", url, err));
+                }
+            }
+        }
+        if attempt >= options.retries {
+            bail!(
+                "Unable to download {} from any mirror after {} attempts: [{}]",
+                path.display(),
+                attempt + 1,
+                errors.join(", ")
+            );
+        }
+        let delay = options.backoff_delay(attempt);
+        progress.on_warning(&format!(
+            "Every mirror failed for {}. Retrying in {:?}.",
+            path.display(),
+            delay
+        ));
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = options.cancel.cancelled() => return Err(Cancelled.into()),
+        }
+        attempt += 1;
+    }
+}
+
+/// Downloads every file of `files`, given as the mirrors serving it and the path to save it to,
+/// concurrently with [`download_file`], returning the result of each download in the same order.
+/// Cancelling the token of `options` stops all of them.
+#[inline]
+pub async fn download_all<U, P>(
+    progress: &dyn ProgressSink,
+    client: &Client,
+    files: &[(Vec<U>, P)],
+    options: &DownloadOptions,
+) -> Vec<Result>
+where
+    U: AsRef<str>,
+    P: AsRef<Path>,
+{
+    join_all(
+        files
+            .iter()
+            .map(|(urls, path)| download_file(progress, client, urls, path, options)),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::NoProgress;
+    #[cfg(feature = "native")]
+    use crate::{
+        calculate_hash,
+        mock::{Behavior, MockServer},
+        HashAlgorithm,
+    };
+
+    /// Returns the contents of the files served in the download tests.
+    #[cfg(feature = "native")]
+    fn contents() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Returns [`DownloadOptions`] retrying right away, so that the tests do not wait.
+    #[cfg(feature = "native")]
+    fn quick_options() -> DownloadOptions {
+        DownloadOptions {
+            backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an empty directory for the download test called `name`.
+    #[cfg(feature = "native")]
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ppot-verifier-download-{}", name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Checks that cancelled downloads stop before sending any request.
+    #[test]
+    fn cancelled_downloads_stop() {
+        let options = DownloadOptions::default();
+        options.cancel.cancel();
+        let path = std::env::temp_dir().join("ppot-verifier-cancel-test");
+        let results = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(download_all(
+                &NoProgress,
+                &Client::new(),
+                &[(vec!["http://127.0.0.1:9/challenge_0000"], &path)],
+                &options,
+            ));
+        assert!(results[0].as_ref().unwrap_err().is::<Cancelled>());
+        assert!(!path.exists());
+    }
+
+    /// Checks that partial files are resumed at their end and can be restarted from scratch.
+    #[test]
+    fn partial_files_restart() {
+        let path = std::env::temp_dir().join("ppot-verifier-restart-test");
+        std::fs::write(&path, b"partial").unwrap();
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let (len, mut file) = open_file(&path, 1 << 16).await.unwrap();
+            assert_eq!(len, 7);
+            file.write_all(b" file").await.unwrap();
+            file.flush().await.unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), b"partial file");
+            restart_file(&mut file).await.unwrap();
+            file.write_all(b"whole").await.unwrap();
+            file.flush().await.unwrap();
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), b"whole");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Checks that a partial file is resumed at its end, hashed as a whole, and that a complete
+    /// file is not downloaded again.
+    #[cfg(feature = "native")]
+    #[test]
+    fn partial_downloads_resume() {
+        let (dir, data) = (test_dir("resume"), contents());
+        let path = dir.join("challenge_0001");
+        std::fs::write(&path, &data[..3000]).unwrap();
+        let options = quick_options();
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let behavior = Behavior {
+                etag: Some("\"v1\"".into()),
+                ..Default::default()
+            };
+            let server = MockServer::start([("challenge_0001", data.clone(), behavior)]);
+            let urls = [server.url("challenge_0001")];
+            download_file(&NoProgress, &Client::new(), &urls, &path, &options)
+                .await
+                .unwrap();
+            download_file(&NoProgress, &Client::new(), &urls, &path, &options)
+                .await
+                .unwrap();
+            let ranges = server
+                .requests()
+                .into_iter()
+                .map(|request| request.range)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                ranges,
+                [Some("bytes=3000-".into()), Some("bytes=10000-".into())]
+            );
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        assert_eq!(
+            Hash64::load(options.hash_path(&path)).unwrap().0.to_vec(),
+            calculate_hash(&data, HashAlgorithm::Blake2b)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks that downloads answered with `429 Too Many Requests` are retried until the server
+    /// serves the file.
+    #[cfg(feature = "native")]
+    #[test]
+    fn throttled_downloads_retry() {
+        let (dir, data) = (test_dir("throttled"), contents());
+        let path = dir.join("response_0001");
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let behavior = Behavior {
+                throttled: 2,
+                ..Default::default()
+            };
+            let server = MockServer::start([("response_0001", data.clone(), behavior)]);
+            download_file(
+                &NoProgress,
+                &Client::new(),
+                &[server.url("response_0001")],
+                &path,
+                &quick_options(),
+            )
+            .await
+            .unwrap();
+            let requests = server.requests();
+            assert_eq!(requests.len(), 3);
+            assert!(requests
+                .iter()
+                .all(|request| request.name == "response_0001"));
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks that a partial file is replaced rather than appended to when the server ignores the
+    /// range of the request or the file changed since the download started.
+    #[cfg(feature = "native")]
+    #[test]
+    fn whole_responses_restart() {
+        let (dir, data) = (test_dir("restart"), contents());
+        let (ignored, changed) = (dir.join("challenge_0001"), dir.join("challenge_0002"));
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let server = MockServer::start([
+                (
+                    "challenge_0001",
+                    data.clone(),
+                    Behavior {
+                        ignore_range: true,
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "challenge_0002",
+                    data.clone(),
+                    Behavior {
+                        etag: Some("\"v2\"".into()),
+                        ..Default::default()
+                    },
+                ),
+            ]);
+            for path in [&ignored, &changed] {
+                std::fs::write(path, b"stale bytes").unwrap();
+            }
+            let url = server.url("challenge_0002");
+            Validator {
+                url: url.clone(),
+                etag: Some("\"v1\"".into()),
+                last_modified: None,
+            }
+            .save(&changed)
+            .await
+            .unwrap();
+            for (url, path) in [(server.url("challenge_0001"), &ignored), (url, &changed)] {
+                download_file(&NoProgress, &Client::new(), &[url], path, &quick_options())
+                    .await
+                    .unwrap();
+            }
+            assert_eq!(server.requests()[1].if_range.as_deref(), Some("\"v1\""));
+        });
+        assert_eq!(std::fs::read(&ignored).unwrap(), data);
+        assert_eq!(std::fs::read(&changed).unwrap(), data);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks that a transfer interrupted in the middle of the body resumes where the bytes on
+    /// disk end, conditioned on the version of the file it started from.
+    #[cfg(feature = "native")]
+    #[test]
+    fn interrupted_downloads_resume() {
+        let (dir, data) = (test_dir("interrupted"), contents());
+        let path = dir.join("response_0002");
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let behavior = Behavior {
+                etag: Some("\"v1\"".into()),
+                interrupt_after: Some(4000),
+                ..Default::default()
+            };
+            let server = MockServer::start([("response_0002", data.clone(), behavior)]);
+            download_file(
+                &NoProgress,
+                &Client::new(),
+                &[server.url("response_0002")],
+                &path,
+                &quick_options(),
+            )
+            .await
+            .unwrap();
+            let requests = server.requests();
+            assert_eq!(requests.len(), 2);
+            assert_eq!(requests[1].if_range.as_deref(), Some("\"v1\""));
+            assert!(requests[1].range.as_deref().unwrap().starts_with("bytes="));
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks that the connection settings left out of the configuration keep their defaults and
+    /// that the client is built from them.
+    #[test]
+    fn http_options_parse() {
+        let http =
+            toml::from_str::<HttpOptions>("http2 = false\npool_max_idle_per_host = 4").unwrap();
+        assert_eq!(
+            http,
+            HttpOptions {
+                http2: false,
+                pool_max_idle_per_host: Some(4),
+                ..Default::default()
+            }
+        );
+        assert!(toml::from_str::<HttpOptions>("http3 = true").is_err());
+        let options = DownloadOptions {
+            http,
+            ..Default::default()
+        };
+        options.client().unwrap();
+    }
+
+    /// Checks that byte ranges are written and resolved against the size of a file as servers do.
+    #[test]
+    fn byte_ranges_resolve() {
+        let ranges = [
+            ByteRange::From(10),
+            ByteRange::of(0..64),
+            ByteRange::Suffix(100),
+        ];
+        assert_eq!(ByteRange::header(&ranges), "bytes=10-,0-63,-100");
+        assert_eq!(ranges[0].resolve(50), Some(10..50));
+        assert_eq!(ranges[1].resolve(50), Some(0..50));
+        assert_eq!(ranges[2].resolve(50), Some(0..50));
+        assert_eq!(ranges[2].resolve(500), Some(400..500));
+        assert_eq!(ranges[0].resolve(10), None);
+        assert_eq!(ByteRange::Suffix(0).resolve(10), None);
+        assert_eq!(
+            "bytes 9-3/10".parse::<ContentRange>(),
+            Err(ContentRangeParseError::InvertedRange)
+        );
+    }
+
+    /// Checks that `multipart/byteranges` bodies are split into their parts, even when a part holds
+    /// the boundary, and that malformed ones are rejected.
+    #[test]
+    fn multipart_bodies_split() {
+        assert_eq!(
+            multipart_boundary("multipart/byteranges; boundary=\"abc\""),
+            Some("abc")
+        );
+        assert_eq!(multipart_boundary("application/octet-stream"), None);
+        let body = b"\r\n--abc\r\nContent-Type: application/octet-stream\r\nContent-Range: bytes 0-4/20\r\n\r\n--abc\r\n--abc\r\ncontent-range: bytes 17-19/20\r\n\r\nxyz\r\n--abc--\r\n";
+        let parts = parse_multipart("abc", body).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].0.range(), Some(0..5));
+        assert_eq!(parts[0].1, b"--abc");
+        assert_eq!(parts[1].0.range(), Some(17..20));
+        assert_eq!(parts[1].1, b"xyz");
+        assert_eq!(
+            parse_multipart("abc", &body[..128]),
+            Err(RangeError::Truncated {
+                expected: 3,
+                received: 1
+            })
+        );
+        assert_eq!(
+            parse_multipart("other", body),
+            Err(RangeError::InvalidMultipart("missing first boundary"))
+        );
+    }
+
+    /// Checks that several ranges, including suffix ones, are fetched with a single request from
+    /// servers answering with a multipart body, a single range or the whole file.
+    #[cfg(feature = "native")]
+    #[test]
+    fn ranges_fetch() {
+        let data = contents();
+        let ranges = [ByteRange::of(0..64), ByteRange::Suffix(100)];
+        let expected = vec![data[..64].to_vec(), data[9_900..].to_vec()];
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let server = MockServer::start([
+                ("ranged", data.clone(), Behavior::default()),
+                (
+                    "whole",
+                    data.clone(),
+                    Behavior {
+                        ignore_range: true,
+                        ..Default::default()
+                    },
+                ),
+            ]);
+            let client = Client::new();
+            for name in ["ranged", "whole"] {
+                let parts = fetch_ranges(&client, &server.url(name), &ranges)
+                    .await
+                    .unwrap();
+                assert_eq!(parts, expected);
+            }
+            let suffix = fetch_range(&client, &server.url("ranged"), ByteRange::Suffix(20_000))
+                .await
+                .unwrap();
+            assert_eq!(suffix, data);
+            let err = fetch_range(&client, &server.url("ranged"), ByteRange::From(10_000))
+                .await
+                .unwrap_err();
+            assert_eq!(
+                err.downcast_ref::<RangeError>(),
+                Some(&RangeError::NotSatisfiable(Some(10_000)))
+            );
+            let requests = server.requests();
+            assert_eq!(requests.len(), 4);
+            assert!(requests[..2]
+                .iter()
+                .all(|request| request.range.as_deref() == Some("bytes=0-63,-100")));
+        });
+    }
+}
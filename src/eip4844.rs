@@ -0,0 +1,369 @@
+//! Ethereum KZG Ceremony Transcripts
+//!
+//! The Ethereum KZG ceremony generated the BLS12-381 powers of tau of EIP-4844 in four
+//! sub-ceremonies of 2^12 to 2^15 powers of tau in G1, each with 65 powers in G2, every
+//! participant contributing to the four of them at once. Instead of a chain of challenge and
+//! response files, the sequencer publishes a single JSON transcript holding the final powers of
+//! each sub-ceremony with a witness of every contribution: the running product of the secrets in
+//! G1 after the contribution, the public key of the contribution in G2 and an optional BLS
+//! signature of the identity of the participant. Points are compressed as in the `pairing` crate
+//! and written in hexadecimal.
+//!
+//! [`Transcript::verify`] runs the checks of the ceremony specification on each sub-ceremony: the
+//! points must be in the subgroups, the public keys must be non-zero and unique, every running
+//! product must be the previous one multiplied by the secret of the public key of the contribution,
+//! the last one must be the first power of tau, and the powers must be successive powers of the
+//! same tau starting from the generators. The BLS signatures tie the contributions to the
+//! identities of the participants and are not verified.
+
+use crate::{
+    accumulator::{combine_powers, same_ratio},
+    curve::{G1, G2},
+    format::{read_g1_on, read_g2_on, Encoding},
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use ark_bls12_381::Bls12_381;
+use ark_ec::AffineCurve;
+use ark_ff::Zero;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// URL of the transcript of the Ethereum KZG ceremony, served by its sequencer
+pub const TRANSCRIPT_URL: &str = "https://seq.ceremony.ethereum.org/info/current_state";
+
+/// Default path of the transcript, relative to the data directory
+pub const TRANSCRIPT_PATH: &str = "eip4844/transcript.json";
+
+/// Powers of Tau of a Sub-Ceremony
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PowersOfTau {
+    /// Powers of tau in G1
+    #[serde(rename = "G1Powers")]
+    pub g1_powers: Vec<String>,
+
+    /// Powers of tau in G2
+    #[serde(rename = "G2Powers")]
+    pub g2_powers: Vec<String>,
+}
+
+/// Witness of the Contributions to a Sub-Ceremony
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Witness {
+    /// Product of the secrets in G1 after each contribution, starting from the generator
+    pub running_products: Vec<String>,
+
+    /// Public key of each contribution in G2, starting from the generator
+    pub pot_pubkeys: Vec<String>,
+
+    /// BLS signature of the identity of each participant, empty if the participant did not sign
+    pub bls_signatures: Vec<String>,
+}
+
+/// Transcript of a Sub-Ceremony
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubTranscript {
+    /// Number of powers of tau in G1
+    pub num_g1_powers: usize,
+
+    /// Number of powers of tau in G2
+    pub num_g2_powers: usize,
+
+    /// Powers of tau
+    pub powers_of_tau: PowersOfTau,
+
+    /// Witness of the contributions
+    pub witness: Witness,
+}
+
+/// Transcript of the Ethereum KZG Ceremony
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    /// Transcripts of the sub-ceremonies
+    pub transcripts: Vec<SubTranscript>,
+
+    /// Identity of each participant
+    #[serde(default)]
+    pub participant_ids: Vec<String>,
+
+    /// ECDSA signature of each participant with an Ethereum identity
+    #[serde(default)]
+    pub participant_ecdsa_signatures: Vec<String>,
+}
+
+/// Decodes the hexadecimal `point`, with or without its `0x` prefix.
+#[inline]
+fn decode(point: &str) -> Result<Vec<u8>> {
+    hex::decode(point.strip_prefix("0x").unwrap_or(point))
+        .map_err(|err| anyhow!("Invalid point '{}': {}", point, err))
+}
+
+/// Parses the compressed G1 `point` of BLS12-381, checking its subgroup.
+#[inline]
+pub fn parse_g1(point: &str) -> Result<G1<Bls12_381>> {
+    let point = read_g1_on::<Bls12_381>(&decode(point)?, Encoding::Compressed)?;
+    ensure!(
+        point.is_in_correct_subgroup_assuming_on_curve(),
+        "G1 point out of the subgroup"
+    );
+    Ok(point)
+}
+
+/// Parses the compressed G2 `point` of BLS12-381, checking its subgroup.
+#[inline]
+pub fn parse_g2(point: &str) -> Result<G2<Bls12_381>> {
+    let point = read_g2_on::<Bls12_381>(&decode(point)?, Encoding::Compressed)?;
+    ensure!(
+        point.is_in_correct_subgroup_assuming_on_curve(),
+        "G2 point out of the subgroup"
+    );
+    Ok(point)
+}
+
+impl SubTranscript {
+    /// Returns the number of contributions to the sub-ceremony.
+    #[inline]
+    pub fn contributions(&self) -> usize {
+        self.witness.running_products.len().saturating_sub(1)
+    }
+
+    /// Returns the number of contributions with a BLS signature.
+    #[inline]
+    pub fn signed_contributions(&self) -> usize {
+        self.witness
+            .bls_signatures
+            .iter()
+            .skip(1)
+            .filter(|signature| !signature.is_empty())
+            .count()
+    }
+
+    /// Verifies the powers of tau of the sub-ceremony and the witness of its contributions.
+    #[inline]
+    pub fn verify(&self) -> Result {
+        let (powers, witness) = (&self.powers_of_tau, &self.witness);
+        ensure!(
+            powers.g1_powers.len() == self.num_g1_powers
+                && powers.g2_powers.len() == self.num_g2_powers,
+            "The transcript holds {} G1 and {} G2 powers instead of {} and {}",
+            powers.g1_powers.len(),
+            powers.g2_powers.len(),
+            self.num_g1_powers,
+            self.num_g2_powers
+        );
+        ensure!(
+            self.num_g1_powers >= 2 && self.num_g2_powers >= 2,
+            "Unable to verify less than two powers of tau"
+        );
+        ensure!(
+            !witness.running_products.is_empty()
+                && witness.pot_pubkeys.len() == witness.running_products.len()
+                && witness.bls_signatures.len() == witness.running_products.len(),
+            "The witness holds {} running products, {} public keys and {} signatures",
+            witness.running_products.len(),
+            witness.pot_pubkeys.len(),
+            witness.bls_signatures.len()
+        );
+        let g1_powers = powers
+            .g1_powers
+            .par_iter()
+            .map(|point| parse_g1(point))
+            .collect::<Result<Vec<_>>>()?;
+        let g2_powers = powers
+            .g2_powers
+            .par_iter()
+            .map(|point| parse_g2(point))
+            .collect::<Result<Vec<_>>>()?;
+        let running_products = witness
+            .running_products
+            .par_iter()
+            .map(|point| parse_g1(point))
+            .collect::<Result<Vec<_>>>()?;
+        let pubkeys = witness
+            .pot_pubkeys
+            .par_iter()
+            .map(|point| parse_g2(point))
+            .collect::<Result<Vec<_>>>()?;
+        let (g1, g2) = (
+            G1::<Bls12_381>::prime_subgroup_generator(),
+            G2::<Bls12_381>::prime_subgroup_generator(),
+        );
+        ensure!(
+            running_products[0] == g1 && pubkeys[0] == g2,
+            "The witness does not start from the generators"
+        );
+        let mut unique = HashSet::with_capacity(witness.pot_pubkeys.len());
+        for (index, (pubkey, encoded)) in pubkeys.iter().zip(&witness.pot_pubkeys).enumerate() {
+            if pubkey.is_zero() {
+                bail!("Contribution {} has a zero public key", index);
+            }
+            if !unique.insert(encoded.to_lowercase()) {
+                bail!(
+                    "Contribution {} reuses the public key of another one",
+                    index
+                );
+            }
+        }
+        for index in 1..running_products.len() {
+            if !same_ratio::<Bls12_381>(
+                (running_products[index - 1], running_products[index]),
+                (g2, pubkeys[index]),
+            ) {
+                bail!(
+                    "The running product of contribution {} does not match its public key",
+                    index
+                );
+            }
+        }
+        ensure!(
+            running_products[running_products.len() - 1] == g1_powers[1],
+            "The last running product is not the first power of tau"
+        );
+        ensure!(
+            g1_powers[0] == g1 && g2_powers[0] == g2,
+            "The powers of tau do not start from the generators"
+        );
+        if !same_ratio::<Bls12_381>(
+            combine_powers(g1_powers.len(), |index| Ok(g1_powers[index]))?,
+            (g2, g2_powers[1]),
+        ) {
+            bail!("The G1 powers are not successive powers of tau");
+        }
+        if !same_ratio::<Bls12_381>(
+            (g1, g1_powers[1]),
+            combine_powers(g2_powers.len(), |index| Ok(g2_powers[index]))?,
+        ) {
+            bail!("The G2 powers are not successive powers of tau");
+        }
+        Ok(())
+    }
+}
+
+impl Transcript {
+    /// Parses the JSON transcript in `bytes`.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Verifies every sub-ceremony of the transcript, which must all have the same contributions.
+    #[inline]
+    pub fn verify(&self) -> Result {
+        ensure!(!self.transcripts.is_empty(), "The transcript is empty");
+        let contributions = self.transcripts[0].contributions();
+        for transcript in &self.transcripts {
+            ensure!(
+                transcript.contributions() == contributions,
+                "The sub-ceremony of 2^{} powers has {} contributions instead of {}",
+                transcript.num_g1_powers.trailing_zeros(),
+                transcript.contributions(),
+                contributions
+            );
+            transcript.verify().map_err(|err| {
+                anyhow!(
+                    "Invalid sub-ceremony of 2^{} powers: {}",
+                    transcript.num_g1_powers.trailing_zeros(),
+                    err
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{write_g1_on, write_g2_on};
+    use ark_bls12_381::Fr;
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::{Field, UniformRand};
+
+    /// Encodes the G1 `point` as in the transcript.
+    fn encode_g1(point: &G1<Bls12_381>) -> String {
+        let mut out = [0; 48];
+        write_g1_on::<Bls12_381>(point, Encoding::Compressed, &mut out);
+        format!("0x{}", hex::encode(out))
+    }
+
+    /// Encodes the G2 `point` as in the transcript.
+    fn encode_g2(point: &G2<Bls12_381>) -> String {
+        let mut out = [0; 96];
+        write_g2_on::<Bls12_381>(point, Encoding::Compressed, &mut out);
+        format!("0x{}", hex::encode(out))
+    }
+
+    /// Builds the transcript of a sub-ceremony with `secrets` as contributions.
+    fn sub_transcript(g1_powers: usize, g2_powers: usize, secrets: &[Fr]) -> SubTranscript {
+        let (g1, g2) = (
+            G1::<Bls12_381>::prime_subgroup_generator(),
+            G2::<Bls12_381>::prime_subgroup_generator(),
+        );
+        let mut witness = Witness {
+            running_products: vec![encode_g1(&g1)],
+            pot_pubkeys: vec![encode_g2(&g2)],
+            bls_signatures: vec![String::new()],
+        };
+        let mut tau = Fr::from(1u64);
+        for secret in secrets {
+            tau *= secret;
+            witness
+                .running_products
+                .push(encode_g1(&g1.mul(tau).into_affine()));
+            witness
+                .pot_pubkeys
+                .push(encode_g2(&g2.mul(*secret).into_affine()));
+            witness.bls_signatures.push(String::new());
+        }
+        SubTranscript {
+            num_g1_powers: g1_powers,
+            num_g2_powers: g2_powers,
+            powers_of_tau: PowersOfTau {
+                g1_powers: (0..g1_powers as u64)
+                    .map(|i| encode_g1(&g1.mul(tau.pow([i])).into_affine()))
+                    .collect(),
+                g2_powers: (0..g2_powers as u64)
+                    .map(|i| encode_g2(&g2.mul(tau.pow([i])).into_affine()))
+                    .collect(),
+            },
+            witness,
+        }
+    }
+
+    /// Checks that a valid transcript goes through a JSON round trip and verifies, and that
+    /// tampered powers and witnesses are rejected.
+    #[test]
+    fn verify_transcript() {
+        let mut rng = rand::thread_rng();
+        let secrets = (0..3).map(|_| Fr::rand(&mut rng)).collect::<Vec<_>>();
+        let transcript = Transcript {
+            transcripts: vec![
+                sub_transcript(8, 3, &secrets),
+                sub_transcript(16, 3, &secrets),
+            ],
+            ..Default::default()
+        };
+        let transcript = Transcript::read(&serde_json::to_vec(&transcript).unwrap()).unwrap();
+        transcript.verify().unwrap();
+        assert_eq!(transcript.transcripts[0].contributions(), 3);
+        let mut tampered = transcript.clone();
+        tampered.transcripts[1].powers_of_tau.g1_powers.swap(3, 4);
+        assert!(tampered.verify().is_err());
+        let mut tampered = transcript.clone();
+        tampered.transcripts[0].powers_of_tau.g2_powers.swap(1, 2);
+        assert!(tampered.verify().is_err());
+        let mut tampered = transcript.clone();
+        tampered.transcripts[0].witness.pot_pubkeys.swap(1, 2);
+        assert!(tampered.verify().is_err());
+        let mut tampered = transcript.clone();
+        tampered.transcripts[1] = sub_transcript(16, 3, &secrets[1..]);
+        assert!(tampered.verify().is_err());
+        let mut tampered = transcript;
+        tampered.transcripts[0].powers_of_tau.g1_powers[2].replace_range(2..4, "00");
+        assert!(tampered.verify().is_err());
+    }
+}
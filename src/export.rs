@@ -0,0 +1,868 @@
+//! SRS Export
+//!
+//! Downstream tooling does not read the challenge files of the ceremony directly. The exporters of
+//! this module convert the accumulator of a verified challenge file into the formats other setups
+//! start from, so that they do not have to trust a conversion done by someone else.
+//!
+//! The `phase1radix2m{N}` files read by the phase 2 tooling of bellman hold, for the radix-2
+//! domain of size `m = 2^N`, alpha and beta in G1, beta in G2, the Lagrange basis of the domain in
+//! G1, in G2, multiplied by alpha and by beta in G1, and the `m - 1` points `tau^i * (tau^m - 1)`
+//! in G1 the quotient polynomial is committed with, uncompressed.
+//!
+//! The KZG parameters of halo2 hold `k` as a little-endian `u32`, the `2^k` powers of tau in G1,
+//! the Lagrange basis of the domain of size `2^k` in G1, the generator of G2 and tau in G2, with the
+//! raw encoding of halo2curves, which is the encoding of `.ptau` files. Like bellman, halo2
+//! generates its domains from the powers of 7 rather than 5 like arkworks, see [`Domain`].
+//!
+//! The arkworks SRS files start with an [`SrsHeader`] giving the number of powers, the round they
+//! come from and the Blake2b hash of its challenge file, followed by an [`Srs`] in the canonical
+//! compressed serialization of `ark-serialize`, so that provers built on arkworks can load it with
+//! [`Srs::deserialize`](CanonicalDeserialize::deserialize) after the header. The Lagrange basis
+//! files start with the same header, with their own magic bytes, followed by the generator of their
+//! [`Domain`] as a little-endian `u64` and the Lagrange basis of the powers of tau in G1.
+//!
+//! Verifier keys and test vectors usually need only a few points, which [`points`] exports from a
+//! range of indices of any section, either as JSON with big-endian hexadecimal coordinates or as
+//! the little-endian coordinates of every point, concatenated, see [`PointFormat`].
+
+use crate::{
+    atomic,
+    fft::{ifft_with_root, Domain},
+    format::{read_g1, read_g2, write_g1, write_g2, Encoding, Layout, Section, HASH_SIZE},
+    hash::Hash64,
+    ptau, Result,
+};
+use anyhow::{anyhow, ensure};
+use ark_bn254::{Fq, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Read, SerializationError};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// Magic bytes starting the arkworks SRS files
+pub const SRS_MAGIC: &[u8; 8] = b"ppot-srs";
+
+/// Magic bytes starting the Lagrange basis files
+pub const LAGRANGE_MAGIC: &[u8; 8] = b"ppot-lag";
+
+/// Version of the arkworks SRS file format
+pub const SRS_VERSION: u32 = 1;
+
+/// Number of points read from the accumulator at once while writing an arkworks SRS file
+const SRS_CHUNK_SIZE: usize = 1 << 20;
+
+/// Returns the path of the `phase1radix2m{log_size}` file in `dir`.
+#[inline]
+pub fn phase1radix_path(dir: &Path, log_size: u32) -> PathBuf {
+    dir.join(format!("phase1radix2m{}", log_size))
+}
+
+/// Reads the points at `range` of `section` of the challenge `accumulator` with `layout`.
+#[inline]
+fn read_points<G, F>(
+    accumulator: &[u8],
+    layout: &Layout,
+    section: Section,
+    range: core::ops::Range<usize>,
+    read: F,
+) -> Result<Vec<G::Projective>>
+where
+    G: AffineCurve,
+    F: Fn(&[u8], Encoding) -> Result<G> + Sync,
+{
+    range
+        .into_par_iter()
+        .map(|index| {
+            Ok(read(
+                &accumulator[layout.point_range(section, index)],
+                layout.encoding,
+            )?
+            .into_projective())
+        })
+        .collect()
+}
+
+/// Appends the G1 `points` uncompressed to `out`.
+#[inline]
+pub(crate) fn push_g1(out: &mut Vec<u8>, points: &[G1Affine]) {
+    let size = Encoding::Uncompressed.g1_size();
+    let start = out.len();
+    out.resize(start + points.len() * size, 0);
+    out[start..]
+        .par_chunks_mut(size)
+        .zip(points)
+        .for_each(|(out, point)| write_g1(point, Encoding::Uncompressed, out));
+}
+
+/// Appends the G2 `points` uncompressed to `out`.
+#[inline]
+pub(crate) fn push_g2(out: &mut Vec<u8>, points: &[G2Affine]) {
+    let size = Encoding::Uncompressed.g2_size();
+    let start = out.len();
+    out.resize(start + points.len() * size, 0);
+    out[start..]
+        .par_chunks_mut(size)
+        .zip(points)
+        .for_each(|(out, point)| write_g2(point, Encoding::Uncompressed, out));
+}
+
+/// Returns the Lagrange basis of `domain` of size `m` in G1 from the powers at the start of
+/// `section` of the challenge `accumulator`.
+#[inline]
+pub fn lagrange_g1(
+    accumulator: &[u8],
+    layout: &Layout,
+    section: Section,
+    domain: Domain,
+    m: usize,
+) -> Result<Vec<G1Affine>> {
+    let mut points = read_points(accumulator, layout, section, 0..m, read_g1)?;
+    ifft_with_root(&mut points, domain.root_of_unity(m)?)?;
+    Ok(G1Projective::batch_normalization_into_affine(&points))
+}
+
+/// Returns the Lagrange basis of `domain` of size `m` in G2 from the powers of tau of the
+/// challenge `accumulator`.
+#[inline]
+pub fn lagrange_g2(
+    accumulator: &[u8],
+    layout: &Layout,
+    domain: Domain,
+    m: usize,
+) -> Result<Vec<G2Affine>> {
+    let mut points = read_points(accumulator, layout, Section::TauG2, 0..m, read_g2)?;
+    ifft_with_root(&mut points, domain.root_of_unity(m)?)?;
+    Ok(G2Projective::batch_normalization_into_affine(&points))
+}
+
+/// Returns the contents of the `phase1radix2m{log_size}` file built from the challenge
+/// `accumulator` with `layout`.
+#[inline]
+pub fn phase1radix_file(accumulator: &[u8], layout: &Layout, log_size: u32) -> Result<Vec<u8>> {
+    let m = 1 << log_size;
+    ensure!(
+        m <= layout.powers,
+        "The domain of size 2^{} needs more powers than the 2^{} of the accumulator",
+        log_size,
+        layout.powers.trailing_zeros()
+    );
+    let (g1, g2) = (
+        Encoding::Uncompressed.g1_size(),
+        Encoding::Uncompressed.g2_size(),
+    );
+    let mut out = Vec::with_capacity(2 * g1 + g2 + m * (3 * g1 + g2) + (m - 1) * g1);
+    push_g1(
+        &mut out,
+        &[
+            read_g1(
+                &accumulator[layout.point_range(Section::AlphaG1, 0)],
+                layout.encoding,
+            )?,
+            read_g1(
+                &accumulator[layout.point_range(Section::BetaG1, 0)],
+                layout.encoding,
+            )?,
+        ],
+    );
+    push_g2(
+        &mut out,
+        &[read_g2(
+            &accumulator[layout.point_range(Section::BetaG2, 0)],
+            layout.encoding,
+        )?],
+    );
+    push_g1(
+        &mut out,
+        &lagrange_g1(accumulator, layout, Section::TauG1, Domain::Bellman, m)?,
+    );
+    push_g2(
+        &mut out,
+        &lagrange_g2(accumulator, layout, Domain::Bellman, m)?,
+    );
+    push_g1(
+        &mut out,
+        &lagrange_g1(accumulator, layout, Section::AlphaG1, Domain::Bellman, m)?,
+    );
+    push_g1(
+        &mut out,
+        &lagrange_g1(accumulator, layout, Section::BetaG1, Domain::Bellman, m)?,
+    );
+    let low = read_points(accumulator, layout, Section::TauG1, 0..m - 1, read_g1)?;
+    let high = read_points(accumulator, layout, Section::TauG1, m..2 * m - 1, read_g1)?;
+    let h = high
+        .into_par_iter()
+        .zip(low)
+        .map(|(high, low)| high - low)
+        .collect::<Vec<_>>();
+    push_g1(&mut out, &G1Projective::batch_normalization_into_affine(&h));
+    Ok(out)
+}
+
+/// Writes the `phase1radix2m{N}` files for every `N` up to `log_size` to `dir` from the challenge
+/// `accumulator` with `powers` powers of tau, returning their paths.
+#[inline]
+pub fn phase1radix(
+    accumulator: &[u8],
+    powers: usize,
+    log_size: u32,
+    dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let layout = Layout::challenge(powers);
+    ensure!(
+        accumulator.len() == layout.file_size(),
+        "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+        accumulator.len(),
+        layout.file_size(),
+        powers.trailing_zeros()
+    );
+    (0..=log_size)
+        .map(|log_size| {
+            let path = phase1radix_path(dir, log_size);
+            info!("Writing {:?}", path);
+            atomic::write(&path, phase1radix_file(accumulator, &layout, log_size)?)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Writes the halo2 KZG parameters of the domain of size `2^k` built from the challenge
+/// `accumulator` with `powers` powers of tau to `path`.
+#[inline]
+pub fn halo2(accumulator: &[u8], powers: usize, k: u32, path: &Path) -> Result {
+    let layout = Layout::challenge(powers);
+    ensure!(
+        accumulator.len() == layout.file_size(),
+        "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+        accumulator.len(),
+        layout.file_size(),
+        powers.trailing_zeros()
+    );
+    let n = 1 << k;
+    ensure!(
+        n <= powers,
+        "The domain of size 2^{} needs more powers than the 2^{} of the accumulator",
+        k,
+        powers.trailing_zeros()
+    );
+    let mut powers_g1 = read_points(accumulator, &layout, Section::TauG1, 0..n, read_g1)?;
+    let g = G1Projective::batch_normalization_into_affine(&powers_g1);
+    ifft_with_root(&mut powers_g1, Domain::Bellman.root_of_unity(n)?)?;
+    let g_lagrange = G1Projective::batch_normalization_into_affine(&powers_g1);
+    let g2 = [
+        read_g2(
+            &accumulator[layout.point_range(Section::TauG2, 0)],
+            layout.encoding,
+        )?,
+        read_g2(
+            &accumulator[layout.point_range(Section::TauG2, 1)],
+            layout.encoding,
+        )?,
+    ];
+    info!("Writing {:?}", path);
+    atomic::write_with(path, |file| {
+        file.write_all(&k.to_le_bytes())?;
+        let mut bytes = vec![0; n * ptau::G1_SIZE];
+        for points in [&g, &g_lagrange] {
+            bytes
+                .par_chunks_mut(ptau::G1_SIZE)
+                .zip(points.par_iter())
+                .for_each(|(out, point)| ptau::write_g1(point, out));
+            file.write_all(&bytes)?;
+        }
+        let mut bytes = [0; ptau::G2_SIZE];
+        for point in &g2 {
+            ptau::write_g2(point, &mut bytes);
+            file.write_all(&bytes)?;
+        }
+        Ok(())
+    })
+}
+
+/// Format of the Exported Points
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum PointFormat {
+    /// JSON document with the big-endian hexadecimal coordinates of every point
+    #[default]
+    Json,
+
+    /// Little-endian coordinates of every point in standard form, concatenated, with `c0` before
+    /// `c1` in G2 and zero coordinates for the point at infinity
+    Raw,
+}
+
+/// Point with Hexadecimal Coordinates
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum HexPoint {
+    /// Point in G1
+    G1 {
+        /// `x` coordinate
+        x: String,
+
+        /// `y` coordinate
+        y: String,
+    },
+
+    /// Point in G2, with the `c0` and `c1` components of its coordinates
+    G2 {
+        /// `x` coordinate
+        x: [String; 2],
+
+        /// `y` coordinate
+        y: [String; 2],
+    },
+}
+
+/// Points Exported from a Section of an Accumulator
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ExportedPoints {
+    /// Section the points come from
+    pub section: Section,
+
+    /// Index of the first point in the section
+    pub start: usize,
+
+    /// Points, in the order of their indices
+    pub points: Vec<HexPoint>,
+}
+
+/// Returns the `0x`-prefixed big-endian hexadecimal representation of `element`.
+#[inline]
+fn hex_field(element: &Fq) -> String {
+    format!("0x{}", hex::encode(element.into_repr().to_bytes_be()))
+}
+
+/// Exports the points at `range` of `section` of the challenge `accumulator` with `powers` powers
+/// of tau in `format`.
+#[inline]
+pub fn points(
+    accumulator: &[u8],
+    powers: usize,
+    section: Section,
+    range: core::ops::Range<usize>,
+    format: PointFormat,
+) -> Result<Vec<u8>> {
+    let layout = Layout::challenge(powers);
+    ensure!(
+        accumulator.len() == layout.file_size(),
+        "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+        accumulator.len(),
+        layout.file_size(),
+        powers.trailing_zeros()
+    );
+    ensure!(
+        range.start < range.end && range.end <= section.len(powers),
+        "The range {}..{} is empty or outside of the {} points of {:?}",
+        range.start,
+        range.end,
+        section.len(powers),
+        section
+    );
+    let start = range.start;
+    let coordinates = if section.is_g2() {
+        let points = read_points(accumulator, &layout, section, range, read_g2)?;
+        G2Projective::batch_normalization_into_affine(&points)
+            .into_iter()
+            .map(|point| vec![point.x.c0, point.x.c1, point.y.c0, point.y.c1])
+            .collect::<Vec<_>>()
+    } else {
+        let points = read_points(accumulator, &layout, section, range, read_g1)?;
+        G1Projective::batch_normalization_into_affine(&points)
+            .into_iter()
+            .map(|point| vec![point.x, point.y])
+            .collect::<Vec<_>>()
+    };
+    Ok(match format {
+        PointFormat::Json => serde_json::to_vec_pretty(&ExportedPoints {
+            section,
+            start,
+            points: coordinates
+                .iter()
+                .map(|coordinates| match coordinates.as_slice() {
+                    [x, y] => HexPoint::G1 {
+                        x: hex_field(x),
+                        y: hex_field(y),
+                    },
+                    _ => HexPoint::G2 {
+                        x: [hex_field(&coordinates[0]), hex_field(&coordinates[1])],
+                        y: [hex_field(&coordinates[2]), hex_field(&coordinates[3])],
+                    },
+                })
+                .collect(),
+        })?,
+        PointFormat::Raw => coordinates
+            .iter()
+            .flatten()
+            .flat_map(|element| element.into_repr().to_bytes_le())
+            .collect(),
+    })
+}
+
+/// Header of the arkworks SRS files
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SrsHeader {
+    /// Base-two logarithm of the number of powers of tau
+    pub log_powers: u32,
+
+    /// Round of the ceremony whose challenge file the powers were exported from
+    pub round: u64,
+
+    /// Blake2b hash of the challenge file the powers were exported from
+    pub provenance: Hash64,
+}
+
+impl SrsHeader {
+    /// Size of the header
+    pub const SIZE: usize = SRS_MAGIC.len() + 4 + 4 + 8 + HASH_SIZE;
+
+    /// Returns the bytes of the header, starting with `magic`.
+    #[inline]
+    pub fn to_bytes(&self, magic: &[u8; 8]) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(magic);
+        bytes.extend_from_slice(&SRS_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.log_powers.to_le_bytes());
+        bytes.extend_from_slice(&self.round.to_le_bytes());
+        bytes.extend_from_slice(&self.provenance.0);
+        bytes
+    }
+
+    /// Reads the header at the start of `bytes`, which must start with `magic`.
+    #[inline]
+    pub fn read(bytes: &[u8], magic: &[u8; 8]) -> Result<Self> {
+        ensure!(
+            bytes.len() >= Self::SIZE && bytes.starts_with(magic),
+            "Not a {} file",
+            String::from_utf8_lossy(magic)
+        );
+        let version = u32::from_le_bytes(bytes[8..12].try_into()?);
+        ensure!(
+            version == SRS_VERSION,
+            "Unsupported SRS file version {}",
+            version
+        );
+        Ok(Self {
+            log_powers: u32::from_le_bytes(bytes[12..16].try_into()?),
+            round: u64::from_le_bytes(bytes[16..24].try_into()?),
+            provenance: Hash64::try_from(&bytes[24..Self::SIZE])?,
+        })
+    }
+}
+
+/// Structured Reference String
+///
+/// The points of an accumulator with `n` powers of tau: `2n - 1` powers of tau in G1, `n` in G2,
+/// `n` multiples of alpha and of beta in G1 and beta in G2.
+#[derive(CanonicalDeserialize, CanonicalSerialize, Clone, Debug, Eq, PartialEq)]
+pub struct Srs {
+    /// Powers of tau in G1
+    pub tau_g1: Vec<G1Affine>,
+
+    /// Powers of tau in G2
+    pub tau_g2: Vec<G2Affine>,
+
+    /// Powers of tau multiplied by alpha in G1
+    pub alpha_g1: Vec<G1Affine>,
+
+    /// Powers of tau multiplied by beta in G1
+    pub beta_g1: Vec<G1Affine>,
+
+    /// Beta in G2
+    pub beta_g2: G2Affine,
+}
+
+impl Srs {
+    /// Reads the header and the SRS of an arkworks SRS file.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<(SrsHeader, Self)> {
+        let header = SrsHeader::read(bytes, SRS_MAGIC)?;
+        let srs = Self::deserialize(&bytes[SrsHeader::SIZE..])
+            .map_err(|err| anyhow!("Invalid SRS: {}", err))?;
+        ensure!(
+            srs.tau_g2.len() == 1 << header.log_powers,
+            "The SRS holds {} powers instead of the 2^{} of its header",
+            srs.tau_g2.len(),
+            header.log_powers
+        );
+        Ok((header, srs))
+    }
+}
+
+/// Writes the first `len` points of `section` of the challenge `accumulator` to `writer` as a
+/// vector in the canonical compressed serialization.
+#[inline]
+fn write_canonical<G, F, W>(
+    writer: &mut W,
+    accumulator: &[u8],
+    layout: &Layout,
+    section: Section,
+    len: usize,
+    read: F,
+) -> Result
+where
+    G: AffineCurve,
+    F: Fn(&[u8], Encoding) -> Result<G> + Sync,
+    W: Write,
+{
+    CanonicalSerialize::serialize(&(len as u64), &mut *writer).map_err(|err| anyhow!("{}", err))?;
+    for start in (0..len).step_by(SRS_CHUNK_SIZE) {
+        let end = (start + SRS_CHUNK_SIZE).min(len);
+        let chunks = (start..end)
+            .into_par_iter()
+            .map(|index| {
+                let point = read(
+                    &accumulator[layout.point_range(section, index)],
+                    layout.encoding,
+                )?;
+                let mut bytes = Vec::with_capacity(point.serialized_size());
+                point
+                    .serialize(&mut bytes)
+                    .map_err(|err| anyhow!("{}", err))?;
+                Ok(bytes)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for bytes in chunks {
+            writer.write_all(&bytes)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the arkworks SRS file of the first `2^header.log_powers` powers of the challenge
+/// `accumulator` with `powers` powers of tau to `path`.
+#[inline]
+pub fn arkworks(accumulator: &[u8], powers: usize, header: &SrsHeader, path: &Path) -> Result {
+    let layout = Layout::challenge(powers);
+    ensure!(
+        accumulator.len() == layout.file_size(),
+        "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+        accumulator.len(),
+        layout.file_size(),
+        powers.trailing_zeros()
+    );
+    let n = 1 << header.log_powers;
+    ensure!(
+        n <= powers,
+        "The SRS needs more powers than the 2^{} of the accumulator",
+        powers.trailing_zeros()
+    );
+    info!("Writing {:?}", path);
+    atomic::write_with(path, |file| {
+        file.write_all(&header.to_bytes(SRS_MAGIC))?;
+        for section in Section::ALL {
+            let len = section.len(n);
+            if section == Section::BetaG2 {
+                let point = read_g2(
+                    &accumulator[layout.point_range(section, 0)],
+                    layout.encoding,
+                )?;
+                point
+                    .serialize(&mut *file)
+                    .map_err(|err| anyhow!("{}", err))?;
+            } else if section.is_g2() {
+                write_canonical(file, accumulator, &layout, section, len, read_g2)?;
+            } else {
+                write_canonical(file, accumulator, &layout, section, len, read_g1)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Writes the Lagrange basis of `domain` of size `2^header.log_powers` in G1, computed from the
+/// challenge `accumulator` with `powers` powers of tau, to `path`.
+#[inline]
+pub fn lagrange(
+    accumulator: &[u8],
+    powers: usize,
+    domain: Domain,
+    header: &SrsHeader,
+    path: &Path,
+) -> Result {
+    let layout = Layout::challenge(powers);
+    ensure!(
+        accumulator.len() == layout.file_size(),
+        "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+        accumulator.len(),
+        layout.file_size(),
+        powers.trailing_zeros()
+    );
+    let n = 1 << header.log_powers;
+    ensure!(
+        n <= powers,
+        "The domain of size 2^{} needs more powers than the 2^{} of the accumulator",
+        header.log_powers,
+        powers.trailing_zeros()
+    );
+    let points = lagrange_g1(accumulator, &layout, Section::TauG1, domain, n)?;
+    info!("Writing {:?}", path);
+    atomic::write_with(path, |file| {
+        file.write_all(&header.to_bytes(LAGRANGE_MAGIC))?;
+        file.write_all(&domain.generator().to_le_bytes())?;
+        points
+            .serialize(&mut *file)
+            .map_err(|err| anyhow!("{}", err))?;
+        Ok(())
+    })
+}
+
+/// Reads the header, the domain and the Lagrange basis of a Lagrange basis file.
+#[inline]
+pub fn read_lagrange(bytes: &[u8]) -> Result<(SrsHeader, Domain, Vec<G1Affine>)> {
+    let header = SrsHeader::read(bytes, LAGRANGE_MAGIC)?;
+    let generator = bytes
+        .get(SrsHeader::SIZE..SrsHeader::SIZE + 8)
+        .ok_or_else(|| anyhow!("Truncated Lagrange basis file"))?;
+    let generator = u64::from_le_bytes(generator.try_into()?);
+    let domain = Domain::from_generator(generator)
+        .ok_or_else(|| anyhow!("Unknown domain generator {}", generator))?;
+    let points = Vec::<G1Affine>::deserialize(&bytes[SrsHeader::SIZE + 8..])
+        .map_err(|err| anyhow!("Invalid Lagrange basis: {}", err))?;
+    ensure!(
+        points.len() == 1 << header.log_powers,
+        "The file holds {} points instead of the 2^{} of its header",
+        points.len(),
+        header.log_powers
+    );
+    Ok((header, domain, points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{Bn254, Fr};
+    use ark_ec::PairingEngine;
+    use ark_ff::{Field, One, UniformRand, Zero};
+    use std::fs;
+
+    /// Root of unity of order `2^28` of halo2curves, the power of 7 its domains derive from
+    const HALO2_ROOT_OF_UNITY: &str =
+        "03ddb9f5166d18b798865ea93dd31f743215cf6dd39329c8d34f1ed960c37c9c";
+
+    /// Builds the challenge accumulator with `powers` powers of `tau`, `alpha` and `beta`.
+    fn accumulator(powers: usize, tau: Fr, alpha: Fr, beta: Fr) -> Vec<u8> {
+        let layout = Layout::challenge(powers);
+        let mut accumulator = vec![0; layout.file_size()];
+        for section in Section::ALL {
+            let mut power = Fr::one();
+            for index in 0..section.len(powers) {
+                let scalar = match section {
+                    Section::TauG1 | Section::TauG2 => power,
+                    Section::AlphaG1 => alpha * power,
+                    Section::BetaG1 | Section::BetaG2 => beta * power,
+                };
+                let out = &mut accumulator[layout.point_range(section, index)];
+                if section.is_g2() {
+                    let point = G2Affine::prime_subgroup_generator().mul(scalar);
+                    write_g2(&point.into_affine(), Encoding::Uncompressed, out);
+                } else {
+                    let point = G1Affine::prime_subgroup_generator().mul(scalar);
+                    write_g1(&point.into_affine(), Encoding::Uncompressed, out);
+                }
+                power *= tau;
+            }
+        }
+        accumulator
+    }
+
+    /// Checks the layout of the exported files and that their points match the secrets of the
+    /// accumulator.
+    #[test]
+    fn export_formats() {
+        let powers = 8;
+        let layout = Layout::challenge(powers);
+        let mut rng = rand::thread_rng();
+        let [tau, alpha, beta] = [0; 3].map(|_| Fr::rand(&mut rng));
+        let accumulator = accumulator(powers, tau, alpha, beta);
+        let dir = std::env::temp_dir().join("ppot-verifier-export-test");
+        fs::create_dir_all(&dir).unwrap();
+        let paths = phase1radix(&accumulator, powers, 2, &dir).unwrap();
+        assert_eq!(paths.len(), 3);
+        let file = fs::read(phase1radix_path(&dir, 2)).unwrap();
+        let (g1, g2) = (64, 128);
+        assert_eq!(file.len(), 2 * g1 + g2 + 4 * (3 * g1 + g2) + 3 * g1);
+        let point_g1 = |offset: usize| read_g1(&file[offset..], Encoding::Uncompressed).unwrap();
+        let point_g2 = |offset: usize| read_g2(&file[offset..], Encoding::Uncompressed).unwrap();
+        assert_eq!(
+            point_g1(0),
+            G1Affine::prime_subgroup_generator()
+                .mul(alpha)
+                .into_affine()
+        );
+        let basis = (0..4).map(|i| point_g1(2 * g1 + g2 + i * g1));
+        assert_eq!(
+            basis.fold(G1Projective::zero(), |sum, point| sum
+                + point.into_projective()),
+            G1Affine::prime_subgroup_generator().into_projective()
+        );
+        let first_g2 = point_g2(2 * g1 + g2 + 4 * g1);
+        let first_g1 = point_g1(2 * g1 + g2);
+        assert_eq!(
+            Bn254::pairing(first_g1, G2Affine::prime_subgroup_generator()),
+            Bn254::pairing(G1Affine::prime_subgroup_generator(), first_g2)
+        );
+        let omega = Domain::Bellman.root_of_unity(4).unwrap();
+        let second = (tau.pow([4]) - Fr::one()) * omega / (Fr::from(4u64) * (tau - omega));
+        assert_eq!(
+            point_g1(3 * g1 + g2),
+            G1Affine::prime_subgroup_generator()
+                .mul(second)
+                .into_affine()
+        );
+        let h = point_g1(file.len() - 3 * g1);
+        let z = tau.pow([4]) - Fr::one();
+        assert_eq!(h, G1Affine::prime_subgroup_generator().mul(z).into_affine());
+        assert!(phase1radix(&accumulator, powers, 4, &dir).is_err());
+        let header = SrsHeader {
+            log_powers: 2,
+            round: 71,
+            provenance: Hash64([7; 64]),
+        };
+        let path = dir.join("srs");
+        arkworks(&accumulator, powers, &header, &path).unwrap();
+        let (read_header, srs) = Srs::read(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(read_header, header);
+        assert_eq!(srs.tau_g1.len(), 7);
+        assert_eq!(
+            srs.tau_g1[5],
+            G1Affine::prime_subgroup_generator()
+                .mul(tau.pow([5]))
+                .into_affine()
+        );
+        assert_eq!(
+            srs.beta_g2,
+            G2Affine::prime_subgroup_generator().mul(beta).into_affine()
+        );
+        let path = dir.join("lagrange");
+        lagrange(&accumulator, powers, Domain::Arkworks, &header, &path).unwrap();
+        let (read_header, domain, lagrange_points) =
+            read_lagrange(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!((read_header, domain), (header, Domain::Arkworks));
+        assert_eq!(
+            lagrange_points,
+            lagrange_g1(&accumulator, &layout, Section::TauG1, Domain::Arkworks, 4).unwrap()
+        );
+        let path = dir.join("params");
+        halo2(&accumulator, powers, 3, &path).unwrap();
+        let params = fs::read(&path).unwrap();
+        assert_eq!(params.len(), 4 + 2 * 8 * 64 + 2 * 128);
+        assert_eq!(params[..4], 3u32.to_le_bytes());
+        let omega = Domain::Bellman.root_of_unity(8).unwrap();
+        let n_inverse = Fr::from(8u64).inverse().unwrap();
+        for i in [0, 5] {
+            let root = omega.pow([i]);
+            let lagrange = (tau.pow([8]) - Fr::one()) * n_inverse * root / (tau - root);
+            assert_eq!(
+                ptau::read_g1(&params[4 + (8 + i as usize) * 64..]).unwrap(),
+                G1Affine::prime_subgroup_generator()
+                    .mul(lagrange)
+                    .into_affine()
+            );
+        }
+        assert_eq!(
+            ptau::read_g2(&params[params.len() - 128..]).unwrap(),
+            G2Affine::prime_subgroup_generator().mul(tau).into_affine()
+        );
+        let json = points(
+            &accumulator,
+            powers,
+            Section::TauG2,
+            1..3,
+            PointFormat::Json,
+        )
+        .unwrap();
+        let exported = serde_json::from_slice::<ExportedPoints>(&json).unwrap();
+        assert_eq!((exported.section, exported.start), (Section::TauG2, 1));
+        let tau_g2 = G2Affine::prime_subgroup_generator().mul(tau).into_affine();
+        assert_eq!(
+            exported.points[0],
+            HexPoint::G2 {
+                x: [hex_field(&tau_g2.x.c0), hex_field(&tau_g2.x.c1)],
+                y: [hex_field(&tau_g2.y.c0), hex_field(&tau_g2.y.c1)],
+            }
+        );
+        let raw = points(&accumulator, powers, Section::TauG1, 0..4, PointFormat::Raw).unwrap();
+        assert_eq!(raw.len(), 4 * 64);
+        let generator = G1Affine::prime_subgroup_generator();
+        assert_eq!(raw[..32], generator.x.into_repr().to_bytes_le());
+        assert_eq!(raw[32..64], generator.y.into_repr().to_bytes_le());
+        assert!(points(
+            &accumulator,
+            powers,
+            Section::BetaG2,
+            0..2,
+            PointFormat::Raw
+        )
+        .is_err());
+        assert!(points(
+            &accumulator,
+            powers,
+            Section::TauG1,
+            3..3,
+            PointFormat::Json
+        )
+        .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks the byte layout of the halo2 KZG parameters and that their Lagrange basis is the
+    /// inverse DFT of their powers of tau over the domain of halo2.
+    #[test]
+    fn halo2_params() {
+        let (powers, k, n) = (8, 2, 4);
+        let mut rng = rand::thread_rng();
+        let tau = Fr::rand(&mut rng);
+        let accumulator = accumulator(powers, tau, Fr::rand(&mut rng), Fr::rand(&mut rng));
+        let dir = std::env::temp_dir().join("ppot-verifier-halo2-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("params");
+        halo2(&accumulator, powers, k, &path).unwrap();
+        let params = fs::read(&path).unwrap();
+        let (g1, g2) = (ptau::G1_SIZE, ptau::G2_SIZE);
+        assert_eq!((g1, g2), (64, 128));
+        assert_eq!(params.len(), 4 + 2 * n * g1 + 2 * g2);
+        assert_eq!(params[..4], [2, 0, 0, 0]);
+        // halo2curves writes the coordinates of points little-endian in Montgomery form.
+        let generator = G1Affine::prime_subgroup_generator();
+        assert_eq!(params[4..36], generator.x.0.to_bytes_le());
+        assert_eq!(params[36..68], generator.y.0.to_bytes_le());
+        let read = |index: usize| ptau::read_g1(&params[4 + index * g1..]).unwrap();
+        let g = (0..n).map(read).collect::<Vec<_>>();
+        let g_lagrange = (n..2 * n).map(read).collect::<Vec<_>>();
+        for (i, point) in g.iter().enumerate() {
+            assert_eq!(*point, generator.mul(tau.pow([i as u64])).into_affine());
+        }
+        let mut omega = Fr::from_be_bytes_mod_order(&hex::decode(HALO2_ROOT_OF_UNITY).unwrap());
+        for _ in k..28 {
+            omega.square_in_place();
+        }
+        assert_eq!(omega.pow([n as u64]), Fr::one());
+        assert_ne!(omega.pow([n as u64 / 2]), Fr::one());
+        let omega_inverse = omega.inverse().unwrap();
+        let n_inverse = Fr::from(n as u64).inverse().unwrap();
+        for (i, point) in g_lagrange.iter().enumerate() {
+            let expected = g
+                .iter()
+                .enumerate()
+                .fold(G1Projective::zero(), |sum, (j, power)| {
+                    sum + power.mul(n_inverse * omega_inverse.pow([(i * j) as u64]))
+                });
+            assert_eq!(*point, expected.into_affine());
+        }
+        let g2_generator = G2Affine::prime_subgroup_generator();
+        let g2_start = 4 + 2 * n * g1;
+        assert_eq!(ptau::read_g2(&params[g2_start..]).unwrap(), g2_generator);
+        assert_eq!(
+            ptau::read_g2(&params[g2_start + g2..]).unwrap(),
+            g2_generator.mul(tau).into_affine()
+        );
+        assert!(halo2(&accumulator, powers, 4, &path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
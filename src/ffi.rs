@@ -0,0 +1,281 @@
+//! C Bindings
+//!
+//! With the `ffi` feature, the `cdylib` of the crate exports the hashing and verification of the
+//! ceremony files through a C ABI, declared in `include/ppot_verifier.h`, for services which are
+//! not written in Rust and would otherwise run the binaries and parse their output. Every function
+//! returns [`PPOT_OK`] on success, [`PPOT_INVALID`] when the files are read but fail the check and
+//! [`PPOT_ERROR`] when they cannot be read or the arguments are invalid, in which case
+//! [`ppot_last_error`] describes the error.
+
+use crate::{
+    accumulator::Accumulator, curve::Curve, hash::Hash64, hash_file, input::InputOptions,
+    verify_hash_chain, Result,
+};
+use anyhow::{anyhow, ensure};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use core::{cell::RefCell, ptr, slice};
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::PathBuf,
+};
+
+/// The call succeeded and the files passed the check
+pub const PPOT_OK: i32 = 0;
+
+/// The files were read but failed the check
+pub const PPOT_INVALID: i32 = 1;
+
+/// The files could not be read or the arguments are invalid
+pub const PPOT_ERROR: i32 = -1;
+
+/// Curve of the BN254 ceremonies, such as the perpetual powers of tau
+pub const PPOT_CURVE_BN254: u32 = 0;
+
+/// Curve of the BLS12-381 ceremonies, such as the Zcash Sapling powers of tau
+pub const PPOT_CURVE_BLS12_381: u32 = 1;
+
+thread_local! {
+    /// Message of the last error of the calling thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records `err` as the last error of the calling thread and returns `code`.
+#[inline]
+fn fail(code: i32, err: anyhow::Error) -> i32 {
+    let message = CString::new(err.to_string().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    code
+}
+
+/// Converts the C string `path` into a path.
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string.
+#[inline]
+unsafe fn path(path: *const c_char) -> Result<PathBuf> {
+    ensure!(!path.is_null(), "Null path");
+    Ok(PathBuf::from(CStr::from_ptr(path).to_str()?))
+}
+
+/// Returns the message of the last error of the calling thread, or null if there is none. The
+/// message stays valid until the next call failing on the same thread.
+#[no_mangle]
+pub extern "C" fn ppot_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Computes the Blake2b hash of the file at `path` and writes its 64 bytes to `out`.
+///
+/// # Safety
+///
+/// `path` must point to a NUL-terminated string and `out` to 64 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ppot_calculate_hash(path: *const c_char, out: *mut u8) -> i32 {
+    let result = (|| {
+        ensure!(!out.is_null(), "Null output buffer");
+        hash_file(self::path(path)?)
+    })();
+    match result {
+        Ok(hash) => {
+            slice::from_raw_parts_mut(out, hash.0.len()).copy_from_slice(&hash.0);
+            PPOT_OK
+        }
+        Err(err) => fail(PPOT_ERROR, err),
+    }
+}
+
+/// Checks the hash chain between the `len` consecutive files at `paths`, where each file asserts
+/// the hash of the one before it in its header. When a link does not match, the index of the
+/// file whose hash differs is written to `mismatch`, if it is not null, and [`PPOT_INVALID`] is
+/// returned.
+///
+/// # Safety
+///
+/// `paths` must point to `len` NUL-terminated strings and `mismatch` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn ppot_verify_hash_chain(
+    paths: *const *const c_char,
+    len: usize,
+    mismatch: *mut usize,
+) -> i32 {
+    let paths = match (|| {
+        ensure!(!paths.is_null() || len == 0, "Null paths");
+        slice::from_raw_parts(paths, len)
+            .iter()
+            .map(|file| path(*file))
+            .collect::<Result<Vec<_>>>()
+    })() {
+        Ok(paths) => paths,
+        Err(err) => return fail(PPOT_ERROR, err),
+    };
+    match verify_hash_chain(&paths) {
+        Ok(None) => PPOT_OK,
+        Ok(Some(index)) => {
+            if !mismatch.is_null() {
+                *mismatch = index;
+            }
+            fail(
+                PPOT_INVALID,
+                anyhow!(
+                    "{:?} does not match the hash asserted by {:?}",
+                    paths[index],
+                    paths[index + 1]
+                ),
+            )
+        }
+        Err(err) => fail(PPOT_ERROR, err),
+    }
+}
+
+/// Verifies the round turning the challenge file at `challenge` into the response file at
+/// `response`, both over `curve` with `2^file_log_powers` powers of tau, on their first
+/// `2^log_powers` powers. Files which cannot be parsed with that many powers are reported as
+/// errors rather than as invalid.
+///
+/// # Safety
+///
+/// `challenge` and `response` must point to NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn ppot_verify_round(
+    challenge: *const c_char,
+    response: *const c_char,
+    curve: u32,
+    file_log_powers: u32,
+    log_powers: u32,
+) -> i32 {
+    let maps = (|| {
+        ensure!(
+            log_powers <= file_log_powers && file_log_powers < usize::BITS,
+            "Unable to verify 2^{} powers of files with 2^{}",
+            log_powers,
+            file_log_powers
+        );
+        let input = InputOptions::default();
+        Ok((
+            input.open(path(challenge)?)?.into_mmap()?,
+            input.open(path(response)?)?.into_mmap()?,
+        ))
+    })();
+    let (challenge, response) = match maps {
+        Ok(maps) => maps,
+        Err(err) => return fail(PPOT_ERROR, err),
+    };
+    let (file_powers, powers) = (1 << file_log_powers, 1 << log_powers);
+    match curve {
+        PPOT_CURVE_BN254 => verify_round::<Bn254>(&challenge, &response, file_powers, powers),
+        PPOT_CURVE_BLS12_381 => {
+            verify_round::<Bls12_381>(&challenge, &response, file_powers, powers)
+        }
+        _ => fail(PPOT_ERROR, anyhow!("Unknown curve {}", curve)),
+    }
+}
+
+/// Verifies the round turning `challenge` into `response` over `C` like
+/// [`verify_round`](crate::accumulator::verify_round), returning [`PPOT_ERROR`] when the files
+/// cannot be parsed with `file_powers` powers of tau and [`PPOT_INVALID`] when they fail the check.
+#[inline]
+fn verify_round<C>(challenge: &[u8], response: &[u8], file_powers: usize, powers: usize) -> i32
+where
+    C: Curve,
+{
+    let accumulators = (|| {
+        Ok((
+            Hash64::from_header(response)
+                .ok_or_else(|| anyhow!("The response file is too short"))?,
+            Accumulator::<C>::challenge(challenge, file_powers)?,
+            Accumulator::<C>::response(response, file_powers)?,
+        ))
+    })();
+    let (challenge_hash, challenge, response) = match accumulators {
+        Ok(accumulators) => accumulators,
+        Err(err) => return fail(PPOT_ERROR, err),
+    };
+    match challenge.verify_transform(&response, &challenge_hash, powers) {
+        Ok(()) => PPOT_OK,
+        Err(err) => fail(PPOT_INVALID, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calculate_hash,
+        synthetic::{Fault, MiniCeremony},
+        HashAlgorithm,
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::fs;
+
+    /// Checks that hashes and hash chains go through the C ABI, and that errors are reported.
+    #[test]
+    fn c_abi() {
+        let dir = std::env::temp_dir().join("ppot-verifier-ffi-test");
+        fs::create_dir_all(&dir).unwrap();
+        let (first, second) = (dir.join("challenge"), dir.join("response"));
+        fs::write(&first, b"challenge").unwrap();
+        let hash = calculate_hash(b"challenge", HashAlgorithm::Blake2b);
+        fs::write(&second, [hash.as_slice(), b"response"].concat()).unwrap();
+        let paths = [&first, &second].map(|path| CString::new(path.to_str().unwrap()).unwrap());
+        let mut out = [0; 64];
+        unsafe {
+            assert_eq!(
+                ppot_calculate_hash(paths[0].as_ptr(), out.as_mut_ptr()),
+                PPOT_OK
+            );
+            assert_eq!(out.as_slice(), hash.as_slice());
+            let pointers = paths.each_ref().map(|path| path.as_ptr());
+            let mut mismatch = usize::MAX;
+            assert_eq!(
+                ppot_verify_hash_chain(pointers.as_ptr(), 2, &mut mismatch),
+                PPOT_OK
+            );
+            fs::write(&first, b"tampered").unwrap();
+            assert_eq!(
+                ppot_verify_hash_chain(pointers.as_ptr(), 2, &mut mismatch),
+                PPOT_INVALID
+            );
+            assert_eq!(mismatch, 0);
+            assert_eq!(
+                ppot_verify_round(pointers[0], pointers[1], PPOT_CURVE_BN254, 2, 1),
+                PPOT_ERROR
+            );
+            assert_eq!(
+                ppot_verify_round(pointers[0], pointers[1], 7, 2, 1),
+                PPOT_ERROR
+            );
+            assert!(CStr::from_ptr(ppot_last_error())
+                .to_str()
+                .unwrap()
+                .contains("Unknown curve"));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Checks that rounds which are parsed but fail the check are told apart from the others.
+    #[test]
+    fn c_abi_rounds() {
+        let dir = std::env::temp_dir().join("ppot-verifier-ffi-round-test");
+        let mut rng = ChaCha20Rng::seed_from_u64(17);
+        for (fault, code) in [(None, PPOT_OK), (Some(Fault::WrongProof), PPOT_INVALID)] {
+            let ceremony = MiniCeremony::<Bn254>::generate_faulty(1 << 2, 1, fault, &mut rng);
+            let (challenges, responses) = ceremony.write(&dir).unwrap();
+            let paths = [&challenges[0], &responses[0]]
+                .map(|path| CString::new(path.to_str().unwrap()).unwrap());
+            assert_eq!(
+                unsafe {
+                    ppot_verify_round(paths[0].as_ptr(), paths[1].as_ptr(), PPOT_CURVE_BN254, 2, 2)
+                },
+                code
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
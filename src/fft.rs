@@ -0,0 +1,228 @@
+//! Group FFT
+//!
+//! Proving systems commit to polynomials in the Lagrange basis of a radix-2 domain, while the
+//! ceremony only produces the monomial basis `tau^i * G`. Since the change of basis is linear, the
+//! inverse FFT of the powers of tau over a domain of size `n` gives the Lagrange polynomials of the
+//! domain evaluated at tau, `L_i(tau) * G`, without knowing tau.
+//!
+//! The order of the Lagrange basis depends on the root of unity generating the domain, and
+//! libraries do not agree on it: see [`Domain`].
+
+use crate::Result;
+use anyhow::ensure;
+use ark_bn254::Fr;
+use ark_ec::ProjectiveCurve;
+use ark_ff::{FftField, FftParameters, Field, FpParameters, One, PrimeField};
+use rayon::prelude::*;
+
+/// Returns the primitive root of unity of order `n`, failing if `n` is not a power of two
+/// supported by the scalar field.
+#[inline]
+pub fn root_of_unity(n: usize) -> Result<Fr> {
+    ensure!(
+        n.is_power_of_two() && n.trailing_zeros() <= <Fr as FftField>::FftParams::TWO_ADICITY,
+        "No radix-2 domain of size {}",
+        n
+    );
+    Ok(Fr::get_root_of_unity(n).expect("the domain size is supported"))
+}
+
+/// Radix-2 Domain Convention
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Domain {
+    /// Roots of unity derived from the multiplicative generator 5, as in arkworks and snarkjs
+    #[default]
+    Arkworks,
+
+    /// Roots of unity derived from the multiplicative generator 7, as in bellman and halo2
+    Bellman,
+}
+
+impl Domain {
+    /// Returns the multiplicative generator of the scalar field the roots of unity are derived
+    /// from.
+    #[inline]
+    pub fn generator(self) -> u64 {
+        match self {
+            Self::Arkworks => 5,
+            Self::Bellman => 7,
+        }
+    }
+
+    /// Returns the domain whose roots of unity are derived from `generator`, if any.
+    #[inline]
+    pub fn from_generator(generator: u64) -> Option<Self> {
+        match generator {
+            5 => Some(Self::Arkworks),
+            7 => Some(Self::Bellman),
+            _ => None,
+        }
+    }
+
+    /// Returns the primitive root of unity of order `n` generating the domain of size `n`.
+    #[inline]
+    pub fn root_of_unity(self, n: usize) -> Result<Fr> {
+        let root = root_of_unity(n)?;
+        if self == Self::Arkworks {
+            return Ok(root);
+        }
+        let two_adicity = <Fr as FftField>::FftParams::TWO_ADICITY;
+        let mut root = Fr::from(self.generator()).pow(<Fr as PrimeField>::Params::T);
+        for _ in n.trailing_zeros()..two_adicity {
+            root.square_in_place();
+        }
+        Ok(root)
+    }
+}
+
+/// Evaluates the polynomial with coefficients `points` over the domain generated by `omega`, in
+/// place.
+#[inline]
+fn radix2<G>(points: &mut [G], omega: Fr)
+where
+    G: ProjectiveCurve<ScalarField = Fr>,
+{
+    let n = points.len();
+    if n < 2 {
+        return;
+    }
+    let log_n = n.trailing_zeros();
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - log_n);
+        if i < j {
+            points.swap(i, j);
+        }
+    }
+    let mut twiddles = Vec::with_capacity(n / 2);
+    let mut twiddle = Fr::one();
+    for _ in 0..n / 2 {
+        twiddles.push(twiddle.into_repr());
+        twiddle *= omega;
+    }
+    let mut half = 1;
+    while half < n {
+        let step = n / (2 * half);
+        points.par_chunks_mut(2 * half).for_each(|chunk| {
+            let (low, high) = chunk.split_at_mut(half);
+            low.par_iter_mut()
+                .zip(high.par_iter_mut())
+                .enumerate()
+                .for_each(|(j, (low, high))| {
+                    let product = high.mul(twiddles[j * step]);
+                    *high = *low - product;
+                    *low += product;
+                });
+        });
+        half *= 2;
+    }
+}
+
+/// Evaluates the polynomial with coefficients `points` over the radix-2 domain of their size, in
+/// place.
+#[inline]
+pub fn fft<G>(points: &mut [G]) -> Result
+where
+    G: ProjectiveCurve<ScalarField = Fr>,
+{
+    let omega = root_of_unity(points.len())?;
+    radix2(points, omega);
+    Ok(())
+}
+
+/// Interpolates the evaluations `points` over the radix-2 domain of their size into the
+/// coefficients of their polynomial, in place. Applied to the powers of tau, this returns the
+/// Lagrange basis of the domain at tau.
+#[inline]
+pub fn ifft<G>(points: &mut [G]) -> Result
+where
+    G: ProjectiveCurve<ScalarField = Fr>,
+{
+    ifft_with_root(points, root_of_unity(points.len())?)
+}
+
+/// Interpolates the evaluations `points` over the domain generated by `omega`, which must be a
+/// primitive root of unity of the order of their size, in place. Libraries derive their domains
+/// from different roots of unity, which order the Lagrange basis differently.
+#[inline]
+pub fn ifft_with_root<G>(points: &mut [G], omega: Fr) -> Result
+where
+    G: ProjectiveCurve<ScalarField = Fr>,
+{
+    let n = points.len();
+    ensure!(
+        n.is_power_of_two()
+            && omega.pow([n as u64]).is_one()
+            && (n == 1 || !omega.pow([n as u64 / 2]).is_one()),
+        "Not a primitive root of unity of order {}",
+        n
+    );
+    radix2(
+        points,
+        omega.inverse().expect("roots of unity are invertible"),
+    );
+    let n_inverse = Fr::from(n as u64)
+        .inverse()
+        .expect("the domain size is invertible")
+        .into_repr();
+    points
+        .par_iter_mut()
+        .for_each(|point| *point = point.mul(n_inverse));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::{G1Affine, G1Projective};
+    use ark_ec::AffineCurve;
+    use ark_ff::{UniformRand, Zero};
+
+    /// Checks that the inverse FFT of the powers of tau gives the Lagrange basis at tau and that
+    /// the FFT brings the powers back.
+    #[test]
+    fn lagrange_basis() {
+        let n = 16;
+        let tau = Fr::rand(&mut rand::thread_rng());
+        let generator = G1Affine::prime_subgroup_generator().into_projective();
+        let mut power = Fr::one();
+        let powers = (0..n)
+            .map(|_| {
+                let point = generator.mul(power.into_repr());
+                power *= tau;
+                point
+            })
+            .collect::<Vec<_>>();
+        let mut points = powers.clone();
+        ifft(&mut points).unwrap();
+        assert_eq!(
+            points
+                .iter()
+                .fold(G1Projective::zero(), |sum, point| sum + point),
+            generator
+        );
+        let first = (tau.pow([n as u64]) - Fr::one()) / (Fr::from(n as u64) * (tau - Fr::one()));
+        assert_eq!(points[0], generator.mul(first.into_repr()));
+        fft(&mut points).unwrap();
+        assert_eq!(points, powers);
+        assert!(ifft(&mut points[..3]).is_err());
+        assert_eq!(
+            Domain::Arkworks.root_of_unity(n).unwrap(),
+            root_of_unity(n).unwrap()
+        );
+        assert_eq!(
+            Domain::Bellman.root_of_unity(1 << 28).unwrap().into_repr(),
+            ark_ff::BigInteger256::new([
+                0xd34f1ed960c37c9c,
+                0x3215cf6dd39329c8,
+                0x98865ea93dd31f74,
+                0x03ddb9f5166d18b7,
+            ])
+        );
+        let omega = Domain::Bellman.root_of_unity(n).unwrap();
+        ifft_with_root(&mut points, omega).unwrap();
+        let root = omega.pow([3]);
+        let lagrange =
+            (tau.pow([n as u64]) - Fr::one()) * root / (Fr::from(n as u64) * (tau - root));
+        assert_eq!(points[3], generator.mul(lagrange.into_repr()));
+    }
+}
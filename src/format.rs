@@ -0,0 +1,558 @@
+//! PPoT File Format
+//!
+//! Every file of the ceremony starts with the 64-byte Blake2b hash of the file it was computed
+//! from, followed by an accumulator: `2 * 2^28 - 1` powers of tau in G1, `2^28` powers of tau in
+//! G2, `2^28` multiples of alpha and of beta in G1 and beta in G2. Challenge files store the points
+//! uncompressed, response files store them compressed and end with the public key of the
+//! contribution, uncompressed.
+//!
+//! Coordinates are written big-endian out of Montgomery form, with the `c1` coefficient of the G2
+//! coordinates first. The two top bits of the first byte of a point are free since the BN254 base
+//! field has 254 bits: the highest one flags a compressed point whose `y` coordinate is the
+//! greatest of `y` and `-y`, and the next one flags the point at infinity.
+//!
+//! Ceremonies over BLS12-381 share the format with wider coordinates and other flags, see
+//! [`Curve`]: the functions and layouts ending in `_on` take the curve as a parameter, the others
+//! are over BN254.
+
+use crate::{
+    curve::{Curve, G1, G2},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use ark_bn254::{Bn254, G1Affine, G2Affine};
+use ark_ec::{models::SWModelParameters, short_weierstrass_jacobian::GroupAffine};
+use ark_ff::{Field, Zero};
+use core::ops::Range;
+use serde::{Deserialize, Serialize};
+
+/// Size of the hash starting every file
+pub const HASH_SIZE: usize = 64;
+
+/// Base-two logarithm of the number of powers of tau in the files of the ceremony
+pub const CEREMONY_LOG_POWERS: u32 = 28;
+
+/// Number of powers of tau in the files of the ceremony
+pub const CEREMONY_POWERS: usize = 1 << CEREMONY_LOG_POWERS;
+
+/// Size of a field element of the BN254 base field
+const FIELD_SIZE: usize = Bn254::FIELD_SIZE;
+
+/// Size of the public key ending the BN254 response files
+pub const PUBLIC_KEY_SIZE: usize = public_key_size(FIELD_SIZE);
+
+/// Returns the size of the public key ending the response files over a base field of
+/// `field_size` bytes: six points in G1 and three in G2, uncompressed.
+#[inline]
+pub const fn public_key_size(field_size: usize) -> usize {
+    6 * 2 * field_size + 3 * 4 * field_size
+}
+
+/// Point Encoding
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Encoding {
+    /// Only the `x` coordinate and the sign of `y`, as in response files
+    Compressed,
+
+    /// Both coordinates, as in challenge files
+    Uncompressed,
+}
+
+impl Encoding {
+    /// Returns the size of a G1 point over a base field of `field_size` bytes encoded with `self`.
+    #[inline]
+    pub fn g1_size_over(self, field_size: usize) -> usize {
+        point_size(field_size, self)
+    }
+
+    /// Returns the size of a BN254 G1 point encoded with `self`.
+    #[inline]
+    pub fn g1_size(self) -> usize {
+        self.g1_size_over(FIELD_SIZE)
+    }
+
+    /// Returns the size of a BN254 G2 point encoded with `self`.
+    #[inline]
+    pub fn g2_size(self) -> usize {
+        2 * self.g1_size()
+    }
+
+    /// Returns the size of a G1 point of `C` encoded with `self`.
+    #[inline]
+    pub fn g1_size_on<C>(self) -> usize
+    where
+        C: Curve,
+    {
+        self.g1_size_over(C::FIELD_SIZE)
+    }
+
+    /// Returns the size of a G2 point of `C` encoded with `self`.
+    #[inline]
+    pub fn g2_size_on<C>(self) -> usize
+    where
+        C: Curve,
+    {
+        2 * self.g1_size_on::<C>()
+    }
+}
+
+/// Accumulator Section
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    /// Powers of tau in G1
+    TauG1,
+
+    /// Powers of tau in G2
+    TauG2,
+
+    /// Powers of tau multiplied by alpha in G1
+    AlphaG1,
+
+    /// Powers of tau multiplied by beta in G1
+    BetaG1,
+
+    /// Beta in G2
+    BetaG2,
+}
+
+impl Section {
+    /// Sections in the order of the files
+    pub const ALL: [Self; 5] = [
+        Self::TauG1,
+        Self::TauG2,
+        Self::AlphaG1,
+        Self::BetaG1,
+        Self::BetaG2,
+    ];
+
+    /// Returns `true` if the points of the section are in G2.
+    #[inline]
+    pub fn is_g2(self) -> bool {
+        matches!(self, Self::TauG2 | Self::BetaG2)
+    }
+
+    /// Returns the number of points of the section in an accumulator of `powers` powers of tau.
+    #[inline]
+    pub fn len(self, powers: usize) -> usize {
+        match self {
+            Self::TauG1 => 2 * powers - 1,
+            Self::TauG2 | Self::AlphaG1 | Self::BetaG1 => powers,
+            Self::BetaG2 => 1,
+        }
+    }
+
+    /// Returns the size of a BN254 point of the section encoded with `encoding`.
+    #[inline]
+    pub fn point_size(self, encoding: Encoding) -> usize {
+        self.point_size_over(FIELD_SIZE, encoding)
+    }
+
+    /// Returns the size of a point of the section over a base field of `field_size` bytes encoded
+    /// with `encoding`.
+    #[inline]
+    pub fn point_size_over(self, field_size: usize, encoding: Encoding) -> usize {
+        let size = encoding.g1_size_over(field_size);
+        if self.is_g2() {
+            2 * size
+        } else {
+            size
+        }
+    }
+}
+
+/// File Layout
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Layout {
+    /// Number of powers of tau
+    pub powers: usize,
+
+    /// Encoding of the points
+    pub encoding: Encoding,
+
+    /// Size of an element of the base field of the curve
+    pub field_size: usize,
+}
+
+impl Layout {
+    /// Returns the layout of the BN254 challenge files with `powers` powers of tau.
+    #[inline]
+    pub fn challenge(powers: usize) -> Self {
+        Self::challenge_on::<Bn254>(powers)
+    }
+
+    /// Returns the layout of the BN254 response files with `powers` powers of tau.
+    #[inline]
+    pub fn response(powers: usize) -> Self {
+        Self::response_on::<Bn254>(powers)
+    }
+
+    /// Returns the layout of the challenge files over `C` with `powers` powers of tau.
+    #[inline]
+    pub fn challenge_on<C>(powers: usize) -> Self
+    where
+        C: Curve,
+    {
+        Self {
+            powers,
+            encoding: Encoding::Uncompressed,
+            field_size: C::FIELD_SIZE,
+        }
+    }
+
+    /// Returns the layout of the response files over `C` with `powers` powers of tau.
+    #[inline]
+    pub fn response_on<C>(powers: usize) -> Self
+    where
+        C: Curve,
+    {
+        Self {
+            powers,
+            encoding: Encoding::Compressed,
+            field_size: C::FIELD_SIZE,
+        }
+    }
+
+    /// Returns the size of a point of `section`.
+    #[inline]
+    pub fn point_size(&self, section: Section) -> usize {
+        section.point_size_over(self.field_size, self.encoding)
+    }
+
+    /// Returns the size of `section`.
+    #[inline]
+    pub fn section_size(&self, section: Section) -> usize {
+        section.len(self.powers) * self.point_size(section)
+    }
+
+    /// Returns the offset of `section` from the start of the file.
+    #[inline]
+    pub fn offset(&self, section: Section) -> usize {
+        HASH_SIZE
+            + Section::ALL
+                .iter()
+                .take_while(|other| **other != section)
+                .map(|other| self.section_size(*other))
+                .sum::<usize>()
+    }
+
+    /// Returns the byte range of the point at `index` in `section`.
+    #[inline]
+    pub fn point_range(&self, section: Section, index: usize) -> Range<usize> {
+        let size = self.point_size(section);
+        let start = self.offset(section) + index * size;
+        start..start + size
+    }
+
+    /// Returns the size of the accumulator, between the hash and the public key.
+    #[inline]
+    pub fn accumulator_size(&self) -> usize {
+        Section::ALL
+            .iter()
+            .map(|section| self.section_size(*section))
+            .sum()
+    }
+
+    /// Returns the byte ranges of the file holding its first `powers` powers of tau: the header,
+    /// the first points of each section and the public key of response files, which make up the
+    /// file with `powers` powers once concatenated. They are the only ranges to request to verify
+    /// a remote file on its first powers.
+    #[inline]
+    pub fn prefix_ranges(&self, powers: usize) -> Vec<Range<usize>> {
+        let prefix = Self { powers, ..*self };
+        let mut ranges = Vec::with_capacity(Section::ALL.len() + 2);
+        ranges.push(0..HASH_SIZE);
+        for section in Section::ALL {
+            let offset = self.offset(section);
+            ranges.push(offset..offset + prefix.section_size(section));
+        }
+        if self.encoding == Encoding::Compressed {
+            let file_size = self.file_size();
+            ranges.push(file_size - public_key_size(self.field_size)..file_size);
+        }
+        ranges
+    }
+
+    /// Returns the size of a whole file, with the public key of response files.
+    #[inline]
+    pub fn file_size(&self) -> usize {
+        let public_key = match self.encoding {
+            Encoding::Compressed => public_key_size(self.field_size),
+            Encoding::Uncompressed => 0,
+        };
+        HASH_SIZE + self.accumulator_size() + public_key
+    }
+}
+
+/// Reads an element of the base field or of its quadratic extension from its big-endian bytes,
+/// without flags, with the `c1` coefficient first.
+#[inline]
+fn read_coordinate<F>(bytes: &[u8]) -> Result<F>
+where
+    F: Field,
+{
+    let mut bytes = bytes.to_vec();
+    bytes.reverse();
+    F::read(bytes.as_slice()).map_err(|_| anyhow!("Invalid field element"))
+}
+
+/// Writes `element` to its big-endian bytes in `out`, with the `c1` coefficient first.
+#[inline]
+fn write_coordinate<F>(element: &F, out: &mut [u8])
+where
+    F: Field,
+{
+    element
+        .write(&mut *out)
+        .expect("the output holds the coordinate");
+    out.reverse();
+}
+
+/// Splits the flags of `C` off the first byte of an encoded point, returning them with the bytes
+/// of the point without flags.
+#[inline]
+fn split_flags<C>(bytes: &[u8], encoding: Encoding) -> Result<(u8, Vec<u8>)>
+where
+    C: Curve,
+{
+    let mut bytes = bytes.to_vec();
+    let flags = bytes[0] & C::FLAGS.mask();
+    bytes[0] &= !C::FLAGS.mask();
+    match encoding {
+        Encoding::Compressed if flags & C::FLAGS.compressed != C::FLAGS.compressed => {
+            bail!("Missing compression flag in a compressed point")
+        }
+        Encoding::Uncompressed if flags & (C::FLAGS.compressed | C::FLAGS.greatest) != 0 => {
+            bail!("Unexpected compression flag in an uncompressed point")
+        }
+        _ => {}
+    }
+    if flags & C::FLAGS.infinity != 0 && bytes.iter().any(|byte| *byte != 0) {
+        bail!("Point at infinity with non-zero coordinates");
+    }
+    Ok((flags, bytes))
+}
+
+/// Reads a point of `C` with parameters `P` encoded with `encoding`, checking that it is on the
+/// curve. The subgroup is not checked.
+#[inline]
+fn read_point<C, P>(bytes: &[u8], encoding: Encoding) -> Result<GroupAffine<P>>
+where
+    C: Curve,
+    P: SWModelParameters,
+{
+    let size = P::BaseField::extension_degree() as usize * C::FIELD_SIZE;
+    let (flags, bytes) = split_flags::<C>(
+        bytes
+            .get(..point_size(size, encoding))
+            .ok_or_else(|| anyhow!("Truncated point"))?,
+        encoding,
+    )?;
+    if flags & C::FLAGS.infinity != 0 {
+        return Ok(GroupAffine::zero());
+    }
+    let x = read_coordinate(&bytes[..size])?;
+    let point = match encoding {
+        Encoding::Compressed => GroupAffine::get_point_from_x(x, flags & C::FLAGS.greatest != 0)
+            .ok_or_else(|| anyhow!("No point with this x coordinate"))?,
+        Encoding::Uncompressed => GroupAffine::new(x, read_coordinate(&bytes[size..])?, false),
+    };
+    if !point.is_on_curve() {
+        bail!("Point not on the curve");
+    }
+    Ok(point)
+}
+
+/// Writes `point` of `C` encoded with `encoding` to `out`.
+#[inline]
+fn write_point<C, P>(point: &GroupAffine<P>, encoding: Encoding, out: &mut [u8])
+where
+    C: Curve,
+    P: SWModelParameters,
+{
+    let size = P::BaseField::extension_degree() as usize * C::FIELD_SIZE;
+    let out = &mut out[..point_size(size, encoding)];
+    out.fill(0);
+    if point.is_zero() {
+        out[0] |= C::FLAGS.infinity;
+    } else {
+        write_coordinate(&point.x, &mut out[..size]);
+        match encoding {
+            Encoding::Compressed => {
+                if point.y > -point.y {
+                    out[0] |= C::FLAGS.greatest;
+                }
+            }
+            Encoding::Uncompressed => write_coordinate(&point.y, &mut out[size..]),
+        }
+    }
+    if encoding == Encoding::Compressed {
+        out[0] |= C::FLAGS.compressed;
+    }
+}
+
+/// Returns the size of a point whose coordinates have `size` bytes encoded with `encoding`.
+#[inline]
+fn point_size(size: usize, encoding: Encoding) -> usize {
+    match encoding {
+        Encoding::Compressed => size,
+        Encoding::Uncompressed => 2 * size,
+    }
+}
+
+/// Reads a G1 point of `C` encoded with `encoding`, checking that it is on the curve. The subgroup
+/// is not checked.
+#[inline]
+pub fn read_g1_on<C>(bytes: &[u8], encoding: Encoding) -> Result<G1<C>>
+where
+    C: Curve,
+{
+    read_point::<C, _>(bytes, encoding).map_err(|err| anyhow!("Invalid G1 point: {}", err))
+}
+
+/// Writes the G1 `point` of `C` encoded with `encoding` to `out`.
+#[inline]
+pub fn write_g1_on<C>(point: &G1<C>, encoding: Encoding, out: &mut [u8])
+where
+    C: Curve,
+{
+    write_point::<C, _>(point, encoding, out)
+}
+
+/// Reads a G2 point of `C` encoded with `encoding`, checking that it is on the curve. The subgroup
+/// is not checked.
+#[inline]
+pub fn read_g2_on<C>(bytes: &[u8], encoding: Encoding) -> Result<G2<C>>
+where
+    C: Curve,
+{
+    read_point::<C, _>(bytes, encoding).map_err(|err| anyhow!("Invalid G2 point: {}", err))
+}
+
+/// Writes the G2 `point` of `C` encoded with `encoding` to `out`.
+#[inline]
+pub fn write_g2_on<C>(point: &G2<C>, encoding: Encoding, out: &mut [u8])
+where
+    C: Curve,
+{
+    write_point::<C, _>(point, encoding, out)
+}
+
+/// Reads a BN254 G1 point encoded with `encoding`, checking that it is on the curve.
+#[inline]
+pub fn read_g1(bytes: &[u8], encoding: Encoding) -> Result<G1Affine> {
+    read_g1_on::<Bn254>(bytes, encoding)
+}
+
+/// Writes the BN254 G1 `point` encoded with `encoding` to `out`.
+#[inline]
+pub fn write_g1(point: &G1Affine, encoding: Encoding, out: &mut [u8]) {
+    write_g1_on::<Bn254>(point, encoding, out)
+}
+
+/// Reads a BN254 G2 point encoded with `encoding`, checking that it is on the curve. The subgroup
+/// is not checked.
+#[inline]
+pub fn read_g2(bytes: &[u8], encoding: Encoding) -> Result<G2Affine> {
+    read_g2_on::<Bn254>(bytes, encoding)
+}
+
+/// Writes the BN254 G2 `point` encoded with `encoding` to `out`.
+#[inline]
+pub fn write_g2(point: &G2Affine, encoding: Encoding, out: &mut [u8]) {
+    write_g2_on::<Bn254>(point, encoding, out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::Scalar;
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::UniformRand;
+
+    /// Checks that the points of `C` round-trip through both encodings.
+    fn round_trip<C>()
+    where
+        C: Curve,
+    {
+        let mut rng = rand::thread_rng();
+        for encoding in [Encoding::Compressed, Encoding::Uncompressed] {
+            let mut g1 = vec![0; encoding.g1_size_on::<C>()];
+            let mut g2 = vec![0; encoding.g2_size_on::<C>()];
+            for _ in 0..8 {
+                let point = G1::<C>::prime_subgroup_generator()
+                    .mul(Scalar::<C>::rand(&mut rng))
+                    .into_affine();
+                write_g1_on::<C>(&point, encoding, &mut g1);
+                assert_eq!(read_g1_on::<C>(&g1, encoding).unwrap(), point);
+                let point = G2::<C>::prime_subgroup_generator()
+                    .mul(Scalar::<C>::rand(&mut rng))
+                    .into_affine();
+                write_g2_on::<C>(&point, encoding, &mut g2);
+                assert_eq!(read_g2_on::<C>(&g2, encoding).unwrap(), point);
+            }
+            write_g1_on::<C>(&G1::<C>::zero(), encoding, &mut g1);
+            assert_eq!(g1[0] & C::FLAGS.infinity, C::FLAGS.infinity);
+            assert!(read_g1_on::<C>(&g1, encoding).unwrap().is_zero());
+        }
+    }
+
+    /// Checks that the points round-trip through both encodings on both curves, that BLS12-381
+    /// points are encoded as in the `pairing` crate and that the layout adds up.
+    #[test]
+    fn points_round_trip() {
+        round_trip::<Bn254>();
+        round_trip::<Bls12_381>();
+        let mut g1 = [0; 48];
+        let mut g2 = [0; 96];
+        write_g1_on::<Bls12_381>(
+            &G1::<Bls12_381>::prime_subgroup_generator(),
+            Encoding::Compressed,
+            &mut g1,
+        );
+        write_g2_on::<Bls12_381>(
+            &G2::<Bls12_381>::prime_subgroup_generator(),
+            Encoding::Compressed,
+            &mut g2,
+        );
+        assert_eq!(
+            hex::encode(g1),
+            "97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        );
+        assert_eq!(
+            hex::encode(g2),
+            "93e02b6052719f607dacd3a088274f65596bd0d09920b61ab5da61bbdc7f5049334cf11213945d57e5ac7d055d042b7e\
+             024aa2b2f08f0a91260805272dc51051c6e47ad4fa403b02b4510b647ae3d1770bac0326a805bbefd48056c8c121bdb8"
+        );
+        g1[0] &= !Bls12_381::FLAGS.compressed;
+        assert!(read_g1_on::<Bls12_381>(&g1, Encoding::Compressed).is_err());
+        let layout = Layout::response(4);
+        assert_eq!(layout.offset(Section::TauG1), HASH_SIZE);
+        assert_eq!(layout.offset(Section::TauG2), HASH_SIZE + 7 * 32);
+        assert_eq!(
+            layout.point_range(Section::BetaG2, 0).end,
+            layout.file_size() - PUBLIC_KEY_SIZE
+        );
+        assert_eq!(
+            Layout::challenge(4).accumulator_size(),
+            2 * layout.accumulator_size()
+        );
+        let layout = Layout::response_on::<Bls12_381>(4);
+        assert_eq!(layout.offset(Section::TauG2), HASH_SIZE + 7 * 48);
+        assert_eq!(
+            layout.file_size(),
+            HASH_SIZE + (7 + 4 + 4) * 48 + (4 + 1) * 96 + public_key_size(48)
+        );
+        let ranges = layout.prefix_ranges(2);
+        assert_eq!(
+            ranges.iter().map(ExactSizeIterator::len).sum::<usize>(),
+            Layout::response_on::<Bls12_381>(2).file_size()
+        );
+        assert_eq!(
+            ranges[2],
+            layout.offset(Section::TauG2)..layout.point_range(Section::TauG2, 2).start
+        );
+        assert_eq!(ranges.last().unwrap().end, layout.file_size());
+    }
+}
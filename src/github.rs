@@ -0,0 +1,556 @@
+//! GitHub Contribution Discovery and Report Publishing
+//!
+//! The contributions to the ceremony are discovered from the `perpetualpowersoftau` repository,
+//! along with the attestations in which their participants published the hash of their response
+//! file, see [`list_attestations`], and the PGP signatures of those attestations are checked with
+//! [`pgp`](crate::pgp). Verification reports are published back to GitHub, as the assets of a release of the
+//! repository of the verifier or as a gist, so that third-party verifications are easy to share.
+
+use crate::{
+    azure::{sort_blobs, Blob},
+    hash::Hash64,
+    pgp::{check_attestation, PublicKey, SignatureCheck},
+    Result,
+};
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE, USER_AGENT},
+    Client, Method, RequestBuilder, StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+/// GitHub API endpoint
+pub const API_URL: &str = "https://api.github.com";
+
+/// GitHub contents API endpoint for the root of the `perpetualpowersoftau` repository
+pub const CONTENTS_URL: &str =
+    "https://api.github.com/repos/weijiekoh/perpetualpowersoftau/contents";
+
+/// Environment variable from which the GitHub API token is read if none is given explicitly
+pub const TOKEN_VARIABLE: &str = "GITHUB_TOKEN";
+
+/// Contribution
+///
+/// Every contribution to the ceremony is recorded as a folder named `NNNN_participant_response` at
+/// the root of the `perpetualpowersoftau` repository.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Contribution {
+    /// Ceremony number of the contribution, starting at `1`
+    pub number: usize,
+
+    /// Name of the participant
+    pub participant: String,
+}
+
+impl Contribution {
+    /// Parses a [`Contribution`] from a folder name of the form `NNNN_participant_response`.
+    #[inline]
+    pub fn from_folder_name(name: &str) -> Option<Self> {
+        let mut parts = name.splitn(3, '_');
+        let number = parts.next()?;
+        if number.len() != 4 {
+            return None;
+        }
+        let number = number.parse().ok().filter(|n| *n > 0)?;
+        let participant = parts.next().filter(|p| !p.is_empty())?;
+        Some(Self {
+            number,
+            participant: participant.into(),
+        })
+    }
+
+    /// Returns the conventional URL of the response file of `self` inside of `container_url`.
+    #[inline]
+    pub fn response_url(&self, container_url: &str) -> String {
+        format!(
+            "{}/response_{:04}_{}",
+            container_url.trim_end_matches('/'),
+            self.number,
+            self.participant
+        )
+    }
+}
+
+/// Largest file of a contribution folder read as part of its attestation
+pub const MAX_ATTESTATION_SIZE: u64 = 64 * 1024;
+
+/// Entry of the GitHub contents API listing
+#[derive(Deserialize)]
+struct ContentEntry {
+    /// File or folder name
+    name: String,
+
+    /// Entry type, one of `file`, `dir`, `symlink` or `submodule`
+    #[serde(rename = "type")]
+    kind: String,
+
+    /// Size of the file in bytes
+    #[serde(default)]
+    size: u64,
+
+    /// Raw download URL of the file
+    #[serde(default)]
+    download_url: Option<String>,
+}
+
+/// Returns the GitHub API token, either `token` or the value of the [`TOKEN_VARIABLE`] environment
+/// variable.
+#[inline]
+pub fn api_token(token: Option<String>) -> Option<String> {
+    token.or_else(|| std::env::var(TOKEN_VARIABLE).ok())
+}
+
+/// Lists the entries of the folder of the `perpetualpowersoftau` repository at `url`.
+#[inline]
+async fn list_entries(
+    client: &Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<Vec<ContentEntry>> {
+    let mut request = client
+        .request(Method::GET, url)
+        .header(USER_AGENT, "ppot-verifier");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    Ok(request
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<ContentEntry>>()
+        .await?)
+}
+
+/// Lists all contributions recorded in the `perpetualpowersoftau` repository by querying the GitHub
+/// contents API at [`CONTENTS_URL`], sorted by ceremony number. Passing a `token` raises the rate
+/// limit of the API from 60 to 5000 requests per hour.
+#[inline]
+pub async fn list_contributions(client: &Client, token: Option<&str>) -> Result<Vec<Contribution>> {
+    let entries = list_entries(client, CONTENTS_URL, token).await?;
+    let mut contributions = entries
+        .into_iter()
+        .filter(|entry| entry.kind == "dir")
+        .filter_map(|entry| Contribution::from_folder_name(&entry.name))
+        .collect::<Vec<_>>();
+    contributions.sort();
+    contributions.dedup_by_key(|c| c.number);
+    Ok(contributions)
+}
+
+/// Returns the hashes written in `text`, either as 128 hex digits in a row, as printed by `b2sum`,
+/// or split into groups of hex digits, as printed by the contribution tools in lines of four
+/// groups of eight digits. Groups of other lengths break a hash, so that words which happen to be
+/// made of hex digits are not mistaken for part of one.
+#[inline]
+pub fn attested_hashes(text: &str) -> Vec<Hash64> {
+    let mut hashes = Vec::new();
+    let mut digits = String::new();
+    for word in text.split(|c: char| c.is_whitespace() || matches!(c, '`' | ',' | ':' | '|')) {
+        let word = word.strip_prefix("0x").unwrap_or(word);
+        if word.is_empty() {
+            continue;
+        }
+        if matches!(word.len(), 8 | 16 | 32 | 64 | 128)
+            && word.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            digits.push_str(word);
+            if digits.len() == 128 {
+                if let Ok(hash) = digits.parse() {
+                    hashes.push(hash);
+                }
+                digits.clear();
+            } else if digits.len() > 128 {
+                digits.clear();
+            }
+        } else {
+            digits.clear();
+        }
+    }
+    hashes
+}
+
+/// Attestation of a Contribution
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Attestation {
+    /// Attested contribution
+    pub contribution: Contribution,
+
+    /// Hashes published in the folder of the contribution, among which the hash of its response
+    /// file and usually the hash of the challenge file it started from
+    pub hashes: Vec<Hash64>,
+
+    /// Check of the PGP signatures of the files of the contribution folder
+    pub signature: SignatureCheck,
+}
+
+/// Comparison of an Attestation with the Local Hash
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AttestationStatus {
+    /// The local hash of the response file is among the published hashes
+    Match,
+
+    /// The published hashes do not include the local hash of the response file
+    Mismatch,
+
+    /// No hash is published for the contribution
+    Unpublished,
+
+    /// The response file is not hashed locally yet
+    Unhashed,
+}
+
+impl Attestation {
+    /// Compares the published hashes with the `local` hash of the response file of the
+    /// contribution.
+    #[inline]
+    pub fn check(&self, local: Option<&Hash64>) -> AttestationStatus {
+        match local {
+            _ if self.hashes.is_empty() => AttestationStatus::Unpublished,
+            Some(local) if self.hashes.contains(local) => AttestationStatus::Match,
+            Some(_) => AttestationStatus::Mismatch,
+            _ => AttestationStatus::Unhashed,
+        }
+    }
+}
+
+/// Lists the attestations of all the contributions recorded in the `perpetualpowersoftau`
+/// repository, reading the hashes published in the small files of each contribution folder, such
+/// as its `README.md`, sorted by ceremony number. The PGP signatures of these files are checked
+/// against the keys published in the folder and the keys of `keyring`. This takes a request per
+/// contribution, so a `token` is needed to stay under the rate limit of the API.
+#[inline]
+pub async fn list_attestations(
+    client: &Client,
+    token: Option<&str>,
+    keyring: &[PublicKey],
+) -> Result<Vec<Attestation>> {
+    let mut attestations = Vec::new();
+    for entry in list_entries(client, CONTENTS_URL, token).await? {
+        let contribution = match Contribution::from_folder_name(&entry.name) {
+            Some(contribution) if entry.kind == "dir" => contribution,
+            _ => continue,
+        };
+        let folder_url = format!("{}/{}", CONTENTS_URL, entry.name);
+        let mut hashes = Vec::new();
+        let mut files = Vec::new();
+        for file in list_entries(client, &folder_url, token).await? {
+            let url = match file.download_url {
+                Some(url) if file.kind == "file" && file.size <= MAX_ATTESTATION_SIZE => url,
+                _ => continue,
+            };
+            let contents = client
+                .get(url)
+                .header(USER_AGENT, "ppot-verifier")
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            for hash in attested_hashes(&String::from_utf8_lossy(&contents)) {
+                if !hashes.contains(&hash) {
+                    hashes.push(hash);
+                }
+            }
+            files.push((file.name, contents.to_vec()));
+        }
+        attestations.push(Attestation {
+            contribution,
+            hashes,
+            signature: check_attestation(&files, keyring),
+        });
+    }
+    attestations.sort_by_key(|attestation| attestation.contribution.number);
+    attestations.dedup_by_key(|attestation| attestation.contribution.number);
+    Ok(attestations)
+}
+
+/// Builds the `challenge` and `response` URLs for `contributions`. Whenever `blobs` contains a file
+/// with the same ceremony number its exact name is used, which takes care of the files that do not
+/// follow the naming convention (such as `challenge_initial` or `response_0016_aurel`), otherwise
+/// the conventional name is assumed. There is one more challenge than there are contributions, since
+/// the last response is turned into a challenge for the next participant.
+#[inline]
+pub fn contribution_urls(
+    contributions: &[Contribution],
+    blobs: Vec<Blob>,
+    container_url: &str,
+) -> (Vec<String>, Vec<String>) {
+    let container_url = container_url.trim_end_matches('/');
+    let (challenges, responses) = sort_blobs(blobs);
+    let find = |blobs: &[Blob], number: usize| {
+        blobs
+            .iter()
+            .find(|b| b.parse_name().map(|(_, n)| n) == Some(number))
+            .map(|b| b.url(container_url))
+    };
+    let mut challenge_urls = vec![
+        find(&challenges, 1).unwrap_or_else(|| format!("{}/challenge_initial", container_url))
+    ];
+    let mut response_urls = Vec::with_capacity(contributions.len());
+    for contribution in contributions {
+        let number = contribution.number;
+        challenge_urls.push(
+            find(&challenges, number + 1)
+                .unwrap_or_else(|| format!("{}/challenge_{:04}", container_url, number + 1)),
+        );
+        response_urls.push(
+            find(&responses, number).unwrap_or_else(|| contribution.response_url(container_url)),
+        );
+    }
+    (challenge_urls, response_urls)
+}
+
+/// File Published to GitHub
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PublishedFile {
+    /// Name of the file
+    pub name: String,
+
+    /// Contents of the file
+    pub contents: String,
+}
+
+impl PublishedFile {
+    /// Builds the file `name` holding `contents`.
+    #[inline]
+    pub fn new<N, C>(name: N, contents: C) -> Self
+    where
+        N: Into<String>,
+        C: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            contents: contents.into(),
+        }
+    }
+
+    /// Returns the media type of the file, from the extension of its name.
+    #[inline]
+    pub fn media_type(&self) -> &'static str {
+        match self.name.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("json") => "application/json",
+            Some("md") => "text/markdown",
+            _ => "text/plain",
+        }
+    }
+}
+
+/// Builds a request to the GitHub API authenticated with `token`.
+#[inline]
+fn api_request(client: &Client, method: Method, url: &str, token: &str) -> RequestBuilder {
+    client
+        .request(method, url)
+        .header(USER_AGENT, "ppot-verifier")
+        .header(ACCEPT, "application/vnd.github+json")
+        .bearer_auth(token)
+}
+
+/// Created Gist
+#[derive(Deserialize)]
+struct Gist {
+    /// Page of the gist
+    html_url: String,
+}
+
+/// Publishes `files` as a new gist described by `description`, listed on the profile of the owner
+/// of `token` if `public`, returning the URL of the gist.
+#[inline]
+pub async fn create_gist(
+    client: &Client,
+    token: &str,
+    description: &str,
+    files: &[PublishedFile],
+    public: bool,
+) -> Result<String> {
+    let files = files
+        .iter()
+        .map(|file| (file.name.clone(), json!({ "content": file.contents })))
+        .collect::<serde_json::Map<_, _>>();
+    let gist = api_request(client, Method::POST, &format!("{}/gists", API_URL), token)
+        .json(&json!({
+            "description": description,
+            "public": public,
+            "files": files,
+        }))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Gist>()
+        .await?;
+    Ok(gist.html_url)
+}
+
+/// Asset of a Release
+#[derive(Deserialize)]
+struct Asset {
+    /// Identifier of the asset
+    id: u64,
+
+    /// File name of the asset
+    name: String,
+}
+
+/// Release
+#[derive(Deserialize)]
+struct Release {
+    /// Page of the release
+    html_url: String,
+
+    /// Upload URL template of the assets, such as `https://uploads.github.com/.../assets{?name,label}`
+    upload_url: String,
+
+    /// Assets already uploaded
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+/// Uploads `files` as the assets of the release `tag` of `repository`, given as `owner/name`,
+/// creating the release if needed and replacing the assets of the same name. Returns the URL of
+/// the release.
+#[inline]
+pub async fn upload_release_assets(
+    client: &Client,
+    token: &str,
+    repository: &str,
+    tag: &str,
+    files: &[PublishedFile],
+) -> Result<String> {
+    let releases = format!("{}/repos/{}/releases", API_URL, repository);
+    let response = api_request(
+        client,
+        Method::GET,
+        &format!("{}/tags/{}", releases, tag),
+        token,
+    )
+    .send()
+    .await?;
+    let release = if response.status() == StatusCode::NOT_FOUND {
+        api_request(client, Method::POST, &releases, token)
+            .json(&json!({ "tag_name": tag, "name": tag }))
+            .send()
+            .await?
+    } else {
+        response
+    }
+    .error_for_status()?
+    .json::<Release>()
+    .await?;
+    let upload_url = release
+        .upload_url
+        .split_once('{')
+        .map_or(release.upload_url.as_str(), |(url, _)| url);
+    for file in files {
+        if let Some(asset) = release.assets.iter().find(|asset| asset.name == file.name) {
+            api_request(
+                client,
+                Method::DELETE,
+                &format!("{}/assets/{}", releases, asset.id),
+                token,
+            )
+            .send()
+            .await?
+            .error_for_status()?;
+        }
+        api_request(client, Method::POST, upload_url, token)
+            .query(&[("name", &file.name)])
+            .header(CONTENT_TYPE, file.media_type())
+            .body(file.contents.clone())
+            .send()
+            .await?
+            .error_for_status()?;
+    }
+    Ok(release.html_url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that contribution folders are parsed and other entries are rejected.
+    #[test]
+    fn parse_folder_names() {
+        assert_eq!(
+            Contribution::from_folder_name("0016_aurel_response"),
+            Some(Contribution {
+                number: 16,
+                participant: "aurel".into()
+            })
+        );
+        assert_eq!(Contribution::from_folder_name("0000_initial"), None);
+        assert_eq!(Contribution::from_folder_name("README.md"), None);
+    }
+
+    /// Checks that hashes are found in both their compact and pretty forms, and that words made of
+    /// hex digits do not get in the way.
+    #[test]
+    fn find_attested_hashes() {
+        let (challenge, response) = (
+            Hash64(core::array::from_fn(|i| i as u8)),
+            Hash64(core::array::from_fn(|i| 255 - i as u8)),
+        );
+        let text = format!(
+            "The BLAKE2b hash of `./challenge` is:\n{}\n\nbeef cafe decade facade\n\
+             The BLAKE2b hash of `./response` is {}\n",
+            challenge.pretty(),
+            response
+        );
+        assert_eq!(attested_hashes(&text), [challenge, response]);
+        let attestation = Attestation {
+            contribution: Contribution {
+                number: 1,
+                participant: "weijie".into(),
+            },
+            hashes: vec![challenge, response],
+            signature: SignatureCheck::UNSIGNED,
+        };
+        assert_eq!(attestation.check(Some(&response)), AttestationStatus::Match);
+        assert_eq!(
+            attestation.check(Some(&Hash64([0; 64]))),
+            AttestationStatus::Mismatch
+        );
+        assert_eq!(attestation.check(None), AttestationStatus::Unhashed);
+    }
+
+    /// Checks that blob names take precedence over the naming convention.
+    #[test]
+    fn irregular_names_come_from_blobs() {
+        let container_url = "https://ppot.blob.core.windows.net/public";
+        let contributions = [
+            Contribution {
+                number: 1,
+                participant: "weijie".into(),
+            },
+            Contribution {
+                number: 2,
+                participant: "kobi".into(),
+            },
+        ];
+        let blobs = [
+            "challenge_initial",
+            "challenge_0002_kobi",
+            "response_0002_kobi_2",
+        ]
+        .into_iter()
+        .map(|name| Blob {
+            name: name.into(),
+            ..Default::default()
+        })
+        .collect();
+        let (challenges, responses) = contribution_urls(&contributions, blobs, container_url);
+        assert_eq!(
+            challenges,
+            [
+                format!("{}/challenge_initial", container_url),
+                format!("{}/challenge_0002_kobi", container_url),
+                format!("{}/challenge_0003", container_url),
+            ]
+        );
+        assert_eq!(
+            responses,
+            [
+                format!("{}/response_0001_weijie", container_url),
+                format!("{}/response_0002_kobi_2", container_url),
+            ]
+        );
+    }
+}
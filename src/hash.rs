@@ -0,0 +1,209 @@
+//! Blake2b Hashes
+
+use crate::{atomic, Result};
+use anyhow::{anyhow, bail};
+use core::{fmt, str::FromStr};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::Path,
+};
+
+/// 64-Byte Hash
+///
+/// Blake2b hash of a ceremony file, as found in the header of the next file of the ceremony and in
+/// the `*_hash` files written by the `hasher` binary. It is displayed and serialized as a hex
+/// string, and [`pretty`](Self::pretty) formats it in lines of 16 bytes split into groups of 4.
+#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Hash64(pub [u8; 64]);
+
+impl Hash64 {
+    /// Length of the hash in bytes
+    pub const LEN: usize = 64;
+
+    /// Reads the hash from the first [`LEN`](Self::LEN) bytes of `bytes`, returning `None` if
+    /// `bytes` is too short.
+    #[inline]
+    pub fn from_header(bytes: &[u8]) -> Option<Self> {
+        Some(Self(bytes.get(..Self::LEN)?.try_into().ok()?))
+    }
+
+    /// Reads the hash stored in the header of the ceremony file at `path`.
+    #[inline]
+    pub fn read_header<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut hash = [0; Self::LEN];
+        File::open(path)?.read_exact(&mut hash)?;
+        Ok(Self(hash))
+    }
+
+    /// Loads the hash saved in the hash file at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        Self::try_from(bytes.as_slice())
+            .map_err(|_| anyhow!("{} does not hold a 64-byte hash.", path.display()))
+    }
+
+    /// Saves the hash to the hash file at `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, self.0)
+    }
+
+    /// Returns a [`Display`](fmt::Display) implementation printing the hash in four indented lines
+    /// of four groups of four bytes.
+    #[inline]
+    pub fn pretty(&self) -> Pretty<'_> {
+        Pretty(self)
+    }
+}
+
+impl Default for Hash64 {
+    #[inline]
+    fn default() -> Self {
+        Self([0; Self::LEN])
+    }
+}
+
+impl AsRef<[u8]> for Hash64 {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 64]> for Hash64 {
+    #[inline]
+    fn from(hash: [u8; 64]) -> Self {
+        Self(hash)
+    }
+}
+
+impl From<Hash64> for [u8; 64] {
+    #[inline]
+    fn from(hash: Hash64) -> Self {
+        hash.0
+    }
+}
+
+impl TryFrom<&[u8]> for Hash64 {
+    type Error = core::array::TryFromSliceError;
+
+    #[inline]
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self(bytes.try_into()?))
+    }
+}
+
+impl fmt::Display for Hash64 {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Hash64 {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Hash64({})", self)
+    }
+}
+
+impl FromStr for Hash64 {
+    type Err = anyhow::Error;
+
+    /// Parses a hex-encoded hash, ignoring whitespace so that the [`pretty`](Hash64::pretty) form
+    /// is accepted as well.
+    #[inline]
+    fn from_str(hash: &str) -> Result<Self, Self::Err> {
+        let hex = hash
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>();
+        let bytes = hex::decode(&hex).map_err(|err| anyhow!("Invalid hash '{}': {}", hash, err))?;
+        match Self::try_from(bytes.as_slice()) {
+            Ok(hash) => Ok(hash),
+            _ => bail!("Expected 64 bytes but '{}' has {}.", hash, bytes.len()),
+        }
+    }
+}
+
+impl Serialize for Hash64 {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash64 {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Pretty-Printed Hash, see [`Hash64::pretty`]
+#[derive(Clone, Copy, Debug)]
+pub struct Pretty<'h>(&'h Hash64);
+
+impl fmt::Display for Pretty<'_> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, line) in self.0 .0.chunks(16).enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "\t")?;
+            for (j, section) in line.chunks(4).enumerate() {
+                if j > 0 {
+                    write!(f, " ")?;
+                }
+                for byte in section {
+                    write!(f, "{:02x}", byte)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that both the compact and the pretty form parse back into the same hash.
+    #[test]
+    fn hex_round_trip() {
+        let hash = Hash64(core::array::from_fn(|i| i as u8));
+        let pretty = hash.pretty().to_string();
+        assert!(pretty.starts_with("\t00010203 04050607 08090a0b 0c0d0e0f\n\t10111213"));
+        assert_eq!(pretty.parse::<Hash64>().unwrap(), hash);
+        assert_eq!(hash.to_string().parse::<Hash64>().unwrap(), hash);
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, format!("\"{}\"", hash));
+        assert_eq!(serde_json::from_str::<Hash64>(&json).unwrap(), hash);
+        assert!("abcd".parse::<Hash64>().is_err());
+    }
+}
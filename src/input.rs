@@ -0,0 +1,563 @@
+//! Input Files
+//!
+//! The ceremony files are read through memory maps, which let the operating system page them in
+//! as needed. Mapping fails in containers with low virtual memory limits and on some network
+//! filesystems, in which case the files are read as buffered streams instead, see [`ReadMode`].
+//!
+//! Reading a whole ceremony file through the page cache evicts everything else cached on the
+//! machine. On shared servers, the [`Advice`] given to the operating system lets it drop the pages
+//! behind the reader, and [`ReadMode::Direct`] bypasses the page cache altogether.
+//!
+//! Files are hashed by chunks sized after the memory available on the machine unless
+//! [`InputOptions::hash_chunk_size`] is set: large chunks suit fast local disks while archive
+//! servers reading from spinning disks do better with smaller ones.
+//!
+//! A file which is missing but has a [`compressed_path`] is read through its compressed copy,
+//! decompressed one frame at a time when it is hashed and into memory when it is mapped.
+
+use crate::{
+    calculate_hashes_streaming_with, calculate_hashes_with,
+    compress::{compressed_path, SeekableFile},
+    memory::{available_memory, hash_chunk_size, ByteSize},
+    HashAlgorithm, Result,
+};
+use indicatif::ProgressBar;
+use memmap::{Mmap, MmapMut};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// Read Mode
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ReadMode {
+    /// Memory map the files, streaming the ones which cannot be mapped
+    #[default]
+    Auto,
+
+    /// Always memory map the files
+    Mmap,
+
+    /// Always stream the files through buffered reads
+    Stream,
+
+    /// Stream the files bypassing the page cache, with `O_DIRECT` on Linux, `F_NOCACHE` on macOS
+    /// and `FILE_FLAG_NO_BUFFERING` on Windows
+    Direct,
+}
+
+/// Page Cache Advice
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Advice {
+    /// Let the operating system manage the page cache
+    #[default]
+    Normal,
+
+    /// Read ahead aggressively since the files are read from start to end
+    Sequential,
+
+    /// Read ahead and evict the pages from the page cache once they are hashed
+    DropBehind,
+}
+
+/// Input Options
+///
+/// Shared by the command line interfaces of the binaries reading the ceremony files.
+#[derive(clap::Args, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct InputOptions {
+    /// How the ceremony files are read
+    #[clap(long, global = true, value_enum, default_value = "auto")]
+    pub read_mode: ReadMode,
+
+    /// How the ceremony files are kept in the page cache, ignored by direct reads
+    #[clap(long, global = true, value_enum, default_value = "normal")]
+    pub advice: Advice,
+
+    /// Size of the chunks the ceremony files are hashed by, like `64MiB`, rounded down to a
+    /// multiple of the page size. Defaults to a share of the available memory.
+    #[clap(long, global = true)]
+    pub hash_chunk_size: Option<ByteSize>,
+}
+
+impl InputOptions {
+    /// Opens the file at `path` with the [`ReadMode`], the [`Advice`] and the chunk size of
+    /// `self`.
+    #[inline]
+    pub fn open<P>(&self, path: P) -> Result<Input>
+    where
+        P: AsRef<Path>,
+    {
+        let input = Input::open(path, self.read_mode)?.with_advice(self.advice);
+        Ok(match self.hash_chunk_size {
+            Some(size) => input.with_chunk_size(size.0 as usize),
+            _ => input,
+        })
+    }
+}
+
+/// Alignment of the chunks read from the files, a multiple of the block size of the disks as
+/// required for direct reads
+pub const PAGE_SIZE: usize = 1 << 12;
+
+/// Input File
+pub struct Input {
+    /// Path of the file
+    path: PathBuf,
+
+    /// Open file
+    file: File,
+
+    /// Length of the file in bytes
+    len: u64,
+
+    /// Memory map of the file, if it is not streamed
+    map: Option<Mmap>,
+
+    /// `true` if the file bypasses the page cache
+    direct: bool,
+
+    /// Advice given to the operating system
+    advice: Advice,
+
+    /// Size of the chunks the file is hashed by
+    chunk_size: usize,
+
+    /// Compressed copy the file is read through, if the file itself is missing
+    compressed: Option<SeekableFile>,
+}
+
+impl Input {
+    /// Opens the file at `path` with the given read `mode`, or its compressed copy if it is
+    /// missing, which is always mapped.
+    #[inline]
+    pub fn open<P>(path: P, mode: ReadMode) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let compressed = compressed_path(path);
+        if !path.exists() && compressed.exists() {
+            let file = File::open(&compressed)?;
+            let seekable = SeekableFile::open(&compressed)?;
+            return Ok(Self {
+                path: path.into(),
+                file,
+                len: seekable.len(),
+                map: None,
+                direct: false,
+                advice: Advice::Normal,
+                chunk_size: hash_chunk_size(available_memory()),
+                compressed: Some(seekable),
+            });
+        }
+        let (file, direct) = match mode {
+            ReadMode::Direct => match open_direct(path) {
+                Ok(file) => (file, true),
+                Err(err) => {
+                    warn!(
+                        "Unable to open {} for direct reads: {}. Streaming it instead.",
+                        path.display(),
+                        err
+                    );
+                    (File::open(path)?, false)
+                }
+            },
+            _ => (File::open(path)?, false),
+        };
+        let len = file.metadata()?.len();
+        let map = match mode {
+            ReadMode::Auto | ReadMode::Mmap => match unsafe { Mmap::map(&file) } {
+                Ok(map) => Some(map),
+                Err(err) if mode == ReadMode::Mmap => return Err(err.into()),
+                Err(err) => {
+                    warn!(
+                        "Unable to map {}: {}. Streaming it instead.",
+                        path.display(),
+                        err
+                    );
+                    None
+                }
+            },
+            _ => None,
+        };
+        Ok(Self {
+            path: path.into(),
+            file,
+            len,
+            map,
+            direct,
+            advice: Advice::Normal,
+            chunk_size: hash_chunk_size(available_memory()),
+            compressed: None,
+        })
+    }
+
+    /// Gives `advice` to the operating system about how the file is going to be read.
+    #[inline]
+    pub fn with_advice(mut self, advice: Advice) -> Self {
+        if !self.direct && advice != Advice::Normal {
+            match &self.map {
+                Some(map) => sys::advise_sequential(map),
+                _ => sys::fadvise_sequential(&self.file),
+            }
+        }
+        self.advice = advice;
+        self
+    }
+
+    /// Hashes the file by chunks of `chunk_size` bytes, rounded down to a multiple of the page
+    /// size so that direct reads stay aligned.
+    #[inline]
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = (chunk_size & !(PAGE_SIZE - 1)).max(PAGE_SIZE);
+        self
+    }
+
+    /// Returns the size of the chunks the file is hashed by.
+    #[inline]
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns `true` if the file is memory mapped.
+    #[inline]
+    pub fn is_mapped(&self) -> bool {
+        self.map.is_some()
+    }
+
+    /// Returns `true` if the file is read through its compressed copy.
+    #[inline]
+    pub fn is_compressed(&self) -> bool {
+        self.compressed.is_some()
+    }
+
+    /// Returns the length of the file in bytes.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Computes the hashes of the file with each of the `algorithms` in a single pass, see
+    /// [`calculate_hashes`](crate::calculate_hashes). Returns `None` if `cancel` is cancelled before
+    /// the end of the file.
+    #[inline]
+    pub fn hashes(
+        &self,
+        algorithms: &[HashAlgorithm],
+        progress: Option<&ProgressBar>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<Option<Vec<Vec<u8>>>> {
+        self.hashes_with(algorithms, progress, cancel, |_, _| {})
+    }
+
+    /// Computes the hashes of the file like [`hashes`](Self::hashes), calling `hashed` with the
+    /// offset and the bytes of each chunk once it has been hashed, before they are dropped from
+    /// the page cache.
+    #[inline]
+    pub fn hashes_with<F>(
+        &self,
+        algorithms: &[HashAlgorithm],
+        progress: Option<&ProgressBar>,
+        cancel: Option<&CancellationToken>,
+        mut hashed: F,
+    ) -> Result<Option<Vec<Vec<u8>>>>
+    where
+        F: FnMut(u64, &[u8]),
+    {
+        if let Some(compressed) = &self.compressed {
+            return Ok(calculate_hashes_streaming_with(
+                compressed.reader(),
+                algorithms,
+                progress,
+                cancel,
+                self.chunk_size,
+                hashed,
+            )?);
+        }
+        let drop_behind = !self.direct && self.advice == Advice::DropBehind;
+        match &self.map {
+            Some(map) => Ok(calculate_hashes_with(
+                map,
+                algorithms,
+                progress,
+                cancel,
+                self.chunk_size,
+                |offset, chunk| {
+                    hashed(offset, chunk);
+                    if drop_behind {
+                        sys::drop_mapped(map, &self.file, offset, chunk.len());
+                    }
+                },
+            )),
+            _ => {
+                let mut file = &self.file;
+                file.seek(SeekFrom::Start(0))?;
+                let reader = DirectReader {
+                    file,
+                    remaining: self.len,
+                };
+                Ok(calculate_hashes_streaming_with(
+                    reader,
+                    algorithms,
+                    progress,
+                    cancel,
+                    self.chunk_size,
+                    |offset, chunk| {
+                        hashed(offset, chunk);
+                        if drop_behind {
+                            sys::drop_cached(&self.file, offset, chunk.len());
+                        }
+                    },
+                )?)
+            }
+        }
+    }
+
+    /// Converts `self` into a memory map for the readers which only accept one. Streamed files are
+    /// copied into an anonymous map, backed by memory and swap rather than by the file, and
+    /// compressed files are decompressed into one.
+    #[inline]
+    pub fn into_mmap(self) -> Result<Mmap> {
+        if let Some(map) = self.map {
+            return Ok(map);
+        }
+        if let Some(compressed) = &self.compressed {
+            let mut map = MmapMut::map_anon(self.len as usize)?;
+            compressed.decompress_into(&mut map)?;
+            return Ok(map.make_read_only()?);
+        }
+        // Direct reads need aligned lengths, so the file is read again through the page cache.
+        let mut file = match self.direct {
+            true => File::open(&self.path)?,
+            _ => self.file,
+        };
+        let mut map = MmapMut::map_anon(self.len as usize)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.read_exact(&mut map)?;
+        Ok(map.make_read_only()?)
+    }
+}
+
+/// Reader stopping at the known length of the file, so that direct reads never request the
+/// unaligned range past its end.
+struct DirectReader<'f> {
+    /// Underlying File
+    file: &'f File,
+
+    /// Number of bytes left until the end of the file
+    remaining: u64,
+}
+
+impl Read for DirectReader<'_> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let read = self.file.read(buf)?;
+        self.remaining = self.remaining.saturating_sub(read as u64);
+        Ok(read)
+    }
+}
+
+/// Opens the file at `path` for reads bypassing the page cache.
+#[inline]
+fn open_direct(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true);
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_DIRECT);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_FLAG_NO_BUFFERING: u32 = 0x2000_0000;
+        options.custom_flags(FILE_FLAG_NO_BUFFERING);
+    }
+    let file = options.open(path)?;
+    #[cfg(target_os = "macos")]
+    {
+        use std::os::unix::io::AsRawFd;
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_NOCACHE, 1) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(file)
+}
+
+/// Page Cache Advice System Calls
+///
+/// The advice is only a hint, so failures are ignored and platforms without the system calls do
+/// nothing.
+#[cfg(unix)]
+mod sys {
+    use super::*;
+    use core::ffi::c_void;
+    use std::os::unix::io::AsRawFd;
+
+    /// Advises the kernel that `map` is read sequentially.
+    #[inline]
+    pub fn advise_sequential(map: &Mmap) {
+        unsafe {
+            libc::madvise(
+                map.as_ptr() as *mut c_void,
+                map.len(),
+                libc::MADV_SEQUENTIAL,
+            );
+        }
+    }
+
+    /// Advises the kernel that `file` is read sequentially.
+    #[inline]
+    pub fn fadvise_sequential(file: &File) {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_SEQUENTIAL);
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = file;
+    }
+
+    /// Releases the `len` bytes of `map` at `offset`, mapping `file`, and evicts them from the page
+    /// cache.
+    #[inline]
+    pub fn drop_mapped(map: &Mmap, file: &File, offset: u64, len: usize) {
+        unsafe {
+            libc::madvise(
+                map.as_ptr().add(offset as usize) as *mut c_void,
+                len,
+                libc::MADV_DONTNEED,
+            );
+        }
+        drop_cached(file, offset, len);
+    }
+
+    /// Evicts the `len` bytes of `file` at `offset` from the page cache.
+    #[inline]
+    pub fn drop_cached(file: &File, offset: u64, len: usize) {
+        #[cfg(target_os = "linux")]
+        unsafe {
+            libc::posix_fadvise(
+                file.as_raw_fd(),
+                offset as libc::off_t,
+                len as libc::off_t,
+                libc::POSIX_FADV_DONTNEED,
+            );
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = (file, offset, len);
+    }
+}
+
+/// Page Cache Advice System Calls
+///
+/// The advice is only a hint, so platforms without the system calls do nothing.
+#[cfg(not(unix))]
+mod sys {
+    use super::*;
+
+    /// Advises the kernel that `map` is read sequentially.
+    #[inline]
+    pub fn advise_sequential(_: &Mmap) {}
+
+    /// Advises the kernel that `file` is read sequentially.
+    #[inline]
+    pub fn fadvise_sequential(_: &File) {}
+
+    /// Releases the `len` bytes of `map` at `offset`, mapping `file`, and evicts them from the page
+    /// cache.
+    #[inline]
+    pub fn drop_mapped(_: &Mmap, _: &File, _: u64, _: usize) {}
+
+    /// Evicts the `len` bytes of `file` at `offset` from the page cache.
+    #[inline]
+    pub fn drop_cached(_: &File, _: u64, _: usize) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that every read mode and advice, and the compressed copy of the file, read the same
+    /// contents and hashes.
+    #[test]
+    fn read_modes_agree() {
+        let path = std::env::temp_dir().join("ppot-verifier-input-test");
+        std::fs::write(&path, b"contents").unwrap();
+        let mapped = Input::open(&path, ReadMode::Mmap).unwrap();
+        assert!(mapped.is_mapped());
+        let algorithms = [HashAlgorithm::Blake2b, HashAlgorithm::Sha256];
+        let expected = mapped.hashes(&algorithms, None, None).unwrap();
+        for read_mode in [ReadMode::Auto, ReadMode::Stream, ReadMode::Direct] {
+            for advice in [Advice::Normal, Advice::Sequential, Advice::DropBehind] {
+                let input = InputOptions {
+                    read_mode,
+                    advice,
+                    ..Default::default()
+                }
+                .open(&path)
+                .unwrap();
+                assert_eq!(input.len(), 8);
+                assert_eq!(input.hashes(&algorithms, None, None).unwrap(), expected);
+                assert_eq!(&*input.into_mmap().unwrap(), b"contents");
+            }
+        }
+        let mut compressed = Vec::new();
+        crate::compress::compress(b"contents", 3, 3, &mut compressed).unwrap();
+        std::fs::write(compressed_path(&path), compressed).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let input = Input::open(&path, ReadMode::Direct).unwrap();
+        assert!(input.is_compressed());
+        assert_eq!(input.len(), 8);
+        assert_eq!(input.hashes(&algorithms, None, None).unwrap(), expected);
+        assert_eq!(&*input.into_mmap().unwrap(), b"contents");
+        std::fs::remove_file(compressed_path(&path)).unwrap();
+    }
+
+    /// Checks that files are hashed by chunks of the configured size, rounded down to the page
+    /// size, whether they are mapped or streamed.
+    #[test]
+    fn chunk_sizes_apply() {
+        let path = std::env::temp_dir().join("ppot-verifier-input-chunks-test");
+        let contents = (0..3 * PAGE_SIZE + 5)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>();
+        std::fs::write(&path, &contents).unwrap();
+        let algorithms = [HashAlgorithm::Blake2b];
+        for read_mode in [ReadMode::Mmap, ReadMode::Stream] {
+            let input = InputOptions {
+                read_mode,
+                hash_chunk_size: Some(ByteSize(PAGE_SIZE as u64 + 100)),
+                ..Default::default()
+            }
+            .open(&path)
+            .unwrap();
+            assert_eq!(input.chunk_size(), PAGE_SIZE);
+            let mut chunks = Vec::new();
+            let hashes = input
+                .hashes_with(&algorithms, None, None, |offset, chunk| {
+                    chunks.push((offset, chunk.len()))
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                hashes[0],
+                crate::calculate_hash(&contents, HashAlgorithm::Blake2b)
+            );
+            assert_eq!(chunks.len(), 4);
+            assert_eq!(chunks[3], (3 * PAGE_SIZE as u64, 5));
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,272 @@
+//! IPFS Publishing
+//!
+//! Once the files are hashed, their hash transcript is pinned to an IPFS node so that what was
+//! verified stays on a content-addressed public record. The transcript of each round, the `b2sum`
+//! lines of its files, fits in a single raw block, so its CID is computed locally by [`raw_cid`]
+//! and matches the CID given by the node when adding it with raw leaves. The files themselves are
+//! split by the node into a UnixFS DAG, so their CIDs come from the node. The CIDs are recorded in
+//! an [`IpfsManifest`] mapping each round to the CIDs of its transcript and response file.
+
+use crate::{
+    atomic,
+    status::{Report, RoundState},
+    storage::StorageOptions,
+    Result,
+};
+use anyhow::ensure;
+use reqwest::{
+    multipart::{Form, Part},
+    Body, Client,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{collections::BTreeMap, path::Path};
+use tokio_util::io::ReaderStream;
+use tracing::info;
+
+/// Default URL of the RPC API of the IPFS node
+pub const IPFS_API_URL: &str = "http://127.0.0.1:5001";
+
+/// Default path of the IPFS manifest in the state directory
+pub const IPFS_MANIFEST_PATH: &str = "ipfs.json";
+
+/// Largest contents added by the node as a single raw block, the default chunk size of `ipfs add`
+pub const MAX_RAW_BLOCK_SIZE: usize = 256 * 1024;
+
+/// Multicodec code of raw binary blocks
+const RAW_CODEC: u8 = 0x55;
+
+/// Multihash code of SHA2-256
+const SHA2_256_CODE: u8 = 0x12;
+
+/// Encodes `bytes` in the lowercase base32 of RFC 4648, without padding.
+#[inline]
+fn base32(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut encoded = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let (mut buffer, mut bits) = (0u16, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | u16::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(ALPHABET[usize::from((buffer >> bits) & 31)] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(ALPHABET[usize::from((buffer << (5 - bits)) & 31)] as char);
+    }
+    encoded
+}
+
+/// Returns the CIDv1 of `bytes` stored as a single raw block, hashed with SHA2-256 and encoded in
+/// base32, as given by `ipfs add --cid-version 1 --raw-leaves` for contents of at most
+/// [`MAX_RAW_BLOCK_SIZE`] bytes.
+#[inline]
+pub fn raw_cid(bytes: &[u8]) -> String {
+    let mut cid = vec![1, RAW_CODEC, SHA2_256_CODE, 32];
+    cid.extend_from_slice(&Sha256::digest(bytes));
+    format!("b{}", base32(&cid))
+}
+
+/// Entry Added to an IPFS Node
+#[derive(Deserialize)]
+struct Added {
+    /// CID of the entry
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+/// IPFS Node
+#[derive(Clone, Debug)]
+pub struct IpfsNode {
+    /// HTTP Client
+    client: Client,
+
+    /// URL of the RPC API of the node
+    api: String,
+}
+
+impl IpfsNode {
+    /// Builds a node reached with `client` through its RPC API at `api`.
+    #[inline]
+    pub fn new(client: Client, api: &str) -> Self {
+        Self {
+            client,
+            api: api.trim_end_matches('/').into(),
+        }
+    }
+
+    /// Adds `part` to the node and pins it, returning its CID.
+    #[inline]
+    async fn add(&self, part: Part) -> Result<String> {
+        let added = self
+            .client
+            .post(format!("{}/api/v0/add", self.api))
+            .query(&[
+                ("cid-version", "1"),
+                ("raw-leaves", "true"),
+                ("pin", "true"),
+            ])
+            .multipart(Form::new().part("file", part))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Added>()
+            .await?;
+        Ok(added.hash)
+    }
+
+    /// Adds `contents` to the node under `name` and pins them, checking that the node gives them
+    /// the CID computed locally by [`raw_cid`]. Returns the CID.
+    #[inline]
+    pub async fn add_raw(&self, name: &str, contents: String) -> Result<String> {
+        ensure!(
+            contents.len() <= MAX_RAW_BLOCK_SIZE,
+            "{} does not fit in a single IPFS block",
+            name
+        );
+        let cid = raw_cid(contents.as_bytes());
+        let added = self
+            .add(Part::text(contents).file_name(name.to_owned()))
+            .await?;
+        ensure!(
+            added == cid,
+            "The IPFS node added {} as {} instead of {}",
+            name,
+            added,
+            cid
+        );
+        Ok(cid)
+    }
+
+    /// Streams the file at `path` to the node and pins it, returning its CID.
+    #[inline]
+    pub async fn add_file(&self, path: &Path) -> Result<String> {
+        let file = tokio::fs::File::open(path).await?;
+        let len = file.metadata().await?.len();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let body = Body::wrap_stream(ReaderStream::new(file));
+        info!("Adding {} to IPFS", path.display());
+        self.add(Part::stream_with_length(body, len).file_name(name))
+            .await
+    }
+}
+
+/// CIDs of a Round
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundCids {
+    /// CID of the hash transcript of the files of the round
+    pub transcript: String,
+
+    /// CID of the response file of the round, if it was added to the node
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+}
+
+/// IPFS Manifest
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct IpfsManifest {
+    /// CID of the hash transcript of every hashed file
+    pub transcript: String,
+
+    /// CIDs of every round with a hashed file
+    pub rounds: BTreeMap<usize, RoundCids>,
+}
+
+impl IpfsManifest {
+    /// Computes the CIDs of the hash transcripts of `report`.
+    #[inline]
+    pub fn from_report(report: &Report) -> Self {
+        Self {
+            transcript: raw_cid(report.transcript().as_bytes()),
+            rounds: report
+                .rounds
+                .iter()
+                .filter_map(|round| {
+                    let transcript = round.transcript();
+                    (!transcript.is_empty()).then(|| {
+                        (
+                            round.round,
+                            RoundCids {
+                                transcript: raw_cid(transcript.as_bytes()),
+                                response: None,
+                            },
+                        )
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Pins the hash transcripts of `report` to `node`, and the response files of its rounds in
+    /// `storage` if `files` is `true`, returning the manifest of their CIDs.
+    #[inline]
+    pub async fn pin(
+        node: &IpfsNode,
+        report: &Report,
+        storage: &StorageOptions,
+        files: bool,
+    ) -> Result<Self> {
+        let mut manifest = Self::from_report(report);
+        node.add_raw("ppot-hashes.b2", report.transcript()).await?;
+        for round in &report.rounds {
+            let cids = match manifest.rounds.get_mut(&round.round) {
+                Some(cids) => cids,
+                _ => continue,
+            };
+            node.add_raw(&format!("round_{:04}.b2", round.round), round.transcript())
+                .await?;
+            if files {
+                cids.response = response_cid(node, round, storage).await?;
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Saves the manifest as JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// Adds the response file of `round` in `storage` to `node`, if it is completely downloaded,
+/// returning its CID.
+#[inline]
+async fn response_cid(
+    node: &IpfsNode,
+    round: &RoundState,
+    storage: &StorageOptions,
+) -> Result<Option<String>> {
+    match round.files.get(1) {
+        Some(response) if response.is_downloaded() && response.blake2b.is_some() => {
+            Ok(Some(node.add_file(&storage.path(&response.path)).await?))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that raw CIDs match the ones given by IPFS.
+    #[test]
+    fn raw_cids() {
+        assert_eq!(
+            raw_cid(b""),
+            "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+        );
+        assert_eq!(
+            raw_cid(b"hello world"),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+    }
+}
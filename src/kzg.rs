@@ -0,0 +1,233 @@
+//! KZG Commitments
+//!
+//! The powers of tau in G1 and the first two powers of tau in G2 are all the KZG polynomial
+//! commitment scheme needs: a polynomial `p` with fewer coefficients than there are powers is
+//! committed to as `C = p(tau)` in G1, its opening at `z` is `p(z)` with the commitment `W` to the
+//! quotient `(p(X) - p(z)) / (X - z)`, and the opening is checked with the pairing equation
+//! `e(C - p(z) * G1, G2) = e(W, tau * G2 - z * G2)`.
+//!
+//! [`Kzg`] runs the scheme on the powers of a verified challenge file or of an exported
+//! [`Srs`], as an end-to-end check that the powers are usable and as a starting point for
+//! experiments. It is not meant for provers: the commitments are not hiding and nothing is
+//! optimized beyond the multi-scalar multiplication.
+
+use crate::{
+    export::Srs,
+    format::{read_g1, read_g2, Layout, Section},
+    Result,
+};
+use anyhow::{bail, ensure};
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, PairingEngine, ProjectiveCurve};
+use ark_ff::{One, PrimeField, UniformRand, Zero};
+use rand::Rng;
+use rayon::prelude::*;
+
+/// Opening of a Committed Polynomial at a Point
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Opening {
+    /// Value of the polynomial at the point
+    pub value: Fr,
+
+    /// Commitment to the quotient of the polynomial minus its value by `X` minus the point
+    pub proof: G1Affine,
+}
+
+/// KZG Commitment Key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Kzg {
+    /// Powers of tau in G1
+    powers_g1: Vec<G1Affine>,
+
+    /// Generator of G2
+    g2: G2Affine,
+
+    /// Tau in G2
+    tau_g2: G2Affine,
+}
+
+impl Kzg {
+    /// Builds a commitment key from the `powers_g1` of tau in G1, the generator `g2` of G2 and
+    /// `tau_g2`.
+    #[inline]
+    pub fn new(powers_g1: Vec<G1Affine>, g2: G2Affine, tau_g2: G2Affine) -> Result<Self> {
+        ensure!(!powers_g1.is_empty(), "A commitment key needs powers in G1");
+        Ok(Self {
+            powers_g1,
+            g2,
+            tau_g2,
+        })
+    }
+
+    /// Builds a commitment key for polynomials of `len` coefficients from the challenge
+    /// `accumulator` with `powers` powers of tau.
+    #[inline]
+    pub fn from_accumulator(accumulator: &[u8], powers: usize, len: usize) -> Result<Self> {
+        let layout = Layout::challenge(powers);
+        ensure!(
+            accumulator.len() == layout.file_size(),
+            "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+            accumulator.len(),
+            layout.file_size(),
+            powers.trailing_zeros()
+        );
+        ensure!(
+            len <= Section::TauG1.len(powers),
+            "Polynomials of {} coefficients need more powers than the accumulator holds",
+            len
+        );
+        let powers_g1 = (0..len)
+            .into_par_iter()
+            .map(|index| {
+                read_g1(
+                    &accumulator[layout.point_range(Section::TauG1, index)],
+                    layout.encoding,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let g2 = |index| {
+            read_g2(
+                &accumulator[layout.point_range(Section::TauG2, index)],
+                layout.encoding,
+            )
+        };
+        Self::new(powers_g1, g2(0)?, g2(1)?)
+    }
+
+    /// Builds a commitment key for polynomials of `len` coefficients from `srs`.
+    #[inline]
+    pub fn from_srs(srs: &Srs, len: usize) -> Result<Self> {
+        ensure!(
+            len <= srs.tau_g1.len() && srs.tau_g2.len() >= 2,
+            "Polynomials of {} coefficients need more powers than the SRS holds",
+            len
+        );
+        Self::new(srs.tau_g1[..len].to_vec(), srs.tau_g2[0], srs.tau_g2[1])
+    }
+
+    /// Returns the largest degree of the polynomials the key commits to.
+    #[inline]
+    pub fn max_degree(&self) -> usize {
+        self.powers_g1.len() - 1
+    }
+
+    /// Commits to the polynomial with `coefficients`, lowest degree first.
+    #[inline]
+    pub fn commit(&self, coefficients: &[Fr]) -> Result<G1Affine> {
+        ensure!(
+            coefficients.len() <= self.powers_g1.len(),
+            "The polynomial has degree {} above the largest degree {} of the key",
+            coefficients.len() - 1,
+            self.max_degree()
+        );
+        let scalars = coefficients
+            .par_iter()
+            .map(|coefficient| coefficient.into_repr())
+            .collect::<Vec<_>>();
+        Ok(
+            VariableBaseMSM::multi_scalar_mul(&self.powers_g1[..scalars.len()], &scalars)
+                .into_affine(),
+        )
+    }
+
+    /// Opens the polynomial with `coefficients` at `point`.
+    #[inline]
+    pub fn open(&self, coefficients: &[Fr], point: Fr) -> Result<Opening> {
+        let (quotient, value) = divide(coefficients, point);
+        Ok(Opening {
+            value,
+            proof: self.commit(&quotient)?,
+        })
+    }
+
+    /// Checks that `opening` is the opening at `point` of the polynomial committed to as
+    /// `commitment`.
+    #[inline]
+    pub fn verify(&self, commitment: &G1Affine, point: Fr, opening: &Opening) -> bool {
+        Bn254::pairing(
+            commitment.into_projective() - self.powers_g1[0].mul(opening.value),
+            self.g2,
+        ) == Bn254::pairing(
+            opening.proof,
+            self.tau_g2.into_projective() - self.g2.mul(point),
+        )
+    }
+
+    /// Commits to a random polynomial of the largest degree, opens it at a random point and
+    /// checks the opening, and that an opening to another value is rejected.
+    #[inline]
+    pub fn smoke_test<R>(&self, rng: &mut R) -> Result
+    where
+        R: Rng + ?Sized,
+    {
+        let coefficients = (0..self.powers_g1.len())
+            .map(|_| Fr::rand(rng))
+            .collect::<Vec<_>>();
+        let point = Fr::rand(rng);
+        let commitment = self.commit(&coefficients)?;
+        let opening = self.open(&coefficients, point)?;
+        if !self.verify(&commitment, point, &opening) {
+            bail!(
+                "The opening of a polynomial of degree {} was rejected",
+                self.max_degree()
+            );
+        }
+        let forged = Opening {
+            value: opening.value + Fr::one(),
+            ..opening
+        };
+        if self.verify(&commitment, point, &forged) {
+            bail!("An opening to a wrong value was accepted");
+        }
+        Ok(())
+    }
+}
+
+/// Divides the polynomial with `coefficients` by `X - point`, returning the quotient and the
+/// remainder, which is the value of the polynomial at `point`.
+#[inline]
+pub fn divide(coefficients: &[Fr], point: Fr) -> (Vec<Fr>, Fr) {
+    let mut quotient = vec![Fr::zero(); coefficients.len().saturating_sub(1)];
+    let mut carry = Fr::zero();
+    for (i, coefficient) in coefficients.iter().enumerate().rev() {
+        carry = *coefficient + carry * point;
+        if i > 0 {
+            quotient[i - 1] = carry;
+        }
+    }
+    (quotient, carry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::Field;
+
+    /// Checks the division by `X - z` and that the scheme works on the powers of a known tau but
+    /// not on powers of tau in G1 which do not match tau in G2.
+    #[test]
+    fn commitments_open() {
+        let mut rng = rand::thread_rng();
+        let coefficients = [3u64, 0, 2, 5].map(Fr::from);
+        let (quotient, value) = divide(&coefficients, Fr::from(2u64));
+        assert_eq!(value, Fr::from(3 + 2 * 4 + 5 * 8u64));
+        assert_eq!(quotient, [24u64, 12, 5].map(Fr::from));
+        let tau = Fr::rand(&mut rng);
+        let powers_g1 = |tau: Fr| {
+            (0..16)
+                .map(|i| {
+                    G1Affine::prime_subgroup_generator()
+                        .mul(tau.pow([i]))
+                        .into_affine()
+                })
+                .collect::<Vec<_>>()
+        };
+        let g2 = G2Affine::prime_subgroup_generator();
+        let kzg = Kzg::new(powers_g1(tau), g2, g2.mul(tau).into_affine()).unwrap();
+        assert_eq!(kzg.max_degree(), 15);
+        kzg.smoke_test(&mut rng).unwrap();
+        assert!(kzg.commit(&[Fr::one(); 17]).is_err());
+        let wrong = Kzg::new(powers_g1(tau + Fr::one()), g2, g2.mul(tau).into_affine()).unwrap();
+        assert!(wrong.smoke_test(&mut rng).is_err());
+    }
+}
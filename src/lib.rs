@@ -1,18 +1,503 @@
-use blake2::{Blake2b, Digest};
-use memmap::Mmap;
-use std::{fs, io};
+use blake2::{Blake2b512, Digest};
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "download")]
+use indicatif::{ProgressBar, ProgressStyle};
+
+#[cfg(feature = "native")]
+use {
+    hash::Hash64,
+    memmap::MmapMut,
+    reqwest::Client,
+    std::io::{self, Read},
+    tokio_util::sync::CancellationToken,
+    tracing::info,
+};
+
+pub mod accumulator;
+#[cfg(feature = "native")]
+pub mod aggregate;
+pub mod atomic;
+#[cfg(feature = "native")]
+pub mod azure;
+pub mod cache;
+#[cfg(feature = "native")]
+pub mod canonical;
+#[cfg(feature = "native")]
+pub mod ceremony;
+pub mod checkpoint;
+#[cfg(feature = "native")]
+pub mod checksum;
+#[cfg(feature = "native")]
+pub mod claims;
+#[cfg(feature = "native")]
+pub mod compress;
+#[cfg(feature = "native")]
+pub mod config;
+pub mod contributions;
+#[cfg(feature = "native")]
+pub mod coordinator;
+pub mod curve;
+#[cfg(feature = "native")]
+pub mod db;
+pub mod der;
+pub mod digests;
+#[cfg(feature = "native")]
+pub mod disk;
+#[cfg(feature = "native")]
+pub mod doctor;
+#[cfg(feature = "download")]
+pub mod download;
+pub mod eip4844;
+pub mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fft;
+pub mod format;
+#[cfg(feature = "native")]
+pub mod github;
+pub mod hash;
+#[cfg(feature = "native")]
+pub mod input;
+#[cfg(feature = "native")]
+pub mod ipfs;
+pub mod kzg;
+#[cfg(feature = "native")]
+pub mod locate;
+#[cfg(feature = "download")]
+pub mod lock;
+#[cfg(feature = "native")]
+pub mod log;
+#[cfg(feature = "native")]
+pub mod manifest;
+#[cfg(feature = "download")]
+pub mod memory;
+#[cfg(feature = "native")]
+pub mod merkle;
+#[cfg(all(test, feature = "native"))]
+mod mock;
+#[cfg(feature = "node")]
+pub mod node;
+#[cfg(feature = "native")]
+pub mod notify;
+#[cfg(feature = "native")]
+pub mod parity;
+#[cfg(feature = "native")]
+pub mod pgp;
+pub mod phase2;
+#[cfg(feature = "native")]
+pub mod plan;
+pub mod pok;
+#[cfg(feature = "native")]
+pub mod prefix;
+pub mod profile;
+#[cfg(feature = "download")]
+pub mod progress;
+#[cfg(feature = "native")]
+pub mod provenance;
+pub mod ptau;
+#[cfg(feature = "native")]
+pub mod quarantine;
+#[cfg(feature = "native")]
+pub mod queue;
+pub mod r1cs;
+#[cfg(feature = "native")]
+pub mod registry;
+#[cfg(feature = "native")]
+pub mod s3;
+#[cfg(feature = "native")]
+pub mod scrub;
+#[cfg(feature = "download")]
+pub mod segment;
+#[cfg(feature = "native")]
+pub mod server;
+#[cfg(feature = "native")]
+pub mod sign;
+#[cfg(feature = "native")]
+pub mod signal;
+#[cfg(feature = "native")]
+pub mod sigstore;
+#[cfg(feature = "native")]
+pub mod snapshot;
+#[cfg(feature = "native")]
+pub mod status;
+#[cfg(feature = "native")]
+pub mod storage;
+pub mod synthetic;
+#[cfg(feature = "download")]
+pub mod throttle;
+#[cfg(feature = "native")]
+pub mod timestamp;
+#[cfg(feature = "native")]
+pub mod torrent;
+#[cfg(feature = "native")]
+pub mod transform;
+#[cfg(feature = "download")]
+pub mod validator;
+#[cfg(feature = "native")]
+pub mod view;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "native")]
+pub mod watch;
+#[cfg(feature = "native")]
+pub mod window;
+pub mod zkey;
+
+/// Result Type
+pub type Result<T = (), E = anyhow::Error> = core::result::Result<T, E>;
+
+/// Hash Algorithm
+///
+/// The ceremony chains its files together with Blake2b hashes, so [`Blake2b`](Self::Blake2b) is
+/// the only algorithm that can be checked against the headers of the files. BLAKE3 hashes on all
+/// cores and is much faster for local integrity checks, while SHA-256 matches the sums published
+/// by other mirrors.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum HashAlgorithm {
+    /// Blake2b with 64-byte output
+    #[default]
+    Blake2b,
+
+    /// BLAKE3 with 32-byte output
+    Blake3,
+
+    /// SHA-256
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// Returns the name of `self`.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Blake2b => "blake2b",
+            Self::Blake3 => "blake3",
+            Self::Sha256 => "sha256",
+        }
+    }
+
+    /// Returns the length of the hashes computed by `self` in bytes.
+    #[inline]
+    pub fn output_len(self) -> usize {
+        match self {
+            Self::Blake2b => 64,
+            Self::Blake3 | Self::Sha256 => 32,
+        }
+    }
+
+    /// Returns the suffix appended to the path of a file to get the path of its hash.
+    #[inline]
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Self::Blake2b => "_hash",
+            Self::Blake3 => "_blake3",
+            Self::Sha256 => "_sha256",
+        }
+    }
+
+    /// Returns the path of the file holding the hash of the file at `path`.
+    #[inline]
+    pub fn hash_path<P>(self, path: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        let mut hash_path = path.as_ref().as_os_str().to_owned();
+        hash_path.push(self.suffix());
+        hash_path.into()
+    }
+
+    /// Builds a new hasher for `self`.
+    #[inline]
+    fn hasher(self) -> Hasher {
+        match self {
+            Self::Blake2b => Hasher::Blake2b(Default::default()),
+            Self::Blake3 => Hasher::Blake3(Default::default()),
+            Self::Sha256 => Hasher::Sha256(Default::default()),
+        }
+    }
+}
+
+/// Hasher for any of the [`HashAlgorithm`]s
+enum Hasher {
+    /// Blake2b Hasher
+    Blake2b(Blake2b512),
+
+    /// BLAKE3 Hasher
+    Blake3(Box<blake3::Hasher>),
+
+    /// SHA-256 Hasher
+    Sha256(Sha256),
+}
+
+impl Hasher {
+    /// Feeds `data` into the hasher.
+    #[inline]
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Blake2b(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update_rayon(data);
+            }
+            Self::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Returns the hash of all the data fed into the hasher.
+    #[inline]
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Blake2b(hasher) => hasher.finalize().to_vec(),
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+        }
+    }
+}
 
 /// Computes the hash of a potentially large file,
 /// such as PPoT `challenge` or `response` files.
-pub fn calculate_hash(input_map: &Mmap) -> [u8; 64] {
-    let chunk_size = 1 << 30; // read by 1GB from map
-    let mut hasher = Blake2b::default();
+pub fn calculate_hash(input_map: &[u8], algorithm: HashAlgorithm) -> Vec<u8> {
+    let mut hasher = algorithm.hasher();
+    hasher.update(input_map);
+    hasher.finalize()
+}
+
+/// Computes the hashes of a potentially large file with each of the `algorithms` in a single pass
+/// over the file by chunks of `chunk_size` bytes, returning them in the same order. The chunk size
+/// is usually derived from the available memory, see [`memory::hash_chunk_size`]. The number of
+/// bytes hashed so far is reported to `progress`, if any, see [`hash_progress_bar`]. Once `cancel`
+/// is cancelled, hashing stops after the current chunk and `None` is returned.
+#[cfg(feature = "native")]
+pub fn calculate_hashes(
+    input_map: &[u8],
+    algorithms: &[HashAlgorithm],
+    progress: Option<&ProgressBar>,
+    cancel: Option<&CancellationToken>,
+    chunk_size: usize,
+) -> Option<Vec<Vec<u8>>> {
+    calculate_hashes_with(
+        input_map,
+        algorithms,
+        progress,
+        cancel,
+        chunk_size,
+        |_, _| {},
+    )
+}
+
+/// Computes the hashes of `input_map` like [`calculate_hashes`] by chunks of `chunk_size` bytes,
+/// calling `hashed` with the offset and the bytes of each chunk once it has been hashed.
+#[cfg(feature = "native")]
+pub(crate) fn calculate_hashes_with<F>(
+    input_map: &[u8],
+    algorithms: &[HashAlgorithm],
+    progress: Option<&ProgressBar>,
+    cancel: Option<&CancellationToken>,
+    chunk_size: usize,
+    mut hashed: F,
+) -> Option<Vec<Vec<u8>>>
+where
+    F: FnMut(u64, &[u8]),
+{
+    let mut hashers = hashers(algorithms);
+    for (i, chunk) in input_map.chunks(chunk_size).enumerate() {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            return None;
+        }
+        update_all(&mut hashers, chunk, progress);
+        hashed((i * chunk_size) as u64, chunk);
+    }
+    Some(hashers.into_iter().map(Hasher::finalize).collect())
+}
+
+/// Size of the buffer used by [`calculate_hashes_streaming`]
+#[cfg(feature = "native")]
+pub const STREAM_CHUNK_SIZE: usize = 1 << 24;
+
+/// Computes the hashes of everything read from `reader` like [`calculate_hashes`], but through
+/// reads of [`STREAM_CHUNK_SIZE`] bytes for files which cannot be memory mapped.
+#[cfg(feature = "native")]
+pub fn calculate_hashes_streaming<R>(
+    reader: R,
+    algorithms: &[HashAlgorithm],
+    progress: Option<&ProgressBar>,
+    cancel: Option<&CancellationToken>,
+) -> io::Result<Option<Vec<Vec<u8>>>>
+where
+    R: Read,
+{
+    calculate_hashes_streaming_with(
+        reader,
+        algorithms,
+        progress,
+        cancel,
+        STREAM_CHUNK_SIZE,
+        |_, _| {},
+    )
+}
+
+/// Computes the hashes of everything read from `reader` like [`calculate_hashes_streaming`] by
+/// chunks of `chunk_size` bytes, calling `hashed` with the offset and the bytes of each chunk once
+/// it has been hashed. The chunks are read into a page-aligned buffer, as required for direct I/O.
+#[cfg(feature = "native")]
+pub(crate) fn calculate_hashes_streaming_with<R, F>(
+    mut reader: R,
+    algorithms: &[HashAlgorithm],
+    progress: Option<&ProgressBar>,
+    cancel: Option<&CancellationToken>,
+    chunk_size: usize,
+    mut hashed: F,
+) -> io::Result<Option<Vec<Vec<u8>>>>
+where
+    R: Read,
+    F: FnMut(u64, &[u8]),
+{
+    let mut hashers = hashers(algorithms);
+    let mut buffer = MmapMut::map_anon(chunk_size)?;
+    let mut offset = 0;
+    loop {
+        if cancel.is_some_and(CancellationToken::is_cancelled) {
+            return Ok(None);
+        }
+        let mut len = 0;
+        while len < buffer.len() {
+            match reader.read(&mut buffer[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        if len == 0 {
+            break;
+        }
+        update_all(&mut hashers, &buffer[..len], progress);
+        hashed(offset, &buffer[..len]);
+        offset += len as u64;
+    }
+    Ok(Some(hashers.into_iter().map(Hasher::finalize).collect()))
+}
+
+/// Builds a new hasher for each of the `algorithms`.
+#[cfg(feature = "native")]
+#[inline]
+fn hashers(algorithms: &[HashAlgorithm]) -> Vec<Hasher> {
+    algorithms
+        .iter()
+        .map(|algorithm| algorithm.hasher())
+        .collect()
+}
+
+/// Feeds `chunk` into all the `hashers` in parallel and reports its length to `progress`.
+#[cfg(feature = "native")]
+#[inline]
+fn update_all(hashers: &mut [Hasher], chunk: &[u8], progress: Option<&ProgressBar>) {
+    std::thread::scope(|scope| {
+        for hasher in hashers {
+            scope.spawn(move || hasher.update(chunk));
+        }
+    });
+    if let Some(progress) = progress {
+        progress.inc(chunk.len() as u64);
+    }
+}
+
+/// Hashing Progress Bar Template
+#[cfg(feature = "download")]
+const HASH_PROGRESS_TEMPLATE: &str =
+    "{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})";
+
+/// Builds a [`ProgressBar`] for hashing the file at `path` of `len` bytes, showing the throughput
+/// and the remaining time. Add it to a [`MultiProgress`](indicatif::MultiProgress) to hash several
+/// files at once.
+#[cfg(feature = "download")]
+#[inline]
+pub fn hash_progress_bar<P>(path: P, len: u64) -> Result<ProgressBar>
+where
+    P: AsRef<Path>,
+{
+    let progress_bar = ProgressBar::new(len);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template(HASH_PROGRESS_TEMPLATE)?
+            .progress_chars("#>-"),
+    );
+    progress_bar.set_message(format!("Hashing {}", path.as_ref().display()));
+    Ok(progress_bar)
+}
+
+/// Returns the path of the file holding the hash of the file at `path`, as written by the `hasher`
+/// binary.
+#[inline]
+pub fn hash_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    HashAlgorithm::Blake2b.hash_path(path)
+}
+
+/// Computes the Blake2b hash of the file at `path`, through a memory map if possible, see
+/// [`Input`](input::Input).
+#[cfg(feature = "native")]
+#[inline]
+pub fn hash_file<P>(path: P) -> Result<Hash64>
+where
+    P: AsRef<Path>,
+{
+    hash_file_with(path, &progress::NoProgress)
+}
 
-    for (counter, chunk) in input_map.chunks(chunk_size).enumerate() {
-        hasher.update(&chunk);
-        println!("Have hashed {:?} GB of the file", counter);
+/// Computes the Blake2b hash of the file at `path` like [`hash_file`], reporting the progress to
+/// `progress`.
+#[cfg(feature = "native")]
+#[inline]
+pub fn hash_file_with<P>(path: P, progress: &dyn progress::ProgressSink) -> Result<Hash64>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let input = input::Input::open(path, input::ReadMode::Auto)?;
+    progress.on_hash_started(path, input.len());
+    let hashes = input.hashes_with(&[HashAlgorithm::Blake2b], None, None, |_, chunk| {
+        progress.on_bytes_hashed(path, chunk.len() as u64)
+    })?;
+    let hash = Hash64(into_array_unchecked(
+        hashes
+            .expect("Hashing without a cancellation token always completes.")
+            .remove(0),
+    ));
+    progress.on_file_hashed(path, &hash);
+    Ok(hash)
+}
+
+/// Checks the hash chain between the consecutive files at `paths`, where each file asserts the hash
+/// of the one before it in its header, returning the index of the first file whose hash does not
+/// match the hash asserted by the next one.
+#[cfg(feature = "native")]
+#[inline]
+pub fn verify_hash_chain<P>(paths: &[P]) -> Result<Option<usize>>
+where
+    P: AsRef<Path>,
+{
+    for (index, pair) in paths.windows(2).enumerate() {
+        if hash_file(&pair[0])? != Hash64::read_header(&pair[1])? {
+            return Ok(Some(index));
+        }
     }
-    into_array_unchecked(hasher.finalize())
+    Ok(None)
+}
+
+/// Returns `true` if the server at `url` answers a range request for the first three bytes of the
+/// download with exactly three bytes, as the mirrors must for downloads to resume.
+#[cfg(feature = "url-check")]
+pub fn check_download_url(url: &str) -> Result<bool> {
+    let mut handle = curl::easy::Easy::new();
+    handle.url(url)?;
+    handle.range("0-2")?;
+    handle.write_function(|data| Ok(data.len()))?;
+    handle.perform()?;
+    Ok(handle.content_length_download()? == 3.0)
 }
 
 /// Error Message for the [`into_array_unchecked`] and [`into_boxed_array_unchecked`] Functions
@@ -31,147 +516,25 @@ where
     }
 }
 
-/// Go to github repo and parse file names
-pub fn get_urls() -> std::io::Result<(Vec<String>, Vec<String>)> {
-    let path = "../perpetualpowersoftau";
-
-    let mut challenge_paths = Vec::<String>::new();
-    let mut response_paths = Vec::<String>::new();
-    let mut counter: usize = 0;
-
-    // Get sorted list of contributions
-    let mut entries = fs::read_dir(path)?
-        .map(|res| res.map(|e| e.path()))
-        .collect::<Result<Vec<_>, io::Error>>()?;
-    entries.sort();
-
-    for entry in entries.iter() {
-        let file_name = entry.as_path().file_name().unwrap().to_str().unwrap();
-        if let Ok(number) = &file_name[0..4].parse::<usize>() {
-            if *number == counter {
-                // Challenge paths are just numbered
-                challenge_paths.push(format!(
-                    "https://ppot.blob.core.windows.net/public/challenge_{:04}",
-                    number + 1
-                ));
-                // Response paths require the participant name and there is no response to initial challenge
-                if counter > 0 {
-                    response_paths.push(format!(
-                        "https://ppot.blob.core.windows.net/public/response_{:04}_{:}",
-                        number,
-                        parse_participant_name(&file_name[5..]).unwrap()
-                    ));
-                }
-
-                counter += 1;
-            }
-        };
-    }
+/// Queries the GitHub contents API for the list of contributions and resolves their `challenge`
+/// and `response` URLs against the listing of the blob container. See [`github::api_token`] for how
+/// `token` is picked up from the environment.
+#[cfg(feature = "native")]
+pub async fn get_urls(client: &Client, token: Option<&str>) -> Result<(Vec<String>, Vec<String>)> {
+    let contributions = github::list_contributions(client, token).await?;
+    let blobs = azure::list_blobs(client, azure::CONTAINER_URL).await?;
+    let (challenge_paths, response_paths) =
+        github::contribution_urls(&contributions, blobs, azure::CONTAINER_URL);
 
-    // Some exceptions to the naming convention
-    challenge_paths[0] = "https://ppot.blob.core.windows.net/public/challenge_initial".into();
-    challenge_paths[1] = "https://ppot.blob.core.windows.net/public/challenge_0002_kobi".into();
-    response_paths[2] = "https://ppot.blob.core.windows.net/public/response_0003_poma".into();
-    response_paths[3] = "https://ppot.blob.core.windows.net/public/response_0004_pepesha".into();
-    response_paths[11] = "https://ppot.blob.core.windows.net/public/response_0012_daniel".into();
-    response_paths[15] = "https://ppot.blob.core.windows.net/public/response_0016_aurel".into();
-    response_paths[39] = "https://ppot.blob.core.windows.net/public/response_0040_weitang".into();
-
-    println!("There are {:?} challenge files", challenge_paths.len());
-    println!("There are {:?} response files", response_paths.len());
+    info!("There are {:?} challenge files", challenge_paths.len());
+    info!("There are {:?} response files", response_paths.len());
 
     Ok((challenge_paths, response_paths))
 }
 
-/// Extracts the name of a participant from a file assumed to
-/// have the form "name_response". All it does is look for the
-/// index where the underscore first appears and return the appropriate
-/// string slice.
-fn parse_participant_name(file_name: &str) -> Option<&str> {
-    for (i, char) in file_name.chars().enumerate() {
-        if char == '_' {
-            return Some(&file_name[0..i]);
-        }
-    }
-    None
-}
-
-/// Temporary hack to get the challenge file URLs.
-pub fn challenge_urls() -> [&'static str; 72] {
-    [
-        "https://ppot.blob.core.windows.net/public/challenge_initial",
-        "https://ppot.blob.core.windows.net/public/challenge_0002_kobi",
-        "https://ppot.blob.core.windows.net/public/challenge_0003",
-        "https://ppot.blob.core.windows.net/public/challenge_0004",
-        "https://ppot.blob.core.windows.net/public/challenge_0005",
-        "https://ppot.blob.core.windows.net/public/challenge_0006",
-        "https://ppot.blob.core.windows.net/public/challenge_0007",
-        "https://ppot.blob.core.windows.net/public/challenge_0008",
-        "https://ppot.blob.core.windows.net/public/challenge_0009",
-        "https://ppot.blob.core.windows.net/public/challenge_0010",
-        "https://ppot.blob.core.windows.net/public/challenge_0011",
-        "https://ppot.blob.core.windows.net/public/challenge_0012",
-        "https://ppot.blob.core.windows.net/public/challenge_0013",
-        "https://ppot.blob.core.windows.net/public/challenge_0014",
-        "https://ppot.blob.core.windows.net/public/challenge_0015",
-        "https://ppot.blob.core.windows.net/public/challenge_0016",
-        "https://ppot.blob.core.windows.net/public/challenge_0017",
-        "https://ppot.blob.core.windows.net/public/challenge_0018",
-        "https://ppot.blob.core.windows.net/public/challenge_0019",
-        "https://ppot.blob.core.windows.net/public/challenge_0020",
-        "https://ppot.blob.core.windows.net/public/challenge_0021",
-        "https://ppot.blob.core.windows.net/public/challenge_0022",
-        "https://ppot.blob.core.windows.net/public/challenge_0023",
-        "https://ppot.blob.core.windows.net/public/challenge_0024",
-        "https://ppot.blob.core.windows.net/public/challenge_0025",
-        "https://ppot.blob.core.windows.net/public/challenge_0026",
-        "https://ppot.blob.core.windows.net/public/challenge_0027",
-        "https://ppot.blob.core.windows.net/public/challenge_0028",
-        "https://ppot.blob.core.windows.net/public/challenge_0029",
-        "https://ppot.blob.core.windows.net/public/challenge_0030",
-        "https://ppot.blob.core.windows.net/public/challenge_0031",
-        "https://ppot.blob.core.windows.net/public/challenge_0032",
-        "https://ppot.blob.core.windows.net/public/challenge_0033",
-        "https://ppot.blob.core.windows.net/public/challenge_0034",
-        "https://ppot.blob.core.windows.net/public/challenge_0035",
-        "https://ppot.blob.core.windows.net/public/challenge_0036",
-        "https://ppot.blob.core.windows.net/public/challenge_0037",
-        "https://ppot.blob.core.windows.net/public/challenge_0038",
-        "https://ppot.blob.core.windows.net/public/challenge_0039",
-        "https://ppot.blob.core.windows.net/public/challenge_0040",
-        "https://ppot.blob.core.windows.net/public/challenge_0041",
-        "https://ppot.blob.core.windows.net/public/challenge_0042",
-        "https://ppot.blob.core.windows.net/public/challenge_0043",
-        "https://ppot.blob.core.windows.net/public/challenge_0044",
-        "https://ppot.blob.core.windows.net/public/challenge_0045",
-        "https://ppot.blob.core.windows.net/public/challenge_0046",
-        "https://ppot.blob.core.windows.net/public/challenge_0047",
-        "https://ppot.blob.core.windows.net/public/challenge_0048",
-        "https://ppot.blob.core.windows.net/public/challenge_0049",
-        "https://ppot.blob.core.windows.net/public/challenge_0050",
-        "https://ppot.blob.core.windows.net/public/challenge_0051",
-        "https://ppot.blob.core.windows.net/public/challenge_0052",
-        "https://ppot.blob.core.windows.net/public/challenge_0053",
-        "https://ppot.blob.core.windows.net/public/challenge_0054",
-        "https://ppot.blob.core.windows.net/public/challenge_0055",
-        "https://ppot.blob.core.windows.net/public/challenge_0056",
-        "https://ppot.blob.core.windows.net/public/challenge_0057",
-        "https://ppot.blob.core.windows.net/public/challenge_0058",
-        "https://ppot.blob.core.windows.net/public/challenge_0059",
-        "https://ppot.blob.core.windows.net/public/challenge_0060",
-        "https://ppot.blob.core.windows.net/public/challenge_0061",
-        "https://ppot.blob.core.windows.net/public/challenge_0062",
-        "https://ppot.blob.core.windows.net/public/challenge_0063",
-        "https://ppot.blob.core.windows.net/public/challenge_0064",
-        "https://ppot.blob.core.windows.net/public/challenge_0065",
-        "https://ppot.blob.core.windows.net/public/challenge_0066",
-        "https://ppot.blob.core.windows.net/public/challenge_0067",
-        "https://ppot.blob.core.windows.net/public/challenge_0068",
-        "https://ppot.blob.core.windows.net/public/challenge_0069",
-        "https://ppot.blob.core.windows.net/public/challenge_0070",
-        "https://ppot.blob.core.windows.net/public/challenge_0071",
-        "https://ppot.blob.core.windows.net/public/challenge_0072",
-    ]
+/// Returns the URLs of the challenge files of the [embedded contribution list](contributions).
+pub fn challenge_urls() -> Vec<String> {
+    contributions::ContributionList::embedded().challenge_urls()
 }
 
 /// Challenge path names numbered from 0 to n
@@ -179,81 +542,9 @@ pub fn challenge_paths(n: usize) -> Vec<String> {
     (0..n + 1).map(|i| format!("challenge_{:04}", i)).collect()
 }
 
-/// Temporary hack to get the response file URLs.
-pub fn response_urls() -> [&'static str; 71] {
-    [
-        "https://ppot.blob.core.windows.net/public/response_0001_weijie",
-        "https://ppot.blob.core.windows.net/public/response_0002_kobi",
-        "https://ppot.blob.core.windows.net/public/response_0003_poma",
-        "https://ppot.blob.core.windows.net/public/response_0004_pepesha",
-        "https://ppot.blob.core.windows.net/public/response_0005_amrullah",
-        "https://ppot.blob.core.windows.net/public/response_0006_zac",
-        "https://ppot.blob.core.windows.net/public/response_0007_youssef",
-        "https://ppot.blob.core.windows.net/public/response_0008_mike",
-        "https://ppot.blob.core.windows.net/public/response_0009_brecht",
-        "https://ppot.blob.core.windows.net/public/response_0010_vano",
-        "https://ppot.blob.core.windows.net/public/response_0011_zhiniang",
-        "https://ppot.blob.core.windows.net/public/response_0012_daniel",
-        "https://ppot.blob.core.windows.net/public/response_0013_kevin",
-        "https://ppot.blob.core.windows.net/public/response_0014_weijie",
-        "https://ppot.blob.core.windows.net/public/response_0015_anon0",
-        "https://ppot.blob.core.windows.net/public/response_0016_aurel",
-        "https://ppot.blob.core.windows.net/public/response_0017_philip",
-        "https://ppot.blob.core.windows.net/public/response_0018_cody",
-        "https://ppot.blob.core.windows.net/public/response_0019_petr",
-        "https://ppot.blob.core.windows.net/public/response_0020_edu",
-        "https://ppot.blob.core.windows.net/public/response_0021_rf",
-        "https://ppot.blob.core.windows.net/public/response_0022_roman",
-        "https://ppot.blob.core.windows.net/public/response_0023_shomari",
-        "https://ppot.blob.core.windows.net/public/response_0024_vb",
-        "https://ppot.blob.core.windows.net/public/response_0025_stefan",
-        "https://ppot.blob.core.windows.net/public/response_0026_geoff",
-        "https://ppot.blob.core.windows.net/public/response_0027_alex",
-        "https://ppot.blob.core.windows.net/public/response_0028_dimitris",
-        "https://ppot.blob.core.windows.net/public/response_0029_gustavo",
-        "https://ppot.blob.core.windows.net/public/response_0030_anant",
-        "https://ppot.blob.core.windows.net/public/response_0031_golem",
-        "https://ppot.blob.core.windows.net/public/response_0032_josephc",
-        "https://ppot.blob.core.windows.net/public/response_0033_oskar",
-        "https://ppot.blob.core.windows.net/public/response_0034_igor",
-        "https://ppot.blob.core.windows.net/public/response_0035_leonard",
-        "https://ppot.blob.core.windows.net/public/response_0036_stefaan",
-        "https://ppot.blob.core.windows.net/public/response_0037_chihcheng",
-        "https://ppot.blob.core.windows.net/public/response_0038_james",
-        "https://ppot.blob.core.windows.net/public/response_0039_wanseob",
-        "https://ppot.blob.core.windows.net/public/response_0040_weitang",
-        "https://ppot.blob.core.windows.net/public/response_0041_evan",
-        "https://ppot.blob.core.windows.net/public/response_0042_vaibhav",
-        "https://ppot.blob.core.windows.net/public/response_0043_albert",
-        "https://ppot.blob.core.windows.net/public/response_0044_yingtong",
-        "https://ppot.blob.core.windows.net/public/response_0045_ben",
-        "https://ppot.blob.core.windows.net/public/response_0046_tkorwin",
-        "https://ppot.blob.core.windows.net/public/response_0047_saravanan",
-        "https://ppot.blob.core.windows.net/public/response_0048_tyler",
-        "https://ppot.blob.core.windows.net/public/response_0049_jordi",
-        "https://ppot.blob.core.windows.net/public/response_0050_weijie",
-        "https://ppot.blob.core.windows.net/public/response_0051_joe",
-        "https://ppot.blob.core.windows.net/public/response_0052_zaki",
-        "https://ppot.blob.core.windows.net/public/response_0053_juan",
-        "https://ppot.blob.core.windows.net/public/response_0054_jarrad",
-        "https://ppot.blob.core.windows.net/public/response_0055_tyler",
-        "https://ppot.blob.core.windows.net/public/response_0056_auryn",
-        "https://ppot.blob.core.windows.net/public/response_0057_gisli",
-        "https://ppot.blob.core.windows.net/public/response_0058_rasikh",
-        "https://ppot.blob.core.windows.net/public/response_0059_pau",
-        "https://ppot.blob.core.windows.net/public/response_0060_weijie",
-        "https://ppot.blob.core.windows.net/public/response_0061_adria",
-        "https://ppot.blob.core.windows.net/public/response_0062_lev",
-        "https://ppot.blob.core.windows.net/public/response_0063_david",
-        "https://ppot.blob.core.windows.net/public/response_0064_ian",
-        "https://ppot.blob.core.windows.net/public/response_0065_adrian",
-        "https://ppot.blob.core.windows.net/public/response_0066_kieran",
-        "https://ppot.blob.core.windows.net/public/response_0067_nick",
-        "https://ppot.blob.core.windows.net/public/response_0068_elena",
-        "https://ppot.blob.core.windows.net/public/response_0069_justice",
-        "https://ppot.blob.core.windows.net/public/response_0070_bertrand",
-        "https://ppot.blob.core.windows.net/public/response_0071_edward",
-    ]
+/// Returns the URLs of the response files of the [embedded contribution list](contributions).
+pub fn response_urls() -> Vec<String> {
+    contributions::ContributionList::embedded().response_urls()
 }
 
 /// Response path names numbered from 1 to n
@@ -261,19 +552,63 @@ pub fn response_paths(n: usize) -> Vec<String> {
     (1..n + 1).map(|i| format!("response_{:04}", i)).collect()
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "native"))]
 mod tests {
     use super::*;
-    use std::path::Path;
+    use memmap::Mmap;
+    use std::fs::File;
+
+    /// Checks that a single pass computes the same hashes as the reference implementations.
+    #[test]
+    fn calculate_hashes_in_one_pass() {
+        let path = std::env::temp_dir().join("ppot-verifier-hash-test");
+        std::fs::write(&path, b"abc").unwrap();
+        let map = unsafe { Mmap::map(&File::open(&path).unwrap()).unwrap() };
+        let hashes = calculate_hashes(
+            &map,
+            &[
+                HashAlgorithm::Blake2b,
+                HashAlgorithm::Blake3,
+                HashAlgorithm::Sha256,
+            ],
+            None,
+            None,
+            2,
+        )
+        .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(hashes[0], Blake2b512::digest(b"abc").to_vec());
+        assert_eq!(hashes[1], blake3::hash(b"abc").as_bytes().to_vec());
+        assert_eq!(
+            hex::encode(&hashes[2]),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let streamed = calculate_hashes_streaming(
+            &b"abc"[..],
+            &[
+                HashAlgorithm::Blake2b,
+                HashAlgorithm::Blake3,
+                HashAlgorithm::Sha256,
+            ],
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(streamed, hashes);
+    }
 
     #[test]
     fn test_correct_urls() {
-        let (challenge_paths, response_paths) = get_urls().unwrap();
+        let (challenge_paths, response_paths) = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(get_urls(&Client::new(), github::api_token(None).as_deref()))
+            .unwrap();
         let mut all_paths_valid = true;
 
         // Check validity of each challenge path
         for (_i, path) in challenge_paths.iter().enumerate() {
-            if !check_download_url(path) {
+            if !check_download_url(path).unwrap() {
                 println!("URL {:?} is invalid ", path);
                 all_paths_valid = false;
             }
@@ -281,7 +616,7 @@ mod tests {
 
         // Check validity of each response path
         for (_i, path) in response_paths.iter().enumerate() {
-            if !check_download_url(path) {
+            if !check_download_url(path).unwrap() {
                 println!("URL {:?} is invalid ", path);
                 all_paths_valid = false;
             }
@@ -289,21 +624,4 @@ mod tests {
         assert_eq!(challenge_paths.len(), response_paths.len() + 1);
         assert!(all_paths_valid);
     }
-
-    /// Checks validity of the URL by requesting a few bytes of the download.
-    /// Returns `true` if the correct number were returned and `false` otherwise.
-    fn check_download_url(path: &str) -> bool {
-        use curl::easy::Easy;
-
-        let mut handle = Easy::new();
-        handle.url(path).unwrap();
-        handle.range("0-2").unwrap();
-        handle.perform().unwrap();
-
-        let content_length = handle.content_length_download().unwrap();
-        if content_length != 3f64 {
-            return false;
-        }
-        true
-    }
 }
@@ -0,0 +1,200 @@
+//! Corruption Locator
+//!
+//! Finds the byte ranges where a local file diverges from the copy served at a URL. Since the
+//! server cannot hash a range for us, every remote byte has to be fetched anyway, so instead of
+//! bisecting with repeated range requests the remote range is streamed once and compared block by
+//! block with the local file. Divergent blocks are narrowed down to their first and last differing
+//! byte and, when repairing, overwritten in place with the remote bytes.
+
+use crate::{
+    download::{progress_bar, ByteRange, ContentRange, RangeError},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use core::ops::Range;
+use indicatif::MultiProgress;
+use reqwest::{header::RANGE, Client, Method, StatusCode};
+use std::{io::SeekFrom, path::Path};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+};
+
+/// Size of the blocks compared between the local and the remote file
+pub const BLOCK_SIZE: usize = 1 << 20;
+
+/// Corruption Report
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct CorruptionReport {
+    /// Size of the remote file
+    pub remote_size: u64,
+
+    /// Size of the local file before any repair
+    pub local_size: u64,
+
+    /// Byte ranges where the local file differs from the remote file, sorted and disjoint
+    pub ranges: Vec<Range<u64>>,
+
+    /// Whether the divergent ranges were overwritten with the remote bytes
+    pub repaired: bool,
+}
+
+impl CorruptionReport {
+    /// Returns the total number of divergent bytes.
+    #[inline]
+    pub fn divergent_bytes(&self) -> u64 {
+        self.ranges
+            .iter()
+            .map(|range| range.end - range.start)
+            .sum()
+    }
+
+    /// Records the divergent `range`, merging it into the last range if they are less than a
+    /// block apart.
+    #[inline]
+    fn push(&mut self, range: Range<u64>) {
+        match self.ranges.last_mut() {
+            Some(last) if range.start <= last.end + BLOCK_SIZE as u64 => last.end = range.end,
+            _ => self.ranges.push(range),
+        }
+    }
+}
+
+/// Returns the range of `local` and `remote` between their first and last differing bytes, where
+/// bytes missing from `local` count as differing.
+#[inline]
+pub fn divergent_range(local: &[u8], remote: &[u8]) -> Option<Range<usize>> {
+    let differs = |i: usize| local.get(i) != remote.get(i);
+    let len = local.len().max(remote.len());
+    let first = (0..len).find(|&i| differs(i))?;
+    let last = (first..len).rev().find(|&i| differs(i))?;
+    Some(first..last + 1)
+}
+
+/// Reads as many bytes as possible from `file` at `offset` into `buffer`, returning how many were
+/// read.
+#[inline]
+async fn read_at(file: &mut File, offset: u64, buffer: &mut [u8]) -> Result<usize> {
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut read = 0;
+    while read < buffer.len() {
+        match file.read(&mut buffer[read..]).await? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+/// Compares the bytes of the local file at `path` within `range` against the file served at `url`,
+/// defaulting to the whole file, and reports where they diverge. If `repair` is set, the divergent
+/// blocks are overwritten with the remote bytes and the local file is truncated to the size of the
+/// remote file.
+#[inline]
+pub async fn locate_corruption<P>(
+    multibar: &MultiProgress,
+    client: &Client,
+    url: &str,
+    path: P,
+    range: Option<Range<u64>>,
+    repair: bool,
+) -> Result<CorruptionReport>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(repair)
+        .open(path)
+        .await?;
+    let local_size = file.metadata().await?.len();
+    let start = range.as_ref().map_or(0, |range| range.start);
+    let header = match &range {
+        Some(range) if range.end > range.start => ByteRange::of(range.clone()),
+        Some(_) => bail!("The range to check is empty."),
+        _ => ByteRange::From(start),
+    };
+    let mut response = client
+        .request(Method::GET, url)
+        .header(RANGE, ByteRange::header(&[header]))
+        .send()
+        .await?
+        .error_for_status()?;
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(RangeError::Unsupported(response.status()).into());
+    }
+    let (end, remote_size) = match ContentRange::from_response(&response) {
+        Some(ContentRange::Full { end, size, .. }) => (end + 1, size),
+        _ => return Err(anyhow!("Failed to parse content range from '{}'", url)),
+    };
+    let mut report = CorruptionReport {
+        remote_size,
+        local_size,
+        ranges: Vec::new(),
+        repaired: repair,
+    };
+    let progress_bar = progress_bar(multibar, end - start)?;
+    progress_bar.set_message(format!("Comparing {} against {}", path.display(), url));
+    let mut remote = Vec::with_capacity(BLOCK_SIZE);
+    let mut local = vec![0; BLOCK_SIZE];
+    let mut offset = start;
+    loop {
+        let chunk = response.chunk().await?;
+        if let Some(chunk) = &chunk {
+            remote.extend_from_slice(chunk);
+        }
+        while remote.len() >= BLOCK_SIZE || (chunk.is_none() && !remote.is_empty()) {
+            let block = remote.len().min(BLOCK_SIZE);
+            let read = read_at(&mut file, offset, &mut local[..block]).await?;
+            if let Some(divergent) = divergent_range(&local[..read], &remote[..block]) {
+                report.push(offset + divergent.start as u64..offset + divergent.end as u64);
+                if repair {
+                    file.seek(SeekFrom::Start(offset)).await?;
+                    file.write_all(&remote[..block]).await?;
+                }
+            }
+            remote.drain(..block);
+            offset += block as u64;
+            progress_bar.set_position(offset - start);
+        }
+        if chunk.is_none() {
+            break;
+        }
+    }
+    if offset != end {
+        bail!("The transfer from '{}' ended early.", url);
+    }
+    if end == remote_size && local_size > remote_size {
+        report.push(remote_size..local_size);
+        if repair {
+            file.set_len(remote_size).await?;
+        }
+    }
+    file.flush().await?;
+    progress_bar.finish_with_message(format!(
+        "Found {} divergent ranges in {}",
+        report.ranges.len(),
+        path.display()
+    ));
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that divergent ranges span from the first to the last differing byte.
+    #[test]
+    fn divergent_range_bounds() {
+        assert_eq!(divergent_range(b"abcdef", b"abcdef"), None);
+        assert_eq!(divergent_range(b"abXdYf", b"abcdef"), Some(2..5));
+        assert_eq!(divergent_range(b"abc", b"abcdef"), Some(3..6));
+        let mut report = CorruptionReport::default();
+        report.push(10..20);
+        report.push(30..40);
+        report.push(1 << 30..(1 << 30) + 1);
+        assert_eq!(report.ranges, vec![10..40, 1 << 30..(1 << 30) + 1]);
+        assert_eq!(report.divergent_bytes(), 31);
+    }
+}
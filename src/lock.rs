@@ -0,0 +1,157 @@
+//! Advisory File Locks
+//!
+//! The binaries are meant to run side by side, for instance the `hasher` picking up the files the
+//! `downloader` has finished. They coordinate through advisory locks taken on a `.lock` file next
+//! to each locked file, rather than on the file itself, so that a process can open the locked file
+//! as many times as it needs: the `downloader` holds an exclusive lock while it writes to a file and
+//! its checkpoints, and the readers hold shared locks while they hash or verify it.
+
+use crate::Result;
+use anyhow::bail;
+use fs2::FileExt;
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+};
+use tracing::info;
+
+/// Returns the path of the lock file guarding the file at `path`.
+#[inline]
+pub fn lock_path(path: &Path) -> PathBuf {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    lock_path.into()
+}
+
+/// Lock Mode
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Mode {
+    /// Held by any number of readers at once
+    Shared,
+
+    /// Held by a single writer
+    Exclusive,
+}
+
+/// File Lock
+///
+/// Advisory lock on a file, released when dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    /// Open lock file holding the lock
+    file: File,
+}
+
+impl FileLock {
+    /// Opens the lock file of `path`, creating it if needed.
+    #[inline]
+    fn open(path: &Path) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path(path))
+    }
+
+    /// Tries to lock `path` in the given `mode`, returning `None` if another process holds a
+    /// conflicting lock.
+    #[inline]
+    fn try_lock(path: &Path, mode: Mode) -> Result<Option<Self>> {
+        let file = Self::open(path)?;
+        let result = match mode {
+            Mode::Shared => FileExt::try_lock_shared(&file),
+            Mode::Exclusive => FileExt::try_lock_exclusive(&file),
+        };
+        match result {
+            Ok(()) => Ok(Some(Self { file })),
+            Err(err) if err.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Locks `path` in the given `mode`, waiting for the other processes to release their
+    /// conflicting locks.
+    #[inline]
+    fn lock(path: &Path, mode: Mode) -> Result<Self> {
+        if let Some(lock) = Self::try_lock(path, mode)? {
+            return Ok(lock);
+        }
+        info!("Waiting for another process to release {}", path.display());
+        let file = Self::open(path)?;
+        match mode {
+            Mode::Shared => FileExt::lock_shared(&file)?,
+            Mode::Exclusive => FileExt::lock_exclusive(&file)?,
+        }
+        Ok(Self { file })
+    }
+
+    /// Takes a shared lock on `path`, waiting for the process writing to it to finish.
+    #[inline]
+    pub fn shared<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::lock(path.as_ref(), Mode::Shared)
+    }
+
+    /// Takes an exclusive lock on `path`, waiting for the other processes using it to finish.
+    #[inline]
+    pub fn exclusive<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::lock(path.as_ref(), Mode::Exclusive)
+    }
+
+    /// Takes an exclusive lock on `path`, failing right away if another process uses it.
+    #[inline]
+    pub fn try_exclusive<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        match Self::try_lock(path, Mode::Exclusive)? {
+            Some(lock) => Ok(lock),
+            _ => bail!("{} is in use by another process.", path.display()),
+        }
+    }
+
+    /// Returns `true` if another process holds an exclusive lock on `path`, for instance while it
+    /// writes to it.
+    #[inline]
+    pub fn is_locked<P>(path: P) -> Result<bool>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(Self::try_lock(path.as_ref(), Mode::Shared)?.is_none())
+    }
+}
+
+impl Drop for FileLock {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that shared locks coexist and exclude exclusive locks.
+    #[test]
+    fn shared_locks_exclude_writers() {
+        let path = std::env::temp_dir().join("ppot-verifier-lock-test");
+        let first = FileLock::shared(&path).unwrap();
+        let second = FileLock::shared(&path).unwrap();
+        assert!(FileLock::try_exclusive(&path).is_err());
+        drop((first, second));
+        assert!(!FileLock::is_locked(&path).unwrap());
+        let exclusive = FileLock::try_exclusive(&path).unwrap();
+        assert!(FileLock::is_locked(&path).unwrap());
+        drop(exclusive);
+        std::fs::remove_file(lock_path(&path)).unwrap();
+    }
+}
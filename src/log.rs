@@ -0,0 +1,97 @@
+//! Logging
+//!
+//! The library reports what it is doing through [`tracing`] events and never prints by itself, so
+//! embedders decide where the events go. The binaries install a subscriber writing to standard
+//! error through [`LogOptions::init`], leaving standard output to the results of the command.
+
+use crate::Result;
+use anyhow::anyhow;
+use tracing::Level;
+use tracing_subscriber::{filter::LevelFilter, EnvFilter};
+
+/// Log Format
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+
+    /// One JSON object per event
+    Json,
+}
+
+/// Logging Options
+///
+/// Shared by the command line interfaces of all the binaries.
+#[derive(clap::Args, Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct LogOptions {
+    /// Only report warnings and errors
+    #[clap(long, short, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Report more details, twice for tracing output
+    #[clap(long, short, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Format of the log events
+    #[clap(long, global = true, value_enum, default_value = "text")]
+    pub log_format: LogFormat,
+}
+
+impl LogOptions {
+    /// Returns the most verbose level reported with `self`.
+    #[inline]
+    pub fn level(&self) -> Level {
+        match (self.quiet, self.verbose) {
+            (true, _) => Level::WARN,
+            (_, 0) => Level::INFO,
+            (_, 1) => Level::DEBUG,
+            _ => Level::TRACE,
+        }
+    }
+
+    /// Installs the global subscriber writing the events to standard error. The `RUST_LOG`
+    /// environment variable takes precedence over the verbosity of `self` when it is set.
+    #[inline]
+    pub fn init(&self) -> Result {
+        let filter = EnvFilter::builder()
+            .with_default_directive(LevelFilter::from_level(self.level()).into())
+            .from_env_lossy();
+        let builder = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(std::io::stderr)
+            .with_target(false);
+        match self.log_format {
+            LogFormat::Text => builder.try_init(),
+            LogFormat::Json => builder.json().try_init(),
+        }
+        .map_err(|err| anyhow!("Unable to install the logger: {}", err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    /// Command Line Arguments of the test
+    #[derive(Parser)]
+    struct Arguments {
+        /// Logging Options
+        #[clap(flatten)]
+        log: LogOptions,
+    }
+
+    /// Checks that the verbosity flags select the expected level.
+    #[test]
+    fn verbosity_levels() {
+        let level = |args: &[&str]| Arguments::parse_from(args).log.level();
+        assert_eq!(level(&["test"]), Level::INFO);
+        assert_eq!(level(&["test", "-q"]), Level::WARN);
+        assert_eq!(level(&["test", "-v"]), Level::DEBUG);
+        assert_eq!(level(&["test", "-vv"]), Level::TRACE);
+        assert!(Arguments::try_parse_from(["test", "-q", "-v"]).is_err());
+        let json = Arguments::parse_from(["test", "--log-format", "json"]).log;
+        assert_eq!(json.log_format, LogFormat::Json);
+    }
+}
@@ -0,0 +1,168 @@
+//! Download Manifests
+//!
+//! A manifest lists the URL, size and Blake2b hash of every file of the ceremony. Checking a
+//! download against it catches truncated files from their size alone and corrupt files in a
+//! single hashing pass, without going through the `hasher` and `hash_check` binaries.
+
+use crate::{
+    atomic, hash::Hash64, hash_file, registry::Registry, storage::StorageOptions, HashAlgorithm,
+    Result,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Default location of the manifest file
+pub const MANIFEST_PATH: &str = "manifest.json";
+
+/// Manifest Entry
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ManifestEntry {
+    /// Local file name
+    pub path: String,
+
+    /// Download URL
+    pub url: String,
+
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Blake2b hash of the file
+    pub blake2b: Hash64,
+}
+
+/// File Status
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum FileStatus {
+    /// The file matches its manifest entry
+    Valid,
+
+    /// The file does not exist
+    Missing,
+
+    /// The file does not have the expected size, it is truncated if `actual` is smaller
+    SizeMismatch {
+        /// Size of the file on disk
+        actual: u64,
+    },
+
+    /// The file has the expected size but not the expected hash
+    HashMismatch {
+        /// Hash of the file on disk
+        actual: Hash64,
+    },
+}
+
+impl ManifestEntry {
+    /// Checks the file of `self` in the storage directory of `storage` against `self`, only
+    /// hashing it if it has the expected size.
+    #[inline]
+    pub fn check(&self, storage: &StorageOptions) -> Result<FileStatus> {
+        let path = storage.path(&self.path);
+        let size = match fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(FileStatus::Missing),
+            Err(err) => return Err(err.into()),
+        };
+        if size != self.size {
+            return Ok(FileStatus::SizeMismatch { actual: size });
+        }
+        let actual = hash_file(&path)?;
+        if actual != self.blake2b {
+            return Ok(FileStatus::HashMismatch { actual });
+        }
+        Ok(FileStatus::Valid)
+    }
+}
+
+/// Download Manifest
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Manifest {
+    /// Files of the ceremony
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Loads the manifest from the JSON file at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Saves the manifest as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Builds the manifest of the files of `registry` present in the storage directory of
+    /// `storage`. Hashes already saved to the state directory by the `hasher` binary are reused, other files are hashed.
+    /// Files missing from the disk are skipped.
+    #[inline]
+    pub fn generate(registry: &Registry, storage: &StorageOptions) -> Result<Self> {
+        let mut files = Vec::new();
+        for file in registry.challenges.iter().chain(&registry.responses) {
+            let path = storage.path(&file.path);
+            let size = match fs::metadata(&path) {
+                Ok(metadata) => metadata.len(),
+                Err(err) if err.kind() == ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let hash = match Hash64::load(storage.hash_path(&path, HashAlgorithm::Blake2b)) {
+                Ok(hash) => hash,
+                _ => hash_file(&path)?,
+            };
+            files.push(ManifestEntry {
+                path: file.path.clone(),
+                url: file.url.clone(),
+                size,
+                blake2b: hash,
+            });
+        }
+        Ok(Self { files })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that truncated and corrupt files are told apart from valid ones.
+    #[test]
+    fn check_detects_truncation_and_corruption() {
+        let path = std::env::temp_dir().join("ppot-verifier-manifest-test");
+        let path_string = path.display().to_string();
+        fs::write(&path, b"contents").unwrap();
+        let mut entry = ManifestEntry {
+            path: path_string,
+            url: "https://example.com/file".into(),
+            size: 8,
+            blake2b: hash_file(&path).unwrap(),
+        };
+        assert_eq!(
+            entry.check(&StorageOptions::default()).unwrap(),
+            FileStatus::Valid
+        );
+        entry.size = 10;
+        assert_eq!(
+            entry.check(&StorageOptions::default()).unwrap(),
+            FileStatus::SizeMismatch { actual: 8 }
+        );
+        entry.size = 8;
+        fs::write(&path, b"CONTENTS").unwrap();
+        assert!(matches!(
+            entry.check(&StorageOptions::default()).unwrap(),
+            FileStatus::HashMismatch { .. }
+        ));
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            entry.check(&StorageOptions::default()).unwrap(),
+            FileStatus::Missing
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! Memory Budget
+//!
+//! The verifier materializes two subaccumulators at once, the one verified so far and the next one,
+//! and their size grows linearly with the number of powers they hold. A [`ByteSize`] budget caps
+//! the number of powers verified in each round to the largest count that fits, see [`fit_powers`].
+//!
+//! Without a budget, the verification is tuned to a share of the memory available on the machine,
+//! picking the largest number of powers and of threads which fit, see [`tune`].
+//!
+//! The memory available on the machine also sizes the chunks files are hashed by and the buffers
+//! downloads are written through, see [`hash_chunk_size`] and [`download_buffer_size`].
+
+use crate::Result;
+use anyhow::{anyhow, bail};
+use core::{fmt, str::FromStr};
+use indicatif::HumanBytes;
+
+/// Byte Size
+///
+/// Parsed from strings like `16G`, `1.5GiB` or `800K`. Decimal suffixes (`K`, `M`, `G`, `T`,
+/// optionally followed by `B`) are powers of 1000 and binary suffixes (`KiB`, `MiB`, `GiB`,
+/// `TiB`) are powers of 1024. A bare number is read as bytes.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ByteSize(pub u64);
+
+impl FromStr for ByteSize {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(size: &str) -> Result<Self, Self::Err> {
+        let size = size.trim();
+        let split = size
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(size.len());
+        let (number, unit) = size.split_at(split);
+        let number = number
+            .parse::<f64>()
+            .map_err(|_| anyhow!("Invalid size '{}'.", size))?;
+        let multiplier = match unit.trim() {
+            "" | "B" => 1u64,
+            "K" | "KB" | "k" | "kB" => 1_000,
+            "M" | "MB" => 1_000_000,
+            "G" | "GB" => 1_000_000_000,
+            "T" | "TB" => 1_000_000_000_000,
+            "KiB" => 1 << 10,
+            "MiB" => 1 << 20,
+            "GiB" => 1 << 30,
+            "TiB" => 1 << 40,
+            unit => bail!("Unknown size unit '{}'.", unit),
+        };
+        Ok(Self((number * multiplier as f64) as u64))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", HumanBytes(self.0))
+    }
+}
+
+/// Size in memory of an affine BN254 G1 point: two 32-byte coordinates and the infinity flag,
+/// padded to the alignment of the coordinates
+pub const G1_SIZE: u64 = 72;
+
+/// Size in memory of an affine BN254 G2 point: two 64-byte coordinates and the infinity flag,
+/// padded to the alignment of the coordinates
+pub const G2_SIZE: u64 = 136;
+
+/// Returns the size in memory of a subaccumulator with `powers` powers of tau. It holds twice as
+/// many powers of tau in G1, but one, as in G2, the alpha and beta multiples in G1 and the beta
+/// element in G2.
+#[inline]
+pub fn accumulator_size(powers: u64) -> u64 {
+    (4 * powers - 1) * G1_SIZE + (powers + 1) * G2_SIZE
+}
+
+/// Returns the memory needed to verify one round with subaccumulators of `powers` powers of tau,
+/// which holds two of them at once.
+#[inline]
+pub fn verification_size(powers: u64) -> u64 {
+    2 * accumulator_size(powers)
+}
+
+/// Returns the largest base-two logarithm of the number of powers, between `min_log_powers` and
+/// `log_powers`, whose verification fits in `budget`. Without a budget, `log_powers` is returned
+/// unchanged.
+#[inline]
+pub fn fit_powers(log_powers: u32, min_log_powers: u32, budget: Option<ByteSize>) -> Result<u32> {
+    let budget = match budget {
+        Some(budget) => budget,
+        _ => return Ok(log_powers),
+    };
+    match (min_log_powers..=log_powers)
+        .rev()
+        .find(|log_powers| verification_size(1 << log_powers) <= budget.0)
+    {
+        Some(log_powers) => Ok(log_powers),
+        _ => bail!(
+            "Verifying 2^{} powers needs {} but the memory budget is {}.",
+            min_log_powers,
+            ByteSize(verification_size(1 << min_log_powers)),
+            budget
+        ),
+    }
+}
+
+/// Size in memory of a projective BN254 G1 point: three 32-byte coordinates
+pub const G1_PROJECTIVE_SIZE: u64 = 96;
+
+/// Share of the available memory the verification is tuned to by default, as a fraction, leaving
+/// the rest to the page cache the ceremony files are read through and to the other processes
+pub const AUTO_MEMORY_SHARE: (u64, u64) = (3, 4);
+
+/// Returns the default memory budget of the verification: [`AUTO_MEMORY_SHARE`] of the
+/// `available` memory, if it is known.
+#[inline]
+pub fn auto_budget(available: Option<u64>) -> Option<ByteSize> {
+    available.map(|available| ByteSize(available / AUTO_MEMORY_SHARE.1 * AUTO_MEMORY_SHARE.0))
+}
+
+/// Returns an estimate of the scratch memory of a thread running a multi-scalar multiplication
+/// over `powers` points: the buckets of every window of the Pippenger algorithm, whose width grows
+/// with the logarithm of the number of points.
+#[inline]
+pub fn msm_scratch_size(powers: u64) -> u64 {
+    let width = (63 - powers.max(1).leading_zeros() as u64) * 69 / 100 + 2;
+    let windows = 254u64.div_ceil(width);
+    (1 << width) * windows * G1_PROJECTIVE_SIZE
+}
+
+/// Verification Parameters
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Tuning {
+    /// Base-two logarithm of the number of powers verified in each round
+    pub log_powers: u32,
+
+    /// Number of threads of the verification
+    pub threads: usize,
+}
+
+/// Tunes the verification to `budget`: the largest number of powers between `min_log_powers` and
+/// `log_powers` whose subaccumulators fit, as with [`fit_powers`], then as many `threads` as the
+/// rest of the budget holds the scratch memory of, up to `cores`. Given `threads` are kept, and
+/// without a budget every core is used.
+#[inline]
+pub fn tune(
+    budget: Option<ByteSize>,
+    log_powers: u32,
+    min_log_powers: u32,
+    threads: Option<usize>,
+    cores: usize,
+) -> Result<Tuning> {
+    let log_powers = fit_powers(log_powers, min_log_powers, budget)?;
+    let threads = match (threads, budget) {
+        (Some(threads), _) => threads,
+        (_, Some(budget)) => {
+            let rest = budget.0 - verification_size(1 << log_powers);
+            ((rest / msm_scratch_size(1 << log_powers)) as usize).clamp(1, cores.max(1))
+        }
+        _ => cores.max(1),
+    };
+    Ok(Tuning {
+        log_powers,
+        threads,
+    })
+}
+
+/// Bounds of the default size of the chunks files are hashed by
+pub const HASH_CHUNK_SIZES: (usize, usize) = (1 << 20, 1 << 30);
+
+/// Bounds of the default size of the buffers downloads are written through
+pub const DOWNLOAD_BUFFER_SIZES: (usize, usize) = (1 << 16, 1 << 23);
+
+/// Returns the memory available for new allocations without swapping, or `None` if it is
+/// unknown. It is read from `/proc/meminfo` on Linux and unknown elsewhere.
+#[inline]
+pub fn available_memory() -> Option<u64> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    meminfo.lines().find_map(|line| {
+        let kilobytes = line
+            .strip_prefix("MemAvailable:")?
+            .trim()
+            .strip_suffix("kB")?;
+        Some(kilobytes.trim().parse::<u64>().ok()? * 1024)
+    })
+}
+
+/// Returns the largest power of two at most `fraction` of the `available` memory, within
+/// `bounds`, or `fallback` if the available memory is unknown.
+#[inline]
+fn share_of(
+    available: Option<u64>,
+    fraction: u64,
+    bounds: (usize, usize),
+    fallback: usize,
+) -> usize {
+    match available {
+        Some(available) => {
+            let share = (available / fraction).max(1);
+            let power = 1u64 << (63 - share.leading_zeros());
+            (power.min(bounds.1 as u64) as usize).max(bounds.0)
+        }
+        _ => fallback,
+    }
+}
+
+/// Returns the default size of the chunks files are hashed by: a sixty-fourth of the `available`
+/// memory, between 1 MiB and 1 GiB, and 16 MiB if the available memory is unknown. Streamed files
+/// hold a whole chunk in memory while mapped ones only go through the page cache.
+#[inline]
+pub fn hash_chunk_size(available: Option<u64>) -> usize {
+    share_of(available, 64, HASH_CHUNK_SIZES, 1 << 24)
+}
+
+/// Returns the default size of the buffers downloads are written through: a four-thousandth of
+/// the `available` memory, between 64 KiB and 8 MiB, and 1 MiB if the available memory is unknown.
+/// Larger buffers turn the small chunks received from the network into fewer, larger writes.
+#[inline]
+pub fn download_buffer_size(available: Option<u64>) -> usize {
+    share_of(available, 4096, DOWNLOAD_BUFFER_SIZES, 1 << 20)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that budgets are parsed and select the largest power count that fits.
+    #[test]
+    fn budgets_limit_powers() {
+        assert_eq!("16G".parse::<ByteSize>().unwrap(), ByteSize(16_000_000_000));
+        assert_eq!("1.5GiB".parse::<ByteSize>().unwrap(), ByteSize(3 << 29));
+        assert!("lots".parse::<ByteSize>().is_err());
+        assert_eq!(fit_powers(19, 16, None).unwrap(), 19);
+        assert_eq!(
+            fit_powers(19, 16, Some("16G".parse().unwrap())).unwrap(),
+            19
+        );
+        let budget = ByteSize(verification_size(1 << 17));
+        assert_eq!(fit_powers(19, 16, Some(budget)).unwrap(), 17);
+        assert!(fit_powers(19, 16, Some(ByteSize(1 << 20))).is_err());
+    }
+
+    /// Checks that tuning picks the largest number of powers which fits, then as many threads as
+    /// the rest of the budget holds, and keeps the given thread count.
+    #[test]
+    fn tuning_fits_memory() {
+        assert_eq!(
+            msm_scratch_size(1 << 19),
+            (1 << 15) * 17 * G1_PROJECTIVE_SIZE
+        );
+        assert_eq!(auto_budget(Some(16 << 30)), Some(ByteSize(12 << 30)));
+        let budget = ByteSize(verification_size(1 << 20) + 3 * msm_scratch_size(1 << 20));
+        assert_eq!(
+            tune(Some(budget), 22, 16, None, 8).unwrap(),
+            Tuning {
+                log_powers: 20,
+                threads: 3
+            }
+        );
+        assert_eq!(tune(Some(budget), 22, 16, None, 2).unwrap().threads, 2);
+        assert_eq!(tune(Some(budget), 22, 16, Some(6), 8).unwrap().threads, 6);
+        assert_eq!(
+            tune(None, 19, 16, None, 4).unwrap(),
+            Tuning {
+                log_powers: 19,
+                threads: 4
+            }
+        );
+        assert!(tune(Some(ByteSize(1 << 20)), 22, 16, None, 8).is_err());
+    }
+
+    /// Checks that the default chunk and buffer sizes are powers of two growing with the
+    /// available memory within their bounds.
+    #[test]
+    fn chunk_sizes_follow_memory() {
+        assert_eq!(hash_chunk_size(None), 1 << 24);
+        assert_eq!(hash_chunk_size(Some(1 << 20)), HASH_CHUNK_SIZES.0);
+        assert_eq!(hash_chunk_size(Some(3 << 32)), 1 << 27);
+        assert_eq!(hash_chunk_size(Some(1 << 40)), HASH_CHUNK_SIZES.1);
+        assert_eq!(download_buffer_size(None), 1 << 20);
+        assert_eq!(download_buffer_size(Some(1 << 30)), 1 << 18);
+        assert_eq!(download_buffer_size(Some(1 << 50)), DOWNLOAD_BUFFER_SIZES.1);
+        assert_eq!(download_buffer_size(Some(0)), DOWNLOAD_BUFFER_SIZES.0);
+    }
+}
@@ -0,0 +1,191 @@
+//! Chunked Merkle Hash Manifests
+//!
+//! Every file is split into chunks of a fixed size which are hashed separately, and the chunk
+//! hashes are combined into a Merkle root. The chunk manifest is saved next to the file, in
+//! [`chunks_path`], so that a corrupt file can be narrowed down to the chunks which changed and only
+//! those have to be validated again or downloaded again.
+
+use crate::{atomic, hash::Hash64, into_array_unchecked, Result};
+use blake2::{Blake2b512, Digest};
+use core::ops::Range;
+use indicatif::ProgressBar;
+use memmap::Mmap;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Default chunk size
+pub const CHUNK_SIZE: u64 = 1 << 30;
+
+/// Domain separator of the leaves of the Merkle tree
+const LEAF_PREFIX: u8 = 0;
+
+/// Domain separator of the inner nodes of the Merkle tree
+const NODE_PREFIX: u8 = 1;
+
+/// Hashes the chunk `data` into a leaf of the Merkle tree.
+#[inline]
+pub fn hash_chunk(data: &[u8]) -> Hash64 {
+    let mut hasher = Blake2b512::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(data);
+    Hash64(into_array_unchecked(hasher.finalize()))
+}
+
+/// Computes the Merkle root of `leaves`. A node without a sibling is carried up to the next level
+/// unchanged, and the root of an empty tree is the hash of the empty chunk.
+#[inline]
+pub fn merkle_root(leaves: &[Hash64]) -> Hash64 {
+    if leaves.is_empty() {
+        return hash_chunk(&[]);
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Blake2b512::new();
+                    hasher.update([NODE_PREFIX]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    Hash64(into_array_unchecked(hasher.finalize()))
+                }
+                [node] => *node,
+                _ => unreachable!("Chunks of two elements have one or two elements."),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Returns the path of the chunk manifest of the file at `path`.
+#[inline]
+pub fn chunks_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut chunks_path = path.as_ref().as_os_str().to_owned();
+    chunks_path.push("_chunks.json");
+    chunks_path.into()
+}
+
+/// Chunk Manifest
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ChunkManifest {
+    /// Size of the file in bytes
+    pub size: u64,
+
+    /// Size of every chunk but the last one
+    pub chunk_size: u64,
+
+    /// Blake2b hash of the whole file, as saved by the `hasher` binary
+    pub blake2b: Hash64,
+
+    /// Merkle root of the chunk hashes
+    pub root: Hash64,
+
+    /// Hashes of the chunks, see [`hash_chunk`]
+    pub chunks: Vec<Hash64>,
+}
+
+impl ChunkManifest {
+    /// Computes the chunk manifest of the file mapped by `map`, hashing the whole file in the same
+    /// pass and reporting the number of bytes hashed so far to `progress`, if any.
+    #[inline]
+    pub fn compute(map: &Mmap, chunk_size: u64, progress: Option<&ProgressBar>) -> Self {
+        let mut hasher = Blake2b512::new();
+        let mut leaves = Vec::new();
+        for chunk in map.chunks(chunk_size as usize) {
+            std::thread::scope(|scope| {
+                let leaf = scope.spawn(|| hash_chunk(chunk));
+                hasher.update(chunk);
+                leaves.push(leaf.join().expect("Hashing a chunk does not panic."));
+            });
+            if let Some(progress) = progress {
+                progress.inc(chunk.len() as u64);
+            }
+        }
+        Self {
+            size: map.len() as u64,
+            chunk_size,
+            blake2b: Hash64(into_array_unchecked(hasher.finalize())),
+            root: merkle_root(&leaves),
+            chunks: leaves,
+        }
+    }
+
+    /// Returns the byte range covered by chunk number `index`.
+    #[inline]
+    pub fn chunk_range(&self, index: usize) -> Range<u64> {
+        let start = index as u64 * self.chunk_size;
+        start..(start + self.chunk_size).min(self.size)
+    }
+
+    /// Checks that the chunk hashes of `self` add up to its Merkle root.
+    #[inline]
+    pub fn is_consistent(&self) -> bool {
+        merkle_root(&self.chunks) == self.root
+    }
+
+    /// Returns the indices of the chunks of the file mapped by `map` which do not match `self`.
+    /// Chunks missing from a truncated file count as mismatched.
+    #[inline]
+    pub fn mismatched_chunks(&self, map: &Mmap) -> Vec<usize> {
+        (0..self.chunks.len())
+            .filter(|&index| {
+                let range = self.chunk_range(index);
+                match map.get(range.start as usize..range.end as usize) {
+                    Some(data) => hash_chunk(data) != self.chunks[index],
+                    _ => true,
+                }
+            })
+            .collect()
+    }
+
+    /// Loads the chunk manifest from the JSON file at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Saves the chunk manifest as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that a corrupt chunk is located and that the root covers every chunk.
+    #[test]
+    fn locate_corrupt_chunk() {
+        let path = std::env::temp_dir().join("ppot-verifier-merkle-test");
+        let mut data = (0..100u8).collect::<Vec<_>>();
+        fs::write(&path, &data).unwrap();
+        let map = unsafe { Mmap::map(&fs::File::open(&path).unwrap()).unwrap() };
+        let manifest = ChunkManifest::compute(&map, 16, None);
+        assert_eq!(manifest.chunks.len(), 7);
+        assert_eq!(manifest.chunk_range(6), 96..100);
+        assert!(manifest.is_consistent());
+        assert_eq!(manifest.blake2b.as_ref(), &Blake2b512::digest(&data)[..]);
+        drop(map);
+        data[40] ^= 1;
+        fs::write(&path, &data).unwrap();
+        let map = unsafe { Mmap::map(&fs::File::open(&path).unwrap()).unwrap() };
+        assert_eq!(manifest.mismatched_chunks(&map), vec![2]);
+        drop(map);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,312 @@
+//! Mock HTTP Server
+//!
+//! Serves files from memory on a local port with the range semantics of the blob store hosting the
+//! ceremony, so that the downloads are tested end to end without the network. Each file can be
+//! made to misbehave like the servers met in practice with a [`Behavior`]: answering with
+//! `429 Too Many Requests` for a while, ignoring the [`RANGE`] header, dropping the connection in
+//! the middle of the body or holding another version than the one a download started from.
+//! Requests for several ranges are answered with a `multipart/byteranges` body. Every request is
+//! recorded so that tests can check where a download resumed.
+
+use crate::download::ByteRange;
+use axum::{
+    body::{boxed, Bytes, Empty, Full, StreamBody},
+    extract::{Extension, Path},
+    http::{
+        header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, IF_RANGE, RANGE},
+        HeaderMap, StatusCode,
+    },
+    response::Response,
+    routing::get,
+    Router, Server,
+};
+use core::{ops::Range, time::Duration};
+use futures::{stream, StreamExt};
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::oneshot;
+
+/// Behavior of a Mock File
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Behavior {
+    /// Number of requests answered with `429 Too Many Requests` before the file is served
+    pub throttled: usize,
+
+    /// Ignores the [`RANGE`] header and always sends the whole file
+    pub ignore_range: bool,
+
+    /// Entity tag of the file, sent in the [`ETAG`] header and compared with [`IF_RANGE`]
+    pub etag: Option<String>,
+
+    /// Drops the connection after this many bytes of the body of the first response
+    pub interrupt_after: Option<usize>,
+}
+
+/// Request received by the [`MockServer`]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MockRequest {
+    /// Name of the requested file
+    pub name: String,
+
+    /// Value of the [`RANGE`] header, if any
+    pub range: Option<String>,
+
+    /// Value of the [`IF_RANGE`] header, if any
+    pub if_range: Option<String>,
+}
+
+/// File served by the [`MockServer`]
+#[derive(Clone, Debug)]
+struct MockFile {
+    /// Contents of the file
+    data: Bytes,
+
+    /// Behavior of the server for the file
+    behavior: Behavior,
+
+    /// Number of requests answered so far
+    requests: usize,
+}
+
+/// State shared by the handlers of the [`MockServer`]
+#[derive(Debug, Default)]
+struct MockState {
+    /// Files by name
+    files: HashMap<String, MockFile>,
+
+    /// Requests received so far, in order
+    requests: Vec<MockRequest>,
+}
+
+/// Shared State of the [`MockServer`]
+type Shared = Arc<Mutex<MockState>>;
+
+/// Mock HTTP Server
+///
+/// The server is stopped when dropped.
+pub struct MockServer {
+    /// Address the server listens on
+    address: SocketAddr,
+
+    /// State shared with the handlers
+    state: Shared,
+
+    /// Sender stopping the server
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl MockServer {
+    /// Starts serving `files`, given as their name, contents and behavior, on a free local port.
+    /// Must be called from within a Tokio runtime.
+    #[inline]
+    pub fn start<I>(files: I) -> Self
+    where
+        I: IntoIterator<Item = (&'static str, Vec<u8>, Behavior)>,
+    {
+        let state = Shared::default();
+        state.lock().unwrap().files = files
+            .into_iter()
+            .map(|(name, data, behavior)| {
+                let file = MockFile {
+                    data: data.into(),
+                    behavior,
+                    requests: 0,
+                };
+                (name.to_string(), file)
+            })
+            .collect();
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Unable to bind a local port.");
+        let address = listener.local_addr().expect("The listener is bound.");
+        let router = Router::new()
+            .route("/:name", get(serve_file))
+            .layer(Extension(state.clone()));
+        let (shutdown, stopped) = oneshot::channel();
+        let server = Server::from_tcp(listener)
+            .expect("The listener is bound.")
+            .serve(router.into_make_service())
+            .with_graceful_shutdown(async {
+                let _ = stopped.await;
+            });
+        tokio::spawn(server);
+        Self {
+            address,
+            state,
+            shutdown: Some(shutdown),
+        }
+    }
+
+    /// Returns the URL of the file called `name`.
+    #[inline]
+    pub fn url(&self, name: &str) -> String {
+        format!("http://{}/{}", self.address, name)
+    }
+
+    /// Returns the requests received so far, in order.
+    #[inline]
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// Parses a [`RANGE`] header, such as `bytes=0-63,-1024`, into its byte ranges.
+#[inline]
+fn parse_ranges(range: &str) -> Option<Vec<ByteRange>> {
+    range
+        .strip_prefix("bytes=")?
+        .split(',')
+        .map(|range| {
+            let (start, end) = range.trim().split_once('-')?;
+            Some(match (start, end) {
+                ("", length) => ByteRange::Suffix(length.parse().ok()?),
+                (start, "") => ByteRange::From(start.parse().ok()?),
+                (start, end) => ByteRange::Bounded {
+                    start: start.parse().ok()?,
+                    end: end.parse().ok()?,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Boundary of the `multipart/byteranges` bodies sent by the [`MockServer`]
+const BOUNDARY: &str = "MOCK_BOUNDARY";
+
+/// Returns the `multipart/byteranges` body holding the `ranges` of `data`.
+#[inline]
+fn multipart(data: &Bytes, ranges: &[Range<u64>]) -> Bytes {
+    let size = data.len();
+    let mut body = Vec::new();
+    for range in ranges {
+        body.extend_from_slice(
+            format!(
+                "--{}\r\nContent-Type: application/octet-stream\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                BOUNDARY,
+                range.start,
+                range.end - 1,
+                size
+            )
+            .as_bytes(),
+        );
+        body.extend_from_slice(&data[range.start as usize..range.end as usize]);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", BOUNDARY).as_bytes());
+    body.into()
+}
+
+/// Handles `GET /{name}`.
+#[inline]
+async fn serve_file(
+    Extension(state): Extension<Shared>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let (range, if_range) = (header(RANGE), header(IF_RANGE));
+    let mut state = state.lock().unwrap();
+    state.requests.push(MockRequest {
+        name: name.clone(),
+        range: range.clone(),
+        if_range: if_range.clone(),
+    });
+    let file = match state.files.get_mut(&name) {
+        Some(file) => file,
+        _ => return status(StatusCode::NOT_FOUND),
+    };
+    file.requests += 1;
+    if file.requests <= file.behavior.throttled {
+        return status(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let size = file.data.len() as u64;
+    let unchanged = match (&if_range, &file.behavior.etag) {
+        (Some(if_range), Some(etag)) => if_range == etag,
+        (Some(_), None) => false,
+        _ => true,
+    };
+    let ranges = range
+        .filter(|_| !file.behavior.ignore_range && unchanged)
+        .and_then(|range| parse_ranges(&range));
+    let mut response = Response::builder();
+    if let Some(etag) = &file.behavior.etag {
+        response = response.header(ETAG, etag);
+    }
+    let body = match ranges {
+        Some(ranges) => {
+            let ranges = ranges
+                .iter()
+                .filter_map(|range| range.resolve(size))
+                .collect::<Vec<_>>();
+            match ranges.as_slice() {
+                [] => {
+                    return response
+                        .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                        .header(CONTENT_RANGE, format!("bytes */{}", size))
+                        .body(boxed(Empty::<Bytes>::new()))
+                        .unwrap();
+                }
+                [range] => {
+                    response = response.status(StatusCode::PARTIAL_CONTENT).header(
+                        CONTENT_RANGE,
+                        format!("bytes {}-{}/{}", range.start, range.end - 1, size),
+                    );
+                    file.data.slice(range.start as usize..range.end as usize)
+                }
+                ranges => {
+                    response = response.status(StatusCode::PARTIAL_CONTENT).header(
+                        CONTENT_TYPE,
+                        format!("multipart/byteranges; boundary={}", BOUNDARY),
+                    );
+                    multipart(&file.data, ranges)
+                }
+            }
+        }
+        _ => {
+            response = response.status(StatusCode::OK);
+            file.data.clone()
+        }
+    };
+    let response = response.header(CONTENT_LENGTH, body.len().to_string());
+    match file.behavior.interrupt_after.take() {
+        Some(sent) if sent < body.len() => {
+            // The connection is only dropped once the headers and the first bytes were flushed,
+            // otherwise the client never sees the response.
+            let chunks =
+                stream::once(async move { Ok(body.slice(..sent)) }).chain(stream::once(async {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    Err(io::Error::new(
+                        io::ErrorKind::ConnectionReset,
+                        "Interrupted by the mock server",
+                    ))
+                }));
+            response.body(boxed(StreamBody::new(chunks))).unwrap()
+        }
+        _ => response.body(boxed(Full::new(body))).unwrap(),
+    }
+}
+
+/// Returns an empty response with `status`.
+#[inline]
+fn status(status: StatusCode) -> Response {
+    Response::builder()
+        .status(status)
+        .body(boxed(Empty::<Bytes>::new()))
+        .unwrap()
+}
@@ -0,0 +1,88 @@
+//! Node.js Bindings
+//!
+//! With the `node` feature, the `cdylib` of the crate is a Node.js addon, built with `napi`, which
+//! lets JavaScript ceremony dashboards download the files of the ceremony and verify them without
+//! running the binaries. Every function returns a promise: downloads run on the runtime of the
+//! addon and hashing and verification on its blocking threads, so that they never stall the event
+//! loop. Errors reject the promise with their message.
+
+use crate::{
+    accumulator::verify_round_on,
+    curve::CurveKind,
+    download::{download_file, DownloadOptions},
+    hash_file,
+    input::InputOptions,
+    progress::NoProgress,
+    verify_hash_chain, Result,
+};
+use anyhow::{anyhow, ensure};
+use clap::ValueEnum;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use tokio::task;
+
+/// Runs `f` on the blocking threads of the runtime of the addon.
+#[inline]
+async fn blocking<T, F>(f: F) -> napi::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    Ok(task::spawn_blocking(f)
+        .await
+        .map_err(anyhow::Error::from)??)
+}
+
+/// Downloads the file at `path` from the first of the mirrors `urls` that succeeds, resuming any
+/// partial download already at `path`.
+#[napi]
+pub async fn download(urls: Vec<String>, path: String) -> napi::Result<()> {
+    let options = DownloadOptions::default();
+    download_file(&NoProgress, &options.client()?, &urls, path, &options).await?;
+    Ok(())
+}
+
+/// Computes the Blake2b hash of the file at `path`.
+#[napi(js_name = "calculateHash")]
+pub async fn calculate_hash(path: String) -> napi::Result<Buffer> {
+    blocking(move || Ok(hash_file(path)?.0.to_vec().into())).await
+}
+
+/// Checks the hash chain between the consecutive files at `paths`, where each file asserts the hash
+/// of the one before it in its header, resolving to the index of the first file whose hash does
+/// not match, or to `null` if the chain holds.
+#[napi(js_name = "verifyHashChain")]
+pub async fn verify_hash_chain_of(paths: Vec<String>) -> napi::Result<Option<u32>> {
+    blocking(move || Ok(verify_hash_chain(&paths)?.map(|index| index as u32))).await
+}
+
+/// Verifies the round turning the challenge file at `challenge` into the response file at
+/// `response`, both over `curve`, `bn254` or `bls12-381`, with `2^fileLogPowers` powers of tau, on
+/// their first `2^logPowers` powers. The promise is rejected if the round is invalid.
+#[napi(js_name = "verifyRound")]
+pub async fn verify_round(
+    challenge: String,
+    response: String,
+    curve: String,
+    file_log_powers: u32,
+    log_powers: u32,
+) -> napi::Result<()> {
+    let curve = CurveKind::from_str(&curve, true).map_err(|err| anyhow!(err))?;
+    blocking(move || {
+        ensure!(
+            log_powers <= file_log_powers && file_log_powers < usize::BITS,
+            "Unable to verify 2^{} powers of files with 2^{}",
+            log_powers,
+            file_log_powers
+        );
+        let input = InputOptions::default();
+        verify_round_on(
+            curve,
+            &input.open(challenge)?.into_mmap()?,
+            &input.open(response)?.into_mmap()?,
+            1 << file_log_powers,
+            1 << log_powers,
+        )
+    })
+    .await
+}
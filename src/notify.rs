@@ -0,0 +1,297 @@
+//! Notifications
+//!
+//! The binaries post the key events of a run to the hooks listed in the `notifications` of the
+//! [`Config`](crate::config::Config), for instance:
+//!
+//! ```toml
+//! [[notifications]]
+//! url = "https://hooks.slack.com/services/..."
+//! format = "slack"
+//! events = ["hash_mismatch", "round_failed"]
+//! ```
+//!
+//! Hooks without `events` receive every event. Notifications are best effort: a hook which cannot
+//! be reached is reported in the log and never stops the run.
+
+use crate::hash::Hash64;
+use core::{fmt, time::Duration};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+/// Timeout of the requests to the hooks
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Event Kind
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A file finished downloading
+    DownloadComplete,
+
+    /// The hash of a file differs from the hash asserted by the next file of the ceremony
+    HashMismatch,
+
+    /// The verification of a round failed
+    RoundFailed,
+
+    /// The verification of every round finished
+    RunComplete,
+
+    /// A stored file changed or disappeared since it was hashed
+    FileCorrupted,
+}
+
+/// Event
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A file finished downloading
+    DownloadComplete {
+        /// Local file name
+        path: String,
+    },
+
+    /// The hash of a file differs from the hash asserted by the next file of the ceremony
+    HashMismatch {
+        /// File whose hash was computed
+        hashed: String,
+
+        /// File whose header asserts the hash of `hashed`
+        asserted_by: String,
+    },
+
+    /// The verification of a round failed
+    RoundFailed {
+        /// Index of the round
+        round: usize,
+
+        /// Verification error
+        error: String,
+    },
+
+    /// The verification of every round finished
+    RunComplete {
+        /// Number of rounds verified successfully
+        verified: usize,
+
+        /// Rounds whose verification failed
+        failed_rounds: Vec<usize>,
+    },
+
+    /// A stored file changed or disappeared since it was hashed
+    FileCorrupted {
+        /// Local file name
+        path: String,
+
+        /// Blake2b hash recorded when the file was hashed
+        expected: Hash64,
+
+        /// Blake2b hash of the file now, `None` if it is missing
+        actual: Option<Hash64>,
+    },
+}
+
+impl Event {
+    /// Returns the kind of the event.
+    #[inline]
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::DownloadComplete { .. } => EventKind::DownloadComplete,
+            Self::HashMismatch { .. } => EventKind::HashMismatch,
+            Self::RoundFailed { .. } => EventKind::RoundFailed,
+            Self::RunComplete { .. } => EventKind::RunComplete,
+            Self::FileCorrupted { .. } => EventKind::FileCorrupted,
+        }
+    }
+}
+
+impl fmt::Display for Event {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DownloadComplete { path } => write!(f, "PPoT: downloaded {}", path),
+            Self::HashMismatch {
+                hashed,
+                asserted_by,
+            } => write!(
+                f,
+                "PPoT: the hash of {} does not match the hash asserted by {}",
+                hashed, asserted_by
+            ),
+            Self::RoundFailed { round, error } => {
+                write!(f, "PPoT: verification of round {} failed: {}", round, error)
+            }
+            Self::RunComplete {
+                verified,
+                failed_rounds,
+            } if failed_rounds.is_empty() => {
+                write!(f, "PPoT: verified {} rounds, all valid", verified)
+            }
+            Self::RunComplete {
+                verified,
+                failed_rounds,
+            } => write!(
+                f,
+                "PPoT: verified {} rounds, rounds {:?} failed",
+                verified, failed_rounds
+            ),
+            Self::FileCorrupted {
+                path, actual: None, ..
+            } => write!(f, "PPoT: {} disappeared since it was hashed", path),
+            Self::FileCorrupted { path, .. } => {
+                write!(f, "PPoT: {} changed on disk since it was hashed", path)
+            }
+        }
+    }
+}
+
+/// Hook Format
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookFormat {
+    /// The event as a JSON object, with its message in the `message` field
+    #[default]
+    Json,
+
+    /// Slack incoming webhook
+    Slack,
+
+    /// Discord webhook
+    Discord,
+}
+
+/// Notification Hook
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Hook {
+    /// URL the events are posted to
+    pub url: String,
+
+    /// Format of the posted events
+    #[serde(default)]
+    pub format: HookFormat,
+
+    /// Events posted to the hook, every event if empty
+    #[serde(default)]
+    pub events: Vec<EventKind>,
+}
+
+impl Hook {
+    /// Returns `true` if `event` is posted to the hook.
+    #[inline]
+    pub fn accepts(&self, event: &Event) -> bool {
+        self.events.is_empty() || self.events.contains(&event.kind())
+    }
+
+    /// Returns the body posted to the hook for `event`.
+    #[inline]
+    pub fn payload(&self, event: &Event) -> Value {
+        match self.format {
+            HookFormat::Json => {
+                let mut payload = json!(event);
+                payload["message"] = event.to_string().into();
+                payload
+            }
+            HookFormat::Slack => json!({ "text": event.to_string() }),
+            HookFormat::Discord => json!({ "content": event.to_string() }),
+        }
+    }
+}
+
+/// Notifier
+#[derive(Clone, Debug, Default)]
+pub struct Notifier {
+    /// HTTP Client
+    client: Client,
+
+    /// Notification Hooks
+    hooks: Vec<Hook>,
+}
+
+impl Notifier {
+    /// Builds a [`Notifier`] posting to `hooks`.
+    #[inline]
+    pub fn new(hooks: Vec<Hook>) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(NOTIFY_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            hooks,
+        }
+    }
+
+    /// Returns `true` if there is no hook to post to.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Posts `event` to every hook accepting it, reporting the failures in the log.
+    #[inline]
+    pub async fn notify(&self, event: &Event) {
+        for hook in self.hooks.iter().filter(|hook| hook.accepts(event)) {
+            let result = self
+                .client
+                .post(&hook.url)
+                .json(&hook.payload(event))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+            if let Err(err) = result {
+                warn!("Unable to notify '{}': {}", hook.url, err);
+            }
+        }
+    }
+
+    /// Posts `event` to every hook accepting it from outside of an asynchronous runtime.
+    #[inline]
+    pub fn notify_blocking(&self, event: &Event) {
+        if !self.hooks.iter().any(|hook| hook.accepts(event)) {
+            return;
+        }
+        match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(runtime) => runtime.block_on(self.notify(event)),
+            Err(err) => warn!("Unable to send the notifications: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the hooks filter the events and format them for their service.
+    #[test]
+    fn hook_payloads() {
+        let hooks: Vec<Hook> = serde_json::from_value(json!([
+            { "url": "https://example.com/hook" },
+            { "url": "https://example.com/slack", "format": "slack", "events": ["round_failed"] },
+        ]))
+        .unwrap();
+        let event = Event::RoundFailed {
+            round: 40,
+            error: "invalid proof".into(),
+        };
+        let message = "PPoT: verification of round 40 failed: invalid proof";
+        assert_eq!(
+            hooks[0].payload(&event),
+            json!({
+                "event": "round_failed",
+                "round": 40,
+                "error": "invalid proof",
+                "message": message,
+            })
+        );
+        assert_eq!(hooks[1].payload(&event), json!({ "text": message }));
+        let download = Event::DownloadComplete {
+            path: "challenge_0040".into(),
+        };
+        assert!(hooks[0].accepts(&download));
+        assert!(!hooks[1].accepts(&download));
+    }
+}
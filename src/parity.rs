@@ -0,0 +1,669 @@
+//! Reed-Solomon Parity
+//!
+//! The hosts of the ceremony files may not exist anymore when the scrubber finds a flipped bit in
+//! an archived file, so large files can be protected by parity data, in the spirit of PAR2, to be
+//! repaired locally. A file is split into blocks of a fixed size whose hashes are recorded, and the
+//! blocks are grouped into stripes of `d` data blocks protected by `p` parity blocks of a
+//! systematic Reed-Solomon code over `GF(2^8)`, which rebuilds any `p` blocks of a stripe from the
+//! others. Stripes interleave the blocks, the stripe `s` of a file of `S` stripes holding the blocks
+//! `s`, `s + S`, `s + 2S` and so on, so that a damaged run of `p * S` consecutive blocks, `p / d` of
+//! the file, is still repairable. The block hashes tell which blocks to rebuild.
+//!
+//! The parity file starts with a [`ParityHeader`], followed by the hashes of the data blocks, the
+//! hashes of the parity blocks and the parity blocks, stripe after stripe. It is saved next to the
+//! chunk manifest of the file, in [`parity_path`].
+
+use crate::{atomic, hash::Hash64, hash_file, into_array_unchecked, merkle::hash_chunk, Result};
+use anyhow::{bail, ensure};
+use core::ops::Range;
+use memmap::{Mmap, MmapMut};
+use rayon::prelude::*;
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+/// Magic bytes starting the parity files
+pub const PARITY_MAGIC: &[u8; 8] = b"ppot-par";
+
+/// Version of the parity file format
+pub const PARITY_VERSION: u32 = 1;
+
+/// Default size of the blocks
+pub const PARITY_BLOCK_SIZE: u32 = 1 << 20;
+
+/// Largest number of data blocks of a stripe
+pub const PARITY_DATA_BLOCKS: usize = 128;
+
+/// Default size of the parity data, as a percentage of the size of the file
+pub const PARITY_REDUNDANCY: u32 = 5;
+
+/// Default size from which files are protected by parity data
+pub const PARITY_MIN_SIZE: u64 = 1 << 30;
+
+/// Size of the hash of a block
+const BLOCK_HASH_SIZE: usize = 64;
+
+/// Number of stripes encoded at once by every thread
+const STRIPES_PER_THREAD: usize = 4;
+
+/// Returns the path of the parity file of the file at `path`.
+#[inline]
+pub fn parity_path<P>(path: P) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    let mut parity_path = path.as_ref().as_os_str().to_owned();
+    parity_path.push(".par");
+    parity_path.into()
+}
+
+/// Field Arithmetic over `GF(2^8)`, with the Reduction Polynomial `x^8 + x^4 + x^3 + x^2 + 1`
+mod gf {
+    /// Exponentials of the generator `2`, twice over so that sums of two logarithms index them
+    const EXP: [u8; 512] = tables().0;
+
+    /// Logarithms in base `2` of the non-zero elements
+    const LOG: [u8; 256] = tables().1;
+
+    /// Builds the tables of exponentials and logarithms.
+    const fn tables() -> ([u8; 512], [u8; 256]) {
+        let (mut exp, mut log) = ([0; 512], [0; 256]);
+        let mut x = 1u16;
+        let mut i = 0;
+        while i < 255 {
+            exp[i] = x as u8;
+            exp[i + 255] = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11d;
+            }
+            i += 1;
+        }
+        (exp, log)
+    }
+
+    /// Multiplies `a` by `b`.
+    #[inline]
+    pub fn mul(a: u8, b: u8) -> u8 {
+        match (a, b) {
+            (0, _) | (_, 0) => 0,
+            _ => EXP[LOG[a as usize] as usize + LOG[b as usize] as usize],
+        }
+    }
+
+    /// Returns the inverse of the non-zero `a`.
+    #[inline]
+    pub fn inv(a: u8) -> u8 {
+        debug_assert!(a != 0, "Zero has no inverse.");
+        EXP[255 - LOG[a as usize] as usize]
+    }
+
+    /// Adds `coefficient` times `input` to `output`, element-wise.
+    #[inline]
+    pub fn mul_add(coefficient: u8, input: &[u8], output: &mut [u8]) {
+        if coefficient == 0 {
+            return;
+        }
+        let table: [u8; 256] = core::array::from_fn(|x| mul(coefficient, x as u8));
+        for (output, input) in output.iter_mut().zip(input) {
+            *output ^= table[*input as usize];
+        }
+    }
+
+    /// Inverts the square `matrix` by Gauss-Jordan elimination, returning `None` if it is
+    /// singular.
+    #[inline]
+    pub fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+        let n = matrix.len();
+        let mut inverse = (0..n)
+            .map(|i| (0..n).map(|j| (i == j) as u8).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        for column in 0..n {
+            let pivot = (column..n).find(|row| matrix[*row][column] != 0)?;
+            matrix.swap(column, pivot);
+            inverse.swap(column, pivot);
+            let scale = inv(matrix[column][column]);
+            for j in 0..n {
+                matrix[column][j] = mul(matrix[column][j], scale);
+                inverse[column][j] = mul(inverse[column][j], scale);
+            }
+            for row in 0..n {
+                let factor = matrix[row][column];
+                if row != column && factor != 0 {
+                    for j in 0..n {
+                        matrix[row][j] ^= mul(factor, matrix[column][j]);
+                        inverse[row][j] ^= mul(factor, inverse[column][j]);
+                    }
+                }
+            }
+        }
+        Some(inverse)
+    }
+}
+
+/// Systematic Reed-Solomon Code
+///
+/// The parity shards are the products of the data shards with a Cauchy matrix, every square
+/// submatrix of which is invertible, so that any `data` of the `data + parity` shards determine
+/// the others.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ReedSolomon {
+    /// Number of data shards
+    data: usize,
+
+    /// Coefficients of the data shards in every parity shard
+    matrix: Vec<Vec<u8>>,
+}
+
+impl ReedSolomon {
+    /// Builds the code of `parity` parity shards for `data` data shards.
+    #[inline]
+    pub fn new(data: usize, parity: usize) -> Result<Self> {
+        ensure!(
+            data > 0 && parity > 0 && data + parity <= 256,
+            "Invalid Reed-Solomon code of {} data and {} parity shards",
+            data,
+            parity
+        );
+        Ok(Self {
+            data,
+            matrix: (0..parity)
+                .map(|j| {
+                    (0..data)
+                        .map(|i| gf::inv((data + j) as u8 ^ i as u8))
+                        .collect()
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns the coefficients of the data shards in the shard at `index`.
+    #[inline]
+    fn row(&self, index: usize) -> Vec<u8> {
+        match index.checked_sub(self.data) {
+            Some(parity) => self.matrix[parity].clone(),
+            _ => (0..self.data).map(|i| (i == index) as u8).collect(),
+        }
+    }
+
+    /// Computes the parity shards of the `data` shards, which have the same size.
+    #[inline]
+    pub fn encode(&self, data: &[&[u8]]) -> Vec<Vec<u8>> {
+        let size = data.first().map_or(0, |shard| shard.len());
+        self.matrix
+            .iter()
+            .map(|row| {
+                let mut parity = vec![0; size];
+                for (coefficient, shard) in row.iter().zip(data) {
+                    gf::mul_add(*coefficient, shard, &mut parity);
+                }
+                parity
+            })
+            .collect()
+    }
+
+    /// Rebuilds the missing data shards among the data shards followed by the parity `shards`.
+    #[inline]
+    pub fn reconstruct(&self, shards: &mut [Option<Vec<u8>>]) -> Result {
+        let available = shards
+            .iter()
+            .enumerate()
+            .filter_map(|(index, shard)| shard.as_ref().map(|_| index))
+            .take(self.data)
+            .collect::<Vec<_>>();
+        ensure!(
+            available.len() == self.data,
+            "{} shards are left of the {} needed",
+            available.len(),
+            self.data
+        );
+        let inverse = match gf::invert(available.iter().map(|index| self.row(*index)).collect()) {
+            Some(inverse) => inverse,
+            _ => bail!("The shards do not determine the data"),
+        };
+        let size = shards[available[0]].as_ref().map_or(0, Vec::len);
+        let missing = (0..self.data)
+            .filter(|index| shards[*index].is_none())
+            .collect::<Vec<_>>();
+        for missing in missing {
+            let mut shard = vec![0; size];
+            for (coefficient, index) in inverse[missing].iter().zip(&available) {
+                if let Some(available) = &shards[*index] {
+                    gf::mul_add(*coefficient, available, &mut shard);
+                }
+            }
+            shards[missing] = Some(shard);
+        }
+        Ok(())
+    }
+}
+
+/// Header of the Parity Files
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ParityHeader {
+    /// Size of the blocks
+    pub block_size: u32,
+
+    /// Number of data blocks of a stripe
+    pub data_blocks: u32,
+
+    /// Number of parity blocks of a stripe
+    pub parity_blocks: u32,
+
+    /// Size of the protected file
+    pub size: u64,
+
+    /// Blake2b hash of the protected file
+    pub blake2b: Hash64,
+}
+
+impl ParityHeader {
+    /// Size of the header
+    pub const SIZE: usize = PARITY_MAGIC.len() + 4 * 4 + 8 + 64;
+
+    /// Builds the header of the parity data of `redundancy` percent of the file of `size` bytes
+    /// with hash `blake2b`, split into blocks of `block_size` bytes.
+    #[inline]
+    pub fn new(size: u64, blake2b: Hash64, block_size: u32, redundancy: u32) -> Result<Self> {
+        ensure!(block_size > 0, "The blocks must not be empty");
+        ensure!(
+            (1..=100).contains(&redundancy),
+            "The redundancy must be between 1 and 100 percent"
+        );
+        let blocks = size.div_ceil(block_size as u64).max(1) as usize;
+        let data_blocks = blocks.min(PARITY_DATA_BLOCKS);
+        Ok(Self {
+            block_size,
+            data_blocks: data_blocks as u32,
+            parity_blocks: (data_blocks * redundancy as usize).div_ceil(100) as u32,
+            size,
+            blake2b,
+        })
+    }
+
+    /// Returns the number of data blocks of the file.
+    #[inline]
+    pub fn blocks(&self) -> usize {
+        self.size.div_ceil(self.block_size as u64).max(1) as usize
+    }
+
+    /// Returns the number of stripes.
+    #[inline]
+    pub fn stripes(&self) -> usize {
+        self.blocks().div_ceil(self.data_blocks as usize)
+    }
+
+    /// Returns the indices of the data blocks of `stripe`, past the end of the file for the last
+    /// ones of some stripes.
+    #[inline]
+    pub fn stripe_blocks(&self, stripe: usize) -> impl Iterator<Item = usize> {
+        let stripes = self.stripes();
+        (0..self.data_blocks as usize).map(move |k| k * stripes + stripe)
+    }
+
+    /// Returns the byte range of the data block at `index` in the file, empty past its end.
+    #[inline]
+    pub fn block_range(&self, index: usize) -> Range<usize> {
+        let start = (index as u64 * self.block_size as u64).min(self.size);
+        let end = (start + self.block_size as u64).min(self.size);
+        start as usize..end as usize
+    }
+
+    /// Returns the offset of the hashes of the parity blocks in the parity file.
+    #[inline]
+    fn parity_hashes_offset(&self) -> usize {
+        Self::SIZE + self.blocks() * BLOCK_HASH_SIZE
+    }
+
+    /// Returns the offset of the parity blocks in the parity file.
+    #[inline]
+    fn parity_offset(&self) -> usize {
+        self.parity_hashes_offset() + self.parity_count() * BLOCK_HASH_SIZE
+    }
+
+    /// Returns the total number of parity blocks.
+    #[inline]
+    fn parity_count(&self) -> usize {
+        self.stripes() * self.parity_blocks as usize
+    }
+
+    /// Returns the size of the parity file.
+    #[inline]
+    pub fn file_size(&self) -> usize {
+        self.parity_offset() + self.parity_count() * self.block_size as usize
+    }
+
+    /// Returns the bytes of the header.
+    #[inline]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        bytes.extend_from_slice(PARITY_MAGIC);
+        bytes.extend_from_slice(&PARITY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.block_size.to_le_bytes());
+        bytes.extend_from_slice(&self.data_blocks.to_le_bytes());
+        bytes.extend_from_slice(&self.parity_blocks.to_le_bytes());
+        bytes.extend_from_slice(&self.size.to_le_bytes());
+        bytes.extend_from_slice(&self.blake2b.0);
+        bytes
+    }
+
+    /// Reads the header at the start of the parity file `bytes`, checking its size.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        ensure!(
+            bytes.len() >= Self::SIZE && bytes.starts_with(PARITY_MAGIC),
+            "Not a parity file"
+        );
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(into_array_unchecked(&bytes[offset..][..4]));
+        let version = u32_at(8);
+        ensure!(
+            version == PARITY_VERSION,
+            "Unsupported parity file version {}",
+            version
+        );
+        let header = Self {
+            block_size: u32_at(12),
+            data_blocks: u32_at(16),
+            parity_blocks: u32_at(20),
+            size: u64::from_le_bytes(into_array_unchecked(&bytes[24..32])),
+            blake2b: Hash64(into_array_unchecked(&bytes[32..96])),
+        };
+        ensure!(
+            header.block_size > 0
+                && header.data_blocks > 0
+                && header.parity_blocks > 0
+                && (header.data_blocks + header.parity_blocks) as usize <= 256,
+            "Invalid parity file header"
+        );
+        ensure!(
+            bytes.len() == header.file_size(),
+            "The parity file holds {} bytes instead of {}",
+            bytes.len(),
+            header.file_size()
+        );
+        Ok(header)
+    }
+
+    /// Returns the data block at `index` of `data`, padded with zeros to the block size.
+    #[inline]
+    fn padded_block(&self, data: &[u8], index: usize) -> Vec<u8> {
+        let mut block = data[self.block_range(index)].to_vec();
+        block.resize(self.block_size as usize, 0);
+        block
+    }
+}
+
+/// Writes the parity data of `redundancy` percent of the file `data` with hash `blake2b`, split
+/// into blocks of `block_size` bytes, to `path`.
+#[inline]
+pub fn generate(
+    data: &[u8],
+    blake2b: Hash64,
+    block_size: u32,
+    redundancy: u32,
+    path: &Path,
+) -> Result<ParityHeader> {
+    let header = ParityHeader::new(data.len() as u64, blake2b, block_size, redundancy)?;
+    let code = ReedSolomon::new(header.data_blocks as usize, header.parity_blocks as usize)?;
+    atomic::write_with(path, |file| {
+        file.write_all(&header.to_bytes())?;
+        let hashes = (0..header.blocks())
+            .into_par_iter()
+            .map(|index| hash_chunk(&data[header.block_range(index)]))
+            .collect::<Vec<_>>();
+        for hash in &hashes {
+            file.write_all(&hash.0)?;
+        }
+        let batch = rayon::current_num_threads() * STRIPES_PER_THREAD;
+        let encode = |stripe| {
+            let blocks = header
+                .stripe_blocks(stripe)
+                .map(|index| header.padded_block(data, index))
+                .collect::<Vec<_>>();
+            code.encode(&blocks.iter().map(Vec::as_slice).collect::<Vec<_>>())
+        };
+        let mut parity = Vec::with_capacity(header.parity_count());
+        let mut hashes = Vec::with_capacity(header.parity_count());
+        for start in (0..header.stripes()).step_by(batch) {
+            let end = (start + batch).min(header.stripes());
+            let stripes = (start..end)
+                .into_par_iter()
+                .map(&encode)
+                .collect::<Vec<_>>();
+            for block in stripes.into_iter().flatten() {
+                hashes.push(hash_chunk(&block));
+                parity.push(block);
+            }
+        }
+        for hash in &hashes {
+            file.write_all(&hash.0)?;
+        }
+        for block in &parity {
+            file.write_all(block)?;
+        }
+        Ok(())
+    })?;
+    Ok(header)
+}
+
+/// Repair Report
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RepairReport {
+    /// Indices of the data blocks which did not match their hash
+    pub damaged: Vec<usize>,
+
+    /// Number of parity blocks which did not match their hash
+    pub damaged_parity: usize,
+
+    /// Indices of the data blocks which could not be rebuilt
+    pub unrepairable: Vec<usize>,
+}
+
+impl RepairReport {
+    /// Returns `true` if every damaged block was rebuilt.
+    #[inline]
+    pub fn is_repaired(&self) -> bool {
+        self.unrepairable.is_empty()
+    }
+}
+
+/// Rebuilds the blocks of the file `data` which do not match their hash in the `parity` file, in
+/// place.
+#[inline]
+pub fn repair(data: &mut [u8], parity: &[u8]) -> Result<RepairReport> {
+    let header = ParityHeader::read(parity)?;
+    ensure!(
+        data.len() as u64 == header.size,
+        "The file holds {} bytes instead of the {} it was protected with",
+        data.len(),
+        header.size
+    );
+    let code = ReedSolomon::new(header.data_blocks as usize, header.parity_blocks as usize)?;
+    let hash_at = |offset: usize, index: usize| {
+        Hash64(into_array_unchecked(
+            &parity[offset + index * BLOCK_HASH_SIZE..][..BLOCK_HASH_SIZE],
+        ))
+    };
+    let damaged = (0..header.blocks())
+        .into_par_iter()
+        .map(|index| {
+            hash_chunk(&data[header.block_range(index)]) != hash_at(ParityHeader::SIZE, index)
+        })
+        .collect::<Vec<_>>();
+    let parity_block = |index: usize| {
+        let size = header.block_size as usize;
+        &parity[header.parity_offset() + index * size..][..size]
+    };
+    let intact_parity = (0..header.parity_count())
+        .into_par_iter()
+        .map(|index| {
+            hash_chunk(parity_block(index)) == hash_at(header.parity_hashes_offset(), index)
+        })
+        .collect::<Vec<_>>();
+    let mut report = RepairReport {
+        damaged: (0..damaged.len()).filter(|index| damaged[*index]).collect(),
+        damaged_parity: intact_parity.iter().filter(|intact| !**intact).count(),
+        unrepairable: Vec::new(),
+    };
+    let blocks = header.blocks();
+    let stripes = report
+        .damaged
+        .iter()
+        .map(|index| index % header.stripes())
+        .collect::<BTreeSet<_>>();
+    let rebuilt = stripes
+        .into_par_iter()
+        .map(|stripe| {
+            let indices = header.stripe_blocks(stripe).collect::<Vec<_>>();
+            let mut shards = indices
+                .iter()
+                .map(|index| match *index < blocks && damaged[*index] {
+                    true => None,
+                    _ => Some(header.padded_block(data, *index)),
+                })
+                .chain((0..header.parity_blocks as usize).map(|j| {
+                    let index = stripe * header.parity_blocks as usize + j;
+                    intact_parity[index].then(|| parity_block(index).to_vec())
+                }))
+                .collect::<Vec<_>>();
+            let missing = indices
+                .iter()
+                .copied()
+                .filter(|index| *index < blocks && damaged[*index])
+                .collect::<Vec<_>>();
+            match code.reconstruct(&mut shards) {
+                Ok(()) => Ok(indices
+                    .into_iter()
+                    .zip(shards)
+                    .filter(|(index, _)| missing.contains(index))
+                    .map(|(index, shard)| (index, shard.unwrap_or_default()))
+                    .collect::<Vec<_>>()),
+                _ => Err(missing),
+            }
+        })
+        .collect::<Vec<_>>();
+    for stripe in rebuilt {
+        match stripe {
+            Ok(blocks) => {
+                for (index, block) in blocks {
+                    let range = header.block_range(index);
+                    let len = range.len();
+                    data[range].copy_from_slice(&block[..len]);
+                }
+            }
+            Err(missing) => report.unrepairable.extend(missing),
+        }
+    }
+    report.unrepairable.sort_unstable();
+    Ok(report)
+}
+
+/// Writes the parity data of the file at `path` to its [`parity_path`] in `state_path`, checking
+/// first that its Blake2b hash is still `blake2b`.
+#[inline]
+pub fn protect_file(
+    path: &Path,
+    parity_path: &Path,
+    blake2b: Hash64,
+    block_size: u32,
+    redundancy: u32,
+) -> Result<ParityHeader> {
+    let hash = hash_file(path)?;
+    ensure!(
+        hash == blake2b,
+        "{} changed since it was hashed, restore it before protecting it",
+        path.display()
+    );
+    // SAFETY: The map is read-only and the ceremony files are not modified once written.
+    let map = unsafe { Mmap::map(&File::open(path)?)? };
+    generate(&map, blake2b, block_size, redundancy, parity_path)
+}
+
+/// Repairs the file at `path` in place with the parity file at `parity_path`, checking that the
+/// repaired file has the hash it was protected with.
+#[inline]
+pub fn repair_file(path: &Path, parity_path: &Path) -> Result<RepairReport> {
+    // SAFETY: The parity file is read-only and the file is only written through its map while the
+    // repair holds it.
+    let parity = unsafe { Mmap::map(&File::open(parity_path)?)? };
+    let mut map =
+        unsafe { MmapMut::map_mut(&OpenOptions::new().read(true).write(true).open(path)?)? };
+    let report = repair(&mut map, &parity)?;
+    if report.damaged.is_empty() || !report.is_repaired() {
+        return Ok(report);
+    }
+    map.flush()?;
+    drop(map);
+    let header = ParityHeader::read(&parity)?;
+    ensure!(
+        hash_file(path)? == header.blake2b,
+        "{} does not have the hash it was protected with after the repair",
+        path.display()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// Checks that erased shards are rebuilt by the code and that damaged runs of blocks and
+    /// scattered blocks are repaired up to the redundancy, and reported beyond it.
+    #[test]
+    fn damaged_blocks_are_repaired() {
+        let code = ReedSolomon::new(5, 3).unwrap();
+        let data = (0..5u8).map(|i| vec![i * 31; 8]).collect::<Vec<_>>();
+        let parity = code.encode(&data.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        let mut shards = data
+            .iter()
+            .chain(&parity)
+            .cloned()
+            .map(Some)
+            .collect::<Vec<_>>();
+        for erased in [0, 3, 6] {
+            shards[erased] = None;
+        }
+        code.reconstruct(&mut shards).unwrap();
+        assert_eq!(
+            shards[..5],
+            data.iter().cloned().map(Some).collect::<Vec<_>>()
+        );
+        shards[1] = None;
+        shards[2] = None;
+        shards[4] = None;
+        shards[5] = None;
+        assert!(code.reconstruct(&mut shards).is_err());
+        let original = (0..300_000u32)
+            .map(|i| (i * 7 + i / 251) as u8)
+            .collect::<Vec<_>>();
+        let path = std::env::temp_dir().join("ppot-verifier-parity-test.par");
+        let header = generate(&original, Hash64([1; 64]), 1024, 10, &path).unwrap();
+        assert_eq!((header.blocks(), header.stripes()), (293, 3));
+        assert_eq!((header.data_blocks, header.parity_blocks), (128, 13));
+        let parity = fs::read(&path).unwrap();
+        assert_eq!(ParityHeader::read(&parity).unwrap(), header);
+        let mut data = original.clone();
+        let report = repair(&mut data, &parity).unwrap();
+        assert!(report.damaged.is_empty());
+        for byte in &mut data[1000..30_000] {
+            *byte ^= 0xff;
+        }
+        data[299_999] ^= 1;
+        let report = repair(&mut data, &parity).unwrap();
+        assert_eq!(report.damaged.len(), 31);
+        assert!(report.is_repaired());
+        assert_eq!(data, original);
+        for byte in &mut data[..100_000] {
+            *byte ^= 0xff;
+        }
+        let report = repair(&mut data, &parity).unwrap();
+        assert!(!report.is_repaired());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,1371 @@
+//! PGP Attestation Signatures
+//!
+//! Many participants of the ceremony signed their attestation with PGP, either clear-signing the
+//! text holding the hash of their response file or publishing a detached signature next to it, and
+//! published their public key in the same folder. This module implements the part of OpenPGP
+//! ([RFC 4880]) needed to check those signatures: ASCII armor, cleartext signed messages, version 4
+//! public keys and signatures, RSA and Ed25519 keys and the SHA-1 and SHA-2 hashes.
+//!
+//! A key is trusted because it is published along with the attestation or found in a local key
+//! ring, but only once it is bound to its user IDs or, for subkeys, to its primary key by valid
+//! self-signatures. Signatures of keys which are not bound, are revoked or had expired when they
+//! were made are reported as [`Unverified`](SignatureStatus::Unverified). Key flags, the
+//! back-signatures of signing subkeys, the expiration of self-signatures and the reasons of
+//! revocations are not checked.
+//!
+//! [RFC 4880]: https://www.rfc-editor.org/rfc/rfc4880
+
+use crate::{into_array_unchecked, Result};
+use anyhow::{anyhow, bail, ensure};
+use core::fmt;
+use ed25519_dalek::Verifier;
+use rsa::{traits::PublicKeyParts, BigUint, Pkcs1v15Sign, RsaPublicKey};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use std::{fs, path::Path};
+
+/// Packet tag of signatures
+const SIGNATURE_TAG: u8 = 2;
+
+/// Packet tag of primary public keys
+const PUBLIC_KEY_TAG: u8 = 6;
+
+/// Packet tag of user IDs
+const USER_ID_TAG: u8 = 13;
+
+/// Packet tag of public subkeys
+const PUBLIC_SUBKEY_TAG: u8 = 14;
+
+/// Packet tag of user attributes
+const USER_ATTRIBUTE_TAG: u8 = 17;
+
+/// Signature type of signatures over binary documents
+const BINARY_SIGNATURE: u8 = 0x00;
+
+/// Signature type of signatures over text documents, made over their lines ending with `\r\n`
+const TEXT_SIGNATURE: u8 = 0x01;
+
+/// Signature types of the certifications of user IDs and user attributes
+const CERTIFICATIONS: [u8; 4] = [0x10, 0x11, 0x12, 0x13];
+
+/// Signature type of the binding of subkeys to their primary key
+const SUBKEY_BINDING: u8 = 0x18;
+
+/// Signature type of signatures directly over a primary key
+const DIRECT_KEY_SIGNATURE: u8 = 0x1f;
+
+/// Signature type of the revocation of primary keys
+const KEY_REVOCATION: u8 = 0x20;
+
+/// Signature type of the revocation of subkeys
+const SUBKEY_REVOCATION: u8 = 0x28;
+
+/// Object identifier of the Ed25519 curve in EdDSA public keys
+const ED25519_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0xda, 0x47, 0x0f, 0x01];
+
+/// Largest RSA modulus accepted, in bits
+const MAX_RSA_BITS: usize = 16384;
+
+/// First line of cleartext signed messages
+const SIGNED_MESSAGE: &str = "-----BEGIN PGP SIGNED MESSAGE-----";
+
+/// First line of the signature of cleartext signed messages
+const SIGNATURE_ARMOR: &str = "-----BEGIN PGP SIGNATURE-----";
+
+/// Key Identifier
+///
+/// The last eight bytes of the fingerprint of a key, written in uppercase hexadecimal.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KeyId(pub [u8; 8]);
+
+impl fmt::Display for KeyId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016X}", u64::from_be_bytes(self.0))
+    }
+}
+
+/// Fingerprint of a Version 4 Key
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Fingerprint(pub [u8; 20]);
+
+impl Fingerprint {
+    /// Returns the identifier of the key with fingerprint `self`.
+    #[inline]
+    pub fn key_id(&self) -> KeyId {
+        KeyId(into_array_unchecked(&self.0[12..]))
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode_upper(self.0))
+    }
+}
+
+/// Hash Algorithm of a Signature
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum DigestAlgorithm {
+    /// SHA-1
+    Sha1,
+
+    /// SHA-224
+    Sha224,
+
+    /// SHA-256
+    Sha256,
+
+    /// SHA-384
+    Sha384,
+
+    /// SHA-512
+    Sha512,
+}
+
+impl DigestAlgorithm {
+    /// Returns the algorithm with identifier `id` in OpenPGP packets, if it is supported.
+    #[inline]
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            2 => Some(Self::Sha1),
+            8 => Some(Self::Sha256),
+            9 => Some(Self::Sha384),
+            10 => Some(Self::Sha512),
+            11 => Some(Self::Sha224),
+            _ => None,
+        }
+    }
+
+    /// Returns the hash of the concatenation of `parts`.
+    #[inline]
+    fn digest(self, parts: &[&[u8]]) -> Vec<u8> {
+        #[inline]
+        fn digest<D>(parts: &[&[u8]]) -> Vec<u8>
+        where
+            D: Digest,
+        {
+            let mut hasher = D::new();
+            for part in parts {
+                hasher.update(part);
+            }
+            hasher.finalize().to_vec()
+        }
+        match self {
+            Self::Sha1 => digest::<Sha1>(parts),
+            Self::Sha224 => digest::<Sha224>(parts),
+            Self::Sha256 => digest::<Sha256>(parts),
+            Self::Sha384 => digest::<Sha384>(parts),
+            Self::Sha512 => digest::<Sha512>(parts),
+        }
+    }
+
+    /// Returns the PKCS #1 v1.5 padding of RSA signatures over hashes of `self`.
+    #[inline]
+    fn pkcs1v15(self) -> Pkcs1v15Sign {
+        match self {
+            Self::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+            Self::Sha224 => Pkcs1v15Sign::new::<Sha224>(),
+            Self::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            Self::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            Self::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+        }
+    }
+}
+
+/// Reader of the Fields of a Packet
+struct Reader<'p>(&'p [u8]);
+
+impl<'p> Reader<'p> {
+    /// Reads the next `length` bytes.
+    #[inline]
+    fn take(&mut self, length: usize) -> Result<&'p [u8]> {
+        ensure!(length <= self.0.len(), "Truncated PGP packet.");
+        let (taken, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    /// Reads a byte.
+    #[inline]
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a big-endian two-byte integer.
+    #[inline]
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(into_array_unchecked(self.take(2)?)))
+    }
+
+    /// Reads a big-endian four-byte integer.
+    #[inline]
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(into_array_unchecked(self.take(4)?)))
+    }
+
+    /// Reads a multiprecision integer, returning its big-endian bytes.
+    #[inline]
+    fn mpi(&mut self) -> Result<&'p [u8]> {
+        let bits = self.u16()? as usize;
+        self.take(bits.div_ceil(8))
+    }
+
+    /// Reads a length in the format of new packet headers and signature subpackets.
+    #[inline]
+    fn length(&mut self) -> Result<usize> {
+        Ok(match self.u8()? {
+            first @ 0..=191 => first as usize,
+            first @ 192..=223 => ((first as usize - 192) << 8) + self.u8()? as usize + 192,
+            255 => self.u32()? as usize,
+            _ => bail!("Partial body lengths are not supported."),
+        })
+    }
+}
+
+/// Splits `data` into its packets, returned as their tag and body.
+#[inline]
+fn packets(data: &[u8]) -> Result<Vec<(u8, &[u8])>> {
+    let mut reader = Reader(data);
+    let mut packets = Vec::new();
+    while !reader.0.is_empty() {
+        let header = reader.u8()?;
+        ensure!(header & 0x80 != 0, "Invalid PGP packet header.");
+        let (tag, length) = if header & 0x40 != 0 {
+            (header & 0x3f, reader.length()?)
+        } else {
+            let length = match header & 0x03 {
+                0 => reader.u8()? as usize,
+                1 => reader.u16()? as usize,
+                2 => reader.u32()? as usize,
+                _ => reader.0.len(),
+            };
+            ((header >> 2) & 0x0f, length)
+        };
+        packets.push((tag, reader.take(length)?));
+    }
+    Ok(packets)
+}
+
+/// Key Material of a Public Key
+#[derive(Clone, Debug)]
+enum KeyMaterial {
+    /// RSA key
+    Rsa(RsaPublicKey),
+
+    /// EdDSA key over Ed25519
+    Ed25519(ed25519_dalek::PublicKey),
+
+    /// Key of an unsupported algorithm, which can still be recognized by its fingerprint
+    Unsupported,
+}
+
+/// Binding of a Key by Self-Signatures
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum KeyBinding {
+    /// No valid self-signature binds the key
+    Unbound,
+
+    /// The key is bound until its expiration time, if any
+    Bound {
+        /// Expiration time of the key, in seconds since the Unix epoch
+        expires: Option<u32>,
+    },
+
+    /// The key or its primary key is revoked
+    Revoked,
+}
+
+/// PGP Public Key
+///
+/// A primary key or a subkey of a version 4 transferable public key.
+#[derive(Clone, Debug)]
+pub struct PublicKey {
+    /// Fingerprint of the key
+    fingerprint: Fingerprint,
+
+    /// Creation time of the key, in seconds since the Unix epoch
+    created: u32,
+
+    /// Key material
+    material: KeyMaterial,
+
+    /// Binding of the key by the self-signatures of its transferable public key
+    binding: KeyBinding,
+}
+
+impl PublicKey {
+    /// Parses the `body` of a public key or public subkey packet, returning `None` for keys of
+    /// another version than 4. The key is [`Unbound`](KeyBinding::Unbound) until its
+    /// self-signatures are checked.
+    #[inline]
+    fn parse(body: &[u8]) -> Result<Option<Self>> {
+        let mut reader = Reader(body);
+        if reader.u8()? != 4 {
+            return Ok(None);
+        }
+        let created = reader.u32()?;
+        let material = match reader.u8()? {
+            1 | 3 => {
+                let (n, e) = (reader.mpi()?, reader.mpi()?);
+                RsaPublicKey::new_with_max_size(
+                    BigUint::from_bytes_be(n),
+                    BigUint::from_bytes_be(e),
+                    MAX_RSA_BITS,
+                )
+                .map_or(KeyMaterial::Unsupported, KeyMaterial::Rsa)
+            }
+            22 => {
+                let oid_length = reader.u8()? as usize;
+                let oid = reader.take(oid_length)?;
+                match reader.mpi()? {
+                    [0x40, point @ ..] if oid == ED25519_OID => {
+                        ed25519_dalek::PublicKey::from_bytes(point)
+                            .map_or(KeyMaterial::Unsupported, KeyMaterial::Ed25519)
+                    }
+                    _ => KeyMaterial::Unsupported,
+                }
+            }
+            _ => KeyMaterial::Unsupported,
+        };
+        let length = u16::try_from(body.len())?.to_be_bytes();
+        Ok(Some(Self {
+            fingerprint: Fingerprint(into_array_unchecked(DigestAlgorithm::Sha1.digest(&[
+                &[0x99],
+                &length,
+                body,
+            ]))),
+            created,
+            material,
+            binding: KeyBinding::Unbound,
+        }))
+    }
+
+    /// Returns `true` if `self` is bound and was not expired at `time`, if known.
+    #[inline]
+    fn is_bound_at(&self, time: Option<u32>) -> bool {
+        match (self.binding, time) {
+            (KeyBinding::Bound { expires: None }, _) => true,
+            (
+                KeyBinding::Bound {
+                    expires: Some(expires),
+                },
+                Some(time),
+            ) => time < expires,
+            _ => false,
+        }
+    }
+
+    /// Returns the fingerprint of the key.
+    #[inline]
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.fingerprint
+    }
+
+    /// Returns the identifier of the key.
+    #[inline]
+    pub fn key_id(&self) -> KeyId {
+        self.fingerprint.key_id()
+    }
+
+    /// Returns `true` if the signature `values` over `digest`, made with `algorithm`, are valid
+    /// for `self`.
+    #[inline]
+    fn verify(&self, algorithm: DigestAlgorithm, digest: &[u8], values: &[&[u8]]) -> bool {
+        match (&self.material, values) {
+            (KeyMaterial::Rsa(key), [signature]) if signature.len() <= key.size() => {
+                let mut padded = vec![0; key.size() - signature.len()];
+                padded.extend_from_slice(signature);
+                key.verify(algorithm.pkcs1v15(), digest, &padded).is_ok()
+            }
+            (KeyMaterial::Ed25519(key), [r, s]) if r.len() <= 32 && s.len() <= 32 => {
+                let mut signature = [0; 64];
+                signature[32 - r.len()..32].copy_from_slice(r);
+                signature[64 - s.len()..].copy_from_slice(s);
+                key.verify(digest, &ed25519_dalek::Signature::from(signature))
+                    .is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the prefix of the key packet `body` in the hashes of the signatures over the key.
+#[inline]
+fn hashed_key(body: &[u8]) -> Vec<u8> {
+    let mut hashed = vec![0x99];
+    hashed.extend_from_slice(&(body.len() as u16).to_be_bytes());
+    hashed.extend_from_slice(body);
+    hashed
+}
+
+/// Packet of a Transferable Public Key with the Signatures Following It
+struct Component<'p> {
+    /// Packet tag
+    tag: u8,
+
+    /// Packet body
+    body: &'p [u8],
+
+    /// Signatures over the packet
+    signatures: Vec<Signature<'p>>,
+}
+
+/// Returns the binding of a key created at `created` by the latest of `signatures`, given with the
+/// message they sign, which is a valid self-signature of one of `kinds` made by `primary`, unless
+/// one of them is a valid signature of type `revocation`.
+#[inline]
+fn bind(
+    primary: &PublicKey,
+    created: u32,
+    signatures: &[(&Signature, &[u8])],
+    kinds: &[u8],
+    revocation: u8,
+) -> KeyBinding {
+    let mut bindings = Vec::new();
+    for (signature, message) in signatures {
+        if signature.kind == revocation && signature.is_made_by(primary, message) {
+            return KeyBinding::Revoked;
+        }
+        if kinds.contains(&signature.kind) && signature.is_made_by(primary, message) {
+            bindings.push(*signature);
+        }
+    }
+    match bindings
+        .into_iter()
+        .max_by_key(|signature| signature.created)
+    {
+        Some(signature) => KeyBinding::Bound {
+            expires: signature
+                .key_expires
+                .filter(|expires| *expires != 0)
+                .map(|expires| created.saturating_add(expires)),
+        },
+        _ => KeyBinding::Unbound,
+    }
+}
+
+/// Binds the primary key and the subkeys of the transferable public key made of `components`,
+/// starting with its primary key, returning no keys if the primary key has another version than 4.
+#[inline]
+fn bind_keys(components: &[Component]) -> Result<Vec<PublicKey>> {
+    let mut primary = match PublicKey::parse(components[0].body)? {
+        Some(primary) => primary,
+        _ => return Ok(Vec::new()),
+    };
+    let hashed_primary = hashed_key(components[0].body);
+    let mut messages = vec![(&components[0], hashed_primary.clone())];
+    for component in &components[1..] {
+        let prefix = match component.tag {
+            USER_ID_TAG => 0xb4,
+            USER_ATTRIBUTE_TAG => 0xd1,
+            _ => continue,
+        };
+        let mut message = hashed_primary.clone();
+        message.push(prefix);
+        message.extend_from_slice(&(component.body.len() as u32).to_be_bytes());
+        message.extend_from_slice(component.body);
+        messages.push((component, message));
+    }
+    let signatures = messages
+        .iter()
+        .flat_map(|(component, message)| {
+            component
+                .signatures
+                .iter()
+                .map(move |signature| (signature, message.as_slice()))
+        })
+        .collect::<Vec<_>>();
+    let kinds = [&CERTIFICATIONS[..], &[DIRECT_KEY_SIGNATURE]].concat();
+    primary.binding = bind(
+        &primary,
+        primary.created,
+        &signatures,
+        &kinds,
+        KEY_REVOCATION,
+    );
+    let mut keys = Vec::new();
+    for component in &components[1..] {
+        if component.tag != PUBLIC_SUBKEY_TAG {
+            continue;
+        }
+        if let Some(mut subkey) = PublicKey::parse(component.body)? {
+            let mut message = hashed_primary.clone();
+            message.extend_from_slice(&hashed_key(component.body));
+            let signatures = component
+                .signatures
+                .iter()
+                .map(|signature| (signature, message.as_slice()))
+                .collect::<Vec<_>>();
+            subkey.binding = match (
+                primary.binding,
+                bind(
+                    &primary,
+                    subkey.created,
+                    &signatures,
+                    &[SUBKEY_BINDING],
+                    SUBKEY_REVOCATION,
+                ),
+            ) {
+                (
+                    KeyBinding::Bound { expires },
+                    KeyBinding::Bound {
+                        expires: subkey_expires,
+                    },
+                ) => KeyBinding::Bound {
+                    expires: expires.into_iter().chain(subkey_expires).min(),
+                },
+                (KeyBinding::Bound { .. }, binding) | (binding, _) => binding,
+            };
+            keys.push(subkey);
+        }
+    }
+    keys.insert(0, primary);
+    Ok(keys)
+}
+
+/// Parses the public keys and subkeys of the transferable public keys in `data`, skipping the
+/// keys of other versions than 4, and checks their self-signatures.
+#[inline]
+pub fn parse_keys(data: &[u8]) -> Result<Vec<PublicKey>> {
+    let mut transferable = Vec::<Vec<Component>>::new();
+    for (tag, body) in packets(data)? {
+        if tag == PUBLIC_KEY_TAG {
+            transferable.push(Vec::new());
+        }
+        let components = match transferable.last_mut() {
+            Some(components) => components,
+            _ => continue,
+        };
+        if tag == SIGNATURE_TAG {
+            if let (Some(component), Ok(Some(signature))) =
+                (components.last_mut(), Signature::parse(body))
+            {
+                component.signatures.push(signature);
+            }
+        } else {
+            components.push(Component {
+                tag,
+                body,
+                signatures: Vec::new(),
+            });
+        }
+    }
+    let mut keys = Vec::new();
+    for components in transferable {
+        keys.extend(bind_keys(&components)?);
+    }
+    Ok(keys)
+}
+
+/// Loads the public keys of the key ring at `path`, either a file of armored or binary keys or a
+/// directory of such files.
+#[inline]
+pub fn load_keyring<P>(path: P) -> Result<Vec<PublicKey>>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    if path.is_dir() {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                keys.extend(load_keyring(entry.path())?);
+            }
+        }
+        return Ok(keys);
+    }
+    let data = fs::read(path)?;
+    let text = String::from_utf8_lossy(&data);
+    let armored = blocks(&text);
+    if armored.is_empty() {
+        return parse_keys(&data);
+    }
+    let mut keys = Vec::new();
+    for block in armored {
+        if let Block::Armored { kind, data } = block {
+            if kind == "PUBLIC KEY BLOCK" {
+                keys.extend(parse_keys(&data?)?);
+            }
+        }
+    }
+    Ok(keys)
+}
+
+/// Status of the PGP Signatures of an Attestation
+///
+/// The variants are sorted by precedence: the status of several signatures is the greatest of
+/// their statuses, so that a single invalid signature taints the attestation.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// No PGP signature was found
+    Unsigned,
+
+    /// The signatures use an algorithm or version of OpenPGP which is not supported
+    Unsupported,
+
+    /// The keys which made the signatures are not published
+    UnknownKey,
+
+    /// The signatures are valid but their keys are not bound by valid self-signatures, are
+    /// revoked or had expired when they were made
+    Unverified,
+
+    /// The signatures are valid
+    Valid,
+
+    /// A signature is malformed or does not match the signed text
+    Invalid,
+}
+
+impl SignatureStatus {
+    /// Returns the name of `self` in the database.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Unsigned => "unsigned",
+            Self::Unsupported => "unsupported",
+            Self::UnknownKey => "unknown_key",
+            Self::Unverified => "unverified",
+            Self::Valid => "valid",
+            Self::Invalid => "invalid",
+        }
+    }
+
+    /// Parses the name of a status in the database.
+    #[inline]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unsigned" => Some(Self::Unsigned),
+            "unsupported" => Some(Self::Unsupported),
+            "unknown_key" => Some(Self::UnknownKey),
+            "unverified" => Some(Self::Unverified),
+            "valid" => Some(Self::Valid),
+            "invalid" => Some(Self::Invalid),
+            _ => None,
+        }
+    }
+}
+
+/// Check of the PGP Signatures of an Attestation
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SignatureCheck {
+    /// Status of the signatures
+    pub status: SignatureStatus,
+
+    /// Fingerprint of the key which made the signature deciding the status, or the identifier of
+    /// the issuer if its key is unknown
+    pub signer: Option<String>,
+}
+
+impl SignatureCheck {
+    /// Check of an attestation without signatures
+    pub const UNSIGNED: Self = Self {
+        status: SignatureStatus::Unsigned,
+        signer: None,
+    };
+
+    /// Builds a check of `status` without a signer.
+    #[inline]
+    fn of(status: SignatureStatus) -> Self {
+        Self {
+            status,
+            signer: None,
+        }
+    }
+
+    /// Combines the checks of two signatures of the same attestation, keeping the one of highest
+    /// precedence.
+    #[inline]
+    pub fn merge(self, other: Self) -> Self {
+        if other.status > self.status {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+/// Version 4 Signature
+struct Signature<'p> {
+    /// Signature type
+    kind: u8,
+
+    /// Hash algorithm identifier
+    digest: u8,
+
+    /// Signed part of the packet, from its version to the end of the hashed subpackets
+    hashed: &'p [u8],
+
+    /// Creation time of the signature, in seconds since the Unix epoch
+    created: Option<u32>,
+
+    /// Validity period of the signed key after its creation, in seconds, for self-signatures
+    key_expires: Option<u32>,
+
+    /// Identifier of the key which made the signature
+    issuer: Option<KeyId>,
+
+    /// Fingerprint of the key which made the signature
+    issuer_fingerprint: Option<Fingerprint>,
+
+    /// First two bytes of the signed hash
+    prefix: &'p [u8],
+
+    /// Signature values
+    values: Vec<&'p [u8]>,
+}
+
+impl<'p> Signature<'p> {
+    /// Parses the `body` of a signature packet, returning `None` for signatures of another version
+    /// than 4.
+    #[inline]
+    fn parse(body: &'p [u8]) -> Result<Option<Self>> {
+        let mut reader = Reader(body);
+        if reader.u8()? != 4 {
+            return Ok(None);
+        }
+        let kind = reader.u8()?;
+        reader.u8()?;
+        let digest = reader.u8()?;
+        let hashed_length = reader.u16()? as usize;
+        reader.take(hashed_length)?;
+        let hashed = &body[..6 + hashed_length];
+        let unhashed_length = reader.u16()? as usize;
+        let unhashed = reader.take(unhashed_length)?;
+        let (mut created, mut key_expires) = (None, None);
+        let (mut issuer, mut issuer_fingerprint) = (None, None);
+        for (subpackets, is_hashed) in [(&hashed[6..], true), (unhashed, false)] {
+            let mut subpackets = Reader(subpackets);
+            while !subpackets.0.is_empty() {
+                let length = subpackets.length()?;
+                let subpacket = subpackets.take(length)?;
+                // The high bit of the subpacket type marks critical subpackets
+                match subpacket.split_first() {
+                    Some((kind, time)) if is_hashed && time.len() == 4 && kind & 0x7f == 2 => {
+                        created = Some(u32::from_be_bytes(into_array_unchecked(time)))
+                    }
+                    Some((kind, time)) if is_hashed && time.len() == 4 && kind & 0x7f == 9 => {
+                        key_expires = Some(u32::from_be_bytes(into_array_unchecked(time)))
+                    }
+                    Some((kind, id)) if kind & 0x7f == 16 && id.len() == 8 => {
+                        issuer = Some(KeyId(into_array_unchecked(id)))
+                    }
+                    Some((kind, [4, fingerprint @ ..]))
+                        if kind & 0x7f == 33 && fingerprint.len() == 20 =>
+                    {
+                        issuer_fingerprint = Some(Fingerprint(into_array_unchecked(fingerprint)))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let prefix = reader.take(2)?;
+        let mut values = Vec::new();
+        while !reader.0.is_empty() {
+            values.push(reader.mpi()?);
+        }
+        Ok(Some(Self {
+            kind,
+            digest,
+            hashed,
+            created,
+            key_expires,
+            issuer,
+            issuer_fingerprint,
+            prefix,
+            values,
+        }))
+    }
+
+    /// Checks `self` over `message` against `keys`.
+    #[inline]
+    fn check(&self, message: &[u8], keys: &[PublicKey]) -> SignatureCheck {
+        let message = match self.kind {
+            BINARY_SIGNATURE => message.to_vec(),
+            TEXT_SIGNATURE => canonical_text(message.split(|byte| *byte == b'\n'), false),
+            _ => return SignatureCheck::of(SignatureStatus::Unsupported),
+        };
+        let key = keys.iter().find(|key| match self.issuer_fingerprint {
+            Some(fingerprint) => key.fingerprint == fingerprint,
+            _ => Some(key.key_id()) == self.issuer,
+        });
+        let key = match key {
+            Some(key) => key,
+            _ => {
+                return SignatureCheck {
+                    status: SignatureStatus::UnknownKey,
+                    signer: self
+                        .issuer_fingerprint
+                        .map(|fingerprint| fingerprint.to_string())
+                        .or_else(|| self.issuer.map(|issuer| issuer.to_string())),
+                }
+            }
+        };
+        if matches!(key.material, KeyMaterial::Unsupported)
+            || DigestAlgorithm::from_id(self.digest).is_none()
+        {
+            return SignatureCheck::of(SignatureStatus::Unsupported);
+        }
+        SignatureCheck {
+            status: if !self.is_made_by(key, &message) {
+                SignatureStatus::Invalid
+            } else if key.is_bound_at(self.created) {
+                SignatureStatus::Valid
+            } else {
+                SignatureStatus::Unverified
+            },
+            signer: Some(key.fingerprint.to_string()),
+        }
+    }
+
+    /// Returns `true` if `self` is a valid signature of `key` over `message`.
+    #[inline]
+    fn is_made_by(&self, key: &PublicKey, message: &[u8]) -> bool {
+        let algorithm = match DigestAlgorithm::from_id(self.digest) {
+            Some(algorithm) => algorithm,
+            _ => return false,
+        };
+        let trailer_length = u32::try_from(self.hashed.len()).unwrap_or(u32::MAX);
+        let digest = algorithm.digest(&[
+            message,
+            self.hashed,
+            &[4, 0xff],
+            &trailer_length.to_be_bytes(),
+        ]);
+        digest.starts_with(self.prefix) && key.verify(algorithm, &digest, &self.values)
+    }
+}
+
+/// Checks the signatures among the packets of `data` over `message` against `keys`.
+#[inline]
+fn check_signatures(data: &[u8], message: &[u8], keys: &[PublicKey]) -> SignatureCheck {
+    let packets = match packets(data) {
+        Ok(packets) => packets,
+        _ => return SignatureCheck::of(SignatureStatus::Invalid),
+    };
+    let mut check = SignatureCheck::UNSIGNED;
+    for (tag, body) in packets {
+        if tag != SIGNATURE_TAG {
+            continue;
+        }
+        check = check.merge(match Signature::parse(body) {
+            Ok(Some(signature)) => signature.check(message, keys),
+            Ok(None) => SignatureCheck::of(SignatureStatus::Unsupported),
+            _ => SignatureCheck::of(SignatureStatus::Invalid),
+        });
+    }
+    check
+}
+
+/// Returns the canonical form of the text made of `lines`, ending with `\r\n` except for the last
+/// one, with their trailing spaces and tabs removed if `strip` is `true`, as for cleartext signed
+/// messages.
+#[inline]
+fn canonical_text<'l, I>(lines: I, strip: bool) -> Vec<u8>
+where
+    I: IntoIterator<Item = &'l [u8]>,
+{
+    let mut text = Vec::new();
+    for (i, line) in lines.into_iter().enumerate() {
+        if i > 0 {
+            text.extend_from_slice(b"\r\n");
+        }
+        let mut line = line.strip_suffix(b"\r").unwrap_or(line);
+        if strip {
+            while let [rest @ .., b' ' | b'\t'] = line {
+                line = rest;
+            }
+        }
+        text.extend_from_slice(line);
+    }
+    text
+}
+
+/// Computes the CRC-24 checksum of the ASCII armor over `data`.
+#[inline]
+fn crc24(data: &[u8]) -> u32 {
+    let mut crc = 0xb704ce_u32;
+    for byte in data {
+        crc ^= (*byte as u32) << 16;
+        for _ in 0..8 {
+            crc <<= 1;
+            if crc & 0x1000000 != 0 {
+                crc ^= 0x1864cfb;
+            }
+        }
+    }
+    crc & 0xffffff
+}
+
+/// Decodes the armored block whose first line, of the form `-----BEGIN PGP {kind}-----`, is the
+/// first of `lines`, returning its kind, its data and the number of lines it spans.
+#[inline]
+fn dearmor<'t>(lines: &[&'t str]) -> (&'t str, Result<Vec<u8>>, usize) {
+    let kind = lines[0]
+        .trim()
+        .trim_start_matches("-----BEGIN PGP ")
+        .trim_end_matches("-----");
+    let end = format!("-----END PGP {}-----", kind);
+    let end = lines.iter().position(|line| line.trim() == end);
+    let decode = || {
+        let end = end.ok_or_else(|| anyhow!("Unterminated PGP armor."))?;
+        let mut encoded = String::new();
+        let mut checksum = None;
+        for line in lines[1..end]
+            .iter()
+            .map(|line| line.trim())
+            .skip_while(|line| line.contains(": "))
+        {
+            match line.strip_prefix('=') {
+                Some(crc) if line.len() == 5 => checksum = Some(crc),
+                _ => encoded.push_str(line),
+            }
+        }
+        let data = base64::decode(encoded)?;
+        if let Some(checksum) = checksum {
+            let checksum = base64::decode(checksum)?;
+            ensure!(
+                checksum.len() == 3
+                    && u32::from_be_bytes([0, checksum[0], checksum[1], checksum[2]])
+                        == crc24(&data),
+                "Invalid PGP armor checksum."
+            );
+        }
+        Ok(data)
+    };
+    (kind, decode(), end.map_or(lines.len(), |end| end + 1))
+}
+
+/// Block of PGP Data in a Text
+enum Block<'t> {
+    /// Cleartext signed message
+    Signed {
+        /// Canonical form of the signed text
+        text: Vec<u8>,
+
+        /// Packets of the signature
+        signature: Result<Vec<u8>>,
+    },
+
+    /// Other armored block
+    Armored {
+        /// Kind of the block, such as `SIGNATURE` or `PUBLIC KEY BLOCK`
+        kind: &'t str,
+
+        /// Packets of the block
+        data: Result<Vec<u8>>,
+    },
+}
+
+/// Finds the cleartext signed messages and the other armored blocks in `text`.
+#[inline]
+fn blocks(text: &str) -> Vec<Block<'_>> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut blocks = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i].trim();
+        if line == SIGNED_MESSAGE {
+            let start = i
+                + 1
+                + lines[i + 1..]
+                    .iter()
+                    .position(|line| line.trim().is_empty())
+                    .unwrap_or(lines.len() - i - 1);
+            let end = start
+                + lines[start..]
+                    .iter()
+                    .position(|line| line.trim() == SIGNATURE_ARMOR)
+                    .unwrap_or(lines.len() - start);
+            let signed = lines[(start + 1).min(end)..end]
+                .iter()
+                .map(|line| line.strip_prefix("- ").unwrap_or(line).as_bytes());
+            let text = canonical_text(signed, true);
+            let (signature, length) = match lines.get(end) {
+                Some(_) => {
+                    let (_, signature, length) = dearmor(&lines[end..]);
+                    (signature, length)
+                }
+                _ => (
+                    Err(anyhow!("Missing signature of a PGP signed message.")),
+                    0,
+                ),
+            };
+            blocks.push(Block::Signed { text, signature });
+            i = end + length;
+        } else if line.starts_with("-----BEGIN PGP ") {
+            let (kind, data, length) = dearmor(&lines[i..]);
+            blocks.push(Block::Armored { kind, data });
+            i += length;
+        } else {
+            i += 1;
+        }
+    }
+    blocks
+}
+
+/// Checks the PGP signatures of the attestation made of `files`, given as their name and contents,
+/// against the public keys published among them and the keys of `keyring`. Signatures are either
+/// cleartext signed messages inside of any file or armored detached signatures of another file,
+/// named after it with the `.asc` or `.sig` extension.
+#[inline]
+pub fn check_attestation(files: &[(String, Vec<u8>)], keyring: &[PublicKey]) -> SignatureCheck {
+    let texts = files
+        .iter()
+        .map(|(name, contents)| (name, contents, String::from_utf8_lossy(contents)))
+        .collect::<Vec<_>>();
+    let mut keys = keyring.to_vec();
+    for (_, _, text) in &texts {
+        for block in blocks(text) {
+            if let Block::Armored {
+                kind: "PUBLIC KEY BLOCK",
+                data: Ok(data),
+            } = block
+            {
+                keys.extend(parse_keys(&data).unwrap_or_default());
+            }
+        }
+    }
+    let mut check = SignatureCheck::UNSIGNED;
+    for (name, _, text) in &texts {
+        let signed = name
+            .strip_suffix(".asc")
+            .or_else(|| name.strip_suffix(".sig"))
+            .and_then(|signed| files.iter().find(|(name, _)| name == signed))
+            .map(|(_, contents)| contents);
+        for block in blocks(text) {
+            let block_check = match block {
+                Block::Signed {
+                    text,
+                    signature: Ok(signature),
+                } => check_signatures(&signature, &text, &keys),
+                Block::Signed { .. } => SignatureCheck::of(SignatureStatus::Invalid),
+                Block::Armored {
+                    kind: "SIGNATURE",
+                    data,
+                } => match (data, signed) {
+                    (Ok(data), Some(signed)) => check_signatures(&data, signed, &keys),
+                    (Ok(_), _) => continue,
+                    _ => SignatureCheck::of(SignatureStatus::Invalid),
+                },
+                _ => continue,
+            };
+            check = check.merge(block_check);
+        }
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Text signed in the test vectors, with a line starting with a dash and trailing spaces
+    const MESSAGE: &str = "The BLAKE2b hash of `./response` is:\n- 0011 trailing spaces   \nend\n";
+
+    /// Ed25519 public key of the test vectors
+    const ED25519_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatK4PhYJKwYBBAHaRw8BAQdApfc6ca5Oqc3N5cjuzrt8YkrPFO56CQ6Pvssj
+F8JCV3W0GkVkIFRlc3RlciA8ZWRAZXhhbXBsZS5vcmc+iJAEExYIADgWIQRWT2xG
+9+g8ysO/JIH8fnxlmn5yYgUCatK4PgIbAwULCQgHAgYVCgkICwIEFgIDAQIeAQIX
+gAAKCRD8fnxlmn5yYnc/AP9Q0UgQqN//nDfrpHHGVkcmFeGwFDn5WFDr4HprJAQ7
+dAEAoG+Irk60+dAYfDJb3I38YucjjCIxM9mrtoSUbv+vBQw=
+=6dCb
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    /// RSA public key of the test vectors
+    const RSA_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mQENBGrSuD4BCADVnq8Aw/vEzcI5PRgQ6SUjNzyPEVyxUrSvIRHmt5cTIkmLyMT8
+prViYURu8iQtsv7LuT/nLZkkAEP/ghb4qdqr2MD0Qi6+Pku0E+OvdCA2zipENvts
+LKEu0V+DoT0LBewuu7xHincn7SKJQznuLOYUnQWQKGjwNGqGOosnzk9P5zKS63Cf
+fr2r03SKRD6lZtuq6QsQtaTe6kQHqkeqi8+QAbD6MLQrVL/u3thspNCmObf7U0Bv
+Y5ih2IwXC4YVOW39bWE01EhuSHuGQiU1Ypf5gjUsGM53/p5o95lb+FB9UFpm7vbk
+o2aqkYFL59BNlAJGd5iITB4A+Um2wjUojd5lABEBAAG0HFJzYSBUZXN0ZXIgPHJz
+YUBleGFtcGxlLm9yZz6JAU4EEwEKADgWIQRVHlVstVMh6RFnMb8PB0ryjDZnJgUC
+atK4PgIbAwULCQgHAgYVCgkICwIEFgIDAQIeAQIXgAAKCRAPB0ryjDZnJod/B/9d
+cQCq2qXlS9a7zBkJTuyWG46HMkpNKZXsEZ2rO0qfaBLq0r5q7mU2mJcwhrLSopy2
+2LVBMA2+bAXZHsUCpLu/scq52D2/lQTMCqSKl003X0oFWG+fxznxI20KtCL9dz06
+pp5ye8eGz4h6KDSo6mXhV5T3D72eVOXwXcwVUrKG/eOUKpsc/V2Q5yGVkIOwAi7+
+S2D7B3PvAwfmUSzz4teAdxyf0PJ44PXNF/lvneiGbULKjVfL75qa3d+eVsNASwGA
+sBw1D8DiCCLlqh3zl5RfBFJy4jblNVPAawTNQ1idYrSx2iJWVMGWVgzPlhxilUwy
+PVURrhQ98EHLii0OCccu
+=si/D
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    /// Cleartext signature of [`MESSAGE`] with the Ed25519 key, over its SHA-256 hash
+    const ED25519_SIGNED: &str = "-----BEGIN PGP SIGNED MESSAGE-----
+Hash: SHA256
+
+The BLAKE2b hash of `./response` is:
+- - 0011 trailing spaces
+end
+-----BEGIN PGP SIGNATURE-----
+
+iIUEARYIAC0WIQRWT2xG9+g8ysO/JIH8fnxlmn5yYgUCatK4Pw8cZWRAZXhhbXBs
+ZS5vcmcACgkQ/H58ZZp+cmLjBAEA8Q6HyLLiyYN44+8zQ73IJUJKiSfirPxVi4mR
+gKJB6t8A/A7mvXlJ7PBG1jW7mvWYeHWhTQHhJdbnXAJ6HlDJGcoI
+=zo8c
+-----END PGP SIGNATURE-----
+";
+
+    /// Cleartext signature of [`MESSAGE`] with the RSA key, over its SHA-512 hash
+    const RSA_SIGNED: &str = "-----BEGIN PGP SIGNED MESSAGE-----
+Hash: SHA512
+
+The BLAKE2b hash of `./response` is:
+- - 0011 trailing spaces
+end
+-----BEGIN PGP SIGNATURE-----
+
+iQFEBAEBCgAuFiEEVR5VbLVTIekRZzG/DwdK8ow2ZyYFAmrSuD8QHHJzYUBleGFt
+cGxlLm9yZwAKCRAPB0ryjDZnJoMPB/9YPyUUVxvLcDE7KZ+fP9Zzn9Htp+UgAM4L
+ofT7MYnW6uZ0ql+1Vmo0huzJQ/I3J04t+2djlH527IUKIMEunr1TWunHb1wutBFX
++l6UfmYPwBq6+dxVrNKuqkRJ336XCJC/uFGt9yWrCTl6X7FAAIUV/8ePzn3Veblh
+Lt8sT1andWbRZZcbMHhFzRFk+gIojJ6t/2oLce4y0tjUbg7RyX34977KgA686z7A
+jr9hkZuVCOrhYpZ/rRe5NvW8KxWc8KEVJ++LIiCSvUsgGoiFhIJX0XANYbjKgLhx
+gNR9Cck6G3zmNhmOvSLoCKLtnCQsn3NYm/Pi6MG7WKpApDLrofeg
+=TiQj
+-----END PGP SIGNATURE-----
+";
+
+    /// Detached binary signature of [`MESSAGE`] with the Ed25519 key
+    const ED25519_DETACHED: &str = "-----BEGIN PGP SIGNATURE-----
+
+iIUEABYIAC0WIQRWT2xG9+g8ysO/JIH8fnxlmn5yYgUCatK4Qw8cZWRAZXhhbXBs
+ZS5vcmcACgkQ/H58ZZp+cmKCnwD/WsMVo/VnwOlZh0CiyTRrRV1VuxCE0WoGogiL
+fiUJqqgA/ioaamYq8z/NFuWkpS3d0aR0GHSXQZyphFDbjJQfb44D
+=VDhe
+-----END PGP SIGNATURE-----
+";
+
+    /// Ed25519 public key of the test vectors with an Ed25519 signing subkey, expiring after two
+    /// years and one year respectively
+    const SUBKEY_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatLPxBYJKwYBBAHaRw8BAQdAKAW7jj5ew75nDvHDI+5jky1/zyI6i2u8uA37
+kiFapYK0H1N1YmtleSBUZXN0ZXIgPHN1YkBleGFtcGxlLm9yZz6IlgQTFggAPhYh
+BOF7Yh2oCAR0NF5e+JCZEpnceAc/BQJq0s/EAhsBBQkDwmcABQsJCAcCBhUKCQgL
+AgQWAgMBAh4BAheAAAoJEJCZEpnceAc/vJQBAP4Ij11R2XwBwrrl1NPrbXn/trud
+4IOYxM3uIcqz8HyLAQDCSPcawQilaA5BmIwdZoaLa3pN+q7GeRivvlg8aKEiAbgz
+BGrSz8QWCSsGAQQB2kcPAQEHQNcwXM6zdom47ILA/fAUoulB+SeFckTxdUlkgse7
+VZUbiPUEGBYIACYWIQThe2IdqAgEdDReXviQmRKZ3HgHPwUCatLPxAIbAgUJAeEz
+gACBCRCQmRKZ3HgHP3YgBBkWCAAdFiEEYRJ3kuox7dM7N38FR4etRbBU1MUFAmrS
+z8QACgkQR4etRbBU1MU5ZAD/SkhttoevudBoMsUSLvMFNjR9V56lDUEOVBBNOs3o
+4wQA/148yhkXBgA1UOkIVZxevPF6BS+TuPyiT8YRFLdXjzEAicEBALwYI0NjhiYI
+aEh+HQEgA560iKzpIexzFB32e8dMPS46AP9RYRgqcQYKgDHm1NT6ffi+HlmO/9Wi
+DZF7QBC4bvh5Cw==
+=YVcd
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    /// [`SUBKEY_KEY`] with the revocation of its primary key
+    const REVOKED_KEY: &str = "-----BEGIN PGP PUBLIC KEY BLOCK-----
+
+mDMEatLPxBYJKwYBBAHaRw8BAQdAKAW7jj5ew75nDvHDI+5jky1/zyI6i2u8uA37
+kiFapYKIeAQgFggAIBYhBOF7Yh2oCAR0NF5e+JCZEpnceAc/BQJq0s/EAh0AAAoJ
+EJCZEpnceAc/FWMBAKORccKQGoGmVSp5V0f2XONV+X/ZjekCNBUyEy85C4EnAP0Y
+3DDzPs/cyxxAaxdNnj0fjG7s+sJOhRy7zJSTVZTmDLQfU3Via2V5IFRlc3RlciA8
+c3ViQGV4YW1wbGUub3JnPoiWBBMWCAA+FiEE4XtiHagIBHQ0Xl74kJkSmdx4Bz8F
+AmrSz8QCGwEFCQPCZwAFCwkIBwIGFQoJCAsCBBYCAwECHgECF4AACgkQkJkSmdx4
+Bz+8lAEA/giPXVHZfAHCuuXU0+ttef+2u53gg5jEze4hyrPwfIsBAMJI9xrBCKVo
+DkGYjB1mhotrek36rsZ5GK++WDxooSIBuDMEatLPxBYJKwYBBAHaRw8BAQdA1zBc
+zrN2ibjsgsD98BSi6UH5J4VyRPF1SWSCx7tVlRuI9QQYFggAJhYhBOF7Yh2oCAR0
+NF5e+JCZEpnceAc/BQJq0s/EAhsCBQkB4TOAAIEJEJCZEpnceAc/diAEGRYIAB0W
+IQRhEneS6jHt0zs3fwVHh61FsFTUxQUCatLPxAAKCRBHh61FsFTUxTlkAP9KSG22
+h6+50GgyxRIu8wU2NH1XnqUNQQ5UEE06zejjBAD/XjzKGRcGADVQ6QhVnF688XoF
+L5O4/KJPxhEUt1ePMQCJwQEAvBgjQ2OGJghoSH4dASADnrSIrOkh7HMUHfZ7x0w9
+LjoA/1FhGCpxBgqAMebU1Pp9+L4eWY7/1aINkXtAELhu+HkL
+=RY1X
+-----END PGP PUBLIC KEY BLOCK-----
+";
+
+    /// Cleartext signature of [`MESSAGE`] with the subkey of [`SUBKEY_KEY`], over its SHA-256 hash
+    const SUBKEY_SIGNED: &str = "-----BEGIN PGP SIGNED MESSAGE-----
+Hash: SHA256
+
+The BLAKE2b hash of `./response` is:
+- - 0011 trailing spaces
+end
+-----BEGIN PGP SIGNATURE-----
+
+iHUEARYIAB0WIQRhEneS6jHt0zs3fwVHh61FsFTUxQUCatLPxAAKCRBHh61FsFTU
+xaKbAPwPLRQjRKq8Sed7hEDsft8xnpdNArGzSQOWGwRow2jPkwEAlPXq14GsGpox
+mhmy4kIYOtVnp8Y5ZSwRyZwb/6X8cg8=
+=lr2D
+-----END PGP SIGNATURE-----
+";
+
+    /// Fingerprint of the Ed25519 key
+    const ED25519_FINGERPRINT: &str = "564F6C46F7E83CCAC3BF2481FC7E7C659A7E7262";
+
+    /// Fingerprint of the RSA key
+    const RSA_FINGERPRINT: &str = "551E556CB55321E9116731BF0F074AF28C366726";
+
+    /// Fingerprint of the signing subkey of [`SUBKEY_KEY`]
+    const SUBKEY_FINGERPRINT: &str = "61127792EA31EDD33B377F054787AD45B054D4C5";
+
+    /// Returns the attestation files made of `files`, given as their name and contents.
+    fn attestation(files: &[(&str, &str)]) -> Vec<(String, Vec<u8>)> {
+        files
+            .iter()
+            .map(|(name, contents)| (name.to_string(), contents.as_bytes().to_vec()))
+            .collect()
+    }
+
+    /// Checks that keys are parsed with their fingerprints, and that the cleartext signatures made
+    /// by `gpg --clearsign` and its detached signatures are verified against the published keys.
+    #[test]
+    fn gpg_signatures_verify() {
+        let keys = load_keys(ED25519_KEY);
+        assert_eq!(keys[0].fingerprint().to_string(), ED25519_FINGERPRINT);
+        assert_eq!(keys[0].key_id().to_string(), "FC7E7C659A7E7262");
+        let valid = |signer: &str| SignatureCheck {
+            status: SignatureStatus::Valid,
+            signer: Some(signer.into()),
+        };
+        let readme = format!("# Attestation\n\n```\n{}```\n", ED25519_SIGNED);
+        assert_eq!(
+            check_attestation(
+                &attestation(&[("README.md", &readme), ("key.asc", ED25519_KEY)]),
+                &[]
+            ),
+            valid(ED25519_FINGERPRINT)
+        );
+        assert_eq!(
+            check_attestation(
+                &attestation(&[("README.md", RSA_SIGNED)]),
+                &load_keys(RSA_KEY)
+            ),
+            valid(RSA_FINGERPRINT)
+        );
+        assert_eq!(
+            check_attestation(
+                &attestation(&[
+                    ("hashes.txt", MESSAGE),
+                    ("hashes.txt.asc", ED25519_DETACHED),
+                    ("key.asc", ED25519_KEY),
+                ]),
+                &[]
+            ),
+            valid(ED25519_FINGERPRINT)
+        );
+    }
+
+    /// Checks that tampered texts, missing keys and missing signatures are told apart.
+    #[test]
+    fn tampered_signatures_fail() {
+        let tampered = ED25519_SIGNED.replace("0011", "0012");
+        let check = check_attestation(
+            &attestation(&[("README.md", &tampered), ("key.asc", ED25519_KEY)]),
+            &[],
+        );
+        assert_eq!(check.status, SignatureStatus::Invalid);
+        let check = check_attestation(
+            &attestation(&[("README.md", ED25519_SIGNED), ("key.asc", RSA_KEY)]),
+            &[],
+        );
+        assert_eq!(check.status, SignatureStatus::UnknownKey);
+        assert_eq!(check.signer.as_deref(), Some(ED25519_FINGERPRINT));
+        let check = check_attestation(
+            &attestation(&[
+                ("README.md", ED25519_SIGNED),
+                ("hashes.txt", &MESSAGE.replace("end", "END")),
+                ("hashes.txt.asc", ED25519_DETACHED),
+                ("key.asc", ED25519_KEY),
+            ]),
+            &[],
+        );
+        assert_eq!(check.status, SignatureStatus::Invalid);
+        assert_eq!(
+            check_attestation(&attestation(&[("README.md", MESSAGE)]), &[]),
+            SignatureCheck::UNSIGNED
+        );
+        let corrupted = ED25519_SIGNED.replace("=zo8c", "=zo8d");
+        assert_eq!(
+            check_attestation(&attestation(&[("README.md", &corrupted)]), &[]).status,
+            SignatureStatus::Invalid
+        );
+    }
+
+    /// Checks that signatures of subkeys are verified through their binding signatures, and that
+    /// signatures of keys which are unbound, revoked or expired are reported as unverified.
+    #[test]
+    fn unbound_keys_are_unverified() {
+        let check = |keys: &[PublicKey]| {
+            check_attestation(&attestation(&[("README.md", SUBKEY_SIGNED)]), keys)
+        };
+        let keys = load_keys(SUBKEY_KEY);
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            keys[0].binding,
+            KeyBinding::Bound {
+                expires: Some(keys[0].created + 2 * 365 * 86400)
+            }
+        );
+        assert_eq!(
+            keys[1].binding,
+            KeyBinding::Bound {
+                expires: Some(keys[1].created + 365 * 86400)
+            }
+        );
+        assert_eq!(
+            check(&keys),
+            SignatureCheck {
+                status: SignatureStatus::Valid,
+                signer: Some(SUBKEY_FINGERPRINT.into()),
+            }
+        );
+        let mut expired = keys.clone();
+        expired[1].binding = KeyBinding::Bound {
+            expires: Some(expired[1].created),
+        };
+        assert_eq!(check(&expired).status, SignatureStatus::Unverified);
+        let revoked = load_keys(REVOKED_KEY);
+        assert!(revoked.iter().all(|key| key.binding == KeyBinding::Revoked));
+        assert_eq!(check(&revoked).status, SignatureStatus::Unverified);
+        let data = match &blocks(SUBKEY_KEY)[0] {
+            Block::Armored { data: Ok(data), .. } => data.clone(),
+            _ => panic!("the key is armored"),
+        };
+        let mut unbound = Vec::new();
+        for (tag, body) in packets(&data).unwrap() {
+            if tag != SIGNATURE_TAG {
+                unbound.extend_from_slice(&[0xc0 | tag, 0xff]);
+                unbound.extend_from_slice(&(body.len() as u32).to_be_bytes());
+                unbound.extend_from_slice(body);
+            }
+        }
+        let unbound = parse_keys(&unbound).unwrap();
+        assert!(unbound.iter().all(|key| key.binding == KeyBinding::Unbound));
+        assert_eq!(check(&unbound).status, SignatureStatus::Unverified);
+        assert_eq!(
+            check_attestation(
+                &attestation(&[("README.md", ED25519_SIGNED)]),
+                &load_keys(ED25519_KEY)
+            )
+            .status,
+            SignatureStatus::Valid
+        );
+    }
+
+    /// Parses the public keys of the armored `text`.
+    fn load_keys(text: &str) -> Vec<PublicKey> {
+        let path = std::env::temp_dir().join(format!(
+            "ppot-pgp-keys-{}-{}",
+            std::process::id(),
+            crc24(text.as_bytes())
+        ));
+        fs::write(&path, text).unwrap();
+        let keys = load_keyring(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        keys
+    }
+}
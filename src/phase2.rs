@@ -0,0 +1,872 @@
+//! Groth16 Phase 2
+//!
+//! The second phase of a Groth16 setup specializes the accumulator to a circuit. The phase 2
+//! tooling of bellman builds the initial parameters of the circuit from the `phase1radix2m{N}` file
+//! of its domain, see [`export`](crate::export), with delta set to one, and every contribution
+//! multiplies delta by a secret and divides the `H` and `L` queries by it. The parameters are
+//! written as a bellman [`Parameters`] followed by the Blake2b hash of the initial parameters and
+//! every [`Contribution`], uncompressed as in challenge files, with big-endian lengths.
+//!
+//! Each contribution proves knowledge of its secret `d` as in phase 1: it holds a random G1 point
+//! `s`, its multiple `s * d` and the multiple `r * d` of a G2 point derived from the transcript of
+//! the previous contributions and of both G1 points. Recomputing the initial parameters from a
+//! verified round and a circuit with [`MpcParams::initial`] ties the final proving and verifying
+//! keys to the accumulator of that round, see [`MpcParams::verify`].
+
+use crate::{
+    export::{push_g1, push_g2},
+    fft::{ifft_with_root, Domain},
+    format::{read_g1, read_g2, Encoding, Section, HASH_SIZE},
+    hash::Hash64,
+    into_array_unchecked,
+    pok::hash_to_g2,
+    ptau::same_ratio,
+    r1cs::R1cs,
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::{Bn254, Fr, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{msm::VariableBaseMSM, AffineCurve, ProjectiveCurve};
+use ark_ff::{One, PrimeField, UniformRand, Zero};
+use blake2::{Blake2b512, Digest};
+use rayon::prelude::*;
+
+/// Number of points combined by a single multi-scalar multiplication
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Size of a contribution: three points in G1, one in G2 and the transcript hash
+const CONTRIBUTION_SIZE: usize = 3 * 64 + 128 + HASH_SIZE;
+
+/// Splits the first `len` bytes off `bytes`.
+#[inline]
+fn take<'b>(bytes: &mut &'b [u8], len: usize) -> Result<&'b [u8]> {
+    ensure!(bytes.len() >= len, "Truncated phase 2 parameters");
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// Splits a big-endian `u32` off `bytes`.
+#[inline]
+fn take_u32(bytes: &mut &[u8]) -> Result<u32> {
+    Ok(u32::from_be_bytes(take(bytes, 4)?.try_into()?))
+}
+
+/// Splits an uncompressed G1 point off `bytes`.
+#[inline]
+fn take_g1(bytes: &mut &[u8]) -> Result<G1Affine> {
+    read_g1(
+        take(bytes, Encoding::Uncompressed.g1_size())?,
+        Encoding::Uncompressed,
+    )
+}
+
+/// Reads an uncompressed G2 point, checking that it is in the subgroup.
+#[inline]
+fn read_g2_checked(bytes: &[u8]) -> Result<G2Affine> {
+    let point = read_g2(bytes, Encoding::Uncompressed)?;
+    ensure!(
+        point.is_in_correct_subgroup_assuming_on_curve(),
+        "G2 point out of the subgroup"
+    );
+    Ok(point)
+}
+
+/// Splits an uncompressed G2 point off `bytes`, checking that it is in the subgroup.
+#[inline]
+fn take_g2(bytes: &mut &[u8]) -> Result<G2Affine> {
+    read_g2_checked(take(bytes, Encoding::Uncompressed.g2_size())?)
+}
+
+/// Splits `len` uncompressed G1 points off `bytes`.
+#[inline]
+fn take_g1s(bytes: &mut &[u8], len: usize) -> Result<Vec<G1Affine>> {
+    let size = Encoding::Uncompressed.g1_size();
+    take(bytes, len * size)?
+        .par_chunks(size)
+        .map(|point| read_g1(point, Encoding::Uncompressed))
+        .collect()
+}
+
+/// Splits `len` uncompressed G2 points off `bytes`, checking that they are in the subgroup.
+#[inline]
+fn take_g2s(bytes: &mut &[u8], len: usize) -> Result<Vec<G2Affine>> {
+    take(bytes, len * Encoding::Uncompressed.g2_size())?
+        .par_chunks(Encoding::Uncompressed.g2_size())
+        .map(read_g2_checked)
+        .collect()
+}
+
+/// Splits a query of G1 points, prefixed with its length, off `bytes`, rejecting the point at
+/// infinity as bellman does.
+#[inline]
+fn take_g1_query(bytes: &mut &[u8]) -> Result<Vec<G1Affine>> {
+    let len = take_u32(bytes)? as usize;
+    let points = take_g1s(bytes, len)?;
+    ensure!(
+        points.iter().all(|point| !point.is_zero()),
+        "Point at infinity in a query"
+    );
+    Ok(points)
+}
+
+/// Splits a query of G2 points, prefixed with its length, off `bytes`, rejecting the point at
+/// infinity as bellman does.
+#[inline]
+fn take_g2_query(bytes: &mut &[u8]) -> Result<Vec<G2Affine>> {
+    let len = take_u32(bytes)? as usize;
+    let points = take_g2s(bytes, len)?;
+    ensure!(
+        points.iter().all(|point| !point.is_zero()),
+        "Point at infinity in a query"
+    );
+    Ok(points)
+}
+
+/// Appends the G1 `query` to `out`, prefixed with its length.
+#[inline]
+fn push_g1_query(out: &mut Vec<u8>, query: &[G1Affine]) {
+    out.extend_from_slice(&(query.len() as u32).to_be_bytes());
+    push_g1(out, query);
+}
+
+/// Appends the G2 `query` to `out`, prefixed with its length.
+#[inline]
+fn push_g2_query(out: &mut Vec<u8>, query: &[G2Affine]) {
+    out.extend_from_slice(&(query.len() as u32).to_be_bytes());
+    push_g2(out, query);
+}
+
+/// Phase 1 Parameters of a Radix-2 Domain
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Phase1Radix {
+    /// Alpha in G1
+    pub alpha_g1: G1Affine,
+
+    /// Beta in G1
+    pub beta_g1: G1Affine,
+
+    /// Beta in G2
+    pub beta_g2: G2Affine,
+
+    /// Lagrange basis of the domain in G1
+    pub coeffs_g1: Vec<G1Affine>,
+
+    /// Lagrange basis of the domain in G2
+    pub coeffs_g2: Vec<G2Affine>,
+
+    /// Lagrange basis of the domain multiplied by alpha in G1
+    pub alpha_coeffs_g1: Vec<G1Affine>,
+
+    /// Lagrange basis of the domain multiplied by beta in G1
+    pub beta_coeffs_g1: Vec<G1Affine>,
+
+    /// Points `tau^i * (tau^m - 1)` in G1
+    pub h: Vec<G1Affine>,
+}
+
+impl Phase1Radix {
+    /// Reads the `phase1radix2m{log_size}` file in `bytes`.
+    #[inline]
+    pub fn read(mut bytes: &[u8], log_size: u32) -> Result<Self> {
+        let m = 1 << log_size;
+        let bytes = &mut bytes;
+        let radix = Self {
+            alpha_g1: take_g1(bytes)?,
+            beta_g1: take_g1(bytes)?,
+            beta_g2: take_g2(bytes)?,
+            coeffs_g1: take_g1s(bytes, m)?,
+            coeffs_g2: take_g2s(bytes, m)?,
+            alpha_coeffs_g1: take_g1s(bytes, m)?,
+            beta_coeffs_g1: take_g1s(bytes, m)?,
+            h: take_g1s(bytes, m - 1)?,
+        };
+        ensure!(
+            bytes.is_empty(),
+            "Trailing bytes after the phase1radix2m{} parameters",
+            log_size
+        );
+        Ok(radix)
+    }
+
+    /// Computes the parameters of the radix-2 domain of size `2^log_size` with the roots of unity
+    /// of `domain` from the points of an accumulator read by `g1` and `g2` by section and index.
+    #[inline]
+    pub fn compute<F, G>(g1: F, g2: G, log_size: u32, domain: Domain) -> Result<Self>
+    where
+        F: Fn(Section, usize) -> Result<G1Affine> + Sync,
+        G: Fn(Section, usize) -> Result<G2Affine> + Sync,
+    {
+        let m = 1 << log_size;
+        let omega = domain.root_of_unity(m)?;
+        let basis_g1 = |section| -> Result<Vec<G1Affine>> {
+            let mut points = (0..m)
+                .into_par_iter()
+                .map(|index| Ok(g1(section, index)?.into_projective()))
+                .collect::<Result<Vec<_>>>()?;
+            ifft_with_root(&mut points, omega)?;
+            Ok(G1Projective::batch_normalization_into_affine(&points))
+        };
+        let mut coeffs_g2 = (0..m)
+            .into_par_iter()
+            .map(|index| Ok(g2(Section::TauG2, index)?.into_projective()))
+            .collect::<Result<Vec<_>>>()?;
+        ifft_with_root(&mut coeffs_g2, omega)?;
+        let h = (0..m - 1)
+            .into_par_iter()
+            .map(|index| {
+                Ok(g1(Section::TauG1, index + m)?.into_projective()
+                    - g1(Section::TauG1, index)?.into_projective())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            alpha_g1: g1(Section::AlphaG1, 0)?,
+            beta_g1: g1(Section::BetaG1, 0)?,
+            beta_g2: g2(Section::BetaG2, 0)?,
+            coeffs_g1: basis_g1(Section::TauG1)?,
+            coeffs_g2: G2Projective::batch_normalization_into_affine(&coeffs_g2),
+            alpha_coeffs_g1: basis_g1(Section::AlphaG1)?,
+            beta_coeffs_g1: basis_g1(Section::BetaG1)?,
+            h: G1Projective::batch_normalization_into_affine(&h),
+        })
+    }
+
+    /// Evaluates the polynomials of the wires of the circuit `r1cs` at tau, with the extra
+    /// constraint binding each input to the `A` polynomials that bellman and snarkjs add.
+    #[inline]
+    pub fn evaluate(&self, r1cs: &R1cs) -> Result<Evaluations> {
+        let m = self.coeffs_g1.len();
+        let inputs = r1cs.inputs();
+        ensure!(
+            r1cs.constraints.len() + inputs <= m,
+            "The circuit needs a domain of size 2^{}, larger than the 2^{} of the phase 1 \
+             parameters",
+            r1cs.domain_log_size(),
+            m.trailing_zeros()
+        );
+        let mut at = vec![Vec::new(); r1cs.wires];
+        let mut bt = vec![Vec::new(); r1cs.wires];
+        let mut ct = vec![Vec::new(); r1cs.wires];
+        for (index, constraint) in r1cs.constraints.iter().enumerate() {
+            for (terms, combination) in [
+                (&mut at, &constraint.a),
+                (&mut bt, &constraint.b),
+                (&mut ct, &constraint.c),
+            ] {
+                for (wire, coefficient) in combination {
+                    terms[*wire].push((index, coefficient.into_repr()));
+                }
+            }
+        }
+        for (input, terms) in at.iter_mut().enumerate().take(inputs) {
+            terms.push((r1cs.constraints.len() + input, Fr::one().into_repr()));
+        }
+        let (mut a_g1, mut b_g1, mut b_g2, mut ext) = (
+            Vec::with_capacity(r1cs.wires),
+            Vec::with_capacity(r1cs.wires),
+            Vec::with_capacity(r1cs.wires),
+            Vec::with_capacity(r1cs.wires),
+        );
+        for (a, b, c, d) in (0..r1cs.wires)
+            .into_par_iter()
+            .map(|wire| {
+                let mut a_g1 = G1Projective::zero();
+                let mut b_g1 = G1Projective::zero();
+                let mut b_g2 = G2Projective::zero();
+                let mut ext = G1Projective::zero();
+                for (index, coefficient) in &at[wire] {
+                    a_g1 += self.coeffs_g1[*index].mul(*coefficient);
+                    ext += self.beta_coeffs_g1[*index].mul(*coefficient);
+                }
+                for (index, coefficient) in &bt[wire] {
+                    b_g1 += self.coeffs_g1[*index].mul(*coefficient);
+                    b_g2 += self.coeffs_g2[*index].mul(*coefficient);
+                    ext += self.alpha_coeffs_g1[*index].mul(*coefficient);
+                }
+                for (index, coefficient) in &ct[wire] {
+                    ext += self.coeffs_g1[*index].mul(*coefficient);
+                }
+                (a_g1, b_g1, b_g2, ext)
+            })
+            .collect::<Vec<_>>()
+        {
+            a_g1.push(a);
+            b_g1.push(b);
+            b_g2.push(c);
+            ext.push(d);
+        }
+        let mut ic = G1Projective::batch_normalization_into_affine(&ext);
+        let l = ic.split_off(inputs);
+        Ok(Evaluations {
+            a_g1: G1Projective::batch_normalization_into_affine(&a_g1),
+            b_g1: G1Projective::batch_normalization_into_affine(&b_g1),
+            b_g2: G2Projective::batch_normalization_into_affine(&b_g2),
+            ic,
+            l,
+        })
+    }
+}
+
+/// Evaluations of the Polynomials of the Wires of a Circuit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Evaluations {
+    /// `A` polynomials of every wire in G1
+    pub a_g1: Vec<G1Affine>,
+
+    /// `B` polynomials of every wire in G1
+    pub b_g1: Vec<G1Affine>,
+
+    /// `B` polynomials of every wire in G2
+    pub b_g2: Vec<G2Affine>,
+
+    /// `beta * A + alpha * B + C` polynomials of the inputs in G1
+    pub ic: Vec<G1Affine>,
+
+    /// `beta * A + alpha * B + C` polynomials of the private wires in G1
+    pub l: Vec<G1Affine>,
+}
+
+/// Groth16 Verifying Key
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VerifyingKey {
+    /// Alpha in G1
+    pub alpha_g1: G1Affine,
+
+    /// Beta in G1
+    pub beta_g1: G1Affine,
+
+    /// Beta in G2
+    pub beta_g2: G2Affine,
+
+    /// Gamma in G2, left to the generator by phase 2
+    pub gamma_g2: G2Affine,
+
+    /// Delta in G1
+    pub delta_g1: G1Affine,
+
+    /// Delta in G2
+    pub delta_g2: G2Affine,
+
+    /// Input query, divided by gamma
+    pub ic: Vec<G1Affine>,
+}
+
+/// Groth16 Parameters
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Parameters {
+    /// Verifying key
+    pub vk: VerifyingKey,
+
+    /// Query of the quotient polynomial, divided by delta
+    pub h: Vec<G1Affine>,
+
+    /// Query of the private wires, divided by delta
+    pub l: Vec<G1Affine>,
+
+    /// Non-zero evaluations of the `A` polynomials of the wires in G1
+    pub a: Vec<G1Affine>,
+
+    /// Non-zero evaluations of the `B` polynomials of the wires in G1
+    pub b_g1: Vec<G1Affine>,
+
+    /// Non-zero evaluations of the `B` polynomials of the wires in G2
+    pub b_g2: Vec<G2Affine>,
+}
+
+impl Parameters {
+    /// Splits the parameters off `bytes`.
+    #[inline]
+    fn take(bytes: &mut &[u8]) -> Result<Self> {
+        let alpha_g1 = take_g1(bytes)?;
+        let beta_g1 = take_g1(bytes)?;
+        let beta_g2 = take_g2(bytes)?;
+        let gamma_g2 = take_g2(bytes)?;
+        let delta_g1 = take_g1(bytes)?;
+        let delta_g2 = take_g2(bytes)?;
+        Ok(Self {
+            vk: VerifyingKey {
+                alpha_g1,
+                beta_g1,
+                beta_g2,
+                gamma_g2,
+                delta_g1,
+                delta_g2,
+                ic: take_g1_query(bytes)?,
+            },
+            h: take_g1_query(bytes)?,
+            l: take_g1_query(bytes)?,
+            a: take_g1_query(bytes)?,
+            b_g1: take_g1_query(bytes)?,
+            b_g2: take_g2_query(bytes)?,
+        })
+    }
+
+    /// Writes the parameters to `out`.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        push_g1(out, &[self.vk.alpha_g1, self.vk.beta_g1]);
+        push_g2(out, &[self.vk.beta_g2, self.vk.gamma_g2]);
+        push_g1(out, &[self.vk.delta_g1]);
+        push_g2(out, &[self.vk.delta_g2]);
+        push_g1_query(out, &self.vk.ic);
+        push_g1_query(out, &self.h);
+        push_g1_query(out, &self.l);
+        push_g1_query(out, &self.a);
+        push_g1_query(out, &self.b_g1);
+        push_g2_query(out, &self.b_g2);
+    }
+}
+
+/// Phase 2 Contribution
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Contribution {
+    /// Delta in G1 after the contribution
+    pub delta_after: G1Affine,
+
+    /// Random G1 point
+    pub s: G1Affine,
+
+    /// Multiple of `s` by the secret of the contribution
+    pub s_delta: G1Affine,
+
+    /// Multiple of the derived G2 point by the secret of the contribution
+    pub r_delta: G2Affine,
+
+    /// Hash of the transcript the G2 point is derived from
+    pub transcript: Hash64,
+}
+
+impl Contribution {
+    /// Splits the contribution off `bytes`.
+    #[inline]
+    fn take(bytes: &mut &[u8]) -> Result<Self> {
+        Ok(Self {
+            delta_after: take_g1(bytes)?,
+            s: take_g1(bytes)?,
+            s_delta: take_g1(bytes)?,
+            r_delta: take_g2(bytes)?,
+            transcript: Hash64::try_from(take(bytes, HASH_SIZE)?)?,
+        })
+    }
+
+    /// Writes the contribution to `out`.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        push_g1(out, &[self.delta_after, self.s, self.s_delta]);
+        push_g2(out, &[self.r_delta]);
+        out.extend_from_slice(&self.transcript.0);
+    }
+
+    /// Returns the hash of the contribution, which its participant publishes.
+    #[inline]
+    pub fn hash(&self) -> Hash64 {
+        let mut bytes = Vec::with_capacity(CONTRIBUTION_SIZE);
+        self.write(&mut bytes);
+        Hash64(into_array_unchecked(Blake2b512::digest(bytes)))
+    }
+
+    /// Checks that the contribution proves knowledge of the ratio between `previous_delta` and its
+    /// delta, with `transcript` holding the hash of the initial parameters and of the previous
+    /// contributions.
+    #[inline]
+    pub(crate) fn verify(&self, transcript: &Blake2b512, previous_delta: G1Affine) -> Result {
+        let mut transcript = transcript.clone();
+        let mut points = Vec::with_capacity(2 * Encoding::Uncompressed.g1_size());
+        push_g1(&mut points, &[self.s, self.s_delta]);
+        transcript.update(points);
+        let hash = Hash64(into_array_unchecked(transcript.finalize()));
+        ensure!(
+            hash == self.transcript,
+            "The transcript hash differs from the hash of the previous contributions"
+        );
+        let r = hash_to_g2::<Bn254>(&hash.0);
+        ensure!(
+            !self.s.is_zero() && same_ratio((self.s, self.s_delta), (r, self.r_delta)),
+            "The proof of knowledge of delta is invalid"
+        );
+        ensure!(
+            same_ratio((previous_delta, self.delta_after), (r, self.r_delta)),
+            "Delta does not change by the secret of the proof of knowledge"
+        );
+        Ok(())
+    }
+}
+
+/// Phase 2 Parameters of a Circuit
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MpcParams {
+    /// Groth16 parameters after the last contribution
+    pub params: Parameters,
+
+    /// Hash of the initial parameters
+    pub cs_hash: Hash64,
+
+    /// Contributions, in order
+    pub contributions: Vec<Contribution>,
+}
+
+/// Returns the sums of `before` and `after` with the same random scalars, so that the two sums
+/// have the ratio the queries share.
+#[inline]
+pub(crate) fn combine(before: &[G1Affine], after: &[G1Affine]) -> (G1Affine, G1Affine) {
+    let (a, b) = before
+        .par_chunks(CHUNK_SIZE)
+        .zip(after.par_chunks(CHUNK_SIZE))
+        .map(|(before, after)| {
+            let mut rng = rand::thread_rng();
+            let scalars = (0..before.len())
+                .map(|_| Fr::rand(&mut rng).into_repr())
+                .collect::<Vec<_>>();
+            (
+                VariableBaseMSM::multi_scalar_mul(before, &scalars),
+                VariableBaseMSM::multi_scalar_mul(after, &scalars),
+            )
+        })
+        .reduce(
+            || (G1Projective::zero(), G1Projective::zero()),
+            |(a, b), (c, d)| (a + c, b + d),
+        );
+    (a.into_affine(), b.into_affine())
+}
+
+impl MpcParams {
+    /// Reads the phase 2 parameters in `bytes`.
+    #[inline]
+    pub fn read(mut bytes: &[u8]) -> Result<Self> {
+        let bytes = &mut bytes;
+        let params = Parameters::take(bytes)?;
+        let cs_hash = Hash64::try_from(take(bytes, HASH_SIZE)?)?;
+        let contributions = (0..take_u32(bytes)?)
+            .map(|_| Contribution::take(bytes))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            bytes.is_empty(),
+            "Trailing bytes after the phase 2 parameters"
+        );
+        Ok(Self {
+            params,
+            cs_hash,
+            contributions,
+        })
+    }
+
+    /// Writes the phase 2 parameters to `out`.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        self.params.write(out);
+        out.extend_from_slice(&self.cs_hash.0);
+        out.extend_from_slice(&(self.contributions.len() as u32).to_be_bytes());
+        for contribution in &self.contributions {
+            contribution.write(out);
+        }
+    }
+
+    /// Builds the initial parameters of the circuit `r1cs` from the phase 1 parameters of a
+    /// domain large enough for it, as bellman does: each input gets an extra constraint so that
+    /// the input query is dense, and every private wire must be constrained.
+    #[inline]
+    pub fn initial(phase1: &Phase1Radix, r1cs: &R1cs) -> Result<Self> {
+        let Evaluations {
+            a_g1,
+            b_g1,
+            b_g2,
+            ic,
+            l,
+        } = phase1.evaluate(r1cs)?;
+        if let Some(wire) = l.iter().position(|point| point.is_zero()) {
+            bail!(
+                "Wire {} of the circuit is unconstrained",
+                r1cs.inputs() + wire
+            );
+        }
+        let non_zero = |points: Vec<G1Affine>| {
+            points
+                .into_iter()
+                .filter(|point| !point.is_zero())
+                .collect()
+        };
+        let params = Parameters {
+            vk: VerifyingKey {
+                alpha_g1: phase1.alpha_g1,
+                beta_g1: phase1.beta_g1,
+                beta_g2: phase1.beta_g2,
+                gamma_g2: G2Affine::prime_subgroup_generator(),
+                delta_g1: G1Affine::prime_subgroup_generator(),
+                delta_g2: G2Affine::prime_subgroup_generator(),
+                ic,
+            },
+            h: phase1.h.clone(),
+            l,
+            a: non_zero(a_g1),
+            b_g1: non_zero(b_g1),
+            b_g2: b_g2.into_iter().filter(|point| !point.is_zero()).collect(),
+        };
+        let mut bytes = Vec::new();
+        params.write(&mut bytes);
+        Ok(Self {
+            params,
+            cs_hash: Hash64(into_array_unchecked(Blake2b512::digest(bytes))),
+            contributions: Vec::new(),
+        })
+    }
+
+    /// Checks that the parts of the parameters no contribution may change are those of `other`.
+    #[inline]
+    fn check_unchanged(&self, other: &Self) -> Result {
+        let (vk, other_vk) = (&self.params.vk, &other.params.vk);
+        ensure!(
+            self.cs_hash == other.cs_hash,
+            "The parameters are for another circuit"
+        );
+        ensure!(
+            vk.alpha_g1 == other_vk.alpha_g1
+                && vk.beta_g1 == other_vk.beta_g1
+                && vk.beta_g2 == other_vk.beta_g2
+                && vk.gamma_g2 == other_vk.gamma_g2
+                && vk.ic == other_vk.ic,
+            "The verifying key changed beyond delta"
+        );
+        ensure!(
+            self.params.a == other.params.a
+                && self.params.b_g1 == other.params.b_g1
+                && self.params.b_g2 == other.params.b_g2,
+            "The A and B queries changed"
+        );
+        ensure!(
+            self.params.h.len() == other.params.h.len()
+                && self.params.l.len() == other.params.l.len(),
+            "The H and L queries changed size"
+        );
+        Ok(())
+    }
+
+    /// Checks that delta in G2 and the `H` and `L` queries match delta in G1, given the queries and
+    /// delta of the `before` parameters.
+    #[inline]
+    fn check_delta(&self, before: &Self) -> Result {
+        let vk = &self.params.vk;
+        ensure!(
+            same_ratio(
+                (G1Affine::prime_subgroup_generator(), vk.delta_g1),
+                (G2Affine::prime_subgroup_generator(), vk.delta_g2)
+            ),
+            "Delta differs in G1 and G2"
+        );
+        for (name, old, new) in [
+            ("H", &before.params.h, &self.params.h),
+            ("L", &before.params.l, &self.params.l),
+        ] {
+            ensure!(
+                same_ratio(combine(old, new), (vk.delta_g2, before.params.vk.delta_g2)),
+                "The {} query is not divided by delta",
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the transcript hasher after the initial parameters and the first `count`
+    /// contributions.
+    #[inline]
+    fn transcript(&self, count: usize) -> Blake2b512 {
+        let mut hasher = Blake2b512::new();
+        hasher.update(self.cs_hash.0);
+        let mut bytes = Vec::with_capacity(CONTRIBUTION_SIZE);
+        for contribution in &self.contributions[..count] {
+            bytes.clear();
+            contribution.write(&mut bytes);
+            hasher.update(&bytes);
+        }
+        hasher
+    }
+
+    /// Checks that the parameters add a single valid contribution to the `before` parameters,
+    /// returning its hash.
+    #[inline]
+    pub fn verify_contribution(&self, before: &Self) -> Result<Hash64> {
+        self.check_unchanged(before)?;
+        let count = before.contributions.len();
+        ensure!(
+            self.contributions.len() == count + 1,
+            "The parameters hold {} contributions instead of {}",
+            self.contributions.len(),
+            count + 1
+        );
+        ensure!(
+            self.contributions[..count] == before.contributions[..],
+            "The previous contributions changed"
+        );
+        let contribution = &self.contributions[count];
+        contribution
+            .verify(&self.transcript(count), before.params.vk.delta_g1)
+            .map_err(|error| anyhow!("Contribution {}: {}", count + 1, error))?;
+        ensure!(
+            contribution.delta_after == self.params.vk.delta_g1,
+            "Delta differs from the delta of the last contribution"
+        );
+        self.check_delta(before)?;
+        Ok(contribution.hash())
+    }
+
+    /// Checks every contribution of the parameters from the `initial` parameters of the circuit,
+    /// returning their hashes.
+    #[inline]
+    pub fn verify(&self, initial: &Self) -> Result<Vec<Hash64>> {
+        ensure!(
+            initial.contributions.is_empty(),
+            "The initial parameters hold contributions"
+        );
+        self.check_unchanged(initial)?;
+        let mut delta = initial.params.vk.delta_g1;
+        let mut hashes = Vec::with_capacity(self.contributions.len());
+        for (index, contribution) in self.contributions.iter().enumerate() {
+            contribution
+                .verify(&self.transcript(index), delta)
+                .map_err(|error| anyhow!("Contribution {}: {}", index + 1, error))?;
+            delta = contribution.delta_after;
+            hashes.push(contribution.hash());
+        }
+        ensure!(
+            delta == self.params.vk.delta_g1,
+            "Delta differs from the delta of the last contribution"
+        );
+        self.check_delta(initial)?;
+        Ok(hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        export::phase1radix_file,
+        format::{write_g1, write_g2, Layout, Section},
+        r1cs::Constraint,
+    };
+    use ark_ff::Field;
+
+    /// Adds a contribution with the secret `delta` to `params`, as the phase 2 tooling does.
+    fn contribute(params: &MpcParams, delta: Fr) -> MpcParams {
+        let mut rng = rand::thread_rng();
+        let s = G1Affine::prime_subgroup_generator()
+            .mul(Fr::rand(&mut rng))
+            .into_affine();
+        let s_delta = s.mul(delta).into_affine();
+        let mut transcript = params.transcript(params.contributions.len());
+        let mut points = Vec::new();
+        push_g1(&mut points, &[s, s_delta]);
+        transcript.update(points);
+        let transcript = Hash64(into_array_unchecked(transcript.finalize()));
+        let mut next = params.clone();
+        let inverse = delta.inverse().unwrap();
+        for query in [&mut next.params.h, &mut next.params.l] {
+            for point in query.iter_mut() {
+                *point = point.mul(inverse).into_affine();
+            }
+        }
+        next.params.vk.delta_g1 = next.params.vk.delta_g1.mul(delta).into_affine();
+        next.params.vk.delta_g2 = next.params.vk.delta_g2.mul(delta).into_affine();
+        next.contributions.push(Contribution {
+            delta_after: next.params.vk.delta_g1,
+            s,
+            s_delta,
+            r_delta: hash_to_g2::<Bn254>(&transcript.0).mul(delta).into_affine(),
+            transcript,
+        });
+        next
+    }
+
+    /// Checks the initial parameters of a circuit against the secrets of the accumulator, and that
+    /// valid contributions are accepted while tampered ones are rejected.
+    #[test]
+    fn verify_contributions() {
+        let powers = 8;
+        let layout = Layout::challenge(powers);
+        let mut rng = rand::thread_rng();
+        let [tau, alpha, beta] = [0; 3].map(|_| Fr::rand(&mut rng));
+        let mut accumulator = vec![0; layout.file_size()];
+        for section in Section::ALL {
+            let mut power = Fr::one();
+            for index in 0..section.len(powers) {
+                let scalar = match section {
+                    Section::TauG1 | Section::TauG2 => power,
+                    Section::AlphaG1 => alpha * power,
+                    Section::BetaG1 | Section::BetaG2 => beta * power,
+                };
+                let out = &mut accumulator[layout.point_range(section, index)];
+                if section.is_g2() {
+                    let point = G2Affine::prime_subgroup_generator().mul(scalar);
+                    write_g2(&point.into_affine(), Encoding::Uncompressed, out);
+                } else {
+                    let point = G1Affine::prime_subgroup_generator().mul(scalar);
+                    write_g1(&point.into_affine(), Encoding::Uncompressed, out);
+                }
+                power *= tau;
+            }
+        }
+        let r1cs = R1cs {
+            wires: 3,
+            public: 1,
+            constraints: vec![Constraint {
+                a: vec![(2, Fr::one())],
+                b: vec![(2, Fr::one())],
+                c: vec![(1, Fr::one())],
+            }],
+        };
+        let log_size = r1cs.domain_log_size();
+        let phase1 = Phase1Radix::read(
+            &phase1radix_file(&accumulator, &layout, log_size).unwrap(),
+            log_size,
+        )
+        .unwrap();
+        assert_eq!(
+            Phase1Radix::compute(
+                |section, index| read_g1(
+                    &accumulator[layout.point_range(section, index)],
+                    Encoding::Uncompressed
+                ),
+                |section, index| read_g2(
+                    &accumulator[layout.point_range(section, index)],
+                    Encoding::Uncompressed
+                ),
+                log_size,
+                Domain::Bellman
+            )
+            .unwrap(),
+            phase1
+        );
+        let initial = MpcParams::initial(&phase1, &r1cs).unwrap();
+        let first = (tau.pow([4]) - Fr::one()) / (Fr::from(4u64) * (tau - Fr::one()));
+        let generator = G1Affine::prime_subgroup_generator();
+        assert_eq!(initial.params.a[2], generator.mul(first).into_affine());
+        assert_eq!(
+            initial.params.l,
+            [generator.mul((alpha + beta) * first).into_affine()]
+        );
+        assert_eq!(initial.params.vk.ic.len(), 2);
+        assert_eq!(initial.params.h.len(), 3);
+        let once = contribute(&initial, Fr::rand(&mut rng));
+        let twice = contribute(&once, Fr::rand(&mut rng));
+        let mut bytes = Vec::new();
+        twice.write(&mut bytes);
+        assert_eq!(MpcParams::read(&bytes).unwrap(), twice);
+        assert_eq!(
+            twice.verify(&initial).unwrap(),
+            [once.contributions[0].hash(), twice.contributions[1].hash()]
+        );
+        assert_eq!(
+            twice.verify_contribution(&once).unwrap(),
+            twice.contributions[1].hash()
+        );
+        assert!(twice.verify_contribution(&initial).is_err());
+        let mut tampered = twice.clone();
+        tampered.params.l[0] = tampered.params.l[0].mul(Fr::from(2u64)).into_affine();
+        assert!(tampered.verify(&initial).is_err());
+        assert!(tampered.verify_contribution(&once).is_err());
+        let mut tampered = twice.clone();
+        tampered.contributions[0].transcript = Hash64([1; 64]);
+        assert!(tampered.verify(&initial).is_err());
+        let unconstrained = R1cs { wires: 4, ..r1cs };
+        assert!(MpcParams::initial(&phase1, &unconstrained).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Verification Plans
+//!
+//! Before downloading anything, a [`Plan`] lists the files a range of rounds needs which are not
+//! on disk yet, how many bytes they add up to, whether they fit on disk and how long they should
+//! take to download, from the bandwidth measured on a short download with [`probe_bandwidth`].
+
+use crate::{
+    config::Config,
+    disk::{estimate, remaining_bytes, SpaceEstimate},
+    download::file_exists,
+    registry::{Registry, RemoteFile},
+    storage::StorageOptions,
+    Result,
+};
+use anyhow::{anyhow, bail};
+use core::{ops::Range, str::FromStr, time::Duration};
+use reqwest::{header::RANGE, Client};
+use std::time::Instant;
+
+/// Number of bytes downloaded to measure the bandwidth
+pub const PROBE_SIZE: u64 = 16 << 20;
+
+/// Round Range
+///
+/// Parsed from strings like `1..71`, excluding the end, `1..=70`, including it, or a single round
+/// like `40`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RoundRange(pub Range<usize>);
+
+impl FromStr for RoundRange {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let parse = |bound: &str| {
+            bound
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| anyhow!("Invalid round '{}'.", bound))
+        };
+        let range = if let Some((start, end)) = range.split_once("..=") {
+            parse(start)?..parse(end)? + 1
+        } else if let Some((start, end)) = range.split_once("..") {
+            parse(start)?..parse(end)?
+        } else {
+            let round = parse(range)?;
+            round..round + 1
+        };
+        if range.is_empty() {
+            bail!("The range of rounds {:?} is empty.", range);
+        }
+        Ok(Self(range))
+    }
+}
+
+/// Returns the files of `registry` needed to verify `rounds`: the challenge file each round starts
+/// from, its response file and the challenge file it produces, each listed once.
+#[inline]
+pub fn round_files(registry: &Registry, rounds: Range<usize>) -> Vec<&RemoteFile> {
+    let mut files = Vec::new();
+    for round in rounds {
+        for file in [
+            registry.challenges.get(round),
+            registry.responses.get(round),
+            registry.challenges.get(round + 1),
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+    files
+}
+
+/// Planned Download
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PlannedFile {
+    /// Local file name
+    pub path: String,
+
+    /// Download URL
+    pub url: String,
+
+    /// Size of the file, if known
+    pub size: Option<u64>,
+
+    /// Number of bytes left to download, if the size is known
+    pub remaining: Option<u64>,
+}
+
+/// Verification Plan
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Plan {
+    /// Files left to download
+    pub files: Vec<PlannedFile>,
+
+    /// Disk space needed by the downloads
+    pub disk: SpaceEstimate,
+}
+
+impl Plan {
+    /// Plans the downloads needed to verify `rounds` of `registry` into the storage directory of
+    /// `storage`, asking the servers for the sizes missing from the registry.
+    #[inline]
+    pub async fn new(
+        client: &Client,
+        config: &Config,
+        storage: &StorageOptions,
+        registry: &Registry,
+        rounds: Range<usize>,
+    ) -> Result<Self> {
+        let mut files = Vec::new();
+        for file in round_files(registry, rounds) {
+            let url = config.resolve_url(&file.url)?;
+            let size = match file.size {
+                Some(size) => Some(size),
+                _ => file_exists(client, &url)
+                    .await
+                    .ok()
+                    .and_then(|info| info.size),
+            };
+            let path = storage.path(&file.path);
+            let remaining = size.map(|size| remaining_bytes(&path, size));
+            if remaining == Some(0) {
+                continue;
+            }
+            files.push(PlannedFile {
+                path: file.path.clone(),
+                url,
+                size,
+                remaining,
+            });
+        }
+        let disk = estimate(
+            &storage.dir,
+            files
+                .iter()
+                .map(|file| (storage.path(&file.path), file.size)),
+        )?;
+        Ok(Self { files, disk })
+    }
+
+    /// Returns the number of bytes left to download, not counting the files of unknown size.
+    #[inline]
+    pub fn bytes(&self) -> u64 {
+        self.files.iter().filter_map(|file| file.remaining).sum()
+    }
+
+    /// Returns the time needed to download [`bytes`](Self::bytes) at `bandwidth` bytes per second.
+    #[inline]
+    pub fn download_time(&self, bandwidth: u64) -> Duration {
+        Duration::from_secs_f64(self.bytes() as f64 / bandwidth.max(1) as f64)
+    }
+}
+
+/// Measures the bandwidth to `url`, in bytes per second, by downloading its first `size` bytes.
+#[inline]
+pub async fn probe_bandwidth(client: &Client, url: &str, size: u64) -> Result<u64> {
+    let start = Instant::now();
+    let mut response = client
+        .get(url)
+        .header(RANGE, format!("bytes=0-{}", size.saturating_sub(1)))
+        .send()
+        .await?
+        .error_for_status()?;
+    let mut received = 0;
+    while let Some(chunk) = response.chunk().await? {
+        received += chunk.len() as u64;
+        if received >= size {
+            break;
+        }
+    }
+    Ok((received as f64 / start.elapsed().as_secs_f64().max(1e-3)) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that round ranges are parsed and select the files their rounds read.
+    #[test]
+    fn rounds_select_files() {
+        assert_eq!("1..71".parse::<RoundRange>().unwrap().0, 1..71);
+        assert_eq!("1..=70".parse::<RoundRange>().unwrap().0, 1..71);
+        assert_eq!("40".parse::<RoundRange>().unwrap().0, 40..41);
+        assert!("3..3".parse::<RoundRange>().is_err());
+        assert!("a..b".parse::<RoundRange>().is_err());
+        let registry = Registry::builtin();
+        let paths = round_files(&registry, 1..3)
+            .into_iter()
+            .map(|file| file.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "challenge_0001",
+                "response_0002",
+                "challenge_0002",
+                "response_0003",
+                "challenge_0003"
+            ]
+        );
+    }
+}
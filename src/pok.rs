@@ -0,0 +1,344 @@
+//! Proofs of Knowledge
+//!
+//! Every response file ends with the [`PublicKey`] of its contribution, which proves that the
+//! participant knows the secrets tau, alpha and beta it multiplied the accumulator by. For each
+//! secret `x`, the key holds a random G1 point `s`, its multiple `s * x` and the multiple `r * x` of
+//! a G2 point `r` derived from the hash of the challenge file and of both G1 points, and the proof
+//! holds if `e(s, r * x) = e(s * x, r)`.
+//!
+//! These checks only read the header and the last bytes of the response file, so a contribution
+//! can be vetted long before its round is verified, or without downloading it at all, see
+//! [`fetch_public_key`].
+//!
+//! The G2 points are derived as in the original ceremony code: the first 32 bytes of the hash seed
+//! a ChaCha20 generator, which draws coordinates until they land on the curve, with the field
+//! elements drawn directly in Montgomery form. Both the BN254 and the BLS12-381 ceremonies derive
+//! them this way, over their own curve.
+
+use crate::{
+    curve::{Curve, G1, G2},
+    format::{public_key_size, read_g1_on, read_g2_on, write_g1_on, Encoding},
+    hash::Hash64,
+    Result,
+};
+use anyhow::bail;
+use ark_bn254::Bn254;
+use ark_ec::{models::ModelParameters, PairingEngine, ProjectiveCurve};
+use ark_ff::{Field, FpParameters, PrimeField, Zero};
+use blake2::{Blake2b512, Digest};
+use core::fmt;
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha20Rng,
+};
+#[cfg(feature = "native")]
+use {
+    crate::download::{fetch_ranges, ByteRange},
+    reqwest::Client,
+};
+
+/// Secret of a contribution
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Secret {
+    /// Tau, the point the powers are evaluated at
+    Tau,
+
+    /// Alpha, multiplying the powers in the alpha section
+    Alpha,
+
+    /// Beta, multiplying the powers in the beta sections
+    Beta,
+}
+
+impl Secret {
+    /// Secrets in the order of the public key
+    pub const ALL: [Self; 3] = [Self::Tau, Self::Alpha, Self::Beta];
+
+    /// Returns the byte prefixed to the hash the G2 point of the secret is derived from.
+    #[inline]
+    pub fn personalization(self) -> u8 {
+        match self {
+            Self::Tau => 0,
+            Self::Alpha => 1,
+            Self::Beta => 2,
+        }
+    }
+}
+
+impl fmt::Display for Secret {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Tau => write!(f, "tau"),
+            Self::Alpha => write!(f, "alpha"),
+            Self::Beta => write!(f, "beta"),
+        }
+    }
+}
+
+/// Public Key of a Contribution
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PublicKey<C = Bn254>
+where
+    C: Curve,
+{
+    /// Random G1 points `s` and their multiples `s * x` for each secret `x`
+    pub g1: [(G1<C>, G1<C>); 3],
+
+    /// Multiples `r * x` of the derived G2 points for each secret `x`
+    pub g2: [G2<C>; 3],
+}
+
+impl<C> PublicKey<C>
+where
+    C: Curve,
+{
+    /// Returns the size of the public key.
+    #[inline]
+    pub fn size() -> usize {
+        public_key_size(C::FIELD_SIZE)
+    }
+
+    /// Reads the public key ending the `response` file, or the bytes of a key alone, checking
+    /// that its points are in the prime-order subgroups.
+    #[inline]
+    pub fn read(response: &[u8]) -> Result<Self> {
+        if response.len() < Self::size() {
+            bail!("The response file is too short to hold a public key.");
+        }
+        let key = &response[response.len() - Self::size()..];
+        let (g1, g2) = (
+            Encoding::Uncompressed.g1_size_on::<C>(),
+            Encoding::Uncompressed.g2_size_on::<C>(),
+        );
+        let point = |i: usize| {
+            let point = read_g1_on::<C>(&key[i * g1..], Encoding::Uncompressed)?;
+            if !point.is_in_correct_subgroup_assuming_on_curve() {
+                bail!("G1 point of the public key out of the subgroup");
+            }
+            Ok(point)
+        };
+        let g2_point = |i: usize| {
+            let point = read_g2_on::<C>(&key[6 * g1 + i * g2..], Encoding::Uncompressed)?;
+            if !point.is_in_correct_subgroup_assuming_on_curve() {
+                bail!("G2 point of the public key out of the subgroup");
+            }
+            Ok(point)
+        };
+        Ok(Self {
+            g1: [
+                (point(0)?, point(1)?),
+                (point(2)?, point(3)?),
+                (point(4)?, point(5)?),
+            ],
+            g2: [g2_point(0)?, g2_point(1)?, g2_point(2)?],
+        })
+    }
+
+    /// Checks the proof of knowledge of `secret` against the hash of the challenge file.
+    #[inline]
+    pub fn verify_secret(&self, challenge_hash: &Hash64, secret: Secret) -> bool {
+        let index = secret.personalization() as usize;
+        let (s, s_x) = self.g1[index];
+        if s.is_zero() || s_x.is_zero() {
+            return false;
+        }
+        let r = derive_g2::<C>(challenge_hash, &s, &s_x, secret);
+        C::Engine::pairing(s, self.g2[index]) == C::Engine::pairing(s_x, r)
+    }
+
+    /// Checks the proofs of knowledge of every secret against the hash of the challenge file,
+    /// returning the secrets whose proof is invalid.
+    #[inline]
+    pub fn verify(&self, challenge_hash: &Hash64) -> Vec<Secret> {
+        Secret::ALL
+            .into_iter()
+            .filter(|secret| !self.verify_secret(challenge_hash, *secret))
+            .collect()
+    }
+}
+
+/// Derives the G2 point of `secret` from the hash of the challenge file and the G1 points `s` and
+/// `s_x` of the public key.
+#[inline]
+pub fn derive_g2<C>(challenge_hash: &Hash64, s: &G1<C>, s_x: &G1<C>, secret: Secret) -> G2<C>
+where
+    C: Curve,
+{
+    let mut point = vec![0; Encoding::Uncompressed.g1_size_on::<C>()];
+    let mut hasher = Blake2b512::new();
+    hasher.update([secret.personalization()]);
+    hasher.update(challenge_hash.0);
+    write_g1_on::<C>(s, Encoding::Uncompressed, &mut point);
+    hasher.update(&point);
+    write_g1_on::<C>(s_x, Encoding::Uncompressed, &mut point);
+    hasher.update(&point);
+    hash_to_g2::<C>(&hasher.finalize())
+}
+
+/// Seeds a ChaCha20 generator with the first 32 bytes of `digest`, read as eight big-endian words,
+/// and draws a G2 point of `C` from it.
+#[inline]
+pub fn hash_to_g2<C>(digest: &[u8]) -> G2<C>
+where
+    C: Curve,
+{
+    let mut seed = [0; 32];
+    for (word, chunk) in seed.chunks_exact_mut(4).zip(digest[..32].chunks_exact(4)) {
+        word.copy_from_slice(
+            &u32::from_be_bytes(chunk.try_into().expect("chunks have four bytes")).to_le_bytes(),
+        );
+    }
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    loop {
+        let c0 = random_fq(&mut rng);
+        let c1 = random_fq(&mut rng);
+        let greatest = rng.next_u32() & 1 == 1;
+        let x =
+            <C::G2Parameters as ModelParameters>::BaseField::from_base_prime_field_elems(&[c0, c1])
+                .expect("G2 is over the quadratic extension");
+        if let Some(point) = G2::<C>::get_point_from_x(x, greatest) {
+            let point = point.scale_by_cofactor().into_affine();
+            if !point.is_zero() {
+                return point;
+            }
+        }
+    }
+}
+
+/// Draws a field element from `rng`, as little-endian limbs in Montgomery form with the top bits
+/// above the modulus cleared, until it is below the modulus.
+#[inline]
+fn random_fq<F, R>(rng: &mut R) -> F
+where
+    F: PrimeField,
+    R: RngCore,
+{
+    let r_inverse = F::from_repr(F::Params::R)
+        .and_then(|r| r.inverse())
+        .expect("the Montgomery radix is invertible");
+    loop {
+        let mut repr = F::BigInt::default();
+        for limb in repr.as_mut() {
+            *limb = rng.next_u64();
+        }
+        if let Some(last) = repr.as_mut().last_mut() {
+            *last &= u64::MAX >> F::Params::REPR_SHAVE_BITS;
+        }
+        if repr < F::Params::MODULUS {
+            return F::from_repr(repr).expect("the limbs are below the modulus") * r_inverse;
+        }
+    }
+}
+
+/// Fetches the hash of the challenge file and the last `size` bytes of the response file served
+/// at `url`, which hold its public key, with a single request for both ranges.
+#[cfg(feature = "native")]
+#[inline]
+pub async fn fetch_public_key_bytes(
+    client: &Client,
+    url: &str,
+    size: usize,
+) -> Result<(Hash64, Vec<u8>)> {
+    let ranges = [
+        ByteRange::Bounded {
+            start: 0,
+            end: crate::format::HASH_SIZE as u64 - 1,
+        },
+        ByteRange::Suffix(size as u64),
+    ];
+    let mut parts = fetch_ranges(client, url, &ranges).await?;
+    let key = parts.pop().expect("There is one part per range.");
+    let header = parts.pop().expect("There is one part per range.");
+    if key.len() != size {
+        bail!("The file at '{}' is too short", url);
+    }
+    Ok((
+        Hash64::from_header(&header)
+            .ok_or_else(|| anyhow::anyhow!("The file at '{}' is too short", url))?,
+        key,
+    ))
+}
+
+/// Fetches the hash of the challenge file and the public key of the response file served at
+/// `url`, with a single range request.
+#[cfg(feature = "native")]
+#[inline]
+pub async fn fetch_public_key<C>(client: &Client, url: &str) -> Result<(Hash64, PublicKey<C>)>
+where
+    C: Curve,
+{
+    let (hash, key) = fetch_public_key_bytes(client, url, PublicKey::<C>::size()).await?;
+    Ok((hash, PublicKey::read(&key)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::Scalar;
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::AffineCurve;
+    use ark_ff::UniformRand;
+
+    /// Checks that valid proofs of knowledge over `C` are accepted, also once read back, while
+    /// tampered ones are rejected.
+    fn check_proofs<C>()
+    where
+        C: Curve,
+    {
+        let mut rng = rand::thread_rng();
+        let challenge_hash = Hash64([3; 64]);
+        let mut g1 = [(G1::<C>::zero(), G1::<C>::zero()); 3];
+        let mut g2 = [G2::<C>::zero(); 3];
+        for secret in Secret::ALL {
+            let x = Scalar::<C>::rand(&mut rng);
+            let s = G1::<C>::prime_subgroup_generator()
+                .mul(Scalar::<C>::rand(&mut rng))
+                .into_affine();
+            let s_x = s.mul(x).into_affine();
+            let index = secret.personalization() as usize;
+            g1[index] = (s, s_x);
+            g2[index] = derive_g2::<C>(&challenge_hash, &s, &s_x, secret)
+                .mul(x)
+                .into_affine();
+        }
+        let key = PublicKey::<C> { g1, g2 };
+        assert!(key.verify(&challenge_hash).is_empty());
+        assert_eq!(key.verify(&Hash64([4; 64])), Secret::ALL);
+        let mut bytes = vec![0; PublicKey::<C>::size()];
+        let (g1_size, g2_size) = (
+            Encoding::Uncompressed.g1_size_on::<C>(),
+            Encoding::Uncompressed.g2_size_on::<C>(),
+        );
+        for (i, point) in key.g1.iter().flat_map(|(s, s_x)| [s, s_x]).enumerate() {
+            write_g1_on::<C>(point, Encoding::Uncompressed, &mut bytes[i * g1_size..]);
+        }
+        for (i, point) in key.g2.iter().enumerate() {
+            crate::format::write_g2_on::<C>(
+                point,
+                Encoding::Uncompressed,
+                &mut bytes[6 * g1_size + i * g2_size..],
+            );
+        }
+        assert_eq!(PublicKey::<C>::read(&bytes).unwrap(), key);
+        let mut tampered = key;
+        tampered.g2.swap(0, 1);
+        assert_eq!(
+            tampered.verify(&challenge_hash),
+            [Secret::Tau, Secret::Alpha]
+        );
+    }
+
+    /// Checks the generator against the ChaCha20 test vectors and the proofs of knowledge over
+    /// both curves.
+    #[test]
+    fn proofs_of_knowledge() {
+        let mut rng = ChaCha20Rng::from_seed([0; 32]);
+        assert_eq!(
+            [0; 4].map(|_| rng.next_u32()),
+            [0xade0b876, 0x903df1a0, 0xe56a5d40, 0x28bd8653]
+        );
+        check_proofs::<Bn254>();
+        check_proofs::<Bls12_381>();
+    }
+}
@@ -0,0 +1,491 @@
+//! Subaccumulator Provenance
+//!
+//! Small SRS files are often published as "extracted from round N of PPoT", which is only true if
+//! their points are exactly the first points of each section of the challenge file of that round.
+//! A [`Prefix`] holds the points of such a file re-encoded as in challenge files, whatever its
+//! format, so that it can be compared byte for byte with the corresponding ranges of the challenge
+//! file, on disk or with range requests on its mirrors.
+//!
+//! Besides the formats written by this crate and snarkjs, the KZG parameters of halo2 are
+//! recognized by their size, and any other file can be read as a list of raw points with
+//! [`Prefix::raw`], given their encoding. Files which are not a prefix are traced point by point by
+//! the [`provenance`](crate::provenance) checker.
+
+use crate::{
+    download::{ByteRange, RangeError},
+    export::{Srs, SRS_MAGIC},
+    format::{self, write_g1, write_g2, Encoding, Layout, Section, CEREMONY_POWERS},
+    ptau::{self, Ptau, PTAU_MAGIC},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use core::ops::Range;
+use rayon::prelude::*;
+use reqwest::{header::RANGE, Client, StatusCode};
+
+/// Format of a Claimed Subaccumulator
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PrefixFormat {
+    /// Challenge file with fewer powers, as written by `reduce_powers` or `ppot extract`
+    Challenge,
+
+    /// snarkjs `.ptau` file
+    Ptau,
+
+    /// arkworks SRS file written by `ppot export arkworks`
+    Arkworks,
+
+    /// halo2 KZG parameters, as written by `ppot export halo2`
+    Halo2,
+
+    /// Raw list of points, see [`Prefix::raw`]
+    Raw,
+}
+
+impl PrefixFormat {
+    /// Detects the format of the file starting with `bytes`.
+    #[inline]
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(PTAU_MAGIC) {
+            Self::Ptau
+        } else if bytes.starts_with(SRS_MAGIC) {
+            Self::Arkworks
+        } else if halo2_log_size(bytes).is_some() {
+            Self::Halo2
+        } else {
+            Self::Challenge
+        }
+    }
+}
+
+/// Returns `k` if `bytes` has the size of the halo2 KZG parameters for `2^k` rows announced by its
+/// first four bytes.
+#[inline]
+fn halo2_log_size(bytes: &[u8]) -> Option<u32> {
+    let k = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+    (k <= CEREMONY_POWERS.trailing_zeros()
+        && bytes.len() == 4 + (2 << k) * ptau::G1_SIZE + 2 * ptau::G2_SIZE)
+        .then_some(k)
+}
+
+/// Encoding of the Points of a Raw SRS File
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RawEncoding {
+    /// Big-endian coordinates, as in challenge files
+    Uncompressed,
+
+    /// Big-endian `x` coordinate with the sign of `y`, as in response files
+    Compressed,
+
+    /// Little-endian coordinates in Montgomery form, as in `.ptau` files and halo2 parameters
+    Montgomery,
+}
+
+impl RawEncoding {
+    /// Returns the size of a G1 point encoded with `self`.
+    #[inline]
+    pub fn g1_size(self) -> usize {
+        match self {
+            Self::Uncompressed => Encoding::Uncompressed.g1_size(),
+            Self::Compressed => Encoding::Compressed.g1_size(),
+            Self::Montgomery => ptau::G1_SIZE,
+        }
+    }
+
+    /// Returns the size of a G2 point encoded with `self`.
+    #[inline]
+    pub fn g2_size(self) -> usize {
+        match self {
+            Self::Uncompressed => Encoding::Uncompressed.g2_size(),
+            Self::Compressed => Encoding::Compressed.g2_size(),
+            Self::Montgomery => ptau::G2_SIZE,
+        }
+    }
+
+    /// Re-encodes the G1 points of `bytes` as in challenge files.
+    #[inline]
+    fn g1_points(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let points = bytes
+            .par_chunks(self.g1_size())
+            .map(|point| match self {
+                Self::Uncompressed => format::read_g1(point, Encoding::Uncompressed),
+                Self::Compressed => format::read_g1(point, Encoding::Compressed),
+                Self::Montgomery => ptau::read_g1(point),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(encode(&points, Encoding::Uncompressed.g1_size(), write_g1))
+    }
+
+    /// Re-encodes the G2 points of `bytes` as in challenge files.
+    #[inline]
+    fn g2_points(self, bytes: &[u8]) -> Result<Vec<u8>> {
+        let points = bytes
+            .par_chunks(self.g2_size())
+            .map(|point| match self {
+                Self::Uncompressed => format::read_g2(point, Encoding::Uncompressed),
+                Self::Compressed => format::read_g2(point, Encoding::Compressed),
+                Self::Montgomery => ptau::read_g2(point),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(encode(&points, Encoding::Uncompressed.g2_size(), write_g2))
+    }
+}
+
+/// Point Mismatch
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Mismatch {
+    /// Section holding the point
+    pub section: Section,
+
+    /// Index of the first point of the section which differs
+    pub index: usize,
+}
+
+/// Claimed Prefix of an Accumulator
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Prefix {
+    /// Format of the file the prefix was read from
+    pub format: PrefixFormat,
+
+    /// Number of powers of tau of the prefix, the number of G1 points of raw files
+    pub powers: usize,
+
+    /// Points of each section, encoded as in challenge files
+    pub sections: Vec<(Section, Vec<u8>)>,
+}
+
+/// Encodes `points` as in challenge files with `write`.
+#[inline]
+fn encode<G, F>(points: &[G], size: usize, write: F) -> Vec<u8>
+where
+    G: Sync,
+    F: Fn(&G, Encoding, &mut [u8]) + Sync,
+{
+    let mut bytes = vec![0; points.len() * size];
+    bytes
+        .par_chunks_mut(size)
+        .zip(points)
+        .for_each(|(out, point)| write(point, Encoding::Uncompressed, out));
+    bytes
+}
+
+impl Prefix {
+    /// Reads the points of the SRS file in `bytes`, detecting its format.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        let (g1, g2) = (
+            Encoding::Uncompressed.g1_size(),
+            Encoding::Uncompressed.g2_size(),
+        );
+        match PrefixFormat::detect(bytes) {
+            PrefixFormat::Challenge => {
+                let powers = (0..=CEREMONY_POWERS.trailing_zeros())
+                    .map(|log_powers| 1 << log_powers)
+                    .find(|powers| Layout::challenge(*powers).file_size() == bytes.len())
+                    .ok_or_else(|| {
+                        anyhow!("The size of the file matches no challenge file of the ceremony")
+                    })?;
+                let layout = Layout::challenge(powers);
+                Ok(Self {
+                    format: PrefixFormat::Challenge,
+                    powers,
+                    sections: Section::ALL
+                        .into_iter()
+                        .map(|section| {
+                            let bytes =
+                                &bytes[layout.offset(section)..][..layout.section_size(section)];
+                            (section, bytes.to_vec())
+                        })
+                        .collect(),
+                })
+            }
+            PrefixFormat::Ptau => {
+                let ptau = Ptau::parse(bytes)?;
+                let powers = ptau.header.powers();
+                let mut sections = Vec::new();
+                for section in Section::ALL {
+                    let data = ptau.section(ptau::section_type(section))?;
+                    let encoded = if section.is_g2() {
+                        let points = data
+                            .par_chunks(ptau::G2_SIZE)
+                            .map(ptau::read_g2)
+                            .collect::<Result<Vec<_>>>()?;
+                        encode(&points, g2, write_g2)
+                    } else {
+                        let points = data
+                            .par_chunks(ptau::G1_SIZE)
+                            .map(ptau::read_g1)
+                            .collect::<Result<Vec<_>>>()?;
+                        encode(&points, g1, write_g1)
+                    };
+                    sections.push((section, encoded));
+                }
+                Ok(Self {
+                    format: PrefixFormat::Ptau,
+                    powers,
+                    sections,
+                })
+            }
+            PrefixFormat::Arkworks => {
+                let (header, srs) = Srs::read(bytes)?;
+                Ok(Self {
+                    format: PrefixFormat::Arkworks,
+                    powers: 1 << header.log_powers,
+                    sections: vec![
+                        (Section::TauG1, encode(&srs.tau_g1, g1, write_g1)),
+                        (Section::TauG2, encode(&srs.tau_g2, g2, write_g2)),
+                        (Section::AlphaG1, encode(&srs.alpha_g1, g1, write_g1)),
+                        (Section::BetaG1, encode(&srs.beta_g1, g1, write_g1)),
+                        (Section::BetaG2, encode(&[srs.beta_g2], g2, write_g2)),
+                    ],
+                })
+            }
+            PrefixFormat::Halo2 => {
+                // The Lagrange basis following the powers in G1 is not in the accumulator
+                let k = halo2_log_size(bytes).expect("The size of the file was checked.");
+                let g1_size = RawEncoding::Montgomery.g1_size();
+                let g2_start = 4 + (2 << k) * g1_size;
+                Ok(Self {
+                    format: PrefixFormat::Halo2,
+                    powers: 1 << k,
+                    sections: vec![
+                        (
+                            Section::TauG1,
+                            RawEncoding::Montgomery.g1_points(&bytes[4..][..g1_size << k])?,
+                        ),
+                        (
+                            Section::TauG2,
+                            RawEncoding::Montgomery.g2_points(&bytes[g2_start..])?,
+                        ),
+                    ],
+                })
+            }
+            PrefixFormat::Raw => unreachable!("Raw files are never detected."),
+        }
+    }
+
+    /// Reads the raw SRS file in `bytes` as a list of G1 points in `encoding` starting `offset`
+    /// bytes into the file, followed by its last `g2` points, which are in G2. The G1 points are
+    /// compared with the powers of tau in G1 and the G2 points with the powers of tau in G2.
+    #[inline]
+    pub fn raw(bytes: &[u8], encoding: RawEncoding, offset: usize, g2: usize) -> Result<Self> {
+        let points = bytes
+            .get(offset..)
+            .ok_or_else(|| anyhow!("The file is shorter than {} bytes", offset))?;
+        let g2_bytes = g2 * encoding.g2_size();
+        let g1_bytes = points.len().saturating_sub(g2_bytes);
+        if g1_bytes + g2_bytes != points.len() || g1_bytes % encoding.g1_size() != 0 {
+            bail!(
+                "The {} bytes after offset {} are not a whole number of G1 points followed by {} G2 \
+                 points in the {:?} encoding",
+                points.len(),
+                offset,
+                g2,
+                encoding
+            );
+        }
+        Ok(Self {
+            format: PrefixFormat::Raw,
+            powers: g1_bytes / encoding.g1_size(),
+            sections: vec![
+                (Section::TauG1, encoding.g1_points(&points[..g1_bytes])?),
+                (Section::TauG2, encoding.g2_points(&points[g1_bytes..])?),
+            ],
+        })
+    }
+
+    /// Returns the byte ranges of the challenge file with `powers` powers of tau the sections of
+    /// the prefix must match.
+    #[inline]
+    pub fn ranges(&self, powers: usize) -> Result<Vec<(Section, Range<u64>)>> {
+        if self.powers > powers {
+            bail!(
+                "The prefix holds 2^{} powers, more than the 2^{} of the challenge file",
+                self.powers.trailing_zeros(),
+                powers.trailing_zeros()
+            );
+        }
+        let layout = Layout::challenge(powers);
+        Ok(self
+            .sections
+            .iter()
+            .map(|(section, bytes)| {
+                let start = layout.offset(*section) as u64;
+                (*section, start..start + bytes.len() as u64)
+            })
+            .collect())
+    }
+
+    /// Compares the prefix with the `challenge` file with `powers` powers of tau, returning the
+    /// first mismatch of each section.
+    #[inline]
+    pub fn mismatches(&self, challenge: &[u8], powers: usize) -> Result<Vec<Mismatch>> {
+        if challenge.len() != Layout::challenge(powers).file_size() {
+            bail!(
+                "The challenge file holds {} bytes instead of {}",
+                challenge.len(),
+                Layout::challenge(powers).file_size()
+            );
+        }
+        Ok(self
+            .ranges(powers)?
+            .into_iter()
+            .zip(&self.sections)
+            .filter_map(|((section, range), (_, expected))| {
+                first_mismatch(
+                    section,
+                    &challenge[range.start as usize..range.end as usize],
+                    expected,
+                    0,
+                )
+            })
+            .collect())
+    }
+
+    /// Compares the prefix with the challenge file with `powers` powers of tau served at `url`,
+    /// with one range request per section, returning the first mismatch of each section.
+    #[inline]
+    pub async fn fetch_mismatches(
+        &self,
+        client: &Client,
+        url: &str,
+        powers: usize,
+    ) -> Result<Vec<Mismatch>> {
+        let mut mismatches = Vec::new();
+        for ((section, range), (_, expected)) in
+            self.ranges(powers)?.into_iter().zip(&self.sections)
+        {
+            let mut response = client
+                .get(url)
+                .header(RANGE, ByteRange::header(&[ByteRange::of(range.clone())]))
+                .send()
+                .await?
+                .error_for_status()?;
+            if response.status() != StatusCode::PARTIAL_CONTENT {
+                return Err(RangeError::Unsupported(response.status()).into());
+            }
+            let mut offset = 0;
+            let mut mismatch = None;
+            while let Some(chunk) = response.chunk().await? {
+                let end = (offset + chunk.len()).min(expected.len());
+                if mismatch.is_none() {
+                    mismatch = first_mismatch(
+                        section,
+                        &chunk[..end - offset],
+                        &expected[offset..end],
+                        offset,
+                    );
+                }
+                offset = end;
+            }
+            if offset != expected.len() {
+                bail!("The transfer from '{}' ended early.", url);
+            }
+            mismatches.extend(mismatch);
+        }
+        Ok(mismatches)
+    }
+}
+
+/// Returns the first point of `section` where `actual` and `expected` differ, starting `offset`
+/// bytes into the section.
+#[inline]
+fn first_mismatch(
+    section: Section,
+    actual: &[u8],
+    expected: &[u8],
+    offset: usize,
+) -> Option<Mismatch> {
+    let size = section.point_size(Encoding::Uncompressed);
+    actual
+        .iter()
+        .zip(expected)
+        .position(|(actual, expected)| actual != expected)
+        .or_else(|| (actual.len() != expected.len()).then_some(actual.len().min(expected.len())))
+        .map(|position| Mismatch {
+            section,
+            index: (offset + position) / size,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        export::{arkworks, halo2, SrsHeader},
+        hash::Hash64,
+        transform::extract,
+    };
+    use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::UniformRand;
+    use std::fs;
+
+    /// Checks that prefixes extracted from a challenge file match it in every format, and that a
+    /// tampered point is located.
+    #[test]
+    fn prefix_mismatches() {
+        let powers = 8;
+        let layout = Layout::challenge(powers);
+        let mut rng = rand::thread_rng();
+        let mut challenge = vec![3; layout.file_size()];
+        for section in Section::ALL {
+            for index in 0..section.len(powers) {
+                let scalar = Fr::rand(&mut rng);
+                let out = &mut challenge[layout.point_range(section, index)];
+                if section.is_g2() {
+                    let point = G2Affine::prime_subgroup_generator().mul(scalar);
+                    write_g2(&point.into_affine(), Encoding::Uncompressed, out);
+                } else {
+                    let point = G1Affine::prime_subgroup_generator().mul(scalar);
+                    write_g1(&point.into_affine(), Encoding::Uncompressed, out);
+                }
+            }
+        }
+        let dir = std::env::temp_dir().join("ppot-verifier-prefix-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("reduced");
+        extract::<Bn254>(&challenge, powers, 4, &path).unwrap();
+        let reduced = fs::read(&path).unwrap();
+        let prefix = Prefix::read(&reduced).unwrap();
+        assert_eq!(prefix.format, PrefixFormat::Challenge);
+        assert_eq!(prefix.powers, 4);
+        assert!(prefix.mismatches(&challenge, powers).unwrap().is_empty());
+        let mut tampered = challenge.clone();
+        let range = layout.point_range(Section::AlphaG1, 2);
+        tampered[range.start + 10] ^= 1;
+        assert_eq!(
+            prefix.mismatches(&tampered, powers).unwrap(),
+            [Mismatch {
+                section: Section::AlphaG1,
+                index: 2
+            }]
+        );
+        assert!(prefix.mismatches(&challenge, 2).is_err());
+        let header = SrsHeader {
+            log_powers: 1,
+            round: 1,
+            provenance: Hash64::default(),
+        };
+        arkworks(&challenge, powers, &header, &path).unwrap();
+        let prefix = Prefix::read(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!((prefix.format, prefix.powers), (PrefixFormat::Arkworks, 2));
+        assert!(prefix.mismatches(&challenge, powers).unwrap().is_empty());
+        halo2(&challenge, powers, 2, &path).unwrap();
+        let prefix = Prefix::read(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!((prefix.format, prefix.powers), (PrefixFormat::Halo2, 4));
+        assert!(prefix.mismatches(&challenge, powers).unwrap().is_empty());
+        let tau_g1 = &challenge[layout.offset(Section::TauG1)..][..3 * 64];
+        let raw = Prefix::raw(tau_g1, RawEncoding::Uncompressed, 64, 0).unwrap();
+        assert_eq!((raw.format, raw.powers), (PrefixFormat::Raw, 2));
+        assert_eq!(
+            raw.mismatches(&challenge, powers).unwrap(),
+            [Mismatch {
+                section: Section::TauG1,
+                index: 0
+            }]
+        );
+        assert!(Prefix::raw(tau_g1, RawEncoding::Uncompressed, 0, 4).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+//! Verification Profiles
+//!
+//! A [`Profile`] breaks the time spent verifying a round down into the phases of the
+//! verification, to tell whether a run is bound by its disks or by its cores. Points are read on
+//! demand from memory-mapped files, so the time spent in [`Phase::Io`] is the time taken to fault
+//! the bytes of each point in from the disk, before they are deserialized. The phases run on every
+//! core at once, so their times are summed over the threads and may add up to more than the wall
+//! time of the round.
+
+use core::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Verification Phase
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum Phase {
+    /// Reading the bytes of the points from the files
+    Io,
+
+    /// Decoding the points from their bytes
+    Deserialization,
+
+    /// Checking that the points are in the prime-order subgroup
+    SubgroupCheck,
+
+    /// Combining the powers with multi-scalar multiplications
+    MultiExp,
+
+    /// Computing pairings
+    Pairing,
+}
+
+impl Phase {
+    /// Every phase, in the order of the verification
+    pub const ALL: [Self; 5] = [
+        Self::Io,
+        Self::Deserialization,
+        Self::SubgroupCheck,
+        Self::MultiExp,
+        Self::Pairing,
+    ];
+}
+
+/// Profile of a Verification in Progress
+///
+/// Shared by the threads of the verification, which record the time they spend in each
+/// [`Phase`] and the bytes they read.
+#[derive(Debug)]
+pub struct Profile {
+    /// Start of the verification
+    start: Instant,
+
+    /// Nanoseconds spent in each phase, indexed like [`Phase::ALL`]
+    nanos: [AtomicU64; 5],
+
+    /// Number of bytes read from the files
+    bytes_read: AtomicU64,
+}
+
+impl Default for Profile {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profile {
+    /// Starts profiling a verification.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            nanos: Default::default(),
+            bytes_read: AtomicU64::new(0),
+        }
+    }
+
+    /// Records `duration` spent in `phase`.
+    #[inline]
+    pub fn record(&self, phase: Phase, duration: Duration) {
+        self.nanos[phase as usize].fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Records that `bytes` bytes were read from the files.
+    #[inline]
+    pub fn read(&self, bytes: usize) {
+        self.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Runs `f`, recording the time it takes in `phase`.
+    #[inline]
+    pub fn time<T, F>(&self, phase: Phase, f: F) -> T
+    where
+        F: FnOnce() -> T,
+    {
+        let start = Instant::now();
+        let result = f();
+        self.record(phase, start.elapsed());
+        result
+    }
+
+    /// Returns the time spent in `phase` so far.
+    #[inline]
+    pub fn elapsed_in(&self, phase: Phase) -> Duration {
+        Duration::from_nanos(self.nanos[phase as usize].load(Ordering::Relaxed))
+    }
+
+    /// Returns the summary of the profile so far.
+    #[inline]
+    pub fn summary(&self) -> RoundProfile {
+        let millis = |phase| self.elapsed_in(phase).as_millis() as u64;
+        RoundProfile {
+            total_ms: self.start.elapsed().as_millis() as u64,
+            io_ms: millis(Phase::Io),
+            deserialization_ms: millis(Phase::Deserialization),
+            subgroup_check_ms: millis(Phase::SubgroupCheck),
+            multiexp_ms: millis(Phase::MultiExp),
+            pairing_ms: millis(Phase::Pairing),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Runs `f`, recording the time it takes in `phase` of `profile`, if any.
+#[inline]
+pub fn time<T, F>(profile: Option<&Profile>, phase: Phase, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    match profile {
+        Some(profile) => profile.time(phase, f),
+        _ => f(),
+    }
+}
+
+/// Profile of the Verification of a Round
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundProfile {
+    /// Wall time of the verification, in milliseconds
+    pub total_ms: u64,
+
+    /// Time spent reading the points, in milliseconds summed over the threads
+    pub io_ms: u64,
+
+    /// Time spent deserializing the points, in milliseconds summed over the threads
+    pub deserialization_ms: u64,
+
+    /// Time spent on subgroup checks, in milliseconds summed over the threads
+    pub subgroup_check_ms: u64,
+
+    /// Time spent on multi-scalar multiplications, in milliseconds summed over the threads
+    pub multiexp_ms: u64,
+
+    /// Time spent on pairings, in milliseconds summed over the threads
+    pub pairing_ms: u64,
+
+    /// Number of bytes read from the files
+    pub bytes_read: u64,
+}
+
+impl RoundProfile {
+    /// Returns the rate at which the bytes were read over the whole verification, in bytes per
+    /// second.
+    #[inline]
+    pub fn read_rate(&self) -> u64 {
+        self.bytes_read * 1000 / self.total_ms.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the time and bytes recorded by every thread add up in the summary.
+    #[test]
+    fn summarize_phases() {
+        let profile = Profile::new();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    profile.record(Phase::Pairing, Duration::from_millis(5));
+                    profile.read(64);
+                });
+            }
+        });
+        assert_eq!(profile.time(Phase::Io, || 7), 7);
+        assert!(time(None, Phase::Io, || true));
+        let summary = profile.summary();
+        assert_eq!(summary.pairing_ms, 20);
+        assert_eq!(summary.multiexp_ms, 0);
+        assert_eq!(summary.bytes_read, 256);
+    }
+}
@@ -0,0 +1,234 @@
+//! Progress Reporting
+//!
+//! Downloads, hashing and verification report their progress to a [`ProgressSink`] rather than
+//! drawing progress bars themselves, so that GUIs and services embedding the verifier can follow
+//! them without parsing terminal output. The command line tools draw [`ProgressBars`], one
+//! [`indicatif`] bar per file, and embedders which do not care pass [`NoProgress`].
+
+use crate::{
+    download::{progress_bar, Cancelled},
+    hash::Hash64,
+    hash_progress_bar, Result,
+};
+use indicatif::{MultiProgress, ProgressBar};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use tracing::{info, warn};
+
+/// Progress Sink
+///
+/// Every method does nothing by default, except [`on_warning`](Self::on_warning) which logs the
+/// warning, so that a sink only implements the events it follows. Files are identified by their
+/// local path, and several files may be in progress at once.
+pub trait ProgressSink: Send + Sync {
+    /// Called when the download of `path` from `url` starts or resumes, with `downloaded` of its
+    /// `len` bytes already on disk.
+    #[inline]
+    fn on_download_started(&self, url: &str, path: &Path, downloaded: u64, len: u64) {
+        let _ = (url, path, downloaded, len);
+    }
+
+    /// Called as `path` downloads, with the number of its bytes on disk, which drops back to zero
+    /// when the download starts over.
+    #[inline]
+    fn on_bytes_downloaded(&self, path: &Path, downloaded: u64) {
+        let _ = (path, downloaded);
+    }
+
+    /// Called once the download of `path` from `url` started with
+    /// [`on_download_started`](Self::on_download_started) ends, successfully or not.
+    #[inline]
+    fn on_download_finished(&self, url: &str, path: &Path, result: &Result) {
+        let _ = (url, path, result);
+    }
+
+    /// Called when hashing the `len` bytes of `path` starts.
+    #[inline]
+    fn on_hash_started(&self, path: &Path, len: u64) {
+        let _ = (path, len);
+    }
+
+    /// Called as `path` is hashed, with the number of bytes hashed since the last call.
+    #[inline]
+    fn on_bytes_hashed(&self, path: &Path, bytes: u64) {
+        let _ = (path, bytes);
+    }
+
+    /// Called once `path` is hashed, with its Blake2b `hash`.
+    #[inline]
+    fn on_file_hashed(&self, path: &Path, hash: &Hash64) {
+        let _ = (path, hash);
+    }
+
+    /// Called once the transform of `round` is verified, with the result of the verification.
+    #[inline]
+    fn on_round_verified(&self, round: usize, result: &Result) {
+        let _ = (round, result);
+    }
+
+    /// Called on a recoverable problem, such as a mirror failing over to the next one.
+    #[inline]
+    fn on_warning(&self, message: &str) {
+        warn!("{}", message);
+    }
+}
+
+/// Sink Ignoring the Progress
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct NoProgress;
+
+impl ProgressSink for NoProgress {}
+
+/// Progress Bars
+///
+/// Draws a bar for each file being downloaded or hashed in a [`MultiProgress`], and prints
+/// warnings and verified rounds above the bars.
+#[derive(Clone, Debug, Default)]
+pub struct ProgressBars {
+    /// Bars Drawn Together
+    multibar: MultiProgress,
+
+    /// Bar of each File in Progress
+    bars: Arc<Mutex<HashMap<PathBuf, ProgressBar>>>,
+}
+
+impl ProgressBars {
+    /// Builds a sink drawing its bars in `multibar`.
+    #[inline]
+    pub fn new(multibar: MultiProgress) -> Self {
+        Self {
+            multibar,
+            bars: Default::default(),
+        }
+    }
+
+    /// Returns the [`MultiProgress`] drawing the bars, to add other bars or print above them.
+    #[inline]
+    pub fn multibar(&self) -> &MultiProgress {
+        &self.multibar
+    }
+
+    /// Returns the bar of `path`, if it is in progress.
+    #[inline]
+    fn bar(&self, path: &Path) -> Option<ProgressBar> {
+        self.bars
+            .lock()
+            .expect("The bars are never poisoned.")
+            .get(path)
+            .cloned()
+    }
+
+    /// Records `bar` as the bar of `path`.
+    #[inline]
+    fn insert(&self, path: &Path, bar: ProgressBar) {
+        self.bars
+            .lock()
+            .expect("The bars are never poisoned.")
+            .insert(path.into(), bar);
+    }
+
+    /// Removes the bar of `path`, returning it if it was in progress.
+    #[inline]
+    fn remove(&self, path: &Path) -> Option<ProgressBar> {
+        self.bars
+            .lock()
+            .expect("The bars are never poisoned.")
+            .remove(path)
+    }
+}
+
+impl ProgressSink for ProgressBars {
+    #[inline]
+    fn on_download_started(&self, url: &str, path: &Path, downloaded: u64, len: u64) {
+        if let Ok(bar) = progress_bar(&self.multibar, len) {
+            bar.set_message(format!("Downloading {}", url));
+            bar.set_position(downloaded);
+            self.insert(path, bar);
+        }
+    }
+
+    #[inline]
+    fn on_bytes_downloaded(&self, path: &Path, downloaded: u64) {
+        if let Some(bar) = self.bar(path) {
+            bar.set_position(downloaded);
+        }
+    }
+
+    #[inline]
+    fn on_download_finished(&self, url: &str, path: &Path, result: &Result) {
+        match (self.remove(path), result) {
+            (Some(bar), Ok(())) => {
+                bar.finish_with_message(format!("Downloaded {} to {}", url, path.display()))
+            }
+            (Some(bar), Err(err)) if err.is::<Cancelled>() => {
+                bar.abandon_with_message(format!("Cancelled downloading {}", url))
+            }
+            (Some(bar), Err(err)) => {
+                bar.abandon_with_message(format!("Failed downloading {}: {}", url, err))
+            }
+            _ => {}
+        }
+    }
+
+    #[inline]
+    fn on_hash_started(&self, path: &Path, len: u64) {
+        if let Some(bar) = self.bar(path) {
+            bar.set_message(format!("Hashing {}", path.display()));
+        } else if let Ok(bar) = hash_progress_bar(path, len) {
+            self.insert(path, self.multibar.add(bar));
+        }
+    }
+
+    #[inline]
+    fn on_bytes_hashed(&self, path: &Path, bytes: u64) {
+        if let Some(bar) = self.bar(path) {
+            bar.inc(bytes);
+        }
+    }
+
+    #[inline]
+    fn on_file_hashed(&self, path: &Path, hash: &Hash64) {
+        if let Some(bar) = self.remove(path) {
+            bar.finish_with_message(format!("Hashed {}: {}", path.display(), hash));
+        }
+    }
+
+    #[inline]
+    fn on_round_verified(&self, round: usize, result: &Result) {
+        self.multibar.suspend(|| match result {
+            Ok(()) => info!("Round {} is valid", round),
+            Err(err) => warn!("Round {} is invalid: {}", round, err),
+        });
+    }
+
+    #[inline]
+    fn on_warning(&self, message: &str) {
+        self.multibar.suspend(|| warn!("{}", message));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indicatif::ProgressDrawTarget;
+
+    /// Checks that the bars follow the downloads and hashes of their files and are dropped once
+    /// they end.
+    #[test]
+    fn bars_follow_files() {
+        let bars = ProgressBars::new(MultiProgress::with_draw_target(ProgressDrawTarget::hidden()));
+        let (download, hash) = (Path::new("response_0001"), Path::new("challenge_0001"));
+        bars.on_download_started("https://example.com/response", download, 3, 10);
+        bars.on_bytes_downloaded(download, 7);
+        bars.on_hash_started(hash, 10);
+        bars.on_bytes_hashed(hash, 4);
+        assert_eq!(bars.bar(download).unwrap().position(), 7);
+        assert_eq!(bars.bar(hash).unwrap().position(), 4);
+        bars.on_download_finished("https://example.com/response", download, &Ok(()));
+        bars.on_file_hashed(hash, &Hash64([0; 64]));
+        assert!(bars.bars.lock().unwrap().is_empty());
+    }
+}
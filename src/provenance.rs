@@ -0,0 +1,300 @@
+//! SRS Provenance
+//!
+//! Downstream projects rarely publish their SRS as a plain prefix of a challenge file: some start
+//! at the second power, keep only the sections their proof system uses or only a couple of points
+//! in G2. [`trace`] looks up every point of an SRS in the challenge file of the round it claims to
+//! come from, first at its own index and then among the first points of the same section, and
+//! groups the points into runs of consecutive points taken from consecutive indices. A
+//! [`ProvenanceReport`] records the outcome together with the round, the challenge file and whether
+//! the round was verified, so that it can be published next to the SRS as JSON or Markdown.
+
+use crate::{
+    format::{Encoding, Layout, Section},
+    hash::Hash64,
+    prefix::Prefix,
+    Result,
+};
+use anyhow::bail;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default number of leading points of each section searched for the points of an SRS which are
+/// not at their own index
+pub const DEFAULT_SEARCH_POINTS: usize = 1 << 20;
+
+/// Run of Consecutive Points Taken from Consecutive Indices
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct PointRun {
+    /// Index of the first point of the run in the SRS
+    pub first_point: usize,
+
+    /// Index of the first point of the run in the challenge file
+    pub first_index: usize,
+
+    /// Number of points of the run
+    pub len: usize,
+}
+
+/// Provenance of the Points of a Section
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SectionProvenance {
+    /// Section of the accumulator the points are looked up in
+    pub section: Section,
+
+    /// Number of points of the SRS in the section
+    pub points: usize,
+
+    /// Runs of points found in the challenge file, in the order of the SRS
+    pub runs: Vec<PointRun>,
+
+    /// Indices in the SRS of the points not found in the challenge file
+    pub unmatched: Vec<usize>,
+}
+
+impl SectionProvenance {
+    /// Returns `true` if every point of the section was found in the challenge file.
+    #[inline]
+    pub fn is_traced(&self) -> bool {
+        self.unmatched.is_empty()
+    }
+
+    /// Returns `true` if the points of the section are the first points of the challenge file.
+    #[inline]
+    pub fn is_prefix(&self) -> bool {
+        self.is_traced()
+            && match self.runs.as_slice() {
+                [] => true,
+                [run] => run.first_index == 0,
+                _ => false,
+            }
+    }
+}
+
+/// Traces every point of `prefix` to the `challenge` file with `powers` powers of tau, searching
+/// the first `search` points of each section for the points which are not at their own index.
+#[inline]
+pub fn trace(
+    prefix: &Prefix,
+    challenge: &[u8],
+    powers: usize,
+    search: usize,
+) -> Result<Vec<SectionProvenance>> {
+    let layout = Layout::challenge(powers);
+    if challenge.len() != layout.file_size() {
+        bail!(
+            "The challenge file holds {} bytes instead of {}",
+            challenge.len(),
+            layout.file_size()
+        );
+    }
+    Ok(prefix
+        .sections
+        .iter()
+        .filter(|(_, points)| !points.is_empty())
+        .map(|(section, points)| {
+            let size = section.point_size(Encoding::Uncompressed);
+            let available = section.len(powers);
+            let point = |index: usize| &challenge[layout.offset(*section) + index * size..][..size];
+            let own = points
+                .par_chunks(size)
+                .enumerate()
+                .map(|(i, bytes)| (i < available && point(i) == bytes).then_some(i))
+                .collect::<Vec<_>>();
+            let index = if own.iter().all(Option::is_some) {
+                HashMap::new()
+            } else {
+                (0..available.min(search))
+                    .map(|index| (point(index), index))
+                    .collect::<HashMap<_, _>>()
+            };
+            let mut provenance = SectionProvenance {
+                section: *section,
+                points: own.len(),
+                runs: Vec::new(),
+                unmatched: Vec::new(),
+            };
+            for (i, (bytes, own)) in points.chunks(size).zip(own).enumerate() {
+                match own.or_else(|| index.get(bytes).copied()) {
+                    Some(found) => match provenance.runs.last_mut() {
+                        Some(run)
+                            if run.first_point + run.len == i
+                                && run.first_index + run.len == found =>
+                        {
+                            run.len += 1
+                        }
+                        _ => provenance.runs.push(PointRun {
+                            first_point: i,
+                            first_index: found,
+                            len: 1,
+                        }),
+                    },
+                    _ => provenance.unmatched.push(i),
+                }
+            }
+            provenance
+        })
+        .collect())
+}
+
+/// Provenance Report of an SRS File
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct ProvenanceReport {
+    /// Name of the SRS file
+    pub file: String,
+
+    /// Format the SRS file was read as
+    pub format: String,
+
+    /// Round the SRS claims to come from
+    pub round: usize,
+
+    /// Challenge file produced by the round
+    pub challenge: String,
+
+    /// Blake2b hash of the challenge file, if it was computed
+    pub challenge_hash: Option<Hash64>,
+
+    /// `true` if the round is recorded as verified
+    pub verified: bool,
+
+    /// Provenance of the points of every section of the SRS
+    pub sections: Vec<SectionProvenance>,
+}
+
+impl ProvenanceReport {
+    /// Returns `true` if every point of the SRS was found in the challenge file of a verified
+    /// round.
+    #[inline]
+    pub fn is_traced(&self) -> bool {
+        self.verified && self.sections.iter().all(SectionProvenance::is_traced)
+    }
+
+    /// Renders the report as a Markdown document.
+    #[inline]
+    pub fn markdown(&self) -> String {
+        let mut markdown = format!("# Provenance of `{}`\n\n", self.file);
+        markdown.push_str(&format!(
+            "- Read as a {} file\n- Claimed source: round {}, `{}`{}\n- Round {}\n",
+            self.format,
+            self.round,
+            self.challenge,
+            match &self.challenge_hash {
+                Some(hash) => format!(" with Blake2b hash `{}`", hash),
+                _ => String::new(),
+            },
+            if self.verified {
+                "verified"
+            } else {
+                "**not verified**"
+            },
+        ));
+        markdown.push_str("\n| Section | Points | Taken From | Unmatched |\n");
+        markdown.push_str("|---------|-------:|------------|----------:|\n");
+        for section in &self.sections {
+            let runs = section
+                .runs
+                .iter()
+                .map(|run| {
+                    format!(
+                        "{}..{} from {}..{}",
+                        run.first_point,
+                        run.first_point + run.len,
+                        run.first_index,
+                        run.first_index + run.len
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            markdown.push_str(&format!(
+                "| {:?} | {} | {} | {} |\n",
+                section.section,
+                section.points,
+                if section.is_prefix() {
+                    String::from("prefix")
+                } else {
+                    runs
+                },
+                section.unmatched.len()
+            ));
+        }
+        markdown.push_str(&format!(
+            "\n{}\n",
+            if self.is_traced() {
+                "Every point of the SRS comes from the verified accumulator."
+            } else {
+                "**The SRS is not traced to a verified accumulator.**"
+            }
+        ));
+        markdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::write_g1, prefix::RawEncoding};
+    use ark_bn254::{Fr, G1Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::UniformRand;
+
+    /// Checks that shifted and reordered points are traced to their indices and that foreign
+    /// points are reported.
+    #[test]
+    fn points_are_traced() {
+        let powers = 8;
+        let layout = Layout::challenge(powers);
+        let mut rng = rand::thread_rng();
+        let mut challenge = vec![0; layout.file_size()];
+        let mut random = || {
+            let mut bytes = [0; 64];
+            let point = G1Affine::prime_subgroup_generator().mul(Fr::rand(&mut rng));
+            write_g1(&point.into_affine(), Encoding::Uncompressed, &mut bytes);
+            bytes
+        };
+        for index in 0..Section::TauG1.len(powers) {
+            challenge[layout.point_range(Section::TauG1, index)].copy_from_slice(&random());
+        }
+        let point = |index| challenge[layout.point_range(Section::TauG1, index)].to_vec();
+        let srs = [point(1), point(2), point(3), point(0), random().to_vec()].concat();
+        let prefix = Prefix::raw(&srs, RawEncoding::Uncompressed, 0, 0).unwrap();
+        let sections = trace(&prefix, &challenge, powers, DEFAULT_SEARCH_POINTS).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(
+            sections[0].runs,
+            [
+                PointRun {
+                    first_point: 0,
+                    first_index: 1,
+                    len: 3
+                },
+                PointRun {
+                    first_point: 3,
+                    first_index: 0,
+                    len: 1
+                },
+            ]
+        );
+        assert_eq!(sections[0].unmatched, [4]);
+        assert!(!sections[0].is_prefix());
+        let mut report = ProvenanceReport {
+            file: "srs.bin".into(),
+            format: "Raw".into(),
+            round: 1,
+            challenge: "challenge_0002".into(),
+            challenge_hash: None,
+            verified: true,
+            sections,
+        };
+        assert!(!report.is_traced());
+        assert!(report
+            .markdown()
+            .contains("| TauG1 | 5 | 0..3 from 1..4, 3..4 from 0..1 | 1 |"));
+        let srs = [point(0), point(1)].concat();
+        let prefix = Prefix::raw(&srs, RawEncoding::Uncompressed, 0, 0).unwrap();
+        report.sections = trace(&prefix, &challenge, powers, DEFAULT_SEARCH_POINTS).unwrap();
+        assert!(report.sections[0].is_prefix());
+        assert!(report.is_traced());
+        assert!(trace(&prefix, &challenge[1..], powers, DEFAULT_SEARCH_POINTS).is_err());
+    }
+}
@@ -0,0 +1,845 @@
+//! snarkjs `.ptau` Files
+//!
+//! snarkjs stores a powers of tau accumulator in a `.ptau` file: after the `ptau` magic, a version
+//! and the number of sections, every section is a little-endian type and size followed by its
+//! data. The header section holds the size of the field elements, the modulus and the number of
+//! powers of the file and of its ceremony, the next five sections hold the same points as a PPoT
+//! accumulator, truncated to the powers of the file, and the contributions section records every
+//! contribution, with its public key and the hash of the challenge it produced. Points are written
+//! little-endian in Montgomery form, `c0` first, and the point at infinity is all zeros.
+//!
+//! The `powersOfTau28_hez_final_XX.ptau` files imported a response file of PPoT and added a random
+//! beacon to it. [`Ptau::check_powers`] checks that the points of a file are powers of the same
+//! secrets, and [`Contribution::follows`] that each contribution builds on the previous one, which
+//! ties the file to the PPoT challenge file whose hash is asserted by the imported contribution.
+//!
+//! The beacon is public and deterministic: its secrets are derived from the beacon hash and the
+//! number of iterations recorded in its contribution, so the files could be rebuilt from a
+//! verified accumulator. Rebuilding them takes the key derivation of snarkjs over its ChaCha
+//! generator and the sections the files add for phase 2, which are not implemented here, and the
+//! hashes the files were published with are not embedded in the crate. [`PublishedHashes`] loads
+//! them instead from a JSON file supplied by the user, mapping the name of every file to its
+//! Blake2b hash: a file whose hash matches and which builds on a verified round is the file the
+//! round vouches for, while without the hashes a file is only known to build on the round.
+
+use crate::{
+    accumulator::{self, combine_powers},
+    format::{Section, HASH_SIZE, PUBLIC_KEY_SIZE},
+    hash::Hash64,
+    pok::{derive_g2, PublicKey, Secret},
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ec::AffineCurve;
+use ark_ff::{BigInteger, BigInteger256, FpParameters, PrimeField, Zero};
+use core::ops::Range;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io::ErrorKind, path::Path};
+
+/// Magic bytes starting every `.ptau` file
+pub const PTAU_MAGIC: &[u8; 4] = b"ptau";
+
+/// Size of a field element of the BN254 base field
+const FIELD_SIZE: usize = 32;
+
+/// Size of a G1 point
+pub const G1_SIZE: usize = 2 * FIELD_SIZE;
+
+/// Size of a G2 point
+pub const G2_SIZE: usize = 4 * FIELD_SIZE;
+
+/// Size of the Blake2b state recorded after hashing the first part of a contribution
+pub const PARTIAL_HASH_SIZE: usize = 216;
+
+/// Type of the header section
+pub const HEADER_SECTION: u32 = 1;
+
+/// Type of the contributions section
+pub const CONTRIBUTIONS_SECTION: u32 = 7;
+
+/// Returns the type of the section of `.ptau` files holding the points of `section`.
+#[inline]
+pub fn section_type(section: Section) -> u32 {
+    match section {
+        Section::TauG1 => 2,
+        Section::TauG2 => 3,
+        Section::AlphaG1 => 4,
+        Section::BetaG1 => 5,
+        Section::BetaG2 => 6,
+    }
+}
+
+/// Reads a little-endian `u32` at `offset` of `bytes`.
+#[inline]
+pub(crate) fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(
+        bytes
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("Unexpected end of the file"))?
+            .try_into()?,
+    ))
+}
+
+/// Reads a little-endian `u64` at `offset` of `bytes`.
+#[inline]
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(
+        bytes
+            .get(offset..offset + 8)
+            .ok_or_else(|| anyhow!("Unexpected end of the file"))?
+            .try_into()?,
+    ))
+}
+
+/// Reads a field element from its 32 little-endian bytes in Montgomery form.
+#[inline]
+fn read_fq(bytes: &[u8]) -> Result<Fq> {
+    let mut limbs = [0; 4];
+    for (limb, chunk) in limbs.iter_mut().zip(bytes[..FIELD_SIZE].chunks_exact(8)) {
+        *limb = u64::from_le_bytes(chunk.try_into()?);
+    }
+    let repr = BigInteger256::new(limbs);
+    ensure!(
+        repr < <Fq as PrimeField>::Params::MODULUS,
+        "Invalid field element"
+    );
+    Ok(Fq::new(repr))
+}
+
+/// Writes `element` to its 32 little-endian bytes in Montgomery form in `out`.
+#[inline]
+fn write_fq(element: &Fq, out: &mut [u8]) {
+    out[..FIELD_SIZE].copy_from_slice(&element.0.to_bytes_le());
+}
+
+/// Reads an element of the quadratic extension, `c0` first.
+#[inline]
+fn read_fq2(bytes: &[u8]) -> Result<Fq2> {
+    Ok(Fq2::new(
+        read_fq(&bytes[..FIELD_SIZE])?,
+        read_fq(&bytes[FIELD_SIZE..])?,
+    ))
+}
+
+/// Writes an element of the quadratic extension, `c0` first.
+#[inline]
+fn write_fq2(element: &Fq2, out: &mut [u8]) {
+    write_fq(&element.c0, &mut out[..FIELD_SIZE]);
+    write_fq(&element.c1, &mut out[FIELD_SIZE..]);
+}
+
+/// Reads a G1 point as written in `.ptau` files, checking that it is on the curve.
+#[inline]
+pub fn read_g1(bytes: &[u8]) -> Result<G1Affine> {
+    let bytes = &bytes[..G1_SIZE];
+    if bytes.iter().all(|byte| *byte == 0) {
+        return Ok(G1Affine::zero());
+    }
+    let point = G1Affine::new(read_fq(bytes)?, read_fq(&bytes[FIELD_SIZE..])?, false);
+    ensure!(point.is_on_curve(), "G1 point not on the curve");
+    Ok(point)
+}
+
+/// Writes `point` as written in `.ptau` files to `out`.
+#[inline]
+pub fn write_g1(point: &G1Affine, out: &mut [u8]) {
+    let out = &mut out[..G1_SIZE];
+    out.fill(0);
+    if !point.is_zero() {
+        write_fq(&point.x, out);
+        write_fq(&point.y, &mut out[FIELD_SIZE..]);
+    }
+}
+
+/// Reads a G2 point as written in `.ptau` files, checking that it is on the curve.
+#[inline]
+pub fn read_g2(bytes: &[u8]) -> Result<G2Affine> {
+    let bytes = &bytes[..G2_SIZE];
+    if bytes.iter().all(|byte| *byte == 0) {
+        return Ok(G2Affine::zero());
+    }
+    let point = G2Affine::new(read_fq2(bytes)?, read_fq2(&bytes[2 * FIELD_SIZE..])?, false);
+    ensure!(point.is_on_curve(), "G2 point not on the curve");
+    Ok(point)
+}
+
+/// Writes `point` as written in `.ptau` files to `out`.
+#[inline]
+pub fn write_g2(point: &G2Affine, out: &mut [u8]) {
+    let out = &mut out[..G2_SIZE];
+    out.fill(0);
+    if !point.is_zero() {
+        write_fq2(&point.x, out);
+        write_fq2(&point.y, &mut out[2 * FIELD_SIZE..]);
+    }
+}
+
+/// Returns `true` if the ratio between the G1 points `a` is the ratio between the G2 points `b`.
+#[inline]
+pub fn same_ratio(a: (G1Affine, G1Affine), b: (G2Affine, G2Affine)) -> bool {
+    accumulator::same_ratio::<Bn254>(a, b)
+}
+
+/// Optional Parameters of a Contribution
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub(crate) struct ContributionParams {
+    /// Name of the participant
+    pub name: Option<String>,
+
+    /// Base-two logarithm of the number of iterations of the beacon
+    pub num_iterations_exp: Option<u8>,
+
+    /// Hash the beacon starts from
+    pub beacon_hash: Option<Vec<u8>>,
+}
+
+/// Reads the optional parameters of a contribution from `params`.
+#[inline]
+pub(crate) fn read_params(params: &[u8]) -> Result<ContributionParams> {
+    let mut result = ContributionParams::default();
+    let mut i = 0;
+    while i < params.len() {
+        let value = |i: usize| {
+            params
+                .get(i)
+                .copied()
+                .ok_or_else(|| anyhow!("Truncated contribution parameters"))
+        };
+        let bytes = |i: usize| -> Result<&[u8]> {
+            let len = value(i)? as usize;
+            params
+                .get(i + 1..i + 1 + len)
+                .ok_or_else(|| anyhow!("Truncated contribution parameters"))
+        };
+        match params[i] {
+            1 => {
+                let bytes = bytes(i + 1)?;
+                result.name = Some(String::from_utf8_lossy(bytes).into_owned());
+                i += 2 + bytes.len();
+            }
+            2 => {
+                result.num_iterations_exp = Some(value(i + 1)?);
+                i += 2;
+            }
+            3 => {
+                let hash = bytes(i + 1)?;
+                result.beacon_hash = Some(hash.to_vec());
+                i += 2 + hash.len();
+            }
+            other => bail!("Unknown contribution parameter {}", other),
+        }
+    }
+    Ok(result)
+}
+
+/// Contribution Record
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Contribution {
+    /// First power of tau in G1 after the contribution
+    pub tau_g1: G1Affine,
+
+    /// First power of tau in G2 after the contribution
+    pub tau_g2: G2Affine,
+
+    /// Alpha in G1 after the contribution
+    pub alpha_g1: G1Affine,
+
+    /// Beta in G1 after the contribution
+    pub beta_g1: G1Affine,
+
+    /// Beta in G2 after the contribution
+    pub beta_g2: G2Affine,
+
+    /// Public key of the contribution
+    pub key: PublicKey,
+
+    /// State of the Blake2b hasher after the first part of the contribution
+    pub partial_hash: Vec<u8>,
+
+    /// Hash of the challenge produced by the contribution
+    pub next_challenge: Hash64,
+
+    /// Whether the contribution was derived from a random beacon
+    pub beacon: bool,
+
+    /// Name given to the contribution, if any
+    pub name: Option<String>,
+
+    /// Hash of the random beacon, if any
+    pub beacon_hash: Option<Vec<u8>>,
+
+    /// Base-two logarithm of the number of iterations of the beacon hash, if any
+    pub num_iterations_exp: Option<u8>,
+}
+
+impl Contribution {
+    /// Size of the fixed part of a record: five points, the public key, the partial hash, the
+    /// challenge hash and the contribution type
+    const FIXED_SIZE: usize =
+        3 * G1_SIZE + 2 * G2_SIZE + PUBLIC_KEY_SIZE + PARTIAL_HASH_SIZE + HASH_SIZE + 4;
+
+    /// Reads the contribution record at the start of `bytes`, returning it with its size.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<(Self, usize)> {
+        ensure!(
+            bytes.len() >= Self::FIXED_SIZE + 4,
+            "Truncated contribution record"
+        );
+        let key = &bytes[3 * G1_SIZE + 2 * G2_SIZE..];
+        let key_point = |i: usize| read_g1(&key[i * G1_SIZE..]);
+        let key_g2_point = |i: usize| read_g2(&key[6 * G1_SIZE + i * G2_SIZE..]);
+        let key = PublicKey {
+            g1: [
+                (key_point(0)?, key_point(1)?),
+                (key_point(2)?, key_point(3)?),
+                (key_point(4)?, key_point(5)?),
+            ],
+            g2: [key_g2_point(0)?, key_g2_point(1)?, key_g2_point(2)?],
+        };
+        let offset = 3 * G1_SIZE + 2 * G2_SIZE + PUBLIC_KEY_SIZE;
+        let partial_hash = bytes[offset..offset + PARTIAL_HASH_SIZE].to_vec();
+        let next_challenge = Hash64::try_from(&bytes[offset + PARTIAL_HASH_SIZE..][..HASH_SIZE])?;
+        let kind = read_u32(bytes, Self::FIXED_SIZE - 4)?;
+        let params_size = read_u32(bytes, Self::FIXED_SIZE)? as usize;
+        let params = bytes
+            .get(Self::FIXED_SIZE + 4..Self::FIXED_SIZE + 4 + params_size)
+            .ok_or_else(|| anyhow!("Truncated contribution parameters"))?;
+        let ContributionParams {
+            name,
+            num_iterations_exp,
+            beacon_hash,
+        } = read_params(params)?;
+        let contribution = Self {
+            tau_g1: read_g1(bytes)?,
+            tau_g2: read_g2(&bytes[G1_SIZE..])?,
+            alpha_g1: read_g1(&bytes[G1_SIZE + G2_SIZE..])?,
+            beta_g1: read_g1(&bytes[2 * G1_SIZE + G2_SIZE..])?,
+            beta_g2: read_g2(&bytes[3 * G1_SIZE + G2_SIZE..])?,
+            key,
+            partial_hash,
+            next_challenge,
+            beacon: kind == 1,
+            name,
+            beacon_hash,
+            num_iterations_exp,
+        };
+        Ok((contribution, Self::FIXED_SIZE + 4 + params_size))
+    }
+
+    /// Writes the contribution record to `out`.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let mut g1 = [0; G1_SIZE];
+        let mut g2 = [0; G2_SIZE];
+        let mut push_g1 = |out: &mut Vec<u8>, point: &G1Affine| {
+            write_g1(point, &mut g1);
+            out.extend_from_slice(&g1);
+        };
+        push_g1(out, &self.tau_g1);
+        write_g2(&self.tau_g2, &mut g2);
+        out.extend_from_slice(&g2);
+        push_g1(out, &self.alpha_g1);
+        push_g1(out, &self.beta_g1);
+        write_g2(&self.beta_g2, &mut g2);
+        out.extend_from_slice(&g2);
+        for (s, s_x) in &self.key.g1 {
+            push_g1(out, s);
+            push_g1(out, s_x);
+        }
+        for point in &self.key.g2 {
+            write_g2(point, &mut g2);
+            out.extend_from_slice(&g2);
+        }
+        out.extend_from_slice(&self.partial_hash);
+        out.extend_from_slice(&self.next_challenge.0);
+        out.extend_from_slice(&u32::from(self.beacon).to_le_bytes());
+        let mut params = Vec::new();
+        if let Some(name) = &self.name {
+            let name = &name.as_bytes()[..name.len().min(64)];
+            params.extend_from_slice(&[1, name.len() as u8]);
+            params.extend_from_slice(name);
+        }
+        if let Some(num_iterations_exp) = self.num_iterations_exp {
+            params.extend_from_slice(&[2, num_iterations_exp]);
+        }
+        if let Some(hash) = &self.beacon_hash {
+            params.extend_from_slice(&[3, hash.len() as u8]);
+            params.extend_from_slice(hash);
+        }
+        out.extend_from_slice(&(params.len() as u32).to_le_bytes());
+        out.extend_from_slice(&params);
+    }
+
+    /// Checks that `self` builds on the `previous` contribution: its proofs of knowledge must hold
+    /// against the challenge produced by `previous`, and its points must be the points of
+    /// `previous` multiplied by its secrets. Returns the secrets whose checks fail.
+    #[inline]
+    pub fn follows(&self, previous: &Self) -> Vec<Secret> {
+        let challenge_hash = &previous.next_challenge;
+        Secret::ALL
+            .into_iter()
+            .filter(|secret| {
+                let index = secret.personalization() as usize;
+                let (s, s_x) = self.key.g1[index];
+                let r = derive_g2::<Bn254>(challenge_hash, &s, &s_x, *secret);
+                let r_x = self.key.g2[index];
+                let valid = self.key.verify_secret(challenge_hash, *secret)
+                    && match secret {
+                        Secret::Tau => {
+                            same_ratio((previous.tau_g1, self.tau_g1), (r, r_x))
+                                && same_ratio((s, s_x), (previous.tau_g2, self.tau_g2))
+                        }
+                        Secret::Alpha => same_ratio((previous.alpha_g1, self.alpha_g1), (r, r_x)),
+                        Secret::Beta => {
+                            same_ratio((previous.beta_g1, self.beta_g1), (r, r_x))
+                                && same_ratio((s, s_x), (previous.beta_g2, self.beta_g2))
+                        }
+                    };
+                !valid
+            })
+            .collect()
+    }
+}
+
+/// `.ptau` Header
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PtauHeader {
+    /// Version of the file format
+    pub version: u32,
+
+    /// Base-two logarithm of the number of powers of tau in the file
+    pub power: u32,
+
+    /// Base-two logarithm of the number of powers of tau of the ceremony the file comes from
+    pub ceremony_power: u32,
+}
+
+impl PtauHeader {
+    /// Returns the number of powers of tau in the file.
+    #[inline]
+    pub fn powers(&self) -> usize {
+        1 << self.power
+    }
+}
+
+/// Parses the version and the byte ranges of the sections, by type, of the file in `data`, made of
+/// sections as `.ptau` files are after their four magic bytes.
+#[inline]
+pub(crate) fn parse_sections(data: &[u8]) -> Result<(u32, BTreeMap<u32, Range<usize>>)> {
+    let version = read_u32(data, 4)?;
+    let count = read_u32(data, 8)?;
+    let mut sections = BTreeMap::new();
+    let mut offset = 12;
+    for _ in 0..count {
+        let kind = read_u32(data, offset)?;
+        let size = read_u64(data, offset + 4)? as usize;
+        let start = offset + 12;
+        ensure!(
+            start + size <= data.len(),
+            "Section {} ends past the end of the file",
+            kind
+        );
+        if sections.insert(kind, start..start + size).is_some() {
+            bail!("Duplicate section {}", kind);
+        }
+        offset = start + size;
+    }
+    Ok((version, sections))
+}
+
+/// `.ptau` File
+#[derive(Clone, Debug)]
+pub struct Ptau<'d> {
+    /// Bytes of the file
+    data: &'d [u8],
+
+    /// Byte ranges of the sections, by type
+    sections: BTreeMap<u32, Range<usize>>,
+
+    /// Header of the file
+    pub header: PtauHeader,
+
+    /// Contributions, in order
+    pub contributions: Vec<Contribution>,
+}
+
+impl<'d> Ptau<'d> {
+    /// Parses the sections, the header and the contributions of the `.ptau` file in `data`. The
+    /// points are only read on demand.
+    #[inline]
+    pub fn parse(data: &'d [u8]) -> Result<Self> {
+        ensure!(data.starts_with(PTAU_MAGIC), "Not a .ptau file");
+        let (version, sections) = parse_sections(data)?;
+        let header = sections
+            .get(&HEADER_SECTION)
+            .map(|range| &data[range.clone()])
+            .ok_or_else(|| anyhow!("Missing header section"))?;
+        ensure!(
+            read_u32(header, 0)? as usize == FIELD_SIZE,
+            "The file is not over BN254"
+        );
+        let modulus = header
+            .get(4..4 + FIELD_SIZE)
+            .ok_or_else(|| anyhow!("Truncated header section"))?;
+        ensure!(
+            modulus == <Fq as PrimeField>::Params::MODULUS.to_bytes_le(),
+            "The file is not over BN254"
+        );
+        let header = PtauHeader {
+            version,
+            power: read_u32(header, 4 + FIELD_SIZE)?,
+            ceremony_power: read_u32(header, 8 + FIELD_SIZE)?,
+        };
+        ensure!(header.power < 64, "Invalid power {}", header.power);
+        let mut ptau = Self {
+            data,
+            sections,
+            header,
+            contributions: Vec::new(),
+        };
+        for section in Section::ALL {
+            let expected = section.len(header.powers()) * point_size(section);
+            ensure!(
+                ptau.section(section_type(section))?.len() == expected,
+                "Section {:?} holds {} bytes instead of {}",
+                section,
+                ptau.section(section_type(section))?.len(),
+                expected
+            );
+        }
+        if let Ok(records) = ptau.section(CONTRIBUTIONS_SECTION) {
+            let count = read_u32(records, 0)?;
+            let mut offset = 4;
+            for _ in 0..count {
+                let (contribution, size) = Contribution::read(&records[offset..])?;
+                ptau.contributions.push(contribution);
+                offset += size;
+            }
+        }
+        Ok(ptau)
+    }
+
+    /// Returns the bytes of the section of type `kind`.
+    #[inline]
+    pub fn section(&self, kind: u32) -> Result<&'d [u8]> {
+        self.sections
+            .get(&kind)
+            .map(|range| &self.data[range.clone()])
+            .ok_or_else(|| anyhow!("Missing section {}", kind))
+    }
+
+    /// Returns the types of the sections of the file.
+    #[inline]
+    pub fn section_types(&self) -> impl Iterator<Item = u32> + '_ {
+        self.sections.keys().copied()
+    }
+
+    /// Returns the bytes of the point at `index` in `section`.
+    #[inline]
+    fn point_bytes(&self, section: Section, index: usize) -> Result<&'d [u8]> {
+        let size = point_size(section);
+        self.section(section_type(section))?
+            .get(index * size..(index + 1) * size)
+            .ok_or_else(|| anyhow!("No point {} in {:?}", index, section))
+    }
+
+    /// Reads the G1 point at `index` in `section`.
+    #[inline]
+    pub fn g1(&self, section: Section, index: usize) -> Result<G1Affine> {
+        ensure!(!section.is_g2(), "{:?} holds G2 points", section);
+        read_g1(self.point_bytes(section, index)?)
+    }
+
+    /// Reads the G2 point at `index` in `section`.
+    #[inline]
+    pub fn g2(&self, section: Section, index: usize) -> Result<G2Affine> {
+        ensure!(section.is_g2(), "{:?} holds G1 points", section);
+        read_g2(self.point_bytes(section, index)?)
+    }
+
+    /// Checks that the points of the last contribution are the points of the file.
+    #[inline]
+    pub fn check_last_contribution(&self) -> Result<bool> {
+        let last = match self.contributions.last() {
+            Some(last) => last,
+            _ => return Ok(true),
+        };
+        Ok(last.tau_g1 == self.g1(Section::TauG1, 1)?
+            && last.tau_g2 == self.g2(Section::TauG2, 1)?
+            && last.alpha_g1 == self.g1(Section::AlphaG1, 0)?
+            && last.beta_g1 == self.g1(Section::BetaG1, 0)?
+            && last.beta_g2 == self.g2(Section::BetaG2, 0)?)
+    }
+
+    /// Checks that the first `powers` powers of each section are successive powers of the same tau,
+    /// starting from the generators, with a random linear combination of the points of each
+    /// section. Returns the sections whose points are not.
+    #[inline]
+    pub fn check_powers(&self, powers: usize) -> Result<Vec<Section>> {
+        let powers = powers.min(self.header.powers());
+        let g1 = G1Affine::prime_subgroup_generator();
+        let g2 = G2Affine::prime_subgroup_generator();
+        let (tau_g1, tau_g2) = (self.g1(Section::TauG1, 1)?, self.g2(Section::TauG2, 1)?);
+        let mut invalid = Vec::new();
+        if self.g1(Section::TauG1, 0)? != g1 || self.g2(Section::TauG2, 0)? != g2 {
+            invalid.push(Section::TauG1);
+        }
+        for section in [Section::TauG1, Section::AlphaG1, Section::BetaG1] {
+            let len = section.len(powers).max(2);
+            let (a, b) = combine_powers(len, |i| self.g1(section, i))?;
+            if !invalid.contains(&section) && !same_ratio((a, b), (g2, tau_g2)) {
+                invalid.push(section);
+            }
+        }
+        let (a, b) = combine_powers(powers.max(2), |i| self.g2(Section::TauG2, i))?;
+        if !same_ratio((g1, tau_g1), (a, b)) {
+            invalid.push(Section::TauG2);
+        }
+        let beta_g1 = self.g1(Section::BetaG1, 0)?;
+        if !same_ratio((g1, beta_g1), (g2, self.g2(Section::BetaG2, 0)?)) {
+            invalid.push(Section::BetaG2);
+        }
+        Ok(invalid)
+    }
+}
+
+/// Default path of the published hashes of `.ptau` files in the state directory
+pub const PUBLISHED_HASHES_PATH: &str = "ptau_hashes.json";
+
+/// Check of the Hash of a `.ptau` File against its Published Hash
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PublishedHash {
+    /// The file has the published hash
+    Match,
+
+    /// The file was published with another hash
+    Mismatch(Hash64),
+
+    /// No hash was published for the file
+    Unknown,
+}
+
+/// Published Hashes of `.ptau` Files, by File Name
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct PublishedHashes(pub BTreeMap<String, Hash64>);
+
+impl PublishedHashes {
+    /// Loads the published hashes from the JSON file at `path`, such as
+    /// `{"powersOfTau28_hez_final_10.ptau": "..."}`, without any if the file does not exist.
+    #[inline]
+    pub fn load_or_default<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read(path) {
+            Ok(contents) => Ok(serde_json::from_slice(&contents)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns the hash published for the file named `name`, if any.
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Hash64> {
+        self.0.get(name)
+    }
+
+    /// Checks `hash`, computed from the file named `name`, against its published hash.
+    #[inline]
+    pub fn check(&self, name: &str, hash: &Hash64) -> PublishedHash {
+        match self.get(name) {
+            Some(published) if published == hash => PublishedHash::Match,
+            Some(published) => PublishedHash::Mismatch(*published),
+            _ => PublishedHash::Unknown,
+        }
+    }
+}
+
+/// Returns the size of a point of `section` in `.ptau` files.
+#[inline]
+fn point_size(section: Section) -> usize {
+    if section.is_g2() {
+        G2_SIZE
+    } else {
+        G1_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bn254::Fr;
+    use ark_ec::ProjectiveCurve;
+    use ark_ff::UniformRand;
+
+    /// Builds the `.ptau` file of an accumulator with `powers` powers of `tau`, `alpha` and `beta`,
+    /// with `contributions`.
+    fn build(powers: usize, secrets: [Fr; 3], contributions: &[Contribution]) -> Vec<u8> {
+        let [tau, alpha, beta] = secrets;
+        let mut sections = vec![(HEADER_SECTION, {
+            let mut header = (FIELD_SIZE as u32).to_le_bytes().to_vec();
+            header.extend_from_slice(&<Fq as PrimeField>::Params::MODULUS.to_bytes_le());
+            header.extend_from_slice(&powers.trailing_zeros().to_le_bytes());
+            header.extend_from_slice(&28u32.to_le_bytes());
+            header
+        })];
+        for section in Section::ALL {
+            let mut data = vec![0; section.len(powers) * point_size(section)];
+            let mut power = Fr::from(1u64);
+            for out in data.chunks_exact_mut(point_size(section)) {
+                let scalar = match section {
+                    Section::TauG1 | Section::TauG2 => power,
+                    Section::AlphaG1 => alpha * power,
+                    Section::BetaG1 | Section::BetaG2 => beta * power,
+                };
+                if section.is_g2() {
+                    write_g2(
+                        &G2Affine::prime_subgroup_generator()
+                            .mul(scalar)
+                            .into_affine(),
+                        out,
+                    );
+                } else {
+                    write_g1(
+                        &G1Affine::prime_subgroup_generator()
+                            .mul(scalar)
+                            .into_affine(),
+                        out,
+                    );
+                }
+                power *= tau;
+            }
+            sections.push((section_type(section), data));
+        }
+        let mut records = (contributions.len() as u32).to_le_bytes().to_vec();
+        for contribution in contributions {
+            contribution.write(&mut records);
+        }
+        sections.push((CONTRIBUTIONS_SECTION, records));
+        let mut file = PTAU_MAGIC.to_vec();
+        file.extend_from_slice(&1u32.to_le_bytes());
+        file.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+        for (kind, data) in sections {
+            file.extend_from_slice(&kind.to_le_bytes());
+            file.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            file.extend_from_slice(&data);
+        }
+        file
+    }
+
+    /// Returns the contribution of `secrets` to the accumulator after `previous`.
+    fn contribute(previous: &Contribution, secrets: [Fr; 3], beacon: bool) -> Contribution {
+        let mut rng = rand::thread_rng();
+        let mut key = PublicKey {
+            g1: [(G1Affine::zero(), G1Affine::zero()); 3],
+            g2: [G2Affine::zero(); 3],
+        };
+        for (secret, x) in Secret::ALL.into_iter().zip(secrets) {
+            let index = secret.personalization() as usize;
+            let s = G1Affine::prime_subgroup_generator()
+                .mul(Fr::rand(&mut rng))
+                .into_affine();
+            let s_x = s.mul(x).into_affine();
+            key.g1[index] = (s, s_x);
+            key.g2[index] = derive_g2::<Bn254>(&previous.next_challenge, &s, &s_x, secret)
+                .mul(x)
+                .into_affine();
+        }
+        let [tau, alpha, beta] = secrets;
+        Contribution {
+            tau_g1: previous.tau_g1.mul(tau).into_affine(),
+            tau_g2: previous.tau_g2.mul(tau).into_affine(),
+            alpha_g1: previous.alpha_g1.mul(alpha).into_affine(),
+            beta_g1: previous.beta_g1.mul(beta).into_affine(),
+            beta_g2: previous.beta_g2.mul(beta).into_affine(),
+            key,
+            partial_hash: vec![5; PARTIAL_HASH_SIZE],
+            next_challenge: Hash64([previous.next_challenge.0[0] + 1; 64]),
+            beacon,
+            name: (!beacon).then(|| "imported".into()),
+            beacon_hash: beacon.then(|| vec![9; 32]),
+            num_iterations_exp: beacon.then_some(10),
+        }
+    }
+
+    /// Checks that a `.ptau` file built from two contributions parses back, that its powers and
+    /// contributions are consistent, and that tampered points or keys are caught.
+    #[test]
+    fn parse_and_check_ptau() {
+        let powers = 8;
+        let mut rng = rand::thread_rng();
+        let start = Contribution {
+            tau_g1: G1Affine::prime_subgroup_generator(),
+            tau_g2: G2Affine::prime_subgroup_generator(),
+            alpha_g1: G1Affine::prime_subgroup_generator(),
+            beta_g1: G1Affine::prime_subgroup_generator(),
+            beta_g2: G2Affine::prime_subgroup_generator(),
+            key: PublicKey {
+                g1: [(G1Affine::zero(), G1Affine::zero()); 3],
+                g2: [G2Affine::zero(); 3],
+            },
+            partial_hash: vec![0; PARTIAL_HASH_SIZE],
+            next_challenge: Hash64([1; 64]),
+            beacon: false,
+            name: None,
+            beacon_hash: None,
+            num_iterations_exp: None,
+        };
+        let first = [0; 3].map(|_| Fr::rand(&mut rng));
+        let second = [0; 3].map(|_| Fr::rand(&mut rng));
+        let imported = contribute(&start, first, false);
+        let beacon = contribute(&imported, second, true);
+        let secrets = [0, 1, 2].map(|i| first[i] * second[i]);
+        let file = build(powers, secrets, &[imported.clone(), beacon.clone()]);
+        let ptau = Ptau::parse(&file).unwrap();
+        assert_eq!(ptau.header.power, 3);
+        assert_eq!(ptau.header.ceremony_power, 28);
+        assert_eq!(ptau.contributions, [imported.clone(), beacon.clone()]);
+        assert!(ptau.check_last_contribution().unwrap());
+        assert!(ptau.check_powers(powers).unwrap().is_empty());
+        assert!(imported.follows(&start).is_empty());
+        assert!(beacon.follows(&imported).is_empty());
+        assert_eq!(beacon.follows(&start), Secret::ALL);
+        let mut tampered = file.clone();
+        let offset = ptau.sections[&section_type(Section::AlphaG1)].start + 3 * G1_SIZE;
+        let point = G1Affine::prime_subgroup_generator()
+            .mul(Fr::rand(&mut rng))
+            .into_affine();
+        write_g1(&point, &mut tampered[offset..]);
+        assert_eq!(
+            Ptau::parse(&tampered)
+                .unwrap()
+                .check_powers(powers)
+                .unwrap(),
+            [Section::AlphaG1]
+        );
+        let mut forged = beacon;
+        forged.key.g2.swap(1, 2);
+        assert_eq!(forged.follows(&imported), [Secret::Alpha, Secret::Beta]);
+        assert!(Ptau::parse(&file[..file.len() - 1]).is_err());
+    }
+
+    /// Checks that the published hashes are read by file name and compared with computed hashes.
+    #[test]
+    fn published_hashes_are_checked() {
+        let name = "powersOfTau28_hez_final_10.ptau";
+        let hashes: PublishedHashes =
+            serde_json::from_str(&format!(r#"{{"{}": "{}"}}"#, name, Hash64([1; 64]))).unwrap();
+        assert_eq!(hashes.check(name, &Hash64([1; 64])), PublishedHash::Match);
+        assert_eq!(
+            hashes.check(name, &Hash64([2; 64])),
+            PublishedHash::Mismatch(Hash64([1; 64]))
+        );
+        assert_eq!(
+            hashes.check("powersOfTau28_hez_final_11.ptau", &Hash64([1; 64])),
+            PublishedHash::Unknown
+        );
+        assert_eq!(
+            PublishedHashes::load_or_default(std::env::temp_dir().join("ppot-no-ptau-hashes.json"))
+                .unwrap(),
+            PublishedHashes::default()
+        );
+    }
+}
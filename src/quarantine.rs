@@ -0,0 +1,203 @@
+//! Quarantine
+//!
+//! A ceremony file which does not match the hash chain, or which cannot be deserialized, was most
+//! likely corrupted on its way to the disk. Rather than being deleted, it is moved to the
+//! [`QUARANTINE_DIR`] of the storage directory, where it stays for forensics, its hashes are
+//! forgotten and it is downloaded again from the mirrors of the registry. This is the workflow the
+//! `hash_problem` and `rehasher` binaries went through by hand for `challenge_0002` and
+//! `challenge_0003`.
+
+use crate::{
+    config::Config,
+    db::{ChainStatus, StateDb},
+    download::{download_file, DownloadOptions},
+    hash::Hash64,
+    input::InputOptions,
+    progress::ProgressBars,
+    registry::Registry,
+    storage::StorageOptions,
+    HashAlgorithm, Result,
+};
+use anyhow::anyhow;
+use clap::ValueEnum;
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// Name of the directory holding the quarantined files in the storage directory
+pub const QUARANTINE_DIR: &str = "quarantine";
+
+/// Returns the file name of `path`.
+#[inline]
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Returns the Blake2b hash of the file at `path`, recorded in `db`, saved to the state directory
+/// of `storage` or else computed with `input`, and records it in `db`.
+#[inline]
+pub fn blake2b(
+    storage: &StorageOptions,
+    input: &InputOptions,
+    db: &StateDb,
+    path: &Path,
+) -> Result<Hash64> {
+    let name = file_name(path);
+    if let Some(hash) = db.hash(&name, HashAlgorithm::Blake2b)? {
+        return Ok(Hash64::try_from(hash.as_slice())?);
+    }
+    let hash_path = storage.hash_path(path, HashAlgorithm::Blake2b);
+    let hash = match Hash64::load(&hash_path) {
+        Ok(hash) => hash,
+        _ => {
+            let hashes = input
+                .open(path)?
+                .hashes(&[HashAlgorithm::Blake2b], None, None)?
+                .ok_or_else(|| anyhow!("Hashing of {:?} was interrupted", path))?;
+            let hash = Hash64::try_from(hashes[0].as_slice())?;
+            hash.save(&hash_path)?;
+            hash
+        }
+    };
+    db.record_hash(&name, HashAlgorithm::Blake2b, &hash.0)?;
+    Ok(hash)
+}
+
+/// Checks the links of the hash chain between the consecutive `files`, where each file asserts the
+/// hash of the one before it, recording them in `db`. Returns the links whose hash does not match,
+/// as the file whose hash was computed and the file asserting its hash. Missing files are skipped.
+#[inline]
+pub fn check_chain<'p>(
+    storage: &StorageOptions,
+    input: &InputOptions,
+    db: &StateDb,
+    files: &[&'p Path],
+) -> Result<Vec<(&'p Path, &'p Path)>> {
+    let mut mismatches = Vec::new();
+    for pair in files.windows(2) {
+        let (hashed, asserted_by) = (pair[0], pair[1]);
+        if !hashed.exists() || !asserted_by.exists() {
+            continue;
+        }
+        let status = if blake2b(storage, input, db, hashed)? == Hash64::read_header(asserted_by)? {
+            ChainStatus::Match
+        } else {
+            mismatches.push((hashed, asserted_by));
+            ChainStatus::Mismatch
+        };
+        db.record_chain(&file_name(hashed), &file_name(asserted_by), status)?;
+    }
+    Ok(mismatches)
+}
+
+/// Moves the file at `path` to the quarantine directory of `storage`, suffixed with the current
+/// time, and forgets its hashes. Returns the path of the quarantined file.
+#[inline]
+pub fn quarantine(storage: &StorageOptions, db: &StateDb, path: &Path) -> Result<PathBuf> {
+    let dir = storage.path(QUARANTINE_DIR);
+    fs::create_dir_all(&dir)?;
+    let name = file_name(path);
+    let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let target = dir.join(format!("{}.{}", name, time));
+    fs::rename(path, &target)?;
+    for algorithm in HashAlgorithm::value_variants() {
+        match fs::remove_file(storage.hash_path(path, *algorithm)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+    }
+    db.forget(&name)?;
+    warn!("Moved {:?} to {:?}", path, target);
+    Ok(target)
+}
+
+/// Quarantines the file at `path` and downloads it again from its mirrors in `registry` with
+/// `options`, from outside of an asynchronous runtime.
+#[inline]
+pub fn redownload(
+    storage: &StorageOptions,
+    config: &Config,
+    db: &StateDb,
+    registry: &Registry,
+    path: &Path,
+    options: &DownloadOptions,
+) -> Result {
+    let name = file_name(path);
+    let file = registry
+        .file(&name)
+        .ok_or_else(|| anyhow!("The file {} is not in the registry", name))?;
+    let urls = file
+        .urls()
+        .map(|url| config.resolve_url(url))
+        .collect::<Result<Vec<_>>>()?;
+    quarantine(storage, db, path)?;
+    let client = options.client()?;
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(download_file(
+            &ProgressBars::default(),
+            &client,
+            &urls,
+            path,
+            options,
+        ))?;
+    if let Ok(metadata) = fs::metadata(path) {
+        db.record_download(&name, metadata.len())?;
+    }
+    info!("Downloaded {:?} again", path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the files breaking the hash chain are found and quarantined with their hashes.
+    #[test]
+    fn quarantine_corrupt_files() {
+        let dir = std::env::temp_dir().join("ppot-verifier-quarantine-test");
+        let storage = StorageOptions {
+            dir: dir.clone(),
+            state_dir: Some(dir.join("state")),
+            cache_dir: Some(dir.join("cache")),
+        };
+        storage.create_dirs().unwrap();
+        let db = StateDb::open_in(&storage).unwrap();
+        let paths =
+            ["challenge_0001", "response_0002", "challenge_0002"].map(|name| storage.path(name));
+        // The response file asserts the hash of the challenge file, but the next challenge file
+        // does not assert the hash of the response file
+        for (path, (header, hash)) in paths.iter().zip([(0, 1), (1, 2), (3, 4)]) {
+            fs::write(path, [header; 64]).unwrap();
+            Hash64([hash; 64])
+                .save(storage.hash_path(path, HashAlgorithm::Blake2b))
+                .unwrap();
+        }
+        let files = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+        let mismatches = check_chain(&storage, &InputOptions::default(), &db, &files).unwrap();
+        assert_eq!(mismatches, [(paths[1].as_path(), paths[2].as_path())]);
+        assert_eq!(
+            db.chain("challenge_0001", "response_0002").unwrap(),
+            Some(ChainStatus::Match)
+        );
+        let target = quarantine(&storage, &db, &paths[1]).unwrap();
+        assert!(target.starts_with(storage.path(QUARANTINE_DIR)));
+        assert!(target.exists() && !paths[1].exists());
+        assert!(!storage
+            .hash_path(&paths[1], HashAlgorithm::Blake2b)
+            .exists());
+        assert_eq!(
+            db.hash("response_0002", HashAlgorithm::Blake2b).unwrap(),
+            None
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
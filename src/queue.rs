@@ -0,0 +1,500 @@
+//! Work Queue
+//!
+//! A lighter way to spread the verification across machines than the [`coordinator`]: the work
+//! items are pushed once into a shared [`WorkQueue`], and any number of independent `ppot queue
+//! pull` processes claim them one round at a time, download, hash and verify the round, and record
+//! its result back into the queue. Claims are leased, so a round whose worker dies is claimed again
+//! once its lease expires. Nothing serves the queue, so workers can come and go at will, and the
+//! hash chain is stitched from the recorded results whenever needed, see [`WorkQueue::report`].
+//!
+//! Two backends are supported:
+//!
+//! - a [`DirectoryQueue`], a directory shared by the workers, for instance over NFS, holding a file
+//!   for each item, claim and result, and guarded by an advisory lock
+//! - a [`RedisQueue`], with the `redis` feature, for workers which do not share a file system
+//!
+//! Unlike the results returned to the coordinator, the results recorded in the queue are not
+//! signed: anyone able to write to the queue is trusted.
+//!
+//! [`coordinator`]: crate::coordinator
+
+use crate::{
+    atomic,
+    coordinator::{verify_item, ChainReport, RoundResult, WorkItem},
+    download::DownloadOptions,
+    lock::FileLock,
+    progress::ProgressSink,
+    storage::StorageOptions,
+    Result,
+};
+use core::time::Duration;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{info, warn};
+
+/// Work Queue
+///
+/// Claims are leased until a time given by the workers, so the clocks of the workers sharing a
+/// queue should roughly agree.
+pub trait WorkQueue: Send + Sync {
+    /// Pushes `item` into the queue, returning `false` if its round is already queued.
+    fn push(&self, item: &WorkItem) -> Result<bool>;
+
+    /// Claims the first round without a result nor a live claim for `worker` at `now`, until
+    /// `lease` later, or returns `None` if every round is claimed or has a result.
+    fn claim(&self, worker: &str, now: SystemTime, lease: Duration) -> Result<Option<WorkItem>>;
+
+    /// Records the `result` of a claimed round and releases its claim, returning `false` if the
+    /// round already has a result, in which case the first result is kept.
+    fn complete(&self, result: &RoundResult) -> Result<bool>;
+
+    /// Returns the rounds pushed into the queue.
+    fn rounds(&self) -> Result<BTreeSet<usize>>;
+
+    /// Returns the results recorded so far.
+    fn results(&self) -> Result<BTreeMap<usize, RoundResult>>;
+
+    /// Stitches the hash chain of the rounds pushed into the queue from the results recorded so
+    /// far, any gap between the queued rounds counting as missing.
+    #[inline]
+    fn report(&self) -> Result<ChainReport> {
+        let rounds = self.rounds()?;
+        let rounds = match (rounds.first(), rounds.last()) {
+            (Some(first), Some(last)) => *first..*last + 1,
+            _ => 0..0,
+        };
+        Ok(ChainReport::stitch(rounds, &self.results()?))
+    }
+}
+
+/// Returns the number of seconds from the Unix epoch to `time`.
+#[inline]
+fn unix_time(time: SystemTime) -> Result<u64> {
+    Ok(time.duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+/// Claim of a Round in a [`DirectoryQueue`]
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+struct Claim {
+    /// Name of the worker holding the claim
+    worker: String,
+
+    /// Expiration of the claim, in seconds from the Unix epoch
+    expires: u64,
+}
+
+/// Directory Queue
+///
+/// Keeps each item, claim and result in a JSON file named after its round, in the `items`,
+/// `claims` and `results` directories of the queue. Every change to the queue is made while holding
+/// an exclusive lock on the `queue.lock` file, and written atomically.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DirectoryQueue {
+    /// Directory of the queue
+    path: PathBuf,
+}
+
+impl DirectoryQueue {
+    /// Directory of the items
+    const ITEMS: &'static str = "items";
+
+    /// Directory of the claims
+    const CLAIMS: &'static str = "claims";
+
+    /// Directory of the results
+    const RESULTS: &'static str = "results";
+
+    /// Opens the queue in the directory at `path`, creating it if needed.
+    #[inline]
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        for directory in [Self::ITEMS, Self::CLAIMS, Self::RESULTS] {
+            fs::create_dir_all(path.join(directory))?;
+        }
+        Ok(Self { path: path.into() })
+    }
+
+    /// Returns the path of the file of `round` in `directory`.
+    #[inline]
+    fn file(&self, directory: &str, round: usize) -> PathBuf {
+        self.path.join(directory).join(format!("{}.json", round))
+    }
+
+    /// Locks the queue against the other workers.
+    #[inline]
+    fn lock(&self) -> Result<FileLock> {
+        FileLock::exclusive(self.path.join("queue"))
+    }
+
+    /// Reads the file of `round` in `directory`, returning `None` if there is none.
+    #[inline]
+    fn read<T>(&self, directory: &str, round: usize) -> Result<Option<T>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        match fs::read(self.file(directory, round)) {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Writes `value` to the file of `round` in `directory`.
+    #[inline]
+    fn write<T>(&self, directory: &str, round: usize, value: &T) -> Result
+    where
+        T: Serialize,
+    {
+        atomic::write(
+            self.file(directory, round),
+            serde_json::to_vec_pretty(value)?,
+        )
+    }
+
+    /// Returns the rounds with a file in `directory`, skipping the temporary files of atomic
+    /// writes.
+    #[inline]
+    fn list(&self, directory: &str) -> Result<BTreeSet<usize>> {
+        let mut rounds = BTreeSet::new();
+        for entry in fs::read_dir(self.path.join(directory))? {
+            let path = entry?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json")
+            {
+                if let Some(round) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())
+                {
+                    rounds.insert(round);
+                }
+            }
+        }
+        Ok(rounds)
+    }
+}
+
+impl WorkQueue for DirectoryQueue {
+    #[inline]
+    fn push(&self, item: &WorkItem) -> Result<bool> {
+        let _lock = self.lock()?;
+        if self.file(Self::ITEMS, item.round).exists() {
+            return Ok(false);
+        }
+        self.write(Self::ITEMS, item.round, item)?;
+        Ok(true)
+    }
+
+    #[inline]
+    fn claim(&self, worker: &str, now: SystemTime, lease: Duration) -> Result<Option<WorkItem>> {
+        let _lock = self.lock()?;
+        let now = unix_time(now)?;
+        let results = self.list(Self::RESULTS)?;
+        for round in self.list(Self::ITEMS)? {
+            if results.contains(&round) {
+                continue;
+            }
+            if let Some(claim) = self.read::<Claim>(Self::CLAIMS, round)? {
+                if claim.expires > now {
+                    continue;
+                }
+                warn!("The claim of round {} by {} expired", round, claim.worker);
+            }
+            let item = match self.read::<WorkItem>(Self::ITEMS, round)? {
+                Some(item) => item,
+                _ => continue,
+            };
+            self.write(
+                Self::CLAIMS,
+                round,
+                &Claim {
+                    worker: worker.into(),
+                    expires: now + lease.as_secs(),
+                },
+            )?;
+            info!("{} claimed round {}", worker, round);
+            return Ok(Some(item));
+        }
+        Ok(None)
+    }
+
+    #[inline]
+    fn complete(&self, result: &RoundResult) -> Result<bool> {
+        let _lock = self.lock()?;
+        if self.file(Self::RESULTS, result.round).exists() {
+            return Ok(false);
+        }
+        self.write(Self::RESULTS, result.round, result)?;
+        match fs::remove_file(self.file(Self::CLAIMS, result.round)) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    #[inline]
+    fn rounds(&self) -> Result<BTreeSet<usize>> {
+        self.list(Self::ITEMS)
+    }
+
+    #[inline]
+    fn results(&self) -> Result<BTreeMap<usize, RoundResult>> {
+        let mut results = BTreeMap::new();
+        for round in self.list(Self::RESULTS)? {
+            if let Some(result) = self.read(Self::RESULTS, round)? {
+                results.insert(round, result);
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// Redis Queue
+///
+/// Keeps the items and results in the `items` and `results` hashes of the queue, keyed by round,
+/// the unclaimed rounds in the `pending` sorted set and the claimed ones in the `claims` sorted
+/// set, scored by the expiration of their claim. Every key is prefixed with the name of the queue,
+/// and every change is made by a Lua script, atomically.
+#[cfg(feature = "redis")]
+pub struct RedisQueue {
+    /// Connection to the server
+    connection: std::sync::Mutex<redis::Connection>,
+
+    /// Prefix of the keys of the queue
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl RedisQueue {
+    /// Default name of the queue
+    pub const DEFAULT_NAME: &'static str = "ppot";
+
+    /// Pushes an item unless its round is already queued.
+    const PUSH: &'static str = r"
+        if redis.call('HSETNX', KEYS[1], ARGV[1], ARGV[2]) == 0 then return 0 end
+        redis.call('ZADD', KEYS[2], ARGV[1], ARGV[1])
+        return 1
+    ";
+
+    /// Requeues the expired claims, then claims the first pending round.
+    const CLAIM: &'static str = r"
+        for _, round in ipairs(redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', ARGV[1])) do
+            redis.call('ZREM', KEYS[2], round)
+            if redis.call('HEXISTS', KEYS[3], round) == 0 then
+                redis.call('ZADD', KEYS[1], round, round)
+            end
+        end
+        local next = redis.call('ZRANGE', KEYS[1], 0, 0)
+        if #next == 0 then return false end
+        redis.call('ZREM', KEYS[1], next[1])
+        redis.call('ZADD', KEYS[2], ARGV[2], next[1])
+        return next[1]
+    ";
+
+    /// Records a result unless the round already has one, and releases its claim.
+    const COMPLETE: &'static str = r"
+        if redis.call('HSETNX', KEYS[1], ARGV[1], ARGV[2]) == 0 then return 0 end
+        redis.call('ZREM', KEYS[2], ARGV[1])
+        redis.call('ZREM', KEYS[3], ARGV[1])
+        return 1
+    ";
+
+    /// Connects to the queue `name` on the server at `url`, such as `redis://10.0.0.1:6379/0`.
+    #[inline]
+    pub fn open(url: &str, name: &str) -> Result<Self> {
+        Ok(Self {
+            connection: std::sync::Mutex::new(redis::Client::open(url)?.get_connection()?),
+            prefix: name.into(),
+        })
+    }
+
+    /// Returns the key `name` of the queue.
+    #[inline]
+    fn key(&self, name: &str) -> String {
+        format!("{}:{}", self.prefix, name)
+    }
+
+    /// Runs `f` on the connection to the server.
+    #[inline]
+    fn with<T, F>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut redis::Connection) -> redis::RedisResult<T>,
+    {
+        Ok(f(&mut self
+            .connection
+            .lock()
+            .expect("The connection is never poisoned."))?)
+    }
+}
+
+#[cfg(feature = "redis")]
+impl WorkQueue for RedisQueue {
+    #[inline]
+    fn push(&self, item: &WorkItem) -> Result<bool> {
+        let item_json = serde_json::to_string(item)?;
+        self.with(|connection| {
+            redis::Script::new(Self::PUSH)
+                .key(self.key("items"))
+                .key(self.key("pending"))
+                .arg(item.round)
+                .arg(item_json)
+                .invoke(connection)
+        })
+    }
+
+    #[inline]
+    fn claim(&self, worker: &str, now: SystemTime, lease: Duration) -> Result<Option<WorkItem>> {
+        let now = unix_time(now)?;
+        let round = self.with(|connection| {
+            redis::Script::new(Self::CLAIM)
+                .key(self.key("pending"))
+                .key(self.key("claims"))
+                .key(self.key("results"))
+                .arg(now)
+                .arg(now + lease.as_secs())
+                .invoke::<Option<usize>>(connection)
+        })?;
+        let round = match round {
+            Some(round) => round,
+            _ => return Ok(None),
+        };
+        let item = self.with(|connection| {
+            redis::cmd("HGET")
+                .arg(self.key("items"))
+                .arg(round)
+                .query::<String>(connection)
+        })?;
+        info!("{} claimed round {}", worker, round);
+        Ok(Some(serde_json::from_str(&item)?))
+    }
+
+    #[inline]
+    fn complete(&self, result: &RoundResult) -> Result<bool> {
+        let result_json = serde_json::to_string(result)?;
+        self.with(|connection| {
+            redis::Script::new(Self::COMPLETE)
+                .key(self.key("results"))
+                .key(self.key("claims"))
+                .key(self.key("pending"))
+                .arg(result.round)
+                .arg(result_json)
+                .invoke(connection)
+        })
+    }
+
+    #[inline]
+    fn rounds(&self) -> Result<BTreeSet<usize>> {
+        self.with(|connection| redis::cmd("HKEYS").arg(self.key("items")).query(connection))
+    }
+
+    #[inline]
+    fn results(&self) -> Result<BTreeMap<usize, RoundResult>> {
+        let results = self.with(|connection| {
+            redis::cmd("HGETALL")
+                .arg(self.key("results"))
+                .query::<BTreeMap<usize, String>>(connection)
+        })?;
+        results
+            .into_iter()
+            .map(|(round, result)| Ok((round, serde_json::from_str(&result)?)))
+            .collect()
+    }
+}
+
+/// Opens the queue at `location`: a Redis server if it is a `redis://` or `rediss://` URL, and
+/// a directory otherwise.
+#[inline]
+pub fn open_queue(location: &str) -> Result<Box<dyn WorkQueue>> {
+    if location.starts_with("redis://") || location.starts_with("rediss://") {
+        #[cfg(feature = "redis")]
+        return Ok(Box::new(RedisQueue::open(
+            location,
+            RedisQueue::DEFAULT_NAME,
+        )?));
+        #[cfg(not(feature = "redis"))]
+        anyhow::bail!("Redis queues require the `redis` feature");
+    }
+    Ok(Box::new(DirectoryQueue::open(location)?))
+}
+
+/// Claims and verifies the rounds of `queue` as `worker`, each claim lasting `lease`, until every
+/// round is claimed or has a result. Returns the number of rounds verified.
+#[inline]
+pub async fn work_from(
+    queue: &dyn WorkQueue,
+    worker: &str,
+    lease: Duration,
+    client: &Client,
+    storage: &StorageOptions,
+    options: &DownloadOptions,
+    progress: &dyn ProgressSink,
+) -> Result<usize> {
+    let mut verified = 0;
+    while let Some(item) = queue.claim(worker, SystemTime::now(), lease)? {
+        let result = verify_item(client, &item, worker, storage, options, progress).await?;
+        if !queue.complete(&result)? {
+            warn!(
+                "Round {} already had a result when {} verified it",
+                item.round, worker
+            );
+        }
+        verified += 1;
+    }
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{coordinator::ChainIssue, hash::Hash64, registry::Registry};
+
+    /// Checks that a directory queue hands each round out once until its claim expires, and
+    /// that it stitches the chain from the first result of each round.
+    #[test]
+    fn directory_queue_claims() {
+        let path = std::env::temp_dir().join("ppot-verifier-queue-test");
+        let _ = fs::remove_dir_all(&path);
+        let queue = DirectoryQueue::open(&path).unwrap();
+        let registry = Registry::builtin();
+        for round in 1..3 {
+            assert!(queue.push(&WorkItem::new(&registry, round, 10)).unwrap());
+        }
+        assert!(!queue.push(&WorkItem::new(&registry, 1, 10)).unwrap());
+        let (now, lease) = (SystemTime::now(), Duration::from_secs(60));
+        assert_eq!(queue.claim("a", now, lease).unwrap().unwrap().round, 1);
+        assert_eq!(queue.claim("b", now, lease).unwrap().unwrap().round, 2);
+        assert!(queue.claim("c", now, lease).unwrap().is_none());
+        let later = now + Duration::from_secs(61);
+        assert_eq!(queue.claim("c", later, lease).unwrap().unwrap().round, 1);
+        let result = |round, byte| RoundResult {
+            round,
+            worker: "a".into(),
+            challenge_header: Hash64([round as u8; 64]),
+            challenge_hash: Hash64([byte; 64]),
+            response_header: Hash64([byte; 64]),
+            response_hash: Hash64([round as u8 + 1; 64]),
+            error: None,
+        };
+        assert!(queue.complete(&result(1, 7)).unwrap());
+        assert!(!queue.complete(&result(1, 8)).unwrap());
+        assert_eq!(
+            queue.report().unwrap().issues,
+            [ChainIssue::Missing { round: 2 }]
+        );
+        assert!(queue.complete(&result(2, 9)).unwrap());
+        assert!(queue.claim("d", later, lease).unwrap().is_none());
+        assert!(queue.report().unwrap().is_valid());
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+//! circom R1CS Files
+//!
+//! circom compiles a circuit into a `.r1cs` file: after the `r1cs` magic, a version and the number
+//! of sections, every section is a little-endian type and size followed by its data, as in `.ptau`
+//! files. The header section gives the size and modulus of the scalar field, the number of wires,
+//! of public outputs, public inputs and private inputs, of labels and of constraints. The
+//! constraints section holds, for each constraint, the linear combinations `A`, `B` and `C` as a
+//! number of terms followed by the wire and coefficient of each term, with coefficients written
+//! little-endian out of Montgomery form.
+//!
+//! Wire 0 is the constant one, followed by the public outputs and inputs, which become the inputs
+//! of the Groth16 circuit, and by the private wires.
+
+use crate::Result;
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, BigInteger256, FpParameters, PrimeField};
+
+/// Magic bytes starting every `.r1cs` file
+pub const R1CS_MAGIC: &[u8; 4] = b"r1cs";
+
+/// Type of the header section
+const HEADER_SECTION: u32 = 1;
+
+/// Type of the constraints section
+const CONSTRAINTS_SECTION: u32 = 2;
+
+/// Size of a scalar field element
+const SCALAR_SIZE: usize = 32;
+
+/// Linear Combination of Wires
+pub type LinearCombination = Vec<(usize, Fr)>;
+
+/// Rank-1 Constraint `A * B = C`
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Constraint {
+    /// Left factor
+    pub a: LinearCombination,
+
+    /// Right factor
+    pub b: LinearCombination,
+
+    /// Product
+    pub c: LinearCombination,
+}
+
+/// Rank-1 Constraint System
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct R1cs {
+    /// Number of wires, including the constant one
+    pub wires: usize,
+
+    /// Number of public outputs and inputs
+    pub public: usize,
+
+    /// Constraints of the circuit
+    pub constraints: Vec<Constraint>,
+}
+
+/// Reads the little-endian `u32` at `offset` of `bytes`.
+#[inline]
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(
+        bytes
+            .get(offset..offset + 4)
+            .ok_or_else(|| anyhow!("Truncated R1CS file"))?
+            .try_into()?,
+    ))
+}
+
+/// Reads the little-endian `u64` at `offset` of `bytes`.
+#[inline]
+fn read_u64(bytes: &[u8], offset: usize) -> Result<u64> {
+    Ok(u64::from_le_bytes(
+        bytes
+            .get(offset..offset + 8)
+            .ok_or_else(|| anyhow!("Truncated R1CS file"))?
+            .try_into()?,
+    ))
+}
+
+impl R1cs {
+    /// Returns the number of inputs of the Groth16 circuit: the constant one and the public
+    /// outputs and inputs.
+    #[inline]
+    pub fn inputs(&self) -> usize {
+        1 + self.public
+    }
+
+    /// Returns the base-two logarithm of the size of the smallest radix-2 domain holding the
+    /// constraints of the circuit and the constraints bellman adds for each input.
+    #[inline]
+    pub fn domain_log_size(&self) -> u32 {
+        (self.constraints.len() + self.inputs())
+            .next_power_of_two()
+            .trailing_zeros()
+    }
+
+    /// Parses the `.r1cs` file in `bytes`.
+    #[inline]
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        ensure!(bytes.starts_with(R1CS_MAGIC), "Not an R1CS file");
+        let version = read_u32(bytes, 4)?;
+        ensure!(version == 1, "Unsupported R1CS version {}", version);
+        let mut sections = Vec::new();
+        let mut offset = 12;
+        for _ in 0..read_u32(bytes, 8)? {
+            let kind = read_u32(bytes, offset)?;
+            let size = read_u64(bytes, offset + 4)? as usize;
+            let data = bytes
+                .get(offset + 12..offset + 12 + size)
+                .ok_or_else(|| anyhow!("Truncated R1CS section {}", kind))?;
+            sections.push((kind, data));
+            offset += 12 + size;
+        }
+        let section = |kind: u32| {
+            sections
+                .iter()
+                .find(|(section, _)| *section == kind)
+                .map(|(_, data)| *data)
+                .ok_or_else(|| anyhow!("Missing R1CS section {}", kind))
+        };
+        let header = section(HEADER_SECTION)?;
+        ensure!(
+            read_u32(header, 0)? as usize == SCALAR_SIZE
+                && header.get(4..4 + SCALAR_SIZE)
+                    == Some(&<Fr as PrimeField>::Params::MODULUS.to_bytes_le()[..]),
+            "The R1CS file is not over the BN254 scalar field"
+        );
+        let header = &header[4 + SCALAR_SIZE..];
+        let wires = read_u32(header, 0)? as usize;
+        let public = read_u32(header, 4)? as usize + read_u32(header, 8)? as usize;
+        let count = read_u32(header, 24)? as usize;
+        ensure!(
+            public < wires,
+            "The R1CS file has more public wires than wires"
+        );
+        let data = section(CONSTRAINTS_SECTION)?;
+        let mut offset = 0;
+        let mut linear_combination = || -> Result<LinearCombination> {
+            let terms = read_u32(data, offset)? as usize;
+            offset += 4;
+            let mut combination = Vec::with_capacity(terms);
+            for _ in 0..terms {
+                let wire = read_u32(data, offset)? as usize;
+                let coefficient = data
+                    .get(offset + 4..offset + 4 + SCALAR_SIZE)
+                    .ok_or_else(|| anyhow!("Truncated R1CS constraint"))?;
+                if wire >= wires {
+                    bail!("Constraint on wire {} out of {}", wire, wires);
+                }
+                let mut limbs = [0; 4];
+                for (limb, chunk) in limbs.iter_mut().zip(coefficient.chunks_exact(8)) {
+                    *limb = u64::from_le_bytes(chunk.try_into()?);
+                }
+                combination.push((
+                    wire,
+                    Fr::from_repr(BigInteger256::new(limbs))
+                        .ok_or_else(|| anyhow!("Coefficient out of the scalar field"))?,
+                ));
+                offset += 4 + SCALAR_SIZE;
+            }
+            Ok(combination)
+        };
+        let mut constraints = Vec::with_capacity(count);
+        for _ in 0..count {
+            constraints.push(Constraint {
+                a: linear_combination()?,
+                b: linear_combination()?,
+                c: linear_combination()?,
+            });
+        }
+        Ok(Self {
+            wires,
+            public,
+            constraints,
+        })
+    }
+
+    /// Writes the `.r1cs` file of the constraint system, with no private inputs and no labels.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let mut header = Vec::new();
+        header.extend_from_slice(&(SCALAR_SIZE as u32).to_le_bytes());
+        header.extend_from_slice(&<Fr as PrimeField>::Params::MODULUS.to_bytes_le());
+        for value in [self.wires, 0, self.public, 0] {
+            header.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&(self.constraints.len() as u32).to_le_bytes());
+        let mut data = Vec::new();
+        for constraint in &self.constraints {
+            for combination in [&constraint.a, &constraint.b, &constraint.c] {
+                data.extend_from_slice(&(combination.len() as u32).to_le_bytes());
+                for (wire, coefficient) in combination {
+                    data.extend_from_slice(&(*wire as u32).to_le_bytes());
+                    data.extend_from_slice(&coefficient.into_repr().to_bytes_le());
+                }
+            }
+        }
+        out.extend_from_slice(R1CS_MAGIC);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&2u32.to_le_bytes());
+        for (kind, section) in [(HEADER_SECTION, header), (CONSTRAINTS_SECTION, data)] {
+            out.extend_from_slice(&kind.to_le_bytes());
+            out.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            out.extend_from_slice(&section);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_ff::UniformRand;
+
+    /// Checks that a constraint system is read back from its `.r1cs` file and that files over
+    /// another field are rejected.
+    #[test]
+    fn read_write() {
+        let mut rng = rand::thread_rng();
+        let r1cs = R1cs {
+            wires: 4,
+            public: 1,
+            constraints: vec![
+                Constraint {
+                    a: vec![(1, Fr::rand(&mut rng))],
+                    b: vec![(2, Fr::rand(&mut rng)), (0, Fr::from(3u64))],
+                    c: vec![(3, -Fr::from(1u64))],
+                },
+                Constraint::default(),
+            ],
+        };
+        assert_eq!(r1cs.domain_log_size(), 2);
+        let mut bytes = Vec::new();
+        r1cs.write(&mut bytes);
+        assert_eq!(R1cs::read(&bytes).unwrap(), r1cs);
+        bytes[24] ^= 1;
+        assert!(R1cs::read(&bytes).is_err());
+        assert!(R1cs::read(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,291 @@
+//! Contribution Registry
+
+use crate::{
+    atomic,
+    azure::{self, Blob},
+    challenge_paths, challenge_urls,
+    curve::CurveKind,
+    format::CEREMONY_LOG_POWERS,
+    github, response_paths, response_urls, Result,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{fs, io::ErrorKind, path::Path};
+
+/// Default location of the registry file
+pub const REGISTRY_PATH: &str = "registry.json";
+
+/// Remote File
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RemoteFile {
+    /// Local file name, following [`challenge_paths`] and [`response_paths`]
+    pub path: String,
+
+    /// Download URL
+    pub url: String,
+
+    /// Alternative download URLs serving the same file, tried in order when `url` fails
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// Magnet link or `.torrent` location used when none of the URLs can be downloaded from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub torrent: Option<String>,
+
+    /// Size of the file in bytes, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    /// Base64-encoded MD5 digest reported by the blob store, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_md5: Option<String>,
+}
+
+impl RemoteFile {
+    /// Builds a [`RemoteFile`] stored at `path` and downloaded from `url` with no metadata.
+    #[inline]
+    pub fn new(path: String, url: String) -> Self {
+        Self {
+            path,
+            url,
+            mirrors: Vec::new(),
+            torrent: None,
+            size: None,
+            content_md5: None,
+        }
+    }
+
+    /// Returns an iterator over the primary URL followed by all the mirrors of `self`.
+    #[inline]
+    pub fn urls(&self) -> impl Iterator<Item = &str> {
+        core::iter::once(self.url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+    }
+
+    /// Builds a [`RemoteFile`] from a `blob` in the container at `container_url`, returning `None`
+    /// if the blob is not a `challenge` or `response` file.
+    #[inline]
+    pub fn from_blob(blob: &Blob, container_url: &str) -> Option<Self> {
+        Some(Self {
+            path: blob.local_path()?,
+            url: blob.url(container_url),
+            mirrors: Vec::new(),
+            torrent: None,
+            size: Some(blob.size),
+            content_md5: blob.content_md5.clone(),
+        })
+    }
+}
+
+/// Contribution Registry
+///
+/// The registry is the local record of every file of the ceremony. Round `i` of the ceremony turns
+/// `challenges[i - 1]` into `challenges[i]` through `responses[i - 1]`, so there is always one more
+/// challenge than there are responses.
+///
+/// The registry of another ceremony with the same file format, such as the Zcash Sapling powers of
+/// tau over BLS12-381, sets its curve and its number of powers.
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Registry {
+    /// Challenge files, starting with `challenge_0000`
+    pub challenges: Vec<RemoteFile>,
+
+    /// Response files, starting with `response_0001`
+    pub responses: Vec<RemoteFile>,
+
+    /// Curve of the ceremony
+    #[serde(default, skip_serializing_if = "CurveKind::is_bn254")]
+    pub curve: CurveKind,
+
+    /// Base-two logarithm of the number of powers of tau in the files of the ceremony, defaults to
+    /// [`CEREMONY_LOG_POWERS`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_powers: Option<u32>,
+}
+
+impl Registry {
+    /// Builds the registry from the embedded contribution list, see [`challenge_urls`] and
+    /// [`response_urls`].
+    #[inline]
+    pub fn builtin() -> Self {
+        let challenges = challenge_urls();
+        let responses = response_urls();
+        Self {
+            challenges: challenge_paths(challenges.len() - 1)
+                .into_iter()
+                .zip(challenges)
+                .map(|(path, url)| RemoteFile::new(path, url))
+                .collect(),
+            responses: response_paths(responses.len())
+                .into_iter()
+                .zip(responses)
+                .map(|(path, url)| RemoteFile::new(path, url))
+                .collect(),
+            curve: CurveKind::Bn254,
+            log_powers: None,
+        }
+    }
+
+    /// Returns the number of rounds covered by the registry.
+    #[inline]
+    pub fn rounds(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Returns the number of powers of tau in the files of the ceremony.
+    #[inline]
+    pub fn powers(&self) -> usize {
+        1 << self.log_powers.unwrap_or(CEREMONY_LOG_POWERS)
+    }
+
+    /// Returns the file stored at `path`, a file name like `challenge_0001`, if it is registered.
+    #[inline]
+    pub fn file(&self, path: &str) -> Option<&RemoteFile> {
+        self.challenges
+            .iter()
+            .chain(&self.responses)
+            .find(|file| file.path == path)
+    }
+
+    /// Loads the registry from the JSON file at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Loads the registry from the JSON file at `path`, falling back to [`Registry::builtin`] if
+    /// the file does not exist.
+    #[inline]
+    pub fn load_or_builtin<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        match fs::read(path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::builtin()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the registry as pretty-printed JSON to `path`.
+    #[inline]
+    pub fn save<P>(&self, path: P) -> Result
+    where
+        P: AsRef<Path>,
+    {
+        atomic::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Builds the registry from the listing of the blob container at `container_url`.
+    #[inline]
+    pub fn from_blobs(blobs: Vec<Blob>, container_url: &str) -> Self {
+        let (challenges, responses) = azure::sort_blobs(blobs);
+        Self {
+            challenges: challenges
+                .iter()
+                .filter_map(|b| RemoteFile::from_blob(b, container_url))
+                .collect(),
+            responses: responses
+                .iter()
+                .filter_map(|b| RemoteFile::from_blob(b, container_url))
+                .collect(),
+            curve: CurveKind::Bn254,
+            log_powers: None,
+        }
+    }
+
+    /// Discovers the current state of the ceremony by listing the blob container at
+    /// [`CONTAINER_URL`](azure::CONTAINER_URL). If `github` is set, the set of rounds is taken from
+    /// the contributions recorded in the GitHub repository instead, using `token` to authenticate.
+    #[inline]
+    pub async fn discover(client: &Client, github: bool, token: Option<&str>) -> Result<Self> {
+        let blobs = azure::list_blobs(client, azure::CONTAINER_URL).await?;
+        if !github {
+            return Ok(Self::from_blobs(blobs, azure::CONTAINER_URL));
+        }
+        let contributions = github::list_contributions(client, token).await?;
+        let listed = Self::from_blobs(blobs.clone(), azure::CONTAINER_URL);
+        let (challenges, responses) =
+            github::contribution_urls(&contributions, blobs, azure::CONTAINER_URL);
+        let lookup = |files: &[RemoteFile], path: String, url: String| {
+            files
+                .iter()
+                .find(|file| file.url == url)
+                .cloned()
+                .unwrap_or_else(|| RemoteFile::new(path, url))
+        };
+        Ok(Self {
+            challenges: challenges
+                .into_iter()
+                .zip(challenge_paths(contributions.len()))
+                .map(|(url, path)| lookup(&listed.challenges, path, url))
+                .collect(),
+            responses: responses
+                .into_iter()
+                .zip(response_paths(contributions.len()))
+                .map(|(url, path)| lookup(&listed.responses, path, url))
+                .collect(),
+            curve: CurveKind::Bn254,
+            log_powers: None,
+        })
+    }
+
+    /// Merges the `latest` state of the ceremony into `self`, returning the number of new rounds.
+    /// Files already present in `self` keep their URL but pick up any missing metadata, and any
+    /// other URL found for them is recorded as a mirror. The last challenge is only appended
+    /// together with the response that precedes it, so the registry always describes complete
+    /// rounds.
+    #[inline]
+    pub fn update(&mut self, latest: Self) -> usize {
+        let rounds = self.rounds();
+        let merge = |known: &mut Vec<RemoteFile>, latest: Vec<RemoteFile>, len: usize| {
+            for (i, file) in latest.into_iter().enumerate().take(len) {
+                match known.get_mut(i) {
+                    Some(known) => {
+                        for mirror in file.urls() {
+                            if !known.urls().any(|url| url == mirror) {
+                                known.mirrors.push(mirror.into());
+                            }
+                        }
+                        known.torrent = known.torrent.take().or(file.torrent);
+                        known.size = known.size.or(file.size);
+                        known.content_md5 = known.content_md5.take().or(file.content_md5);
+                    }
+                    _ => known.push(file),
+                }
+            }
+        };
+        let new_rounds = latest
+            .rounds()
+            .min(latest.challenges.len().saturating_sub(1));
+        merge(&mut self.responses, latest.responses, new_rounds);
+        merge(&mut self.challenges, latest.challenges, new_rounds + 1);
+        self.rounds().saturating_sub(rounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that updating the registry only appends complete rounds.
+    #[test]
+    fn update_appends_complete_rounds() {
+        let mut registry = Registry::builtin();
+        let rounds = registry.rounds();
+        let mut latest = registry.clone();
+        latest.responses.push(RemoteFile::new(
+            format!("response_{:04}", rounds + 1),
+            "response".into(),
+        ));
+        assert_eq!(registry.clone().update(latest.clone()), 0);
+        latest.challenges.push(RemoteFile::new(
+            format!("challenge_{:04}", rounds + 1),
+            "challenge".into(),
+        ));
+        assert_eq!(registry.update(latest), 1);
+        assert_eq!(registry.challenges.len(), registry.rounds() + 1);
+    }
+}
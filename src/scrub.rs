@@ -0,0 +1,185 @@
+//! Bit-Rot Scrubbing
+//!
+//! Archived ceremony files are kept for years, long enough for disks to silently flip bits. `ppot
+//! scrub` re-hashes the files whose Blake2b hash is recorded in the [`StateDb`] once every
+//! [`SCRUB_INTERVAL`], reading them at a limited rate so that it can run next to other work, and
+//! alerts as soon as a hash changes or a file disappears. Every scrubbed file is recorded in the
+//! database, so an interrupted scrub resumes with the files it did not reach, the ones left
+//! unscrubbed the longest first. The recorded hashes are never replaced by the scrub: a corrupted
+//! file keeps failing its scrubs until it is restored, or repaired with its
+//! [`parity`](crate::parity) data.
+
+use crate::{
+    compress::is_stored,
+    db::StateDb,
+    hash::Hash64,
+    input::{Advice, Input, ReadMode},
+    throttle::ByteRate,
+    HashAlgorithm, Result,
+};
+use core::{cmp::Reverse, time::Duration};
+use std::{path::Path, thread, time::Instant};
+use tokio_util::sync::CancellationToken;
+
+/// Default interval between two scrubs of the same file
+pub const SCRUB_INTERVAL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Default rate at which the files are read
+pub const SCRUB_RATE: &str = "100MB/s";
+
+/// Size of the chunks the files are hashed by, small enough to pace the reads smoothly
+pub const SCRUB_CHUNK_SIZE: usize = 16 << 20;
+
+/// Read Pacer
+///
+/// Sleeps after each chunk read in excess of the allowed rate, averaged since the pacer was built.
+#[derive(Clone, Copy, Debug)]
+pub struct Pacer {
+    /// Allowed rate
+    rate: ByteRate,
+
+    /// Instant the pacer was built at
+    start: Instant,
+
+    /// Number of bytes read since the pacer was built
+    bytes: u64,
+}
+
+impl Pacer {
+    /// Builds a new [`Pacer`] allowing `rate` bytes per second.
+    #[inline]
+    pub fn new(rate: ByteRate) -> Self {
+        Self {
+            rate,
+            start: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// Records that `bytes` more bytes were read, sleeping until they fit in the allowed rate.
+    #[inline]
+    pub fn consume(&mut self, bytes: u64) {
+        self.bytes += bytes;
+        let due = Duration::from_secs_f64(self.bytes as f64 / self.rate.0 as f64);
+        if let Some(ahead) = due.checked_sub(self.start.elapsed()) {
+            thread::sleep(ahead);
+        }
+    }
+}
+
+/// Outcome of the Scrub of a File
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ScrubOutcome {
+    /// The hash of the file is the recorded one
+    Intact,
+
+    /// The hash of the file changed to the given one
+    Corrupted(Hash64),
+
+    /// The file is not in the storage directory anymore
+    Missing,
+}
+
+impl ScrubOutcome {
+    /// Returns `true` if the file is intact.
+    #[inline]
+    pub fn is_intact(&self) -> bool {
+        matches!(self, Self::Intact)
+    }
+}
+
+/// Returns the names of the files whose Blake2b hash is recorded in `db` and which were not
+/// scrubbed in the last `interval`, with their recorded hash, the ones never scrubbed first and
+/// then the ones scrubbed the longest ago.
+#[inline]
+pub fn due_files(db: &StateDb, interval: Duration) -> Result<Vec<(String, Hash64)>> {
+    let scrubs = db.scrubs()?;
+    let mut due = db
+        .hashes(HashAlgorithm::Blake2b)?
+        .into_iter()
+        .filter_map(|(name, hash)| {
+            let age = scrubs.get(&name).map(|scrub| scrub.age);
+            match age {
+                Some(age) if age < interval.as_secs() => None,
+                _ => Some((age, name, Hash64::try_from(hash.as_slice()).ok()?)),
+            }
+        })
+        .collect::<Vec<_>>();
+    due.sort_by_key(|(age, ..)| Reverse(age.unwrap_or(u64::MAX)));
+    Ok(due
+        .into_iter()
+        .map(|(_, name, hash)| (name, hash))
+        .collect())
+}
+
+/// Re-hashes the file at `path` at the rate of `pacer` and compares its hash to `expected`.
+/// Returns `None` if `cancel` is cancelled before the end of the file.
+#[inline]
+pub fn scrub_file(
+    path: &Path,
+    expected: &Hash64,
+    pacer: &mut Pacer,
+    cancel: Option<&CancellationToken>,
+) -> Result<Option<ScrubOutcome>> {
+    if !is_stored(path) {
+        return Ok(Some(ScrubOutcome::Missing));
+    }
+    let input = Input::open(path, ReadMode::Auto)?
+        .with_advice(Advice::DropBehind)
+        .with_chunk_size(SCRUB_CHUNK_SIZE);
+    let hashes = match input.hashes_with(&[HashAlgorithm::Blake2b], None, cancel, |_, chunk| {
+        pacer.consume(chunk.len() as u64)
+    })? {
+        Some(hashes) => hashes,
+        _ => return Ok(None),
+    };
+    let hash = Hash64::try_from(hashes[0].as_slice())?;
+    Ok(Some(if hash == *expected {
+        ScrubOutcome::Intact
+    } else {
+        ScrubOutcome::Corrupted(hash)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hash_file;
+    use std::fs;
+
+    /// Checks that the files due for a scrub are picked by age and that flipped bits and missing
+    /// files are reported.
+    #[test]
+    fn scrubs_detect_changes() {
+        let dir = std::env::temp_dir().join("ppot-verifier-scrub-test");
+        fs::create_dir_all(&dir).unwrap();
+        let db = StateDb::open(dir.join("state.db")).unwrap();
+        let path = dir.join("challenge_0001");
+        let mut bytes = vec![0x5a; 1 << 20];
+        fs::write(&path, &bytes).unwrap();
+        let hash = hash_file(&path).unwrap();
+        for name in ["challenge_0001", "response_0001"] {
+            db.record_hash(name, HashAlgorithm::Blake2b, &hash.0)
+                .unwrap();
+        }
+        db.record_scrub("response_0001", true).unwrap();
+        let due = due_files(&db, SCRUB_INTERVAL).unwrap();
+        assert_eq!(due, [("challenge_0001".into(), hash)]);
+        assert_eq!(due_files(&db, Duration::ZERO).unwrap().len(), 2);
+        let mut pacer = Pacer::new(ByteRate(1 << 30));
+        let scrub = |pacer: &mut Pacer, name: &str| {
+            scrub_file(&dir.join(name), &hash, pacer, None)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(scrub(&mut pacer, "challenge_0001"), ScrubOutcome::Intact);
+        assert_eq!(scrub(&mut pacer, "response_0001"), ScrubOutcome::Missing);
+        bytes[1000] ^= 4;
+        fs::write(&path, &bytes).unwrap();
+        assert!(matches!(
+            scrub(&mut pacer, "challenge_0001"),
+            ScrubOutcome::Corrupted(changed) if changed != hash
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
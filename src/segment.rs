@@ -0,0 +1,437 @@
+//! Segmented Downloads
+//!
+//! A segmented download splits the file into byte ranges which are fetched over separate
+//! connections and written in place into a file preallocated to its final size. The progress of
+//! every segment is recorded next to the file, in [`state_path`], so that an interrupted download
+//! resumes each segment where it left off. Every range request is conditioned on the version of
+//! the file the download started from, and the download starts over if the file changed. Since
+//! segments arrive out of order, the file is hashed in a separate pass once it is complete.
+
+use crate::{
+    atomic,
+    download::{
+        hash_if_missing, resume_hasher, save_hash, send_download_request, ByteRange, Cancelled,
+        DownloadOptions, RangeError, MAX_STALLS,
+    },
+    progress::ProgressSink,
+    throttle::RateLimiter,
+    validator::{RemoteFileChanged, Validator},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use core::sync::atomic::{AtomicU64, Ordering};
+use futures::future::join_all;
+use reqwest::{header::RANGE, Client, Method, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs::{self, OpenOptions},
+    io::{AsyncSeekExt, AsyncWriteExt, BufWriter},
+    sync::Mutex,
+    time::timeout,
+};
+use tokio_util::sync::CancellationToken;
+
+/// Number of bytes a segment downloads between two updates of the state file
+const CHECKPOINT_INTERVAL: u64 = 1 << 26;
+
+/// Segment
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Segment {
+    /// Starting index
+    pub start: u64,
+
+    /// Ending index, exclusive
+    pub end: u64,
+
+    /// Number of bytes of the segment already written to disk
+    pub downloaded: u64,
+}
+
+impl Segment {
+    /// Returns the index of the next byte to download.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.start + self.downloaded
+    }
+
+    /// Returns `true` if the segment has been fully downloaded.
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.position() >= self.end
+    }
+}
+
+/// Segmented Download State
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SegmentState {
+    /// Total size of the file
+    pub size: u64,
+
+    /// Segments covering the part of the file that was missing when the download started
+    pub segments: Vec<Segment>,
+}
+
+impl SegmentState {
+    /// Splits the range from `start` to `size` into at most `count` segments of equal length.
+    #[inline]
+    pub fn split(start: u64, size: u64, count: usize) -> Self {
+        let remaining = size.saturating_sub(start);
+        let count = (count.max(1) as u64).min(remaining.max(1));
+        let length = remaining.div_ceil(count);
+        let segments = (0..count)
+            .map(|i| Segment {
+                start: start + i * length,
+                end: (start + (i + 1) * length).min(size),
+                downloaded: 0,
+            })
+            .filter(|segment| segment.start < segment.end)
+            .collect();
+        Self { size, segments }
+    }
+
+    /// Returns the number of bytes of the file present on disk.
+    #[inline]
+    pub fn downloaded(&self) -> u64 {
+        let missing = self
+            .segments
+            .iter()
+            .map(|segment| segment.end - segment.position().min(segment.end))
+            .sum::<u64>();
+        self.size - missing
+    }
+}
+
+/// Returns the path of the file recording the state of the segmented download of `path`.
+#[inline]
+pub fn state_path(path: &Path) -> PathBuf {
+    let mut state_path = path.as_os_str().to_owned();
+    state_path.push(".segments");
+    state_path.into()
+}
+
+/// Saves `state` to `path`.
+#[inline]
+async fn save_state(path: &Path, state: &SegmentState) -> Result {
+    atomic::write_async(path, serde_json::to_vec(state)?).await
+}
+
+/// Sends a request for the bytes of `url` from `start` up to `end`, exclusive, conditioned on
+/// `validator`.
+#[inline]
+async fn send_range_request(
+    client: &Client,
+    url: &str,
+    start: u64,
+    end: u64,
+    validator: &Validator,
+) -> Result<Response> {
+    let request = client
+        .request(Method::GET, url)
+        .header(RANGE, ByteRange::header(&[ByteRange::of(start..end)]));
+    let response = Validator::apply(Some(validator), request)
+        .send()
+        .await?
+        .error_for_status()?;
+    if response.status() == StatusCode::OK && validator.if_range().is_some() {
+        return Err(RemoteFileChanged { url: url.into() }.into());
+    }
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(RangeError::Unsupported(response.status()).into());
+    }
+    Ok(response)
+}
+
+/// Downloads segment number `index` of `state` from `url` into the file at `path`, saving its
+/// progress and stopping once `cancel` is cancelled.
+#[inline]
+#[allow(clippy::too_many_arguments)]
+async fn download_segment(
+    client: &Client,
+    url: &str,
+    path: &Path,
+    state_path: &Path,
+    state: &Mutex<SegmentState>,
+    validator: &Validator,
+    index: usize,
+    downloaded: &AtomicU64,
+    progress: &dyn ProgressSink,
+    rate_limits: &[&RateLimiter],
+    options: &DownloadOptions,
+    cancel: &CancellationToken,
+) -> Result {
+    let mut segment = state.lock().await.segments[index];
+    if segment.is_complete() {
+        return Ok(());
+    }
+    let mut file = BufWriter::with_capacity(
+        options.buffer_size,
+        OpenOptions::new().write(true).open(path).await?,
+    );
+    file.seek(SeekFrom::Start(segment.position())).await?;
+    let mut response =
+        send_range_request(client, url, segment.position(), segment.end, validator).await?;
+    let mut unsaved = 0;
+    let mut stalls = 0;
+    while !segment.is_complete() {
+        let chunk = tokio::select! {
+            chunk = timeout(options.read_timeout, response.chunk()) => chunk,
+            _ = cancel.cancelled() => break,
+        };
+        match chunk.map(|c| c.transpose()) {
+            Ok(Some(Ok(chunk))) => {
+                for rate_limit in rate_limits {
+                    rate_limit.acquire(chunk.len() as u64).await;
+                }
+                let length = chunk.len().min((segment.end - segment.position()) as usize);
+                file.write_all(&chunk[..length]).await?;
+                segment.downloaded += length as u64;
+                unsaved += length as u64;
+                progress.on_bytes_downloaded(
+                    path,
+                    downloaded.fetch_add(length as u64, Ordering::Relaxed) + length as u64,
+                );
+                if unsaved >= CHECKPOINT_INTERVAL {
+                    file.flush().await?;
+                    let mut state = state.lock().await;
+                    state.segments[index] = segment;
+                    save_state(state_path, &state).await?;
+                    unsaved = 0;
+                }
+            }
+            Ok(None) => break,
+            Ok(Some(Err(err))) => return Err(err.into()),
+            Err(_) => {
+                stalls += 1;
+                if stalls >= MAX_STALLS {
+                    bail!("Segment {} of '{}' stalled {} times.", index, url, stalls);
+                }
+                response =
+                    send_range_request(client, url, segment.position(), segment.end, validator)
+                        .await?;
+            }
+        }
+    }
+    file.flush().await?;
+    let mut state = state.lock().await;
+    state.segments[index] = segment;
+    save_state(state_path, &state).await?;
+    if segment.is_complete() {
+        Ok(())
+    } else if cancel.is_cancelled() {
+        Err(Cancelled.into())
+    } else {
+        Err(anyhow!("Segment {} of '{}' ended early.", index, url))
+    }
+}
+
+/// Downloads the file at `url` to `path` over the number of connections given by the segments of
+/// `options`. If `path` already holds the beginning of the file, only the rest is downloaded, and
+/// if a previous segmented download was interrupted, each of its segments is resumed.
+#[inline]
+pub async fn download_segmented(
+    progress: &dyn ProgressSink,
+    client: &Client,
+    url: &str,
+    path: &Path,
+    options: &DownloadOptions,
+) -> Result {
+    let state_path = state_path(path);
+    let saved_validator = Validator::load(path, url).await;
+    let (state, validator) = match fs::read(&state_path).await {
+        Ok(bytes) => {
+            let validator = match saved_validator {
+                Some(validator) => validator,
+                _ => {
+                    let response = client.head(url).send().await?.error_for_status()?;
+                    Validator::from_response(url, &response)
+                }
+            };
+            (serde_json::from_slice::<SegmentState>(&bytes)?, validator)
+        }
+        _ => {
+            let existing = match fs::metadata(path).await {
+                Ok(metadata) => metadata.len(),
+                _ => 0,
+            };
+            match send_download_request(client, url, existing, saved_validator.as_ref()).await? {
+                Some((size, response)) if response.status() == StatusCode::OK => (
+                    SegmentState::split(0, size, options.segments),
+                    Validator::from_response(url, &response),
+                ),
+                Some((size, response)) => (
+                    SegmentState::split(existing, size, options.segments),
+                    Validator::from_response(url, &response),
+                ),
+                _ => {
+                    if options.hash {
+                        hash_if_missing(path, options).await?;
+                    }
+                    return Validator::remove(path).await;
+                }
+            }
+        }
+    };
+    validator.save(path).await?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .await?;
+    file.set_len(state.size).await?;
+    drop(file);
+    save_state(&state_path, &state).await?;
+    progress.on_download_started(url, path, state.downloaded(), state.size);
+    let downloaded = AtomicU64::new(state.downloaded());
+    let result: Result = async {
+        let file_rate_limit = options.rate_limit_per_file.map(RateLimiter::new);
+        let rate_limits = options
+            .rate_limit
+            .as_deref()
+            .into_iter()
+            .chain(file_rate_limit.as_ref())
+            .collect::<Vec<_>>();
+        let count = state.segments.len();
+        let state = Mutex::new(state);
+        // A failing segment stops the others, which save their progress before returning
+        let cancel = options.cancel.child_token();
+        let results = join_all((0..count).map(|index| {
+            let segment = download_segment(
+                client,
+                url,
+                path,
+                &state_path,
+                &state,
+                &validator,
+                index,
+                &downloaded,
+                progress,
+                &rate_limits,
+                options,
+                &cancel,
+            );
+            async {
+                let result = segment.await;
+                if result.is_err() {
+                    cancel.cancel();
+                }
+                result
+            }
+        }))
+        .await;
+        let error = results
+            .into_iter()
+            .filter_map(Result::err)
+            .min_by_key(|err| err.is::<Cancelled>());
+        if let Some(err) = error {
+            if err.is::<RemoteFileChanged>() {
+                fs::remove_file(&state_path).await?;
+                Validator::remove(path).await?;
+                OpenOptions::new()
+                    .write(true)
+                    .open(path)
+                    .await?
+                    .set_len(0)
+                    .await?;
+            }
+            return Err(err);
+        }
+        fs::remove_file(&state_path).await?;
+        Validator::remove(path).await?;
+        if options.hash {
+            let size = state.into_inner().size;
+            progress.on_hash_started(path, size);
+            let hasher = resume_hasher(path, size).await?;
+            save_hash(path, hasher, options).await?;
+        }
+        Ok(())
+    }
+    .await;
+    progress.on_download_finished(url, path, &result);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "native")]
+    use crate::{
+        calculate_hash,
+        download::download_file,
+        hash::Hash64,
+        mock::{Behavior, MockServer},
+        progress::NoProgress,
+        HashAlgorithm,
+    };
+
+    /// Checks that the segments cover exactly the missing part of the file.
+    #[test]
+    fn split_covers_missing_range() {
+        let state = SegmentState::split(10, 1000, 4);
+        assert_eq!(state.segments.len(), 4);
+        assert_eq!(state.segments[0].start, 10);
+        assert_eq!(state.segments[3].end, 1000);
+        for pair in state.segments.windows(2) {
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+        assert_eq!(state.downloaded(), 10);
+        assert_eq!(SegmentState::split(0, 3, 8).segments.len(), 3);
+    }
+
+    /// Checks that a file downloaded over several connections is written in place, one range
+    /// request per segment, and hashed once complete.
+    #[cfg(feature = "native")]
+    #[test]
+    fn segments_join() {
+        let data = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let path = std::env::temp_dir().join("ppot-verifier-segments-test");
+        let options = DownloadOptions {
+            segments: 4,
+            ..Default::default()
+        };
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let behavior = Behavior {
+                etag: Some("\"v1\"".into()),
+                ..Default::default()
+            };
+            let server = MockServer::start([("challenge_0003", data.clone(), behavior)]);
+            download_file(
+                &NoProgress,
+                &Client::new(),
+                &[server.url("challenge_0003")],
+                &path,
+                &options,
+            )
+            .await
+            .unwrap();
+            let mut ranges = server.requests()[1..]
+                .iter()
+                .map(|request| {
+                    assert_eq!(request.if_range.as_deref(), Some("\"v1\""));
+                    request.range.clone().unwrap()
+                })
+                .collect::<Vec<_>>();
+            ranges.sort();
+            assert_eq!(
+                ranges,
+                [
+                    "bytes=0-2499",
+                    "bytes=2500-4999",
+                    "bytes=5000-7499",
+                    "bytes=7500-9999"
+                ]
+            );
+        });
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        assert_eq!(
+            Hash64::load(options.hash_path(&path)).unwrap().0.to_vec(),
+            calculate_hash(&data, HashAlgorithm::Blake2b)
+        );
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(options.hash_path(&path)).unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+//! Status Server
+//!
+//! Serves the [`Report`] of the pipeline as JSON over HTTP, so that long runs can be monitored
+//! without a shell on the machine running them. Every request collects the report again from the
+//! storage and state directories:
+//!
+//! - `GET /status` returns the overall [`Status`]
+//! - `GET /rounds/{round}` returns the [`RoundState`] of a round
+//! - `GET /report` returns the whole [`Report`]
+
+use crate::{
+    status::{Report, RoundState, Status},
+    storage::StorageOptions,
+    Result,
+};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    routing::get,
+    Json, Router,
+};
+use core::future::Future;
+use std::{net::SocketAddr, sync::Arc};
+use tokio::task;
+use tracing::{error, info};
+
+/// Default address the status server listens on
+pub const LISTEN_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Response of the handlers
+type Response<T> = core::result::Result<Json<T>, StatusCode>;
+
+/// Collects the report of the pipeline in `storage` on the blocking thread pool.
+#[inline]
+async fn collect(storage: Arc<StorageOptions>) -> core::result::Result<Report, StatusCode> {
+    match task::spawn_blocking(move || Report::collect(&storage)).await {
+        Ok(Ok(report)) => Ok(report),
+        Ok(Err(err)) => {
+            error!("Unable to collect the report: {}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+        Err(err) => {
+            error!("Unable to collect the report: {}", err);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Handles `GET /status`.
+#[inline]
+async fn status(Extension(storage): Extension<Arc<StorageOptions>>) -> Response<Status> {
+    Ok(Json(collect(storage).await?.status))
+}
+
+/// Handles `GET /rounds/{round}`.
+#[inline]
+async fn round(
+    Extension(storage): Extension<Arc<StorageOptions>>,
+    Path(round): Path<usize>,
+) -> Response<RoundState> {
+    collect(storage)
+        .await?
+        .round(round)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Handles `GET /report`.
+#[inline]
+async fn report(Extension(storage): Extension<Arc<StorageOptions>>) -> Response<Report> {
+    Ok(Json(collect(storage).await?))
+}
+
+/// Builds the router of the status server reporting on the pipeline in `storage`.
+#[inline]
+pub fn router(storage: StorageOptions) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/rounds/:round", get(round))
+        .route("/report", get(report))
+        .layer(Extension(Arc::new(storage)))
+}
+
+/// Serves the status of the pipeline in `storage` on `address` until `shutdown` completes.
+#[inline]
+pub async fn serve<F>(address: SocketAddr, storage: StorageOptions, shutdown: F) -> Result
+where
+    F: Future<Output = ()>,
+{
+    let server = axum::Server::try_bind(&address)?;
+    info!("Serving the status on http://{}", address);
+    server
+        .serve(router(storage).into_make_service())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,484 @@
+//! Report Signatures
+//!
+//! Canonical reports are signed with Ed25519 keys in the format of [minisign], so that anyone can
+//! check a published report with `minisign -V -m ppot-report.canonical.json -p ppot.pub` as well
+//! as with `ppot report verify`. Keys are read from minisign key files or as the hexadecimal
+//! encoding of raw Ed25519 keys: the 32-byte seed of a secret key or the 32-byte public key.
+//!
+//! Signatures are made over the Blake2b hash of the report, as minisign does for large files, and
+//! carry a trusted comment signed along with them. Encrypted minisign secret keys are not
+//! supported, keys have to be generated with `minisign -G -W` or `ppot report keygen`.
+//!
+//! [minisign]: https://jedisct1.github.io/minisign/
+
+use crate::{into_array_unchecked, Result};
+use anyhow::{anyhow, bail, ensure};
+use blake2::{digest::consts::U32, Blake2b, Blake2b512, Digest};
+use core::fmt;
+use ed25519_dalek::{Keypair, Signer, Verifier};
+use rand::{CryptoRng, RngCore};
+use std::{fs, path::Path};
+
+/// Signature algorithm of minisign keys and of signatures over the message itself
+const ALGORITHM: &[u8; 2] = b"Ed";
+
+/// Signature algorithm of minisign signatures over the Blake2b hash of the message
+const PREHASHED_ALGORITHM: &[u8; 2] = b"ED";
+
+/// Key derivation algorithm of unencrypted minisign secret keys
+const NO_KDF: &[u8; 2] = b"\0\0";
+
+/// Checksum algorithm of minisign secret keys
+const CHECKSUM_ALGORITHM: &[u8; 2] = b"B2";
+
+/// Prefix of the untrusted comment line of minisign files
+const UNTRUSTED_PREFIX: &str = "untrusted comment: ";
+
+/// Prefix of the trusted comment line of minisign signatures
+const TRUSTED_PREFIX: &str = "trusted comment: ";
+
+/// Key Identifier
+///
+/// Eight bytes naming the key which made a signature, written in uppercase hexadecimal.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct KeyId(pub [u8; 8]);
+
+impl KeyId {
+    /// Returns the identifier of the raw Ed25519 `public_key`, the beginning of its Blake2b hash.
+    #[inline]
+    fn of(public_key: &ed25519_dalek::PublicKey) -> Self {
+        Self(into_array_unchecked(
+            &Blake2b512::digest(public_key.as_bytes())[..8],
+        ))
+    }
+}
+
+impl fmt::Display for KeyId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // minisign prints the key identifier as a little-endian integer
+        write!(f, "{:016X}", u64::from_le_bytes(self.0))
+    }
+}
+
+/// Splits a minisign file into its base64 payload, following the optional untrusted comment, and
+/// the lines after it.
+#[inline]
+fn split_minisign(text: &str) -> Result<(Vec<u8>, Vec<&str>)> {
+    let mut lines = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .skip_while(|line| line.starts_with(UNTRUSTED_PREFIX));
+    let line = lines
+        .next()
+        .ok_or_else(|| anyhow!("Missing the payload of the minisign file."))?;
+    let payload = base64::decode(line.trim()).map_err(|err| anyhow!("Invalid base64: {}", err))?;
+    Ok((payload, lines.collect()))
+}
+
+/// Decodes the hexadecimal encoding of a 32-byte key, if `text` is one.
+#[inline]
+fn decode_raw(text: &str) -> Option<[u8; 32]> {
+    let bytes = hex::decode(text.trim()).ok()?;
+    <[u8; 32]>::try_from(bytes.as_slice()).ok()
+}
+
+/// Secret Key
+pub struct SecretKey {
+    /// Identifier of the key
+    key_id: KeyId,
+
+    /// Ed25519 key pair
+    keypair: Keypair,
+}
+
+impl SecretKey {
+    /// Builds the secret key derived from the 32-byte `seed`.
+    #[inline]
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&seed).expect("Seeds are 32 bytes long.");
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        Self {
+            key_id: KeyId::of(&public),
+            keypair: Keypair { secret, public },
+        }
+    }
+
+    /// Generates a new secret key from `rng`.
+    #[inline]
+    pub fn generate<R>(rng: &mut R) -> Self
+    where
+        R: CryptoRng + RngCore,
+    {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+
+    /// Returns the identifier of the key.
+    #[inline]
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    /// Returns the public key matching `self`.
+    #[inline]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            key_id: self.key_id,
+            key: self.keypair.public,
+        }
+    }
+
+    /// Signs the Blake2b hash of `message` along with `trusted_comment`, which must fit on one
+    /// line.
+    #[inline]
+    pub fn sign(&self, message: &[u8], trusted_comment: &str) -> Result<Signature> {
+        ensure!(
+            !trusted_comment.contains(['\r', '\n']),
+            "The trusted comment must fit on one line."
+        );
+        let signature = self.keypair.sign(&Blake2b512::digest(message)).to_bytes();
+        let global_signature = self
+            .keypair
+            .sign(&[&signature[..], trusted_comment.as_bytes()].concat())
+            .to_bytes();
+        Ok(Signature {
+            algorithm: *PREHASHED_ALGORITHM,
+            key_id: self.key_id,
+            signature,
+            trusted_comment: trusted_comment.into(),
+            global_signature,
+        })
+    }
+
+    /// Parses an unencrypted minisign secret key, or the hexadecimal encoding of a raw Ed25519
+    /// seed.
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self> {
+        if let Some(seed) = decode_raw(text) {
+            return Ok(Self::from_seed(seed));
+        }
+        let (payload, _) = split_minisign(text)?;
+        ensure!(
+            payload.len() == 158,
+            "Invalid minisign secret key of {} bytes.",
+            payload.len()
+        );
+        ensure!(
+            &payload[..2] == ALGORITHM && &payload[4..6] == CHECKSUM_ALGORITHM,
+            "Unsupported minisign secret key algorithms."
+        );
+        if &payload[2..4] != NO_KDF {
+            bail!("Encrypted minisign secret keys are not supported, see `minisign -G -W`.");
+        }
+        let (key_id, secret, checksum) = (&payload[54..62], &payload[62..126], &payload[126..]);
+        let expected = Blake2b::<U32>::new()
+            .chain_update(ALGORITHM)
+            .chain_update(key_id)
+            .chain_update(secret)
+            .finalize();
+        ensure!(
+            checksum == &expected[..],
+            "Invalid checksum of the minisign secret key."
+        );
+        let keypair = Self::from_seed(into_array_unchecked(&secret[..32])).keypair;
+        ensure!(
+            keypair.public.as_bytes() == &secret[32..],
+            "The public key of the minisign secret key does not match its secret key."
+        );
+        Ok(Self {
+            key_id: KeyId(into_array_unchecked(key_id)),
+            keypair,
+        })
+    }
+
+    /// Loads the secret key saved at `path`, see [`parse`](Self::parse).
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Encodes `self` as an unencrypted minisign secret key.
+    #[inline]
+    pub fn to_minisign(&self) -> String {
+        let secret = self.keypair.to_bytes();
+        let checksum = Blake2b::<U32>::new()
+            .chain_update(ALGORITHM)
+            .chain_update(self.key_id.0)
+            .chain_update(secret)
+            .finalize();
+        let payload = [
+            &ALGORITHM[..],
+            NO_KDF,
+            CHECKSUM_ALGORITHM,
+            &[0; 48],
+            &self.key_id.0,
+            &secret,
+            &checksum,
+        ]
+        .concat();
+        format!(
+            "{}minisign secret key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            self.key_id,
+            base64::encode(payload)
+        )
+    }
+}
+
+/// Public Key
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PublicKey {
+    /// Identifier of the key
+    key_id: KeyId,
+
+    /// Ed25519 public key
+    key: ed25519_dalek::PublicKey,
+}
+
+impl PublicKey {
+    /// Returns the identifier of the key.
+    #[inline]
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    /// Parses a minisign public key, with or without its comment line, or the hexadecimal
+    /// encoding of a raw Ed25519 public key.
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self> {
+        if let Some(key) = decode_raw(text) {
+            let key = ed25519_dalek::PublicKey::from_bytes(&key)?;
+            return Ok(Self {
+                key_id: KeyId::of(&key),
+                key,
+            });
+        }
+        let (payload, _) = split_minisign(text)?;
+        ensure!(
+            payload.len() == 42 && &payload[..2] == ALGORITHM,
+            "Invalid minisign public key."
+        );
+        Ok(Self {
+            key_id: KeyId(into_array_unchecked(&payload[2..10])),
+            key: ed25519_dalek::PublicKey::from_bytes(&payload[10..])?,
+        })
+    }
+
+    /// Loads the public key saved at `path`, see [`parse`](Self::parse).
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Encodes `self` as a minisign public key.
+    #[inline]
+    pub fn to_minisign(&self) -> String {
+        let payload = [&ALGORITHM[..], &self.key_id.0, self.key.as_bytes()].concat();
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_PREFIX,
+            self.key_id,
+            base64::encode(payload)
+        )
+    }
+
+    /// Checks that `signature` was made by `self` over `message` and its trusted comment.
+    #[inline]
+    pub fn verify(&self, message: &[u8], signature: &Signature) -> Result {
+        ensure!(
+            signature.key_id == self.key_id,
+            "The signature was made by key {}, not by key {}.",
+            signature.key_id,
+            self.key_id
+        );
+        let signed = match &signature.algorithm {
+            PREHASHED_ALGORITHM => Blake2b512::digest(message).to_vec(),
+            _ => message.to_vec(),
+        };
+        let invalid = |_| anyhow!("Invalid signature by key {}.", self.key_id);
+        self.key
+            .verify_strict(
+                &signed,
+                &ed25519_dalek::Signature::from(signature.signature),
+            )
+            .map_err(invalid)?;
+        self.key
+            .verify(
+                &[
+                    &signature.signature[..],
+                    signature.trusted_comment.as_bytes(),
+                ]
+                .concat(),
+                &ed25519_dalek::Signature::from(signature.global_signature),
+            )
+            .map_err(|_| anyhow!("Invalid trusted comment signed by key {}.", self.key_id))
+    }
+}
+
+/// Signature
+///
+/// Detached minisign signature of a report.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Signature {
+    /// Signature algorithm, [`PREHASHED_ALGORITHM`] for the signatures made here
+    algorithm: [u8; 2],
+
+    /// Identifier of the key which made the signature
+    key_id: KeyId,
+
+    /// Signature of the message or of its Blake2b hash
+    signature: [u8; 64],
+
+    /// Comment signed along with the signature
+    trusted_comment: String,
+
+    /// Signature of the signature and of the trusted comment
+    global_signature: [u8; 64],
+}
+
+impl Signature {
+    /// Returns the identifier of the key which made the signature.
+    #[inline]
+    pub fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    /// Returns the comment signed along with the signature.
+    #[inline]
+    pub fn trusted_comment(&self) -> &str {
+        &self.trusted_comment
+    }
+
+    /// Parses a minisign signature.
+    #[inline]
+    pub fn parse(text: &str) -> Result<Self> {
+        let (payload, rest) = split_minisign(text)?;
+        ensure!(payload.len() == 74, "Invalid minisign signature.");
+        let algorithm = into_array_unchecked::<u8, _, 2>(&payload[..2]);
+        ensure!(
+            &algorithm == ALGORITHM || &algorithm == PREHASHED_ALGORITHM,
+            "Unsupported minisign signature algorithm."
+        );
+        let (trusted_comment, global_signature) = match rest.as_slice() {
+            [comment, global_signature, ..] => (
+                comment
+                    .strip_prefix(TRUSTED_PREFIX)
+                    .ok_or_else(|| anyhow!("Missing the trusted comment of the signature."))?,
+                base64::decode(global_signature.trim())
+                    .map_err(|err| anyhow!("Invalid base64: {}", err))?,
+            ),
+            _ => bail!("Missing the trusted comment of the signature."),
+        };
+        ensure!(global_signature.len() == 64, "Invalid minisign signature.");
+        Ok(Self {
+            algorithm,
+            key_id: KeyId(into_array_unchecked(&payload[2..10])),
+            signature: into_array_unchecked(&payload[10..]),
+            trusted_comment: trusted_comment.into(),
+            global_signature: into_array_unchecked(global_signature),
+        })
+    }
+
+    /// Loads the signature saved at `path`, see [`parse`](Self::parse).
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+}
+
+impl fmt::Display for Signature {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let payload = [&self.algorithm[..], &self.key_id.0, &self.signature].concat();
+        writeln!(
+            f,
+            "{}signature from minisign secret key {}",
+            UNTRUSTED_PREFIX, self.key_id
+        )?;
+        writeln!(f, "{}", base64::encode(payload))?;
+        writeln!(f, "{}{}", TRUSTED_PREFIX, self.trusted_comment)?;
+        writeln!(f, "{}", base64::encode(self.global_signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that signatures made by minisign itself are verified.
+    #[test]
+    fn minisign_signatures_verify() {
+        let key =
+            PublicKey::parse("RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3").unwrap();
+        let signature = Signature::parse(
+            "untrusted comment: signature from minisign secret key\n\
+             RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+             trusted comment: timestamp:1633700835\tfile:test\tprehashed\n\
+             wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==\n",
+        )
+        .unwrap();
+        assert_eq!(
+            signature.trusted_comment(),
+            "timestamp:1633700835\tfile:test\tprehashed"
+        );
+        key.verify(b"test", &signature).unwrap();
+        assert!(key.verify(b"tset", &signature).is_err());
+    }
+
+    /// Checks that signatures made here verify once written and parsed back, with keys in both
+    /// formats, and that tampered reports and comments are rejected.
+    #[test]
+    fn signatures_round_trip() {
+        let secret = SecretKey::generate(&mut rand::thread_rng());
+        let secret = SecretKey::parse(&secret.to_minisign()).unwrap();
+        let public = PublicKey::parse(&secret.public_key().to_minisign()).unwrap();
+        assert_eq!(public, secret.public_key());
+        let signature = secret.sign(b"report", "file:report").unwrap();
+        let parsed = Signature::parse(&signature.to_string()).unwrap();
+        assert_eq!(parsed, signature);
+        public.verify(b"report", &parsed).unwrap();
+        assert!(public.verify(b"reporT", &parsed).is_err());
+        let mut tampered = parsed.clone();
+        tampered.trusted_comment = "file:other".into();
+        assert!(public.verify(b"report", &tampered).is_err());
+        let raw = SecretKey::parse(&hex::encode([7; 32])).unwrap();
+        let raw_public = PublicKey::parse(&hex::encode(raw.public_key().key.as_bytes())).unwrap();
+        assert_eq!(raw_public, raw.public_key());
+        let signature = raw.sign(b"report", "").unwrap();
+        raw_public.verify(b"report", &signature).unwrap();
+        assert!(public.verify(b"report", &signature).is_err());
+        assert!(secret.sign(b"report", "two\nlines").is_err());
+    }
+
+    /// Checks that secret keys whose public key was swapped for another one are rejected, even
+    /// with a valid checksum.
+    #[test]
+    fn spliced_secret_keys_fail() {
+        let secret = SecretKey::from_seed([3; 32]);
+        let other = SecretKey::from_seed([5; 32]);
+        let (mut payload, _) = split_minisign(&secret.to_minisign()).unwrap();
+        payload[94..126].copy_from_slice(other.keypair.public.as_bytes());
+        let checksum = Blake2b::<U32>::new()
+            .chain_update(ALGORITHM)
+            .chain_update(&payload[54..62])
+            .chain_update(&payload[62..126])
+            .finalize();
+        payload[126..].copy_from_slice(&checksum);
+        let spliced = format!("{}\n{}\n", UNTRUSTED_PREFIX, base64::encode(payload));
+        match SecretKey::parse(&spliced) {
+            Err(err) => assert!(err.to_string().contains("does not match")),
+            _ => panic!("the spliced secret key was accepted"),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Interrupt Handling
+//!
+//! Killing a binary in the middle of a write leaves truncated files behind. Instead, the first
+//! Ctrl-C, or `SIGTERM`, cancels a [`CancellationToken`] which the long-running loops check between
+//! two chunks of work, so that they flush their buffers and save their progress before exiting. A
+//! second interrupt exits right away.
+
+use crate::Result;
+use std::process;
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
+
+/// Exit code of a process stopped by an interrupt
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Installs the interrupt handler of the process, returning the token it cancels on the first
+/// interrupt. The handler can only be installed once per process.
+#[inline]
+pub fn cancel_on_interrupt() -> Result<CancellationToken> {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    ctrlc::set_handler(move || {
+        if cancel.is_cancelled() {
+            process::exit(INTERRUPTED_EXIT_CODE);
+        }
+        warn!("Interrupted, saving progress. Interrupt again to exit immediately.");
+        cancel.cancel();
+    })?;
+    Ok(token)
+}
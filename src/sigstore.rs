@@ -0,0 +1,1122 @@
+//! Keyless Signatures
+//!
+//! Reports can be signed without a long-lived key, the way `cosign sign-blob` does with
+//! [Sigstore]: an ephemeral P-256 key is generated, Fulcio certifies it for the identity of an
+//! OpenID Connect token, such as the email of a person or the workflow of a CI job, and the
+//! signature is recorded in the Rekor transparency log. The key is dropped once the report is
+//! signed, what remains is a [`KeylessSignature`] holding the short-lived certificate, the
+//! signature and its entry in the log, which proves that the signature was made while the
+//! certificate was valid.
+//!
+//! The certificate and the signature are those of `cosign`, so a published signature can also be
+//! checked with the root of trust of Sigstore:
+//!
+//! ```text
+//! jq -r '.certificates[0]' ppot-report.canonical.json.sigstore.json > report.pem
+//! jq -r .signature ppot-report.canonical.json.sigstore.json > report.sig
+//! cosign verify-blob --certificate report.pem --signature report.sig \
+//!     --certificate-identity verifier@example.org \
+//!     --certificate-oidc-issuer https://github.com/login/oauth ppot-report.canonical.json
+//! ```
+//!
+//! Here the certificate must chain up to the pinned root of Fulcio and the entry must be signed by
+//! the pinned key of the Rekor log, whose signed entry timestamp proves the time the certificate
+//! is checked at, see [`TrustRoot`]. The signed certificate timestamps of the certificates are not
+//! checked.
+//!
+//! [Sigstore]: https://www.sigstore.dev
+
+use crate::{
+    der::{
+        encode, explicit, implicit, Reader, BIT_STRING, BOOLEAN, GENERALIZED_TIME, INTEGER,
+        OBJECT_IDENTIFIER, OCTET_STRING, SEQUENCE, UTC_TIME, UTF8_STRING,
+    },
+    download::USER_AGENT,
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use core::{fmt, str::FromStr};
+use p256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use reqwest::{header::USER_AGENT as USER_AGENT_HEADER, Client};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, fs, path::Path};
+use time::{macros::format_description, PrimitiveDateTime};
+
+/// Default URL of the Fulcio certificate authority
+pub const DEFAULT_FULCIO_URL: &str = "https://fulcio.sigstore.dev";
+
+/// Default URL of the Rekor transparency log
+pub const DEFAULT_REKOR_URL: &str = "https://rekor.sigstore.dev";
+
+/// Extension appended to the name of a report to name its keyless signature
+pub const SIGSTORE_EXTENSION: &str = ".sigstore.json";
+
+/// Environment variable from which the OpenID Connect identity token is read if none is given
+/// explicitly
+pub const TOKEN_VARIABLE: &str = "SIGSTORE_ID_TOKEN";
+
+/// Audience of the identity tokens accepted by Fulcio
+const AUDIENCE: &str = "sigstore";
+
+/// Object identifier of elliptic curve public keys
+const EC_PUBLIC_KEY_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+
+/// Object identifier of the P-256 curve
+const P256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+
+/// Object identifier of the P-384 curve
+const P384_OID: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+
+/// Object identifier of ECDSA signatures over SHA-256 hashes
+const ECDSA_SHA256_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+
+/// Object identifier of ECDSA signatures over SHA-384 hashes
+const ECDSA_SHA384_OID: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+
+/// Object identifier of the basic constraints extension
+const BASIC_CONSTRAINTS_OID: &[u8] = &[0x55, 0x1d, 0x13];
+
+/// Object identifier of the extended key usage extension
+const EXTENDED_KEY_USAGE_OID: &[u8] = &[0x55, 0x1d, 0x25];
+
+/// Object identifier of the code signing key usage
+const CODE_SIGNING_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x03];
+
+/// Object identifier of the subject alternative name extension
+const SUBJECT_ALT_NAME_OID: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// Object identifier of the Fulcio extension holding the OpenID Connect issuer, as a UTF-8 string
+const ISSUER_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x08];
+
+/// Object identifier of the deprecated Fulcio extension holding the OpenID Connect issuer, as raw
+/// bytes
+const LEGACY_ISSUER_OID: &[u8] = &[0x2b, 0x06, 0x01, 0x04, 0x01, 0x83, 0xbf, 0x30, 0x01, 0x01];
+
+/// Largest number of certificates above a signing certificate in its chain
+const MAX_CHAIN_LENGTH: usize = 4;
+
+/// Root certificate of the public instance of Fulcio
+const FULCIO_ROOT: &str = "-----BEGIN CERTIFICATE-----
+MIIB9zCCAXygAwIBAgIUALZNAPFdxHPwjeDloDwyYChAO/4wCgYIKoZIzj0EAwMw
+KjEVMBMGA1UEChMMc2lnc3RvcmUuZGV2MREwDwYDVQQDEwhzaWdzdG9yZTAeFw0y
+MTEwMDcxMzU2NTlaFw0zMTEwMDUxMzU2NThaMCoxFTATBgNVBAoTDHNpZ3N0b3Jl
+LmRldjERMA8GA1UEAxMIc2lnc3RvcmUwdjAQBgcqhkjOPQIBBgUrgQQAIgNiAAT7
+XeFT4rb3PQGwS4IajtLk3/OlnpgangaBclYpsYBr5i+4ynB07ceb3LP0OIOZdxex
+X69c5iVuyJRQ+Hz05yi+UF3uBWAlHpiS5sh0+H2GHE7SXrk1EC5m1Tr19L9gg92j
+YzBhMA4GA1UdDwEB/wQEAwIBBjAPBgNVHRMBAf8EBTADAQH/MB0GA1UdDgQWBBRY
+wB5fkUWlZql6zJChkyLQKsXF+jAfBgNVHSMEGDAWgBRYwB5fkUWlZql6zJChkyLQ
+KsXF+jAKBggqhkjOPQQDAwNpADBmAjEAj1nHeXZp+13NWBNa+EDsDP8G1WWg1tCM
+WP/WHPqpaVo0jhsweNFZgSs0eE7wYI4qAjEA2WB9ot98sIkoF3vZYdd3/VtWB5b9
+TNMea7Ix/stJ5TfcLLeABLE4BNJOsQ4vnBHJ
+-----END CERTIFICATE-----
+";
+
+/// Intermediate certificate of the public instance of Fulcio, issued by [`FULCIO_ROOT`]
+const FULCIO_INTERMEDIATE: &str = "-----BEGIN CERTIFICATE-----
+MIICGjCCAaGgAwIBAgIUALnViVfnU0brJasmRkHrn/UnfaQwCgYIKoZIzj0EAwMw
+KjEVMBMGA1UEChMMc2lnc3RvcmUuZGV2MREwDwYDVQQDEwhzaWdzdG9yZTAeFw0y
+MjA0MTMyMDA2MTVaFw0zMTEwMDUxMzU2NThaMDcxFTATBgNVBAoTDHNpZ3N0b3Jl
+LmRldjEeMBwGA1UEAxMVc2lnc3RvcmUtaW50ZXJtZWRpYXRlMHYwEAYHKoZIzj0C
+AQYFK4EEACIDYgAE8RVS/ysH+NOvuDZyPIZtilgUF9NlarYpAd9HP1vBBH1U5CV7
+7LSS7s0ZiH4nE7Hv7ptS6LvvR/STk798LVgMzLlJ4HeIfF3tHSaexLcYpSASr1kS
+0N/RgBJz/9jWCiXno3sweTAOBgNVHQ8BAf8EBAMCAQYwEwYDVR0lBAwwCgYIKwYB
+BQUHAwMwEgYDVR0TAQH/BAgwBgEB/wIBADAdBgNVHQ4EFgQU39Ppz1YkEZb5qNjp
+KFWixi4YZD8wHwYDVR0jBBgwFoAUWMAeX5FFpWapesyQoZMi0CrFxfowCgYIKoZI
+zj0EAwMDZwAwZAIwPCsQK4DYiZYDPIaDi5HFKnfxXx6ASSVmERfsynYBiX2X6SJR
+nZU84/9DZdnFvvxmAjBOt6QpBlc4J/0DxvkTCqpclvziL6BCCPnjdlIB3Pu3BxsP
+mygUY7Ii2zbdCdliiow=
+-----END CERTIFICATE-----
+";
+
+/// Public key of the public instance of Rekor
+const REKOR_PUBLIC_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE2G2Y+2tabdTV5BcGiBIx0a9fAFwr
+kBbmLSGtks4L3qX6yYY0zufBnhC8Ur/iy55GhWP/9A/bY2LhC30M9+RYtw==
+-----END PUBLIC KEY-----
+";
+
+/// Encodes `der` in PEM with `label`.
+#[inline]
+fn pem_encode(label: &str, der: &[u8]) -> String {
+    let encoded = base64::encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(&String::from_utf8_lossy(line));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+/// Decodes the first PEM block of `pem`, whatever its label.
+#[inline]
+fn pem_decode(pem: &str) -> Result<Vec<u8>> {
+    let mut lines = pem.lines().map(str::trim);
+    lines
+        .by_ref()
+        .find(|line| line.starts_with("-----BEGIN "))
+        .ok_or_else(|| anyhow!("Missing PEM block."))?;
+    let encoded = lines
+        .take_while(|line| !line.starts_with("-----END "))
+        .collect::<String>();
+    Ok(base64::decode(encoded)?)
+}
+
+/// Returns the DER-encoded subject public key info of `key`.
+#[inline]
+fn public_key_info(key: &VerifyingKey) -> Vec<u8> {
+    let algorithm = [
+        encode(OBJECT_IDENTIFIER, EC_PUBLIC_KEY_OID),
+        encode(OBJECT_IDENTIFIER, P256_OID),
+    ]
+    .concat();
+    let mut point = vec![0];
+    point.extend_from_slice(key.to_encoded_point(false).as_bytes());
+    encode(
+        SEQUENCE,
+        &[encode(SEQUENCE, &algorithm), encode(BIT_STRING, &point)].concat(),
+    )
+}
+
+/// Parses a DER UTC or generalized `time` into a Unix timestamp.
+#[inline]
+fn parse_time(tag: u8, time: &[u8]) -> Result<i64> {
+    let time = core::str::from_utf8(time)?;
+    let time = match tag {
+        // UTC times hold the last two digits of years from 1950 to 2049
+        UTC_TIME if time.get(..2).is_some_and(|year| year < "50") => format!("20{}", time),
+        UTC_TIME => format!("19{}", time),
+        GENERALIZED_TIME => time.to_string(),
+        _ => bail!("Invalid certificate time."),
+    };
+    Ok(PrimitiveDateTime::parse(
+        &time,
+        format_description!("[year][month][day][hour][minute][second]Z"),
+    )?
+    .assume_utc()
+    .unix_timestamp())
+}
+
+/// Identity Certified by Fulcio
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Identity {
+    /// Email or URI of the signer, from the subject alternative name of the certificate
+    pub subject: String,
+
+    /// OpenID Connect issuer which authenticated the signer
+    pub issuer: Option<String>,
+}
+
+impl fmt::Display for Identity {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.subject)?;
+        if let Some(issuer) = &self.issuer {
+            write!(f, " ({})", issuer)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Identity {
+    type Err = anyhow::Error;
+
+    /// Parses an identity in its [`Display`](fmt::Display) form, `subject (issuer)`, which must
+    /// name its issuer.
+    #[inline]
+    fn from_str(identity: &str) -> Result<Self, Self::Err> {
+        match identity
+            .strip_suffix(')')
+            .and_then(|identity| identity.rsplit_once(" ("))
+        {
+            Some((subject, issuer)) if !subject.is_empty() && !issuer.is_empty() => Ok(Self {
+                subject: subject.into(),
+                issuer: Some(issuer.into()),
+            }),
+            _ => bail!(
+                "Expected an identity such as 'name@example.org (https://github.com/login/oauth)' \
+                 but got '{}'.",
+                identity
+            ),
+        }
+    }
+}
+
+/// Public Key Certified by a Certificate
+enum CertifiedKey {
+    /// ECDSA key over P-256, such as the keys of signing certificates and of the Rekor log
+    P256(VerifyingKey),
+
+    /// ECDSA key over P-384, such as the keys of the certificate authorities of Fulcio
+    P384(p384::ecdsa::VerifyingKey),
+}
+
+impl CertifiedKey {
+    /// Parses the DER-encoded subject public key info `der`.
+    #[inline]
+    fn parse(der: &[u8]) -> Result<Self> {
+        let mut key_info = Reader(Reader(der).expect(SEQUENCE)?);
+        let mut algorithm = Reader(key_info.expect(SEQUENCE)?);
+        ensure!(
+            algorithm.expect(OBJECT_IDENTIFIER)? == EC_PUBLIC_KEY_OID,
+            "Only elliptic curve keys are supported."
+        );
+        let curve = algorithm.expect(OBJECT_IDENTIFIER)?;
+        let point = match key_info.expect(BIT_STRING)? {
+            [0, point @ ..] => point,
+            _ => bail!("Invalid public key."),
+        };
+        Ok(match curve {
+            P256_OID => Self::P256(VerifyingKey::from_sec1_bytes(point)?),
+            P384_OID => Self::P384(p384::ecdsa::VerifyingKey::from_sec1_bytes(point)?),
+            _ => bail!("Only P-256 and P-384 keys are supported."),
+        })
+    }
+
+    /// Returns `true` if the DER-encoded `signature` made with the signature `algorithm` is a
+    /// valid signature of `message` for `self`.
+    #[inline]
+    fn verify(&self, algorithm: &[u8], message: &[u8], signature: &[u8]) -> bool {
+        match (self, algorithm) {
+            (Self::P256(key), ECDSA_SHA256_OID) => Signature::from_der(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            (Self::P384(key), ECDSA_SHA384_OID) => p384::ecdsa::Signature::from_der(signature)
+                .is_ok_and(|signature| key.verify(message, &signature).is_ok()),
+            _ => false,
+        }
+    }
+}
+
+/// X.509 Certificate
+struct Certificate {
+    /// DER encoding of the certificate
+    der: Vec<u8>,
+
+    /// DER encoding of the signed part of the certificate
+    tbs: Vec<u8>,
+
+    /// Object identifier of the algorithm of the signature of the issuer
+    algorithm: Vec<u8>,
+
+    /// Signature of the issuer, DER-encoded
+    signature: Vec<u8>,
+
+    /// DER encoding of the name of the issuer
+    issuer: Vec<u8>,
+
+    /// DER encoding of the name of the subject
+    subject: Vec<u8>,
+
+    /// Certified public key
+    key: CertifiedKey,
+
+    /// Certified identity, for signing certificates
+    identity: Option<Identity>,
+
+    /// Unix timestamp from which the certificate is valid
+    not_before: i64,
+
+    /// Unix timestamp until which the certificate is valid
+    not_after: i64,
+
+    /// Whether the certificate is the one of a certificate authority
+    is_ca: bool,
+
+    /// Whether the key of the certificate may sign code, which Fulcio allows
+    code_signing: bool,
+}
+
+impl Certificate {
+    /// Parses the DER-encoded X.509 certificate `der`.
+    #[inline]
+    fn parse(der: &[u8]) -> Result<Self> {
+        let mut certificate = Reader(Reader(der).expect(SEQUENCE)?);
+        let tbs = certificate.next_encoded()?;
+        let algorithm = Reader(certificate.expect(SEQUENCE)?).expect(OBJECT_IDENTIFIER)?;
+        let signature = match certificate.expect(BIT_STRING)? {
+            [0, signature @ ..] => signature,
+            _ => bail!("Invalid signature of the certificate."),
+        };
+        let mut tbs_reader = Reader(Reader(tbs).expect(SEQUENCE)?);
+        tbs_reader.optional(explicit(0))?;
+        tbs_reader.expect(INTEGER)?;
+        tbs_reader.expect(SEQUENCE)?;
+        let issuer = tbs_reader.next_encoded()?;
+        let mut validity = Reader(tbs_reader.expect(SEQUENCE)?);
+        let (tag, not_before) = validity.next()?;
+        let not_before = parse_time(tag, not_before)?;
+        let (tag, not_after) = validity.next()?;
+        let not_after = parse_time(tag, not_after)?;
+        let subject_name = tbs_reader.next_encoded()?;
+        let key = CertifiedKey::parse(tbs_reader.next_encoded()?)?;
+        tbs_reader.optional(implicit(1))?;
+        tbs_reader.optional(implicit(2))?;
+        let mut subject = None;
+        let mut oidc_issuer = None;
+        let (mut is_ca, mut code_signing) = (false, false);
+        if let Some(extensions) = tbs_reader.optional(explicit(3))? {
+            let mut extensions = Reader(Reader(extensions).expect(SEQUENCE)?);
+            while !extensions.is_empty() {
+                let mut extension = Reader(extensions.expect(SEQUENCE)?);
+                let id = extension.expect(OBJECT_IDENTIFIER)?;
+                extension.optional(BOOLEAN)?;
+                let value = extension.expect(OCTET_STRING)?;
+                match id {
+                    SUBJECT_ALT_NAME_OID => {
+                        let mut names = Reader(Reader(value).expect(SEQUENCE)?);
+                        while !names.is_empty() {
+                            // Emails are tagged `[1]` and URIs `[6]`
+                            match names.next()? {
+                                (tag, name) if tag == implicit(1) || tag == implicit(6) => {
+                                    subject.get_or_insert(String::from_utf8(name.to_vec())?);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    BASIC_CONSTRAINTS_OID => {
+                        is_ca = Reader(Reader(value).expect(SEQUENCE)?)
+                            .optional(BOOLEAN)?
+                            .is_some_and(|ca| ca.iter().any(|byte| *byte != 0));
+                    }
+                    EXTENDED_KEY_USAGE_OID => {
+                        let mut usages = Reader(Reader(value).expect(SEQUENCE)?);
+                        while !usages.is_empty() {
+                            code_signing |= usages.expect(OBJECT_IDENTIFIER)? == CODE_SIGNING_OID;
+                        }
+                    }
+                    ISSUER_OID => {
+                        oidc_issuer = Some(String::from_utf8(
+                            Reader(value).expect(UTF8_STRING)?.to_vec(),
+                        )?)
+                    }
+                    LEGACY_ISSUER_OID if oidc_issuer.is_none() => {
+                        oidc_issuer = Some(String::from_utf8(value.to_vec())?)
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(Self {
+            der: der.to_vec(),
+            tbs: tbs.to_vec(),
+            algorithm: algorithm.to_vec(),
+            signature: signature.to_vec(),
+            issuer: issuer.to_vec(),
+            subject: subject_name.to_vec(),
+            key,
+            identity: subject.map(|subject| Identity {
+                subject,
+                issuer: oidc_issuer,
+            }),
+            not_before,
+            not_after,
+            is_ca,
+            code_signing,
+        })
+    }
+
+    /// Parses the PEM-encoded X.509 certificate `pem`.
+    #[inline]
+    fn from_pem(pem: &str) -> Result<Self> {
+        Self::parse(&pem_decode(pem)?)
+    }
+
+    /// Returns `true` if `self` is valid at the Unix timestamp `time`.
+    #[inline]
+    fn is_valid_at(&self, time: i64) -> bool {
+        (self.not_before..=self.not_after).contains(&time)
+    }
+
+    /// Returns `true` if `self` is signed by the subject of `issuer`.
+    #[inline]
+    fn is_issued_by(&self, issuer: &Certificate) -> bool {
+        self.issuer == issuer.subject
+            && issuer
+                .key
+                .verify(&self.algorithm, &self.tbs, &self.signature)
+    }
+}
+
+/// Root of Trust of Keyless Signatures
+///
+/// The certificate authorities of Fulcio which signing certificates must chain up to, and the
+/// Rekor log which must have promised to include the signatures, see [`sigstore`](Self::sigstore).
+pub struct TrustRoot {
+    /// Root certificates of Fulcio
+    roots: Vec<Certificate>,
+
+    /// Intermediate certificates of Fulcio, checked against the roots like the ones given along
+    /// with signing certificates
+    intermediates: Vec<Certificate>,
+
+    /// Public key of the Rekor log
+    rekor_key: VerifyingKey,
+
+    /// Identifier of the Rekor log, the hexadecimal SHA-256 hash of its public key
+    rekor_log_id: String,
+
+    /// URL of the Rekor log
+    rekor_url: String,
+}
+
+impl TrustRoot {
+    /// Builds the root of trust of the Fulcio instance with the PEM-encoded `roots` and
+    /// `intermediates` certificates and of the Rekor log at `rekor_url` with the PEM-encoded
+    /// public key `rekor_key`.
+    #[inline]
+    pub fn new(
+        roots: &[&str],
+        intermediates: &[&str],
+        rekor_key: &str,
+        rekor_url: &str,
+    ) -> Result<Self> {
+        let rekor_key_info = pem_decode(rekor_key)?;
+        let rekor_key = match CertifiedKey::parse(&rekor_key_info)? {
+            CertifiedKey::P256(key) => key,
+            _ => bail!("Only P-256 keys of Rekor logs are supported."),
+        };
+        Ok(Self {
+            roots: roots
+                .iter()
+                .map(|root| Certificate::from_pem(root))
+                .collect::<Result<_>>()?,
+            intermediates: intermediates
+                .iter()
+                .map(|intermediate| Certificate::from_pem(intermediate))
+                .collect::<Result<_>>()?,
+            rekor_key,
+            rekor_log_id: hex::encode(Sha256::digest(&rekor_key_info)),
+            rekor_url: rekor_url.trim_end_matches('/').into(),
+        })
+    }
+
+    /// Returns the root of trust of the public instances of Fulcio and Rekor run by Sigstore.
+    #[inline]
+    pub fn sigstore() -> Self {
+        Self::new(
+            &[FULCIO_ROOT],
+            &[FULCIO_INTERMEDIATE],
+            REKOR_PUBLIC_KEY,
+            DEFAULT_REKOR_URL,
+        )
+        .expect("The root of trust of Sigstore is valid.")
+    }
+
+    /// Checks that `certificate` chains up to a root of Fulcio through the `presented`
+    /// certificates or the intermediates of `self`, every certificate being valid at `time`.
+    #[inline]
+    fn check_chain(
+        &self,
+        certificate: &Certificate,
+        presented: &[Certificate],
+        time: i64,
+    ) -> Result {
+        let mut certificate = certificate;
+        for _ in 0..MAX_CHAIN_LENGTH {
+            if self
+                .roots
+                .iter()
+                .any(|root| root.is_valid_at(time) && certificate.is_issued_by(root))
+            {
+                return Ok(());
+            }
+            certificate = presented
+                .iter()
+                .chain(&self.intermediates)
+                .find(|issuer| {
+                    issuer.is_ca && issuer.is_valid_at(time) && certificate.is_issued_by(issuer)
+                })
+                .ok_or_else(|| anyhow!("The signing certificate is not issued by Fulcio."))?;
+        }
+        bail!("The chain of the signing certificate is too long.")
+    }
+
+    /// Checks that `entry` is signed by the Rekor log of `self` and records the signature of
+    /// `data`, in Base64, made with the key of `certificate`.
+    #[inline]
+    fn check_entry(
+        &self,
+        entry: &RekorEntry,
+        data: &[u8],
+        signature: &str,
+        certificate: &Certificate,
+    ) -> Result {
+        ensure!(
+            entry.url.trim_end_matches('/') == self.rekor_url && entry.log_id == self.rekor_log_id,
+            "The signature is logged in {}, not in the trusted Rekor log at {}.",
+            entry.url,
+            self.rekor_url
+        );
+        let signed_entry_timestamp = entry
+            .signed_entry_timestamp
+            .as_deref()
+            .ok_or_else(|| anyhow!("The Rekor log did not sign the entry of the signature."))?;
+        let body = hashed_rekord(
+            data,
+            signature,
+            &pem_encode("CERTIFICATE", &certificate.der),
+        );
+        let payload = json!({
+            "body": base64::encode(serde_json::to_vec(&body)?),
+            "integratedTime": entry.integrated_time,
+            "logID": entry.log_id,
+            "logIndex": entry.log_index,
+        });
+        self.rekor_key
+            .verify(
+                &serde_json::to_vec(&payload)?,
+                &Signature::from_der(&base64::decode(signed_entry_timestamp)?)?,
+            )
+            .map_err(|_| anyhow!("Invalid signature of the Rekor log over the entry."))
+    }
+}
+
+/// Entry of a Signature in the Rekor Transparency Log
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RekorEntry {
+    /// URL of the log
+    pub url: String,
+
+    /// Identifier of the entry
+    pub uuid: String,
+
+    /// Index of the entry in the log
+    pub log_index: u64,
+
+    /// Unix timestamp at which the entry was added to the log
+    pub integrated_time: i64,
+
+    /// Identifier of the log, the hash of its public key
+    pub log_id: String,
+
+    /// Signature of the log over the entry, promising to include it, in Base64
+    pub signed_entry_timestamp: Option<String>,
+}
+
+/// Entry in the Replies of the Rekor API
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogEntry {
+    /// Canonical body of the entry, in Base64
+    body: String,
+
+    /// Unix timestamp at which the entry was added to the log
+    integrated_time: i64,
+
+    /// Identifier of the log
+    #[serde(rename = "logID")]
+    log_id: String,
+
+    /// Index of the entry in the log
+    log_index: u64,
+
+    /// Proofs of the log
+    #[serde(default)]
+    verification: Option<LogVerification>,
+}
+
+/// Proofs of an Entry of the Rekor Log
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogVerification {
+    /// Signature of the log over the entry, in Base64
+    #[serde(default)]
+    signed_entry_timestamp: Option<String>,
+}
+
+/// Keyless Signature of a Report
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeylessSignature {
+    /// Chain of the signing certificate issued by Fulcio, leaf first, in PEM
+    pub certificates: Vec<String>,
+
+    /// ECDSA signature of the report, DER-encoded, in Base64
+    pub signature: String,
+
+    /// Entry of the signature in the Rekor transparency log
+    pub rekor: RekorEntry,
+}
+
+impl KeylessSignature {
+    /// Signs `data` with an ephemeral key certified by the Fulcio instance at `fulcio_url` for the
+    /// identity of the OpenID Connect `token`, and records the signature in the Rekor log at
+    /// `rekor_url`.
+    #[inline]
+    pub async fn sign(
+        client: &Client,
+        data: &[u8],
+        token: &str,
+        fulcio_url: &str,
+        rekor_url: &str,
+    ) -> Result<Self> {
+        let key = SigningKey::random(&mut rand::rngs::OsRng);
+        let public_key = pem_encode("PUBLIC KEY", &public_key_info(key.verifying_key()));
+        let proof: Signature = key.sign(token_subject(token)?.as_bytes());
+        let reply = client
+            .post(format!(
+                "{}/api/v2/signingCert",
+                fulcio_url.trim_end_matches('/')
+            ))
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .json(&json!({
+                "credentials": { "oidcIdentityToken": token },
+                "publicKeyRequest": {
+                    "publicKey": { "algorithm": "ECDSA", "content": public_key },
+                    "proofOfPossession": base64::encode(proof.to_der()),
+                },
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<serde_json::Value>()
+            .await?;
+        let certificates = [
+            "signedCertificateEmbeddedSct",
+            "signedCertificateDetachedSct",
+        ]
+        .iter()
+        .find_map(|field| reply[field]["chain"]["certificates"].as_array())
+        .ok_or_else(|| anyhow!("Fulcio did not issue a certificate."))?
+        .iter()
+        .map(|certificate| certificate.as_str().map(String::from))
+        .collect::<Option<Vec<_>>>()
+        .filter(|certificates| !certificates.is_empty())
+        .ok_or_else(|| anyhow!("Invalid certificate chain issued by Fulcio."))?;
+        let signature: Signature = key.sign(data);
+        let signature = base64::encode(signature.to_der());
+        let rekor_url = rekor_url.trim_end_matches('/');
+        let entries = client
+            .post(format!("{}/api/v1/log/entries", rekor_url))
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .json(&hashed_rekord(data, &signature, &certificates[0]))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<HashMap<String, LogEntry>>()
+            .await?;
+        let (uuid, entry) = entries
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Rekor did not record the signature."))?;
+        Ok(Self {
+            certificates,
+            signature,
+            rekor: RekorEntry {
+                url: rekor_url.into(),
+                uuid,
+                log_index: entry.log_index,
+                integrated_time: entry.integrated_time,
+                log_id: entry.log_id,
+                signed_entry_timestamp: entry
+                    .verification
+                    .and_then(|verification| verification.signed_entry_timestamp),
+            },
+        })
+    }
+
+    /// Checks that `self` is a signature of `data` against the root of trust of Sigstore, see
+    /// [`verify_with`](Self::verify_with).
+    #[inline]
+    pub fn verify(&self, data: &[u8]) -> Result<Identity> {
+        self.verify_with(data, &TrustRoot::sigstore())
+    }
+
+    /// Checks that `self` is a signature of `data` made with the key of a code signing certificate
+    /// issued by the Fulcio of `root`, while the certificate was valid, at the time the Rekor log
+    /// of `root` signed its entry, returning the certified identity of the signer.
+    #[inline]
+    pub fn verify_with(&self, data: &[u8], root: &TrustRoot) -> Result<Identity> {
+        let certificates = self
+            .certificates
+            .iter()
+            .map(|certificate| Certificate::from_pem(certificate))
+            .collect::<Result<Vec<_>>>()?;
+        let (certificate, presented) = certificates
+            .split_first()
+            .ok_or_else(|| anyhow!("Missing signing certificate."))?;
+        let identity = certificate
+            .identity
+            .clone()
+            .ok_or_else(|| anyhow!("The certificate does not name its subject."))?;
+        ensure!(
+            !certificate.is_ca && certificate.code_signing,
+            "The certificate of {} is not a code signing certificate.",
+            identity
+        );
+        ensure!(
+            certificate
+                .key
+                .verify(ECDSA_SHA256_OID, data, &base64::decode(&self.signature)?),
+            "Invalid signature for {}.",
+            identity
+        );
+        root.check_entry(&self.rekor, data, &self.signature, certificate)?;
+        ensure!(
+            certificate.is_valid_at(self.rekor.integrated_time),
+            "The signature was logged while the certificate of {} was not valid.",
+            identity
+        );
+        root.check_chain(certificate, presented, self.rekor.integrated_time)?;
+        Ok(identity)
+    }
+
+    /// Looks up the entry of `self` in the public Rekor log, checking that it records the
+    /// signature of `data` at the time and index of `self`.
+    #[inline]
+    pub async fn check_log(&self, client: &Client, data: &[u8]) -> Result {
+        let entry = fetch_entry(client, DEFAULT_REKOR_URL, &self.rekor.uuid).await?;
+        let body = serde_json::from_slice::<serde_json::Value>(&base64::decode(&entry.body)?)?;
+        let expected = hashed_rekord(data, &self.signature, &self.certificates[0]);
+        ensure!(
+            body["spec"] == expected["spec"]
+                && entry.log_index == self.rekor.log_index
+                && entry.integrated_time == self.rekor.integrated_time,
+            "The entry of the Rekor log does not match the signature."
+        );
+        Ok(())
+    }
+
+    /// Fetches the keyless signature recorded in the entry `uuid` of the Rekor log at `rekor_url`,
+    /// for signers who only published the entry.
+    #[inline]
+    pub async fn fetch(client: &Client, rekor_url: &str, uuid: &str) -> Result<Self> {
+        let rekor_url = rekor_url.trim_end_matches('/');
+        let entry = fetch_entry(client, rekor_url, uuid).await?;
+        let body = serde_json::from_slice::<serde_json::Value>(&base64::decode(&entry.body)?)?;
+        ensure!(
+            body["kind"] == "hashedrekord",
+            "The entry {} of the Rekor log is not the signature of a report.",
+            uuid
+        );
+        let signature = &body["spec"]["signature"];
+        let (signature, certificate) = match (
+            signature["content"].as_str(),
+            signature["publicKey"]["content"].as_str(),
+        ) {
+            (Some(signature), Some(certificate)) => (signature, certificate),
+            _ => bail!("Invalid signature in the entry {} of the Rekor log.", uuid),
+        };
+        Ok(Self {
+            certificates: vec![String::from_utf8(base64::decode(certificate)?)?],
+            signature: signature.into(),
+            rekor: RekorEntry {
+                url: rekor_url.into(),
+                uuid: uuid.into(),
+                log_index: entry.log_index,
+                integrated_time: entry.integrated_time,
+                log_id: entry.log_id,
+                signed_entry_timestamp: entry
+                    .verification
+                    .and_then(|verification| verification.signed_entry_timestamp),
+            },
+        })
+    }
+
+    /// Loads the keyless signature saved as JSON at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+/// Fetches the entry `uuid` of the Rekor log at `rekor_url`.
+#[inline]
+async fn fetch_entry(client: &Client, rekor_url: &str, uuid: &str) -> Result<LogEntry> {
+    client
+        .get(format!("{}/api/v1/log/entries/{}", rekor_url, uuid))
+        .header(USER_AGENT_HEADER, USER_AGENT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<HashMap<String, LogEntry>>()
+        .await?
+        .into_values()
+        .next()
+        .ok_or_else(|| anyhow!("The entry {} is not in the Rekor log.", uuid))
+}
+
+/// Returns the `hashedrekord` entry of the signature of `data`, in Base64, made with the key of
+/// `certificate`, in PEM.
+#[inline]
+fn hashed_rekord(data: &[u8], signature: &str, certificate: &str) -> serde_json::Value {
+    json!({
+        "apiVersion": "0.0.1",
+        "kind": "hashedrekord",
+        "spec": {
+            "data": {
+                "hash": { "algorithm": "sha256", "value": hex::encode(Sha256::digest(data)) },
+            },
+            "signature": {
+                "content": signature,
+                "publicKey": { "content": base64::encode(certificate) },
+            },
+        },
+    })
+}
+
+/// Claims of an Identity Token
+#[derive(Deserialize)]
+struct Claims {
+    /// Subject of the token
+    sub: String,
+
+    /// Email of the subject, if the token holds one
+    #[serde(default)]
+    email: Option<String>,
+}
+
+/// Returns the subject of the OpenID Connect `token` which Fulcio expects to be signed to prove
+/// the possession of the key: its email if it has one, its subject otherwise.
+#[inline]
+fn token_subject(token: &str) -> Result<String> {
+    let claims = token
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| anyhow!("Invalid identity token."))?;
+    let claims = serde_json::from_slice::<Claims>(&base64::decode_config(
+        claims.trim_end_matches('='),
+        base64::URL_SAFE_NO_PAD,
+    )?)?;
+    Ok(claims.email.unwrap_or(claims.sub))
+}
+
+/// Returns the OpenID Connect identity token to sign with: `token` if given, otherwise the value
+/// of the [`TOKEN_VARIABLE`] environment variable, otherwise a token of the GitHub Actions workflow
+/// running the command, if it was given the `id-token: write` permission.
+#[inline]
+pub async fn identity_token(client: &Client, token: Option<String>) -> Result<String> {
+    if let Some(token) = token.or_else(|| std::env::var(TOKEN_VARIABLE).ok()) {
+        return Ok(token);
+    }
+    match (
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_URL"),
+        std::env::var("ACTIONS_ID_TOKEN_REQUEST_TOKEN"),
+    ) {
+        (Ok(url), Ok(request_token)) => {
+            #[derive(Deserialize)]
+            struct Reply {
+                value: String,
+            }
+            Ok(client
+                .get(url)
+                .query(&[("audience", AUDIENCE)])
+                .header(USER_AGENT_HEADER, USER_AGENT)
+                .bearer_auth(request_token)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Reply>()
+                .await?
+                .value)
+        }
+        _ => bail!(
+            "Keyless signing requires an OpenID Connect identity token, see `--identity-token`"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Root certificate of the certificate authority of the test vectors
+    const TEST_ROOT: &str = "-----BEGIN CERTIFICATE-----
+MIIBsjCCATegAwIBAgIBATAKBggqhkjOPQQDAzAxMRswGQYDVQQKDBJwcG90LXZl
+cmlmaWVyLnRlc3QxEjAQBgNVBAMMCXRlc3Qtcm9vdDAeFw0yNjAxMDEwMDAwMDBa
+Fw0zNjAxMDEwMDAwMDBaMDExGzAZBgNVBAoMEnBwb3QtdmVyaWZpZXIudGVzdDES
+MBAGA1UEAwwJdGVzdC1yb290MHYwEAYHKoZIzj0CAQYFK4EEACIDYgAEeunh2xFg
+eUxwwe8HG+UZH2xmJA9GCPvopE7NNsPtXbl1APLk2E4/tFhQ/W9DAzAr2KwhREzY
+DSQVONgDSzmaDe42NutcsAuUmdBhpXHbDxS7R+15RTlI7WPzpsy79udPoyMwITAP
+BgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIBBjAKBggqhkjOPQQDAwNpADBm
+AjEA9sFgPAH/WEYV+EdFf2WrEHHq6chXwbaE51bAWNDADLccbOv7ad3iHZlL0h3J
+dMonAjEAuUOQnfIWUgHoNy1UXbLA9kcH6ifx7l1w3pNjIpABNinhqCBTCLozlN9q
+egSzymHs
+-----END CERTIFICATE-----
+";
+
+    /// Intermediate certificate issued by [`TEST_ROOT`]
+    const TEST_INTERMEDIATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBuzCCAUKgAwIBAgIBAjAKBggqhkjOPQQDAzAxMRswGQYDVQQKDBJwcG90LXZl
+cmlmaWVyLnRlc3QxEjAQBgNVBAMMCXRlc3Qtcm9vdDAeFw0yNjAxMDEwMDAwMDBa
+Fw0zNjAxMDEwMDAwMDBaMDkxGzAZBgNVBAoMEnBwb3QtdmVyaWZpZXIudGVzdDEa
+MBgGA1UEAwwRdGVzdC1pbnRlcm1lZGlhdGUwdjAQBgcqhkjOPQIBBgUrgQQAIgNi
+AASFaKNah5sNUGEymTxtkR0xNqcxQJq4xdJv16AegUJSgUU6bGrfTNLzjHelBCgV
+6kjoWsnN7tlcaZynmbnHMLEg85RUpTc+ofbGTEMZVTo4mij0a3pGF86HsmveF2lX
+OgmjJjAkMBIGA1UdEwEB/wQIMAYBAf8CAQAwDgYDVR0PAQH/BAQDAgEGMAoGCCqG
+SM49BAMDA2cAMGQCMGM8IB880msDx+DlMdeIneSOf/iKrPrDbDM5wcSdrvCu/V4b
+Wg+9pgec1Pc6MJgbxAIwWuTQNVH9yyqvbCCiWnQAa3Jx+u9NYoALhOge4qVJwEMI
+PVFscV/OZvmM/hAKlUOw
+-----END CERTIFICATE-----
+";
+
+    /// Certificate for `verifier@example.org`, authenticated by GitHub, issued by
+    /// [`TEST_INTERMEDIATE`] for ten minutes
+    const CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBwzCCAUmgAwIBAgIBAzAKBggqhkjOPQQDAzA5MRswGQYDVQQKDBJwcG90LXZl
+cmlmaWVyLnRlc3QxGjAYBgNVBAMMEXRlc3QtaW50ZXJtZWRpYXRlMB4XDTI2MTAx
+NzEwMDAwMFoXDTI2MTAxNzEwMTAwMFowADBZMBMGByqGSM49AgEGCCqGSM49AwEH
+A0IABH9+rij00QsnSJZ+pOTaGansyTragBOso58OKgnwbic+Ts81bAYir2N6oa7O
+XKQJFPcgscvtiK/B+9FSM5RBlXyjezB5MCIGA1UdEQEB/wQYMBaBFHZlcmlmaWVy
+QGV4YW1wbGUub3JnMA4GA1UdDwEB/wQEAwIHgDATBgNVHSUEDDAKBggrBgEFBQcD
+AzAuBgorBgEEAYO/MAEIBCAMHmh0dHBzOi8vZ2l0aHViLmNvbS9sb2dpbi9vYXV0
+aDAKBggqhkjOPQQDAwNoADBlAjEA8W/xCCpAKMhGtnCsbA3ax/odfqSMOPJYtqCh
+SbIdc7Qo86hDC4cXT7Jx1iSBHHK0AjBj7auzI6WOLkw3wr+p5tv4nDkXGdRVepxX
+C6ZrfRd9MNTGaoBIBnOOEQeAVHTweU4=
+-----END CERTIFICATE-----
+";
+
+    /// Self-signed certificate with the identity and the key of [`CERTIFICATE`]
+    const SELF_SIGNED_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBajCCARCgAwIBAgIBAzAKBggqhkjOPQQDAjAAMB4XDTI2MTAxNzEwMDAwMFoX
+DTI2MTAxNzEwMTAwMFowADBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABH9+rij0
+0QsnSJZ+pOTaGansyTragBOso58OKgnwbic+Ts81bAYir2N6oa7OXKQJFPcgscvt
+iK/B+9FSM5RBlXyjezB5MCIGA1UdEQEB/wQYMBaBFHZlcmlmaWVyQGV4YW1wbGUu
+b3JnMA4GA1UdDwEB/wQEAwIHgDATBgNVHSUEDDAKBggrBgEFBQcDAzAuBgorBgEE
+AYO/MAEIBCAMHmh0dHBzOi8vZ2l0aHViLmNvbS9sb2dpbi9vYXV0aDAKBggqhkjO
+PQQDAgNIADBFAiEAnhgDlQknnnfDboThmJR85D2TkQw6Bo7jf9AgRwO894cCID/p
+UqAt7HFrnoCiN0bvnnq9BcO8eyDUWuq2yDDbF2JA
+-----END CERTIFICATE-----
+";
+
+    /// Public key of the Rekor log of the test vectors
+    const TEST_REKOR_KEY: &str = "-----BEGIN PUBLIC KEY-----
+MFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE7PJpWDKH+cIM7VuzWPAAXylG6Jyn
+0BFc9IZ+q+xOMYXp5mxEb1hVDtLozgsJOZnM8ecA3w4lHooxTkKBL9NCxw==
+-----END PUBLIC KEY-----
+";
+
+    /// Secret key certified by [`CERTIFICATE`]
+    const SECRET_KEY: &str = "31ce1ab2f522a9046d14b4a15841eb7e1c886f00656567e043879b0e7505de7d";
+
+    /// Report signed in the test vectors
+    const REPORT: &[u8] = b"{\"log_powers\":19,\"rounds\":[],\"version\":1}\n";
+
+    /// Returns the root of trust of the test vectors.
+    fn test_root() -> TrustRoot {
+        TrustRoot::new(
+            &[TEST_ROOT],
+            &[],
+            TEST_REKOR_KEY,
+            "https://rekor.example.org",
+        )
+        .unwrap()
+    }
+
+    /// Returns the keyless signature of [`REPORT`] with `certificates`, logged at `log_index`
+    /// with `signed_entry_timestamp` by the Rekor log of the test vectors.
+    fn signature(
+        certificates: &[&str],
+        log_index: u64,
+        signed_entry_timestamp: &str,
+    ) -> KeylessSignature {
+        KeylessSignature {
+            certificates: certificates.iter().map(|certificate| certificate.to_string()).collect(),
+            signature: "MEYCIQCPflqZ1NOzKKEQqtqaaDiOShHLOw0H/5KF2vNXhIV3IQIhAI/kesTy/5Nwv3fqV/TU9Eh7JQcwCXnYjH5sNdPedzFI".into(),
+            rekor: RekorEntry {
+                url: "https://rekor.example.org".into(),
+                uuid: "24296fb24b8ad77a".into(),
+                log_index,
+                integrated_time: 1_792_231_260,
+                log_id: "6343dca48d3fe24baf9b3fdd20a6c62e644696135189dc3e6785198e75900bff".into(),
+                signed_entry_timestamp: Some(signed_entry_timestamp.into()),
+            },
+        }
+    }
+
+    /// Checks that signatures are verified against the chain of their certificate and the signed
+    /// time of their entry in the log, and that the identity is read from the certificate.
+    #[test]
+    fn keyless_signatures_verify() {
+        let root = test_root();
+        let signed = signature(
+            &[CERTIFICATE, TEST_INTERMEDIATE],
+            7,
+            "MEUCIQCbh30e9hCw1HY55mB1pt6GN5tSKk3JBtjAPlrg2ZZ7wAIgWHqFoQcjZq9q1Si6I3VBw6OTaZ8kFNkCszgxDvDg0Lg=",
+        );
+        assert_eq!(
+            signed.verify_with(REPORT, &root).unwrap(),
+            Identity {
+                subject: "verifier@example.org".into(),
+                issuer: Some("https://github.com/login/oauth".into()),
+            }
+        );
+        assert!(signed.verify_with(b"another report", &root).is_err());
+        assert!(signed.verify(REPORT).is_err());
+        let mut unchained = signed.clone();
+        unchained.certificates.pop();
+        assert!(unchained.verify_with(REPORT, &root).is_err());
+        let mut moved = signed.clone();
+        moved.rekor.integrated_time += 3600;
+        assert!(moved.verify_with(REPORT, &root).is_err());
+        let mut elsewhere = signed.clone();
+        elsewhere.rekor.url = DEFAULT_REKOR_URL.into();
+        assert!(elsewhere.verify_with(REPORT, &root).is_err());
+        let mut unsigned = signed;
+        unsigned.rekor.signed_entry_timestamp = None;
+        assert!(unsigned.verify_with(REPORT, &root).is_err());
+        let key = SigningKey::from_slice(&hex::decode(SECRET_KEY).unwrap()).unwrap();
+        let public_key_info = public_key_info(key.verifying_key());
+        assert_eq!(
+            pem_decode(&pem_encode("PUBLIC KEY", &public_key_info)).unwrap(),
+            public_key_info
+        );
+        assert!(matches!(
+            Certificate::from_pem(CERTIFICATE).unwrap().key,
+            CertifiedKey::P256(certified) if &certified == key.verifying_key()
+        ));
+        let token = format!(
+            "e30.{}.c2ln",
+            base64::encode_config(
+                "{\"sub\":\"1234\",\"email\":\"verifier@example.org\"}",
+                base64::URL_SAFE_NO_PAD
+            )
+        );
+        assert_eq!(token_subject(&token).unwrap(), "verifier@example.org");
+        let identity = Identity {
+            subject: "verifier@example.org".into(),
+            issuer: Some("https://github.com/login/oauth".into()),
+        };
+        assert_eq!(identity.to_string().parse::<Identity>().unwrap(), identity);
+        assert!("verifier@example.org".parse::<Identity>().is_err());
+    }
+
+    /// Checks that a self-signed certificate is rejected even with a valid signature logged by
+    /// the trusted log.
+    #[test]
+    fn self_signed_certificates_fail() {
+        let signed = signature(
+            &[SELF_SIGNED_CERTIFICATE],
+            8,
+            "MEYCIQC9Uo2XYk4C1S9HTlgE0iqW1MzmAg9Pe7mZ4HyoJ1U52QIhALVBuCmfvMPc62lW4u+1ZduwnwUYba5F1PvYceVMfMaZ",
+        );
+        let err = signed.verify_with(REPORT, &test_root()).unwrap_err();
+        assert!(err.to_string().contains("not issued by Fulcio"), "{}", err);
+        assert!(signed.verify(REPORT).is_err());
+    }
+
+    /// Checks that the pinned intermediate certificate of Fulcio chains up to its pinned root and
+    /// that the pinned key of Rekor has the identifier of the public log.
+    #[test]
+    fn sigstore_root_of_trust() {
+        let root = TrustRoot::sigstore();
+        let intermediate = &root.intermediates[0];
+        assert!(intermediate.is_ca && intermediate.is_issued_by(&root.roots[0]));
+        root.check_chain(intermediate, &[], intermediate.not_before)
+            .unwrap();
+        assert_eq!(
+            root.rekor_log_id,
+            "c0d23d6ad406973f9559f3ba2d1ca01f84147d8ffc5b8445c224f98b9591801d"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Accumulator Snapshots
+//!
+//! Reading a subaccumulator from a challenge file decompresses and checks every one of its points,
+//! which is repeated on every run over the same files. Once a subaccumulator has been read, its
+//! points are saved uncompressed to a snapshot in the cache directory, keyed by the Blake2b hash of
+//! the challenge file and the number of powers, and later runs load them back without the checks.
+//!
+//! The hashes are looked up in the [`HashCache`], so snapshots are only used for files hashed by
+//! the `hasher` binary and are ignored as soon as the file changes.
+
+use crate::{
+    atomic,
+    cache::{HashCache, HASH_CACHE_PATH},
+    storage::StorageOptions,
+    HashAlgorithm, Result,
+};
+use anyhow::anyhow;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tracing::{debug, warn};
+
+/// Name of the directory holding the snapshots in the cache directory
+pub const SNAPSHOT_DIR: &str = "accumulators";
+
+/// Magic bytes starting every snapshot, bumped whenever the format changes
+const MAGIC: &[u8; 8] = b"PPOTACC1";
+
+/// Snapshot Cache
+#[derive(Debug)]
+pub struct SnapshotCache {
+    /// Directory holding the snapshots
+    dir: PathBuf,
+
+    /// Hashes of the challenge files
+    hashes: HashCache,
+}
+
+impl SnapshotCache {
+    /// Builds a [`SnapshotCache`] in `dir` looking up the hashes of the challenge files in
+    /// `hashes`.
+    #[inline]
+    pub fn new<P>(dir: P, hashes: HashCache) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            dir: dir.into(),
+            hashes,
+        }
+    }
+
+    /// Opens the [`SnapshotCache`] in the cache directory of `storage`.
+    #[inline]
+    pub fn open(storage: &StorageOptions) -> Result<Self> {
+        let dir = storage.cache_path(SNAPSHOT_DIR);
+        fs::create_dir_all(&dir)?;
+        Ok(Self::new(
+            dir,
+            HashCache::load_or_default(storage.cache_path(HASH_CACHE_PATH))?,
+        ))
+    }
+
+    /// Returns the path of the snapshot of the subaccumulator with `powers` powers read from the
+    /// file at `path`, or `None` if the hash of the file is not known in its current state.
+    #[inline]
+    pub fn snapshot_path<P>(&self, path: P, powers: usize) -> Result<Option<PathBuf>>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(self
+            .hashes
+            .get(path, HashAlgorithm::Blake2b)?
+            .map(|hash| self.dir.join(format!("{}_{}", hex::encode(hash), powers))))
+    }
+
+    /// Loads the snapshot of the subaccumulator with `powers` powers read from the file at `path`,
+    /// returning `None` if there is none.
+    #[inline]
+    pub fn load<T, P>(&self, path: P, powers: usize) -> Result<Option<T>>
+    where
+        T: CanonicalDeserialize,
+        P: AsRef<Path>,
+    {
+        let snapshot_path = match self.snapshot_path(path, powers)? {
+            Some(snapshot_path) => snapshot_path,
+            _ => return Ok(None),
+        };
+        let bytes = match fs::read(&snapshot_path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        match bytes.strip_prefix(MAGIC) {
+            Some(mut points) => match T::deserialize_unchecked(&mut points) {
+                Ok(value) => return Ok(Some(value)),
+                Err(err) => warn!("Ignoring invalid snapshot {:?}: {}", snapshot_path, err),
+            },
+            _ => warn!("Ignoring snapshot {:?} in an unknown format", snapshot_path),
+        }
+        Ok(None)
+    }
+
+    /// Saves `value` as the snapshot of the subaccumulator with `powers` powers read from the file
+    /// at `path`, returning `false` if the hash of the file is not known.
+    #[inline]
+    pub fn save<T, P>(&self, path: P, powers: usize, value: &T) -> Result<bool>
+    where
+        T: CanonicalSerialize,
+        P: AsRef<Path>,
+    {
+        let snapshot_path = match self.snapshot_path(path, powers)? {
+            Some(snapshot_path) => snapshot_path,
+            _ => return Ok(false),
+        };
+        let mut bytes = MAGIC.to_vec();
+        value
+            .serialize_unchecked(&mut bytes)
+            .map_err(|err| anyhow!("Unable to serialize the snapshot: {}", err))?;
+        atomic::write(snapshot_path, bytes)?;
+        Ok(true)
+    }
+
+    /// Loads the snapshot of the subaccumulator with `powers` powers read from the file at `path`,
+    /// or reads it with `read` and saves its snapshot for the next runs.
+    #[inline]
+    pub fn load_or_else<T, P, F>(&self, path: P, powers: usize, read: F) -> Result<T>
+    where
+        T: CanonicalSerialize + CanonicalDeserialize,
+        P: AsRef<Path>,
+        F: FnOnce() -> Result<T>,
+    {
+        let path = path.as_ref();
+        if let Some(value) = self.load(path, powers)? {
+            debug!("Loaded the subaccumulator of {:?} from its snapshot", path);
+            return Ok(value);
+        }
+        let value = read()?;
+        match self.save(path, powers, &value) {
+            Ok(true) => debug!("Saved a snapshot of the subaccumulator of {:?}", path),
+            Ok(false) => debug!("Not saving a snapshot of {:?} until it is hashed", path),
+            Err(err) => warn!("Unable to save a snapshot of {:?}: {}", path, err),
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that snapshots are only used while the file keeps the hash they were saved for.
+    #[test]
+    fn snapshots_follow_the_file() {
+        let dir = std::env::temp_dir().join("ppot-verifier-snapshot-test");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("challenge_0001");
+        fs::write(&path, b"contents").unwrap();
+        let mut hashes = HashCache::default();
+        hashes
+            .insert(&path, HashAlgorithm::Blake2b, &[7; 64])
+            .unwrap();
+        let cache = SnapshotCache::new(&dir, hashes);
+        let points = vec![1u64, 2, 3];
+        assert_eq!(cache.load::<Vec<u64>, _>(&path, 4).unwrap(), None);
+        let loaded = cache.load_or_else(&path, 4, || Ok(points.clone())).unwrap();
+        assert_eq!(loaded, points);
+        let cached: Vec<u64> = cache
+            .load_or_else(&path, 4, || Err(anyhow!("the snapshot is used")))
+            .unwrap();
+        assert_eq!(cached, points);
+        assert_eq!(cache.load::<Vec<u64>, _>(&path, 8).unwrap(), None);
+        fs::write(&path, b"new contents").unwrap();
+        assert_eq!(cache.load::<Vec<u64>, _>(&path, 4).unwrap(), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,791 @@
+//! Pipeline Status
+//!
+//! The binaries do not talk to each other, they leave their progress in the storage and state
+//! directories: the `downloader` holds the [`lock`](crate::lock) of the files it writes to and the
+//! `hasher` holds the lock of the hash cache, while the results of every stage are recorded in the
+//! [`StateDb`]. A [`Status`] is collected from these files, so it reflects the state of processes
+//! running at the same time. Hash files and the [`Checkpoint`] of verifications made before the
+//! database existed are read as well.
+
+use crate::{
+    cache::HASH_CACHE_PATH,
+    checkpoint::{Checkpoint, CHECKPOINT_PATH},
+    db::{ChainStatus, RoundRecord, StateDb},
+    hash::Hash64,
+    lock::FileLock,
+    pgp::{SignatureCheck, SignatureStatus},
+    profile::RoundProfile,
+    registry::{Registry, RemoteFile, REGISTRY_PATH},
+    storage::StorageOptions,
+    HashAlgorithm, Result,
+};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// File State
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct FileState {
+    /// Local file name
+    pub path: String,
+
+    /// Size of the file on disk, if it exists
+    pub size: Option<u64>,
+
+    /// Size of the file recorded in the registry
+    pub expected_size: Option<u64>,
+
+    /// `true` if another process is writing to the file
+    pub downloading: bool,
+
+    /// Blake2b hash of the file, if it has been hashed
+    pub blake2b: Option<Hash64>,
+}
+
+impl FileState {
+    /// Collects the state of the registry `file` in the storage directory of `storage`, recorded
+    /// in `db`.
+    #[inline]
+    pub fn collect(storage: &StorageOptions, db: &StateDb, file: &RemoteFile) -> Result<Self> {
+        let path = storage.path(&file.path);
+        let size = fs::metadata(&path).ok().map(|metadata| metadata.len());
+        let blake2b = match db.hash(&file.path, HashAlgorithm::Blake2b)? {
+            Some(hash) => Some(Hash64::try_from(hash.as_slice())?),
+            _ => Hash64::load(storage.hash_path(&path, HashAlgorithm::Blake2b)).ok(),
+        };
+        Ok(Self {
+            path: file.path.clone(),
+            size,
+            expected_size: match file.size {
+                Some(size) => Some(size),
+                _ => db.downloaded_size(&file.path)?,
+            },
+            downloading: size.is_some() && FileLock::is_locked(&path)?,
+            blake2b,
+        })
+    }
+
+    /// Returns a short label of the download state of the file: `missing`, `downloading`,
+    /// `partial` or `complete`.
+    #[inline]
+    pub fn download_label(&self) -> &'static str {
+        if self.size.is_none() {
+            "missing"
+        } else if self.downloading {
+            "downloading"
+        } else if self.is_downloaded() {
+            "complete"
+        } else {
+            "partial"
+        }
+    }
+
+    /// Returns `true` if the file is completely downloaded, as far as its expected size tells.
+    #[inline]
+    pub fn is_downloaded(&self) -> bool {
+        !self.downloading
+            && match (self.size, self.expected_size) {
+                (Some(size), Some(expected_size)) => size == expected_size,
+                (size, _) => size.is_some(),
+            }
+    }
+
+    /// Returns the line of the file in a transcript in the format of `b2sum`, if it is hashed.
+    #[inline]
+    pub fn transcript_line(&self) -> Option<String> {
+        self.blake2b
+            .as_ref()
+            .map(|hash| format!("{}  {}\n", hash, self.path))
+    }
+}
+
+/// Round Verification
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verification {
+    /// The round has not been verified yet
+    Pending,
+
+    /// The round has been verified
+    Verified,
+
+    /// The verification of the round failed
+    Failed,
+}
+
+impl Verification {
+    /// Returns the name of `self`.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Verified => "verified",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// Round State
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RoundState {
+    /// Index of the round
+    pub round: usize,
+
+    /// Challenge file the round starts from, response file and challenge file it produces
+    pub files: Vec<FileState>,
+
+    /// Status of the links of the hash chain between the files of the round, if they were checked
+    pub chain: Option<ChainStatus>,
+
+    /// Verification of the round
+    pub verification: Verification,
+
+    /// Breakdown of the time spent verifying the round, if it was profiled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<RoundProfile>,
+
+    /// Check of the PGP signatures of the attestation of the contribution of the round, if it was
+    /// checked by `ppot attestations`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureCheck>,
+
+    /// Verification of the first `2^log_powers` powers of the round, by `log_powers`, for every
+    /// power level `verify_ppot` was asked for with `--levels`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub levels: BTreeMap<u32, Verification>,
+}
+
+impl RoundState {
+    /// Renders the Blake2b hash of every hashed file of the round in the format of `b2sum`.
+    #[inline]
+    pub fn transcript(&self) -> String {
+        self.files
+            .iter()
+            .filter_map(FileState::transcript_line)
+            .collect()
+    }
+}
+
+/// Pipeline Status
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Status {
+    /// Number of files in the registry
+    pub files: usize,
+
+    /// Number of files completely downloaded
+    pub downloaded: usize,
+
+    /// Number of files being downloaded
+    pub downloading: usize,
+
+    /// Number of files with a Blake2b hash
+    pub hashed: usize,
+
+    /// `true` if the `hasher` is running
+    pub hashing: bool,
+
+    /// Number of rounds to verify
+    pub rounds: usize,
+
+    /// Number of rounds verified successfully
+    pub verified: usize,
+
+    /// Rounds whose verification failed
+    pub failed_rounds: Vec<usize>,
+
+    /// Base-two logarithm of the number of powers verified in each round, if any was verified
+    pub log_powers: Option<u32>,
+}
+
+/// Pipeline Report
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Report {
+    /// Overall status
+    pub status: Status,
+
+    /// State of every round
+    pub rounds: Vec<RoundState>,
+}
+
+impl Report {
+    /// Collects the report of the pipeline working in the directories of `storage`.
+    #[inline]
+    pub fn collect(storage: &StorageOptions) -> Result<Self> {
+        let registry = Registry::load_or_builtin(storage.state_path(REGISTRY_PATH))?;
+        let checkpoint = Checkpoint::load(storage.state_path(CHECKPOINT_PATH))?;
+        let db = StateDb::open_in(storage)?;
+        let records = db.rounds()?;
+        let profiles = db.profiles()?;
+        let mut attestations = db.attestations()?;
+        let mut levels = db.levels()?;
+        let challenges = registry
+            .challenges
+            .iter()
+            .map(|file| FileState::collect(storage, &db, file))
+            .collect::<Result<Vec<_>>>()?;
+        let responses = registry
+            .responses
+            .iter()
+            .map(|file| FileState::collect(storage, &db, file))
+            .collect::<Result<Vec<_>>>()?;
+        let num_rounds = registry.rounds();
+        let rounds = (1..num_rounds)
+            .map(|round| {
+                Ok(RoundState {
+                    round,
+                    files: [
+                        challenges.get(round),
+                        responses.get(round),
+                        challenges.get(round + 1),
+                    ]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+                    chain: chain(&db, &registry, round)?,
+                    verification: verification(&records, checkpoint.as_ref(), round),
+                    profile: profiles.get(&round).copied(),
+                    // The response of round `r` is the one of contribution `r + 1`
+                    signature: attestations.remove(&(round + 1)),
+                    levels: levels
+                        .remove(&round)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|(log_powers, verified)| {
+                            (
+                                log_powers,
+                                if verified {
+                                    Verification::Verified
+                                } else {
+                                    Verification::Failed
+                                },
+                            )
+                        })
+                        .collect(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let files = challenges.iter().chain(&responses);
+        let status = Status {
+            files: challenges.len() + responses.len(),
+            downloaded: files.clone().filter(|file| file.is_downloaded()).count(),
+            downloading: files.clone().filter(|file| file.downloading).count(),
+            hashed: files.filter(|file| file.blake2b.is_some()).count(),
+            hashing: FileLock::is_locked(storage.cache_path(HASH_CACHE_PATH))?,
+            rounds: rounds.len(),
+            verified: rounds
+                .iter()
+                .filter(|round| round.verification == Verification::Verified)
+                .count(),
+            failed_rounds: rounds
+                .iter()
+                .filter(|round| round.verification == Verification::Failed)
+                .map(|round| round.round)
+                .collect(),
+            log_powers: records
+                .last()
+                .map(|record| record.log_powers)
+                .or_else(|| checkpoint.map(|checkpoint| checkpoint.log_powers)),
+        };
+        Ok(Self { status, rounds })
+    }
+
+    /// Returns the state of `round`, if it is in the report.
+    #[inline]
+    pub fn round(&self, round: usize) -> Option<&RoundState> {
+        self.rounds.iter().find(|state| state.round == round)
+    }
+
+    /// Renders the report as a Markdown document, with a table of the rounds.
+    #[inline]
+    pub fn markdown(&self) -> String {
+        let status = &self.status;
+        let mut markdown = String::from("# Perpetual Powers of Tau Verification Report\n\n");
+        markdown.push_str(&format!(
+            "- {}/{} rounds verified, {} failed{}\n- {}/{} files downloaded and {} hashed\n",
+            status.verified,
+            status.rounds,
+            status.failed_rounds.len(),
+            match status.log_powers {
+                Some(log_powers) => format!(", on 2^{} powers of tau", log_powers),
+                _ => String::new(),
+            },
+            status.downloaded,
+            status.files,
+            status.hashed,
+        ));
+        markdown.push_str("\n| Round | Response | Hash Chain | Verification |\n");
+        markdown.push_str("|------:|----------|------------|--------------|\n");
+        for round in &self.rounds {
+            markdown.push_str(&format!(
+                "| {} | `{}` | {} | {} |\n",
+                round.round,
+                round.files.get(1).map_or("", |file| file.path.as_str()),
+                round.chain.map_or("unchecked", |status| status.name()),
+                match round.verification {
+                    Verification::Pending => "pending",
+                    Verification::Verified => "verified",
+                    Verification::Failed => "**FAILED**",
+                },
+            ));
+        }
+        let signed = self
+            .rounds
+            .iter()
+            .filter_map(|round| Some((round.round, round.signature.as_ref()?)))
+            .collect::<Vec<_>>();
+        if !signed.is_empty() {
+            markdown.push_str("\n## Attestation Signatures\n\n");
+            markdown.push_str("| Round | Signature | Signer |\n");
+            markdown.push_str("|------:|-----------|--------|\n");
+            for (round, signature) in signed {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    round,
+                    match signature.status {
+                        SignatureStatus::Invalid => "**INVALID**",
+                        status => status.name(),
+                    },
+                    signature
+                        .signer
+                        .as_ref()
+                        .map_or(String::new(), |signer| format!("`{}`", signer)),
+                ));
+            }
+        }
+        let mut levels = BTreeMap::<u32, (usize, Vec<usize>)>::new();
+        for round in &self.rounds {
+            for (log_powers, verification) in &round.levels {
+                let (verified, failed) = levels.entry(*log_powers).or_default();
+                match verification {
+                    Verification::Verified => *verified += 1,
+                    Verification::Failed => failed.push(round.round),
+                    Verification::Pending => {}
+                }
+            }
+        }
+        if !levels.is_empty() {
+            markdown.push_str("\n## Power Levels\n\n");
+            markdown.push_str("| Powers | Verified Rounds | Failed Rounds |\n");
+            markdown.push_str("|-------:|----------------:|---------------|\n");
+            for (log_powers, (verified, failed)) in levels {
+                markdown.push_str(&format!(
+                    "| 2^{} | {} | {} |\n",
+                    log_powers,
+                    verified,
+                    failed
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+        }
+        let profiled = self
+            .rounds
+            .iter()
+            .filter_map(|round| Some((round.round, round.profile?)))
+            .collect::<Vec<_>>();
+        if !profiled.is_empty() {
+            markdown.push_str("\n## Performance\n\n");
+            markdown.push_str(
+                "| Round | Total (ms) | IO (ms) | Deserialization (ms) | Subgroup Checks (ms) \
+                 | Multi-Exponentiations (ms) | Pairings (ms) | Bytes Read |\n",
+            );
+            markdown.push_str("|------:|---:|---:|---:|---:|---:|---:|---:|\n");
+            for (round, profile) in profiled {
+                markdown.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} | {} | {} |\n",
+                    round,
+                    profile.total_ms,
+                    profile.io_ms,
+                    profile.deserialization_ms,
+                    profile.subgroup_check_ms,
+                    profile.multiexp_ms,
+                    profile.pairing_ms,
+                    profile.bytes_read,
+                ));
+            }
+        }
+        markdown
+    }
+
+    /// Loads a report saved as JSON at `path`, such as the output of `ppot status --json`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    /// Compares `self` with the report of another verifier, `theirs`, round by round, returning
+    /// the rounds missing from either report and the hashes, hash chain links and verifications
+    /// on which they disagree. Files not yet hashed, links not yet checked and rounds not yet
+    /// verified by either side are not discrepancies.
+    #[inline]
+    pub fn diff(&self, theirs: &Report) -> Vec<Discrepancy> {
+        let mut discrepancies = Vec::new();
+        for round in &theirs.rounds {
+            if self.round(round.round).is_none() {
+                discrepancies.push(Discrepancy::MissingRound {
+                    round: round.round,
+                    side: Side::Mine,
+                });
+            }
+        }
+        for mine in &self.rounds {
+            let round = mine.round;
+            let theirs = match theirs.round(round) {
+                Some(theirs) => theirs,
+                _ => {
+                    discrepancies.push(Discrepancy::MissingRound {
+                        round,
+                        side: Side::Theirs,
+                    });
+                    continue;
+                }
+            };
+            for file in &mine.files {
+                let other = theirs.files.iter().find(|other| other.path == file.path);
+                if let (Some(mine), Some(Some(theirs))) =
+                    (file.blake2b, other.map(|other| other.blake2b))
+                {
+                    if mine != theirs {
+                        discrepancies.push(Discrepancy::Hash {
+                            round,
+                            path: file.path.clone(),
+                            mine,
+                            theirs,
+                        });
+                    }
+                }
+            }
+            if let (Some(mine), Some(theirs)) = (mine.chain, theirs.chain) {
+                if mine != theirs {
+                    discrepancies.push(Discrepancy::Chain {
+                        round,
+                        mine,
+                        theirs,
+                    });
+                }
+            }
+            let (mine, theirs) = (mine.verification, theirs.verification);
+            if mine != theirs && mine != Verification::Pending && theirs != Verification::Pending {
+                discrepancies.push(Discrepancy::Verification {
+                    round,
+                    mine,
+                    theirs,
+                });
+            }
+        }
+        discrepancies
+    }
+
+    /// Renders the Blake2b hash of every hashed file in the format of `b2sum`, so that the files
+    /// can be checked with `b2sum --check`.
+    #[inline]
+    pub fn transcript(&self) -> String {
+        let mut paths = BTreeSet::new();
+        let mut transcript = String::new();
+        for file in self.rounds.iter().flat_map(|round| &round.files) {
+            if let Some(line) = file.transcript_line() {
+                if paths.insert(&file.path) {
+                    transcript.push_str(&line);
+                }
+            }
+        }
+        transcript
+    }
+}
+
+/// Side of a Report Comparison
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    /// Local report
+    Mine,
+
+    /// Report of the other verifier
+    Theirs,
+}
+
+/// Discrepancy between Two Reports
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Discrepancy {
+    /// The round is missing from one of the reports
+    MissingRound {
+        /// Missing round
+        round: usize,
+
+        /// Report missing the round
+        side: Side,
+    },
+
+    /// The reports disagree on the Blake2b hash of a file
+    Hash {
+        /// Round of the file
+        round: usize,
+
+        /// Local file name
+        path: String,
+
+        /// Local hash
+        mine: Hash64,
+
+        /// Hash of the other verifier
+        theirs: Hash64,
+    },
+
+    /// The reports disagree on the hash chain of a round
+    Chain {
+        /// Round of the links
+        round: usize,
+
+        /// Local status of the links
+        mine: ChainStatus,
+
+        /// Status of the links according to the other verifier
+        theirs: ChainStatus,
+    },
+
+    /// One report verified the round and the other failed it
+    Verification {
+        /// Verified round
+        round: usize,
+
+        /// Local verification
+        mine: Verification,
+
+        /// Verification of the other verifier
+        theirs: Verification,
+    },
+}
+
+impl fmt::Display for Discrepancy {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingRound { round, side } => write!(
+                f,
+                "Round {} is missing from {} report",
+                round,
+                match side {
+                    Side::Mine => "my",
+                    Side::Theirs => "their",
+                }
+            ),
+            Self::Hash {
+                round,
+                path,
+                mine,
+                theirs,
+            } => write!(
+                f,
+                "Round {}: {} hashes to {} but to {} for them",
+                round, path, mine, theirs
+            ),
+            Self::Chain {
+                round,
+                mine,
+                theirs,
+            } => write!(
+                f,
+                "Round {}: the hash chain is a {} but a {} for them",
+                round,
+                mine.name(),
+                theirs.name()
+            ),
+            Self::Verification {
+                round,
+                mine,
+                theirs,
+            } => write!(
+                f,
+                "Round {}: the round is {} but {} for them",
+                round,
+                mine.name(),
+                theirs.name()
+            ),
+        }
+    }
+}
+
+/// Returns the status of the hash chain of `round` recorded in `db`: the response file asserts the
+/// hash of the challenge file the round starts from, and the next challenge file asserts the hash
+/// of the response file.
+#[inline]
+fn chain(db: &StateDb, registry: &Registry, round: usize) -> Result<Option<ChainStatus>> {
+    let (challenge, response, next) = match (
+        registry.challenges.get(round),
+        registry.responses.get(round),
+        registry.challenges.get(round + 1),
+    ) {
+        (Some(challenge), Some(response), Some(next)) => (challenge, response, next),
+        _ => return Ok(None),
+    };
+    let links = [
+        db.chain(&challenge.path, &response.path)?,
+        db.chain(&response.path, &next.path)?,
+    ];
+    Ok(if links.contains(&Some(ChainStatus::Mismatch)) {
+        Some(ChainStatus::Mismatch)
+    } else if links.contains(&Some(ChainStatus::Missing)) {
+        Some(ChainStatus::Missing)
+    } else if links.iter().all(|link| *link == Some(ChainStatus::Match)) {
+        Some(ChainStatus::Match)
+    } else {
+        None
+    })
+}
+
+/// Returns the verification of `round` recorded in `records`, or in `checkpoint` if it was
+/// verified before the database existed.
+#[inline]
+fn verification(
+    records: &[RoundRecord],
+    checkpoint: Option<&Checkpoint>,
+    round: usize,
+) -> Verification {
+    match records.iter().find(|record| record.round == round) {
+        Some(record) if record.verified => return Verification::Verified,
+        Some(_) => return Verification::Failed,
+        _ => {}
+    }
+    match checkpoint {
+        Some(checkpoint) if checkpoint.failed_rounds.contains(&round) => Verification::Failed,
+        Some(checkpoint) if round < checkpoint.next_round => Verification::Verified,
+        _ => Verification::Pending,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the rounds are reported with the files and verification they depend on.
+    #[test]
+    fn report_rounds() {
+        let dir = std::env::temp_dir().join("ppot-verifier-status-test");
+        let storage = StorageOptions {
+            dir: dir.clone(),
+            state_dir: Some(dir.join("state")),
+            cache_dir: Some(dir.join("cache")),
+        };
+        storage.create_dirs().unwrap();
+        let file = |path: &str| RemoteFile::new(path.into(), String::new());
+        let registry = Registry {
+            challenges: vec![
+                file("challenge_0000"),
+                file("challenge_0001"),
+                file("challenge_0002"),
+                file("challenge_0003"),
+            ],
+            responses: vec![
+                file("response_0001"),
+                file("response_0002"),
+                file("response_0003"),
+            ],
+            curve: Default::default(),
+            log_powers: None,
+        };
+        registry.save(storage.state_path(REGISTRY_PATH)).unwrap();
+        fs::write(storage.path("challenge_0001"), b"challenge").unwrap();
+        Hash64([1; 64])
+            .save(storage.hash_path("challenge_0001", HashAlgorithm::Blake2b))
+            .unwrap();
+        let mut checkpoint = Checkpoint::new(19);
+        checkpoint.record(1, false, None);
+        checkpoint
+            .save(storage.state_path(CHECKPOINT_PATH))
+            .unwrap();
+        let db = StateDb::open_in(&storage).unwrap();
+        db.record_round(2, 19, None).unwrap();
+        db.record_level(1, 10, None).unwrap();
+        db.record_level(2, 10, None).unwrap();
+        db.record_level(2, 15, None).unwrap();
+        db.record_chain("challenge_0001", "response_0002", ChainStatus::Match)
+            .unwrap();
+        db.record_chain("response_0002", "challenge_0002", ChainStatus::Mismatch)
+            .unwrap();
+        db.record_chain("challenge_0002", "response_0003", ChainStatus::Match)
+            .unwrap();
+        db.record_attestation(
+            2,
+            &SignatureCheck {
+                status: SignatureStatus::Invalid,
+                signer: Some("551E556CB55321E9116731BF0F074AF28C366726".into()),
+            },
+        )
+        .unwrap();
+        drop(db);
+        let report = Report::collect(&storage).unwrap();
+        assert_eq!(report.status.files, 7);
+        assert_eq!(report.status.downloaded, 1);
+        assert_eq!(report.status.hashed, 1);
+        assert_eq!(report.status.rounds, 2);
+        assert_eq!(report.status.failed_rounds, [1]);
+        let first = report.round(1).unwrap();
+        assert_eq!(first.verification, Verification::Failed);
+        assert_eq!(first.files[0].size, Some(9));
+        assert_eq!(first.files[1].path, "response_0002");
+        assert_eq!(first.chain, Some(ChainStatus::Mismatch));
+        assert_eq!(report.round(2).unwrap().chain, None);
+        assert_eq!(
+            first.signature.as_ref().map(|signature| signature.status),
+            Some(SignatureStatus::Invalid)
+        );
+        assert_eq!(report.round(2).unwrap().signature, None);
+        assert_eq!(
+            report.round(2).unwrap().verification,
+            Verification::Verified
+        );
+        assert!(report.round(3).is_none());
+        assert_eq!(
+            report.round(2).unwrap().levels,
+            BTreeMap::from([(10, Verification::Verified), (15, Verification::Verified)])
+        );
+        assert!(report.markdown().contains("| 2^10 | 2 |  |"));
+        assert!(report
+            .markdown()
+            .contains("| 1 | `response_0002` | mismatch | **FAILED** |"));
+        assert!(report
+            .markdown()
+            .contains("| 1 | **INVALID** | `551E556CB55321E9116731BF0F074AF28C366726` |"));
+        assert_eq!(
+            report.transcript(),
+            format!("{}  challenge_0001\n", Hash64([1; 64]))
+        );
+        let mut theirs = report.clone();
+        theirs.rounds.pop();
+        theirs.rounds[0].files[0].blake2b = Some(Hash64([2; 64]));
+        theirs.rounds[0].verification = Verification::Verified;
+        assert!(report.diff(&report).is_empty());
+        assert_eq!(
+            report.diff(&theirs),
+            [
+                Discrepancy::Hash {
+                    round: 1,
+                    path: "challenge_0001".into(),
+                    mine: Hash64([1; 64]),
+                    theirs: Hash64([2; 64]),
+                },
+                Discrepancy::Verification {
+                    round: 1,
+                    mine: Verification::Failed,
+                    theirs: Verification::Verified,
+                },
+                Discrepancy::MissingRound {
+                    round: 2,
+                    side: Side::Theirs,
+                },
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+//! Storage Layout
+//!
+//! The ceremony files are stored under the names given by [`challenge_paths`] and
+//! [`response_paths`] in a single directory. It defaults to the current directory and can be moved
+//! to a dedicated mount with `--dir` or the [`DIR_ENV`] environment variable.
+//!
+//! Everything the verifier derives from the ceremony files is kept apart from them, so that
+//! cleaning one does not destroy the other. The [state database](crate::db), hashes, chunk
+//! manifests, manifest, registry and configuration are stored in the state directory,
+//! `ppot-verifier` in the XDG data directory by default, and the hash cache in the cache
+//! directory, `ppot-verifier` in the XDG cache directory by default. The state of interrupted
+//! downloads and the [`lock`](crate::lock) files stay next to the ceremony files they describe.
+
+use crate::{challenge_paths, response_paths, HashAlgorithm, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Environment variable holding the storage directory
+pub const DIR_ENV: &str = "PPOT_DIR";
+
+/// Environment variable holding the state directory
+pub const STATE_DIR_ENV: &str = "PPOT_STATE_DIR";
+
+/// Environment variable holding the cache directory
+pub const CACHE_DIR_ENV: &str = "PPOT_CACHE_DIR";
+
+/// Name of the subdirectory of the data and cache directories used by the verifier
+const APPLICATION: &str = "ppot-verifier";
+
+/// Storage Options
+///
+/// Shared by the command line interfaces of all the binaries.
+#[derive(clap::Args, Clone, Debug, Eq, Hash, PartialEq)]
+pub struct StorageOptions {
+    /// Directory holding the ceremony files
+    #[clap(long, env = DIR_ENV, global = true, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Directory holding the hashes, manifests, registry and configuration, defaults to
+    /// `ppot-verifier` in the user data directory
+    #[clap(long, env = STATE_DIR_ENV, global = true)]
+    pub state_dir: Option<PathBuf>,
+
+    /// Directory holding the hash cache, defaults to `ppot-verifier` in the user cache directory
+    #[clap(long, env = CACHE_DIR_ENV, global = true)]
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl StorageOptions {
+    /// Builds [`StorageOptions`] storing the ceremony files in `dir` and the rest in the default
+    /// state and cache directories.
+    #[inline]
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            dir: dir.into(),
+            state_dir: None,
+            cache_dir: None,
+        }
+    }
+
+    /// Returns the state directory, falling back to the storage directory on platforms without a
+    /// user data directory.
+    #[inline]
+    pub fn state_dir(&self) -> PathBuf {
+        match &self.state_dir {
+            Some(state_dir) => state_dir.clone(),
+            _ => dirs::data_dir()
+                .map(|dir| dir.join(APPLICATION))
+                .unwrap_or_else(|| self.dir.clone()),
+        }
+    }
+
+    /// Returns the cache directory, falling back to the state directory on platforms without a
+    /// user cache directory.
+    #[inline]
+    pub fn cache_dir(&self) -> PathBuf {
+        match &self.cache_dir {
+            Some(cache_dir) => cache_dir.clone(),
+            _ => dirs::cache_dir()
+                .map(|dir| dir.join(APPLICATION))
+                .unwrap_or_else(|| self.state_dir()),
+        }
+    }
+
+    /// Creates the state and cache directories if they do not exist yet.
+    #[inline]
+    pub fn create_dirs(&self) -> Result {
+        fs::create_dir_all(self.state_dir())?;
+        fs::create_dir_all(self.cache_dir())?;
+        Ok(())
+    }
+
+    /// Returns the path of the file named `name` in the storage directory. Absolute names are
+    /// returned unchanged.
+    #[inline]
+    pub fn path<P>(&self, name: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        self.dir.join(name)
+    }
+
+    /// Returns the path of the file named `name` in the state directory. Absolute names are
+    /// returned unchanged.
+    #[inline]
+    pub fn state_path<P>(&self, name: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        self.state_dir().join(name)
+    }
+
+    /// Returns the path of the file named `name` in the cache directory. Absolute names are
+    /// returned unchanged.
+    #[inline]
+    pub fn cache_path<P>(&self, name: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        self.cache_dir().join(name)
+    }
+
+    /// Returns the path in the state directory named after the ceremony file at `path`, to which
+    /// the suffixes of hash files and chunk manifests are appended.
+    #[inline]
+    pub fn state_path_of<P>(&self, path: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        self.state_path(path.as_ref().file_name().unwrap_or_default())
+    }
+
+    /// Returns the path of the file holding the `algorithm` hash of the ceremony file at `path`.
+    #[inline]
+    pub fn hash_path<P>(&self, path: P, algorithm: HashAlgorithm) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        algorithm.hash_path(self.state_path_of(path))
+    }
+
+    /// Returns the paths of the first `n + 1` challenge files in the storage directory.
+    #[inline]
+    pub fn challenge_paths(&self, n: usize) -> Vec<PathBuf> {
+        challenge_paths(n)
+            .into_iter()
+            .map(|name| self.path(name))
+            .collect()
+    }
+
+    /// Returns the paths of the first `n` response files in the storage directory.
+    #[inline]
+    pub fn response_paths(&self, n: usize) -> Vec<PathBuf> {
+        response_paths(n)
+            .into_iter()
+            .map(|name| self.path(name))
+            .collect()
+    }
+}
+
+impl Default for StorageOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that ceremony files and verifier state are resolved in their own directories.
+    #[test]
+    fn paths_are_resolved_in_their_directories() {
+        let storage = StorageOptions {
+            dir: "/mnt/ppot".into(),
+            state_dir: Some("/var/lib/ppot".into()),
+            cache_dir: Some("/var/cache/ppot".into()),
+        };
+        assert_eq!(
+            storage.challenge_paths(1),
+            [
+                PathBuf::from("/mnt/ppot/challenge_0000"),
+                PathBuf::from("/mnt/ppot/challenge_0001")
+            ]
+        );
+        assert_eq!(
+            storage.response_paths(1),
+            [PathBuf::from("/mnt/ppot/response_0001")]
+        );
+        assert_eq!(
+            storage.path("/tmp/registry.json"),
+            Path::new("/tmp/registry.json")
+        );
+        assert_eq!(
+            storage.hash_path("/mnt/ppot/response_0001", HashAlgorithm::Blake2b),
+            Path::new("/var/lib/ppot/response_0001_hash")
+        );
+        assert_eq!(
+            storage.cache_path("hash_cache.json"),
+            Path::new("/var/cache/ppot/hash_cache.json")
+        );
+    }
+}
@@ -0,0 +1,497 @@
+//! Synthetic Ceremonies
+//!
+//! The files of the ceremony weigh about 97 GB each, so the verifier can hardly be tested against
+//! them. A [`MiniCeremony`] has the same file format and the same hash chain on a few powers of
+//! tau: an initial challenge file whose points are the generators, the response files of a few
+//! participants multiplying the accumulator by random secrets, each ending with the public key
+//! proving the knowledge of these secrets, and the challenge files derived from them. Every round
+//! of a mini ceremony verifies like a round of the real ceremony, so the whole pipeline can run on
+//! files of a few hundred kilobytes.
+//!
+//! The secrets are drawn from the random number generator given to [`MiniCeremony::generate`], so
+//! a seeded generator always produces the same files.
+//!
+//! A contribution can also be broken on purpose with a [`Fault`], as a participant or a download
+//! would break it, through [`MiniCeremony::contribute_faulty`], so that every way the verifier
+//! rejects a round is exercised without corrupting a real file.
+
+use crate::{
+    calculate_hash, challenge_paths,
+    curve::{Curve, Scalar, G1, G2},
+    format::{write_g1_on, write_g2_on, Encoding, Layout, Section, HASH_SIZE},
+    hash::Hash64,
+    pok::{derive_g2, PublicKey, Secret},
+    response_paths, HashAlgorithm, Result,
+};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{One, UniformRand, Zero};
+use rand::Rng;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Default base-two logarithm of the number of powers of tau of a mini ceremony
+pub const SYNTHETIC_LOG_POWERS: u32 = 8;
+
+/// Default number of participants of a mini ceremony
+pub const SYNTHETIC_PARTICIPANTS: usize = 3;
+
+/// Secrets tau, alpha and beta, in the order of [`Secret::ALL`]
+pub type Secrets<C> = [Scalar<C>; 3];
+
+/// Draws random secrets over `C` from `rng`.
+#[inline]
+pub fn random_secrets<C, R>(rng: &mut R) -> Secrets<C>
+where
+    C: Curve,
+    R: Rng + ?Sized,
+{
+    [0; 3].map(|_| Scalar::<C>::rand(rng))
+}
+
+/// Builds the public key of a contribution multiplying the accumulator of the challenge file
+/// whose hash is `challenge_hash` by `secrets`, with random G1 points drawn from `rng`.
+#[inline]
+pub fn public_key<C, R>(challenge_hash: &Hash64, secrets: &Secrets<C>, rng: &mut R) -> PublicKey<C>
+where
+    C: Curve,
+    R: Rng + ?Sized,
+{
+    let mut key = PublicKey::<C> {
+        g1: [(G1::<C>::zero(), G1::<C>::zero()); 3],
+        g2: [G2::<C>::zero(); 3],
+    };
+    for secret in Secret::ALL {
+        let index = secret.personalization() as usize;
+        let s = G1::<C>::prime_subgroup_generator()
+            .mul(Scalar::<C>::rand(rng))
+            .into_affine();
+        let s_x = s.mul(secrets[index]).into_affine();
+        key.g1[index] = (s, s_x);
+        key.g2[index] = derive_g2::<C>(challenge_hash, &s, &s_x, secret)
+            .mul(secrets[index])
+            .into_affine();
+    }
+    key
+}
+
+/// Writes `key` to `out`, in the uncompressed encoding ending the response files.
+#[inline]
+pub fn write_public_key<C>(key: &PublicKey<C>, out: &mut [u8])
+where
+    C: Curve,
+{
+    let (g1_size, g2_size) = (
+        Encoding::Uncompressed.g1_size_on::<C>(),
+        Encoding::Uncompressed.g2_size_on::<C>(),
+    );
+    for (i, point) in key.g1.iter().flat_map(|(s, s_x)| [s, s_x]).enumerate() {
+        write_g1_on::<C>(point, Encoding::Uncompressed, &mut out[i * g1_size..]);
+    }
+    for (i, point) in key.g2.iter().enumerate() {
+        write_g2_on::<C>(
+            point,
+            Encoding::Uncompressed,
+            &mut out[6 * g1_size + i * g2_size..],
+        );
+    }
+}
+
+/// Writes a file over `C` with `layout`, starting with `header`, whose accumulator holds the
+/// powers of the secrets `tau`, `alpha` and `beta`, and ending with `key` for response files.
+#[inline]
+pub fn write_accumulator<C>(
+    layout: &Layout,
+    header: &Hash64,
+    [tau, alpha, beta]: Secrets<C>,
+    key: Option<&PublicKey<C>>,
+) -> Vec<u8>
+where
+    C: Curve,
+{
+    let mut data = vec![0; layout.file_size()];
+    data[..HASH_SIZE].copy_from_slice(&header.0);
+    let g1 = G1::<C>::prime_subgroup_generator();
+    let g2 = G2::<C>::prime_subgroup_generator();
+    for section in Section::ALL {
+        let mut power = Scalar::<C>::one();
+        for index in 0..section.len(layout.powers) {
+            let out = &mut data[layout.point_range(section, index)];
+            match section {
+                Section::TauG1 => {
+                    write_g1_on::<C>(&g1.mul(power).into_affine(), layout.encoding, out)
+                }
+                Section::AlphaG1 => {
+                    write_g1_on::<C>(&g1.mul(alpha * power).into_affine(), layout.encoding, out)
+                }
+                Section::BetaG1 => {
+                    write_g1_on::<C>(&g1.mul(beta * power).into_affine(), layout.encoding, out)
+                }
+                Section::TauG2 => {
+                    write_g2_on::<C>(&g2.mul(power).into_affine(), layout.encoding, out)
+                }
+                Section::BetaG2 => {
+                    write_g2_on::<C>(&g2.mul(beta).into_affine(), layout.encoding, out)
+                }
+            }
+            power *= tau;
+        }
+    }
+    if let Some(key) = key {
+        let size = PublicKey::<C>::size();
+        write_public_key::<C>(key, &mut data[layout.file_size() - size..]);
+    }
+    data
+}
+
+/// Fault of a Contribution
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Fault {
+    /// The public key does not prove the knowledge of the secrets for the challenge file
+    WrongProof,
+
+    /// The last point of the tau powers in G1 is replaced by another point of the group
+    TamperedPoint,
+
+    /// The header does not hold the hash of the challenge file
+    BadHeader,
+
+    /// The file is cut in the middle, as by an interrupted download
+    Truncated,
+}
+
+impl Fault {
+    /// Every fault
+    pub const ALL: [Self; 4] = [
+        Self::WrongProof,
+        Self::TamperedPoint,
+        Self::BadHeader,
+        Self::Truncated,
+    ];
+}
+
+/// Returns the Blake2b hash of `data`.
+#[inline]
+fn blake2b(data: &[u8]) -> Hash64 {
+    Hash64::try_from(calculate_hash(data, HashAlgorithm::Blake2b).as_slice())
+        .expect("Blake2b hashes are 64 bytes long.")
+}
+
+/// Mini Ceremony
+///
+/// Round `i` turns `challenges[i - 1]` into `challenges[i]` through `responses[i - 1]`, as in the
+/// [`Registry`](crate::registry::Registry) of the ceremony.
+#[derive(Clone, Debug)]
+pub struct MiniCeremony<C>
+where
+    C: Curve,
+{
+    /// Number of powers of tau of the files
+    pub powers: usize,
+
+    /// Challenge files, starting with the initial challenge
+    pub challenges: Vec<Vec<u8>>,
+
+    /// Response files, starting with the response of the first participant
+    pub responses: Vec<Vec<u8>>,
+
+    /// Product of the secrets of all the contributions so far
+    secrets: Secrets<C>,
+}
+
+impl<C> MiniCeremony<C>
+where
+    C: Curve,
+{
+    /// Builds a mini ceremony with `powers` powers of tau and no contribution yet. Its initial
+    /// challenge file holds the generators, with the Blake2b hash of nothing as header, like the
+    /// initial challenge of the ceremony.
+    #[inline]
+    pub fn new(powers: usize) -> Self {
+        let secrets = [Scalar::<C>::one(); 3];
+        Self {
+            powers,
+            challenges: vec![write_accumulator::<C>(
+                &Layout::challenge_on::<C>(powers),
+                &blake2b(&[]),
+                secrets,
+                None,
+            )],
+            responses: Vec::new(),
+            secrets,
+        }
+    }
+
+    /// Generates a mini ceremony with `powers` powers of tau and `participants` contributions of
+    /// secrets drawn from `rng`.
+    #[inline]
+    pub fn generate<R>(powers: usize, participants: usize, rng: &mut R) -> Self
+    where
+        R: Rng + ?Sized,
+    {
+        let mut ceremony = Self::new(powers);
+        for _ in 0..participants {
+            ceremony.contribute(random_secrets::<C, _>(rng), rng);
+        }
+        ceremony
+    }
+
+    /// Generates a mini ceremony like [`generate`](Self::generate) whose last contribution is
+    /// broken by `fault`, if any, as [`contribute_faulty`](Self::contribute_faulty) breaks it.
+    #[inline]
+    pub fn generate_faulty<R>(
+        powers: usize,
+        participants: usize,
+        fault: Option<Fault>,
+        rng: &mut R,
+    ) -> Self
+    where
+        R: Rng + ?Sized,
+    {
+        match fault {
+            Some(fault) if participants > 0 => {
+                let mut ceremony = Self::generate(powers, participants - 1, rng);
+                ceremony.contribute_faulty(random_secrets::<C, _>(rng), fault, rng);
+                ceremony
+            }
+            _ => Self::generate(powers, participants, rng),
+        }
+    }
+
+    /// Returns the number of rounds of the ceremony.
+    #[inline]
+    pub fn rounds(&self) -> usize {
+        self.responses.len()
+    }
+
+    /// Returns the Blake2b hash of the last challenge file.
+    #[inline]
+    pub fn challenge_hash(&self) -> Hash64 {
+        blake2b(
+            self.challenges
+                .last()
+                .expect("There is always an initial challenge."),
+        )
+    }
+
+    /// Adds a round multiplying the accumulator of the last challenge file by `secrets`, proven by
+    /// a public key with random G1 points drawn from `rng`, and returns the response file.
+    #[inline]
+    pub fn contribute<R>(&mut self, secrets: Secrets<C>, rng: &mut R) -> &[u8]
+    where
+        R: Rng + ?Sized,
+    {
+        self.push_round(secrets, None, rng)
+    }
+
+    /// Adds a round like [`contribute`](Self::contribute) whose response file is broken by
+    /// `fault`, and returns the response file. The challenge file derived from it holds the
+    /// accumulator of the broken response, with the hash of the broken response as header, so
+    /// that later rounds build on it.
+    #[inline]
+    pub fn contribute_faulty<R>(&mut self, secrets: Secrets<C>, fault: Fault, rng: &mut R) -> &[u8]
+    where
+        R: Rng + ?Sized,
+    {
+        self.push_round(secrets, Some(fault), rng)
+    }
+
+    /// Adds a round multiplying the accumulator by `secrets`, broken by `fault` if any, and returns
+    /// the response file.
+    #[inline]
+    fn push_round<R>(&mut self, secrets: Secrets<C>, fault: Option<Fault>, rng: &mut R) -> &[u8]
+    where
+        R: Rng + ?Sized,
+    {
+        let challenge_hash = self.challenge_hash();
+        let key = match fault {
+            Some(Fault::WrongProof) => {
+                public_key::<C, _>(&blake2b(&challenge_hash.0), &secrets, rng)
+            }
+            _ => public_key::<C, _>(&challenge_hash, &secrets, rng),
+        };
+        self.secrets = [0, 1, 2].map(|i| self.secrets[i] * secrets[i]);
+        let mut header = challenge_hash;
+        if fault == Some(Fault::BadHeader) {
+            header.0[0] ^= 1;
+        }
+        let (from, to) = (
+            Layout::response_on::<C>(self.powers),
+            Layout::challenge_on::<C>(self.powers),
+        );
+        let mut response = write_accumulator::<C>(&from, &header, self.secrets, Some(&key));
+        let mut challenge = write_accumulator::<C>(&to, &Hash64::default(), self.secrets, None);
+        match fault {
+            Some(Fault::TamperedPoint) => {
+                let (section, index) = (Section::TauG1, Section::TauG1.len(self.powers) - 1);
+                let point = G1::<C>::prime_subgroup_generator()
+                    .mul(Scalar::<C>::rand(rng))
+                    .into_affine();
+                write_g1_on::<C>(
+                    &point,
+                    from.encoding,
+                    &mut response[from.point_range(section, index)],
+                );
+                write_g1_on::<C>(
+                    &point,
+                    to.encoding,
+                    &mut challenge[to.point_range(section, index)],
+                );
+            }
+            Some(Fault::Truncated) => response.truncate(response.len() / 2),
+            _ => {}
+        }
+        challenge[..HASH_SIZE].copy_from_slice(&blake2b(&response).0);
+        self.challenges.push(challenge);
+        self.responses.push(response);
+        self.responses.last().expect("The response was just added.")
+    }
+
+    /// Writes the files of the ceremony to `dir` under the names of [`challenge_paths`] and
+    /// [`response_paths`], returning the paths of the challenge and of the response files.
+    #[inline]
+    pub fn write<P>(&self, dir: P) -> Result<(Vec<PathBuf>, Vec<PathBuf>)>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let write = |names: Vec<String>, files: &[Vec<u8>]| {
+            names
+                .into_iter()
+                .zip(files)
+                .map(|(name, data)| {
+                    let path = dir.join(name);
+                    fs::write(&path, data)?;
+                    Ok(path)
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok((
+            write(challenge_paths(self.rounds()), &self.challenges)?,
+            write(response_paths(self.rounds()), &self.responses)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        accumulator::{verify_round, verify_round_on},
+        curve::CurveKind,
+    };
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::Bn254;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// Checks that every round of a mini ceremony over `C` verifies, that its files are chained
+    /// by their headers and that the same seed generates the same files.
+    fn generate_on<C>()
+    where
+        C: Curve,
+    {
+        let powers = 1 << 3;
+        let ceremony = MiniCeremony::<C>::generate(powers, 3, &mut ChaCha20Rng::seed_from_u64(7));
+        assert_eq!(ceremony.rounds(), 3);
+        assert_eq!(ceremony.challenges.len(), 4);
+        for (round, response) in ceremony.responses.iter().enumerate() {
+            let (challenge, next) = (&ceremony.challenges[round], &ceremony.challenges[round + 1]);
+            assert_eq!(Hash64::from_header(response), Some(blake2b(challenge)));
+            assert_eq!(Hash64::from_header(next), Some(blake2b(response)));
+            verify_round::<C>(challenge, response, powers, powers).unwrap();
+        }
+        assert!(verify_round::<C>(
+            &ceremony.challenges[0],
+            &ceremony.responses[1],
+            powers,
+            powers
+        )
+        .is_err());
+        let again = MiniCeremony::<C>::generate(powers, 3, &mut ChaCha20Rng::seed_from_u64(7));
+        assert_eq!(again.responses, ceremony.responses);
+    }
+
+    /// Checks that the verification of a round over `C` broken by each [`Fault`] fails for the
+    /// reason of the fault, while the round before it still verifies.
+    fn faults_on<C>()
+    where
+        C: Curve,
+    {
+        let powers = 1 << 3;
+        for fault in Fault::ALL {
+            let mut rng = ChaCha20Rng::seed_from_u64(11);
+            let mut ceremony = MiniCeremony::<C>::generate(powers, 1, &mut rng);
+            let secrets = random_secrets::<C, _>(&mut rng);
+            ceremony.contribute_faulty(secrets, fault, &mut rng);
+            let (challenge, response) = (&ceremony.challenges[1], &ceremony.responses[1]);
+            verify_round::<C>(
+                &ceremony.challenges[0],
+                &ceremony.responses[0],
+                powers,
+                powers,
+            )
+            .unwrap();
+            let error = verify_round::<C>(challenge, response, powers, powers)
+                .unwrap_err()
+                .to_string();
+            let expected = match fault {
+                Fault::WrongProof | Fault::BadHeader => "Invalid proofs of knowledge",
+                Fault::TamperedPoint => "successive powers of tau",
+                Fault::Truncated => "bytes instead of",
+            };
+            assert!(error.contains(expected), "{:?}: {}", fault, error);
+            assert_eq!(
+                Hash64::from_header(response) == Some(blake2b(challenge)),
+                fault != Fault::BadHeader
+            );
+            assert_eq!(
+                Hash64::from_header(&ceremony.challenges[2]),
+                Some(blake2b(response))
+            );
+        }
+    }
+
+    /// Checks the generation of mini ceremonies over both curves.
+    #[test]
+    fn generate() {
+        generate_on::<Bn254>();
+        generate_on::<Bls12_381>();
+    }
+
+    /// Checks the faulty contributions over both curves.
+    #[test]
+    fn faults() {
+        faults_on::<Bn254>();
+        faults_on::<Bls12_381>();
+    }
+
+    /// Checks that the verifier accepts every round of the files of a generated ceremony up to the
+    /// one broken by a fault, and rejects that one.
+    #[test]
+    fn faulty_ceremonies_are_rejected() {
+        let (powers, dir) = (
+            1 << 3,
+            std::env::temp_dir().join("ppot-verifier-faulty-test"),
+        );
+        for fault in [None].into_iter().chain(Fault::ALL.map(Some)) {
+            let mut rng = ChaCha20Rng::seed_from_u64(13);
+            let ceremony = MiniCeremony::<Bn254>::generate_faulty(powers, 2, fault, &mut rng);
+            let (challenges, responses) = ceremony.write(&dir).unwrap();
+            let verify = |round: usize| {
+                verify_round_on(
+                    CurveKind::Bn254,
+                    &fs::read(&challenges[round]).unwrap(),
+                    &fs::read(&responses[round]).unwrap(),
+                    powers,
+                    powers,
+                )
+            };
+            verify(0).unwrap();
+            assert_eq!(verify(1).is_ok(), fault.is_none(), "{:?}", fault);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
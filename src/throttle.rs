@@ -0,0 +1,102 @@
+//! Bandwidth Throttling
+
+use crate::{memory::ByteSize, Result};
+use anyhow::{anyhow, bail};
+use core::{fmt, str::FromStr, time::Duration};
+use std::sync::Mutex;
+use tokio::time::{sleep_until, Instant};
+
+/// Byte Rate
+///
+/// Parsed from strings like `50MB/s`, `1.5GiB/s` or `800K`, with the units of [`ByteSize`]. A
+/// trailing `/s` is optional and a bare number is read as bytes per second.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ByteRate(pub u64);
+
+impl FromStr for ByteRate {
+    type Err = anyhow::Error;
+
+    #[inline]
+    fn from_str(rate: &str) -> Result<Self, Self::Err> {
+        let rate = rate.trim();
+        let ByteSize(rate) = rate
+            .strip_suffix("/s")
+            .unwrap_or(rate)
+            .parse()
+            .map_err(|err| anyhow!("Invalid rate '{}': {}", rate, err))?;
+        if rate == 0 {
+            bail!("The rate must be positive.");
+        }
+        Ok(Self(rate))
+    }
+}
+
+impl fmt::Display for ByteRate {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}B/s", self.0)
+    }
+}
+
+/// Rate Limiter
+///
+/// Paces the callers of [`acquire`](Self::acquire) so that, taken together, they consume at most
+/// the configured number of bytes per second. A single limiter can be shared between several
+/// downloads to cap their total bandwidth.
+#[derive(Debug)]
+pub struct RateLimiter {
+    /// Allowed rate
+    rate: ByteRate,
+
+    /// Instant at which the bytes handed out so far have been paid for
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Builds a new [`RateLimiter`] allowing `rate` bytes per second.
+    #[inline]
+    pub fn new(rate: ByteRate) -> Self {
+        Self {
+            rate,
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Returns the rate allowed by `self`.
+    #[inline]
+    pub fn rate(&self) -> ByteRate {
+        self.rate
+    }
+
+    /// Waits until `bytes` more bytes can be consumed without exceeding the rate.
+    #[inline]
+    pub async fn acquire(&self, bytes: u64) {
+        let wake = {
+            let mut next = self
+                .next
+                .lock()
+                .expect("Rate limiter lock is never poisoned.");
+            let now = Instant::now();
+            *next = (*next).max(now) + Duration::from_secs_f64(bytes as f64 / self.rate.0 as f64);
+            *next
+        };
+        sleep_until(wake).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that rates are parsed with decimal and binary units.
+    #[test]
+    fn parse_rates() {
+        assert_eq!("50MB/s".parse::<ByteRate>().unwrap(), ByteRate(50_000_000));
+        assert_eq!("1.5GiB/s".parse::<ByteRate>().unwrap(), ByteRate(3 << 29));
+        assert_eq!("800K".parse::<ByteRate>().unwrap(), ByteRate(800_000));
+        assert_eq!("1024".parse::<ByteRate>().unwrap(), ByteRate(1024));
+        assert!("fast".parse::<ByteRate>().is_err());
+        assert!("0MB/s".parse::<ByteRate>().is_err());
+        assert!("10XB/s".parse::<ByteRate>().is_err());
+    }
+}
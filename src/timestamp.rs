@@ -0,0 +1,352 @@
+//! Trusted Timestamps
+//!
+//! A canonical report is timestamped by a Time-Stamping Authority following [RFC 3161]: the
+//! SHA-256 hash of the report is sent to the authority, which answers with a token signing the
+//! hash along with the time at which it received it. The token proves that the report existed, and
+//! so that the verification it records was performed, by that time. It is kept in a [`Timestamp`]
+//! next to the report, with the reply of the authority encoded in Base64 so that `openssl ts` can
+//! check the signature of the authority:
+//!
+//! ```text
+//! jq -r .reply ppot-report.canonical.json.timestamp.json | base64 -d > reply.tsr
+//! openssl ts -verify -in reply.tsr -data ppot-report.canonical.json -CAfile tsa.pem
+//! ```
+//!
+//! The reply is parsed here to check that it timestamps the hash of the report and answers the
+//! nonce of the request. The signature itself is left to `openssl ts`, which is given the
+//! certificates of the authority: until then, the time and the authority of a saved timestamp are
+//! only claimed, since anyone can write a reply with any time.
+//!
+//! [RFC 3161]: https://www.rfc-editor.org/rfc/rfc3161
+
+use crate::{
+    der::{
+        encode, explicit, integer_content, Reader, BOOLEAN, GENERALIZED_TIME, INTEGER, NULL,
+        OBJECT_IDENTIFIER, OCTET_STRING, SEQUENCE, SET,
+    },
+    download::USER_AGENT,
+    Result,
+};
+use anyhow::{bail, ensure};
+use reqwest::{
+    header::{CONTENT_TYPE, USER_AGENT as USER_AGENT_HEADER},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+
+/// Default Time-Stamping Authority, the free service of FreeTSA
+pub const DEFAULT_TSA_URL: &str = "https://freetsa.org/tsr";
+
+/// Extension appended to the name of a report to name its timestamp
+pub const TIMESTAMP_EXTENSION: &str = ".timestamp.json";
+
+/// Media type of timestamp requests
+const QUERY_MEDIA_TYPE: &str = "application/timestamp-query";
+
+/// Object identifier of SHA-256
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+/// Object identifier of the signed data content type of CMS
+const SIGNED_DATA_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+
+/// Object identifier of the timestamp token info content type
+const TST_INFO_OID: &[u8] = &[
+    0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x10, 0x01, 0x04,
+];
+
+/// Builds the DER-encoded `TimeStampReq` for the SHA-256 `digest`, with `nonce`, asking for the
+/// certificate of the authority to be included in the token.
+#[inline]
+fn request_body(digest: &[u8; 32], nonce: &[u8]) -> Vec<u8> {
+    let algorithm = [encode(OBJECT_IDENTIFIER, SHA256_OID), encode(NULL, &[])].concat();
+    let imprint = [encode(SEQUENCE, &algorithm), encode(OCTET_STRING, digest)].concat();
+    encode(
+        SEQUENCE,
+        &[
+            encode(INTEGER, &[1]),
+            encode(SEQUENCE, &imprint),
+            encode(INTEGER, &integer_content(nonce)),
+            encode(BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+/// Timestamp Token Info
+///
+/// The fields of the `TSTInfo` signed by the authority which are checked against the request.
+struct TstInfo<'d> {
+    /// Object identifier of the hash algorithm of the message imprint
+    algorithm: &'d [u8],
+
+    /// Hash of the timestamped data
+    imprint: &'d [u8],
+
+    /// Time of the timestamp, as a DER generalized time
+    time: &'d [u8],
+
+    /// Content of the nonce integer, if any
+    nonce: Option<&'d [u8]>,
+}
+
+/// Parses the `TSTInfo` of the DER-encoded `TimeStampResp` in `reply`, failing if the authority
+/// rejected the request.
+#[inline]
+fn parse_reply(reply: &[u8]) -> Result<TstInfo<'_>> {
+    let mut response = Reader(Reader(reply).expect(SEQUENCE)?);
+    let mut status = Reader(response.expect(SEQUENCE)?);
+    match status.expect(INTEGER)? {
+        [0] | [1] => {}
+        [code] => bail!(
+            "The Time-Stamping Authority rejected the request with status {}.",
+            code
+        ),
+        _ => bail!("Invalid status of the timestamp reply."),
+    }
+    let mut content_info = Reader(response.expect(SEQUENCE)?);
+    ensure!(
+        content_info.expect(OBJECT_IDENTIFIER)? == SIGNED_DATA_OID,
+        "The timestamp token is not signed data."
+    );
+    let mut signed_data = Reader(Reader(content_info.expect(explicit(0))?).expect(SEQUENCE)?);
+    signed_data.expect(INTEGER)?;
+    signed_data.expect(SET)?;
+    let mut content = Reader(signed_data.expect(SEQUENCE)?);
+    ensure!(
+        content.expect(OBJECT_IDENTIFIER)? == TST_INFO_OID,
+        "The timestamp token does not hold a timestamp."
+    );
+    let tst_info = Reader(content.expect(explicit(0))?).expect(OCTET_STRING)?;
+    let mut tst_info = Reader(Reader(tst_info).expect(SEQUENCE)?);
+    tst_info.expect(INTEGER)?;
+    tst_info.expect(OBJECT_IDENTIFIER)?;
+    let mut imprint = Reader(tst_info.expect(SEQUENCE)?);
+    let algorithm = Reader(imprint.expect(SEQUENCE)?).expect(OBJECT_IDENTIFIER)?;
+    let imprint = imprint.expect(OCTET_STRING)?;
+    tst_info.expect(INTEGER)?;
+    let time = tst_info.expect(GENERALIZED_TIME)?;
+    let mut nonce = None;
+    while !tst_info.is_empty() {
+        if let (INTEGER, value) = tst_info.next()? {
+            nonce = Some(value);
+            break;
+        }
+    }
+    Ok(TstInfo {
+        algorithm,
+        imprint,
+        time,
+        nonce,
+    })
+}
+
+/// Formats the DER generalized `time`, of the form `YYYYMMDDHHMMSS[.fff]Z`, as in RFC 3339.
+#[inline]
+fn format_time(time: &[u8]) -> Result<String> {
+    let time = core::str::from_utf8(time)?;
+    ensure!(
+        time.len() >= 15
+            && time.ends_with('Z')
+            && time[..14].bytes().all(|byte| byte.is_ascii_digit()),
+        "Invalid timestamp time {:?}.",
+        time
+    );
+    Ok(format!(
+        "{}-{}-{}T{}:{}:{}{}",
+        &time[..4],
+        &time[4..6],
+        &time[6..8],
+        &time[8..10],
+        &time[10..12],
+        &time[12..14],
+        &time[14..]
+    ))
+}
+
+/// Trusted Timestamp of a Report
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timestamp {
+    /// URL of the Time-Stamping Authority
+    pub tsa: String,
+
+    /// Time at which the authority timestamped the report, in the format of RFC 3339
+    pub time: String,
+
+    /// SHA-256 hash of the report, in hexadecimal
+    pub sha256: String,
+
+    /// Reply of the authority, a DER-encoded `TimeStampResp`, in Base64
+    pub reply: String,
+}
+
+impl Timestamp {
+    /// Asks the Time-Stamping Authority at `tsa` to timestamp `data`, checking that its reply
+    /// timestamps the hash of `data` and answers the nonce of the request.
+    #[inline]
+    pub async fn request(client: &Client, tsa: &str, data: &[u8]) -> Result<Self> {
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        let nonce = rand::random::<u64>().to_be_bytes();
+        let reply = client
+            .post(tsa)
+            .header(USER_AGENT_HEADER, USER_AGENT)
+            .header(CONTENT_TYPE, QUERY_MEDIA_TYPE)
+            .body(request_body(&digest, &nonce))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let time = Self::check_reply(&reply, &digest, Some(&nonce))?;
+        Ok(Self {
+            tsa: tsa.into(),
+            time,
+            sha256: hex::encode(digest),
+            reply: base64::encode(&reply),
+        })
+    }
+
+    /// Checks that `reply` timestamps the SHA-256 `digest` and answers `nonce`, if any, returning
+    /// the time of the timestamp.
+    #[inline]
+    fn check_reply(reply: &[u8], digest: &[u8; 32], nonce: Option<&[u8]>) -> Result<String> {
+        let info = parse_reply(reply)?;
+        ensure!(
+            info.algorithm == SHA256_OID && info.imprint == digest,
+            "The timestamp is not for the SHA-256 hash of the report."
+        );
+        if let Some(nonce) = nonce {
+            ensure!(
+                info.nonce == Some(integer_content(nonce).as_slice()),
+                "The timestamp reply does not answer the nonce of the request."
+            );
+        }
+        format_time(info.time)
+    }
+
+    /// Checks that `self` timestamps `data` at the recorded time.
+    ///
+    /// The signature of the authority is not checked, so the time and the authority remain
+    /// unverified claims, see the [module documentation](crate::timestamp).
+    #[inline]
+    pub fn check(&self, data: &[u8]) -> Result {
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        ensure!(
+            self.sha256 == hex::encode(digest),
+            "The timestamp is for another report."
+        );
+        let time = Self::check_reply(&base64::decode(&self.reply)?, &digest, None)?;
+        ensure!(
+            time == self.time,
+            "The timestamp was made at {}, not at {}.",
+            time,
+            self.time
+        );
+        Ok(())
+    }
+
+    /// Loads the timestamp saved as JSON at `path`.
+    #[inline]
+    pub fn load<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the reply of an authority timestamping the SHA-256 `digest` at `time`, with
+    /// `status` and `nonce`.
+    fn reply(status: u8, digest: &[u8], time: &str, nonce: &[u8]) -> Vec<u8> {
+        let imprint = [
+            encode(
+                SEQUENCE,
+                &[encode(OBJECT_IDENTIFIER, SHA256_OID), encode(NULL, &[])].concat(),
+            ),
+            encode(OCTET_STRING, digest),
+        ]
+        .concat();
+        let tst_info = encode(
+            SEQUENCE,
+            &[
+                encode(INTEGER, &[1]),
+                encode(OBJECT_IDENTIFIER, &[0x2b, 0x06, 0x01, 0x04, 0x01]),
+                encode(SEQUENCE, &imprint),
+                encode(INTEGER, &[0x12, 0x34]),
+                encode(GENERALIZED_TIME, time.as_bytes()),
+                encode(SEQUENCE, &encode(INTEGER, &[1])),
+                encode(INTEGER, &integer_content(nonce)),
+            ]
+            .concat(),
+        );
+        let content = [
+            encode(OBJECT_IDENTIFIER, TST_INFO_OID),
+            encode(explicit(0), &encode(OCTET_STRING, &tst_info)),
+        ]
+        .concat();
+        let signed_data = [
+            encode(INTEGER, &[3]),
+            encode(SET, &[]),
+            encode(SEQUENCE, &content),
+            encode(explicit(0), &[0; 300]),
+        ]
+        .concat();
+        let token = [
+            encode(OBJECT_IDENTIFIER, SIGNED_DATA_OID),
+            encode(explicit(0), &encode(SEQUENCE, &signed_data)),
+        ]
+        .concat();
+        encode(
+            SEQUENCE,
+            &[
+                encode(SEQUENCE, &encode(INTEGER, &[status])),
+                encode(SEQUENCE, &token),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Checks that requests are encoded in DER and that replies are checked against the
+    /// timestamped report.
+    #[test]
+    fn timestamps_check() {
+        let report = b"{\"log_powers\":19,\"rounds\":[],\"version\":1}\n";
+        let digest: [u8; 32] = Sha256::digest(report).into();
+        let request = request_body(&digest, &[0x80, 1]);
+        assert_eq!(request[..2], [SEQUENCE, 62]);
+        assert!(request.ends_with(&[INTEGER, 3, 0, 0x80, 1, BOOLEAN, 1, 0xff]));
+        let nonce = [0, 7, 0xff];
+        let granted = reply(0, &digest, "20261016093000.5Z", &nonce);
+        let info = parse_reply(&granted).unwrap();
+        assert_eq!(info.nonce, Some(&[7, 0xff][..]));
+        let timestamp = Timestamp {
+            tsa: DEFAULT_TSA_URL.into(),
+            time: "2026-10-16T09:30:00.5Z".into(),
+            sha256: hex::encode(digest),
+            reply: base64::encode(&granted),
+        };
+        timestamp.check(report).unwrap();
+        assert!(timestamp.check(b"another report").is_err());
+        let other = reply(0, &[0; 32], "20261016093000.5Z", &nonce);
+        assert!(Timestamp {
+            reply: base64::encode(other),
+            ..timestamp.clone()
+        }
+        .check(report)
+        .is_err());
+        assert!(Timestamp {
+            time: "2020-01-01T00:00:00Z".into(),
+            ..timestamp.clone()
+        }
+        .check(report)
+        .is_err());
+        assert!(parse_reply(&reply(2, &digest, "20261016093000Z", &nonce)).is_err());
+        assert!(parse_reply(&granted[..granted.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,108 @@
+//! BitTorrent Downloads
+//!
+//! Torrent transfers are delegated to [`aria2c`](https://aria2.github.io), which must be available
+//! on the `PATH`. It verifies the pieces already on disk before fetching the missing ones, so a
+//! partial file left behind by an HTTP download is resumed rather than started over.
+
+use crate::Result;
+use anyhow::{anyhow, bail};
+use core::time::Duration;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+    process::Stdio,
+};
+use tokio::{fs, process::Command, time::sleep};
+
+/// Torrent client executable
+pub const ARIA2C: &str = "aria2c";
+
+/// Interval between two updates of the progress bar
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Builds the [`ARIA2C`] arguments downloading the single file described by `torrent` to `path`.
+#[inline]
+fn arguments(torrent: &str, path: &Path) -> Result<Vec<OsString>> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid download path {}.", path.display()))?;
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.as_os_str(),
+        _ => OsStr::new("."),
+    };
+    let option = |name: &str, value: &OsStr| {
+        let mut option = OsString::from(name);
+        option.push(value);
+        option
+    };
+    Ok(vec![
+        "--continue=true".into(),
+        "--check-integrity=true".into(),
+        "--file-allocation=none".into(),
+        "--seed-time=0".into(),
+        "--follow-torrent=mem".into(),
+        "--bt-save-metadata=false".into(),
+        "--console-log-level=warn".into(),
+        "--summary-interval=0".into(),
+        option("--dir=", directory),
+        option("--index-out=1=", file_name),
+        torrent.into(),
+    ])
+}
+
+/// Downloads the file described by `torrent`, either a magnet link or a URL or path to a
+/// `.torrent` file, to `path`. The torrent is expected to contain exactly the one file.
+#[inline]
+pub async fn download_torrent<P>(multibar: &MultiProgress, torrent: &str, path: P) -> Result
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut child = Command::new(ARIA2C)
+        .args(arguments(torrent, path)?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| anyhow!("Unable to start `{}`: {}", ARIA2C, err))?;
+    let progress_bar = multibar.add(ProgressBar::new_spinner());
+    progress_bar.set_style(
+        ProgressStyle::default_spinner()
+            .template("{msg}\n{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec})")?,
+    );
+    progress_bar.set_message(format!("Downloading {} via BitTorrent", path.display()));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if let Ok(metadata) = fs::metadata(path).await {
+            progress_bar.set_position(metadata.len());
+        }
+        progress_bar.tick();
+        sleep(POLL_INTERVAL).await;
+    };
+    if !status.success() {
+        progress_bar.abandon_with_message(format!("Failed to download {}", path.display()));
+        bail!("`{}` exited with {} for '{}'", ARIA2C, status, torrent);
+    }
+    progress_bar.finish_with_message(format!("Downloaded {} via BitTorrent", path.display()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the file is renamed into place inside of its parent directory.
+    #[test]
+    fn aria2c_arguments() {
+        let magnet = "magnet:?xt=urn:btih:0123456789abcdef";
+        let absolute = arguments(magnet, Path::new("/data/challenge_0001")).unwrap();
+        assert!(absolute.contains(&"--dir=/data".into()));
+        assert!(absolute.contains(&"--index-out=1=challenge_0001".into()));
+        assert_eq!(absolute.last(), Some(&magnet.into()));
+        let relative = arguments(magnet, Path::new("challenge_0001")).unwrap();
+        assert!(relative.contains(&"--dir=.".into()));
+    }
+}
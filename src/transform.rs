@@ -0,0 +1,273 @@
+//! Challenge Reconstruction
+//!
+//! The challenge file produced by a round is fully determined by its response file: the header of
+//! the challenge is the Blake2b hash of the response and its accumulator is the accumulator of the
+//! response, decompressed, without the public key. Reconstructing the challenge files locally
+//! saves downloading about 97 GB per round, and each of them is checked against the hash asserted
+//! by the header of the next response file, which [`fetch_header`] reads with a 64-byte range
+//! request when that response file is not on disk.
+//!
+//! A challenge file can also be cut down to its first `2^k` powers with [`extract`], which keeps
+//! its header like the `reduce_powers` tool of the ceremony, so that contributors and tests can
+//! work with a well-formed file of a few megabytes.
+//!
+//! Both are generic over the [`Curve`] of the ceremony.
+
+use crate::{
+    atomic, calculate_hash,
+    curve::Curve,
+    download::{fetch_range, ByteRange},
+    format::{
+        read_g1_on, read_g2_on, write_g1_on, write_g2_on, Encoding, Layout, Section, HASH_SIZE,
+    },
+    hash::Hash64,
+    input::InputOptions,
+    HashAlgorithm, Result,
+};
+use anyhow::{anyhow, bail};
+use blake2::{Blake2b512, Digest};
+use memmap::MmapMut;
+use rayon::prelude::*;
+use reqwest::Client;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+use tracing::info;
+
+/// Decompresses the accumulator of the `response` file into the `challenge` file, both over `C`
+/// with `powers` powers of tau. The header of `challenge` is left untouched.
+#[inline]
+pub fn decompress<C>(response: &[u8], challenge: &mut [u8], powers: usize) -> Result
+where
+    C: Curve,
+{
+    let (from, to) = (
+        Layout::response_on::<C>(powers),
+        Layout::challenge_on::<C>(powers),
+    );
+    if response.len() != from.file_size() {
+        bail!(
+            "The response file holds {} bytes instead of {} for 2^{} powers.",
+            response.len(),
+            from.file_size(),
+            powers.trailing_zeros()
+        );
+    }
+    if challenge.len() != to.file_size() {
+        bail!(
+            "The challenge file holds {} bytes instead of {}.",
+            challenge.len(),
+            to.file_size()
+        );
+    }
+    for section in Section::ALL {
+        let input = &response[from.offset(section)..][..from.section_size(section)];
+        let output = &mut challenge[to.offset(section)..][..to.section_size(section)];
+        input
+            .par_chunks(from.point_size(section))
+            .zip(output.par_chunks_mut(to.point_size(section)))
+            .try_for_each(|(input, output)| {
+                if section.is_g2() {
+                    write_g2_on::<C>(
+                        &read_g2_on::<C>(input, Encoding::Compressed)?,
+                        Encoding::Uncompressed,
+                        output,
+                    );
+                } else {
+                    write_g1_on::<C>(
+                        &read_g1_on::<C>(input, Encoding::Compressed)?,
+                        Encoding::Uncompressed,
+                        output,
+                    );
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+            .map_err(|err| anyhow!("Unable to decompress {:?}: {}", section, err))?;
+    }
+    Ok(())
+}
+
+/// Reconstructs the challenge file at `challenge` from the response file at `response`, opened
+/// with `input`, both over `C` with `powers` powers of tau, and returns its Blake2b hash.
+#[inline]
+pub fn reconstruct<C>(
+    response: &Path,
+    challenge: &Path,
+    powers: usize,
+    input: &InputOptions,
+) -> Result<Hash64>
+where
+    C: Curve,
+{
+    let response_map = input.open(response)?.into_mmap()?;
+    let temporary = atomic::temporary_path(challenge);
+    let result = (|| {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temporary)?;
+        file.set_len(Layout::challenge_on::<C>(powers).file_size() as u64)?;
+        let mut map = unsafe { MmapMut::map_mut(&file)? };
+        info!("Hashing {:?}", response);
+        map[..HASH_SIZE].copy_from_slice(&calculate_hash(&response_map, HashAlgorithm::Blake2b));
+        info!("Decompressing {:?} into {:?}", response, challenge);
+        decompress::<C>(&response_map, &mut map, powers)?;
+        map.flush()?;
+        let hash = Hash64::try_from(calculate_hash(&map, HashAlgorithm::Blake2b).as_slice())?;
+        fs::rename(&temporary, challenge)?;
+        Ok(hash)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temporary);
+    }
+    result
+}
+
+/// Writes the first `reduced` powers of the `challenge` file over `C` with `powers` powers of tau
+/// to a new challenge file at `output`, with the same header, and returns its Blake2b hash.
+#[inline]
+pub fn extract<C>(challenge: &[u8], powers: usize, reduced: usize, output: &Path) -> Result<Hash64>
+where
+    C: Curve,
+{
+    let from = Layout::challenge_on::<C>(powers);
+    if challenge.len() != from.file_size() {
+        bail!(
+            "The challenge file holds {} bytes instead of {} for 2^{} powers.",
+            challenge.len(),
+            from.file_size(),
+            powers.trailing_zeros()
+        );
+    }
+    if !reduced.is_power_of_two() || reduced > powers {
+        bail!(
+            "Unable to extract {} powers out of 2^{}",
+            reduced,
+            powers.trailing_zeros()
+        );
+    }
+    let mut hasher = Blake2b512::new();
+    atomic::write_with(output, |file| {
+        let mut write = |bytes: &[u8]| {
+            hasher.update(bytes);
+            file.write_all(bytes)
+        };
+        for range in from.prefix_ranges(reduced) {
+            write(&challenge[range])?;
+        }
+        Ok(())
+    })?;
+    Ok(Hash64::try_from(&hasher.finalize()[..])?)
+}
+
+/// Fetches the hash in the header of the file served at `url`.
+#[inline]
+pub async fn fetch_header(client: &Client, url: &str) -> Result<Hash64> {
+    let range = ByteRange::Bounded {
+        start: 0,
+        end: HASH_SIZE as u64 - 1,
+    };
+    let header = fetch_range(client, url, range).await?;
+    Hash64::from_header(&header).ok_or_else(|| anyhow!("The file at '{}' is too short", url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{Scalar, G1, G2};
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::Bn254;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::UniformRand;
+
+    /// Checks that the reconstructed challenge file over `C` holds the hash of the response file
+    /// and its points uncompressed, and that extracting its first powers keeps its header.
+    fn reconstruct_on<C>()
+    where
+        C: Curve,
+    {
+        let powers = 4;
+        let (from, to) = (
+            Layout::response_on::<C>(powers),
+            Layout::challenge_on::<C>(powers),
+        );
+        let mut rng = rand::thread_rng();
+        let mut response = vec![7; from.file_size()];
+        let mut expected = vec![0; to.file_size()];
+        for section in Section::ALL {
+            for index in 0..section.len(powers) {
+                let scalar = Scalar::<C>::rand(&mut rng);
+                let (compressed, uncompressed) = (
+                    &mut response[from.point_range(section, index)],
+                    &mut expected[to.point_range(section, index)],
+                );
+                if section.is_g2() {
+                    let point = G2::<C>::prime_subgroup_generator()
+                        .mul(scalar)
+                        .into_affine();
+                    write_g2_on::<C>(&point, Encoding::Compressed, compressed);
+                    write_g2_on::<C>(&point, Encoding::Uncompressed, uncompressed);
+                } else {
+                    let point = G1::<C>::prime_subgroup_generator()
+                        .mul(scalar)
+                        .into_affine();
+                    write_g1_on::<C>(&point, Encoding::Compressed, compressed);
+                    write_g1_on::<C>(&point, Encoding::Uncompressed, uncompressed);
+                }
+            }
+        }
+        let dir = std::env::temp_dir().join(format!("ppot-verifier-transform-test-{}", C::KIND));
+        fs::create_dir_all(&dir).unwrap();
+        let (response_path, challenge_path) = (dir.join("response"), dir.join("challenge"));
+        fs::write(&response_path, &response).unwrap();
+        let hash = reconstruct::<C>(
+            &response_path,
+            &challenge_path,
+            powers,
+            &InputOptions::default(),
+        )
+        .unwrap();
+        let challenge = fs::read(&challenge_path).unwrap();
+        assert_eq!(
+            challenge[..HASH_SIZE],
+            calculate_hash(&response, HashAlgorithm::Blake2b)
+        );
+        assert_eq!(challenge[HASH_SIZE..], expected[HASH_SIZE..]);
+        assert_eq!(
+            hash.0.to_vec(),
+            calculate_hash(&challenge, HashAlgorithm::Blake2b)
+        );
+        response.pop();
+        assert!(decompress::<C>(&response, &mut expected, powers).is_err());
+        let reduced_path = dir.join("reduced");
+        let reduced_hash = extract::<C>(&challenge, powers, 2, &reduced_path).unwrap();
+        let reduced = fs::read(&reduced_path).unwrap();
+        let reduced_layout = Layout::challenge_on::<C>(2);
+        assert_eq!(reduced.len(), reduced_layout.file_size());
+        assert_eq!(reduced[..HASH_SIZE], challenge[..HASH_SIZE]);
+        for section in Section::ALL {
+            for index in 0..section.len(2) {
+                assert_eq!(
+                    reduced[reduced_layout.point_range(section, index)],
+                    challenge[to.point_range(section, index)]
+                );
+            }
+        }
+        assert_eq!(
+            reduced_hash.0.to_vec(),
+            calculate_hash(&reduced, HashAlgorithm::Blake2b)
+        );
+        assert!(extract::<C>(&challenge, powers, 8, &reduced_path).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    /// Checks the reconstruction of challenge files over both curves.
+    #[test]
+    fn reconstruct_challenge() {
+        reconstruct_on::<Bn254>();
+        reconstruct_on::<Bls12_381>();
+    }
+}
@@ -0,0 +1,149 @@
+//! Resume Validation
+//!
+//! When a download starts, the [`ETag`](ETAG) and [`Last-Modified`](LAST_MODIFIED) headers of the
+//! remote file are recorded next to the partial file, in [`validator_path`]. Resuming the download
+//! sends them back in an [`If-Range`](IF_RANGE) header so that the server only answers with the
+//! missing range if the file has not changed in the meantime, and with the whole file otherwise.
+
+use crate::{atomic, Result};
+use core::fmt;
+use reqwest::{
+    header::{ETAG, IF_RANGE, LAST_MODIFIED},
+    RequestBuilder, Response,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+/// Validator
+#[derive(Clone, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct Validator {
+    /// URL the partial file was downloaded from
+    pub url: String,
+
+    /// Entity tag of the remote file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub etag: Option<String>,
+
+    /// Modification date of the remote file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_modified: Option<String>,
+}
+
+impl Validator {
+    /// Reads the validator of the file served at `url` from the headers of `response`.
+    #[inline]
+    pub fn from_response(url: &str, response: &Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(String::from)
+        };
+        Self {
+            url: url.into(),
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    /// Returns the value of the [`IF_RANGE`] header matching `self`. Weak entity tags cannot be
+    /// used for range requests, in which case the modification date is used instead.
+    #[inline]
+    pub fn if_range(&self) -> Option<&str> {
+        self.etag
+            .as_deref()
+            .filter(|etag| !etag.starts_with("W/"))
+            .or(self.last_modified.as_deref())
+    }
+
+    /// Adds the [`IF_RANGE`] header matching `self` to `request`, if any.
+    #[inline]
+    pub fn apply(validator: Option<&Self>, request: RequestBuilder) -> RequestBuilder {
+        match validator.and_then(Self::if_range) {
+            Some(if_range) => request.header(IF_RANGE, if_range),
+            _ => request,
+        }
+    }
+
+    /// Loads the validator recorded for the partial file at `path`, returning `None` if there is
+    /// none or if it was recorded for a different URL than `url`, since mirrors do not share entity
+    /// tags.
+    #[inline]
+    pub async fn load(path: &Path, url: &str) -> Option<Self> {
+        let bytes = fs::read(validator_path(path)).await.ok()?;
+        serde_json::from_slice::<Self>(&bytes)
+            .ok()
+            .filter(|validator| validator.url == url)
+    }
+
+    /// Records `self` as the validator of the partial file at `path`.
+    #[inline]
+    pub async fn save(&self, path: &Path) -> Result {
+        atomic::write_async(validator_path(path), serde_json::to_vec(self)?).await
+    }
+
+    /// Removes the validator recorded for the file at `path` once it is complete.
+    #[inline]
+    pub async fn remove(path: &Path) -> Result {
+        match fs::remove_file(validator_path(path)).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the path of the file recording the validator of the partial download of `path`.
+#[inline]
+pub fn validator_path(path: &Path) -> PathBuf {
+    let mut validator_path = path.as_os_str().to_owned();
+    validator_path.push(".validator");
+    validator_path.into()
+}
+
+/// Remote File Changed Error
+///
+/// Returned when a server answers a validated range request with the whole file, meaning that the
+/// bytes already on disk belong to a different version of the file.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RemoteFileChanged {
+    /// URL of the file
+    pub url: String,
+}
+
+impl fmt::Display for RemoteFileChanged {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The file at '{}' changed since the download started",
+            self.url
+        )
+    }
+}
+
+impl std::error::Error for RemoteFileChanged {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that weak entity tags are skipped in favor of the modification date.
+    #[test]
+    fn if_range_prefers_strong_etag() {
+        let mut validator = Validator {
+            url: "https://example.com/challenge_0001".into(),
+            etag: Some("\"0x8D9\"".into()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".into()),
+        };
+        assert_eq!(validator.if_range(), Some("\"0x8D9\""));
+        validator.etag = Some("W/\"0x8D9\"".into());
+        assert_eq!(validator.if_range(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        validator.last_modified = None;
+        assert_eq!(validator.if_range(), None);
+    }
+}
@@ -0,0 +1,268 @@
+//! Lazy Accumulator Queries
+//!
+//! Applications needing a handful of points of the ceremony, for a verifier key or the first
+//! powers of a small circuit, should not have to read gigabytes of a challenge file to get them.
+//! An [`AccumulatorView`] maps the file and decodes single points on demand at their offset in its
+//! [`Layout`], so that only the pages holding the requested points are read from disk. Like the
+//! rest of [`format`](crate::format), the functions ending in `_on` take the curve as a parameter,
+//! the others are over BN254.
+
+use crate::{
+    curve::{Curve, G1, G2},
+    format::{read_g1_on, read_g2_on, Encoding, Layout, Section, HASH_SIZE},
+    hash::Hash64,
+    Result,
+};
+use anyhow::ensure;
+use ark_bn254::Bn254;
+use core::{marker::PhantomData, ops::Range};
+use memmap::Mmap;
+use rayon::prelude::*;
+use std::{fs::File, path::Path};
+
+/// Read-Only View of the Points of an Accumulator File
+pub struct AccumulatorView<B = Mmap, C = Bn254> {
+    /// Bytes of the file
+    bytes: B,
+
+    /// Layout of the file
+    layout: Layout,
+
+    /// Curve of the points
+    __: PhantomData<C>,
+}
+
+impl AccumulatorView {
+    /// Maps the BN254 file at `path` holding `powers` powers of tau encoded with `encoding`.
+    #[inline]
+    pub fn open<P>(path: P, encoding: Encoding, powers: usize) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::open_on(path, encoding, powers)
+    }
+}
+
+impl<C> AccumulatorView<Mmap, C>
+where
+    C: Curve,
+{
+    /// Maps the file over `C` at `path` holding `powers` powers of tau encoded with `encoding`.
+    #[inline]
+    pub fn open_on<P>(path: P, encoding: Encoding, powers: usize) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = File::open(path)?;
+        // SAFETY: The map is read-only and the ceremony files are not modified once written.
+        let map = unsafe { Mmap::map(&file)? };
+        Self::new_on(map, encoding, powers)
+    }
+}
+
+impl<B> AccumulatorView<B>
+where
+    B: AsRef<[u8]>,
+{
+    /// Builds a view of the BN254 file `bytes` holding `powers` powers of tau encoded with
+    /// `encoding`.
+    #[inline]
+    pub fn new(bytes: B, encoding: Encoding, powers: usize) -> Result<Self> {
+        Self::new_on(bytes, encoding, powers)
+    }
+}
+
+impl<B, C> AccumulatorView<B, C>
+where
+    B: AsRef<[u8]>,
+    C: Curve,
+{
+    /// Builds a view of the file over `C` `bytes` holding `powers` powers of tau encoded with
+    /// `encoding`, checking its size.
+    #[inline]
+    pub fn new_on(bytes: B, encoding: Encoding, powers: usize) -> Result<Self> {
+        let layout = Layout {
+            powers,
+            encoding,
+            field_size: C::FIELD_SIZE,
+        };
+        ensure!(
+            bytes.as_ref().len() == layout.file_size(),
+            "The file holds {} bytes instead of {} for 2^{} {:?} powers.",
+            bytes.as_ref().len(),
+            layout.file_size(),
+            powers.trailing_zeros(),
+            encoding
+        );
+        Ok(Self {
+            bytes,
+            layout,
+            __: PhantomData,
+        })
+    }
+
+    /// Returns the layout of the file.
+    #[inline]
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    /// Returns the number of powers of tau of the accumulator.
+    #[inline]
+    pub fn powers(&self) -> usize {
+        self.layout.powers
+    }
+
+    /// Returns the hash starting the file, the Blake2b hash of the file it was computed from.
+    #[inline]
+    pub fn hash(&self) -> Hash64 {
+        let mut hash = [0; HASH_SIZE];
+        hash.copy_from_slice(&self.bytes.as_ref()[..HASH_SIZE]);
+        Hash64(hash)
+    }
+
+    /// Returns the bytes of the point at `index` of `section`.
+    #[inline]
+    fn point(&self, section: Section, index: usize) -> Result<&[u8]> {
+        let len = section.len(self.layout.powers);
+        ensure!(
+            index < len,
+            "Index {} is out of the {} points of {:?}",
+            index,
+            len,
+            section
+        );
+        Ok(&self.bytes.as_ref()[self.layout.point_range(section, index)])
+    }
+
+    /// Reads the point at `index` of the G1 `section`.
+    #[inline]
+    pub fn g1(&self, section: Section, index: usize) -> Result<G1<C>> {
+        ensure!(!section.is_g2(), "The points of {:?} are in G2", section);
+        read_g1_on::<C>(self.point(section, index)?, self.layout.encoding)
+    }
+
+    /// Reads the point at `index` of the G2 `section`.
+    #[inline]
+    pub fn g2(&self, section: Section, index: usize) -> Result<G2<C>> {
+        ensure!(section.is_g2(), "The points of {:?} are in G1", section);
+        read_g2_on::<C>(self.point(section, index)?, self.layout.encoding)
+    }
+
+    /// Reads the points at `range` of the G1 `section`, in parallel.
+    #[inline]
+    pub fn g1_range(&self, section: Section, range: Range<usize>) -> Result<Vec<G1<C>>>
+    where
+        B: Sync,
+    {
+        range
+            .into_par_iter()
+            .map(|index| self.g1(section, index))
+            .collect()
+    }
+
+    /// Reads the points at `range` of the G2 `section`, in parallel.
+    #[inline]
+    pub fn g2_range(&self, section: Section, range: Range<usize>) -> Result<Vec<G2<C>>>
+    where
+        B: Sync,
+    {
+        range
+            .into_par_iter()
+            .map(|index| self.g2(section, index))
+            .collect()
+    }
+
+    /// Reads `tau^i` in G1.
+    #[inline]
+    pub fn g1_power(&self, i: usize) -> Result<G1<C>> {
+        self.g1(Section::TauG1, i)
+    }
+
+    /// Reads `tau^i` in G2.
+    #[inline]
+    pub fn g2_power(&self, i: usize) -> Result<G2<C>> {
+        self.g2(Section::TauG2, i)
+    }
+
+    /// Reads `alpha * tau^i` in G1.
+    #[inline]
+    pub fn alpha_g1(&self, i: usize) -> Result<G1<C>> {
+        self.g1(Section::AlphaG1, i)
+    }
+
+    /// Reads `beta * tau^i` in G1.
+    #[inline]
+    pub fn beta_g1(&self, i: usize) -> Result<G1<C>> {
+        self.g1(Section::BetaG1, i)
+    }
+
+    /// Reads `beta` in G2.
+    #[inline]
+    pub fn beta_g2(&self) -> Result<G2<C>> {
+        self.g2(Section::BetaG2, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::{write_g1, write_g2};
+    use ark_bn254::{Fr, G1Affine, G2Affine};
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::{Field, UniformRand};
+    use std::fs;
+
+    /// Checks that the points read on demand from mapped challenge and response files and from
+    /// their bytes are the powers they were written from, and that out of range queries fail.
+    #[test]
+    fn points_are_read_on_demand() {
+        let powers = 8;
+        let mut rng = rand::thread_rng();
+        let tau = Fr::rand(&mut rng);
+        for encoding in [Encoding::Uncompressed, Encoding::Compressed] {
+            let layout = Layout {
+                powers,
+                encoding,
+                ..Layout::challenge(powers)
+            };
+            let mut bytes = vec![7; layout.file_size()];
+            for section in [Section::TauG1, Section::TauG2] {
+                for index in 0..section.len(powers) {
+                    let scalar = tau.pow([index as u64]);
+                    let out = &mut bytes[layout.point_range(section, index)];
+                    if section.is_g2() {
+                        let point = G2Affine::prime_subgroup_generator().mul(scalar);
+                        write_g2(&point.into_affine(), encoding, out);
+                    } else {
+                        let point = G1Affine::prime_subgroup_generator().mul(scalar);
+                        write_g1(&point.into_affine(), encoding, out);
+                    }
+                }
+            }
+            let path = std::env::temp_dir().join("ppot-verifier-view-test");
+            fs::write(&path, &bytes).unwrap();
+            let view = AccumulatorView::open(&path, encoding, powers).unwrap();
+            assert_eq!(view.hash(), Hash64([7; 64]));
+            assert_eq!(
+                view.g1_power(5).unwrap(),
+                G1Affine::prime_subgroup_generator()
+                    .mul(tau.pow([5]))
+                    .into_affine()
+            );
+            assert_eq!(
+                view.g2_power(1).unwrap(),
+                G2Affine::prime_subgroup_generator().mul(tau).into_affine()
+            );
+            let range = view.g1_range(Section::TauG1, 2..4).unwrap();
+            assert_eq!(range[1], view.g1_power(3).unwrap());
+            assert!(view.g1_power(2 * powers - 1).is_err());
+            assert!(view.g1(Section::TauG2, 0).is_err());
+            let tau_g2 = view.g2_power(3).unwrap();
+            fs::remove_file(&path).unwrap();
+            let view = AccumulatorView::new(&bytes, encoding, powers).unwrap();
+            assert_eq!(view.g2_power(3).unwrap(), tau_g2);
+            assert!(AccumulatorView::new(&bytes[1..], encoding, powers).is_err());
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Browser Bindings
+//!
+//! With the `wasm` feature, and without the default `native` one, the crate builds for
+//! `wasm32-unknown-unknown` and exposes the verification of a single round to JavaScript, so that
+//! anyone can spot-check the ceremony from a web page. The page downloads nothing but the byte
+//! ranges given by [`prefix_ranges`] of the challenge and response files of the round, with range
+//! requests, and concatenates them into the files cut down to their first powers, which
+//! [`verify_transform`] checks. The hash chain is checked by streaming the previous response file,
+//! or any file whose hash is asserted by the next one, through [`Blake2b`] and comparing the hash
+//! to the one read by [`challenge_hash`].
+
+use crate::{
+    accumulator::verify_round_on,
+    curve::{Curve, CurveKind},
+    format::Layout,
+    hash::Hash64,
+};
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use blake2::{Blake2b512, Digest};
+use clap::ValueEnum;
+use wasm_bindgen::prelude::*;
+
+/// Converts `err` into an exception thrown to JavaScript.
+#[inline]
+fn js_error(err: anyhow::Error) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Parses the name of a curve, `bn254` or `bls12-381`.
+#[inline]
+fn parse_curve(curve: &str) -> Result<CurveKind, JsError> {
+    CurveKind::from_str(curve, true).map_err(|err| JsError::new(&err))
+}
+
+/// Returns the layout of the challenge or `response` files over `C` with `powers` powers of tau.
+#[inline]
+fn layout<C>(response: bool, powers: usize) -> Layout
+where
+    C: Curve,
+{
+    if response {
+        Layout::response_on::<C>(powers)
+    } else {
+        Layout::challenge_on::<C>(powers)
+    }
+}
+
+/// Returns the byte ranges of a challenge or `response` file over `curve` with `2^file_log_powers`
+/// powers of tau which hold its first `2^log_powers` powers, as the start and the end of each
+/// range in turn.
+#[wasm_bindgen(js_name = prefixRanges)]
+pub fn prefix_ranges(
+    curve: &str,
+    response: bool,
+    file_log_powers: u32,
+    log_powers: u32,
+) -> Result<Vec<f64>, JsError> {
+    if log_powers > file_log_powers {
+        return Err(JsError::new("The file holds fewer powers than requested"));
+    }
+    let layout = match parse_curve(curve)? {
+        CurveKind::Bn254 => layout::<Bn254>(response, 1 << file_log_powers),
+        CurveKind::Bls12_381 => layout::<Bls12_381>(response, 1 << file_log_powers),
+    };
+    Ok(layout
+        .prefix_ranges(1 << log_powers)
+        .into_iter()
+        .flat_map(|range| [range.start as f64, range.end as f64])
+        .collect())
+}
+
+/// Returns the hash of the challenge file asserted by the header of the `response` file.
+#[wasm_bindgen(js_name = challengeHash)]
+pub fn challenge_hash(response: &[u8]) -> Result<Vec<u8>, JsError> {
+    Hash64::from_header(response)
+        .map(|hash| hash.0.to_vec())
+        .ok_or_else(|| JsError::new("The response file is too short"))
+}
+
+/// Verifies that the `response` file over `curve` is the transform of the `challenge` file, both
+/// cut down to their first `2^log_powers` powers of tau.
+#[wasm_bindgen(js_name = verifyTransform)]
+pub fn verify_transform(
+    curve: &str,
+    challenge: &[u8],
+    response: &[u8],
+    log_powers: u32,
+) -> Result<(), JsError> {
+    let powers = 1 << log_powers;
+    verify_round_on(parse_curve(curve)?, challenge, response, powers, powers).map_err(js_error)
+}
+
+/// Blake2b Hasher
+///
+/// Hashes a file fed in chunks as it streams, the ceremony chaining its files with their Blake2b
+/// hashes.
+#[wasm_bindgen]
+#[derive(Clone, Debug, Default)]
+pub struct Blake2b(Blake2b512);
+
+#[wasm_bindgen]
+impl Blake2b {
+    /// Builds a new hasher.
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds `chunk` into the hasher.
+    pub fn update(&mut self, chunk: &[u8]) {
+        self.0.update(chunk);
+    }
+
+    /// Returns the hash of all the chunks fed into the hasher.
+    pub fn finalize(self) -> Vec<u8> {
+        self.0.finalize().to_vec()
+    }
+}
@@ -0,0 +1,144 @@
+//! Watch Mode
+//!
+//! The ceremony keeps growing after a verification run, so `ppot watch` polls the registry sources
+//! for new rounds and runs the rest of the pipeline on them: the `downloader` and the `verify_ppot`
+//! binaries resume from the verification checkpoint, see [`checkpoint`](crate::checkpoint), and
+//! the `hasher` only hashes the files which changed. Each [`Stage`] runs its binary as a child
+//! process, installed next to the `ppot` binary, with the storage and logging options of the
+//! parent.
+
+use crate::{
+    log::{LogFormat, LogOptions},
+    signal::INTERRUPTED_EXIT_CODE,
+    storage::{StorageOptions, CACHE_DIR_ENV, DIR_ENV, STATE_DIR_ENV},
+    Result,
+};
+use anyhow::{anyhow, bail};
+use core::time::Duration;
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use tokio::process::Command;
+use tracing::info;
+
+/// Default interval between two polls for new rounds
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Pipeline Stage
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Stage {
+    /// Downloads the files of the rounds which are not verified yet
+    Download,
+
+    /// Hashes the files which changed since they were last hashed
+    Hash,
+
+    /// Verifies the rounds which are not verified yet
+    Verify,
+}
+
+impl Stage {
+    /// Stages in the order they run
+    pub const ALL: [Self; 3] = [Self::Download, Self::Hash, Self::Verify];
+
+    /// Returns the name of the binary running the stage.
+    #[inline]
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Self::Download => "downloader",
+            Self::Hash => "hasher",
+            Self::Verify => "verify_ppot",
+        }
+    }
+
+    /// Returns the arguments of the binary running the stage, besides the logging options.
+    #[inline]
+    pub fn arguments(&self) -> &'static [&'static str] {
+        match self {
+            Self::Download | Self::Verify => &["--incremental"],
+            Self::Hash => &[],
+        }
+    }
+
+    /// Runs the stage to completion with the `storage` and `log` options of the parent.
+    #[inline]
+    pub async fn run(&self, storage: &StorageOptions, log: &LogOptions) -> Result {
+        let binary = sibling_binary(self.binary())?;
+        info!("Running {}", binary.display());
+        let mut command = Command::new(&binary);
+        command.args(self.arguments()).args(log_arguments(log));
+        set_storage_env(&mut command, storage);
+        let status = command
+            .status()
+            .await
+            .map_err(|err| anyhow!("Unable to run {}: {}", binary.display(), err))?;
+        match status.code() {
+            Some(0) => Ok(()),
+            Some(INTERRUPTED_EXIT_CODE) => bail!("{} was interrupted.", self.binary()),
+            _ => bail!("{} failed with {}.", self.binary(), status),
+        }
+    }
+}
+
+/// Returns the path of the binary called `name` installed next to the current one.
+#[inline]
+pub fn sibling_binary(name: &str) -> Result<PathBuf> {
+    let current = env::current_exe()?;
+    let dir = current.parent().unwrap_or_else(|| Path::new("."));
+    Ok(dir.join(format!("{}{}", name, env::consts::EXE_SUFFIX)))
+}
+
+/// Returns the command line arguments reproducing the logging options `log`.
+#[inline]
+pub fn log_arguments(log: &LogOptions) -> Vec<OsString> {
+    let mut arguments = Vec::new();
+    if log.quiet {
+        arguments.push("--quiet".into());
+    }
+    for _ in 0..log.verbose {
+        arguments.push("--verbose".into());
+    }
+    if log.log_format == LogFormat::Json {
+        arguments.push("--log-format=json".into());
+    }
+    arguments
+}
+
+/// Passes the storage options `storage` to `command` through their environment variables.
+#[inline]
+fn set_storage_env(command: &mut Command, storage: &StorageOptions) {
+    command.env(DIR_ENV, &storage.dir);
+    match &storage.state_dir {
+        Some(state_dir) => command.env(STATE_DIR_ENV, state_dir),
+        _ => command.env_remove(STATE_DIR_ENV),
+    };
+    match &storage.cache_dir {
+        Some(cache_dir) => command.env(CACHE_DIR_ENV, cache_dir),
+        _ => command.env_remove(CACHE_DIR_ENV),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks that the logging options are forwarded to the stages.
+    #[test]
+    fn forward_log_options() {
+        assert!(log_arguments(&LogOptions::default()).is_empty());
+        let log = LogOptions {
+            quiet: false,
+            verbose: 2,
+            log_format: LogFormat::Json,
+        };
+        assert_eq!(
+            log_arguments(&log),
+            ["--verbose", "--verbose", "--log-format=json"]
+        );
+        assert!(sibling_binary("hasher")
+            .unwrap()
+            .starts_with(env::current_exe().unwrap().parent().unwrap()));
+    }
+}
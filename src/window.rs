@@ -0,0 +1,237 @@
+//! Bounded Disk Window
+//!
+//! The whole ceremony takes about 14 TB, but verifying a round only needs its challenge file, its
+//! response file and the next challenge file. With a disk budget, the verifier downloads the files
+//! ahead of the round it is verifying in a background thread, as long as they fit in a [`Window`]
+//! of the budget, and deletes the files of each round once they are hashed and the round is
+//! verified, which makes room for the next downloads.
+
+use crate::{
+    download::{download_file, file_exists, DownloadOptions},
+    progress::ProgressBars,
+    Result,
+};
+use anyhow::anyhow;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+};
+use tracing::{info, warn};
+
+/// Window State
+#[derive(Debug, Default)]
+struct WindowState {
+    /// Number of bytes reserved by the files on disk or being downloaded
+    used: u64,
+
+    /// Files completely downloaded
+    ready: HashSet<PathBuf>,
+
+    /// Files whose download failed, with the error
+    failed: HashMap<PathBuf, String>,
+
+    /// Files the verifier is waiting for, admitted even if they do not fit
+    required: HashSet<PathBuf>,
+}
+
+/// Disk Window
+#[derive(Debug)]
+pub struct Window {
+    /// Disk budget in bytes
+    budget: u64,
+
+    /// Window State
+    state: Mutex<WindowState>,
+
+    /// Notified when the state changes
+    changed: Condvar,
+}
+
+impl Window {
+    /// Builds a [`Window`] keeping the files within `budget` bytes.
+    #[inline]
+    pub fn new(budget: u64) -> Self {
+        Self {
+            budget,
+            state: Default::default(),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// Locks the state of the window.
+    #[inline]
+    fn state(&self) -> MutexGuard<'_, WindowState> {
+        self.state.lock().expect("window lock is never poisoned")
+    }
+
+    /// Returns the number of bytes reserved in the window.
+    #[inline]
+    pub fn used(&self) -> u64 {
+        self.state().used
+    }
+
+    /// Reserves `size` bytes for the file at `path`, waiting for other files to be released until
+    /// they fit. Files the verifier waits for are admitted right away, so that a budget too small
+    /// for the files of a round is exceeded rather than never making progress.
+    #[inline]
+    pub fn reserve(&self, path: &Path, size: u64) {
+        let mut state = self.state();
+        while state.used > 0 && state.used + size > self.budget && !state.required.contains(path) {
+            state = self
+                .changed
+                .wait(state)
+                .expect("window lock is never poisoned");
+        }
+        if state.used + size > self.budget {
+            warn!("Exceeding the disk budget to download {}", path.display());
+        }
+        state.used += size;
+    }
+
+    /// Releases `size` bytes, once the file holding them has been deleted.
+    #[inline]
+    pub fn release(&self, size: u64) {
+        let mut state = self.state();
+        state.used = state.used.saturating_sub(size);
+        self.changed.notify_all();
+    }
+
+    /// Marks the file at `path` as completely downloaded.
+    #[inline]
+    pub fn mark_ready(&self, path: &Path) {
+        self.state().ready.insert(path.into());
+        self.changed.notify_all();
+    }
+
+    /// Marks the download of the file at `path` as failed with `error`.
+    #[inline]
+    pub fn mark_failed(&self, path: &Path, error: String) {
+        self.state().failed.insert(path.into(), error);
+        self.changed.notify_all();
+    }
+
+    /// Waits for the file at `path` to be completely downloaded.
+    #[inline]
+    pub fn wait_ready(&self, path: &Path) -> Result {
+        let mut state = self.state();
+        state.required.insert(path.into());
+        self.changed.notify_all();
+        loop {
+            if state.ready.contains(path) {
+                return Ok(());
+            }
+            if let Some(error) = state.failed.get(path) {
+                return Err(anyhow!("Unable to download {}: {}", path.display(), error));
+            }
+            state = self
+                .changed
+                .wait(state)
+                .expect("window lock is never poisoned");
+        }
+    }
+
+    /// Deletes the file at `path` and releases its bytes.
+    #[inline]
+    pub fn remove(&self, path: &Path) -> Result {
+        let size = fs::metadata(path)?.len();
+        fs::remove_file(path)?;
+        let mut state = self.state();
+        state.ready.remove(path);
+        state.required.remove(path);
+        drop(state);
+        self.release(size);
+        info!("Deleted {} to free disk space", path.display());
+        Ok(())
+    }
+}
+
+/// File Downloaded Ahead
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct PrefetchFile {
+    /// Path to save the file to
+    pub path: PathBuf,
+
+    /// Mirrors serving the file
+    pub urls: Vec<String>,
+
+    /// Size of the file, if known
+    pub size: Option<u64>,
+}
+
+/// Downloads `files` in order on a background thread with `options`, reserving their size in
+/// `window` before each download. Files already on disk with their expected size are kept as is.
+#[inline]
+pub fn spawn_prefetch(
+    window: Arc<Window>,
+    files: Vec<PrefetchFile>,
+    options: DownloadOptions,
+) -> Result<JoinHandle<()>> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    let client = options.client()?;
+    Ok(thread::spawn(move || {
+        let progress = ProgressBars::default();
+        runtime.block_on(async {
+            for file in files {
+                let size = match file.size {
+                    Some(size) => Some(size),
+                    _ => match file.urls.first() {
+                        Some(url) => file_exists(&client, url).await.ok().and_then(|i| i.size),
+                        _ => None,
+                    },
+                };
+                let existing = fs::metadata(&file.path).ok().map(|metadata| metadata.len());
+                if size.is_none() {
+                    warn!("Unknown size of {}", file.path.display());
+                }
+                window.reserve(&file.path, size.or(existing).unwrap_or_default());
+                if existing.is_some() && existing == size {
+                    window.mark_ready(&file.path);
+                    continue;
+                }
+                match download_file(&progress, &client, &file.urls, &file.path, &options).await {
+                    Ok(()) => window.mark_ready(&file.path),
+                    Err(err) => window.mark_failed(&file.path, err.to_string()),
+                }
+                if options.cancel.is_cancelled() {
+                    return;
+                }
+            }
+        })
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::time::Duration;
+
+    /// Checks that reservations wait for enough bytes to be released.
+    #[test]
+    fn reservations_wait_for_releases() {
+        let window = Arc::new(Window::new(100));
+        let path = Path::new("challenge_0001");
+        window.reserve(path, 60);
+        window.reserve(path, 40);
+        let reserving = {
+            let window = window.clone();
+            thread::spawn(move || window.reserve(Path::new("response_0002"), 50))
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(window.used(), 100);
+        window.release(60);
+        reserving.join().unwrap();
+        assert_eq!(window.used(), 90);
+        window.release(90);
+        window.reserve(path, 500);
+        assert_eq!(window.used(), 500);
+        window.mark_failed(path, "not found".into());
+        assert!(window.wait_ready(path).is_err());
+        window.reserve(path, 10);
+        assert_eq!(window.used(), 510);
+    }
+}
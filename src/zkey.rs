@@ -0,0 +1,691 @@
+//! snarkjs `.zkey` Files
+//!
+//! snarkjs stores the Groth16 proving key of a circuit in a `.zkey` file, made of sections like
+//! `.ptau` files: the protocol, the sizes of the circuit with its verifying key, the input query,
+//! the coefficients of the `A` and `B` matrices, the `A`, `B` and `C` queries, the `H` query and the
+//! phase 2 contributions. Points are written little-endian in Montgomery form as in `.ptau` files,
+//! and the coefficients are written multiplied by the square of the Montgomery radix.
+//!
+//! snarkjs follows the phase 2 of bellman, see [`phase2`](crate::phase2): the hash of the initial
+//! parameters is the hash of their bellman serialization, without filtering the points at infinity
+//! out of the `A` and `B` queries, and each contribution proves knowledge of its secret against the
+//! same transcript. Only the `H` query differs, holding the odd elements of the Lagrange basis of
+//! the domain twice the size of the circuit domain, with the roots of unity of
+//! [`Domain::Arkworks`]. [`Zkey::initial`] recomputes the initial parameters of a circuit from an
+//! accumulator, and [`Zkey::verify`] checks that a `.zkey` file derives from them.
+
+use crate::{
+    fft::{ifft_with_root, Domain},
+    format::{Section, HASH_SIZE},
+    hash::Hash64,
+    into_array_unchecked,
+    phase2::{self, combine, Evaluations, Parameters, Phase1Radix, VerifyingKey},
+    ptau::{self, parse_sections, read_params, read_u32, G1_SIZE, G2_SIZE},
+    r1cs::R1cs,
+    Result,
+};
+use anyhow::{anyhow, bail, ensure};
+use ark_bn254::{Fq, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::{BigInteger, BigInteger256, Field, FpParameters, One, PrimeField, Zero};
+use blake2::{Blake2b512, Digest};
+use core::ops::Range;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+/// Magic bytes starting every `.zkey` file
+pub const ZKEY_MAGIC: &[u8; 4] = b"zkey";
+
+/// Protocol identifier of Groth16
+const GROTH16: u32 = 1;
+
+/// Size of a field element
+const FIELD_SIZE: usize = 32;
+
+/// Size of a coefficient record: the matrix, the constraint, the wire and the coefficient
+const COEFFICIENT_SIZE: usize = 12 + FIELD_SIZE;
+
+/// Type of the protocol section
+const HEADER_SECTION: u32 = 1;
+
+/// Type of the Groth16 header section
+const GROTH16_HEADER_SECTION: u32 = 2;
+
+/// Type of the input query section
+const IC_SECTION: u32 = 3;
+
+/// Type of the coefficients section
+const COEFFICIENTS_SECTION: u32 = 4;
+
+/// Type of the `A` query section
+const A_SECTION: u32 = 5;
+
+/// Type of the `B` query section in G1
+const B_G1_SECTION: u32 = 6;
+
+/// Type of the `B` query section in G2
+const B_G2_SECTION: u32 = 7;
+
+/// Type of the `C` query section
+const C_SECTION: u32 = 8;
+
+/// Type of the `H` query section
+const H_SECTION: u32 = 9;
+
+/// Type of the contributions section
+const CONTRIBUTIONS_SECTION: u32 = 10;
+
+/// Coefficient of the `A` or `B` Matrix
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Coefficient {
+    /// Matrix holding the coefficient, `0` for `A` and `1` for `B`
+    pub matrix: u32,
+
+    /// Constraint of the coefficient
+    pub constraint: u32,
+
+    /// Wire of the coefficient
+    pub wire: u32,
+
+    /// Value of the coefficient
+    pub value: Fr,
+}
+
+/// Phase 2 Contribution of a `.zkey` File
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Contribution {
+    /// Public key of the contribution, as in bellman
+    pub key: phase2::Contribution,
+
+    /// Whether the contribution is a random beacon
+    pub beacon: bool,
+
+    /// Name of the participant
+    pub name: Option<String>,
+}
+
+/// `.zkey` File
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Zkey {
+    /// Number of wires, including the constant one
+    pub wires: usize,
+
+    /// Number of public outputs and inputs
+    pub public: usize,
+
+    /// Size of the domain of the circuit
+    pub domain_size: usize,
+
+    /// Verifying key
+    pub vk: VerifyingKey,
+
+    /// Coefficients of the `A` and `B` matrices
+    pub coefficients: Vec<Coefficient>,
+
+    /// `A` polynomials of every wire in G1
+    pub a: Vec<G1Affine>,
+
+    /// `B` polynomials of every wire in G1
+    pub b_g1: Vec<G1Affine>,
+
+    /// `B` polynomials of every wire in G2
+    pub b_g2: Vec<G2Affine>,
+
+    /// Query of the private wires, divided by delta
+    pub c: Vec<G1Affine>,
+
+    /// Query of the quotient polynomial, divided by delta
+    pub h: Vec<G1Affine>,
+
+    /// Hash of the initial parameters
+    pub cs_hash: Hash64,
+
+    /// Contributions, in order
+    pub contributions: Vec<Contribution>,
+}
+
+/// Returns the square of the Montgomery radix of the scalar field, which multiplies the
+/// coefficients of `.zkey` files.
+#[inline]
+fn radix_squared() -> Fr {
+    Fr::from_repr(<Fr as PrimeField>::Params::R2).expect("R^2 is reduced")
+}
+
+/// Reads a G2 point as written in `.ptau` files, checking that it is in the subgroup.
+#[inline]
+fn read_g2_checked(bytes: &[u8]) -> Result<G2Affine> {
+    let point = ptau::read_g2(bytes)?;
+    ensure!(
+        point.is_in_correct_subgroup_assuming_on_curve(),
+        "G2 point out of the subgroup"
+    );
+    Ok(point)
+}
+
+/// Reads the `len` G1 points of `bytes`.
+#[inline]
+fn read_g1s(bytes: &[u8], len: usize) -> Result<Vec<G1Affine>> {
+    ensure!(
+        bytes.len() == len * G1_SIZE,
+        "The section holds {} bytes instead of {}",
+        bytes.len(),
+        len * G1_SIZE
+    );
+    bytes.par_chunks(G1_SIZE).map(ptau::read_g1).collect()
+}
+
+/// Reads the `len` G2 points of `bytes`.
+#[inline]
+fn read_g2s(bytes: &[u8], len: usize) -> Result<Vec<G2Affine>> {
+    ensure!(
+        bytes.len() == len * G2_SIZE,
+        "The section holds {} bytes instead of {}",
+        bytes.len(),
+        len * G2_SIZE
+    );
+    bytes.par_chunks(G2_SIZE).map(ptau::read_g2).collect()
+}
+
+/// Appends the G1 `points` as written in `.ptau` files to `out`.
+#[inline]
+fn push_g1s(out: &mut Vec<u8>, points: &[G1Affine]) {
+    let start = out.len();
+    out.resize(start + points.len() * G1_SIZE, 0);
+    out[start..]
+        .par_chunks_mut(G1_SIZE)
+        .zip(points)
+        .for_each(|(out, point)| ptau::write_g1(point, out));
+}
+
+/// Appends the G2 `points` as written in `.ptau` files to `out`.
+#[inline]
+fn push_g2s(out: &mut Vec<u8>, points: &[G2Affine]) {
+    let start = out.len();
+    out.resize(start + points.len() * G2_SIZE, 0);
+    out[start..]
+        .par_chunks_mut(G2_SIZE)
+        .zip(points)
+        .for_each(|(out, point)| ptau::write_g2(point, out));
+}
+
+impl Zkey {
+    /// Reads the Groth16 `.zkey` file in `data`.
+    #[inline]
+    pub fn read(data: &[u8]) -> Result<Self> {
+        ensure!(data.starts_with(ZKEY_MAGIC), "Not a .zkey file");
+        let (_, ranges) = parse_sections(data)?;
+        let section = |kind: u32| {
+            ranges
+                .get(&kind)
+                .map(|range: &Range<usize>| &data[range.clone()])
+                .ok_or_else(|| anyhow!("Missing section {}", kind))
+        };
+        let protocol = read_u32(section(HEADER_SECTION)?, 0)?;
+        ensure!(protocol == GROTH16, "Unsupported protocol {}", protocol);
+        let header = section(GROTH16_HEADER_SECTION)?;
+        let modulus = |offset: usize, expected: Vec<u8>| -> Result {
+            ensure!(
+                read_u32(header, offset)? as usize == FIELD_SIZE
+                    && header.get(offset + 4..offset + 4 + FIELD_SIZE) == Some(&expected[..]),
+                "The file is not over BN254"
+            );
+            Ok(())
+        };
+        modulus(0, <Fq as PrimeField>::Params::MODULUS.to_bytes_le())?;
+        modulus(
+            4 + FIELD_SIZE,
+            <Fr as PrimeField>::Params::MODULUS.to_bytes_le(),
+        )?;
+        let offset = 8 + 2 * FIELD_SIZE;
+        let wires = read_u32(header, offset)? as usize;
+        let public = read_u32(header, offset + 4)? as usize;
+        let domain_size = read_u32(header, offset + 8)? as usize;
+        ensure!(
+            public < wires && domain_size.is_power_of_two(),
+            "Invalid circuit sizes"
+        );
+        let points = header
+            .get(offset + 12..)
+            .filter(|points| points.len() == 3 * G1_SIZE + 3 * G2_SIZE)
+            .ok_or_else(|| anyhow!("The header section holds {} bytes", header.len()))?;
+        let vk = VerifyingKey {
+            alpha_g1: ptau::read_g1(points)?,
+            beta_g1: ptau::read_g1(&points[G1_SIZE..])?,
+            beta_g2: read_g2_checked(&points[2 * G1_SIZE..])?,
+            gamma_g2: read_g2_checked(&points[2 * G1_SIZE + G2_SIZE..])?,
+            delta_g1: ptau::read_g1(&points[2 * G1_SIZE + 2 * G2_SIZE..])?,
+            delta_g2: read_g2_checked(&points[3 * G1_SIZE + 2 * G2_SIZE..])?,
+            ic: read_g1s(section(IC_SECTION)?, public + 1)?,
+        };
+        let records = section(COEFFICIENTS_SECTION)?;
+        let count = read_u32(records, 0)? as usize;
+        ensure!(
+            records.len() == 4 + count * COEFFICIENT_SIZE,
+            "The coefficients section holds {} bytes instead of {}",
+            records.len(),
+            4 + count * COEFFICIENT_SIZE
+        );
+        let unscale = radix_squared().inverse().expect("R^2 is invertible");
+        let coefficients = records[4..]
+            .par_chunks(COEFFICIENT_SIZE)
+            .map(|record| {
+                let mut limbs = [0; 4];
+                for (limb, chunk) in limbs.iter_mut().zip(record[12..].chunks_exact(8)) {
+                    *limb = u64::from_le_bytes(chunk.try_into()?);
+                }
+                let value = Fr::from_repr(BigInteger256::new(limbs))
+                    .ok_or_else(|| anyhow!("Coefficient out of the scalar field"))?;
+                Ok(Coefficient {
+                    matrix: read_u32(record, 0)?,
+                    constraint: read_u32(record, 4)?,
+                    wire: read_u32(record, 8)?,
+                    value: value * unscale,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let records = section(CONTRIBUTIONS_SECTION)?;
+        let cs_hash = Hash64::try_from(
+            records
+                .get(..HASH_SIZE)
+                .ok_or_else(|| anyhow!("Truncated contributions section"))?,
+        )?;
+        let mut contributions = Vec::new();
+        let mut offset = HASH_SIZE + 4;
+        for _ in 0..read_u32(records, HASH_SIZE)? {
+            let record = records
+                .get(offset..offset + 3 * G1_SIZE + G2_SIZE + HASH_SIZE + 8)
+                .ok_or_else(|| anyhow!("Truncated contribution"))?;
+            let params_size = read_u32(record, record.len() - 4)? as usize;
+            let params = records
+                .get(offset + record.len()..offset + record.len() + params_size)
+                .ok_or_else(|| anyhow!("Truncated contribution parameters"))?;
+            contributions.push(Contribution {
+                key: phase2::Contribution {
+                    delta_after: ptau::read_g1(record)?,
+                    s: ptau::read_g1(&record[G1_SIZE..])?,
+                    s_delta: ptau::read_g1(&record[2 * G1_SIZE..])?,
+                    r_delta: read_g2_checked(&record[3 * G1_SIZE..])?,
+                    transcript: Hash64::try_from(&record[3 * G1_SIZE + G2_SIZE..][..HASH_SIZE])?,
+                },
+                beacon: read_u32(record, record.len() - 8)? == 1,
+                name: read_params(params)?.name,
+            });
+            offset += record.len() + params_size;
+        }
+        Ok(Self {
+            wires,
+            public,
+            domain_size,
+            vk,
+            coefficients,
+            a: read_g1s(section(A_SECTION)?, wires)?,
+            b_g1: read_g1s(section(B_G1_SECTION)?, wires)?,
+            b_g2: read_g2s(section(B_G2_SECTION)?, wires)?,
+            c: read_g1s(section(C_SECTION)?, wires - public - 1)?,
+            h: read_g1s(section(H_SECTION)?, domain_size)?,
+            cs_hash,
+            contributions,
+        })
+    }
+
+    /// Writes the `.zkey` file of the parameters to `out`.
+    #[inline]
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let mut header = Vec::new();
+        for modulus in [
+            <Fq as PrimeField>::Params::MODULUS.to_bytes_le(),
+            <Fr as PrimeField>::Params::MODULUS.to_bytes_le(),
+        ] {
+            header.extend_from_slice(&(FIELD_SIZE as u32).to_le_bytes());
+            header.extend_from_slice(&modulus);
+        }
+        for value in [self.wires, self.public, self.domain_size] {
+            header.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        push_g1s(&mut header, &[self.vk.alpha_g1, self.vk.beta_g1]);
+        push_g2s(&mut header, &[self.vk.beta_g2, self.vk.gamma_g2]);
+        push_g1s(&mut header, &[self.vk.delta_g1]);
+        push_g2s(&mut header, &[self.vk.delta_g2]);
+        let mut coefficients = (self.coefficients.len() as u32).to_le_bytes().to_vec();
+        let scale = radix_squared();
+        for coefficient in &self.coefficients {
+            for value in [coefficient.matrix, coefficient.constraint, coefficient.wire] {
+                coefficients.extend_from_slice(&value.to_le_bytes());
+            }
+            coefficients.extend_from_slice(&(coefficient.value * scale).into_repr().to_bytes_le());
+        }
+        let mut contributions = self.cs_hash.0.to_vec();
+        contributions.extend_from_slice(&(self.contributions.len() as u32).to_le_bytes());
+        for contribution in &self.contributions {
+            let key = &contribution.key;
+            push_g1s(&mut contributions, &[key.delta_after, key.s, key.s_delta]);
+            push_g2s(&mut contributions, &[key.r_delta]);
+            contributions.extend_from_slice(&key.transcript.0);
+            contributions.extend_from_slice(&u32::from(contribution.beacon).to_le_bytes());
+            let mut params = Vec::new();
+            if let Some(name) = &contribution.name {
+                let name = &name.as_bytes()[..name.len().min(64)];
+                params.extend_from_slice(&[1, name.len() as u8]);
+                params.extend_from_slice(name);
+            }
+            contributions.extend_from_slice(&(params.len() as u32).to_le_bytes());
+            contributions.extend_from_slice(&params);
+        }
+        let g1s = |points: &[G1Affine]| {
+            let mut bytes = Vec::new();
+            push_g1s(&mut bytes, points);
+            bytes
+        };
+        let mut b_g2 = Vec::new();
+        push_g2s(&mut b_g2, &self.b_g2);
+        let sections = [
+            (HEADER_SECTION, GROTH16.to_le_bytes().to_vec()),
+            (GROTH16_HEADER_SECTION, header),
+            (IC_SECTION, g1s(&self.vk.ic)),
+            (COEFFICIENTS_SECTION, coefficients),
+            (A_SECTION, g1s(&self.a)),
+            (B_G1_SECTION, g1s(&self.b_g1)),
+            (B_G2_SECTION, b_g2),
+            (C_SECTION, g1s(&self.c)),
+            (H_SECTION, g1s(&self.h)),
+            (CONTRIBUTIONS_SECTION, contributions),
+        ];
+        out.extend_from_slice(ZKEY_MAGIC);
+        out.extend_from_slice(&1u32.to_le_bytes());
+        out.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+        for (kind, section) in sections {
+            out.extend_from_slice(&kind.to_le_bytes());
+            out.extend_from_slice(&(section.len() as u64).to_le_bytes());
+            out.extend_from_slice(&section);
+        }
+    }
+
+    /// Computes the initial parameters of the circuit `r1cs` as snarkjs does, from the points of an
+    /// accumulator read by `g1` and `g2` by section and index.
+    #[inline]
+    pub fn initial<F, G>(g1: F, g2: G, r1cs: &R1cs) -> Result<Self>
+    where
+        F: Fn(Section, usize) -> Result<G1Affine> + Sync,
+        G: Fn(Section, usize) -> Result<G2Affine> + Sync,
+    {
+        let log_size = r1cs.domain_log_size();
+        let domain_size = 1 << log_size;
+        let phase1 = Phase1Radix::compute(&g1, &g2, log_size, Domain::Arkworks)?;
+        let Evaluations {
+            a_g1,
+            b_g1,
+            b_g2,
+            ic,
+            l,
+        } = phase1.evaluate(r1cs)?;
+        let mut h = (0..2 * domain_size)
+            .into_par_iter()
+            .map(|index| Ok(g1(Section::TauG1, index)?.into_projective()))
+            .collect::<Result<Vec<_>>>()?;
+        ifft_with_root(&mut h, Domain::Arkworks.root_of_unity(2 * domain_size)?)?;
+        let h = G1Projective::batch_normalization_into_affine(
+            &h.into_iter().skip(1).step_by(2).collect::<Vec<_>>(),
+        );
+        let vk = VerifyingKey {
+            alpha_g1: phase1.alpha_g1,
+            beta_g1: phase1.beta_g1,
+            beta_g2: phase1.beta_g2,
+            gamma_g2: G2Affine::prime_subgroup_generator(),
+            delta_g1: G1Affine::prime_subgroup_generator(),
+            delta_g2: G2Affine::prime_subgroup_generator(),
+            ic,
+        };
+        let params = Parameters {
+            vk,
+            h: phase1.h,
+            l,
+            a: a_g1,
+            b_g1,
+            b_g2,
+        };
+        let mut bytes = Vec::new();
+        params.write(&mut bytes);
+        let mut coefficients = Vec::new();
+        for (index, constraint) in r1cs.constraints.iter().enumerate() {
+            for (matrix, combination) in [(0, &constraint.a), (1, &constraint.b)] {
+                for (wire, value) in combination {
+                    coefficients.push(Coefficient {
+                        matrix,
+                        constraint: index as u32,
+                        wire: *wire as u32,
+                        value: *value,
+                    });
+                }
+            }
+        }
+        for input in 0..r1cs.inputs() {
+            coefficients.push(Coefficient {
+                matrix: 0,
+                constraint: (r1cs.constraints.len() + input) as u32,
+                wire: input as u32,
+                value: Fr::one(),
+            });
+        }
+        Ok(Self {
+            wires: r1cs.wires,
+            public: r1cs.public,
+            domain_size,
+            vk: params.vk,
+            coefficients,
+            a: params.a,
+            b_g1: params.b_g1,
+            b_g2: params.b_g2,
+            c: params.l,
+            h,
+            cs_hash: Hash64(into_array_unchecked(Blake2b512::digest(bytes))),
+            contributions: Vec::new(),
+        })
+    }
+
+    /// Checks that the parameters derive from the `initial` parameters of their circuit through
+    /// their contributions, returning the hashes of the contributions.
+    #[inline]
+    pub fn verify(&self, initial: &Self) -> Result<Vec<Hash64>> {
+        ensure!(
+            (self.wires, self.public, self.domain_size)
+                == (initial.wires, initial.public, initial.domain_size),
+            "The sizes of the circuit differ from the sizes of the R1CS file"
+        );
+        let (vk, initial_vk) = (&self.vk, &initial.vk);
+        ensure!(
+            vk.alpha_g1 == initial_vk.alpha_g1
+                && vk.beta_g1 == initial_vk.beta_g1
+                && vk.beta_g2 == initial_vk.beta_g2
+                && vk.gamma_g2 == initial_vk.gamma_g2,
+            "Alpha, beta or gamma differ from the accumulator"
+        );
+        ensure!(
+            vk.ic == initial_vk.ic,
+            "The input query differs from the circuit on the accumulator"
+        );
+        let sorted = |coefficients: &[Coefficient]| {
+            let mut coefficients = coefficients
+                .iter()
+                .map(|coefficient| {
+                    (
+                        (coefficient.matrix, coefficient.constraint, coefficient.wire),
+                        coefficient.value,
+                    )
+                })
+                .collect::<BTreeMap<_, _>>();
+            coefficients.retain(|_, value| !value.is_zero());
+            coefficients
+        };
+        ensure!(
+            sorted(&self.coefficients) == sorted(&initial.coefficients),
+            "The coefficients differ from the constraints of the R1CS file"
+        );
+        ensure!(
+            self.a == initial.a && self.b_g1 == initial.b_g1 && self.b_g2 == initial.b_g2,
+            "The A and B queries differ from the circuit on the accumulator"
+        );
+        ensure!(
+            self.cs_hash == initial.cs_hash,
+            "The hash of the initial parameters differs from the circuit on the accumulator"
+        );
+        let mut transcript = Blake2b512::new();
+        transcript.update(self.cs_hash.0);
+        let mut delta = initial.vk.delta_g1;
+        let mut hashes = Vec::with_capacity(self.contributions.len());
+        for (index, contribution) in self.contributions.iter().enumerate() {
+            let key = &contribution.key;
+            key.verify(&transcript, delta)
+                .map_err(|error| anyhow!("Contribution {}: {}", index + 1, error))?;
+            let mut bytes = Vec::new();
+            key.write(&mut bytes);
+            transcript.update(&bytes);
+            delta = key.delta_after;
+            hashes.push(key.hash());
+        }
+        ensure!(
+            delta == vk.delta_g1,
+            "Delta differs from the delta of the last contribution"
+        );
+        ensure!(
+            ptau::same_ratio(
+                (G1Affine::prime_subgroup_generator(), vk.delta_g1),
+                (G2Affine::prime_subgroup_generator(), vk.delta_g2)
+            ),
+            "Delta differs in G1 and G2"
+        );
+        for (name, old, new) in [("C", &initial.c, &self.c), ("H", &initial.h, &self.h)] {
+            ensure!(old.len() == new.len(), "The {} query changed size", name);
+            if !ptau::same_ratio(combine(old, new), (vk.delta_g2, initial_vk.delta_g2)) {
+                bail!("The {} query is not divided by delta", name);
+            }
+        }
+        Ok(hashes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{export::push_g1, pok::hash_to_g2, r1cs::Constraint};
+    use ark_bn254::Bn254;
+    use ark_ff::UniformRand;
+
+    /// Adds a contribution with the secret `delta` to `zkey`, as snarkjs does.
+    fn contribute(zkey: &Zkey, delta: Fr, name: &str) -> Zkey {
+        let mut rng = rand::thread_rng();
+        let s = G1Affine::prime_subgroup_generator()
+            .mul(Fr::rand(&mut rng))
+            .into_affine();
+        let s_delta = s.mul(delta).into_affine();
+        let mut bytes = zkey.cs_hash.0.to_vec();
+        for contribution in &zkey.contributions {
+            contribution.key.write(&mut bytes);
+        }
+        push_g1(&mut bytes, &[s, s_delta]);
+        let transcript = Hash64(into_array_unchecked(Blake2b512::digest(bytes)));
+        let mut next = zkey.clone();
+        let inverse = delta.inverse().unwrap();
+        for query in [&mut next.c, &mut next.h] {
+            for point in query.iter_mut() {
+                *point = point.mul(inverse).into_affine();
+            }
+        }
+        next.vk.delta_g1 = next.vk.delta_g1.mul(delta).into_affine();
+        next.vk.delta_g2 = next.vk.delta_g2.mul(delta).into_affine();
+        next.contributions.push(Contribution {
+            key: phase2::Contribution {
+                delta_after: next.vk.delta_g1,
+                s,
+                s_delta,
+                r_delta: hash_to_g2::<Bn254>(&transcript.0).mul(delta).into_affine(),
+                transcript,
+            },
+            beacon: false,
+            name: Some(name.into()),
+        });
+        next
+    }
+
+    /// Checks the initial parameters of a circuit against the secrets of the accumulator, that a
+    /// file is read back and that valid contributions are accepted while tampered files are
+    /// rejected.
+    #[test]
+    fn verify_zkey() {
+        let mut rng = rand::thread_rng();
+        let [tau, alpha, beta] = [0; 3].map(|_| Fr::rand(&mut rng));
+        let g1 = |section: Section, index: usize| {
+            let scalar = tau.pow([index as u64])
+                * match section {
+                    Section::AlphaG1 => alpha,
+                    Section::BetaG1 => beta,
+                    _ => Fr::one(),
+                };
+            Ok(G1Affine::prime_subgroup_generator()
+                .mul(scalar)
+                .into_affine())
+        };
+        let g2 = |section: Section, index: usize| {
+            let scalar = tau.pow([index as u64])
+                * if section == Section::BetaG2 {
+                    beta
+                } else {
+                    Fr::one()
+                };
+            Ok(G2Affine::prime_subgroup_generator()
+                .mul(scalar)
+                .into_affine())
+        };
+        let r1cs = R1cs {
+            wires: 3,
+            public: 1,
+            constraints: vec![Constraint {
+                a: vec![(2, Fr::one())],
+                b: vec![(2, Fr::one())],
+                c: vec![(1, Fr::one())],
+            }],
+        };
+        let initial = Zkey::initial(g1, g2, &r1cs).unwrap();
+        assert_eq!(initial.domain_size, 4);
+        assert_eq!(initial.coefficients.len(), 4);
+        let omega = Domain::Arkworks.root_of_unity(8).unwrap();
+        let first = (tau.pow([8]) - Fr::one()) * omega / (Fr::from(8u64) * (tau - omega));
+        assert_eq!(
+            initial.h[0],
+            G1Affine::prime_subgroup_generator()
+                .mul(first)
+                .into_affine()
+        );
+        let once = contribute(&initial, Fr::rand(&mut rng), "first");
+        let twice = contribute(&once, Fr::rand(&mut rng), "second");
+        let mut bytes = Vec::new();
+        twice.write(&mut bytes);
+        assert_eq!(Zkey::read(&bytes).unwrap(), twice);
+        assert_eq!(
+            twice.verify(&initial).unwrap(),
+            [
+                once.contributions[0].key.hash(),
+                twice.contributions[1].key.hash()
+            ]
+        );
+        let mut tampered = twice.clone();
+        tampered.h[1] = tampered.h[0];
+        assert!(tampered.verify(&initial).is_err());
+        let mut tampered = twice.clone();
+        tampered.coefficients[0].value += Fr::one();
+        assert!(tampered.verify(&initial).is_err());
+        let mut tampered = twice.clone();
+        tampered.contributions.swap(0, 1);
+        assert!(tampered.verify(&initial).is_err());
+        let other = R1cs {
+            constraints: vec![Constraint {
+                a: vec![(2, Fr::one())],
+                b: vec![(2, Fr::one())],
+                c: vec![(1, Fr::from(2u64))],
+            }],
+            ..r1cs
+        };
+        assert!(twice
+            .verify(&Zkey::initial(g1, g2, &other).unwrap())
+            .is_err());
+    }
+}